@@ -0,0 +1,233 @@
+use std::path::Path;
+
+use clap::{Subcommand, ValueEnum};
+use osu_db_parser::{backups::Backups, links, prelude::*};
+
+pub use cache::ParseCache;
+
+use crate::error::Error;
+
+mod bulk_edit;
+mod bundle;
+mod cache;
+mod collections;
+mod diff;
+mod dump;
+mod export_links;
+mod random;
+mod redact;
+mod repair;
+mod resolve;
+mod restore;
+mod scores;
+mod similar;
+mod snapshot;
+mod sqlite;
+mod stats;
+mod verify;
+#[cfg(feature = "watch")]
+mod watch;
+
+/// Represents the available commands.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Changes the per-beatmap settings (e.g. local offset or video) of every beatmap matching a query
+    BulkEdit(bulk_edit::BulkEditArgs),
+
+    /// Writes statistics, a table, download links, collections and a report into one zip archive
+    Bundle(bundle::BundleArgs),
+
+    /// Manages the cache of parsed databases used by `--cache`
+    Cache(cache::CacheArgs),
+
+    /// Makes collections from groups of beatmaps (e.g. the beatmaps sharing a song source), and imports or exports
+    /// them as text
+    Collections(collections::CollectionsArgs),
+
+    /// Compares two versions of osu!.db, e.g. to find the beatmaps whose star rating changed the most
+    Diff(diff::DiffArgs),
+
+    /// Prints the beatmaps in osu!.db as JSON, or formatted using a template
+    Dump(dump::DumpArgs),
+
+    /// Lists download links for each beatmapset in a collection or matching a query
+    ExportLinks(export_links::ExportLinksArgs),
+
+    /// Picks random beatmaps matching a query
+    Random(random::RandomArgs),
+
+    /// Writes a copy of osu!.db with the player's name and beatmaps' text replaced by pseudonyms, e.g. for a bug report
+    Redact(redact::RedactArgs),
+
+    /// Recovers the beatmaps from a damaged osu!.db, writing them to a new file
+    Repair(repair::RepairArgs),
+
+    /// Looks up a list of MD5 hashes, beatmap IDs or links (e.g. a tournament mappool) in osu!.db
+    Resolve(resolve::ResolveArgs),
+
+    /// Lists the backups made before a database was written over, or puts one of them back
+    Restore(restore::RestoreArgs),
+
+    /// Lists local scores from scores.db
+    Scores(scores::ScoresArgs),
+
+    /// Suggests beatmaps similar to a beatmap, e.g. with the same mapper or a similar star rating and BPM
+    Similar(similar::SimilarArgs),
+
+    /// Saves or loads a snapshot of osu!.db, which is faster to load than the original file
+    Snapshot(snapshot::SnapshotArgs),
+
+    /// Exports the database files to an SQLite database
+    Sqlite(sqlite::SqliteArgs),
+
+    /// Summarises osu!.db, with the number of beatmaps played and scores set each month
+    Stats(stats::StatsArgs),
+
+    /// Compares the metadata in osu!.db with the beatmaps' .osu files, e.g. after editing them by hand
+    Verify(verify::VerifyArgs),
+
+    /// Watches a database for changes made by osu!
+    #[cfg(feature = "watch")]
+    Watch(watch::WatchArgs),
+}
+
+/// Represents the gameplay modes that can be selected on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ModeArg {
+    #[value(alias = "std", alias = "osu")]
+    Standard,
+    Taiko,
+    #[value(alias = "ctb", alias = "fruits")]
+    Catch,
+    Mania,
+}
+
+/// Represents the output formats for commands which produce structured data.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// A table with aligned columns
+    Table,
+
+    /// A JSON array
+    Json,
+}
+
+impl From<ModeArg> for GameplayMode {
+    fn from(value: ModeArg) -> Self {
+        match value {
+            ModeArg::Standard => GameplayMode::Standard,
+            ModeArg::Taiko => GameplayMode::Taiko,
+            ModeArg::Catch => GameplayMode::Catch,
+            ModeArg::Mania => GameplayMode::Mania,
+        }
+    }
+}
+
+/// Runs a command, loading `osu!.db` through the parse cache. Commands which write over a database keep `keep_backups`
+/// backups of it, or don't back it up if this is 0.
+pub fn run(command: Command, cache: &ParseCache, keep_backups: usize) -> Result<(), Error> {
+    match command {
+        Command::BulkEdit(args) => bulk_edit::run(args, cache, keep_backups),
+        Command::Bundle(args) => bundle::run(args, cache),
+        Command::Cache(args) => cache::run(args, cache),
+        Command::Collections(args) => collections::run(args, cache, keep_backups),
+        Command::Diff(args) => diff::run(args, cache),
+        Command::Dump(args) => dump::run(args, cache),
+        Command::ExportLinks(args) => export_links::run(args, cache),
+        Command::Random(args) => random::run(args, cache),
+        Command::Redact(args) => redact::run(args, cache, keep_backups),
+        Command::Repair(args) => repair::run(args, keep_backups),
+        Command::Resolve(args) => resolve::run(args, cache),
+        Command::Restore(args) => restore::run(args, keep_backups.max(1)),
+        Command::Scores(args) => scores::run(args, cache),
+        Command::Similar(args) => similar::run(args, cache),
+        Command::Snapshot(args) => snapshot::run(args, cache),
+        Command::Sqlite(args) => sqlite::run(args, cache),
+        Command::Stats(args) => stats::run(args, cache),
+        Command::Verify(args) => verify::run(args, cache, keep_backups),
+        #[cfg(feature = "watch")]
+        Command::Watch(args) => watch::run(args),
+    }
+}
+
+/// Backs up a file before a command writes over it, keeping the newest `keep` backups of it. Nothing is backed up if
+/// `keep` is 0 or the file doesn't exist yet.
+fn back_up(path: &Path, operation: &str, changed: usize, keep: usize) -> Result<(), Error> {
+    if keep == 0 {
+        return Ok(());
+    }
+
+    if let Some(backup) = Backups::beside(path)
+        .keep(keep)
+        .back_up(path, operation, changed)?
+    {
+        println!(
+            "Backed up {} as {} (restore it with `osu-db restore {} {}`)",
+            path.display(),
+            backup.path.display(),
+            path.display(),
+            backup.id
+        );
+    }
+
+    Ok(())
+}
+
+/// Formats a beatmap's link, or a placeholder if the beatmap hasn't been submitted.
+fn beatmap_link(beatmap: &BeatmapEntry) -> String {
+    links::beatmap_url(beatmap).unwrap_or_else(|| "(unsubmitted)".to_string())
+}
+
+/// Formats rows of text as a table, padding each column to the width of its longest cell.
+fn format_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = headers
+        .iter()
+        .map(|h| h.chars().count())
+        .collect::<Vec<_>>();
+
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let headers = headers.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+    let mut table = String::new();
+
+    for row in std::iter::once(&headers).chain(rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_columns_are_aligned() {
+        let table = format_table(
+            &["Name", "Score"],
+            &[
+                vec!["Beatmap with a long name".to_string(), "1".to_string()],
+                vec!["Short".to_string(), "1000000".to_string()],
+            ],
+        );
+
+        assert_eq!(
+            table,
+            "Name                      Score\n\
+             Beatmap with a long name  1\n\
+             Short                     1000000\n"
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! The `bulk-edit` command, which changes the per-beatmap settings (e.g. the local offset or whether the video is shown)
+//! of every beatmap matching a query.
+
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Args};
+use osu_db_parser::prelude::*;
+
+use super::{back_up, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("edits").required(true).multiple(true)))]
+pub struct BulkEditArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// Only change beatmaps matching this query (e.g. "mode=mania status=ranked")
+    #[arg(short, long, default_value = "")]
+    query: String,
+
+    /// Path to write the edited `osu!.db` file to
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Overwrite the output file if it already exists
+    #[arg(long)]
+    force: bool,
+
+    /// Add this many milliseconds to the local offset (e.g. `+12` or `-5`)
+    #[arg(long, allow_hyphen_values = true, group = "edits")]
+    offset: Option<i16>,
+
+    /// Set the local offset to this many milliseconds
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        group = "edits",
+        conflicts_with = "offset"
+    )]
+    set_offset: Option<i16>,
+
+    /// Ignore the beatmap's hitsounds (use `=false` to stop ignoring them)
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        group = "edits"
+    )]
+    ignore_hitsounds: Option<bool>,
+
+    /// Ignore the beatmap's skin (use `=false` to stop ignoring it)
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        group = "edits"
+    )]
+    ignore_skin: Option<bool>,
+
+    /// Disable the storyboard (use `=false` to enable it again)
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        group = "edits"
+    )]
+    disable_storyboard: Option<bool>,
+
+    /// Disable the video (use `=false` to enable it again)
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        group = "edits"
+    )]
+    disable_video: Option<bool>,
+
+    /// Turn on the visual settings override (use `=false` to turn it off)
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        group = "edits"
+    )]
+    visual_override: Option<bool>,
+
+    /// Set the osu!mania scroll speed
+    #[arg(long, group = "edits", value_parser = clap::value_parser!(u8).range(1..=40))]
+    mania_scroll_speed: Option<u8>,
+}
+
+impl BulkEditArgs {
+    /// Gets the changes to make to each beatmap.
+    fn edit(&self) -> BulkEdit {
+        let local_offset = match (self.offset, self.set_offset) {
+            (_, Some(offset)) => Some(OffsetEdit::Set(offset)),
+            (Some(delta), None) => Some(OffsetEdit::Shift(delta)),
+            (None, None) => None,
+        };
+
+        BulkEdit {
+            local_offset,
+            ignore_beatmap_hitsounds: self.ignore_hitsounds,
+            ignore_beatmap_skin: self.ignore_skin,
+            disable_storyboard: self.disable_storyboard,
+            disable_video: self.disable_video,
+            visual_override: self.visual_override,
+            mania_scroll_speed: self.mania_scroll_speed,
+        }
+    }
+}
+
+pub fn run(args: BulkEditArgs, cache: &ParseCache, keep_backups: usize) -> Result<(), Error> {
+    if args.out.exists() && !args.force {
+        return Err(Error::OutputExists(args.out));
+    }
+
+    let filter = args.query.parse::<BeatmapFilter>()?;
+    let mut listing = cache.load_listing(&args.osu_db)?;
+
+    let matched = listing
+        .beatmaps
+        .iter()
+        .filter(|b| filter.matches(b))
+        .count();
+    let changed = listing.bulk_edit(&filter, args.edit());
+
+    println!(
+        "Changed {} of {} matching beatmaps ({} already had these settings)",
+        changed,
+        matched,
+        matched - changed
+    );
+
+    back_up(&args.out, "bulk-edit", changed, keep_backups)?;
+    listing.to_file(&args.out)?;
+    println!("Saved the edited database to {}", args.out.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: BulkEditArgs,
+    }
+
+    fn parse(args: &[&str]) -> Result<BulkEdit, clap::Error> {
+        let args = ["bulk-edit", "osu!.db", "--out", "new.db"]
+            .iter()
+            .chain(args);
+
+        Cli::try_parse_from(args).map(|cli| cli.args.edit())
+    }
+
+    #[test]
+    fn edits_are_parsed() {
+        assert_eq!(
+            parse(&["--disable-video", "--offset", "+12"]).unwrap(),
+            BulkEdit {
+                disable_video: Some(true),
+                local_offset: Some(OffsetEdit::Shift(12)),
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            parse(&["--ignore-skin=false", "--set-offset", "-5"]).unwrap(),
+            BulkEdit {
+                ignore_beatmap_skin: Some(false),
+                local_offset: Some(OffsetEdit::Set(-5)),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn mania_scroll_speed_is_parsed() {
+        assert_eq!(
+            parse(&["--mania-scroll-speed", "24"]).unwrap(),
+            BulkEdit {
+                mania_scroll_speed: Some(24),
+                ..Default::default()
+            }
+        );
+
+        assert!(parse(&["--mania-scroll-speed", "0"]).is_err());
+    }
+
+    #[test]
+    fn invalid_edits_are_rejected() {
+        // Nothing to change
+        assert!(parse(&[]).is_err());
+
+        // The offset can't be shifted and set at the same time
+        assert!(parse(&["--offset", "5", "--set-offset", "5"]).is_err());
+    }
+}
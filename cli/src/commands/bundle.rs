@@ -0,0 +1,258 @@
+//! The `bundle` command, which writes several exports of a library (statistics, a table, download links, collections
+//! and a report) into one zip archive.
+
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use clap::{Args, ValueEnum};
+use osu_db_parser::{
+    export::{self, BundleEntry},
+    links::Mirror,
+    prelude::*,
+    report::ReportOptions,
+};
+use time::OffsetDateTime;
+
+use super::{export_links::MirrorArg, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct BundleArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// Path to create the zip archive at
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Exports to include in the archive
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [Part::Stats, Part::Table, Part::Links, Part::Collections, Part::Report]
+    )]
+    include: Vec<Part>,
+
+    /// Only include beatmaps matching this query in the table and download links
+    #[arg(short, long)]
+    query: Option<String>,
+
+    /// Path to the `collection.db` file, to include each collection as text
+    #[arg(long)]
+    collections: Option<PathBuf>,
+
+    /// Site to download beatmapsets from
+    #[arg(long, value_enum, default_value_t = MirrorArg::Official)]
+    mirror: MirrorArg,
+
+    /// Title shown at the top of the report
+    #[arg(long, default_value = "My osu! Library")]
+    title: String,
+
+    /// Overwrite the output file if it already exists
+    #[arg(long)]
+    force: bool,
+}
+
+/// Represents the exports that can be included in a bundle.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Part {
+    /// Summary statistics as JSON
+    Stats,
+
+    /// A CSV table of the beatmaps matching the query, with every column
+    Table,
+
+    /// Download links for the beatmapsets matching the query
+    Links,
+
+    /// Each collection as text, if `--collections` is given
+    Collections,
+
+    /// An HTML report summarising the library
+    Report,
+}
+
+pub fn run(args: BundleArgs, cache: &ParseCache) -> Result<(), Error> {
+    let listing = cache.load_listing(&args.osu_db)?;
+    let collections = args
+        .collections
+        .as_ref()
+        .map(CollectionListing::from_file)
+        .transpose()?;
+
+    if args.out.exists() && !args.force {
+        return Err(Error::OutputExists(args.out));
+    }
+
+    let filter = args
+        .query
+        .as_deref()
+        .unwrap_or_default()
+        .parse::<BeatmapFilter>()?;
+    let beatmaps = listing
+        .beatmaps
+        .iter()
+        .filter(|beatmap| filter.matches(beatmap))
+        .collect::<Vec<_>>();
+
+    let entries = bundle_entries(
+        &args,
+        &listing,
+        &beatmaps,
+        collections.as_ref(),
+        OffsetDateTime::now_utc(),
+    );
+
+    let result = File::create(&args.out)
+        .map_err(osu_db_parser::error::Error::from)
+        .and_then(|file| {
+            export::bundle(
+                BufWriter::new(file),
+                &listing,
+                &entries,
+                MetadataLanguage::default(),
+                |_, _| true,
+            )
+        });
+
+    // Don't leave behind a partially written archive
+    if let Err(e) = result {
+        std::fs::remove_file(&args.out).ok();
+        return Err(e.into());
+    }
+
+    println!(
+        "Wrote {} files to {} ({} of {} beatmaps matched)",
+        entries.len(),
+        args.out.display(),
+        beatmaps.len(),
+        listing.beatmaps.len()
+    );
+
+    Ok(())
+}
+
+/// Chooses the exports to write, in the order they're listed in the archive.
+fn bundle_entries<'a>(
+    args: &BundleArgs,
+    listing: &'a BeatmapListing,
+    beatmaps: &[&'a BeatmapEntry],
+    collections: Option<&'a CollectionListing>,
+    generated: OffsetDateTime,
+) -> Vec<BundleEntry<'a>> {
+    let mut entries = Vec::new();
+
+    for part in [
+        Part::Stats,
+        Part::Table,
+        Part::Links,
+        Part::Collections,
+        Part::Report,
+    ] {
+        if !args.include.contains(&part) {
+            continue;
+        }
+
+        match part {
+            Part::Stats => entries.push(BundleEntry::Stats),
+            Part::Table => entries.push(BundleEntry::Table {
+                columns: Column::ALL.to_vec(),
+                beatmaps: beatmaps.to_vec(),
+            }),
+            Part::Links => entries.push(BundleEntry::Links {
+                mirror: Mirror::from(args.mirror),
+                beatmaps: beatmaps.to_vec(),
+            }),
+            Part::Collections => entries.extend(
+                collections
+                    .iter()
+                    .flat_map(|collections| &collections.collections)
+                    .map(BundleEntry::Collection),
+            ),
+            Part::Report => entries.push(BundleEntry::Report(ReportOptions {
+                title: args.title.clone(),
+                player_name: listing.player_name_str().map(str::to_string),
+                top_count: 10,
+                table: None,
+                language: MetadataLanguage::default(),
+                generated,
+            })),
+        }
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: BundleArgs,
+    }
+
+    fn args(extra: &[&str]) -> BundleArgs {
+        let base = ["bundle", "osu!.db", "--out", "bundle.zip"];
+        Cli::parse_from(base.iter().chain(extra)).args
+    }
+
+    #[test]
+    fn every_part_is_included_by_default() {
+        let listing = BeatmapListing {
+            version: 20250108,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: OffsetDateTime::UNIX_EPOCH,
+            player_name: None,
+            beatmaps: vec![BeatmapEntry::default()],
+            user_permissions: Default::default(),
+        };
+        let collections = CollectionListing {
+            version: 20150203,
+            collections: vec![
+                Collection::from_beatmaps("A".to_string(), []),
+                Collection::from_beatmaps("B".to_string(), []),
+            ],
+        };
+        let beatmaps = listing.beatmaps.iter().collect::<Vec<_>>();
+        let generated = OffsetDateTime::UNIX_EPOCH;
+
+        let kinds = |args: &BundleArgs, collections: Option<&CollectionListing>| {
+            bundle_entries(args, &listing, &beatmaps, collections, generated)
+                .iter()
+                .map(|entry| match entry {
+                    BundleEntry::Stats => "stats",
+                    BundleEntry::Table { .. } => "table",
+                    BundleEntry::Links { .. } => "links",
+                    BundleEntry::Collection(_) => "collection",
+                    BundleEntry::Report(_) => "report",
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            kinds(&args(&[]), Some(&collections)),
+            [
+                "stats",
+                "table",
+                "links",
+                "collection",
+                "collection",
+                "report"
+            ]
+        );
+        assert_eq!(
+            kinds(&args(&[]), None),
+            ["stats", "table", "links", "report"]
+        );
+        assert_eq!(
+            kinds(&args(&["--include", "report,links"]), Some(&collections)),
+            ["links", "report"]
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! The parse cache, which saves each parsed `osu!.db` as a snapshot so that running several commands on the same file
+//! only parses it once, and the `cache` command which manages it.
+//!
+//! Caching is opt-in, using `--cache` or the `OSU_DB_CACHE` environment variable. Entries are keyed by the database's
+//! path, size, modification time and a hash of its first 1MB, so a database that osu! has since written to is parsed
+//! again. An entry that can't be loaded (e.g. it's corrupt, or was saved by an older version) is ignored and replaced.
+
+use std::{
+    cell::Cell,
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use clap::{Args, Subcommand};
+use osu_db_parser::prelude::*;
+
+use super::snapshot;
+use crate::error::Error;
+
+/// Number of bytes at the start of a database which are hashed for its cache key.
+const HASHED_BYTES: u64 = 1024 * 1024;
+
+/// File extension of cache entries, so that clearing the cache doesn't remove anything else.
+const ENTRY_EXTENSION: &str = "msgpack";
+
+#[derive(Args, Debug)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    command: CacheCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// Removes every cached database
+    Clear,
+}
+
+/// Loads beatmap listings, using cached snapshots where possible.
+#[derive(Debug)]
+pub struct ParseCache {
+    /// Directory that entries are saved in, or `None` if the platform doesn't have a cache directory.
+    dir: Option<PathBuf>,
+
+    /// Whether entries are used; if not, every database is parsed.
+    enabled: bool,
+
+    /// Number of databases that have been fully parsed, rather than loaded from the cache.
+    parses: Cell<usize>,
+}
+
+impl ParseCache {
+    /// Creates a cache that saves entries in `dir`, which is only used if `enabled` is true.
+    pub fn new(dir: Option<PathBuf>, enabled: bool) -> Self {
+        Self {
+            dir,
+            enabled,
+            parses: Cell::new(0),
+        }
+    }
+
+    /// Gets the platform's cache directory for the command line tools, e.g. `~/.cache/osu-db` on Linux.
+    pub fn default_dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "osu-db").map(|dirs| dirs.cache_dir().to_path_buf())
+    }
+
+    /// Loads a beatmap listing, from the cache if it has an up to date entry for the file, otherwise by parsing it and
+    /// saving a new entry.
+    ///
+    /// Problems with the cache itself (e.g. a corrupt entry or a read-only cache directory) are ignored, since the
+    /// database can always be parsed instead.
+    ///
+    /// A warning is printed if the database is from a newer version of osu! than is known, since its beatmaps may be
+    /// incomplete or wrong.
+    pub fn load_listing(&self, path: &Path) -> Result<BeatmapListing, Error> {
+        let listing = self.load_cached(path)?;
+
+        if listing.format_confidence() == FormatConfidence::BestEffort {
+            eprintln!(
+                "warning: {} is from a newer version of osu! ({}) than is known, so some beatmaps may be missing or \
+                 wrong, and it can't be written",
+                path.display(),
+                listing.version
+            );
+        }
+
+        Ok(listing)
+    }
+
+    /// Loads a beatmap listing like [`ParseCache::load_listing`], without any warnings.
+    fn load_cached(&self, path: &Path) -> Result<BeatmapListing, Error> {
+        let entry = match &self.dir {
+            Some(dir) if self.enabled => entry_key(path).ok().map(|key| (dir, key)),
+            _ => None,
+        };
+
+        let Some((dir, (path_key, content_key))) = entry else {
+            return self.parse(path);
+        };
+
+        let entry_path = dir.join(entry_name(path_key, content_key));
+        if let Ok(listing) = snapshot::load(&entry_path) {
+            return Ok(listing);
+        }
+
+        let listing = self.parse(path)?;
+        let _ = save_entry(dir, &entry_path, path_key, &listing);
+
+        Ok(listing)
+    }
+
+    /// Removes every entry from the cache, returning how many were removed.
+    pub fn clear(&self) -> Result<usize, Error> {
+        let Some(dir) = &self.dir else {
+            return Ok(0);
+        };
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == ENTRY_EXTENSION) {
+                fs::remove_file(path)?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Gets the number of databases that have been fully parsed.
+    #[cfg(test)]
+    fn parses(&self) -> usize {
+        self.parses.get()
+    }
+
+    fn parse(&self, path: &Path) -> Result<BeatmapListing, Error> {
+        self.parses.set(self.parses.get() + 1);
+        Ok(BeatmapListing::from_file(path)?)
+    }
+}
+
+pub fn run(args: CacheArgs, cache: &ParseCache) -> Result<(), Error> {
+    match args.command {
+        CacheCommand::Clear => {
+            let removed = cache.clear()?;
+            println!("Removed {} cached databases", removed);
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes the cache key of a database, as a hash of its path and a hash of its contents (using its size,
+/// modification time and first 1MB).
+///
+/// The hashes aren't guaranteed to be the same between builds, which only means that an entry is parsed again.
+fn entry_key(path: &Path) -> io::Result<(u64, u64)> {
+    let path = path.canonicalize()?;
+    let metadata = fs::metadata(&path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let mut start = Vec::new();
+    File::open(&path)?
+        .take(HASHED_BYTES)
+        .read_to_end(&mut start)?;
+
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    let path_key = hasher.finish();
+
+    let mut hasher = DefaultHasher::new();
+    metadata.len().hash(&mut hasher);
+    modified.hash(&mut hasher);
+    start.hash(&mut hasher);
+    let content_key = hasher.finish();
+
+    Ok((path_key, content_key))
+}
+
+/// Gets the file name of an entry. This starts with the path's hash, so that older entries for the same database can be
+/// found and removed.
+fn entry_name(path_key: u64, content_key: u64) -> String {
+    format!("{:016x}-{:016x}.{}", path_key, content_key, ENTRY_EXTENSION)
+}
+
+/// Saves a new entry, replacing any older entries for the same database.
+///
+/// The entry is written to a temporary file first, so that an interrupted write doesn't leave a partial entry behind.
+fn save_entry(
+    dir: &Path,
+    entry_path: &Path,
+    path_key: u64,
+    listing: &BeatmapListing,
+) -> Result<(), Error> {
+    fs::create_dir_all(dir)?;
+
+    let prefix = format!("{:016x}-", path_key);
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_older_entry = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(ENTRY_EXTENSION));
+
+        if is_older_entry {
+            fs::remove_file(path)?;
+        }
+    }
+
+    let temp_path = entry_path.with_extension(format!("{}.tmp", std::process::id()));
+    snapshot::save(listing, &temp_path)?;
+    fs::rename(&temp_path, entry_path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty directory in the temp dir for a test, with a database in it.
+    fn setup(name: &str) -> (PathBuf, PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("osu-db-cache-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+
+        let osu_db = dir.join("osu!.db");
+        write_listing(&osu_db, "Player");
+
+        (dir, osu_db)
+    }
+
+    fn write_listing(path: &Path, player_name: &str) {
+        let listing = BeatmapListing {
+            version: 20250108,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: osu_db_parser::common::WINDOWS_EPOCH,
+            player_name: Some(player_name.to_string()),
+            beatmaps: vec![BeatmapEntry {
+                md5: Some("a".to_string()),
+                ..Default::default()
+            }],
+            user_permissions: Default::default(),
+        };
+
+        listing.to_file(path).unwrap();
+    }
+
+    #[test]
+    fn database_is_parsed_once_across_commands() {
+        let (dir, osu_db) = setup("once");
+        let cache_dir = dir.join("cache");
+
+        // e.g. `stats`, `dump` and `export-links` run one after another, each with their own cache
+        let listings = (0..3)
+            .map(|_| {
+                let cache = ParseCache::new(Some(cache_dir.clone()), true);
+                let listing = cache.load_listing(&osu_db).unwrap();
+                (cache.parses(), listing)
+            })
+            .collect::<Vec<_>>();
+
+        fs::remove_dir_all(&dir).ok();
+
+        let parses = listings.iter().map(|(parses, _)| parses).sum::<usize>();
+        assert_eq!(parses, 1);
+        assert!(listings
+            .iter()
+            .all(|(_, listing)| *listing == listings[0].1));
+    }
+
+    #[test]
+    fn disabled_cache_always_parses() {
+        let (dir, osu_db) = setup("disabled");
+
+        let cache = ParseCache::new(Some(dir.join("cache")), false);
+        cache.load_listing(&osu_db).unwrap();
+        cache.load_listing(&osu_db).unwrap();
+        let cache_exists = dir.join("cache").exists();
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(cache.parses(), 2);
+        assert!(!cache_exists);
+    }
+
+    #[test]
+    fn bad_entries_are_replaced() {
+        let (dir, osu_db) = setup("bad");
+        let cache = ParseCache::new(Some(dir.join("cache")), true);
+
+        cache.load_listing(&osu_db).unwrap();
+        let (path_key, content_key) = entry_key(&osu_db).unwrap();
+        let entry_path = dir.join("cache").join(entry_name(path_key, content_key));
+        fs::write(&entry_path, b"not a snapshot").unwrap();
+
+        let reparsed = cache.load_listing(&osu_db);
+        let cached = cache.load_listing(&osu_db);
+        let parses = cache.parses();
+
+        // Changing the database makes a new entry, which replaces the old one
+        write_listing(&osu_db, "Someone else");
+        let changed = cache.load_listing(&osu_db).unwrap();
+        let entries = cache.clear().unwrap();
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert!(reparsed.is_ok());
+        assert_eq!(cached.unwrap(), reparsed.unwrap());
+        assert_eq!(parses, 2);
+        assert_eq!(changed.player_name.as_deref(), Some("Someone else"));
+        assert_eq!(cache.parses(), 3);
+        assert_eq!(entries, 1);
+    }
+}
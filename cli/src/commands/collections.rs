@@ -0,0 +1,293 @@
+//! The `collections` command, which makes collections from groups of beatmaps such as beatmap packs, and imports or
+//! exports them as plain text lists of MD5 hashes.
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Subcommand};
+use osu_db_parser::{analysis, prelude::*};
+
+use super::{back_up, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct CollectionsArgs {
+    #[command(subcommand)]
+    command: CollectionsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum CollectionsCommand {
+    /// Makes a collection from the beatmaps sharing a song source, adding to the collection if it already exists
+    Make {
+        /// Path to the `osu!.db` file
+        #[arg(long)]
+        osu_db: PathBuf,
+
+        /// Path to the `collection.db` file, which is created if it doesn't exist
+        #[arg(long)]
+        collections: PathBuf,
+
+        /// Song source of the beatmaps to add, ignoring case (e.g. the anime the songs are from)
+        #[arg(long)]
+        from_source: String,
+
+        /// Name of the collection, instead of the song source
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Writes a collection as text, with one MD5 hash on each line followed by the beatmap's name as a comment
+    Export {
+        /// Path to the `collection.db` file
+        #[arg(long)]
+        collections: PathBuf,
+
+        /// Name of the collection to export
+        #[arg(long)]
+        name: String,
+
+        /// Path to the `osu!.db` file, used to name each beatmap in a comment
+        #[arg(long)]
+        osu_db: Option<PathBuf>,
+
+        /// File to write to, instead of standard output
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Adds a collection from a text file of MD5 hashes, adding to the collection if it already exists
+    Import {
+        /// Path to the `collection.db` file, which is created if it doesn't exist
+        #[arg(long)]
+        collections: PathBuf,
+
+        /// Text file with one MD5 hash on each line. Anything after a `#` is a comment.
+        file: PathBuf,
+
+        /// Name of the collection, instead of the file name
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Path to the `osu!.db` file, used to report beatmaps which aren't installed
+        #[arg(long)]
+        osu_db: Option<PathBuf>,
+    },
+}
+
+pub fn run(args: CollectionsArgs, cache: &ParseCache, keep_backups: usize) -> Result<(), Error> {
+    match args.command {
+        CollectionsCommand::Make {
+            osu_db,
+            collections,
+            from_source,
+            name,
+        } => {
+            let listing = cache.load_listing(&osu_db)?;
+
+            let group = analysis::group_by_source(&listing)
+                .into_iter()
+                .find(|group| group.name.eq_ignore_ascii_case(from_source.trim()))
+                .ok_or_else(|| Error::SourceNotFound(from_source.clone()))?;
+
+            let mut collection = group.to_collection();
+            if let Some(name) = name {
+                collection.name = Some(name);
+            }
+
+            add_collection(
+                &collections,
+                listing.version,
+                collection,
+                "collections make",
+                keep_backups,
+            )?;
+        }
+        CollectionsCommand::Export {
+            collections,
+            name,
+            osu_db,
+            out,
+        } => {
+            let collection_listing = CollectionListing::from_file(&collections)?;
+            let collection = collection_listing
+                .collections
+                .iter()
+                .find(|c| c.name.as_deref() == Some(name.as_str()))
+                .ok_or_else(|| Error::CollectionNotFound(name.clone()))?;
+
+            let listing = osu_db
+                .as_ref()
+                .map(|path| cache.load_listing(path))
+                .transpose()?;
+
+            match out {
+                Some(out) => {
+                    collection.write_text(listing.as_ref(), BufWriter::new(File::create(&out)?))?;
+                    eprintln!(
+                        "Wrote {} beatmaps to {}",
+                        collection.beatmap_md5s.len(),
+                        out.display()
+                    );
+                }
+                None => collection.write_text(listing.as_ref(), io::stdout().lock())?,
+            }
+        }
+        CollectionsCommand::Import {
+            collections,
+            file,
+            name,
+            osu_db,
+        } => {
+            let name = name.unwrap_or_else(|| {
+                file.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+            let collection = Collection::from_text(name, &std::fs::read_to_string(&file)?)?;
+
+            let listing = osu_db
+                .as_ref()
+                .map(|path| cache.load_listing(path))
+                .transpose()?;
+
+            if let Some(listing) = &listing {
+                let beatmaps = listing.beatmaps_by_md5();
+                let missing = collection
+                    .beatmap_md5s
+                    .iter()
+                    .flatten()
+                    .filter(|md5| !beatmaps.contains_key(md5.as_str()))
+                    .count();
+
+                if missing > 0 {
+                    println!("{} of the beatmaps aren't in osu!.db", missing);
+                }
+            }
+
+            let version = listing.map_or(DEFAULT_VERSION, |listing| listing.version);
+            add_collection(
+                &collections,
+                version,
+                collection,
+                "collections import",
+                keep_backups,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds a collection to a `collection.db` file, creating the file with `version` if it doesn't exist, and reports how
+/// many beatmaps were added. The file is backed up first, naming `operation` as the reason.
+fn add_collection(
+    path: &Path,
+    version: u32,
+    collection: Collection,
+    operation: &str,
+    keep_backups: usize,
+) -> Result<(), Error> {
+    let mut collection_listing = if path.exists() {
+        CollectionListing::from_file(path)?
+    } else {
+        CollectionListing {
+            version,
+            collections: Vec::new(),
+        }
+    };
+
+    let name = collection.name.clone().unwrap_or_default();
+    let total = collection.beatmap_md5s.len();
+    let added = merge_collection(&mut collection_listing, collection);
+
+    back_up(path, operation, added, keep_backups)?;
+    collection_listing.to_file(path)?;
+
+    println!(
+        "Added {} beatmaps to '{}' ({} were already in it)",
+        added,
+        name,
+        total - added
+    );
+
+    Ok(())
+}
+
+/// Version given to a new `collection.db` when there's no `osu!.db` to take the version from.
+const DEFAULT_VERSION: u32 = 20150203;
+
+/// Adds a collection to a listing, or its beatmaps to the collection with the same name if there is one, skipping any
+/// beatmaps that it already contains. Returns how many beatmaps were added.
+fn merge_collection(collection_listing: &mut CollectionListing, collection: Collection) -> usize {
+    let Some(existing) = collection_listing
+        .collections
+        .iter_mut()
+        .find(|existing| existing.name == collection.name)
+    else {
+        let added = collection.beatmap_md5s.len();
+        collection_listing.collections.push(collection);
+        return added;
+    };
+
+    let mut seen = existing
+        .beatmap_md5s
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<HashSet<_>>();
+
+    let before = existing.beatmap_md5s.len();
+    existing.beatmap_md5s.extend(
+        collection
+            .beatmap_md5s
+            .into_iter()
+            .filter(|md5| md5.as_ref().is_some_and(|md5| seen.insert(md5.clone()))),
+    );
+
+    existing.beatmap_md5s.len() - before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collection(name: &str, md5s: &[&str]) -> Collection {
+        Collection {
+            name: Some(name.to_string()),
+            beatmap_md5s: md5s.iter().map(|md5| Some(md5.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn collections_are_merged_by_name() {
+        let mut collection_listing = CollectionListing {
+            version: 20150203,
+            collections: vec![collection("Stellaria", &["a", "b"])],
+        };
+
+        assert_eq!(
+            merge_collection(
+                &mut collection_listing,
+                collection("Stellaria", &["b", "c", "c"])
+            ),
+            1
+        );
+        assert_eq!(
+            merge_collection(&mut collection_listing, collection("Other", &["a"])),
+            1
+        );
+
+        assert_eq!(
+            collection_listing.collections,
+            vec![
+                collection("Stellaria", &["a", "b", "c"]),
+                collection("Other", &["a"]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! The `diff` command, which compares two versions of `osu!.db`, e.g. from before and after osu! recalculated star
+//! ratings, or finds which of several `osu!.db` files each beatmap is in.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use osu_db_parser::{
+    analysis::{self, StarChange},
+    flagset::FlagSet,
+    prelude::*,
+};
+
+use super::{format_table, ModeArg, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Path to the older `osu!.db` file
+    old: PathBuf,
+
+    /// Path to the newer `osu!.db` file
+    new: PathBuf,
+
+    /// Paths to more `osu!.db` files, e.g. from other computers. Instead of listing changes, every beatmap that isn't in
+    /// all of the files is listed, with a `+` for each file it's in and a `-` for each file it isn't.
+    #[arg(conflicts_with = "star_changes")]
+    more: Vec<PathBuf>,
+
+    /// List the beatmaps whose star rating changed the most, instead of every change
+    #[arg(long)]
+    star_changes: bool,
+
+    /// Gameplay mode of the star ratings to compare
+    #[arg(long, value_enum, default_value_t = ModeArg::Standard, requires = "star_changes")]
+    mode: ModeArg,
+
+    /// Mods of the star ratings to compare, as acronyms (e.g. `HDDT`)
+    #[arg(long, default_value = "NM", value_parser = parse_mods, requires = "star_changes")]
+    mods: FlagSet<Mods>,
+
+    /// Maximum number of star rating changes to list
+    #[arg(short = 'n', long, default_value_t = 50, requires = "star_changes")]
+    count: usize,
+}
+
+pub fn run(args: DiffArgs, cache: &ParseCache) -> Result<(), Error> {
+    if !args.more.is_empty() {
+        let mut workspace = Workspace::new();
+
+        for path in [&args.old, &args.new].into_iter().chain(&args.more) {
+            workspace.add(path.display().to_string(), cache.load_listing(path)?);
+        }
+
+        print!("{}", presence_listing(&workspace));
+        print!("{}", presence_summary(&workspace));
+        return Ok(());
+    }
+
+    let old = cache.load_listing(&args.old)?;
+    let new = cache.load_listing(&args.new)?;
+
+    if args.star_changes {
+        let changes = analysis::star_rating_changes(&old, &new, args.mode.into(), args.mods);
+
+        println!(
+            "{} beatmaps have a different {} star rating",
+            changes.len(),
+            Mods::acronyms(args.mods)
+        );
+        print!(
+            "{}",
+            star_changes_table(&changes[..changes.len().min(args.count)])
+        );
+
+        return Ok(());
+    }
+
+    let diff = ListingDiff::new(&old, &new);
+
+    for beatmap in &diff.added {
+        println!("+ {}", beatmap.display_name(MetadataLanguage::Romanised));
+    }
+
+    for beatmap in &diff.removed {
+        println!("- {}", beatmap.display_name(MetadataLanguage::Romanised));
+    }
+
+    for changes in &diff.changed {
+        println!(
+            "~ {}",
+            changes.new.display_name(MetadataLanguage::Romanised)
+        );
+
+        for field in &changes.fields {
+            println!("    {}: {} -> {}", field.name, field.old, field.new);
+        }
+    }
+
+    println!(
+        "{} added, {} removed, {} changed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    );
+
+    Ok(())
+}
+
+/// Lists the beatmaps that aren't in every listing of a workspace, marking which listings each one is in.
+fn presence_listing(workspace: &Workspace) -> String {
+    let presence = workspace.presence();
+    let mut output = String::new();
+
+    for (i, (_, source)) in workspace.sources().enumerate() {
+        output += &format!("{}{}\n", "|".repeat(i), source.name);
+    }
+
+    for (_, beatmap) in workspace.union(&workspace.ids()) {
+        let sources = beatmap
+            .md5
+            .as_deref()
+            .and_then(|md5| presence.get(md5))
+            .map_or(&[][..], Vec::as_slice);
+
+        if sources.len() == workspace.len() {
+            continue;
+        }
+
+        let marks = workspace
+            .ids()
+            .into_iter()
+            .map(|id| if sources.contains(&id) { '+' } else { '-' })
+            .collect::<String>();
+
+        output += &format!(
+            "{} {}\n",
+            marks,
+            beatmap.display_name(MetadataLanguage::Romanised)
+        );
+    }
+
+    output
+}
+
+/// Summarises how many beatmaps each listing of a workspace has, how many are only in that listing, and how many of the
+/// other listings' beatmaps it's missing.
+fn presence_summary(workspace: &Workspace) -> String {
+    let ids = workspace.ids();
+    let total = workspace.union(&ids).len();
+
+    let rows = ids
+        .iter()
+        .map(|&id| {
+            let others = ids
+                .iter()
+                .copied()
+                .filter(|&other| other != id)
+                .collect::<Vec<_>>();
+            let count = workspace.union(&[id]).len();
+
+            vec![
+                workspace.name(id).to_string(),
+                count.to_string(),
+                workspace.difference(id, &others).len().to_string(),
+                (total - count).to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    format_table(&["File", "Beatmaps", "Only Here", "Missing"], &rows)
+}
+
+/// Parses a combination of mods from their acronyms, e.g. `HDDT`, where `NM` (or nothing) is NoMod.
+fn parse_mods(text: &str) -> Result<FlagSet<Mods>, String> {
+    let text = text.trim().to_uppercase();
+
+    if text.is_empty() || text == "NM" {
+        return Ok(Mods::none());
+    }
+
+    if !text.is_ascii() {
+        return Err(format!("Unknown mods '{}'", text));
+    }
+
+    let mut mods = Mods::none();
+
+    for acronym in text.as_bytes().chunks(2) {
+        let acronym = std::str::from_utf8(acronym).unwrap_or_default();
+        let m = FlagSet::<Mods>::full()
+            .into_iter()
+            .filter(|m| !m.acronym().is_empty())
+            .find(|m| m.acronym() == acronym)
+            .ok_or_else(|| format!("Unknown mod '{}'", acronym))?;
+
+        mods |= m;
+    }
+
+    Ok(mods)
+}
+
+/// Formats the star rating changes as a table.
+fn star_changes_table(changes: &[StarChange]) -> String {
+    let rows = changes
+        .iter()
+        .map(|change| {
+            vec![
+                format!("{:+.2}", change.delta),
+                format!("{:.2}", change.old_rating),
+                format!("{:.2}", change.new_rating),
+                change.new.display_name(MetadataLanguage::Romanised),
+                change.md5.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    format_table(&["Change", "Before", "After", "Beatmap", "MD5"], &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mods_are_parsed_from_acronyms() {
+        assert_eq!(parse_mods("NM"), Ok(Mods::none()));
+        assert_eq!(parse_mods(""), Ok(Mods::none()));
+        assert_eq!(parse_mods("hddt"), Ok(Mods::Hidden | Mods::DoubleTime));
+        assert!(parse_mods("HDX").is_err());
+        assert!(parse_mods("ZZ").is_err());
+    }
+
+    #[test]
+    fn presence_is_listed_for_each_file() {
+        let listing = |md5s: &[&str]| BeatmapListing {
+            version: 20250108,
+            folder_count: md5s.len() as u32,
+            account_unlocked: true,
+            account_unlock_date: time::OffsetDateTime::UNIX_EPOCH,
+            player_name: None,
+            beatmaps: md5s
+                .iter()
+                .map(|md5| BeatmapEntry {
+                    md5: Some(md5.to_string()),
+                    song_title: Some(md5.to_uppercase()),
+                    ..Default::default()
+                })
+                .collect(),
+            user_permissions: Default::default(),
+        };
+
+        let mut workspace = Workspace::new();
+        workspace.add("desktop", listing(&["a", "b", "c"]));
+        workspace.add("laptop", listing(&["b", "c", "d"]));
+        workspace.add("backup", listing(&["a", "b"]));
+
+        assert_eq!(
+            presence_listing(&workspace),
+            "desktop\n\
+             |laptop\n\
+             ||backup\n\
+             +-+  - A []\n\
+             ++-  - C []\n\
+             -+-  - D []\n"
+        );
+        assert_eq!(
+            presence_summary(&workspace),
+            "File     Beatmaps  Only Here  Missing\n\
+             desktop  3         0          1\n\
+             laptop   3         1          1\n\
+             backup   2         0          2\n"
+        );
+    }
+
+    #[test]
+    fn star_changes_are_tabulated() {
+        let beatmap = BeatmapEntry {
+            artist_name: Some("xi".to_string()),
+            song_title: Some("FREEDOM DiVE".to_string()),
+            difficulty: Some("FOUR DIMENSIONS".to_string()),
+            ..Default::default()
+        };
+        let change = StarChange {
+            md5: "abc",
+            old: &beatmap,
+            new: &beatmap,
+            old_rating: 7.0,
+            new_rating: 6.751,
+            delta: -0.249,
+        };
+
+        assert_eq!(
+            star_changes_table(&[change]),
+            "Change  Before  After  Beatmap                              MD5\n\
+             -0.25   7.00    6.75   xi - FREEDOM DiVE [FOUR DIMENSIONS]  abc\n"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! The `dump` command, which prints the beatmaps in `osu!.db` as JSON or using a template.
+
+use std::{
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use clap::Args;
+use osu_db_parser::{format, prelude::*};
+
+use super::ParseCache;
+use crate::{error::Error, template::Template};
+
+#[derive(Args, Debug)]
+pub struct DumpArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// Only include beatmaps matching this query
+    #[arg(short, long, default_value = "")]
+    query: String,
+
+    /// Print each beatmap using a template instead of JSON (e.g. "{artist} - {title} [{difficulty}] {stars_std:.2}*")
+    #[arg(short, long)]
+    template: Option<String>,
+
+    /// Also summarise the matching beatmaps, e.g. their mean star rating and total drain time
+    #[arg(long)]
+    summary: bool,
+}
+
+pub fn run(args: DumpArgs, cache: &ParseCache) -> Result<(), Error> {
+    // Check the arguments before spending time parsing the database
+    let filter = args.query.parse::<BeatmapFilter>()?;
+    let template = args.template.as_deref().map(Template::parse).transpose()?;
+
+    let listing = cache.load_listing(&args.osu_db)?;
+
+    // The summary is built while the beatmaps are printed, so that the listing is only filtered once
+    let mut aggregates = FilterAggregates::default();
+    let beatmaps = listing
+        .beatmaps
+        .iter()
+        .filter(|b| filter.matches(b))
+        .inspect(|b| aggregates.add(b, filter.stars));
+
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+
+    match template {
+        Some(template) => {
+            for beatmap in beatmaps {
+                writeln!(stdout, "{}", template.render(beatmap))?;
+            }
+        }
+        None => {
+            serde_json::to_writer_pretty(&mut stdout, &beatmaps.collect::<Vec<_>>())
+                .map_err(std::io::Error::from)?;
+            writeln!(stdout)?;
+        }
+    }
+
+    stdout.flush()?;
+
+    // Report to stderr so that the output can still be piped into a file or parsed as JSON
+    if args.summary {
+        eprint!("{}", summary(&aggregates));
+    }
+
+    Ok(())
+}
+
+/// Formats a summary of the matching beatmaps, with one statistic per line.
+fn summary(aggregates: &FilterAggregates) -> String {
+    let mut summary = format!("Beatmaps: {}\n", aggregates.count);
+
+    if let Some(stars) = aggregates.mean_stars() {
+        summary.push_str(&format!(
+            "Mean star rating: {:.2} (of {} rated)\n",
+            stars, aggregates.rated
+        ));
+    }
+
+    summary.push_str(&format!(
+        "Total drain time: {}\n",
+        format::duration_hmmss(time::Duration::seconds(aggregates.drain_time as i64))
+    ));
+
+    if let Some((min, max)) = aggregates.bpm_range {
+        summary.push_str(&format!("BPM: {:.0}-{:.0}\n", min, max));
+    }
+
+    for (status, count) in &aggregates.statuses {
+        summary.push_str(&format!("{}: {}\n", status, count));
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_list_each_statistic() {
+        let aggregates = FilterAggregates {
+            count: 3,
+            rated: 2,
+            star_total: 9.5,
+            drain_time: 3725,
+            bpm_range: Some((150.0, 180.4)),
+            statuses: vec![(RankedStatus::Ranked, 2), (RankedStatus::Loved, 1)],
+        };
+
+        assert_eq!(
+            summary(&aggregates),
+            "Beatmaps: 3\n\
+             Mean star rating: 4.75 (of 2 rated)\n\
+             Total drain time: 1:02:05\n\
+             BPM: 150-180\n\
+             Ranked: 2\n\
+             Loved: 1\n"
+        );
+        assert_eq!(
+            summary(&FilterAggregates::default()),
+            "Beatmaps: 0\nTotal drain time: 0:00:00\n"
+        );
+    }
+}
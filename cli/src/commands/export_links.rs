@@ -0,0 +1,283 @@
+//! The `export-links` command, which lists download links for beatmapsets in a collection or matching a query.
+
+use std::{collections::HashSet, path::PathBuf};
+
+use clap::{Args, ValueEnum};
+use osu_db_parser::{export::html_escape, links::Mirror, prelude::*};
+
+use super::ParseCache;
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct ExportLinksArgs {
+    /// Path to the `osu!.db` file
+    #[arg(long)]
+    osu_db: PathBuf,
+
+    /// Name of the collection to export links for
+    #[arg(long, requires = "collections", conflicts_with = "query")]
+    collection: Option<String>,
+
+    /// Path to the `collection.db` file
+    #[arg(long, requires = "collection")]
+    collections: Option<PathBuf>,
+
+    /// Only export links for beatmaps matching this query
+    #[arg(short, long)]
+    query: Option<String>,
+
+    /// Site to download beatmapsets from
+    #[arg(long, value_enum, default_value_t = MirrorArg::Official)]
+    mirror: MirrorArg,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = LinkFormat::Text)]
+    format: LinkFormat,
+}
+
+/// Represents the mirrors that can be selected on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum MirrorArg {
+    Official,
+    Beatconnect,
+    Nerinyan,
+    Catboy,
+    Sayobot,
+}
+
+/// Represents the available output formats.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LinkFormat {
+    /// One link per line
+    Text,
+
+    /// A web page with clickable links
+    Html,
+}
+
+/// Represents the beatmapsets found for a set of beatmaps.
+#[derive(Debug, Default)]
+struct BeatmapsetLinks<'a> {
+    /// One beatmap for each unique beatmapset with its ID, in the order they were first seen
+    beatmapsets: Vec<(BeatmapSetId, &'a BeatmapEntry)>,
+
+    /// Number of beatmaps skipped because they haven't been submitted
+    unsubmitted: usize,
+
+    /// Number of MD5 hashes which couldn't be found in `osu!.db`
+    unresolved: usize,
+}
+
+impl From<MirrorArg> for Mirror {
+    fn from(value: MirrorArg) -> Self {
+        match value {
+            MirrorArg::Official => Mirror::Official,
+            MirrorArg::Beatconnect => Mirror::Beatconnect,
+            MirrorArg::Nerinyan => Mirror::Nerinyan,
+            MirrorArg::Catboy => Mirror::Catboy,
+            MirrorArg::Sayobot => Mirror::Sayobot,
+        }
+    }
+}
+
+pub fn run(args: ExportLinksArgs, cache: &ParseCache) -> Result<(), Error> {
+    let listing = cache.load_listing(&args.osu_db)?;
+
+    let links = match (&args.collection, &args.collections) {
+        (Some(name), Some(path)) => {
+            let collection_listing = CollectionListing::from_file(path)?;
+            let collection = collection_listing
+                .collections
+                .iter()
+                .find(|c| c.name.as_deref() == Some(name.as_str()))
+                .ok_or_else(|| Error::CollectionNotFound(name.clone()))?;
+
+            from_collection(&listing, collection)
+        }
+        _ => {
+            let filter = args
+                .query
+                .as_deref()
+                .unwrap_or_default()
+                .parse::<BeatmapFilter>()?;
+
+            BeatmapsetLinks::from_beatmaps(listing.beatmaps.iter().filter(|b| filter.matches(b)))
+        }
+    };
+
+    let mirror = Mirror::from(args.mirror);
+
+    match args.format {
+        LinkFormat::Text => {
+            for (beatmapset_id, _) in &links.beatmapsets {
+                println!("{}", mirror.download_url(*beatmapset_id));
+            }
+        }
+        LinkFormat::Html => print!("{}", links.to_html(mirror)),
+    }
+
+    // Report to stderr so that the links can be piped straight into a file
+    eprintln!(
+        "{} beatmapsets found ({} unsubmitted beatmaps skipped, {} MD5 hashes not found in osu!.db)",
+        links.beatmapsets.len(),
+        links.unsubmitted,
+        links.unresolved
+    );
+
+    Ok(())
+}
+
+/// Finds the beatmapsets for each beatmap in a collection.
+fn from_collection<'a>(
+    listing: &'a BeatmapListing,
+    collection: &Collection,
+) -> BeatmapsetLinks<'a> {
+    let beatmaps = listing.beatmaps_by_md5();
+
+    let (resolved, unresolved): (Vec<_>, Vec<_>) = collection
+        .beatmap_md5s
+        .iter()
+        .flatten()
+        .map(|md5| beatmaps.get(md5.as_str()).copied())
+        .partition(|b| b.is_some());
+
+    BeatmapsetLinks {
+        unresolved: unresolved.len(),
+        ..BeatmapsetLinks::from_beatmaps(resolved.into_iter().flatten())
+    }
+}
+
+impl<'a> BeatmapsetLinks<'a> {
+    /// Finds the unique beatmapsets for a set of beatmaps.
+    fn from_beatmaps(beatmaps: impl Iterator<Item = &'a BeatmapEntry>) -> Self {
+        let mut found = BeatmapsetLinks::default();
+        let mut seen = HashSet::new();
+
+        for beatmap in beatmaps {
+            match beatmap.beatmapset_id() {
+                Some(beatmapset_id) if beatmap.ranked_status != RankedStatus::Unsubmitted => {
+                    if seen.insert(beatmapset_id) {
+                        found.beatmapsets.push((beatmapset_id, beatmap));
+                    }
+                }
+                _ => found.unsubmitted += 1,
+            }
+        }
+
+        found
+    }
+
+    /// Creates a web page listing the download links.
+    fn to_html(&self, mirror: Mirror) -> String {
+        let items = self
+            .beatmapsets
+            .iter()
+            .map(|(beatmapset_id, b)| {
+                format!(
+                    "      <li><a href=\"{}\">{} - {}</a></li>\n",
+                    html_escape(&mirror.download_url(*beatmapset_id)),
+                    html_escape(&b.display_artist(false).unwrap_or_default().sanitized()),
+                    html_escape(&b.display_title(false).unwrap_or_default().sanitized())
+                )
+            })
+            .collect::<String>();
+
+        format!(
+            "<!DOCTYPE html>\n\
+             <html>\n  \
+               <head>\n    \
+                 <meta charset=\"utf-8\" />\n    \
+                 <title>Beatmap Downloads</title>\n  \
+               </head>\n  \
+               <body>\n    \
+                 <h1>Beatmap Downloads ({} beatmapsets)</h1>\n    \
+                 <ol>\n{}    </ol>\n  \
+               </body>\n\
+             </html>\n",
+            self.beatmapsets.len(),
+            items
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing() -> BeatmapListing {
+        let beatmap = |md5: &str, beatmap_id: u32, ranked_status: RankedStatus| BeatmapEntry {
+            md5: Some(md5.to_string()),
+            artist_name: Some("Artist".to_string()),
+            song_title: Some("Title & More".to_string()),
+            beatmap_id,
+            ranked_status,
+            ..Default::default()
+        };
+
+        BeatmapListing {
+            version: 20250108,
+            folder_count: 3,
+            account_unlocked: true,
+            account_unlock_date: osu_db_parser::common::WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps: vec![
+                beatmap("a", 1, RankedStatus::Ranked),
+                beatmap("b", 1, RankedStatus::Ranked),
+                beatmap("c", 2, RankedStatus::Loved),
+                beatmap("d", 0, RankedStatus::Unsubmitted),
+                beatmap("e", 3, RankedStatus::Pending),
+            ],
+            user_permissions: Default::default(),
+        }
+    }
+
+    #[test]
+    fn beatmapsets_are_deduplicated() {
+        let listing = listing();
+        let links = BeatmapsetLinks::from_beatmaps(listing.beatmaps.iter());
+
+        let ids = links
+            .beatmapsets
+            .iter()
+            .map(|(beatmapset_id, _)| beatmapset_id.get())
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(links.unsubmitted, 1);
+        assert_eq!(links.unresolved, 0);
+    }
+
+    #[test]
+    fn unresolved_collection_beatmaps_are_counted() {
+        let listing = listing();
+        let collection = Collection {
+            name: Some("Test".to_string()),
+            beatmap_md5s: vec![
+                Some("b".to_string()),
+                Some("a".to_string()),
+                Some("missing".to_string()),
+                Some("d".to_string()),
+                Some("also missing".to_string()),
+                None,
+            ],
+        };
+
+        let links = from_collection(&listing, &collection);
+
+        assert_eq!(links.beatmapsets.len(), 1);
+        assert_eq!(links.beatmapsets[0].1.md5.as_deref(), Some("b"));
+        assert_eq!(links.unsubmitted, 1);
+        assert_eq!(links.unresolved, 2);
+    }
+
+    #[test]
+    fn html_output_is_escaped() {
+        let listing = listing();
+        let links = BeatmapsetLinks::from_beatmaps(listing.beatmaps.iter().take(1));
+        let html = links.to_html(Mirror::Beatconnect);
+
+        assert!(html.contains(
+            "<li><a href=\"https://beatconnect.io/b/1\">Artist - Title &amp; More</a></li>"
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+//! The `random` command, which picks random beatmaps matching a query.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use osu_db_parser::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{beatmap_link, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct RandomArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// Only pick beatmaps matching this query (e.g. "stars>5.5 stars<6 mode=std status=ranked length<240")
+    #[arg(short, long, default_value = "")]
+    query: String,
+
+    /// Number of beatmaps to pick
+    #[arg(short = 'n', long, default_value_t = 1)]
+    count: usize,
+
+    /// Only pick beatmaps that haven't been played yet
+    #[arg(long)]
+    unplayed_only: bool,
+
+    /// Seed for the random number generator, for reproducible picks
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+pub fn run(args: RandomArgs, cache: &ParseCache) -> Result<(), Error> {
+    let filter = args.query.parse::<BeatmapFilter>()?;
+    let listing = cache.load_listing(&args.osu_db)?;
+
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let (matched, picks) = pick(
+        &listing.beatmaps,
+        &filter,
+        args.unplayed_only,
+        args.count,
+        &mut rng,
+    );
+
+    println!("{} matching beatmaps", matched);
+
+    for beatmap in picks {
+        println!(
+            "{}  {}",
+            beatmap.display_name(MetadataLanguage::Romanised),
+            beatmap_link(beatmap)
+        );
+    }
+
+    Ok(())
+}
+
+/// Picks up to `count` random beatmaps that match a filter, along with the total number of matching beatmaps.
+fn pick<'a, R: Rng>(
+    beatmaps: &'a [BeatmapEntry],
+    filter: &BeatmapFilter,
+    unplayed_only: bool,
+    count: usize,
+    rng: &mut R,
+) -> (usize, Vec<&'a BeatmapEntry>) {
+    let candidates = beatmaps
+        .iter()
+        .filter(|b| !unplayed_only || b.is_unplayed)
+        .filter(|b| filter.matches(b));
+
+    sample(candidates, count, rng)
+}
+
+/// Uniformly samples up to `count` items in a single pass over an iterator (i.e. reservoir sampling).
+///
+/// Returns the total number of items seen, along with the sampled items in the order they were seen.
+fn sample<T, R: Rng>(
+    items: impl IntoIterator<Item = T>,
+    count: usize,
+    rng: &mut R,
+) -> (usize, Vec<T>) {
+    // Keep track of each item's position so the final picks can be reported in a stable order
+    let mut reservoir: Vec<(usize, T)> = Vec::with_capacity(count);
+    let mut seen = 0;
+
+    for item in items {
+        if reservoir.len() < count {
+            reservoir.push((seen, item));
+        } else {
+            let j = rng.gen_range(0..=seen);
+            if j < count {
+                reservoir[j] = (seen, item);
+            }
+        }
+
+        seen += 1;
+    }
+
+    reservoir.sort_unstable_by_key(|(i, _)| *i);
+    (seen, reservoir.into_iter().map(|(_, item)| item).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beatmaps() -> Vec<BeatmapEntry> {
+        (0..100)
+            .map(|i| BeatmapEntry {
+                md5: Some(format!("{:032x}", i)),
+                gameplay_mode: if i % 2 == 0 {
+                    GameplayMode::Standard
+                } else {
+                    GameplayMode::Mania
+                },
+                is_unplayed: i % 3 == 0,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn md5s(beatmaps: &[&BeatmapEntry]) -> Vec<String> {
+        beatmaps
+            .iter()
+            .map(|b| b.md5.clone().unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn seeded_picks_are_deterministic() {
+        let beatmaps = beatmaps();
+        let filter = BeatmapFilter::parse("mode=mania").unwrap();
+
+        let (matched_a, picks_a) =
+            pick(&beatmaps, &filter, false, 5, &mut StdRng::seed_from_u64(42));
+        let (matched_b, picks_b) =
+            pick(&beatmaps, &filter, false, 5, &mut StdRng::seed_from_u64(42));
+
+        assert_eq!(matched_a, 50);
+        assert_eq!(matched_b, 50);
+        assert_eq!(picks_a.len(), 5);
+        assert_eq!(md5s(&picks_a), md5s(&picks_b));
+
+        assert!(picks_a
+            .iter()
+            .all(|b| b.gameplay_mode == GameplayMode::Mania));
+    }
+
+    #[test]
+    fn unplayed_only_is_respected() {
+        let beatmaps = beatmaps();
+        let filter = BeatmapFilter::default();
+
+        let (matched, picks) = pick(&beatmaps, &filter, true, 10, &mut StdRng::seed_from_u64(7));
+
+        assert_eq!(matched, 34);
+        assert_eq!(picks.len(), 10);
+        assert!(picks.iter().all(|b| b.is_unplayed));
+    }
+
+    #[test]
+    fn sampling_returns_everything_when_count_exceeds_matches() {
+        let (seen, picks) = sample(0..3, 5, &mut StdRng::seed_from_u64(1));
+
+        assert_eq!(seen, 3);
+        assert_eq!(picks, vec![0, 1, 2]);
+
+        let (seen, picks) = sample(0..0, 5, &mut StdRng::seed_from_u64(1));
+
+        assert_eq!(seen, 0);
+        assert!(picks.is_empty());
+    }
+
+    #[test]
+    fn sampling_is_roughly_uniform() {
+        let mut rng = StdRng::seed_from_u64(1234);
+        let mut counts = [0; 10];
+
+        for _ in 0..10_000 {
+            let (_, picks) = sample(0..10, 1, &mut rng);
+            counts[picks[0]] += 1;
+        }
+
+        // Each item should be picked ~1000 times
+        assert!(counts.iter().all(|c| (800..1200).contains(c)));
+    }
+}
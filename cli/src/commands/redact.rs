@@ -0,0 +1,54 @@
+//! The `redact` command, which writes a copy of `osu!.db` with the player's name and beatmaps' text replaced by
+//! pseudonyms, e.g. to attach to a bug report.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use osu_db_parser::anonymise::RedactOptions;
+
+use super::{back_up, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct RedactArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// Path to write the redacted `osu!.db` file to
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Also clear the times each beatmap was last modified, played and checked online
+    #[arg(long)]
+    zero_timestamps: bool,
+
+    /// Seed for the pseudonyms, to get the same ones each time; a random seed is used otherwise
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Overwrite the output file if it already exists
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(args: RedactArgs, cache: &ParseCache, keep_backups: usize) -> Result<(), Error> {
+    if args.out.exists() && !args.force {
+        return Err(Error::OutputExists(args.out));
+    }
+
+    let listing = cache.load_listing(&args.osu_db)?;
+    let redacted = listing.redact(RedactOptions {
+        seed: args.seed.unwrap_or_else(rand::random),
+        zero_timestamps: args.zero_timestamps,
+    });
+
+    back_up(&args.out, "redact", redacted.beatmaps.len(), keep_backups)?;
+    redacted.to_file(&args.out)?;
+    println!(
+        "Saved {} redacted beatmaps to {}",
+        redacted.beatmaps.len(),
+        args.out.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! The `repair` command, which recovers the beatmaps from a damaged `osu!.db` so that osu! doesn't need to rebuild the
+//! whole library.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use osu_db_parser::{detect::FileKind, repair::repair, shared::read_database};
+
+use super::back_up;
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct RepairArgs {
+    /// Path to the damaged `osu!.db` file
+    osu_db: PathBuf,
+
+    /// Path to write the repaired `osu!.db` file to
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Overwrite the output file if it already exists
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(args: RepairArgs, keep_backups: usize) -> Result<(), Error> {
+    if args.out.exists() && !args.force {
+        return Err(Error::OutputExists(args.out));
+    }
+
+    let data = read_database(&args.osu_db, FileKind::Beatmaps)?;
+    let repair = repair(&data)?;
+
+    println!(
+        "Recovered {} of {} beatmaps",
+        repair.recovered(),
+        repair.expected
+    );
+
+    for region in &repair.unrecoverable {
+        println!(
+            "Unable to recover bytes {}..{} ({} bytes)",
+            region.start,
+            region.end,
+            region.len()
+        );
+    }
+
+    back_up(&args.out, "repair", repair.recovered(), keep_backups)?;
+    repair.listing.to_file(&args.out)?;
+    println!("Saved the repaired database to {}", args.out.display());
+
+    Ok(())
+}
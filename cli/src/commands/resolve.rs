@@ -0,0 +1,100 @@
+//! The `resolve` command, which looks up a plain text list of beatmaps (e.g. a tournament mappool) in `osu!.db`.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use osu_db_parser::{
+    import::{self, ResolvedList},
+    prelude::*,
+};
+
+use super::{format_table, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct ResolveArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// Path to the list, with one MD5 hash, beatmap ID or osu! website link per line
+    #[arg(short, long)]
+    file: PathBuf,
+}
+
+pub fn run(args: ResolveArgs, cache: &ParseCache) -> Result<(), Error> {
+    let text = std::fs::read_to_string(&args.file)?;
+    let listing = cache.load_listing(&args.osu_db)?;
+    let resolved = import::resolve_list(&text, &listing);
+
+    print!("{}", found_table(&resolved, &listing));
+
+    // Report to stderr so that the table of found beatmaps can be piped into a file
+    for (line, entry) in &resolved.missing {
+        eprintln!("Line {}: {} isn't in osu!.db", line, entry);
+    }
+
+    for (line, text) in &resolved.unparseable {
+        eprintln!("Line {}: '{}' isn't a beatmap", line, text);
+    }
+
+    eprintln!(
+        "{} beatmaps found ({} missing, {} lines skipped)",
+        resolved.found.len(),
+        resolved.missing.len(),
+        resolved.unparseable.len()
+    );
+
+    Ok(())
+}
+
+/// Formats the beatmaps that were found as a table, along with the line each one was listed on.
+fn found_table(resolved: &ResolvedList, listing: &BeatmapListing) -> String {
+    let rows = resolved
+        .found
+        .iter()
+        .map(|&(line, index)| {
+            let beatmap = &listing.beatmaps[index];
+
+            vec![
+                line.to_string(),
+                beatmap.display_name(MetadataLanguage::Romanised),
+                beatmap.md5.clone().unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    format_table(&["Line", "Beatmap", "MD5"], &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn found_beatmaps_are_tabulated() {
+        let listing = BeatmapListing {
+            version: 20240101,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: osu_db_parser::common::WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps: vec![BeatmapEntry {
+                artist_name: Some("xi".to_string()),
+                song_title: Some("FREEDOM DiVE".to_string()),
+                difficulty: Some("FOUR DIMENSIONS".to_string()),
+                md5: Some("da8aae79c8f3306b5d65ec951874a7fb".to_string()),
+                difficulty_id: 129891,
+                ..Default::default()
+            }],
+            user_permissions: Default::default(),
+        };
+
+        let resolved = import::resolve_list("NM1\n129891\n", &listing);
+
+        assert_eq!(
+            found_table(&resolved, &listing),
+            "Line  Beatmap                              MD5\n\
+             2     xi - FREEDOM DiVE [FOUR DIMENSIONS]  da8aae79c8f3306b5d65ec951874a7fb\n"
+        );
+    }
+}
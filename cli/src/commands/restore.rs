@@ -0,0 +1,151 @@
+//! The `restore` command, which lists the backups made before a database was written over and puts one of them back.
+
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use osu_db_parser::backups::{Backup, BackupMethod, Backups};
+use time::macros::format_description;
+
+use super::format_table;
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct RestoreArgs {
+    /// Path to the database file that was backed up (e.g. `osu!.db` or `collection.db`)
+    file: PathBuf,
+
+    /// ID of the backup to restore, as shown in the list; the backups are listed if this isn't given
+    id: Option<String>,
+
+    /// Directory the backups are in, if it isn't the `osu-db-backups` directory next to the file
+    #[arg(long)]
+    backups: Option<PathBuf>,
+}
+
+pub fn run(args: RestoreArgs, keep: usize) -> Result<(), Error> {
+    let backups = backups_for(&args.file, args.backups.as_deref(), keep);
+
+    let Some(id) = &args.id else {
+        let listed = backups.list_for(&args.file)?;
+
+        if listed.is_empty() {
+            println!("There are no backups of {}", args.file.display());
+        } else {
+            print!("{}", backup_table(&listed));
+        }
+
+        return Ok(());
+    };
+
+    let backup = backups.restore(id)?;
+    println!(
+        "Restored {} to how it was before '{}' on {}",
+        backup.original.display(),
+        backup.operation,
+        created_text(&backup)
+    );
+
+    Ok(())
+}
+
+/// Gets the backups directory for a file, keeping `keep` backups of it. Restoring always backs up the file being
+/// replaced, even if backups are turned off, since the backup being restored might be the wrong one.
+fn backups_for(file: &Path, directory: Option<&Path>, keep: usize) -> Backups {
+    let backups = match directory {
+        Some(directory) => Backups::new(directory),
+        None => Backups::beside(file),
+    };
+
+    backups.keep(keep)
+}
+
+/// Formats the backups as a table, newest first.
+fn backup_table(backups: &[Backup]) -> String {
+    let rows = backups
+        .iter()
+        .map(|backup| {
+            vec![
+                backup.id.clone(),
+                created_text(backup),
+                backup.operation.clone(),
+                backup.changed.to_string(),
+                format!("{:.1} MB", backup.size as f64 / 1_000_000.0),
+                match backup.method {
+                    BackupMethod::HardLink => "hard link".to_string(),
+                    BackupMethod::Copy => "copy".to_string(),
+                },
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    format_table(
+        &["ID", "Made (UTC)", "Before", "Changed", "Size", "Stored as"],
+        &rows,
+    )
+}
+
+/// Formats when a backup was made, in UTC.
+fn created_text(backup: &Backup) -> String {
+    backup
+        .created
+        .format(format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second]"
+        ))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn backups_are_listed_and_restored() {
+        let dir = std::env::temp_dir().join(format!("osu-db-cli-restore-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let collection_db = dir.join("collection.db");
+        fs::write(&collection_db, b"original").unwrap();
+
+        let backups = backups_for(&collection_db, None, 5);
+        let backup = backups
+            .back_up(&collection_db, "collections", 2)
+            .unwrap()
+            .unwrap();
+        fs::write(&collection_db, b"edited").unwrap();
+
+        let table = backup_table(&backups.list_for(&collection_db).unwrap());
+        assert!(table.starts_with("ID "), "{}", table);
+        assert!(table.contains(&backup.id), "{}", table);
+        assert!(table.contains("collections  2"), "{}", table);
+        assert!(table.contains("0.0 MB  copy"), "{}", table);
+
+        run(
+            RestoreArgs {
+                file: collection_db.clone(),
+                id: Some(backup.id.clone()),
+                backups: None,
+            },
+            5,
+        )
+        .unwrap();
+        assert_eq!(fs::read(&collection_db).unwrap(), b"original");
+
+        let error = run(
+            RestoreArgs {
+                file: collection_db,
+                id: Some("19990101-000000".to_string()),
+                backups: None,
+            },
+            5,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "There's no backup with the ID 19990101-000000"
+        );
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
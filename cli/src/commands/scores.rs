@@ -0,0 +1,391 @@
+//! The `scores` command, which lists local scores from `scores.db`.
+
+use std::{cmp::Reverse, collections::HashMap, path::PathBuf};
+
+use clap::{Args, Subcommand, ValueEnum};
+use osu_db_parser::{format, links, prelude::*};
+use serde::Serialize;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime, UtcOffset};
+
+use super::{format_table, ModeArg, OutputFormat, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct ScoresArgs {
+    #[command(subcommand)]
+    command: ScoresCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ScoresCommand {
+    /// Lists the best scores across all beatmaps
+    Top(TopArgs),
+
+    /// Lists the scores set within the last few days, newest first
+    Recent(RecentArgs),
+
+    /// Lists the scores set on a single beatmap
+    Map(MapArgs),
+}
+
+/// Represents the arguments shared by each `scores` subcommand.
+#[derive(Args, Debug)]
+struct SourceArgs {
+    /// Path to the `scores.db` file
+    #[arg(long)]
+    scores: PathBuf,
+
+    /// Path to the `osu!.db` file, used to show beatmap names instead of MD5 hashes
+    #[arg(long)]
+    osu_db: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+struct TopArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    /// Only include scores for this gameplay mode
+    #[arg(long, value_enum)]
+    mode: Option<ModeArg>,
+
+    /// What to rank scores by
+    #[arg(long, value_enum, default_value_t = RankBy::Score)]
+    by: RankBy,
+
+    /// Maximum number of scores to list
+    #[arg(short = 'n', long, default_value_t = 25)]
+    limit: usize,
+}
+
+#[derive(Args, Debug)]
+struct RecentArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    /// Only include scores set within this many days
+    #[arg(long, default_value_t = 30)]
+    days: u32,
+
+    /// Only include scores for this gameplay mode
+    #[arg(long, value_enum)]
+    mode: Option<ModeArg>,
+
+    /// Maximum number of scores to list
+    #[arg(short = 'n', long, default_value_t = 25)]
+    limit: usize,
+}
+
+#[derive(Args, Debug)]
+struct MapArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    /// MD5 hash of the beatmap
+    #[arg(long)]
+    md5: String,
+}
+
+/// Represents the ways that scores can be ranked.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum RankBy {
+    Score,
+    Accuracy,
+}
+
+/// Represents a score in the command's output.
+#[derive(Serialize, Debug)]
+struct ScoreRow<'a> {
+    beatmap: String,
+    beatmap_md5: Option<&'a str>,
+    player: Option<&'a str>,
+    mode: String,
+    score: u32,
+    accuracy: f64,
+    grade: String,
+    mods: String,
+    max_combo: u16,
+    misses: u16,
+    timestamp: String,
+    url: Option<String>,
+
+    /// Date shown in the table
+    #[serde(skip)]
+    date: String,
+}
+
+pub fn run(args: ScoresArgs, cache: &ParseCache) -> Result<(), Error> {
+    let source = match &args.command {
+        ScoresCommand::Top(args) => &args.source,
+        ScoresCommand::Recent(args) => &args.source,
+        ScoresCommand::Map(args) => &args.source,
+    };
+
+    let listing = ScoreListing::from_file(&source.scores)?;
+    let beatmap_listing = source
+        .osu_db
+        .as_ref()
+        .map(|path| cache.load_listing(path))
+        .transpose()?;
+
+    let scores = match &args.command {
+        ScoresCommand::Top(args) => {
+            top_scores(&listing, args.mode.map(Into::into), args.by, args.limit)
+        }
+        ScoresCommand::Recent(args) => {
+            let since = OffsetDateTime::now_utc() - Duration::days(args.days.into());
+            recent_scores(&listing, since, args.mode.map(Into::into), args.limit)
+        }
+        ScoresCommand::Map(args) => map_scores(&listing, &args.md5),
+    };
+
+    let beatmaps = beatmap_listing
+        .as_ref()
+        .map(|l| l.beatmaps_by_md5())
+        .unwrap_or_default();
+
+    let rows = scores
+        .into_iter()
+        .map(|score| score_row(score, &beatmaps))
+        .collect::<Vec<_>>();
+
+    match source.format {
+        OutputFormat::Table => print!("{}", rows_to_table(&rows)),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&rows).expect("score rows should always serialize")
+        ),
+    }
+
+    Ok(())
+}
+
+/// Iterates over every score in a listing.
+fn all_scores(listing: &ScoreListing) -> impl Iterator<Item = &ScoreReplay> {
+    listing.beatmap_scores.iter().flat_map(|b| b.scores.iter())
+}
+
+/// Finds the best scores, optionally only for a single gameplay mode.
+fn top_scores(
+    listing: &ScoreListing,
+    mode: Option<GameplayMode>,
+    by: RankBy,
+    limit: usize,
+) -> Vec<&ScoreReplay> {
+    let mut scores = all_scores(listing)
+        .filter(|s| mode.is_none_or(|m| m == s.gameplay_mode))
+        .collect::<Vec<_>>();
+
+    match by {
+        RankBy::Score => scores.sort_by_key(|s| Reverse(s.score)),
+        RankBy::Accuracy => scores.sort_by(|a, b| {
+            b.accuracy()
+                .total_cmp(&a.accuracy())
+                .then(b.score.cmp(&a.score))
+        }),
+    }
+
+    scores.truncate(limit);
+    scores
+}
+
+/// Finds the scores set since a point in time, newest first.
+fn recent_scores(
+    listing: &ScoreListing,
+    since: OffsetDateTime,
+    mode: Option<GameplayMode>,
+    limit: usize,
+) -> Vec<&ScoreReplay> {
+    let mut scores = all_scores(listing)
+        .filter(|s| mode.is_none_or(|m| m == s.gameplay_mode))
+        .filter(|s| s.timestamp >= since)
+        .collect::<Vec<_>>();
+
+    scores.sort_by_key(|s| Reverse(s.timestamp));
+    scores.truncate(limit);
+    scores
+}
+
+/// Finds the scores set on a beatmap, ordered by score like the in-game leaderboard.
+fn map_scores<'a>(listing: &'a ScoreListing, md5: &str) -> Vec<&'a ScoreReplay> {
+    let mut scores = listing
+        .beatmap_scores
+        .iter()
+        .filter(|b| {
+            b.md5
+                .as_deref()
+                .is_some_and(|m| m.eq_ignore_ascii_case(md5))
+        })
+        .flat_map(|b| b.scores.iter())
+        .collect::<Vec<_>>();
+
+    scores.sort_by_key(|s| Reverse(s.score));
+    scores
+}
+
+/// Creates the output row for a score, using the beatmap's name if it can be found.
+fn score_row<'a>(score: &'a ScoreReplay, beatmaps: &HashMap<&str, &BeatmapEntry>) -> ScoreRow<'a> {
+    let beatmap_md5 = score.beatmap_md5.as_deref();
+    let beatmap = beatmap_md5.and_then(|md5| beatmaps.get(md5).copied());
+
+    ScoreRow {
+        beatmap: match (beatmap, beatmap_md5) {
+            (Some(beatmap), _) => beatmap.display_name(MetadataLanguage::Romanised),
+            (None, Some(md5)) => md5.to_string(),
+            (None, None) => "(unknown)".to_string(),
+        },
+        beatmap_md5,
+        player: score.player_name.as_deref(),
+        mode: score.gameplay_mode.to_string(),
+        score: score.score,
+        accuracy: score.accuracy(),
+        grade: score.grade().to_string(),
+        mods: Mods::acronyms(score.mods),
+        max_combo: score.max_combo,
+        misses: score.misses,
+        timestamp: score.timestamp.format(&Rfc3339).unwrap_or_default(),
+        url: links::score_url(score),
+        date: format::short_date(score.timestamp, UtcOffset::UTC),
+    }
+}
+
+/// Formats the output rows as a table.
+fn rows_to_table(rows: &[ScoreRow]) -> String {
+    let cells = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            vec![
+                (i + 1).to_string(),
+                row.date.clone(),
+                row.player.unwrap_or_default().to_string(),
+                row.beatmap.clone(),
+                row.mods.clone(),
+                row.score.to_string(),
+                format!("{:.2}%", row.accuracy),
+                format!("{}x", row.max_combo),
+                row.misses.to_string(),
+                row.grade.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    format_table(
+        &[
+            "#", "Date", "Player", "Beatmap", "Mods", "Score", "Accuracy", "Combo", "Misses",
+            "Grade",
+        ],
+        &cells,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing() -> ScoreListing {
+        let score =
+            |md5: &str, score: u32, hits_100: u16, days_ago: i64, mode: GameplayMode| ScoreReplay {
+                gameplay_mode: mode,
+                beatmap_md5: Some(md5.to_string()),
+                player_name: Some("Player".to_string()),
+                hits_300: 100,
+                hits_100,
+                score,
+                timestamp: OffsetDateTime::now_utc() - Duration::days(days_ago),
+                ..Default::default()
+            };
+
+        ScoreListing {
+            version: 20250108,
+            beatmap_scores: vec![
+                BeatmapScores {
+                    md5: Some("a".to_string()),
+                    scores: vec![
+                        score("a", 500, 10, 1, GameplayMode::Standard),
+                        score("a", 900, 20, 40, GameplayMode::Standard),
+                    ],
+                },
+                BeatmapScores {
+                    md5: Some("b".to_string()),
+                    scores: vec![
+                        score("b", 700, 0, 3, GameplayMode::Mania),
+                        score("b", 100, 0, 2, GameplayMode::Mania),
+                    ],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn top_scores_are_ranked_correctly() {
+        let listing = listing();
+
+        let by_score = top_scores(&listing, None, RankBy::Score, 3)
+            .iter()
+            .map(|s| s.score)
+            .collect::<Vec<_>>();
+        assert_eq!(by_score, vec![900, 700, 500]);
+
+        let by_accuracy = top_scores(&listing, None, RankBy::Accuracy, 10)
+            .iter()
+            .map(|s| s.score)
+            .collect::<Vec<_>>();
+        assert_eq!(by_accuracy, vec![700, 100, 500, 900]);
+
+        let mania = top_scores(&listing, Some(GameplayMode::Mania), RankBy::Score, 10);
+        assert!(mania.iter().all(|s| s.gameplay_mode == GameplayMode::Mania));
+        assert_eq!(mania.len(), 2);
+    }
+
+    #[test]
+    fn recent_scores_are_newest_first() {
+        let listing = listing();
+        let since = OffsetDateTime::now_utc() - Duration::days(30);
+
+        let recent = recent_scores(&listing, since, None, 10)
+            .iter()
+            .map(|s| s.score)
+            .collect::<Vec<_>>();
+
+        assert_eq!(recent, vec![500, 100, 700]);
+    }
+
+    #[test]
+    fn map_scores_are_found_by_md5() {
+        let listing = listing();
+
+        let scores = map_scores(&listing, "B")
+            .iter()
+            .map(|s| s.score)
+            .collect::<Vec<_>>();
+
+        assert_eq!(scores, vec![700, 100]);
+        assert!(map_scores(&listing, "missing").is_empty());
+    }
+
+    #[test]
+    fn beatmap_names_are_used_when_available() {
+        let listing = listing();
+        let score = &listing.beatmap_scores[0].scores[0];
+
+        let beatmap = BeatmapEntry {
+            md5: Some("a".to_string()),
+            artist_name: Some("Artist".to_string()),
+            song_title: Some("Title".to_string()),
+            difficulty: Some("Insane".to_string()),
+            ..Default::default()
+        };
+        let beatmaps = HashMap::from([("a", &beatmap)]);
+
+        assert_eq!(
+            score_row(score, &beatmaps).beatmap,
+            "Artist - Title [Insane]"
+        );
+        assert_eq!(score_row(score, &HashMap::new()).beatmap, "a");
+    }
+}
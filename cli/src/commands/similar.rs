@@ -0,0 +1,98 @@
+//! The `similar` command, which suggests beatmaps in the library that are similar to a given beatmap.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use osu_db_parser::{
+    analysis::{self, SimilarityOptions},
+    prelude::*,
+};
+
+use super::{beatmap_link, format_table, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct SimilarArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// MD5 hash of the beatmap to find similar beatmaps for
+    #[arg(long)]
+    md5: String,
+
+    /// Number of beatmaps to suggest
+    #[arg(short = 'n', long, default_value_t = 10)]
+    count: usize,
+
+    /// Also suggest other difficulties from the same beatmapset
+    #[arg(long)]
+    include_same_set: bool,
+}
+
+pub fn run(args: SimilarArgs, cache: &ParseCache) -> Result<(), Error> {
+    let listing = cache.load_listing(&args.osu_db)?;
+
+    let beatmap = listing
+        .beatmaps
+        .iter()
+        .find(|beatmap| {
+            beatmap
+                .md5
+                .as_deref()
+                .is_some_and(|md5| md5.eq_ignore_ascii_case(&args.md5))
+        })
+        .ok_or_else(|| Error::BeatmapNotFound(args.md5.clone()))?;
+
+    let options = SimilarityOptions {
+        include_same_set: args.include_same_set,
+        ..Default::default()
+    };
+    let similar = analysis::similar_maps_with(&listing, beatmap, args.count, &options);
+
+    println!(
+        "Beatmaps similar to {}",
+        beatmap.display_name(MetadataLanguage::Romanised)
+    );
+    print!("{}", similar_table(&similar));
+
+    Ok(())
+}
+
+/// Formats the suggested beatmaps as a table.
+fn similar_table(similar: &[(&BeatmapEntry, f32)]) -> String {
+    let rows = similar
+        .iter()
+        .enumerate()
+        .map(|(i, (beatmap, score))| {
+            vec![
+                (i + 1).to_string(),
+                format!("{:.0}%", score * 100.0),
+                beatmap.display_name(MetadataLanguage::Romanised),
+                beatmap_link(beatmap),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    format_table(&["#", "Similarity", "Beatmap", "Link"], &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestions_are_tabulated() {
+        let beatmap = BeatmapEntry {
+            artist_name: Some("xi".to_string()),
+            song_title: Some("FREEDOM DiVE".to_string()),
+            difficulty: Some("FOUR DIMENSIONS".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            similar_table(&[(&beatmap, 0.854)]),
+            "#  Similarity  Beatmap                              Link\n\
+             1  85%         xi - FREEDOM DiVE [FOUR DIMENSIONS]  (unsubmitted)\n"
+        );
+    }
+}
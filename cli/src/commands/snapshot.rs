@@ -0,0 +1,146 @@
+//! The `snapshot` command, which caches a parsed `osu!.db` as MessagePack so that it can be loaded again quickly.
+
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use clap::{Args, Subcommand};
+use osu_db_parser::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::ParseCache;
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct SnapshotArgs {
+    #[command(subcommand)]
+    command: SnapshotCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum SnapshotCommand {
+    /// Parses an `osu!.db` file and saves it as a snapshot
+    Save {
+        /// Path to the `osu!.db` file
+        osu_db: PathBuf,
+
+        /// Path to save the snapshot to
+        #[arg(long)]
+        out: PathBuf,
+    },
+
+    /// Loads a snapshot and prints a summary of its contents
+    Load {
+        /// Path to the snapshot
+        snapshot: PathBuf,
+    },
+}
+
+/// Version of the snapshot format; this must be incremented whenever the parser's models change.
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Represents the contents of a snapshot file.
+#[derive(Serialize, Deserialize, Debug)]
+struct Snapshot<'a> {
+    version: u32,
+    listing: Cow<'a, BeatmapListing>,
+}
+
+pub fn run(args: SnapshotArgs, cache: &ParseCache) -> Result<(), Error> {
+    match args.command {
+        SnapshotCommand::Save { osu_db, out } => {
+            let listing = cache.load_listing(&osu_db)?;
+            save(&listing, &out)?;
+
+            println!(
+                "Saved {} beatmaps to {}",
+                listing.beatmaps.len(),
+                out.display()
+            );
+        }
+        SnapshotCommand::Load { snapshot } => {
+            let start = Instant::now();
+            let listing = load(&snapshot)?;
+
+            println!(
+                "Loaded {} beatmaps in {:.1?}",
+                listing.beatmaps.len(),
+                start.elapsed()
+            );
+            println!("osu! version: {}", listing.version);
+            println!(
+                "Player name: {}",
+                listing.player_name_str().unwrap_or("(no player name)")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Saves a beatmap listing as a snapshot.
+pub(super) fn save(listing: &BeatmapListing, path: &Path) -> Result<(), Error> {
+    let snapshot = Snapshot {
+        version: SNAPSHOT_VERSION,
+        listing: Cow::Borrowed(listing),
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    rmp_serde::encode::write_named(&mut writer, &snapshot)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Loads a beatmap listing from a snapshot, checking that it was saved using the current snapshot format.
+pub(super) fn load(path: &Path) -> Result<BeatmapListing, Error> {
+    let reader = BufReader::new(File::open(path)?);
+    let snapshot: Snapshot = rmp_serde::decode::from_read(reader)?;
+
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(Error::SnapshotVersion(snapshot.version));
+    }
+
+    Ok(snapshot.listing.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_round_trip() {
+        let listing = BeatmapListing {
+            version: 20250108,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: osu_db_parser::common::WINDOWS_EPOCH,
+            player_name: Some("Player".to_string()),
+            beatmaps: vec![BeatmapEntry {
+                md5: Some("a".to_string()),
+                star_ratings_std: Some(vec![StarRating {
+                    mods: Mods::Hidden | Mods::DoubleTime,
+                    rating: 7.25,
+                }]),
+                timing_points: vec![TimingPoint {
+                    bpm: 300.0,
+                    song_offset: 1000.0,
+                    inherited: true,
+                }],
+                ..Default::default()
+            }],
+            user_permissions: Default::default(),
+        };
+
+        let path = std::env::temp_dir().join(format!("osu-db-snapshot-{}", std::process::id()));
+        save(&listing, &path).unwrap();
+        let loaded = load(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.unwrap(), listing);
+    }
+}
@@ -0,0 +1,508 @@
+//! The `sqlite` command, which exports the database files to an SQLite database for analysis with other tools.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use osu_db_parser::{
+    common::{finite, WINDOWS_EPOCH},
+    prelude::*,
+};
+use rusqlite::{params, Connection, Transaction};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use super::ParseCache;
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct SqliteArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// Path to create the SQLite database at
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Path to the `scores.db` file, to also export local scores
+    #[arg(long)]
+    scores: Option<PathBuf>,
+
+    /// Path to the `collection.db` file, to also export collections
+    #[arg(long)]
+    collections: Option<PathBuf>,
+
+    /// Overwrite the output file if it already exists
+    #[arg(long)]
+    force: bool,
+}
+
+/// Schema for the exported database.
+const SCHEMA: &str = "
+CREATE TABLE beatmaps (
+    md5 TEXT,
+    beatmapset_id INTEGER,
+    difficulty_id INTEGER,
+    artist TEXT,
+    artist_unicode TEXT,
+    title TEXT,
+    title_unicode TEXT,
+    creator TEXT,
+    difficulty TEXT,
+    source TEXT,
+    tags TEXT,
+    mode TEXT NOT NULL,
+    ranked_status TEXT NOT NULL,
+    stars REAL,
+    approach_rate REAL,
+    circle_size REAL,
+    overall_difficulty REAL,
+    hp_drain REAL,
+    drain_time INTEGER NOT NULL,
+    total_time INTEGER NOT NULL,
+    hitcircle_count INTEGER NOT NULL,
+    slider_count INTEGER NOT NULL,
+    spinner_count INTEGER NOT NULL,
+    is_unplayed INTEGER NOT NULL,
+    last_played TEXT,
+    last_modified TEXT,
+    folder_name TEXT,
+    beatmap_filename TEXT,
+    audio_filename TEXT
+);
+
+CREATE TABLE star_ratings (
+    beatmap_md5 TEXT,
+    mode TEXT NOT NULL,
+    mods TEXT NOT NULL,
+    mods_bits INTEGER NOT NULL,
+    rating REAL
+);
+
+CREATE TABLE scores (
+    beatmap_md5 TEXT,
+    player TEXT,
+    mode TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    accuracy REAL,
+    grade TEXT NOT NULL,
+    mods TEXT NOT NULL,
+    mods_bits INTEGER NOT NULL,
+    max_combo INTEGER NOT NULL,
+    is_perfect_combo INTEGER NOT NULL,
+    hits_300 INTEGER NOT NULL,
+    hits_100 INTEGER NOT NULL,
+    hits_50 INTEGER NOT NULL,
+    hits_geki INTEGER NOT NULL,
+    hits_katu INTEGER NOT NULL,
+    misses INTEGER NOT NULL,
+    timestamp TEXT,
+    online_score_id INTEGER NOT NULL,
+    replay_md5 TEXT
+);
+
+CREATE TABLE collections (
+    id INTEGER PRIMARY KEY,
+    name TEXT
+);
+
+CREATE TABLE collection_beatmaps (
+    collection_id INTEGER NOT NULL REFERENCES collections (id),
+    beatmap_md5 TEXT
+);
+
+CREATE INDEX beatmaps_md5 ON beatmaps (md5);
+CREATE INDEX star_ratings_beatmap_md5 ON star_ratings (beatmap_md5);
+CREATE INDEX scores_beatmap_md5 ON scores (beatmap_md5);
+CREATE INDEX collection_beatmaps_beatmap_md5 ON collection_beatmaps (beatmap_md5);
+";
+
+pub fn run(args: SqliteArgs, cache: &ParseCache) -> Result<(), Error> {
+    // Parse everything up front, so that a bad input file doesn't leave a partially written database behind
+    let listing = cache.load_listing(&args.osu_db)?;
+    let scores = args
+        .scores
+        .as_ref()
+        .map(ScoreListing::from_file)
+        .transpose()?;
+    let collections = args
+        .collections
+        .as_ref()
+        .map(CollectionListing::from_file)
+        .transpose()?;
+
+    if args.out.exists() {
+        if !args.force {
+            return Err(Error::OutputExists(args.out));
+        }
+
+        std::fs::remove_file(&args.out)?;
+    }
+
+    let mut connection = Connection::open(&args.out)?;
+    let row_counts = export(
+        &mut connection,
+        &listing,
+        scores.as_ref(),
+        collections.as_ref(),
+    )?;
+
+    println!("Exported to {}", args.out.display());
+
+    for (table, count) in row_counts {
+        println!("  {:<20} {} rows", table, count);
+    }
+
+    Ok(())
+}
+
+/// Exports the database files to an empty SQLite database, returning the number of rows written to each table.
+fn export(
+    connection: &mut Connection,
+    listing: &BeatmapListing,
+    scores: Option<&ScoreListing>,
+    collections: Option<&CollectionListing>,
+) -> rusqlite::Result<Vec<(&'static str, usize)>> {
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+
+    let mut row_counts = insert_beatmaps(&transaction, listing)?;
+
+    if let Some(scores) = scores {
+        row_counts.push(("scores", insert_scores(&transaction, scores)?));
+    }
+
+    if let Some(collections) = collections {
+        row_counts.extend(insert_collections(&transaction, collections)?);
+    }
+
+    transaction.commit()?;
+    Ok(row_counts)
+}
+
+/// Inserts each beatmap and its star ratings.
+fn insert_beatmaps(
+    transaction: &Transaction,
+    listing: &BeatmapListing,
+) -> rusqlite::Result<Vec<(&'static str, usize)>> {
+    let mut insert_beatmap = transaction.prepare(
+        "INSERT INTO beatmaps VALUES (
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+            ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29
+        )",
+    )?;
+    let mut insert_star_rating =
+        transaction.prepare("INSERT INTO star_ratings VALUES (?1, ?2, ?3, ?4, ?5)")?;
+
+    let mut star_rating_count = 0;
+
+    for beatmap in &listing.beatmaps {
+        insert_beatmap.execute(params![
+            beatmap.md5,
+            beatmap.beatmapset_id().map(u32::from),
+            beatmap.difficulty_id().map(u32::from),
+            beatmap.artist_name,
+            beatmap.artist_name_unicode,
+            beatmap.song_title,
+            beatmap.song_title_unicode,
+            beatmap.creator_name,
+            beatmap.difficulty,
+            beatmap.song_source,
+            beatmap.song_tags,
+            beatmap.gameplay_mode.to_string(),
+            beatmap.ranked_status.to_string(),
+            beatmap.nomod_star_rating().and_then(finite),
+            finite(beatmap.approach_rate),
+            finite(beatmap.circle_size),
+            finite(beatmap.overall_difficulty),
+            finite(beatmap.hp_drain),
+            beatmap.drain_time,
+            beatmap.total_time,
+            beatmap.hitcircle_count,
+            beatmap.slider_count,
+            beatmap.spinner_count,
+            beatmap.is_unplayed,
+            timestamp(beatmap.last_played),
+            timestamp(beatmap.last_modification_time),
+            beatmap.folder_name,
+            beatmap.beatmap_filename,
+            beatmap.audio_filename,
+        ])?;
+
+        for mode in [
+            GameplayMode::Standard,
+            GameplayMode::Taiko,
+            GameplayMode::Catch,
+            GameplayMode::Mania,
+        ] {
+            for star_rating in beatmap.star_ratings(mode).unwrap_or_default() {
+                insert_star_rating.execute(params![
+                    beatmap.md5,
+                    mode.to_string(),
+                    Mods::acronyms(star_rating.mods),
+                    star_rating.mods.bits(),
+                    finite(star_rating.rating),
+                ])?;
+
+                star_rating_count += 1;
+            }
+        }
+    }
+
+    Ok(vec![
+        ("beatmaps", listing.beatmaps.len()),
+        ("star_ratings", star_rating_count),
+    ])
+}
+
+/// Inserts each local score.
+fn insert_scores(transaction: &Transaction, scores: &ScoreListing) -> rusqlite::Result<usize> {
+    let mut insert_score = transaction.prepare(
+        "INSERT INTO scores VALUES (
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19
+        )",
+    )?;
+
+    let mut count = 0;
+
+    for score in scores.beatmap_scores.iter().flat_map(|b| b.scores.iter()) {
+        insert_score.execute(params![
+            score.beatmap_md5,
+            score.player_name,
+            score.gameplay_mode.to_string(),
+            score.score,
+            finite(score.accuracy()),
+            score.grade().to_string(),
+            Mods::acronyms(score.mods),
+            score.mods.bits(),
+            score.max_combo,
+            score.is_perfect_combo,
+            score.hits_300,
+            score.hits_100,
+            score.hits_50,
+            score.hits_geki,
+            score.hits_katu,
+            score.misses,
+            timestamp(score.timestamp),
+            // SQLite integers are signed, so the rare IDs above i64::MAX are stored as negative numbers
+            score.online_score_id as i64,
+            score.replay_md5,
+        ])?;
+
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Inserts each collection and the beatmaps in it.
+fn insert_collections(
+    transaction: &Transaction,
+    collections: &CollectionListing,
+) -> rusqlite::Result<Vec<(&'static str, usize)>> {
+    let mut insert_collection =
+        transaction.prepare("INSERT INTO collections (name) VALUES (?1)")?;
+    let mut insert_beatmap =
+        transaction.prepare("INSERT INTO collection_beatmaps VALUES (?1, ?2)")?;
+
+    let mut beatmap_count = 0;
+
+    for collection in &collections.collections {
+        insert_collection.execute(params![collection.name])?;
+        let collection_id = transaction.last_insert_rowid();
+
+        for md5 in &collection.beatmap_md5s {
+            insert_beatmap.execute(params![collection_id, md5])?;
+            beatmap_count += 1;
+        }
+    }
+
+    Ok(vec![
+        ("collections", collections.collections.len()),
+        ("collection_beatmaps", beatmap_count),
+    ])
+}
+
+/// Formats a timestamp for storage, treating osu!'s default of `0001-01-01` as a missing value.
+fn timestamp(datetime: OffsetDateTime) -> Option<String> {
+    if datetime == WINDOWS_EPOCH {
+        None
+    } else {
+        datetime.format(&Rfc3339).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_rows(connection: &Connection, table: &str) -> usize {
+        connection
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn exported_database_has_expected_rows() {
+        let listing = BeatmapListing {
+            version: 20250108,
+            folder_count: 2,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps: vec![
+                BeatmapEntry {
+                    md5: Some("a".to_string()),
+                    star_ratings_std: Some(vec![
+                        StarRating {
+                            mods: Mods::none(),
+                            rating: 5.5,
+                        },
+                        StarRating {
+                            mods: Mods::DoubleTime.into(),
+                            rating: 7.25,
+                        },
+                    ]),
+                    ..Default::default()
+                },
+                BeatmapEntry {
+                    md5: Some("b".to_string()),
+                    ..Default::default()
+                },
+            ],
+            user_permissions: Default::default(),
+        };
+
+        let scores = ScoreListing {
+            version: 20250108,
+            beatmap_scores: vec![BeatmapScores {
+                md5: Some("a".to_string()),
+                scores: vec![
+                    ScoreReplay {
+                        beatmap_md5: Some("a".to_string()),
+                        online_score_id: u64::MAX,
+                        ..Default::default()
+                    },
+                    ScoreReplay::default(),
+                    ScoreReplay::default(),
+                ],
+            }],
+        };
+
+        let collections = CollectionListing {
+            version: 20250108,
+            collections: vec![
+                Collection {
+                    name: Some("Favourites".to_string()),
+                    beatmap_md5s: vec![Some("a".to_string()), Some("b".to_string())],
+                },
+                Collection {
+                    name: Some("Empty".to_string()),
+                    beatmap_md5s: Vec::new(),
+                },
+            ],
+        };
+
+        let path =
+            std::env::temp_dir().join(format!("osu-db-sqlite-{}.sqlite", std::process::id()));
+        std::fs::remove_file(&path).ok();
+
+        let row_counts = export(
+            &mut Connection::open(&path).unwrap(),
+            &listing,
+            Some(&scores),
+            Some(&collections),
+        )
+        .unwrap();
+
+        assert_eq!(
+            row_counts,
+            vec![
+                ("beatmaps", 2),
+                ("star_ratings", 2),
+                ("scores", 3),
+                ("collections", 2),
+                ("collection_beatmaps", 2),
+            ]
+        );
+
+        // Reopen the file to make sure everything was committed
+        let connection = Connection::open(&path).unwrap();
+
+        for (table, count) in row_counts {
+            assert_eq!(count_rows(&connection, table), count);
+        }
+
+        let (mods, last_played): (String, Option<String>) = connection
+            .query_row(
+                "SELECT s.mods, b.last_played FROM star_ratings s JOIN beatmaps b ON b.md5 = s.beatmap_md5 WHERE s.rating > 6",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+
+        assert_eq!(mods, "DT");
+        assert_eq!(last_played, None);
+
+        drop(connection);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn non_finite_values_are_exported_as_null() {
+        let listing = BeatmapListing {
+            version: 20250108,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps: vec![BeatmapEntry {
+                md5: Some("a".to_string()),
+                star_ratings_std: Some(vec![StarRating {
+                    mods: Mods::none(),
+                    rating: f64::NAN,
+                }]),
+                approach_rate: f32::INFINITY,
+                ..Default::default()
+            }],
+            user_permissions: Default::default(),
+        };
+
+        // A score without any hits has no accuracy
+        let scores = ScoreListing {
+            version: 20250108,
+            beatmap_scores: vec![BeatmapScores {
+                md5: Some("a".to_string()),
+                scores: vec![ScoreReplay {
+                    beatmap_md5: Some("a".to_string()),
+                    ..Default::default()
+                }],
+            }],
+        };
+
+        assert!(scores.beatmap_scores[0].scores[0].accuracy().is_nan());
+
+        let mut connection = Connection::open_in_memory().unwrap();
+        export(&mut connection, &listing, Some(&scores), None).unwrap();
+
+        let (rating, accuracy): (Option<f64>, Option<f64>) = connection
+            .query_row(
+                "SELECT s.rating, c.accuracy FROM star_ratings s JOIN scores c ON c.beatmap_md5 = s.beatmap_md5",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+
+        assert_eq!(rating, None);
+        assert_eq!(accuracy, None);
+
+        let (stars, approach_rate): (Option<f64>, Option<f64>) = connection
+            .query_row("SELECT stars, approach_rate FROM beatmaps", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+
+        assert_eq!(stars, None);
+        assert_eq!(approach_rate, None);
+    }
+}
@@ -0,0 +1,168 @@
+//! The `stats` command, which summarises a library and how much it was played each month.
+
+use std::{collections::BTreeSet, path::PathBuf};
+
+use clap::Args;
+use osu_db_parser::{analysis, prelude::*, stats::LibraryStats};
+use time::{macros::format_description, UtcOffset};
+
+use super::{format_table, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// Path to the `scores.db` file, to also count the scores set each month
+    #[arg(long)]
+    scores: Option<PathBuf>,
+
+    /// Timezone to split days and months in, as an offset from UTC (e.g. `+09:00` or `-5`)
+    #[arg(long, value_parser = parse_utc_offset, default_value = "+00:00")]
+    utc_offset: UtcOffset,
+}
+
+pub fn run(args: StatsArgs, cache: &ParseCache) -> Result<(), Error> {
+    let listing = cache.load_listing(&args.osu_db)?;
+    let stats = LibraryStats::from_beatmaps(&listing.beatmaps, 0);
+
+    println!(
+        "{} · {}",
+        listing.player_name_str().unwrap_or("Unknown player"),
+        account_status_text(listing.account_status(), args.utc_offset)
+    );
+    println!(
+        "{} beatmaps, {} unplayed ({:.1}%)",
+        stats.total,
+        stats.unplayed,
+        stats.unplayed_percentage()
+    );
+
+    let played = analysis::monthly_totals(&analysis::beatmap_activity(&listing, args.utc_offset));
+    let scores = match &args.scores {
+        Some(path) => {
+            let scores = ScoreListing::from_file(path)?;
+            Some(analysis::monthly_totals(&analysis::score_activity(
+                &scores,
+                args.utc_offset,
+            )))
+        }
+        None => None,
+    };
+
+    let months = played
+        .keys()
+        .chain(scores.iter().flat_map(|scores| scores.keys()))
+        .collect::<BTreeSet<_>>();
+
+    if months.is_empty() {
+        println!("No play activity recorded");
+        return Ok(());
+    }
+
+    let rows = months
+        .into_iter()
+        .map(|month| {
+            let mut row = vec![
+                format!("{}-{:02}", month.0, month.1),
+                played.get(month).copied().unwrap_or_default().to_string(),
+            ];
+
+            if let Some(scores) = &scores {
+                row.push(scores.get(month).copied().unwrap_or_default().to_string());
+            }
+
+            row
+        })
+        .collect::<Vec<_>>();
+
+    let headers: &[&str] = if scores.is_some() {
+        &["Month", "Last played", "Scores set"]
+    } else {
+        &["Month", "Last played"]
+    };
+
+    println!();
+    print!("{}", format_table(headers, &rows));
+
+    Ok(())
+}
+
+/// Describes whether the account is locked, with the unlock date in the timezone `tz`.
+fn account_status_text(status: AccountStatus, tz: UtcOffset) -> String {
+    match status {
+        AccountStatus::Unlocked => "account unlocked".to_string(),
+        AccountStatus::LockedUntil(date) => format!(
+            "account locked until {}",
+            date.to_offset(tz)
+                .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+                .unwrap_or_default()
+        ),
+        AccountStatus::LockedIndefinitely => "account locked indefinitely".to_string(),
+    }
+}
+
+/// Parses an offset from UTC, given as hours with optional minutes (e.g. `+9`, `-05:00` or `+5:30`).
+fn parse_utc_offset(value: &str) -> Result<UtcOffset, String> {
+    let invalid = || format!("invalid UTC offset `{}`, expected e.g. `+09:00`", value);
+
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hours = hours.parse::<i8>().map_err(|_| invalid())?;
+    let minutes = minutes.parse::<i8>().map_err(|_| invalid())?;
+
+    if !(0..60).contains(&minutes) {
+        return Err(invalid());
+    }
+
+    UtcOffset::from_hms(sign * hours, sign * minutes, 0).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{datetime, offset};
+
+    use super::*;
+
+    #[test]
+    fn utc_offsets_are_parsed() {
+        assert_eq!(parse_utc_offset("+09:00"), Ok(offset!(+9)));
+        assert_eq!(parse_utc_offset("9"), Ok(offset!(+9)));
+        assert_eq!(parse_utc_offset("-5"), Ok(offset!(-5)));
+        assert_eq!(parse_utc_offset("+5:30"), Ok(offset!(+5:30)));
+        assert_eq!(parse_utc_offset("-03:30"), Ok(offset!(-3:30)));
+        assert_eq!(parse_utc_offset("+00:00"), Ok(UtcOffset::UTC));
+    }
+
+    #[test]
+    fn invalid_utc_offsets_are_rejected() {
+        assert!(parse_utc_offset("").is_err());
+        assert!(parse_utc_offset("+9:60").is_err());
+        assert!(parse_utc_offset("+30").is_err());
+        assert!(parse_utc_offset("UTC").is_err());
+    }
+
+    #[test]
+    fn account_statuses_are_described() {
+        assert_eq!(
+            account_status_text(AccountStatus::Unlocked, UtcOffset::UTC),
+            "account unlocked"
+        );
+        assert_eq!(
+            account_status_text(
+                AccountStatus::LockedUntil(datetime!(2031-05-04 20:30 UTC)),
+                offset!(+9)
+            ),
+            "account locked until 2031-05-05 05:30"
+        );
+        assert_eq!(
+            account_status_text(AccountStatus::LockedIndefinitely, UtcOffset::UTC),
+            "account locked indefinitely"
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! The `verify` command, which compares the metadata stored in `osu!.db` with the beatmaps' `.osu` files, e.g. after
+//! editing them by hand, and can write a copy of `osu!.db` with the stale fields corrected.
+
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use osu_db_parser::{
+    health::{self, StaleMetadata},
+    osu_file, paths,
+    prelude::*,
+};
+
+use super::{back_up, format_table, ParseCache};
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// Path to the Songs folder, instead of the one set in osu!'s config file (or next to `osu!.db`)
+    #[arg(long)]
+    songs: Option<PathBuf>,
+
+    /// Path to write a copy of `osu!.db` to, with the stale fields replaced by the values in the `.osu` files
+    #[arg(long)]
+    out: Option<PathBuf>,
+
+    /// Overwrite the output file if it already exists
+    #[arg(long, requires = "out")]
+    force: bool,
+}
+
+pub fn run(args: VerifyArgs, cache: &ParseCache, keep_backups: usize) -> Result<(), Error> {
+    if let Some(out) = &args.out {
+        if out.exists() && !args.force {
+            return Err(Error::OutputExists(out.clone()));
+        }
+    }
+
+    let mut listing = cache.load_listing(&args.osu_db)?;
+    let songs_dir = args.songs.clone().unwrap_or_else(|| {
+        let osu_directory = args.osu_db.parent().unwrap_or(Path::new(""));
+        paths::songs_dir_guess(osu_directory, None)
+    });
+
+    let stale = health::stale_metadata(&songs_dir, &listing.beatmaps, |_, _| true)?;
+
+    if stale.is_empty() {
+        println!(
+            "The metadata of every beatmap found in {} matches osu!.db",
+            songs_dir.display()
+        );
+    } else {
+        print!("{}", mismatch_table(&listing.beatmaps, &stale));
+        println!(
+            "{} of {} beatmaps have stale metadata",
+            stale.len(),
+            listing.beatmaps.len()
+        );
+    }
+
+    let Some(out) = &args.out else {
+        return Ok(());
+    };
+
+    for stale in &stale {
+        let beatmap = &mut listing.beatmaps[stale.index];
+
+        // The file was just read, so this only fails if it was deleted since
+        if let Some(path) = paths::beatmap_file_in_songs(&songs_dir, beatmap) {
+            beatmap.apply_osu_metadata(&osu_file::read_metadata(path)?);
+        }
+    }
+
+    back_up(out, "verify", stale.len(), keep_backups)?;
+    listing.to_file(out)?;
+    println!("Saved the corrected database to {}", out.display());
+
+    Ok(())
+}
+
+/// Formats the stale fields as a table, with one row for each field.
+fn mismatch_table(beatmaps: &[BeatmapEntry], stale: &[StaleMetadata]) -> String {
+    let rows = stale
+        .iter()
+        .flat_map(|stale| {
+            let name = beatmaps[stale.index].display_name(MetadataLanguage::Romanised);
+
+            stale.mismatches.iter().map(move |mismatch| {
+                vec![
+                    name.clone(),
+                    mismatch.field.to_string(),
+                    mismatch.stored.clone(),
+                    mismatch.in_file.clone(),
+                ]
+            })
+        })
+        .collect::<Vec<_>>();
+
+    format_table(&["Beatmap", "Field", "osu!.db", ".osu file"], &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use osu_db_parser::reconcile::{FieldMismatch, MetadataField};
+
+    use super::*;
+
+    #[test]
+    fn mismatches_are_tabulated() {
+        let beatmap = BeatmapEntry {
+            artist_name: Some("xi".to_string()),
+            song_title: Some("FREEDOM DiVE".to_string()),
+            difficulty: Some("FOUR DIMENSIONS".to_string()),
+            ..Default::default()
+        };
+        let stale = StaleMetadata {
+            index: 0,
+            mismatches: vec![
+                FieldMismatch {
+                    field: MetadataField::Difficulty,
+                    stored: "FOUR DIMENSIONS".to_string(),
+                    in_file: "FIVE DIMENSIONS".to_string(),
+                },
+                FieldMismatch {
+                    field: MetadataField::ApproachRate,
+                    stored: "9".to_string(),
+                    in_file: "9.3".to_string(),
+                },
+            ],
+        };
+
+        assert_eq!(
+            mismatch_table(&[beatmap], &[stale]),
+            "Beatmap                              Field       osu!.db          .osu file\n\
+             xi - FREEDOM DiVE [FOUR DIMENSIONS]  Difficulty  FOUR DIMENSIONS  FIVE DIMENSIONS\n\
+             xi - FREEDOM DiVE [FOUR DIMENSIONS]  AR          9                9.3\n"
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! The `watch` command, which reloads `osu!.db` whenever osu! rewrites it.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+use clap::{Args, ValueEnum};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use osu_db_parser::prelude::*;
+
+use crate::error::Error;
+
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    /// Path to the `osu!.db` file
+    osu_db: PathBuf,
+
+    /// What to print when the database changes
+    #[arg(long, value_enum, default_value_t = OnChange::Diff)]
+    on_change: OnChange,
+
+    /// Shell command to run after each successful reload (e.g. another `osu-db` command)
+    #[arg(long)]
+    exec: Option<String>,
+
+    /// How long to wait for further changes before reloading, in milliseconds
+    #[arg(long, default_value_t = 500)]
+    debounce: u64,
+
+    /// How many times to retry parsing if the database appears to be mid-write
+    #[arg(long, default_value_t = 5)]
+    retries: u32,
+}
+
+/// Represents what should be printed after the database is reloaded.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OnChange {
+    /// Print each added, removed and changed beatmap
+    Diff,
+
+    /// Print the number of added, removed and changed beatmaps
+    Summary,
+
+    /// Don't print anything
+    Quiet,
+}
+
+/// Initial delay before retrying a failed reload; this doubles after each attempt.
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+
+pub fn run(args: WatchArgs) -> Result<(), Error> {
+    let path = args.osu_db.canonicalize()?;
+    let mut current = BeatmapListing::from_file(&path)?;
+
+    println!(
+        "Watching {} ({} beatmaps)",
+        path.display(),
+        current.beatmaps.len()
+    );
+
+    // osu! may replace the file rather than writing to it directly, so watch the parent directory instead
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let directory = path.parent().unwrap_or(Path::new("."));
+    watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+    let debounce = Duration::from_millis(args.debounce);
+
+    while wait_for_change(&rx, &path, debounce) {
+        let listing = match reload_with_retry(&path, args.retries, RETRY_DELAY) {
+            Ok(listing) => listing,
+            Err(e) => {
+                eprintln!("Unable to reload database, keeping previous version: {}", e);
+                continue;
+            }
+        };
+
+        let diff = ListingDiff::new(&current, &listing);
+        print_changes(&diff, args.on_change);
+
+        // A command that can't be started this time might work next time, so keep watching
+        if let Some(command) = &args.exec {
+            if let Err(e) = run_shell_command(command) {
+                eprintln!("Unable to run command '{}': {}", command, e);
+            }
+        }
+
+        current = listing;
+    }
+
+    Ok(())
+}
+
+/// Waits for an event affecting `path`, then keeps waiting until no further events affecting it have arrived for
+/// `debounce`. Events for other files, and reads, are ignored throughout, so that other activity in the osu! folder
+/// can't hold off the reload.
+///
+/// Returns `false` if the watcher has stopped.
+fn wait_for_change(rx: &Receiver<notify::Result<Event>>, path: &Path, debounce: Duration) -> bool {
+    let affects_path = |event: &notify::Result<Event>| match event {
+        Ok(event) => {
+            !matches!(event.kind, EventKind::Access(_))
+                && event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == path.file_name())
+        }
+        Err(e) => {
+            eprintln!("Watcher error: {}", e);
+            false
+        }
+    };
+
+    // Wait for the first relevant event
+    loop {
+        match rx.recv() {
+            Ok(event) if affects_path(&event) => break,
+            Ok(_) => continue,
+            Err(_) => return false,
+        }
+    }
+
+    // Then wait for things to settle down
+    let mut deadline = Instant::now() + debounce;
+
+    loop {
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(event) if affects_path(&event) => deadline = Instant::now() + debounce,
+            Ok(_) => continue,
+            Err(RecvTimeoutError::Timeout) => return true,
+            Err(RecvTimeoutError::Disconnected) => return false,
+        }
+    }
+}
+
+/// Parses the database, retrying with an exponential backoff if parsing fails (e.g. if osu! is still writing to it).
+fn reload_with_retry(
+    path: &Path,
+    retries: u32,
+    initial_delay: Duration,
+) -> Result<BeatmapListing, Error> {
+    let mut delay = initial_delay;
+    let mut attempt = 0;
+
+    loop {
+        match BeatmapListing::from_file(path) {
+            Ok(listing) => return Ok(listing),
+            Err(_) if attempt < retries => {
+                attempt += 1;
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Prints the changes between two versions of the database.
+fn print_changes(diff: &ListingDiff, on_change: OnChange) {
+    match on_change {
+        OnChange::Diff => {
+            for beatmap in &diff.added {
+                println!("+ {}", beatmap.display_name(MetadataLanguage::Romanised));
+            }
+
+            for beatmap in &diff.removed {
+                println!("- {}", beatmap.display_name(MetadataLanguage::Romanised));
+            }
+
+            for changes in &diff.changed {
+                println!(
+                    "~ {}",
+                    changes.new.display_name(MetadataLanguage::Romanised)
+                );
+
+                for field in &changes.fields {
+                    println!("    {}: {} -> {}", field.name, field.old, field.new);
+                }
+            }
+        }
+        OnChange::Summary => println!(
+            "{} added, {} removed, {} changed",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len()
+        ),
+        OnChange::Quiet => {}
+    }
+}
+
+/// Runs a command using the platform's shell.
+fn run_shell_command(command: &str) -> Result<(), Error> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+
+    let status = std::process::Command::new(shell)
+        .args([flag, command])
+        .status()?;
+
+    if !status.success() {
+        eprintln!("Command '{}' exited with {}", command, status);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates the bytes for an `osu!.db` file with no beatmaps.
+    fn empty_database() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&20250108u32.to_le_bytes()); // Version
+        data.extend_from_slice(&0u32.to_le_bytes()); // Folder count
+        data.push(0x01); // Account unlocked
+        data.extend_from_slice(&0u64.to_le_bytes()); // Unlock date
+        data.push(0x00); // Player name
+        data.extend_from_slice(&0u32.to_le_bytes()); // Beatmap count
+        data.extend_from_slice(&1u32.to_le_bytes()); // User permissions
+        data
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("osu-db-watch-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory.join("osu!.db")
+    }
+
+    #[test]
+    fn reload_retries_until_write_completes() {
+        let path = temp_file("retry");
+        let data = empty_database();
+
+        // Simulate osu! being part way through writing the file
+        std::fs::write(&path, &data[..6]).unwrap();
+
+        let writer = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(100));
+                std::fs::write(&path, &data).unwrap();
+            })
+        };
+
+        let listing = reload_with_retry(&path, 5, Duration::from_millis(50));
+        writer.join().unwrap();
+
+        assert_eq!(listing.unwrap().version, 20250108);
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn reload_gives_up_after_retries() {
+        let path = temp_file("give_up");
+        std::fs::write(&path, [0x01, 0x02]).unwrap();
+
+        assert!(reload_with_retry(&path, 2, Duration::from_millis(1)).is_err());
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn rapid_events_are_debounced() {
+        let path = PathBuf::from("/osu!/osu!.db");
+        let (tx, rx) = channel();
+
+        let event = |path: &str| {
+            Ok(
+                Event::new(EventKind::Modify(notify::event::ModifyKind::Any))
+                    .add_path(PathBuf::from(path)),
+            )
+        };
+
+        // Unrelated files shouldn't trigger a reload, but a burst of events for the database should only trigger one
+        tx.send(event("/osu!/scores.db")).unwrap();
+        for _ in 0..10 {
+            tx.send(event("/osu!/osu!.db")).unwrap();
+        }
+
+        assert!(wait_for_change(&rx, &path, Duration::from_millis(20)));
+        assert!(rx.try_recv().is_err());
+
+        drop(tx);
+        assert!(!wait_for_change(&rx, &path, Duration::from_millis(20)));
+    }
+
+    #[test]
+    fn unrelated_events_do_not_delay_reload() {
+        let path = PathBuf::from("/osu!/osu!.db");
+        let (tx, rx) = channel();
+
+        let event =
+            |kind: EventKind, path: &str| Ok(Event::new(kind).add_path(PathBuf::from(path)));
+        let modify = EventKind::Modify(notify::event::ModifyKind::Any);
+        let access = EventKind::Access(notify::event::AccessKind::Any);
+
+        tx.send(event(modify, "/osu!/osu!.db")).unwrap();
+
+        // osu! keeps touching other files (and reading the database) while it's running
+        let busy = std::thread::spawn(move || {
+            for i in 0..100 {
+                let path = if i % 2 == 0 {
+                    "/osu!/scores.db"
+                } else {
+                    "/osu!/osu!.db"
+                };
+                let kind = if i % 2 == 0 { modify } else { access };
+
+                tx.send(event(kind, path)).unwrap();
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        });
+
+        let start = Instant::now();
+        assert!(wait_for_change(&rx, &path, Duration::from_millis(50)));
+        assert!(start.elapsed() < Duration::from_millis(400));
+
+        busy.join().unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use osu_db_parser::{collections::InvalidLines, query::QueryError};
+use thiserror::Error;
+
+use crate::template::TemplateError;
+
+/// Represents an error that can occur when running a command.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("{}", .0)]
+    Database(#[from] osu_db_parser::error::Error),
+
+    #[error("Invalid query: {}", .0)]
+    Query(#[from] QueryError),
+
+    #[error("Invalid template: {}", .0)]
+    Template(#[from] TemplateError),
+
+    #[error("Collection '{}' not found", .0)]
+    CollectionNotFound(String),
+
+    #[error("Invalid collection file: {}", .0)]
+    CollectionText(#[from] InvalidLines),
+
+    #[error("No beatmaps with the song source '{}' found", .0)]
+    SourceNotFound(String),
+
+    #[error("No beatmap with MD5 hash '{}' found", .0)]
+    BeatmapNotFound(String),
+
+    #[error("{} already exists; use --force to overwrite it", .0.display())]
+    OutputExists(PathBuf),
+
+    #[error("SQLite error occurred: {}", .0)]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("Unable to save snapshot: {}", .0)]
+    SnapshotEncode(#[from] rmp_serde::encode::Error),
+
+    #[error("Unable to load snapshot: {}", .0)]
+    SnapshotDecode(#[from] rmp_serde::decode::Error),
+
+    #[error("Snapshot was saved using an unsupported format (version {}); please save it again", .0)]
+    SnapshotVersion(u32),
+
+    #[error("I/O error occurred: {}", .0)]
+    IO(#[from] std::io::Error),
+
+    #[cfg(feature = "watch")]
+    #[error("Unable to watch file: {}", .0)]
+    Watch(#[from] notify::Error),
+}
@@ -0,0 +1,78 @@
+use std::process::ExitCode;
+
+use clap::Parser;
+
+mod commands;
+mod error;
+mod template;
+
+/// Command line tools for osu!stable's database files.
+#[derive(Parser, Debug)]
+#[command(name = "osu-db", version, about)]
+struct Cli {
+    /// Logs what's being parsed and how long it takes to stderr, e.g. to attach to a bug report. RUST_LOG can be used
+    /// instead for finer control (e.g. `RUST_LOG=osu_db_parser=trace`)
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Caches each parsed osu!.db, so that running several commands on the same file only parses it once. This can also
+    /// be turned on by setting OSU_DB_CACHE=1
+    #[arg(long, global = true, env = "OSU_DB_CACHE")]
+    cache: bool,
+
+    /// Parses osu!.db without using the cache, even if it's turned on
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Number of backups kept of each database written over by a command, in an osu-db-backups directory next to it.
+    /// Use 0 to stop making backups
+    #[arg(
+        long,
+        global = true,
+        env = "OSU_DB_KEEP_BACKUPS",
+        default_value_t = osu_db_parser::backups::DEFAULT_KEEP
+    )]
+    keep_backups: usize,
+
+    #[command(subcommand)]
+    command: commands::Command,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    init_logging(cli.verbose);
+
+    let cache = commands::ParseCache::new(
+        commands::ParseCache::default_dir(),
+        cli.cache && !cli.no_cache,
+    );
+
+    match commands::run(cli.command, &cache, cli.keep_backups) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Logs to stderr if `--verbose` was given or `RUST_LOG` is set, so that normal output isn't cluttered.
+fn init_logging(verbose: bool) {
+    use std::io::IsTerminal;
+
+    use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+
+    let filter = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) => EnvFilter::new(directives),
+        Err(_) if verbose => EnvFilter::new("osu_db_parser=debug"),
+        Err(_) => return,
+    };
+
+    // Closed spans are logged with how long they took
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .init();
+}
@@ -0,0 +1,263 @@
+//! A small template engine for formatting beatmaps, e.g. `{artist} - {title} [{difficulty}] {stars_std:.2}*`.
+//!
+//! Placeholders are the names of [`Column`]s, optionally followed by a format:
+//!
+//! - `{name:.N}` shows numbers with `N` decimal places.
+//! - `{name:[year]-[month]-[day]}` formats dates using the [`time` format description] syntax.
+//!
+//! Literal braces are written as `{{` and `}}`.
+//!
+//! [`time` format description]: https://time-rs.github.io/book/api/format-description.html
+
+use osu_db_parser::{
+    columns::{Column, ColumnValue},
+    prelude::*,
+};
+use thiserror::Error;
+use time::format_description::OwnedFormatItem;
+
+/// Represents a parsed template.
+#[derive(Clone, Debug)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+/// Represents part of a template.
+#[derive(Clone, Debug)]
+enum Segment {
+    Literal(String),
+    Placeholder {
+        column: Column,
+        format: Option<Format>,
+    },
+}
+
+/// Represents the format given for a placeholder.
+#[derive(Clone, Debug)]
+enum Format {
+    /// Number of decimal places
+    Precision(usize),
+
+    /// Date format
+    DateTime(OwnedFormatItem),
+}
+
+/// Represents an error that can occur when parsing a template.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("Unknown placeholder '{{{}}}'; valid placeholders are: {}", .0, placeholder_names())]
+    UnknownPlaceholder(String),
+
+    #[error("Placeholder starting at position {} is never closed", .0)]
+    Unclosed(usize),
+
+    #[error("Unmatched '}}' at position {}; use '}}}}' for a literal '}}'", .0)]
+    UnmatchedBrace(usize),
+
+    #[error("Invalid format '{}' for placeholder '{{{}}}'", .1, .0)]
+    InvalidFormat(String, String),
+}
+
+/// Lists the names of every placeholder that can be used in a template.
+fn placeholder_names() -> String {
+    Column::ALL
+        .iter()
+        .map(|c| c.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Template {
+    /// Parses a template.
+    pub fn parse(template: &str) -> Result<Template, TemplateError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|(_, c)| *c == '{').is_some() => literal.push('{'),
+                '}' if chars.next_if(|(_, c)| *c == '}').is_some() => literal.push('}'),
+                '}' => return Err(TemplateError::UnmatchedBrace(i)),
+                '{' => {
+                    let end = template[i..]
+                        .find('}')
+                        .map(|offset| i + offset)
+                        .ok_or(TemplateError::Unclosed(i))?;
+
+                    // Skip over the rest of the placeholder
+                    while chars.next_if(|(j, _)| *j <= end).is_some() {}
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+
+                    segments.push(parse_placeholder(&template[i + 1..end])?);
+                }
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Template { segments })
+    }
+
+    /// Renders the template for a beatmap.
+    pub fn render(&self, beatmap: &BeatmapEntry) -> String {
+        let mut output = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => output.push_str(text),
+                Segment::Placeholder { column, format } => {
+                    let value = column.value(beatmap);
+
+                    let text = match (format, &value) {
+                        (Some(Format::Precision(precision)), value) => {
+                            format!("{:.*}", precision, value)
+                        }
+                        (Some(Format::DateTime(format)), ColumnValue::DateTime(datetime)) => {
+                            datetime.format(format).unwrap_or_default()
+                        }
+                        _ => value.to_string(),
+                    };
+
+                    output.push_str(&text);
+                }
+            }
+        }
+
+        output
+    }
+}
+
+/// Parses the contents of a placeholder, i.e. `name` or `name:format`.
+fn parse_placeholder(placeholder: &str) -> Result<Segment, TemplateError> {
+    let (name, format) = match placeholder.split_once(':') {
+        Some((name, format)) => (name, Some(format)),
+        None => (placeholder, None),
+    };
+
+    let column = name
+        .parse::<Column>()
+        .map_err(|_| TemplateError::UnknownPlaceholder(name.to_string()))?;
+
+    let invalid_format =
+        || TemplateError::InvalidFormat(name.to_string(), format.unwrap_or_default().to_string());
+
+    let format = match format {
+        None => None,
+        Some(format) if format.starts_with('.') => Some(Format::Precision(
+            format[1..].parse().map_err(|_| invalid_format())?,
+        )),
+        Some(format) => Some(Format::DateTime(
+            time::format_description::parse_owned::<2>(format).map_err(|_| invalid_format())?,
+        )),
+    };
+
+    Ok(Segment::Placeholder { column, format })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn beatmap() -> BeatmapEntry {
+        BeatmapEntry {
+            artist_name: Some("Camellia".to_string()),
+            song_title: Some("Exit This Earth's Atomosphere".to_string()),
+            difficulty: Some("Evolution".to_string()),
+            md5: Some("0123456789abcdef0123456789abcdef".to_string()),
+            beatmap_id: 1032103,
+            difficulty_id: 2160283,
+            star_ratings_std: Some(vec![StarRating {
+                mods: Mods::none(),
+                rating: 7.16342,
+            }]),
+            total_time: 283_000,
+            approach_rate: 9.6,
+            last_modification_time: datetime!(2023-11-02 18:45:00 UTC),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn templates_are_rendered_correctly() {
+        let template =
+            Template::parse("{artist} - {title} [{difficulty}] {stars_std:.2}* ({length}, AR{ar})")
+                .unwrap();
+
+        let beatmaps = [
+            beatmap(),
+            BeatmapEntry {
+                artist_name: Some("Unknown".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let output = beatmaps
+            .iter()
+            .map(|b| template.render(b))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // Missing values are left blank
+        assert_eq!(
+            output,
+            "Camellia - Exit This Earth's Atomosphere [Evolution] 7.16* (4:43, AR9.6)\n\
+             Unknown -  [] * (0:00, AR0)"
+        );
+    }
+
+    #[test]
+    fn links_and_dates_can_be_rendered() {
+        let template = Template::parse(
+            "{md5} {url} {last_modified:[year]/[month]/[day]} {last_played:[year]}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            template.render(&beatmap()),
+            "0123456789abcdef0123456789abcdef https://osu.ppy.sh/beatmapsets/1032103#osu/2160283 2023/11/02 "
+        );
+    }
+
+    #[test]
+    fn braces_can_be_escaped() {
+        let template = Template::parse("{{{title}}} }}{{").unwrap();
+        let beatmap = BeatmapEntry {
+            song_title: Some("Title".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(template.render(&beatmap), "{Title} }{");
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        let error = Template::parse("{artist} {song}").unwrap_err();
+        assert_eq!(error, TemplateError::UnknownPlaceholder("song".to_string()));
+        assert!(error.to_string().contains("artist, artist_unicode, title"));
+
+        assert_eq!(
+            Template::parse("{artist").unwrap_err(),
+            TemplateError::Unclosed(0)
+        );
+        assert_eq!(
+            Template::parse("artist}").unwrap_err(),
+            TemplateError::UnmatchedBrace(6)
+        );
+        assert_eq!(
+            Template::parse("{stars:.x}").unwrap_err(),
+            TemplateError::InvalidFormat("stars".to_string(), ".x".to_string())
+        );
+        assert_eq!(
+            Template::parse("{last_played:[nonsense]}").unwrap_err(),
+            TemplateError::InvalidFormat("last_played".to_string(), "[nonsense]".to_string())
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Times parsing, encoding and reading the IDs of a large generated library (see
+//! [`synthetic::SyntheticOptions::benchmark`]).
+//!
+//! ```text
+//! cargo bench -p osu-db-parser --features synthetic
+//! ```
+//!
+//! This doesn't use a benchmarking framework, so each step is timed a few times and the fastest time is reported.
+
+use std::time::{Duration, Instant};
+
+use osu_db_parser::{
+    beatmaps::{BeatmapListing, ParseOptions},
+    synthetic::{self, SyntheticOptions},
+};
+
+/// Number of times each step is run.
+const RUNS: usize = 5;
+
+/// Runs `f` several times, returning the fastest time.
+fn fastest<T>(mut f: impl FnMut() -> T) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() {
+    let options = SyntheticOptions::benchmark();
+    let listing = synthetic::listing(&options);
+    let data = listing.to_bytes();
+
+    println!(
+        "{} beatmaps ({:.1} MB)",
+        listing.beatmaps.len(),
+        data.len() as f64 / 1_000_000.0
+    );
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let steps: [(String, Box<dyn Fn()>); 5] = [
+        (
+            "generate".to_string(),
+            Box::new(|| drop(synthetic::listing(&options))),
+        ),
+        (
+            "parse".to_string(),
+            Box::new(|| drop(BeatmapListing::from_bytes(&data).unwrap())),
+        ),
+        (
+            format!("parse ({} threads)", threads),
+            Box::new(|| {
+                let options = ParseOptions {
+                    threads,
+                    ..Default::default()
+                };
+                drop(BeatmapListing::from_bytes_with_options(&data, options, |_, _| true).unwrap());
+            }),
+        ),
+        (
+            "id map".to_string(),
+            Box::new(|| drop(BeatmapListing::parse_id_map(&data).unwrap())),
+        ),
+        ("encode".to_string(), Box::new(|| drop(listing.to_bytes()))),
+    ];
+
+    for (name, step) in &steps {
+        println!("{:<24}{:>10.1?}", name, fastest(step));
+    }
+}
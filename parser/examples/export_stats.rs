@@ -0,0 +1,102 @@
+//! Prints a summary of the beatmaps in an `osu!.db` file, and exports the hardest beatmaps (by NoMod star rating) to a
+//! CSV file.
+//!
+//! ```text
+//! cargo run --example export_stats -- <osu!.db> <output.csv>
+//! ```
+
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use osu_db_parser::{
+    export::{self, Format},
+    prelude::*,
+    stats::LibraryStats,
+};
+
+/// Number of beatmaps exported.
+const HARDEST: usize = 100;
+
+/// Number of mappers included in the summary.
+const TOP_MAPPERS: usize = 5;
+
+/// Columns exported for each beatmap.
+const COLUMNS: [Column; 8] = [
+    Column::Artist,
+    Column::Title,
+    Column::Difficulty,
+    Column::Creator,
+    Column::Mode,
+    Column::Stars,
+    Column::Bpm,
+    Column::Length,
+];
+
+/// Summarises the listing at `input`, and writes its hardest beatmaps to `output`, hardest first. Returns the summary
+/// and the number of beatmaps written.
+pub fn export_stats(input: &Path, output: &Path) -> Result<(LibraryStats, usize), Error> {
+    let listing = BeatmapListing::from_file(input)?;
+    let stats = LibraryStats::from_beatmaps(&listing.beatmaps, TOP_MAPPERS);
+
+    // Beatmaps without a star rating are sorted last, so they're only exported if there aren't enough others
+    let mut indices = (0..listing.beatmaps.len()).collect::<Vec<_>>();
+    let key = SortKey {
+        order: SortOrder::Descending,
+        ..SortKey::new(Column::Stars)
+    };
+    key.sort_indices(&listing.beatmaps, &mut indices);
+    indices.truncate(HARDEST);
+
+    let hardest = indices
+        .iter()
+        .map(|&i| &listing.beatmaps[i])
+        .collect::<Vec<_>>();
+
+    export::write(
+        BufWriter::new(File::create(output)?),
+        Format::Csv,
+        &COLUMNS,
+        MetadataLanguage::default(),
+        &hardest,
+        |_, _| true,
+    )?;
+
+    Ok((stats, hardest.len()))
+}
+
+fn main() -> Result<(), Error> {
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+
+    let [input, output] = args.as_slice() else {
+        eprintln!("Usage: export_stats <osu!.db> <output.csv>");
+        std::process::exit(2);
+    };
+
+    let output = PathBuf::from(output);
+    let (stats, exported) = export_stats(Path::new(input), &output)?;
+
+    println!(
+        "{} beatmaps, {:.1}% unplayed",
+        stats.total,
+        stats.unplayed_percentage()
+    );
+
+    for (mode, count) in &stats.modes {
+        println!("  {}: {}", mode, count);
+    }
+
+    for (creator, count) in &stats.top_mappers {
+        println!("  {} mapped {}", creator, count);
+    }
+
+    println!(
+        "Exported the {} hardest beatmaps to {}",
+        exported,
+        output.display()
+    );
+
+    Ok(())
+}
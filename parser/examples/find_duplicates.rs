@@ -0,0 +1,91 @@
+//! Finds beatmaps which are listed more than once in an `osu!.db` file, then writes a copy of the file with only the
+//! first entry for each beatmap.
+//!
+//! ```text
+//! cargo run --example find_duplicates -- <osu!.db> [output]
+//! ```
+//!
+//! The copy is written to the temporary folder unless an output path is given, so that the original file is never
+//! replaced.
+
+use std::path::{Path, PathBuf};
+
+use osu_db_parser::{health, prelude::*};
+
+/// Represents what was found and removed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DuplicateReport {
+    /// Groups of beatmaps which share an MD5 hash, naming each one once
+    pub duplicates: Vec<(String, usize)>,
+
+    /// Number of entries removed
+    pub removed: usize,
+
+    /// Number of beatmaps in the cleaned copy
+    pub remaining: usize,
+}
+
+/// Reads the listing at `input`, and writes a copy without the duplicate entries to `output`.
+pub fn find_duplicates(input: &Path, output: &Path) -> Result<DuplicateReport, Error> {
+    let mut listing = BeatmapListing::from_file(input)?;
+    let groups = health::duplicates(&listing.beatmaps);
+
+    let duplicates = groups
+        .iter()
+        .map(|group| {
+            let beatmap = &listing.beatmaps[group.indices[0]];
+            let name = format!(
+                "{} - {} [{}]",
+                beatmap.artist_name.as_deref().unwrap_or_default(),
+                beatmap.song_title.as_deref().unwrap_or_default(),
+                beatmap.difficulty.as_deref().unwrap_or_default()
+            );
+
+            (name, group.indices.len())
+        })
+        .collect();
+
+    // The first entry for each hash is kept, as osu! would find that one first
+    let removed = groups
+        .iter()
+        .flat_map(|group| group.indices[1..].iter().copied())
+        .collect::<Vec<_>>();
+
+    listing.retain(|i, _| !removed.contains(&i));
+    listing.to_file(output)?;
+
+    Ok(DuplicateReport {
+        duplicates,
+        removed: removed.len(),
+        remaining: listing.beatmaps.len(),
+    })
+}
+
+fn main() -> Result<(), Error> {
+    let mut args = std::env::args_os().skip(1);
+
+    let Some(input) = args.next().map(PathBuf::from) else {
+        eprintln!("Usage: find_duplicates <osu!.db> [output]");
+        std::process::exit(2);
+    };
+
+    let output = args
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("osu!.deduplicated.db"));
+
+    let report = find_duplicates(&input, &output)?;
+
+    for (name, count) in &report.duplicates {
+        println!("{} is listed {} times", name, count);
+    }
+
+    println!(
+        "Removed {} duplicate entries, leaving {} beatmaps in {}",
+        report.removed,
+        report.remaining,
+        output.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Makes a collection of the beatmaps matching a search query, using the same syntax as the search box in osu! (e.g.
+//! `stars>5 mode=mania`), and saves it to a `collection.db` file.
+//!
+//! ```text
+//! cargo run --example make_collection -- <osu!.db> <query> <name> <collection.db>
+//! ```
+//!
+//! If the `collection.db` file already exists, the collection is added to it, replacing any collection with the same
+//! name.
+
+use std::path::{Path, PathBuf};
+
+use osu_db_parser::prelude::*;
+
+/// Version written to new `collection.db` files.
+const COLLECTION_VERSION: u32 = 20150203;
+
+/// Adds a collection named `name` with the beatmaps in the listing at `input` which match `query`, to the collections
+/// at `output`. Returns the number of beatmaps in the collection.
+pub fn make_collection(
+    input: &Path,
+    query: &str,
+    name: &str,
+    output: &Path,
+) -> Result<usize, Error> {
+    let listing = BeatmapListing::from_file(input)?;
+    let filter = BeatmapFilter::parse(query)?;
+
+    let collection = Collection::from_beatmaps(
+        name.to_string(),
+        listing
+            .beatmaps
+            .iter()
+            .filter(|beatmap| filter.matches(beatmap)),
+    );
+    let count = collection.beatmap_md5s.len();
+
+    let mut collections = if output.exists() {
+        CollectionListing::from_file(output)?
+    } else {
+        CollectionListing {
+            version: COLLECTION_VERSION,
+            collections: Vec::new(),
+        }
+    };
+
+    collections
+        .collections
+        .retain(|existing| existing.name.as_deref() != Some(name));
+    collections.collections.push(collection);
+    collections.to_file(output)?;
+
+    Ok(count)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let [input, query, name, output] = args.as_slice() else {
+        eprintln!("Usage: make_collection <osu!.db> <query> <name> <collection.db>");
+        std::process::exit(2);
+    };
+
+    let output = PathBuf::from(output);
+    let count = make_collection(Path::new(input), query, name, &output)?;
+
+    println!(
+        "Saved '{}' with {} beatmaps to {}",
+        name,
+        count,
+        output.display()
+    );
+
+    Ok(())
+}
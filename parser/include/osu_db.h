@@ -0,0 +1,154 @@
+#ifndef OSU_DB_H
+#define OSU_DB_H
+
+/* This file is generated by cbindgen from src/ffi.rs. Don't edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Represents the string fields of a beatmap, which can be read with [`osudb_beatmap_string`].
+typedef enum OsuDbField {
+  OSU_DB_FIELD_ARTIST = 0,
+  OSU_DB_FIELD_ARTIST_UNICODE = 1,
+  OSU_DB_FIELD_TITLE = 2,
+  OSU_DB_FIELD_TITLE_UNICODE = 3,
+  OSU_DB_FIELD_CREATOR = 4,
+  OSU_DB_FIELD_DIFFICULTY = 5,
+  OSU_DB_FIELD_SOURCE = 6,
+  OSU_DB_FIELD_TAGS = 7,
+  OSU_DB_FIELD_MD5 = 8,
+  OSU_DB_FIELD_AUDIO_FILENAME = 9,
+  OSU_DB_FIELD_BEATMAP_FILENAME = 10,
+  // Name of the beatmap's folder, relative to the `Songs` folder
+  OSU_DB_FIELD_FOLDER_NAME = 11,
+} OsuDbField;
+
+// Represents the result of a function.
+typedef enum OsuDbStatus {
+  OSU_DB_STATUS_OK = 0,
+  // A required pointer was NULL
+  OSU_DB_STATUS_NULL_ARGUMENT = 1,
+  // The data isn't a valid `osu!.db` file
+  OSU_DB_STATUS_INVALID_DATA = 2,
+  // The beatmap index is past the end of the listing, or the field isn't one of [`OsuDbField`]
+  OSU_DB_STATUS_OUT_OF_RANGE = 3,
+  // Something unexpected went wrong inside the library
+  OSU_DB_STATUS_INTERNAL_ERROR = 4,
+} OsuDbStatus;
+
+// Represents a parsed `osu!.db` file.
+typedef struct OsuDbHandle OsuDbHandle;
+
+// The numeric fields of a beatmap, which can be read with [`osudb_beatmap_info`].
+typedef struct OsuDbBeatmapInfo {
+  uint32_t beatmapset_id;
+  uint32_t difficulty_id;
+  // Ranked status, using the values from the file (e.g. 4 for ranked, 7 for loved)
+  uint8_t ranked_status;
+  // Gameplay mode (0 for osu!standard, 1 for osu!taiko, 2 for osu!catch and 3 for osu!mania)
+  uint8_t gameplay_mode;
+  uint16_t hitcircle_count;
+  uint16_t slider_count;
+  uint16_t spinner_count;
+  float approach_rate;
+  float circle_size;
+  float hp_drain;
+  float overall_difficulty;
+  double slider_velocity;
+  // NoMod star rating in the beatmap's own gameplay mode, or NaN if it hasn't been calculated
+  double star_rating;
+  // Drain time, in seconds
+  uint32_t drain_time;
+  // Total time, in milliseconds
+  uint32_t total_time;
+  // Time that the audio preview starts, in milliseconds
+  uint32_t audio_preview_time;
+  // When the beatmap was last modified, as a Unix timestamp in seconds, or 0 if it's unknown
+  int64_t last_modified;
+  // When the beatmap was last played, as a Unix timestamp in seconds, or 0 if it's unknown
+  int64_t last_played;
+  bool is_unplayed;
+} OsuDbBeatmapInfo;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses the contents of an `osu!.db` file.
+//
+// On success, `*out` is set to a handle which must be freed with [`osudb_free`]. Otherwise, `*out` is set to NULL
+// and the reason can be read with [`osudb_last_error_message`].
+//
+// # Safety
+//
+// `data` must point to `len` readable bytes, and `out` must be a valid pointer to write the handle to.
+enum OsuDbStatus osudb_parse(const uint8_t *data,
+                             size_t len,
+                             struct OsuDbHandle **out);
+
+// Frees a handle from [`osudb_parse`], including the strings returned by its accessors. Does nothing if `handle` is
+// NULL.
+//
+// # Safety
+//
+// `handle` must be NULL or a handle from [`osudb_parse`] which hasn't already been freed.
+void osudb_free(struct OsuDbHandle *handle);
+
+// Gets a description of the most recent error on this thread, or NULL if there hasn't been one.
+//
+// The message is owned by the library, and is valid until the next error on this thread.
+const char *osudb_last_error_message(void);
+
+// Gets the version of the file (e.g. 20211031), or 0 if `handle` is NULL.
+//
+// # Safety
+//
+// `handle` must be NULL or a valid handle.
+uint32_t osudb_version(const struct OsuDbHandle *handle);
+
+// Gets the name of the player who owns the file, or NULL if it isn't set or `handle` is NULL.
+//
+// The string is owned by the handle.
+//
+// # Safety
+//
+// `handle` must be NULL or a valid handle.
+const char *osudb_player_name(const struct OsuDbHandle *handle);
+
+// Gets the number of beatmaps in the file, or 0 if `handle` is NULL.
+//
+// # Safety
+//
+// `handle` must be NULL or a valid handle.
+size_t osudb_beatmap_count(const struct OsuDbHandle *handle);
+
+// Reads the numeric fields of the beatmap at `index` into `*out`.
+//
+// # Safety
+//
+// `handle` must be NULL or a valid handle, and `out` must be NULL or a valid pointer to write to.
+enum OsuDbStatus osudb_beatmap_info(const struct OsuDbHandle *handle,
+                                    size_t index,
+                                    struct OsuDbBeatmapInfo *out);
+
+// Reads a string field (one of [`OsuDbField`]) of the beatmap at `index`, setting `*out` to the string, or to NULL
+// if the beatmap doesn't have a value for the field.
+//
+// The string is owned by the handle.
+//
+// # Safety
+//
+// `handle` must be NULL or a valid handle, and `out` must be NULL or a valid pointer to write to.
+enum OsuDbStatus osudb_beatmap_string(const struct OsuDbHandle *handle,
+                                      size_t index,
+                                      uint32_t field,
+                                      const char **out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* OSU_DB_H */
@@ -0,0 +1,225 @@
+//! Approximating when beatmaps were added to the library.
+//!
+//! `osu!.db` doesn't store when a beatmap was added, but there are two ways of estimating it:
+//!
+//! - The beatmap's folder in the `Songs` folder, which is created when the beatmap is imported. Its creation time isn't
+//!   available on every file system, so the earliest modification time of the files directly inside it is used too.
+//! - The last time the beatmap's `.osu` file was modified, as stored in `osu!.db`. This is also updated when osu!
+//!   downloads an update, so it's only used when the folder can't be found.
+//!
+//! Several difficulties usually share a folder, so the folder times are cached in [`AddedDates`].
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use time::OffsetDateTime;
+
+use crate::{beatmaps::BeatmapEntry, format};
+
+/// Remembers when the beatmap folders in a `Songs` folder were added, so that each folder is only looked at once.
+///
+/// Folders which don't exist (or can't be read) are remembered as well, and fall back to the times stored in
+/// `osu!.db`.
+#[derive(Clone, Debug, Default)]
+pub struct AddedDates {
+    songs_dir: Option<PathBuf>,
+
+    /// Earliest time found for each folder, by folder name
+    folders: HashMap<String, Option<OffsetDateTime>>,
+}
+
+impl AddedDates {
+    /// Creates an empty cache for the beatmap folders in `songs_dir`. Without a `Songs` folder, only the times stored
+    /// in `osu!.db` are used.
+    pub fn new(songs_dir: Option<&Path>) -> AddedDates {
+        AddedDates {
+            songs_dir: songs_dir.map(Path::to_path_buf),
+            folders: HashMap::new(),
+        }
+    }
+
+    /// Approximates when a beatmap was added, preferring the time its folder was created (see the
+    /// [module documentation](self)). Returns `None` if neither time is known.
+    pub fn added_date(&mut self, beatmap: &BeatmapEntry) -> Option<OffsetDateTime> {
+        self.folder_date(beatmap)
+            .or_else(|| format::known(beatmap.last_modification_time))
+    }
+
+    /// Gets the number of folders that have been looked at.
+    pub fn len(&self) -> usize {
+        self.folders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.folders.is_empty()
+    }
+
+    /// Forgets every folder, e.g. after beatmaps have been imported or deleted.
+    pub fn clear(&mut self) {
+        self.folders.clear();
+    }
+
+    fn folder_date(&mut self, beatmap: &BeatmapEntry) -> Option<OffsetDateTime> {
+        let songs_dir = self.songs_dir.as_deref()?;
+        let folder_name = beatmap.folder_name.as_deref().filter(|f| !f.is_empty())?;
+
+        if let Some(&date) = self.folders.get(folder_name) {
+            return date;
+        }
+
+        let date = earliest_time(&songs_dir.join(folder_name));
+        self.folders.insert(folder_name.to_string(), date);
+        date
+    }
+}
+
+/// Finds the earliest of a folder's creation time and the modification times of the files and folders directly inside
+/// it. Returns `None` if the folder doesn't exist or none of the times are available.
+fn earliest_time(folder: &Path) -> Option<OffsetDateTime> {
+    let metadata = fs::metadata(folder).ok().filter(|m| m.is_dir())?;
+    let entries = fs::read_dir(folder).ok()?;
+
+    let modified = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter_map(|metadata| metadata.modified().ok());
+
+    metadata
+        .created()
+        .ok()
+        .into_iter()
+        .chain(modified)
+        .min()
+        .map(OffsetDateTime::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        time::{Duration as StdDuration, SystemTime},
+    };
+
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::{
+        beatmaps::{BeatmapListing, UserPermissions},
+        common::WINDOWS_EPOCH,
+    };
+
+    const DB_TIME: OffsetDateTime = datetime!(2024-06-01 12:00 UTC);
+
+    fn beatmap(folder: &str, modified: OffsetDateTime) -> BeatmapEntry {
+        BeatmapEntry {
+            folder_name: Some(folder.to_string()),
+            last_modification_time: modified,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a beatmap folder containing a file modified `days` before now.
+    fn folder(songs: &Path, name: &str, days: u64) -> SystemTime {
+        let modified = SystemTime::now() - StdDuration::from_secs(days * 86400);
+        let path = songs.join(name);
+
+        fs::create_dir_all(&path).unwrap();
+        File::create(path.join("audio.mp3"))
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        modified
+    }
+
+    fn songs_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "osu-db-parser-added-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    #[test]
+    fn folder_times_are_preferred_and_cached() {
+        let songs = songs_dir("dates");
+        let old = folder(&songs, "1 Old - Song", 400);
+
+        let mut dates = AddedDates::new(Some(&songs));
+        let difficulties = [
+            beatmap("1 Old - Song", DB_TIME),
+            beatmap("1 Old - Song", DB_TIME),
+        ];
+
+        for difficulty in &difficulties {
+            assert_eq!(
+                dates.added_date(difficulty),
+                Some(OffsetDateTime::from(old))
+            );
+        }
+
+        assert_eq!(dates.len(), 1);
+
+        // Missing folders fall back to the time in osu!.db, and are remembered too
+        assert_eq!(
+            dates.added_date(&beatmap("2 Deleted", DB_TIME)),
+            Some(DB_TIME)
+        );
+        assert_eq!(dates.len(), 2);
+
+        // Without a Songs folder only the time in osu!.db is used, and without either time there's nothing to go on
+        assert_eq!(
+            AddedDates::new(None).added_date(&beatmap("1 Old - Song", DB_TIME)),
+            Some(DB_TIME)
+        );
+        assert_eq!(dates.added_date(&BeatmapEntry::default()), None);
+
+        fs::remove_dir_all(&songs).ok();
+    }
+
+    #[test]
+    fn recently_added_beatmaps_are_newest_first() {
+        let songs = songs_dir("recent");
+        folder(&songs, "1 Old - Song", 400);
+        let new = folder(&songs, "2 New - Song", 2);
+
+        // osu!.db says the old beatmap was modified recently, e.g. because it was updated
+        let listing = BeatmapListing {
+            version: 20250108,
+            beatmaps: vec![
+                beatmap("1 Old - Song", OffsetDateTime::now_utc()),
+                beatmap("2 New - Song", DB_TIME),
+                beatmap(
+                    "3 Missing",
+                    OffsetDateTime::now_utc() - time::Duration::days(1),
+                ),
+                beatmap("4 Missing", DB_TIME),
+            ],
+            folder_count: 4,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            user_permissions: UserPermissions::Normal.into(),
+        };
+
+        let since = OffsetDateTime::now_utc() - time::Duration::days(7);
+        let recent = listing
+            .recently_added(since, Some(&songs))
+            .into_iter()
+            .map(|(beatmap, date)| (beatmap.folder_name.as_deref().unwrap(), date))
+            .collect::<Vec<_>>();
+
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].0, "3 Missing");
+        assert_eq!(recent[1], ("2 New - Song", OffsetDateTime::from(new)));
+
+        assert_eq!(listing.recently_added(since, None).len(), 2);
+
+        fs::remove_dir_all(&songs).ok();
+    }
+}
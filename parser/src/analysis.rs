@@ -0,0 +1,1008 @@
+//! Analysis of a beatmap library, e.g. when beatmaps were played and scores were set for an activity heatmap, which
+//! beatmaps are similar to one the player likes, which beatmaps came from the same pack, or how star ratings changed
+//! after osu! recalculated them.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use flagset::FlagSet;
+use time::{Date, OffsetDateTime, UtcOffset};
+
+use crate::{
+    beatmaps::{BeatmapEntry, BeatmapListing},
+    collections::Collection,
+    common::{GameplayMode, Mods},
+    difficulty,
+    format::TimeZone,
+    scores::ScoreListing,
+};
+
+/// Dates before osu! was released are placeholders, such as the Windows epoch used for dates that were never set.
+const FIRST_YEAR: i32 = 2007;
+
+/// Counts the dates falling on each calendar day in the timezone `tz`.
+///
+/// Unset dates and dates in the future are skipped, since they can't be plotted meaningfully. Days without any dates
+/// aren't included.
+pub fn activity_heatmap(
+    dates: impl Iterator<Item = OffsetDateTime>,
+    tz: UtcOffset,
+) -> HashMap<Date, u32> {
+    activity_heatmap_until(dates, tz, OffsetDateTime::now_utc())
+}
+
+/// Like [`activity_heatmap`], but skipping dates after `now` rather than the current time.
+pub fn activity_heatmap_until(
+    dates: impl Iterator<Item = OffsetDateTime>,
+    tz: UtcOffset,
+    now: OffsetDateTime,
+) -> HashMap<Date, u32> {
+    let mut heatmap = HashMap::new();
+
+    for date in dates {
+        if date.year() < FIRST_YEAR || date > now {
+            continue;
+        }
+
+        *heatmap.entry(date.to_offset(tz).date()).or_default() += 1;
+    }
+
+    heatmap
+}
+
+/// Counts the beatmaps last played on each day in the timezone `tz` (see [`activity_heatmap`]). Unplayed beatmaps
+/// aren't counted.
+///
+/// Play dates are stored as local time, so they're taken to be in `tz` rather than moved to it, and each beatmap is
+/// counted on the day osu! showed when it was played.
+pub fn beatmap_activity(listing: &BeatmapListing, tz: UtcOffset) -> HashMap<Date, u32> {
+    let zone = TimeZone::fixed(tz);
+    let dates = listing
+        .beatmaps
+        .iter()
+        .filter(|beatmap| !beatmap.is_unplayed)
+        .filter_map(|beatmap| zone.zoned(beatmap.last_played, BeatmapEntry::LAST_PLAYED_KIND));
+
+    activity_heatmap(dates, tz)
+}
+
+/// Counts the scores set on each day in the timezone `tz` (see [`activity_heatmap`]).
+pub fn score_activity(listing: &ScoreListing, tz: UtcOffset) -> HashMap<Date, u32> {
+    let dates = listing
+        .beatmap_scores
+        .iter()
+        .flat_map(|beatmap| &beatmap.scores)
+        .map(|score| score.timestamp);
+
+    activity_heatmap(dates, tz)
+}
+
+/// Totals a heatmap for each month, keyed by year and month number (1 to 12).
+pub fn monthly_totals(heatmap: &HashMap<Date, u32>) -> BTreeMap<(i32, u8), u32> {
+    let mut totals = BTreeMap::new();
+
+    for (date, count) in heatmap {
+        *totals.entry((date.year(), date.month() as u8)).or_default() += count;
+    }
+
+    totals
+}
+
+/// Options for finding similar beatmaps with [`similar_maps_with`].
+///
+/// Each weight is how much that kind of similarity counts towards a beatmap's score, relative to the others. The
+/// tolerances are the largest differences that still count as similar.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SimilarityOptions {
+    /// Weight for having the same mapper
+    pub mapper: f32,
+
+    /// Weight for sharing tags, which is scaled by the proportion of tags in common
+    pub tags: f32,
+
+    /// Weight for having a similar nomod star rating
+    pub stars: f32,
+
+    /// Weight for having a similar main BPM
+    pub bpm: f32,
+
+    /// Weight for having a similar length
+    pub length: f32,
+
+    /// Weight for being for the same gameplay mode
+    pub mode: f32,
+
+    /// Largest difference in nomod star rating that counts as similar
+    pub star_tolerance: f64,
+
+    /// Largest difference in main BPM that counts as similar
+    pub bpm_tolerance: f64,
+
+    /// Largest difference in length that counts as similar, as a proportion of the longer beatmap (e.g. `0.2` for 20%)
+    pub length_tolerance: f64,
+
+    /// Whether to suggest other difficulties from the same beatmapset
+    pub include_same_set: bool,
+}
+
+impl Default for SimilarityOptions {
+    fn default() -> Self {
+        Self {
+            mapper: 2.0,
+            tags: 1.5,
+            stars: 1.5,
+            bpm: 1.0,
+            length: 0.5,
+            mode: 1.0,
+            star_tolerance: 0.3,
+            bpm_tolerance: 10.0,
+            length_tolerance: 0.2,
+            include_same_set: false,
+        }
+    }
+}
+
+/// The parts of a beatmap that are compared when finding similar beatmaps, worked out once per beatmap.
+struct Traits {
+    mapper: Option<String>,
+    tags: HashSet<String>,
+    stars: Option<f64>,
+    bpm: Option<f64>,
+    length: u32,
+    mode: GameplayMode,
+}
+
+impl Traits {
+    fn new(beatmap: &BeatmapEntry) -> Self {
+        Self {
+            mapper: beatmap
+                .creator_name
+                .as_deref()
+                .filter(|mapper| !mapper.is_empty())
+                .map(str::to_lowercase),
+            tags: beatmap
+                .song_tags
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_lowercase)
+                .collect(),
+            stars: beatmap.star_rating(beatmap.gameplay_mode, Mods::none()),
+            bpm: beatmap.main_bpm(),
+            length: beatmap.total_time,
+            mode: beatmap.gameplay_mode,
+        }
+    }
+
+    /// Scores how similar two beatmaps are, from 0 (nothing in common) to 1 (similar in every way).
+    fn similarity(&self, other: &Traits, options: &SimilarityOptions) -> f32 {
+        let total_weight = options.mapper
+            + options.tags
+            + options.stars
+            + options.bpm
+            + options.length
+            + options.mode;
+
+        if total_weight <= 0.0 {
+            return 0.0;
+        }
+
+        let mut score = 0.0;
+
+        if self.mapper.is_some() && self.mapper == other.mapper {
+            score += options.mapper;
+        }
+
+        let shared_tags = self.tags.intersection(&other.tags).count();
+        if shared_tags > 0 {
+            let all_tags = self.tags.len() + other.tags.len() - shared_tags;
+            score += options.tags * shared_tags as f32 / all_tags as f32;
+        }
+
+        if let (Some(a), Some(b)) = (self.stars, other.stars) {
+            score += options.stars * closeness((a - b).abs(), options.star_tolerance);
+        }
+
+        if let (Some(a), Some(b)) = (self.bpm, other.bpm) {
+            score += options.bpm * closeness((a - b).abs(), options.bpm_tolerance);
+        }
+
+        let longest = self.length.max(other.length);
+        if longest > 0 {
+            let difference = self.length.abs_diff(other.length) as f64 / longest as f64;
+            score += options.length * closeness(difference, options.length_tolerance);
+        }
+
+        if self.mode == other.mode {
+            score += options.mode;
+        }
+
+        score / total_weight
+    }
+}
+
+/// Scores how close two values are from their difference: 1 when they're the same, falling to 0.5 at the tolerance,
+/// and 0 beyond it.
+fn closeness(difference: f64, tolerance: f64) -> f32 {
+    if difference > tolerance {
+        0.0
+    } else if tolerance <= 0.0 {
+        1.0
+    } else {
+        (1.0 - difference / (2.0 * tolerance)) as f32
+    }
+}
+
+/// Checks whether two beatmaps are difficulties of the same beatmapset. Unsubmitted beatmaps are compared by their
+/// folder instead.
+fn same_set(a: &BeatmapEntry, b: &BeatmapEntry) -> bool {
+    match (a.beatmapset_id(), b.beatmapset_id()) {
+        (Some(a), Some(b)) => a == b,
+        _ => match (a.folder_name.as_deref(), b.folder_name.as_deref()) {
+            (Some(a), Some(b)) => !a.is_empty() && a.eq_ignore_ascii_case(b),
+            _ => false,
+        },
+    }
+}
+
+/// Suggests up to `limit` beatmaps in a listing that are similar to `beatmap`, using the default options (see
+/// [`similar_maps_with`]).
+pub fn similar_maps<'a>(
+    listing: &'a BeatmapListing,
+    beatmap: &BeatmapEntry,
+    limit: usize,
+) -> Vec<(&'a BeatmapEntry, f32)> {
+    similar_maps_with(listing, beatmap, limit, &SimilarityOptions::default())
+}
+
+/// Suggests up to `limit` beatmaps in a listing that are similar to `beatmap`, along with their scores from 0 to 1.
+///
+/// Beatmaps are scored on having the same mapper, shared tags, and a similar star rating, BPM, length and gameplay
+/// mode, weighted by `options`. The best matches come first, with ties ordered by beatmapset ID and then difficulty
+/// ID. The beatmap itself (matched by its MD5 hash) is never suggested, nor are beatmaps with nothing in common.
+pub fn similar_maps_with<'a>(
+    listing: &'a BeatmapListing,
+    beatmap: &BeatmapEntry,
+    limit: usize,
+    options: &SimilarityOptions,
+) -> Vec<(&'a BeatmapEntry, f32)> {
+    similar_map_indices(listing, beatmap, limit, options)
+        .into_iter()
+        .map(|(i, score)| (&listing.beatmaps[i], score))
+        .collect()
+}
+
+/// Like [`similar_maps_with`], but returning the indices of the beatmaps in the listing.
+pub fn similar_map_indices(
+    listing: &BeatmapListing,
+    beatmap: &BeatmapEntry,
+    limit: usize,
+    options: &SimilarityOptions,
+) -> Vec<(usize, f32)> {
+    let traits = Traits::new(beatmap);
+
+    let mut scored = listing
+        .beatmaps
+        .iter()
+        .enumerate()
+        .filter(|(_, candidate)| {
+            !std::ptr::eq(*candidate, beatmap)
+                && (candidate.md5.is_none() || candidate.md5 != beatmap.md5)
+                && (options.include_same_set || !same_set(candidate, beatmap))
+        })
+        .map(|(i, candidate)| (i, traits.similarity(&Traits::new(candidate), options)))
+        .filter(|(_, score)| *score > 0.0)
+        .collect::<Vec<_>>();
+
+    scored.sort_by(|(a, a_score), (b, b_score)| {
+        let (a, b) = (&listing.beatmaps[*a], &listing.beatmaps[*b]);
+
+        b_score
+            .total_cmp(a_score)
+            .then(a.beatmap_id.cmp(&b.beatmap_id))
+            .then(a.difficulty_id.cmp(&b.difficulty_id))
+    });
+
+    scored.truncate(limit);
+    scored
+}
+
+/// Represents a group of beatmaps that share a song source or tag, such as the beatmaps from a beatmap pack or a
+/// tournament's mappools.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeatmapGroup<'a> {
+    /// The source or tag shared by the beatmaps, as written on the first beatmap found with it
+    pub name: String,
+
+    /// Beatmaps in the group, in the order they're listed
+    pub beatmaps: Vec<&'a BeatmapEntry>,
+}
+
+impl BeatmapGroup<'_> {
+    /// Counts the beatmapsets in the group, using the beatmaps' folders so that unsubmitted beatmaps are counted too.
+    pub fn beatmapset_count(&self) -> usize {
+        self.beatmaps
+            .iter()
+            .map(|beatmap| {
+                beatmap
+                    .folder_name
+                    .as_deref()
+                    .unwrap_or_default()
+                    .to_lowercase()
+            })
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Creates a collection containing the group's beatmaps, named after the group.
+    pub fn to_collection(&self) -> Collection {
+        Collection::from_beatmaps(self.name.clone(), self.beatmaps.iter().copied())
+    }
+}
+
+/// Groups the beatmaps in a listing by their song source (e.g. the anime a song is from), ignoring case. Beatmaps
+/// without a source aren't grouped.
+///
+/// The largest groups come first, with ties ordered by name.
+pub fn group_by_source(listing: &BeatmapListing) -> Vec<BeatmapGroup<'_>> {
+    group_by(listing, |beatmap| {
+        beatmap
+            .song_source
+            .as_deref()
+            .map(str::trim)
+            .filter(|source| !source.is_empty())
+            .into_iter()
+            .collect()
+    })
+}
+
+/// Groups the beatmaps in a listing by each of their tags which start with one of `prefixes` (e.g. `owc` for tags
+/// like `OWC2023`), ignoring case. A beatmap is in one group for each matching tag.
+///
+/// The largest groups come first, with ties ordered by name.
+pub fn group_by_tag_prefix<'a>(
+    listing: &'a BeatmapListing,
+    prefixes: &[&str],
+) -> Vec<BeatmapGroup<'a>> {
+    let prefixes = prefixes
+        .iter()
+        .map(|prefix| prefix.trim().to_lowercase())
+        .filter(|prefix| !prefix.is_empty())
+        .collect::<Vec<_>>();
+
+    group_by(listing, |beatmap| {
+        beatmap
+            .song_tags
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter(|tag| {
+                let tag = tag.to_lowercase();
+                prefixes
+                    .iter()
+                    .any(|prefix| tag.starts_with(prefix.as_str()))
+            })
+            .collect()
+    })
+}
+
+/// Groups the beatmaps in a listing by the names given for each beatmap, ignoring case.
+fn group_by<'a>(
+    listing: &'a BeatmapListing,
+    names: impl Fn(&'a BeatmapEntry) -> Vec<&'a str>,
+) -> Vec<BeatmapGroup<'a>> {
+    let mut groups = Vec::<BeatmapGroup>::new();
+    let mut indices = HashMap::new();
+
+    for beatmap in &listing.beatmaps {
+        // Tags can be repeated, but the beatmap should only be added to each group once
+        let mut seen = HashSet::new();
+
+        for name in names(beatmap) {
+            let key = name.to_lowercase();
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+
+            let index = *indices.entry(key).or_insert_with(|| {
+                groups.push(BeatmapGroup {
+                    name: name.to_string(),
+                    beatmaps: Vec::new(),
+                });
+                groups.len() - 1
+            });
+
+            groups[index].beatmaps.push(beatmap);
+        }
+    }
+
+    groups.sort_by(|a, b| {
+        b.beatmaps
+            .len()
+            .cmp(&a.beatmaps.len())
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    groups
+}
+
+/// Represents a beatmap whose star rating is different in two listings, e.g. before and after osu! recalculated star
+/// ratings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StarChange<'a> {
+    /// MD5 hash of the beatmap, which is used to match it up between the listings
+    pub md5: &'a str,
+
+    /// The entry in the old listing
+    pub old: &'a BeatmapEntry,
+
+    /// The entry in the new listing
+    pub new: &'a BeatmapEntry,
+
+    pub old_rating: f64,
+    pub new_rating: f64,
+
+    /// How much the star rating went up, or down if negative
+    pub delta: f64,
+}
+
+/// Finds the beatmaps whose star rating for a gameplay mode and mod combination changed between two listings, with the
+/// largest changes (in either direction) first.
+///
+/// Only the mods which affect star ratings are used, e.g. Nightcore is treated as Double Time. Beatmaps are matched up
+/// using their MD5 hashes, and beatmaps which are only in one of the listings, or which don't have a rating for the
+/// mods in both of them, are skipped.
+pub fn star_rating_changes<'a>(
+    old: &'a BeatmapListing,
+    new: &'a BeatmapListing,
+    mode: GameplayMode,
+    mods: FlagSet<Mods>,
+) -> Vec<StarChange<'a>> {
+    let mods = difficulty::star_rating_mods(mods);
+    let old_beatmaps = old.beatmaps_by_md5();
+
+    let mut changes = new
+        .beatmaps
+        .iter()
+        .filter_map(|beatmap| {
+            let md5 = beatmap.md5.as_deref()?;
+            let old_beatmap = old_beatmaps.get(md5).copied()?;
+            let old_rating = old_beatmap.star_rating(mode, mods)?;
+            let new_rating = beatmap.star_rating(mode, mods)?;
+            let delta = new_rating - old_rating;
+
+            // NOTE: NaN ratings are skipped, since they can't be ordered by how much they changed
+            (delta != 0.0 && !delta.is_nan()).then_some(StarChange {
+                md5,
+                old: old_beatmap,
+                new: beatmap,
+                old_rating,
+                new_rating,
+                delta,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    changes.sort_by(|a, b| {
+        b.delta
+            .abs()
+            .total_cmp(&a.delta.abs())
+            .then_with(|| a.md5.cmp(b.md5))
+    });
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime, offset};
+
+    use super::*;
+    use crate::{
+        beatmaps::{StarRating, TimingPoint},
+        common::WINDOWS_EPOCH,
+        scores::{BeatmapScores, ScoreReplay},
+    };
+
+    const NOW: OffsetDateTime = datetime!(2024-06-01 12:00 UTC);
+
+    fn heatmap(dates: &[OffsetDateTime], tz: UtcOffset) -> HashMap<Date, u32> {
+        activity_heatmap_until(dates.iter().copied(), tz, NOW)
+    }
+
+    #[test]
+    fn dates_are_counted_per_day() {
+        let dates = [
+            datetime!(2024-03-01 09:00 UTC),
+            datetime!(2024-03-01 21:00 UTC),
+            datetime!(2024-03-02 00:00 UTC),
+        ];
+
+        assert_eq!(
+            heatmap(&dates, UtcOffset::UTC),
+            HashMap::from([(date!(2024 - 03 - 01), 2), (date!(2024 - 03 - 02), 1)])
+        );
+    }
+
+    #[test]
+    fn days_are_split_in_the_given_timezone() {
+        // 23:30 UTC is already the next day in Tokyo, and 00:30 UTC is still the previous day in New York
+        let dates = [
+            datetime!(2024-03-01 23:30 UTC),
+            datetime!(2024-03-02 00:30 UTC),
+        ];
+
+        assert_eq!(
+            heatmap(&dates, UtcOffset::UTC),
+            HashMap::from([(date!(2024 - 03 - 01), 1), (date!(2024 - 03 - 02), 1)])
+        );
+        assert_eq!(
+            heatmap(&dates, offset!(+9)),
+            HashMap::from([(date!(2024 - 03 - 02), 2)])
+        );
+        assert_eq!(
+            heatmap(&dates, offset!(-5)),
+            HashMap::from([(date!(2024 - 03 - 01), 2)])
+        );
+    }
+
+    #[test]
+    fn day_boundaries_are_inclusive_of_midnight() {
+        let dates = [
+            datetime!(2024-03-01 14:59:59 UTC),
+            datetime!(2024-03-01 15:00 UTC),
+        ];
+
+        // Midnight in UTC+9 is 15:00 UTC
+        assert_eq!(
+            heatmap(&dates, offset!(+9)),
+            HashMap::from([(date!(2024 - 03 - 01), 1), (date!(2024 - 03 - 02), 1)])
+        );
+    }
+
+    #[test]
+    fn dates_with_offsets_are_converted() {
+        // Both are the same instant, so they fall on the same day in any timezone
+        let dates = [
+            datetime!(2024-03-01 23:00 -2),
+            datetime!(2024-03-02 01:00 UTC),
+        ];
+
+        assert_eq!(
+            heatmap(&dates, UtcOffset::UTC),
+            HashMap::from([(date!(2024 - 03 - 02), 2)])
+        );
+        assert_eq!(
+            heatmap(&dates, offset!(-2)),
+            HashMap::from([(date!(2024 - 03 - 01), 2)])
+        );
+    }
+
+    #[test]
+    fn year_boundaries_are_split() {
+        let dates = [datetime!(2023-12-31 20:00 UTC)];
+
+        assert_eq!(
+            heatmap(&dates, offset!(+5:30)),
+            HashMap::from([(date!(2024 - 01 - 01), 1)])
+        );
+        assert_eq!(
+            monthly_totals(&heatmap(&dates, offset!(+5:30))),
+            BTreeMap::from([((2024, 1), 1)])
+        );
+    }
+
+    #[test]
+    fn sentinel_and_future_dates_are_skipped() {
+        let dates = [
+            WINDOWS_EPOCH,
+            datetime!(2000-01-01 00:00 UTC),
+            datetime!(2024-06-01 11:59 UTC),
+            datetime!(2024-06-01 12:01 UTC),
+            datetime!(2030-01-01 00:00 UTC),
+        ];
+
+        assert_eq!(
+            heatmap(&dates, UtcOffset::UTC),
+            HashMap::from([(date!(2024 - 06 - 01), 1)])
+        );
+    }
+
+    #[test]
+    fn future_dates_are_compared_as_instants() {
+        // Already the next day in UTC+14, but still in the past
+        let dates = [datetime!(2024-06-01 11:00 UTC)];
+
+        assert_eq!(
+            heatmap(&dates, offset!(+14)),
+            HashMap::from([(date!(2024 - 06 - 02), 1)])
+        );
+    }
+
+    #[test]
+    fn monthly_totals_are_summed() {
+        let heatmap = HashMap::from([
+            (date!(2024 - 02 - 28), 3),
+            (date!(2024 - 02 - 29), 2),
+            (date!(2024 - 03 - 01), 1),
+            (date!(2023 - 02 - 01), 4),
+        ]);
+
+        assert_eq!(
+            monthly_totals(&heatmap),
+            BTreeMap::from([((2023, 2), 4), ((2024, 2), 5), ((2024, 3), 1)])
+        );
+    }
+
+    #[test]
+    fn listing_wrappers_skip_unset_dates() {
+        let beatmaps = BeatmapListing {
+            version: 20240101,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps: vec![
+                BeatmapEntry {
+                    is_unplayed: false,
+                    last_played: datetime!(2020-05-05 10:00 UTC),
+                    ..Default::default()
+                },
+                BeatmapEntry {
+                    is_unplayed: true,
+                    last_played: datetime!(2020-05-05 10:00 UTC),
+                    ..Default::default()
+                },
+                BeatmapEntry {
+                    is_unplayed: false,
+                    last_played: WINDOWS_EPOCH,
+                    ..Default::default()
+                },
+            ],
+            user_permissions: Default::default(),
+        };
+
+        assert_eq!(
+            beatmap_activity(&beatmaps, UtcOffset::UTC),
+            HashMap::from([(date!(2020 - 05 - 05), 1)])
+        );
+
+        // Play dates are local time, so they stay on the same day in any time zone
+        assert_eq!(
+            beatmap_activity(&beatmaps, offset!(+14)),
+            HashMap::from([(date!(2020 - 05 - 05), 1)])
+        );
+
+        let scores = ScoreListing {
+            version: 20240101,
+            beatmap_scores: vec![BeatmapScores {
+                md5: None,
+                scores: vec![
+                    ScoreReplay {
+                        timestamp: datetime!(2020-05-05 10:00 UTC),
+                        ..Default::default()
+                    },
+                    ScoreReplay {
+                        timestamp: datetime!(2020-05-06 10:00 UTC),
+                        ..Default::default()
+                    },
+                    ScoreReplay::default(),
+                ],
+            }],
+        };
+
+        assert_eq!(
+            score_activity(&scores, UtcOffset::UTC),
+            HashMap::from([(date!(2020 - 05 - 05), 1), (date!(2020 - 05 - 06), 1)])
+        );
+    }
+
+    fn similar_listing() -> BeatmapListing {
+        let beatmap =
+            |md5: &str, set: u32, mapper: &str, tags: &str, stars: f64, bpm: f64, length: u32| {
+                BeatmapEntry {
+                    md5: Some(md5.to_string()),
+                    beatmap_id: set,
+                    difficulty_id: set * 10,
+                    creator_name: Some(mapper.to_string()),
+                    song_tags: Some(tags.to_string()),
+                    star_ratings_std: Some(vec![StarRating {
+                        mods: Mods::none(),
+                        rating: stars,
+                    }]),
+                    timing_points: vec![TimingPoint {
+                        bpm: 60_000.0 / bpm,
+                        song_offset: 0.0,
+                        inherited: true,
+                    }],
+                    total_time: length,
+                    ..Default::default()
+                }
+            };
+
+        let mut mania = beatmap("mania", 600, "Other", "", 2.0, 120.0, 60_000);
+        mania.gameplay_mode = GameplayMode::Mania;
+        mania.star_ratings_std = None;
+
+        BeatmapListing {
+            version: 20240101,
+            folder_count: 7,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps: vec![
+                beatmap("target", 100, "Sotarks", "anime jpop", 5.0, 180.0, 200_000),
+                beatmap(
+                    "same set",
+                    100,
+                    "Sotarks",
+                    "anime jpop",
+                    5.0,
+                    180.0,
+                    200_000,
+                ),
+                beatmap(
+                    "same mapper",
+                    200,
+                    "sotarks",
+                    "jpop anime",
+                    5.1,
+                    180.0,
+                    210_000,
+                ),
+                beatmap("tags", 300, "Someone", "anime", 5.2, 185.0, 200_000),
+                mania,
+                beatmap("tie b", 500, "Someone", "jpop", 5.0, 170.0, 200_000),
+                beatmap("tie a", 400, "Someone", "jpop", 5.0, 170.0, 200_000),
+                beatmap("target", 700, "Sotarks", "anime jpop", 5.0, 180.0, 200_000),
+            ],
+            user_permissions: Default::default(),
+        }
+    }
+
+    fn md5s<'a>(similar: &[(&'a BeatmapEntry, f32)]) -> Vec<&'a str> {
+        similar
+            .iter()
+            .map(|(beatmap, _)| beatmap.md5.as_deref().unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn similar_maps_are_ranked() {
+        let listing = similar_listing();
+        let similar = similar_maps(&listing, &listing.beatmaps[0], 10);
+
+        // The mania map has nothing in common, and ties are ordered by beatmapset ID
+        assert_eq!(
+            md5s(&similar),
+            vec!["same mapper", "tie a", "tie b", "tags"]
+        );
+        assert!(similar.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert!(similar.iter().all(|(_, score)| (0.0..=1.0).contains(score)));
+
+        assert_eq!(
+            md5s(&similar_maps(&listing, &listing.beatmaps[0], 2)),
+            vec!["same mapper", "tie a"]
+        );
+    }
+
+    #[test]
+    fn similar_maps_can_include_the_same_set() {
+        let listing = similar_listing();
+        let options = SimilarityOptions {
+            include_same_set: true,
+            ..Default::default()
+        };
+
+        let similar = similar_maps_with(&listing, &listing.beatmaps[0], 1, &options);
+        assert_eq!(md5s(&similar), vec!["same set"]);
+        assert_eq!(similar[0].1, 1.0);
+    }
+
+    #[test]
+    fn similarity_weights_are_configurable() {
+        let listing = similar_listing();
+        let options = SimilarityOptions {
+            mapper: 1.0,
+            tags: 0.0,
+            stars: 0.0,
+            bpm: 0.0,
+            length: 0.0,
+            mode: 0.0,
+            ..Default::default()
+        };
+
+        let similar = similar_maps_with(&listing, &listing.beatmaps[0], 10, &options);
+        assert_eq!(md5s(&similar), vec!["same mapper"]);
+        assert_eq!(
+            similar_map_indices(&listing, &listing.beatmaps[0], 10, &options),
+            vec![(2, 1.0)]
+        );
+    }
+
+    fn grouped_listing() -> BeatmapListing {
+        let beatmap = |md5: &str, folder: &str, source: &str, tags: &str| BeatmapEntry {
+            md5: Some(md5.to_string()),
+            folder_name: Some(folder.to_string()),
+            song_source: Some(source.to_string()),
+            song_tags: Some(tags.to_string()),
+            ..Default::default()
+        };
+
+        BeatmapListing {
+            version: 20240101,
+            folder_count: 4,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps: vec![
+                beatmap("a1", "A", "Stellaria", "OWC2023 tournament"),
+                beatmap("a2", "A", "Stellaria", "owc2023 owc2023"),
+                beatmap("b", "B", "STELLARIA ", "owc2022 jpop"),
+                beatmap("c", "C", "Other Anime", "OWC2023"),
+                beatmap("d", "D", "", "jpop"),
+                BeatmapEntry {
+                    md5: Some("e".to_string()),
+                    ..Default::default()
+                },
+            ],
+            user_permissions: Default::default(),
+        }
+    }
+
+    fn group_summary(groups: &[BeatmapGroup]) -> Vec<(String, Vec<String>)> {
+        groups
+            .iter()
+            .map(|group| {
+                let md5s = group
+                    .beatmaps
+                    .iter()
+                    .map(|beatmap| beatmap.md5.clone().unwrap_or_default())
+                    .collect();
+
+                (group.name.clone(), md5s)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn beatmaps_are_grouped_by_source() {
+        let listing = grouped_listing();
+        let groups = group_by_source(&listing);
+
+        // Sources are compared ignoring case and surrounding spaces, and empty sources are skipped
+        assert_eq!(
+            group_summary(&groups),
+            vec![
+                (
+                    "Stellaria".to_string(),
+                    vec!["a1".into(), "a2".into(), "b".into()]
+                ),
+                ("Other Anime".to_string(), vec!["c".into()]),
+            ]
+        );
+        assert_eq!(groups[0].beatmapset_count(), 2);
+
+        let collection = groups[0].to_collection();
+        assert_eq!(collection.name.as_deref(), Some("Stellaria"));
+        assert_eq!(
+            collection.beatmap_md5s,
+            vec![Some("a1".into()), Some("a2".into()), Some("b".into())]
+        );
+    }
+
+    #[test]
+    fn beatmaps_are_grouped_by_tag_prefix() {
+        let listing = grouped_listing();
+
+        // Repeated tags only add the beatmap once
+        assert_eq!(
+            group_summary(&group_by_tag_prefix(&listing, &["OWC"])),
+            vec![
+                (
+                    "OWC2023".to_string(),
+                    vec!["a1".into(), "a2".into(), "c".into()]
+                ),
+                ("owc2022".to_string(), vec!["b".into()]),
+            ]
+        );
+
+        assert_eq!(
+            group_summary(&group_by_tag_prefix(&listing, &["owc2022", "JP"])),
+            vec![
+                ("jpop".to_string(), vec!["b".into(), "d".into()]),
+                ("owc2022".to_string(), vec!["b".into()]),
+            ]
+        );
+        assert!(group_by_tag_prefix(&listing, &["", " "]).is_empty());
+    }
+
+    fn rated_listing(ratings: &[(&str, f64, f64)]) -> BeatmapListing {
+        let beatmaps = ratings
+            .iter()
+            .map(|&(md5, nomod, dt)| BeatmapEntry {
+                md5: Some(md5.to_string()),
+                star_ratings_std: Some(vec![
+                    StarRating {
+                        mods: Mods::none(),
+                        rating: nomod,
+                    },
+                    StarRating {
+                        mods: Mods::DoubleTime.into(),
+                        rating: dt,
+                    },
+                ]),
+                ..Default::default()
+            })
+            .collect();
+
+        BeatmapListing {
+            version: 20250108,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps,
+            user_permissions: Default::default(),
+        }
+    }
+
+    fn deltas(changes: &[StarChange]) -> Vec<(String, f64)> {
+        changes
+            .iter()
+            .map(|change| {
+                (
+                    change.md5.to_string(),
+                    (change.delta * 100.0).round() / 100.0,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn star_rating_changes_are_ranked_by_size() {
+        let old = rated_listing(&[
+            ("same", 5.0, 7.0),
+            ("up", 4.0, 6.0),
+            ("down", 6.0, 8.5),
+            ("tie b", 3.0, 4.0),
+            ("tie a", 3.0, 4.0),
+            ("removed", 2.0, 3.0),
+        ]);
+        let new = rated_listing(&[
+            ("added", 1.0, 2.0),
+            ("tie a", 3.1, 4.0),
+            ("down", 5.5, 8.5),
+            ("up", 4.25, 6.0),
+            ("same", 5.0, 7.5),
+            ("tie b", 2.9, 4.0),
+        ]);
+
+        let changes = star_rating_changes(&old, &new, GameplayMode::Standard, Mods::none());
+
+        // Beatmaps only in one listing are skipped, as are unchanged ratings
+        assert_eq!(
+            deltas(&changes),
+            vec![
+                ("down".to_string(), -0.5),
+                ("up".to_string(), 0.25),
+                ("tie a".to_string(), 0.1),
+                ("tie b".to_string(), -0.1),
+            ]
+        );
+        assert_eq!(changes[0].old_rating, 6.0);
+        assert_eq!(changes[0].new_rating, 5.5);
+        assert_eq!(changes[0].old.md5, changes[0].new.md5);
+
+        // Nightcore uses the Double Time ratings, and other modes have no ratings to compare
+        assert_eq!(
+            deltas(&star_rating_changes(
+                &old,
+                &new,
+                GameplayMode::Standard,
+                Mods::Nightcore | Mods::DoubleTime | Mods::Hidden
+            )),
+            vec![("same".to_string(), 0.5)]
+        );
+        assert!(star_rating_changes(&old, &new, GameplayMode::Taiko, Mods::none()).is_empty());
+    }
+}
@@ -0,0 +1,554 @@
+//! Anonymisation of a beatmap listing's text, e.g. so that a listing can be attached to a bug report without revealing
+//! the player's name or which beatmaps they have.
+//!
+//! Each word (a run of letters and digits) is replaced by a pseudonym made from characters of the same kind: ASCII
+//! letters of the same case, digits, kana, CJK characters and so on, as well as the same number of UTF-8 bytes.
+//! Everything else (spaces, punctuation and symbols) is kept. This keeps the length and shape of every string, so that
+//! problems with parsing or layout still happen with the anonymised listing.
+//!
+//! The same word always gets the same pseudonym (ignoring case), so duplicates stay duplicates and searches for whole
+//! words still find the same beatmaps. Numeric fields, such as beatmap IDs, are left alone.
+//!
+//! [`BeatmapListing::redact`] makes an anonymised copy of a whole listing, e.g. to share as a test fixture, optionally
+//! clearing the times each beatmap was modified, played and checked too.
+
+use std::collections::HashMap;
+
+use crate::{
+    beatmaps::{BeatmapEntry, BeatmapListing},
+    common::{OsuString, WINDOWS_EPOCH},
+    query::{self, BeatmapFilter, Condition},
+};
+
+/// Number of code points in each range that a replacement character is picked from. Ranges are aligned to this, so
+/// that every character in a range is encoded using the same number of UTF-8 bytes.
+const RANGE_SIZE: u32 = 128;
+
+/// Replaces the text in beatmap listings and search queries with pseudonyms.
+///
+/// Pseudonyms depend on the seed, which should be random and kept secret, otherwise the original text could be
+/// guessed by anonymising likely words with the same seed.
+#[derive(Clone, Debug)]
+pub struct Anonymiser {
+    seed: u64,
+
+    /// Pseudonyms of each word seen so far
+    words: HashMap<String, String>,
+
+    /// Characters of each kind in each range, for picking replacements
+    candidates: HashMap<(u32, CharClass), Vec<char>>,
+}
+
+/// Represents how to redact a listing (see [`BeatmapListing::redact`]).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RedactOptions {
+    /// Seed for the pseudonyms, which should be random (see [`Anonymiser`])
+    pub seed: u64,
+
+    /// Whether to clear the times each beatmap was last modified, played and checked online, which can reveal when
+    /// the player was active
+    pub zero_timestamps: bool,
+}
+
+/// Represents the kind of a character, which its replacement must share.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct CharClass {
+    alphabetic: bool,
+    numeric: bool,
+    lowercase: bool,
+    uppercase: bool,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        Self {
+            alphabetic: c.is_alphabetic(),
+            numeric: c.is_numeric(),
+            lowercase: c.is_lowercase(),
+            uppercase: c.is_uppercase(),
+        }
+    }
+}
+
+impl Anonymiser {
+    /// Creates an anonymiser whose pseudonyms depend on `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            words: HashMap::new(),
+            candidates: HashMap::new(),
+        }
+    }
+
+    /// Anonymises every word in some text.
+    pub fn text(&mut self, text: &str) -> String {
+        let mut anonymised = String::with_capacity(text.len());
+        let mut word = String::new();
+
+        for c in text.chars() {
+            if c.is_alphanumeric() {
+                word.push(c);
+                continue;
+            }
+
+            if !word.is_empty() {
+                anonymised.push_str(&self.word(&word));
+                word.clear();
+            }
+
+            anonymised.push(c);
+        }
+
+        if !word.is_empty() {
+            anonymised.push_str(&self.word(&word));
+        }
+
+        anonymised
+    }
+
+    /// Anonymises a file name, keeping its extension so that the kind of file can still be seen.
+    pub fn file_name(&mut self, file_name: &str) -> String {
+        match file_name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => {
+                format!("{}.{}", self.text(stem), extension)
+            }
+            _ => self.text(file_name),
+        }
+    }
+
+    /// Anonymises a search query, so that it finds the same beatmaps in an anonymised listing.
+    ///
+    /// Free text and the values of conditions on text fields (e.g. `artist=...`) are anonymised, while keys and the
+    /// values of other conditions (e.g. `stars>5` or `mode=mania`) are kept.
+    pub fn query(&mut self, query: &str) -> String {
+        let mut anonymised = String::with_capacity(query.len());
+        let mut term = String::new();
+
+        for c in query.chars() {
+            if c.is_whitespace() {
+                anonymised.push_str(&self.term(&term));
+                anonymised.push(c);
+                term.clear();
+            } else {
+                term.push(c);
+            }
+        }
+
+        anonymised.push_str(&self.term(&term));
+        anonymised
+    }
+
+    /// Anonymises the free text and the values of text conditions in a filter.
+    pub fn filter(&mut self, filter: &mut BeatmapFilter) {
+        for text in &mut filter.text {
+            *text = self.text(text);
+        }
+
+        for condition in &mut filter.conditions {
+            if let Condition::Text { value, .. } | Condition::Note { value, .. } = condition {
+                *value = self.text(value);
+            }
+        }
+    }
+
+    /// Anonymises the player's name and the text of every beatmap in a listing.
+    pub fn listing(&mut self, listing: &mut BeatmapListing) {
+        self.optional(&mut listing.player_name, Self::text);
+
+        for beatmap in &mut listing.beatmaps {
+            self.beatmap(beatmap);
+        }
+    }
+
+    /// Anonymises the text of a beatmap, including its MD5 hash and file names.
+    pub fn beatmap(&mut self, beatmap: &mut BeatmapEntry) {
+        for field in [
+            &mut beatmap.artist_name,
+            &mut beatmap.artist_name_unicode,
+            &mut beatmap.song_title,
+            &mut beatmap.song_title_unicode,
+            &mut beatmap.creator_name,
+            &mut beatmap.difficulty,
+            &mut beatmap.md5,
+            &mut beatmap.song_source,
+            &mut beatmap.song_tags,
+            &mut beatmap.font,
+            &mut beatmap.folder_name,
+        ] {
+            self.optional(field, Self::text);
+        }
+
+        self.optional(&mut beatmap.audio_filename, Self::file_name);
+        self.optional(&mut beatmap.beatmap_filename, Self::file_name);
+    }
+
+    fn optional(&mut self, value: &mut OsuString, anonymise: fn(&mut Self, &str) -> String) {
+        if let Some(value) = value {
+            *value = anonymise(self, value);
+        }
+    }
+
+    /// Anonymises a term of a search query.
+    fn term(&mut self, term: &str) -> String {
+        let Some((key, op, value)) = query::split_condition(term) else {
+            return self.text(term);
+        };
+
+        match query::condition(term, key, op, value) {
+            Ok(Condition::Text { .. } | Condition::Note { .. }) => {
+                let prefix = &term[..term.len() - value.len()];
+                format!("{}{}", prefix, self.text(value))
+            }
+            _ => term.to_string(),
+        }
+    }
+
+    /// Gets the pseudonym of a word, which only depends on the seed and the word (ignoring case).
+    fn word(&mut self, word: &str) -> String {
+        if let Some(pseudonym) = self.words.get(word) {
+            return pseudonym.clone();
+        }
+
+        let mut state = hash(self.seed, &word.to_lowercase());
+        let pseudonym = word
+            .chars()
+            .map(|c| self.replace(c, splitmix(&mut state)))
+            .collect::<String>();
+
+        self.words.insert(word.to_string(), pseudonym.clone());
+        pseudonym
+    }
+
+    /// Replaces a character with a different one of the same kind, keeping its case.
+    fn replace(&mut self, c: char, random: u64) -> char {
+        // Lowercase letters are picked first, so that the pseudonym of a word doesn't depend on its case
+        let lower = single_char(c.to_lowercase())
+            .filter(|lower| lower.len_utf8() == c.len_utf8())
+            .unwrap_or(c);
+
+        let replacement = self.pick(lower, random);
+        if lower == c {
+            return replacement;
+        }
+
+        single_char(replacement.to_uppercase())
+            .filter(|upper| {
+                CharClass::of(*upper) == CharClass::of(c) && upper.len_utf8() == c.len_utf8()
+            })
+            .unwrap_or_else(|| self.pick(c, random))
+    }
+
+    /// Picks a character of the same kind as `c` from the same range of code points, other than `c` itself.
+    fn pick(&mut self, c: char, random: u64) -> char {
+        let class = CharClass::of(c);
+        let start = c as u32 / RANGE_SIZE * RANGE_SIZE;
+
+        let candidates = self.candidates.entry((start, class)).or_insert_with(|| {
+            (start..start + RANGE_SIZE)
+                .filter_map(char::from_u32)
+                .filter(|&other| CharClass::of(other) == class)
+                .collect()
+        });
+
+        let others = candidates.len().saturating_sub(1);
+        if others == 0 {
+            return c;
+        }
+
+        // Skip over `c`, so that every character is changed
+        let position = candidates
+            .iter()
+            .position(|&other| other == c)
+            .unwrap_or(others);
+        let index = (random % others as u64) as usize;
+        candidates[if index >= position { index + 1 } else { index }]
+    }
+}
+
+impl BeatmapListing {
+    /// Makes a copy of the listing which can be shared, e.g. as a test fixture in a bug report.
+    ///
+    /// The player's name and the text of every beatmap (including creators, tags, sources and folder names) are
+    /// replaced with pseudonyms, which are the same for the same words throughout the listing so that grouping (e.g.
+    /// by folder or creator) still works. Everything else, including the version, counts, lengths and star ratings, is
+    /// kept so that parsing problems can still be reproduced, and the copy can be written as a valid `osu.db`. The
+    /// account's unlock date is kept too, since it decides the account status.
+    pub fn redact(&self, options: RedactOptions) -> BeatmapListing {
+        let mut redacted = self.clone();
+        Anonymiser::new(options.seed).listing(&mut redacted);
+
+        if options.zero_timestamps {
+            for beatmap in &mut redacted.beatmaps {
+                beatmap.last_modification_time = WINDOWS_EPOCH;
+                beatmap.last_played = WINDOWS_EPOCH;
+                beatmap.last_checked_online = WINDOWS_EPOCH;
+            }
+        }
+
+        redacted
+    }
+}
+
+/// Gets the only character in an iterator, or `None` if there's more than one (e.g. `ß` in uppercase is `SS`).
+fn single_char(mut chars: impl Iterator<Item = char>) -> Option<char> {
+    let c = chars.next()?;
+    chars.next().is_none().then_some(c)
+}
+
+/// Hashes a word with a seed using FNV-1a, which unlike `std`'s hasher gives the same result in every build.
+fn hash(seed: u64, word: &str) -> u64 {
+    seed.to_le_bytes()
+        .iter()
+        .chain(word.as_bytes())
+        .fold(0xcbf29ce484222325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// Gets the next number from a SplitMix64 generator.
+pub(crate) fn splitmix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::beatmaps::StarRating;
+
+    fn listing() -> BeatmapListing {
+        let beatmap = |artist: &str, title: &str, difficulty: &str| BeatmapEntry {
+            artist_name: Some(artist.to_string()),
+            artist_name_unicode: Some(artist.to_string()),
+            song_title: Some(title.to_string()),
+            song_title_unicode: Some(title.to_string()),
+            creator_name: Some("Nakagawa-Kanon".to_string()),
+            difficulty: Some(difficulty.to_string()),
+            audio_filename: Some("audio.mp3".to_string()),
+            md5: Some(format!("{:032x}", difficulty.len())),
+            song_tags: Some("dubstep electronic".to_string()),
+            folder_name: Some(format!("39804 {} - {}", artist, title)),
+            ..Default::default()
+        };
+
+        BeatmapListing {
+            version: 20240101,
+            folder_count: 2,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: Some("Player".to_string()),
+            beatmaps: vec![
+                beatmap("xi", "FREEDOM DiVE", "FOUR DIMENSIONS"),
+                beatmap("xi", "FREEDOM DiVE", "Another"),
+                beatmap("ゆよゆっぺ", "虚空エレクトロン", "Insane"),
+            ],
+            user_permissions: Default::default(),
+        }
+    }
+
+    #[test]
+    fn shape_of_text_is_kept() {
+        let mut anonymiser = Anonymiser::new(1);
+
+        for text in [
+            "FREEDOM DiVE [4K]",
+            "ゆよゆっぺ - 虚空エレクトロン",
+            "Ünïcödé 2.0!",
+            "x",
+        ] {
+            let anonymised = anonymiser.text(text);
+            assert_ne!(anonymised, text);
+            assert_eq!(anonymised.len(), text.len());
+            assert_eq!(anonymised.chars().count(), text.chars().count());
+
+            for (original, replaced) in text.chars().zip(anonymised.chars()) {
+                assert_eq!(CharClass::of(replaced), CharClass::of(original));
+
+                if !original.is_alphanumeric() {
+                    assert_eq!(replaced, original);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn words_are_pseudonymised_consistently() {
+        let mut anonymiser = Anonymiser::new(1);
+        let freedom = anonymiser.text("FREEDOM DiVE");
+
+        assert_eq!(anonymiser.text("FREEDOM DiVE"), freedom);
+        assert_eq!(anonymiser.text("freedom dive"), freedom.to_lowercase());
+        assert!(anonymiser
+            .text("FREEDOM (Extended)")
+            .starts_with(&freedom[..8]));
+        assert_ne!(Anonymiser::new(2).text("FREEDOM DiVE"), freedom);
+
+        assert_eq!(
+            anonymiser.file_name("FREEDOM.mp3"),
+            format!("{}.mp3", &freedom[..7])
+        );
+    }
+
+    #[test]
+    fn no_original_text_survives() {
+        let original = listing();
+        let mut anonymised = original.clone();
+        Anonymiser::new(42).listing(&mut anonymised);
+
+        // Anonymised listings can still be written and read back
+        let bytes = anonymised.to_bytes();
+        let read = BeatmapListing::from_bytes(&bytes).unwrap();
+        assert_eq!(read.player_name, anonymised.player_name);
+        assert_eq!(
+            read.beatmaps[2].song_title,
+            anonymised.beatmaps[2].song_title
+        );
+
+        let written = String::from_utf8_lossy(&bytes);
+        for beatmap in &original.beatmaps {
+            for text in [
+                &beatmap.artist_name,
+                &beatmap.song_title,
+                &beatmap.difficulty,
+            ] {
+                assert!(!written.contains(text.as_deref().unwrap()));
+            }
+        }
+        assert!(!written.contains("Player"));
+
+        // Duplicates are kept
+        let beatmaps = &anonymised.beatmaps;
+        assert_eq!(beatmaps[0].song_title, beatmaps[1].song_title);
+        assert_eq!(beatmaps[0].song_title, beatmaps[0].song_title_unicode);
+        assert_ne!(beatmaps[0].difficulty, beatmaps[1].difficulty);
+        assert_eq!(beatmaps[2].audio_filename.as_deref().unwrap().len(), 9);
+        assert!(beatmaps[2]
+            .audio_filename
+            .as_deref()
+            .unwrap()
+            .ends_with(".mp3"));
+    }
+
+    #[test]
+    fn redacted_listings_keep_their_structure() {
+        let mut original = listing();
+        original.version = 20250108;
+
+        for beatmap in &mut original.beatmaps {
+            beatmap.song_source = Some("Touhou Project".to_string());
+            beatmap.last_modification_time = datetime!(2023-04-05 6:07 UTC);
+            beatmap.last_played = datetime!(2024-01-02 3:04 UTC);
+            beatmap.last_checked_online = datetime!(2024-01-01 0:00 UTC);
+            beatmap.hitcircle_count = 500;
+            beatmap.total_time = 123_000;
+            beatmap.star_ratings_std = Some(vec![StarRating {
+                mods: Default::default(),
+                rating: 6.5,
+            }]);
+            beatmap.star_ratings_taiko = Some(Vec::new());
+            beatmap.star_ratings_ctb = Some(Vec::new());
+            beatmap.star_ratings_mania = Some(Vec::new());
+        }
+
+        let redacted = original.redact(RedactOptions {
+            seed: 3,
+            zero_timestamps: true,
+        });
+
+        let bytes = redacted.to_bytes();
+        let read = BeatmapListing::from_bytes(&bytes).unwrap();
+        assert_eq!(read, redacted);
+
+        let written = String::from_utf8_lossy(&bytes);
+        for text in [
+            "Player",
+            "Nakagawa-Kanon",
+            "dubstep",
+            "Touhou",
+            "FREEDOM",
+            "39804 xi",
+        ] {
+            assert!(!written.contains(text), "{}", text);
+        }
+
+        // Beatmaps from the same folder are still grouped together
+        let beatmaps = &redacted.beatmaps;
+        assert_eq!(beatmaps[0].folder_name, beatmaps[1].folder_name);
+        assert_ne!(beatmaps[0].folder_name, beatmaps[2].folder_name);
+        assert_eq!(beatmaps[0].creator_name, beatmaps[2].creator_name);
+
+        for (redacted, original) in beatmaps.iter().zip(&original.beatmaps) {
+            assert_eq!(redacted.last_played, WINDOWS_EPOCH);
+            assert_eq!(redacted.last_modification_time, WINDOWS_EPOCH);
+            assert_eq!(redacted.last_checked_online, WINDOWS_EPOCH);
+            assert_eq!(redacted.hitcircle_count, original.hitcircle_count);
+            assert_eq!(redacted.total_time, original.total_time);
+            assert_eq!(redacted.star_ratings_std, original.star_ratings_std);
+        }
+
+        assert_eq!(redacted.version, original.version);
+        assert_eq!(redacted.folder_count, original.folder_count);
+
+        // Timestamps are only cleared when asked
+        let kept = original.redact(RedactOptions {
+            seed: 3,
+            zero_timestamps: false,
+        });
+        assert_eq!(
+            kept.beatmaps[0].last_played,
+            original.beatmaps[0].last_played
+        );
+        assert_eq!(kept.beatmaps[0].folder_name, beatmaps[0].folder_name);
+    }
+
+    #[test]
+    fn queries_find_the_same_beatmaps() {
+        let original = listing();
+        let mut anonymiser = Anonymiser::new(7);
+        let mut anonymised = original.clone();
+        anonymiser.listing(&mut anonymised);
+
+        for query in [
+            "freedom",
+            "artist=xi stars<5",
+            "diff=\"four dimensions\"",
+            "mode=std 虚空エレクトロン",
+            "creator!=nakagawa-kanon",
+        ] {
+            let anonymised_query = anonymiser.query(query);
+            assert_ne!(anonymised_query, query);
+
+            let matching = |listing: &BeatmapListing, query: &str| {
+                let filter = BeatmapFilter::parse(query).unwrap();
+                listing
+                    .beatmaps
+                    .iter()
+                    .map(|beatmap| filter.matches(beatmap))
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(
+                matching(&anonymised, &anonymised_query),
+                matching(&original, query),
+                "{} -> {}",
+                query,
+                anonymised_query
+            );
+
+            let mut filter = BeatmapFilter::parse(query).unwrap();
+            anonymiser.filter(&mut filter);
+            assert_eq!(filter, BeatmapFilter::parse(&anonymised_query).unwrap());
+        }
+
+        assert_eq!(
+            anonymiser.query("stars>5.5 mode=mania"),
+            "stars>5.5 mode=mania"
+        );
+    }
+}
@@ -0,0 +1,354 @@
+//! Async versions of the slow operations, for use in a [tokio](https://tokio.rs/) runtime (e.g. a server or a chat
+//! bot) without blocking its threads. Requires the `async` feature.
+//!
+//! The parser itself isn't async - each operation runs on tokio's blocking thread pool using
+//! [`spawn_blocking`](tokio::task::spawn_blocking), and returns the same types as the function it wraps. Dropping a
+//! returned future cancels the operation the next time it reports progress, so work stops soon after the result is no
+//! longer wanted (e.g. after a timeout).
+//!
+//! Exporting to SQLite is done by the command line tools rather than the parser, so only the text formats in
+//! [`export`](crate::export) are available here.
+
+use std::{
+    fs::File,
+    future::Future,
+    io::BufWriter,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+use crate::{
+    beatmaps::{BeatmapListing, MetadataLanguage},
+    columns::Column,
+    detect::FileKind,
+    error::Error,
+    export::{self, Format},
+    health::{self, MissingFile},
+    shared::read_database,
+};
+
+/// Number of progress updates that can be waiting to be received before newer ones are dropped. Updates are only sent
+/// when the percentage changes, so this is enough for a whole operation.
+const PROGRESS_CAPACITY: usize = 128;
+
+/// Represents the progress of parsing an `osu!.db` file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseProgress {
+    /// Number of beatmaps parsed so far
+    pub parsed: usize,
+
+    /// Total number of beatmaps in the file
+    pub total: usize,
+}
+
+impl ParseProgress {
+    /// Gets the fraction of beatmaps parsed so far, between 0 and 1.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.parsed as f32 / self.total as f32
+        }
+    }
+}
+
+/// Receives the progress of parsing a file from [`parse_with_progress_async`]. The stream ends when parsing finishes,
+/// fails or is cancelled.
+#[derive(Debug)]
+struct ProgressStream {
+    receiver: mpsc::Receiver<ParseProgress>,
+}
+
+impl Stream for ProgressStream {
+    type Item = ParseProgress;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ParseProgress>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// Sets a flag when dropped, which the blocking task checks each time it reports progress.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Parses an `osu!.db` file on the blocking thread pool (see [`BeatmapListing::from_file`]).
+///
+/// Dropping the returned future cancels parsing.
+pub async fn parse_async<P: Into<PathBuf>>(path: P) -> Result<BeatmapListing, Error> {
+    let path = path.into();
+    spawn_cancellable(move |cancelled| {
+        let data = read_database(path, FileKind::Beatmaps)?;
+        BeatmapListing::from_bytes_with_progress(&data, |_, _| !cancelled())
+    })
+    .await
+}
+
+/// Parses an `osu!.db` file on the blocking thread pool like [`parse_async`], with a stream of its progress.
+///
+/// Progress is only sent when the percentage of beatmaps parsed changes, so that a slow receiver (e.g. one that edits a
+/// chat message for each update) isn't flooded. The returned future must be polled for parsing to start, and dropping
+/// it cancels parsing.
+pub fn parse_with_progress_async<P: Into<PathBuf>>(
+    path: P,
+) -> (
+    impl Future<Output = Result<BeatmapListing, Error>> + Send,
+    impl Stream<Item = ParseProgress> + Send + Unpin,
+) {
+    let path = path.into();
+    let (sender, receiver) = mpsc::channel(PROGRESS_CAPACITY);
+
+    let parse = spawn_cancellable(move |cancelled| {
+        let data = read_database(path, FileKind::Beatmaps)?;
+        let mut last_percent = None;
+
+        BeatmapListing::from_bytes_with_progress(&data, |parsed, total| {
+            let percent = parsed * 100 / total.max(1);
+
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+
+                // If the receiver isn't keeping up, it'll still get the newer updates once it catches up
+                let _ = sender.try_send(ParseProgress { parsed, total });
+            }
+
+            !cancelled()
+        })
+    });
+
+    (parse, ProgressStream { receiver })
+}
+
+/// Exports a listing's beatmaps to a file on the blocking thread pool (see [`export::write`]), in file order.
+///
+/// Dropping the returned future cancels the export, leaving a partially written file.
+pub async fn export_async<P: Into<PathBuf>>(
+    path: P,
+    format: Format,
+    columns: Vec<Column>,
+    language: MetadataLanguage,
+    listing: Arc<BeatmapListing>,
+) -> Result<(), Error> {
+    let path = path.into();
+    spawn_cancellable(move |cancelled| {
+        let writer = BufWriter::new(File::create(path)?);
+        let beatmaps = listing.beatmaps.iter().collect::<Vec<_>>();
+
+        export::write(writer, format, &columns, language, &beatmaps, |_, _| {
+            !cancelled()
+        })
+    })
+    .await
+}
+
+/// Finds beatmaps whose `.osu` file doesn't exist in the osu! installation folder on the blocking thread pool (see
+/// [`health::missing_files`]).
+///
+/// Dropping the returned future cancels the check.
+pub async fn audit_files_async<P: Into<PathBuf>>(
+    osu_directory: P,
+    listing: Arc<BeatmapListing>,
+) -> Result<Vec<MissingFile>, Error> {
+    let osu_directory = osu_directory.into();
+    spawn_cancellable(move |cancelled| {
+        health::missing_files(&osu_directory, &listing.beatmaps, |_, _| !cancelled())
+    })
+    .await
+}
+
+/// Runs a task on the blocking thread pool, passing it a function which returns whether the returned future has been
+/// dropped. Tasks should check it regularly, and stop with [`Error::Cancelled`] once it returns `true`.
+async fn spawn_cancellable<T, F>(task: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn Fn() -> bool) -> Result<T, Error> + Send + 'static,
+{
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let _guard = CancelOnDrop(cancelled.clone());
+
+    let handle = tokio::task::spawn_blocking(move || task(&|| cancelled.load(Ordering::Relaxed)));
+
+    match handle.await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+
+        // The runtime is shutting down, so the task never ran
+        Err(_) => Err(Error::Cancelled),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::poll_fn, path::Path};
+
+    use tokio::runtime::{Builder, Runtime};
+
+    use super::*;
+    use crate::{
+        beatmaps::{BeatmapEntry, UserPermissions},
+        common::WINDOWS_EPOCH,
+    };
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().build().unwrap()
+    }
+
+    async fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+        poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    /// Writes a listing with many beatmaps, so that parsing it takes long enough to be cancelled part of the way.
+    fn write_listing(path: &Path, count: usize) -> BeatmapListing {
+        let beatmaps = (0..count)
+            .map(|i| BeatmapEntry {
+                song_title: Some(format!("Beatmap {}", i)),
+                folder_name: Some(format!("{} Folder", i)),
+                beatmap_filename: Some("beatmap.osu".to_string()),
+                ..Default::default()
+            })
+            .collect();
+
+        let listing = BeatmapListing {
+            version: 20191106,
+            folder_count: count as u32,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps,
+            user_permissions: UserPermissions::Normal.into(),
+        };
+
+        std::fs::write(path, listing.to_bytes()).unwrap();
+        BeatmapListing::from_file(path).unwrap()
+    }
+
+    #[test]
+    fn files_are_parsed_and_audited() {
+        let dir = std::env::temp_dir().join(format!("osu-db-parser-async-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Songs/0 Folder")).unwrap();
+        std::fs::write(dir.join("Songs/0 Folder/beatmap.osu"), "").unwrap();
+
+        let path = dir.join("osu!.db");
+        let expected = write_listing(&path, 3);
+
+        let (listing, missing) = runtime().block_on(async {
+            let listing = Arc::new(parse_async(&path).await.unwrap());
+            let missing = audit_files_async(&dir, listing.clone()).await.unwrap();
+            (listing, missing)
+        });
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(listing.beatmaps, expected.beatmaps);
+        assert_eq!(
+            missing.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn progress_is_streamed() {
+        let path = std::env::temp_dir().join(format!(
+            "osu-db-parser-async-progress-{}.db",
+            std::process::id()
+        ));
+        write_listing(&path, 1000);
+
+        let updates = runtime().block_on(async {
+            let (parse, mut progress) = parse_with_progress_async(path.clone());
+            parse.await.unwrap();
+
+            let mut updates = Vec::new();
+            while let Some(update) = next(&mut progress).await {
+                updates.push(update);
+            }
+
+            updates
+        });
+
+        std::fs::remove_file(&path).unwrap();
+
+        // One update for each percentage, rather than for each beatmap
+        assert_eq!(updates.len(), 101);
+        assert_eq!(
+            updates[0],
+            ParseProgress {
+                parsed: 1,
+                total: 1000
+            }
+        );
+        assert_eq!(
+            updates.last(),
+            Some(&ParseProgress {
+                parsed: 1000,
+                total: 1000
+            })
+        );
+    }
+
+    #[test]
+    fn dropping_the_future_cancels_parsing() {
+        let path = std::env::temp_dir().join(format!(
+            "osu-db-parser-async-cancel-{}.db",
+            std::process::id()
+        ));
+        write_listing(&path, 200_000);
+
+        let (result, last) = runtime().block_on(async {
+            let (parse, mut progress) = parse_with_progress_async(path.clone());
+            let handle = tokio::spawn(parse);
+
+            // Wait until parsing has started, then drop the future
+            next(&mut progress).await.unwrap();
+            handle.abort();
+            let result = handle.await;
+
+            // The stream ends once the blocking task stops
+            let mut last = None;
+            while let Some(update) = next(&mut progress).await {
+                last = Some(update);
+            }
+
+            (result, last)
+        });
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.unwrap_err().is_cancelled());
+        if let Some(last) = last {
+            assert!(last.parsed < last.total, "parsing wasn't cancelled");
+        }
+    }
+
+    #[test]
+    fn progress_fraction_is_calculated() {
+        assert_eq!(
+            ParseProgress {
+                parsed: 1,
+                total: 4
+            }
+            .fraction(),
+            0.25
+        );
+        assert_eq!(
+            ParseProgress {
+                parsed: 0,
+                total: 0
+            }
+            .fraction(),
+            1.0
+        );
+    }
+}
@@ -0,0 +1,528 @@
+//! Backups of database files made before they're written over, so that a bad edit can be undone.
+//!
+//! Each backup is a copy of the original file in a backups directory, named after the time it was made, along with an
+//! entry in a log (`backups.jsonl`, one JSON object per line) saying what was about to be done to the file, how many
+//! entries it changed and the MD5 hash of the original. Only the newest few backups of each file are kept.
+//!
+//! Backups are copies of the original file, which some file systems (e.g. APFS and Btrfs) make without duplicating its
+//! contents. `osu!.db` files can be hundreds of megabytes, so backups can be made as hard links to the original instead
+//! (see [`Backups::hard_links`]). Saving never writes into the original file, but replaces it with a new one (see
+//! [`BeatmapListing::to_file`](crate::beatmaps::BeatmapListing::to_file)), so the link keeps the old contents. Other
+//! programs, osu! included, might write into the file instead, which would change a hard linked backup too, so the
+//! hash is checked before a backup is restored.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use time::OffsetDateTime;
+
+use crate::{common::write_file_replacing, error::Error, hashes::Md5};
+
+/// Name of the directory made next to a file by [`Backups::beside`].
+pub const DEFAULT_DIRECTORY: &str = "osu-db-backups";
+
+/// Number of backups kept of each file by default.
+pub const DEFAULT_KEEP: usize = 10;
+
+/// Name of the log of backups in the backups directory.
+const LOG_NAME: &str = "backups.jsonl";
+
+/// A directory of backups, and how many of them are kept.
+#[derive(Clone, Debug)]
+pub struct Backups {
+    directory: PathBuf,
+    keep: usize,
+    hard_links: bool,
+}
+
+/// Represents a backup of a file, as recorded in the log.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Backup {
+    /// Identifies the backup, e.g. `20240501-183012`, which is when it was made (in UTC)
+    pub id: String,
+
+    /// What was about to be done to the file, e.g. `bulk-edit`
+    pub operation: String,
+
+    /// Number of entries (e.g. beatmaps or collections) the operation changed
+    pub changed: usize,
+
+    /// The file that was backed up
+    pub original: PathBuf,
+
+    /// Where the backup is
+    pub path: PathBuf,
+
+    /// Hash of the original contents, which the backup must still match to be restored
+    pub md5: Md5,
+
+    /// Size of the file in bytes
+    pub size: u64,
+
+    #[serde(with = "time::serde::timestamp")]
+    pub created: OffsetDateTime,
+
+    pub method: BackupMethod,
+}
+
+/// Represents how a backup was made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupMethod {
+    /// The backup is another name for the original contents, so it takes up no extra space
+    HardLink,
+
+    /// The contents were copied
+    Copy,
+}
+
+impl Backups {
+    /// Uses a backups directory, keeping the [`DEFAULT_KEEP`] newest backups of each file. The directory is made when
+    /// the first backup is.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Backups {
+        Backups {
+            directory: directory.into(),
+            keep: DEFAULT_KEEP,
+            hard_links: false,
+        }
+    }
+
+    /// Uses the backups directory next to a file, e.g. `osu!/osu-db-backups` for `osu!/osu!.db`. Being on the same
+    /// drive as the file means backups can be hard links, if they're turned on.
+    pub fn beside<P: AsRef<Path>>(path: P) -> Backups {
+        let parent = path.as_ref().parent().unwrap_or(Path::new(""));
+        Backups::new(parent.join(DEFAULT_DIRECTORY))
+    }
+
+    /// Sets how many backups of each file are kept, removing older ones whenever a backup is made. At least one
+    /// backup is always kept.
+    pub fn keep(mut self, keep: usize) -> Backups {
+        self.keep = keep.max(1);
+        self
+    }
+
+    /// Sets whether backups can be hard links to the original file rather than copies. Hard links can only be made
+    /// within one drive, so backups elsewhere are still copied.
+    ///
+    /// Hard links were meant to be how backups are made, since they take up no extra space, but they're off by
+    /// default. osu! writes its databases in place rather than replacing them, and doing so through a hard link would
+    /// change the backup too, leaving nothing to restore. The hash check in [`Backups::restore`] would catch this, but
+    /// only once the backup is needed, so hard links should only be turned on when nothing else writes to the file.
+    pub fn hard_links(mut self, hard_links: bool) -> Backups {
+        self.hard_links = hard_links;
+        self
+    }
+
+    /// Gets the backups directory.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Backs up a file before `operation` writes over it, returning `None` if the file doesn't exist yet.
+    ///
+    /// `changed` is the number of entries the operation changed, which is recorded so that backups can be told apart.
+    pub fn back_up<P: AsRef<Path>>(
+        &self,
+        path: P,
+        operation: &str,
+        changed: usize,
+    ) -> Result<Option<Backup>, Error> {
+        self.back_up_at(path.as_ref(), operation, changed, OffsetDateTime::now_utc())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.display(), operation))
+    )]
+    fn back_up_at(
+        &self,
+        path: &Path,
+        operation: &str,
+        changed: usize,
+        now: OffsetDateTime,
+    ) -> Result<Option<Backup>, Error> {
+        // The log only records whole seconds
+        let now = now.replace_nanosecond(0).unwrap_or(now);
+
+        let original = match std::path::absolute(path) {
+            Ok(original) if original.is_file() => original,
+            Ok(_) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        fs::create_dir_all(&self.directory)?;
+
+        let mut backups = self.read_log()?;
+        let id = unique_id(&backups, now);
+        let file_name = original.file_name().unwrap_or_default().to_string_lossy();
+        let backup_path = self.directory.join(format!("{}-{}", id, file_name));
+
+        let method = if self.hard_links && fs::hard_link(&original, &backup_path).is_ok() {
+            BackupMethod::HardLink
+        } else {
+            fs::copy(&original, &backup_path)?;
+            BackupMethod::Copy
+        };
+
+        let backup = Backup {
+            id,
+            operation: operation.to_string(),
+            changed,
+            md5: Md5::of_file(&backup_path)?,
+            size: fs::metadata(&backup_path)?.len(),
+            original,
+            path: backup_path,
+            created: now,
+            method,
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            id = %backup.id,
+            changed,
+            md5 = %backup.md5,
+            backup = %backup.path.display(),
+            method = ?backup.method,
+            "backed up file"
+        );
+
+        backups.push(backup.clone());
+        self.rotate(&mut backups, &backup.original)?;
+
+        Ok(Some(backup))
+    }
+
+    /// Lists the backups that still exist, newest first.
+    ///
+    /// Lines of the log that can't be read (e.g. because it was edited by hand) are skipped, so that one bad line
+    /// doesn't hide every backup.
+    pub fn list(&self) -> Result<Vec<Backup>, Error> {
+        let mut backups = self.read_log()?;
+        backups.retain(|backup| backup.path.is_file());
+        backups.reverse();
+
+        Ok(backups)
+    }
+
+    /// Lists the backups of one file that still exist, newest first.
+    pub fn list_for<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Backup>, Error> {
+        let original = std::path::absolute(path)?;
+        let mut backups = self.list()?;
+        backups.retain(|backup| backup.original == original);
+
+        Ok(backups)
+    }
+
+    /// Puts a backup back in place of the file it was made from, returning it.
+    ///
+    /// The file being replaced is backed up first, so restoring can be undone too. If the backup no longer matches
+    /// the hash of the original, [`Error::BackupChanged`] is returned and nothing is changed.
+    pub fn restore(&self, id: &str) -> Result<Backup, Error> {
+        let backup = self
+            .list()?
+            .into_iter()
+            .find(|backup| backup.id == id)
+            .ok_or_else(|| Error::BackupNotFound(id.to_string()))?;
+
+        // The backup is read before the current file is backed up, since that could remove it
+        let data = fs::read(&backup.path)?;
+
+        if Md5::of(&data) != backup.md5 {
+            return Err(Error::BackupChanged(backup.id));
+        }
+
+        self.back_up(&backup.original, &format!("restore {}", backup.id), 0)?;
+        write_file_replacing(&backup.original, &data)?;
+
+        #[cfg(feature = "tracing")]
+        tracing::info!(id, original = %backup.original.display(), "restored backup");
+
+        Ok(backup)
+    }
+
+    /// Reads every backup in the log, oldest first.
+    fn read_log(&self) -> Result<Vec<Backup>, Error> {
+        let file = match File::open(self.directory.join(LOG_NAME)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut backups = Vec::new();
+
+        for line in BufReader::new(file).lines() {
+            if let Ok(backup) = serde_json::from_str(&line?) {
+                backups.push(backup);
+            }
+        }
+
+        Ok(backups)
+    }
+
+    /// Removes the oldest backups of a file beyond the number to keep, then writes the log of the rest.
+    fn rotate(&self, backups: &mut Vec<Backup>, original: &Path) -> Result<(), Error> {
+        let count = backups
+            .iter()
+            .filter(|backup| backup.original == original)
+            .count();
+        let mut excess = count.saturating_sub(self.keep);
+
+        backups.retain(|backup| {
+            if excess == 0 || backup.original != original {
+                return true;
+            }
+
+            excess -= 1;
+
+            // A backup that can't be removed is forgotten anyway, so that it doesn't count towards the limit forever
+            if let Err(e) = fs::remove_file(&backup.path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(backup = %backup.path.display(), error = %e, "unable to remove old backup");
+                }
+            }
+
+            false
+        });
+
+        let mut log = Vec::new();
+
+        for backup in backups.iter() {
+            serde_json::to_writer(&mut log, backup).map_err(io::Error::from)?;
+            log.write_all(b"\n")?;
+        }
+
+        write_file_replacing(&self.directory.join(LOG_NAME), &log)?;
+        Ok(())
+    }
+}
+
+/// Makes an ID from the time a backup is made, numbering it after any other backups made in the same second so that
+/// IDs are never reused.
+fn unique_id(backups: &[Backup], now: OffsetDateTime) -> String {
+    let base = format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        now.year(),
+        now.month() as u8,
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second()
+    );
+
+    // The first backup in a second isn't numbered, so it counts as the first
+    let latest = backups
+        .iter()
+        .filter_map(|backup| match backup.id.strip_prefix(&base)? {
+            "" => Some(1),
+            suffix => suffix.strip_prefix('-')?.parse::<u32>().ok(),
+        })
+        .max();
+
+    match latest {
+        Some(n) => format!("{}-{}", base, n + 1),
+        None => base,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("osu-db-backups-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a new version of a file the same way saving does, by replacing it.
+    fn save(path: &Path, contents: &[u8]) {
+        write_file_replacing(path, contents).unwrap();
+    }
+
+    #[test]
+    fn old_backups_are_removed() {
+        let dir = temp_dir("rotation");
+        let osu_db = dir.join("osu!.db");
+        let collection_db = dir.join("collection.db");
+        let backups = Backups::beside(&osu_db).keep(3);
+
+        save(&collection_db, b"collections");
+        backups
+            .back_up_at(
+                &collection_db,
+                "collections",
+                1,
+                datetime!(2024-05-01 12:00 UTC),
+            )
+            .unwrap();
+
+        for i in 0..5u8 {
+            save(&osu_db, &[i; 100]);
+
+            let backup = backups
+                .back_up_at(
+                    &osu_db,
+                    "bulk-edit",
+                    i as usize,
+                    datetime!(2024-05-01 18:30 UTC),
+                )
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(backup.md5, Md5::of(&[i; 100]));
+            assert_eq!(backup.size, 100);
+        }
+
+        let listed = backups.list_for(&osu_db).unwrap();
+        let ids = listed.iter().map(|b| b.id.as_str()).collect::<Vec<_>>();
+
+        // Backups made in the same second are numbered, and only the newest three are kept
+        assert_eq!(
+            ids,
+            [
+                "20240501-183000-5",
+                "20240501-183000-4",
+                "20240501-183000-3"
+            ]
+        );
+        assert_eq!(listed[0].changed, 4);
+        assert_eq!(
+            fs::read_dir(backups.directory()).unwrap().count(),
+            3 + 1 + 1
+        );
+
+        // Backups of other files don't count towards the limit
+        let all = backups.list().unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[3].operation, "collections");
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn backups_are_restored() {
+        let dir = temp_dir("restore");
+        let osu_db = dir.join("osu!.db");
+        let backups = Backups::beside(&osu_db);
+
+        assert_eq!(backups.back_up(&osu_db, "repair", 0).unwrap(), None);
+
+        save(&osu_db, b"original");
+        let backup = backups.back_up(&osu_db, "bulk-edit", 3).unwrap().unwrap();
+        save(&osu_db, b"edited");
+
+        // The backup keeps the original contents even if it's a hard link, since saving replaces the file
+        assert_eq!(fs::read(&backup.path).unwrap(), b"original");
+
+        let restored = backups.restore(&backup.id).unwrap();
+        assert_eq!(restored, backup);
+        assert_eq!(fs::read(&osu_db).unwrap(), b"original");
+
+        // The edited file was backed up before being replaced
+        let listed = backups.list().unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].operation, format!("restore {}", backup.id));
+        assert_eq!(fs::read(&listed[0].path).unwrap(), b"edited");
+
+        assert!(matches!(
+            backups.restore("20000101-000000"),
+            Err(Error::BackupNotFound(_))
+        ));
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn changed_backups_are_not_restored() {
+        let dir = temp_dir("changed");
+        let osu_db = dir.join("osu!.db");
+        let backups = Backups::beside(&osu_db);
+
+        save(&osu_db, b"original");
+        let backup = backups.back_up(&osu_db, "bulk-edit", 1).unwrap().unwrap();
+
+        // Writing into the file rather than replacing it changes a hard linked backup too
+        fs::write(&osu_db, b"written in place").unwrap();
+        fs::write(&backup.path, b"written in place").unwrap();
+
+        assert!(matches!(
+            backups.restore(&backup.id),
+            Err(Error::BackupChanged(_))
+        ));
+        assert_eq!(fs::read(&osu_db).unwrap(), b"written in place");
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn backups_are_copied_by_default() {
+        let dir = temp_dir("copy");
+        let osu_db = dir.join("osu!.db");
+        let backups = Backups::beside(&osu_db);
+
+        save(&osu_db, b"original");
+        let backup = backups.back_up(&osu_db, "redact", 1).unwrap().unwrap();
+
+        assert_eq!(backup.method, BackupMethod::Copy);
+
+        // Writing into the original doesn't change a copy
+        fs::write(&osu_db, b"written in place").unwrap();
+        assert_eq!(fs::read(&backup.path).unwrap(), b"original");
+
+        backups.restore(&backup.id).unwrap();
+        assert_eq!(fs::read(&osu_db).unwrap(), b"original");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            assert_eq!(fs::metadata(&backup.path).unwrap().nlink(), 1);
+        }
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn backups_can_be_hard_links() {
+        let dir = temp_dir("link");
+        let osu_db = dir.join("osu!.db");
+        let backups = Backups::beside(&osu_db).hard_links(true);
+
+        save(&osu_db, b"original");
+        let backup = backups.back_up(&osu_db, "bulk-edit", 1).unwrap().unwrap();
+
+        assert_eq!(backup.method, BackupMethod::HardLink);
+
+        // Saving replaces the original, so the link keeps the old contents
+        save(&osu_db, b"edited");
+        assert_eq!(fs::read(&backup.path).unwrap(), b"original");
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn unreadable_log_lines_are_skipped() {
+        let dir = temp_dir("log");
+        let osu_db = dir.join("osu!.db");
+        let backups = Backups::beside(&osu_db);
+
+        save(&osu_db, b"original");
+        let backup = backups.back_up(&osu_db, "repair", 0).unwrap().unwrap();
+
+        let log_path = backups.directory().join(LOG_NAME);
+        let mut log = fs::read_to_string(&log_path).unwrap();
+        log.insert_str(0, "not json\n");
+        fs::write(&log_path, log).unwrap();
+
+        assert_eq!(backups.list().unwrap(), vec![backup]);
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -1,52 +1,215 @@
 //! Models for the main `osu.db` database file, which contains information on installed beatmaps.
 
-use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Read;
+#[cfg(feature = "writer")]
+use std::io::Write;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    mpsc,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::Path,
+};
 
 use flagset::{flags, FlagSet};
 use nom::{
-    bytes::complete::tag,
-    combinator::{cond, map},
+    combinator::{cond, map, verify},
     multi::length_count,
     number::complete::{le_f32, le_f64, le_u16, le_u32, u8},
-    sequence::{preceded, tuple},
+    sequence::tuple,
     IResult,
 };
-use time::OffsetDateTime;
+use time::{macros::datetime, Duration, OffsetDateTime};
 
+#[cfg(feature = "writer")]
+use crate::binary::{
+    write_boolean, write_gameplay_mode, write_int_double_pair, write_int_float_pair,
+    write_osu_string, write_windows_datetime,
+};
+#[cfg(all(feature = "writer", not(target_arch = "wasm32")))]
+use crate::common::write_file_replacing;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::raw::skip_beatmap_entry;
 use crate::{
-    common::{
-        boolean, gameplay_mode, osu_string, windows_datetime, GameplayMode, Grade, Mods, OsuString,
+    added::AddedDates,
+    binary::{
+        parse_boolean, parse_gameplay_mode, parse_int_double_pair, parse_int_float_pair,
+        parse_osu_string, parse_windows_datetime,
     },
+    common::{GameplayMode, Grade, Mods, OsuStr, OsuString, TimestampKind, WINDOWS_EPOCH},
+    detect::FileKind,
     error::Error,
 };
 
 /// Represents the `osu.db` file.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BeatmapListing {
     /// osu! version (e.g. 20150203)
     pub version: u32,
 
-    /// Folder count
+    /// Folder count. osu! doesn't always keep this up to date, so it may not match the number of distinct folders (see
+    /// [`BeatmapListing::folder_count_mismatch`]).
     pub folder_count: u32,
 
-    /// AccountUnlocked (only false when the account is locked or banned in any way)
+    /// AccountUnlocked (only false when the account is locked or banned in any way). Use
+    /// [`BeatmapListing::account_status`] rather than reading this and the unlock date directly.
     pub account_unlocked: bool,
 
-    /// Date the account will be unlocked
+    /// Date the account will be unlocked, which is only meaningful when the account is locked and may be a sentinel (see
+    /// [`AccountStatus`]). Stored as UTC.
     pub account_unlock_date: OffsetDateTime,
 
     /// Player name
     pub player_name: OsuString,
 
-    /// Beatmaps
+    /// Beatmaps, always in the order they're stored in the file, however the file was parsed (see
+    /// [`ParseOptions::threads`]). Anything that lists or exports the beatmaps keeps this order unless it's explicitly
+    /// asked to sort them, so that the output for the same file is always identical.
     pub beatmaps: Vec<BeatmapEntry>,
 
     /// User permissions
     pub user_permissions: FlagSet<UserPermissions>,
 }
 
+/// Number of bytes read from the start of an `osu.db` file when only its header is needed. The header's only
+/// variable-length field is the player name, which is far shorter than this.
+#[cfg(not(target_arch = "wasm32"))]
+const HEADER_PROBE_LENGTH: u64 = 1024;
+
+/// Number of beatmaps in each chunk parsed by a worker thread when parsing on several threads. Progress is reported, and
+/// cancellation noticed, after each chunk.
+#[cfg(not(target_arch = "wasm32"))]
+const PARALLEL_CHUNK_SIZE: usize = 256;
+
+/// Number of beatmaps parsed between each progress event when tracing is enabled.
+#[cfg(feature = "tracing")]
+const PROGRESS_EVENT_INTERVAL: usize = 1000;
+
+/// Represents the information at the start of an `osu.db` file, which can be read without parsing any beatmaps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListingHeader {
+    /// osu! version (e.g. 20150203)
+    pub version: u32,
+
+    /// Folder count
+    pub folder_count: u32,
+
+    /// AccountUnlocked (only false when the account is locked or banned in any way)
+    pub account_unlocked: bool,
+
+    /// Date the account will be unlocked, stored as UTC
+    pub account_unlock_date: OffsetDateTime,
+
+    /// Player name
+    pub player_name: OsuString,
+
+    /// Number of beatmaps in the file
+    pub beatmap_count: u32,
+}
+
+/// Newest osu! version whose `osu.db` layout is known. Files from newer versions are parsed using the newest known
+/// layout, on a best-effort basis (see [`FormatConfidence`]).
+pub const NEWEST_KNOWN_VERSION: u32 = 20260101;
+
+/// Represents how far a parsed listing can be trusted, depending on whether its version is known.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FormatConfidence {
+    /// The version's layout is known
+    #[default]
+    Known,
+
+    /// The version is newer than [`NEWEST_KNOWN_VERSION`], so the newest known layout was assumed. If osu! changed the
+    /// layout, values may be wrong and beatmaps after the first one that couldn't be parsed are missing, so these
+    /// listings can't be written (see [`Error::UnsupportedVersionForWrite`]).
+    BestEffort,
+}
+
+impl FormatConfidence {
+    /// Gets the confidence in the layout used to parse a version.
+    pub fn of_version(version: u32) -> Self {
+        if version > NEWEST_KNOWN_VERSION {
+            FormatConfidence::BestEffort
+        } else {
+            FormatConfidence::Known
+        }
+    }
+}
+
+/// How far apart a beatmap's modification time and the last time it was checked online can be before it's treated as
+/// possibly outdated (see [`BeatmapEntry::is_possibly_outdated`]).
+pub const DEFAULT_OUTDATED_TOLERANCE: Duration = Duration::days(30);
+
+/// .NET's `DateTime.MaxValue`, which is stored as the unlock date of accounts that are locked with no end date.
+pub const LOCKED_INDEFINITELY_DATE: OffsetDateTime = datetime!(9999-12-31 23:59:59.9999999 UTC);
+
+/// Represents whether the account that `osu.db` belongs to is locked (e.g. restricted or banned), which is stored as a
+/// flag and an unlock date in the header.
+///
+/// The unlock date is only meaningful while the account is locked. An account locked with no end date has an unlock
+/// date of [`LOCKED_INDEFINITELY_DATE`], but the Windows epoch (i.e. a date that was never set) is treated the same
+/// way, since there's nothing to wait for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AccountStatus {
+    Unlocked,
+    LockedUntil(OffsetDateTime),
+    LockedIndefinitely,
+}
+
+impl AccountStatus {
+    /// Interprets the account fields of an `osu.db` header.
+    pub fn from_header(unlocked: bool, unlock_date: OffsetDateTime) -> Self {
+        if unlocked {
+            AccountStatus::Unlocked
+        } else if unlock_date == WINDOWS_EPOCH
+            || unlock_date.year() >= LOCKED_INDEFINITELY_DATE.year()
+        {
+            AccountStatus::LockedIndefinitely
+        } else {
+            AccountStatus::LockedUntil(unlock_date)
+        }
+    }
+
+    /// Gets the values stored in an `osu.db` header for this status, i.e. whether the account is unlocked and its
+    /// unlock date. Unlocked accounts have an unlock date of the Windows epoch.
+    pub fn to_header(self) -> (bool, OffsetDateTime) {
+        match self {
+            AccountStatus::Unlocked => (true, WINDOWS_EPOCH),
+            AccountStatus::LockedUntil(date) => (false, date),
+            AccountStatus::LockedIndefinitely => (false, LOCKED_INDEFINITELY_DATE),
+        }
+    }
+}
+
+impl ListingHeader {
+    /// Clock that [`ListingHeader::account_unlock_date`] is stored from.
+    pub const ACCOUNT_UNLOCK_DATE_KIND: TimestampKind = TimestampKind::Utc;
+
+    /// Gets whether the account is locked, and until when (see [`AccountStatus`]).
+    pub fn account_status(&self) -> AccountStatus {
+        AccountStatus::from_header(self.account_unlocked, self.account_unlock_date)
+    }
+
+    /// Gets the name of the player, or `None` if there isn't one (see [`BeatmapListing::player_name_str`]).
+    pub fn player_name_str(&self) -> Option<&str> {
+        non_empty_name(self.player_name.as_deref())
+    }
+
+    /// Gets how far a listing with this header can be trusted (see [`FormatConfidence`]).
+    pub fn format_confidence(&self) -> FormatConfidence {
+        FormatConfidence::of_version(self.version)
+    }
+}
+
 /// Represents a beatmap entry found in `osu.db`.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BeatmapEntry {
     /// Size in bytes of the beatmap entry. Only present if version is less than 20191106.
     pub size: Option<u32>,
@@ -90,7 +253,7 @@ pub struct BeatmapEntry {
     /// Number of spinners (note: this will be present in every mode)
     pub spinner_count: u16,
 
-    /// Last modification time, Windows ticks
+    /// Last modification time, Windows ticks. Stored as UTC.
     pub last_modification_time: OffsetDateTime,
 
     /// Approach rate. Byte if the version is less than 20140609, Single otherwise.
@@ -106,18 +269,23 @@ pub struct BeatmapEntry {
     pub overall_difficulty: f32,
 
     /// Slider velocity
+    #[cfg_attr(feature = "serde", serde(with = "crate::common::non_finite_as_null"))]
     pub slider_velocity: f64,
 
-    /// Star Rating info for osu! standard. Only present if version is greater than or equal to 20140609.
+    /// Star Rating info for osu! standard. Only present if version is greater than or equal to 20140609. Ratings are stored
+    /// as Singles if the version is greater than or equal to 20250108, Doubles otherwise.
     pub star_ratings_std: Option<Vec<StarRating>>,
 
-    /// Star Rating info for Taiko. Only present if version is greater than or equal to 20140609.
+    /// Star Rating info for Taiko. Only present if version is greater than or equal to 20140609. Ratings are stored
+    /// as Singles if the version is greater than or equal to 20250108, Doubles otherwise.
     pub star_ratings_taiko: Option<Vec<StarRating>>,
 
-    /// Star Rating info for CTB. Only present if version is greater than or equal to 20140609.
+    /// Star Rating info for CTB. Only present if version is greater than or equal to 20140609. Ratings are stored
+    /// as Singles if the version is greater than or equal to 20250108, Doubles otherwise.
     pub star_ratings_ctb: Option<Vec<StarRating>>,
 
-    /// Star Rating info for osu!mania. Only present if version is greater than or equal to 20140609.
+    /// Star Rating info for osu!mania. Only present if version is greater than or equal to 20140609. Ratings are stored
+    /// as Singles if the version is greater than or equal to 20250108, Doubles otherwise.
     pub star_ratings_mania: Option<Vec<StarRating>>,
 
     /// Drain time, in seconds
@@ -157,6 +325,7 @@ pub struct BeatmapEntry {
     pub local_offset: u16,
 
     /// Stack leniency
+    #[cfg_attr(feature = "serde", serde(with = "crate::common::non_finite_as_null"))]
     pub stack_leniency: f32,
 
     /// osu! gameplay mode
@@ -177,7 +346,7 @@ pub struct BeatmapEntry {
     /// Is beatmap unplayed
     pub is_unplayed: bool,
 
-    /// Last time when beatmap was played
+    /// Last time when beatmap was played. Stored as local time (see [`TimestampKind`]).
     pub last_played: OffsetDateTime,
 
     /// Is the beatmap osz2
@@ -186,7 +355,7 @@ pub struct BeatmapEntry {
     /// Folder name of the beatmap, relative to Songs folder
     pub folder_name: OsuString,
 
-    /// Last time when beatmap was checked against osu! repository
+    /// Last time when beatmap was checked against osu! repository. Stored as local time (see [`TimestampKind`]).
     pub last_checked_online: OffsetDateTime,
 
     /// Ignore beatmap sound
@@ -215,8 +384,10 @@ pub struct BeatmapEntry {
 }
 
 /// Represents the ranked status of a beatmap.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RankedStatus {
+    #[default]
     Unknown = 0,
     Unsubmitted = 1,
 
@@ -232,27 +403,52 @@ pub enum RankedStatus {
 
 /// Represents a star rating calculation for a particular mod combination.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StarRating {
     /// The mods used for this star rating
     pub mods: FlagSet<Mods>,
 
     /// The calculated star rating
+    #[cfg_attr(feature = "serde", serde(with = "crate::common::non_finite_as_null"))]
     pub rating: f64,
 }
 
 /// Represents a timing point found in `osu.db`.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimingPoint {
-    /// The BPM of this timing point.
+    /// The raw BPM value of this timing point.
+    ///
+    /// For uninherited timing points, this is actually the length of a beat in milliseconds. For inherited timing
+    /// points, this is a negative inverse slider velocity multiplier (e.g. `-50` is 2x). Use [`TimingPoint::bpm()`] and
+    /// [`TimingPoint::slider_velocity_multiplier()`] to get the actual values.
     pub bpm: f64,
 
     /// The offset into the song.
     pub song_offset: f64,
 
     /// Whether this timing point is inherited.
+    ///
+    /// NOTE: osu! actually stores `true` here for *uninherited* timing points; see [`TimingPoint::is_uninherited()`].
     pub inherited: bool,
 }
 
+/// Summary of a beatmap's rhythm, as returned by [`BeatmapEntry::timing_summary`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimingSummary {
+    /// Number of valid uninherited timing points, i.e. timing sections
+    pub sections: usize,
+
+    /// Whether more than one BPM is used, after rounding to whole BPMs
+    pub variable_bpm: bool,
+
+    /// Duration of the longest part of the beatmap with a constant BPM, in milliseconds
+    pub longest_constant_bpm: f64,
+
+    /// Average slider velocity multiplier of the inherited timing points, if there are any
+    pub average_slider_velocity: Option<f64>,
+}
+
 flags! {
     /// Represents the available user permissions.
     pub enum UserPermissions : u32 {
@@ -281,330 +477,2618 @@ impl std::fmt::Display for RankedStatus {
     }
 }
 
-impl BeatmapListing {
-    /// Parses the contents of an `osu.db` file.
-    pub fn from_bytes(data: &[u8]) -> Result<BeatmapListing, Error> {
-        let (_, listing) = beatmap_listing(data).map_err(|e| e.to_owned())?;
-        Ok(listing)
+impl Default for BeatmapEntry {
+    fn default() -> Self {
+        Self {
+            size: None,
+            artist_name: None,
+            artist_name_unicode: None,
+            song_title: None,
+            song_title_unicode: None,
+            creator_name: None,
+            difficulty: None,
+            audio_filename: None,
+            md5: None,
+            beatmap_filename: None,
+            ranked_status: RankedStatus::default(),
+            hitcircle_count: 0,
+            slider_count: 0,
+            spinner_count: 0,
+            last_modification_time: WINDOWS_EPOCH,
+            approach_rate: 0.0,
+            circle_size: 0.0,
+            hp_drain: 0.0,
+            overall_difficulty: 0.0,
+            slider_velocity: 0.0,
+            star_ratings_std: None,
+            star_ratings_taiko: None,
+            star_ratings_ctb: None,
+            star_ratings_mania: None,
+            drain_time: 0,
+            total_time: 0,
+            audio_preview_time: 0,
+            timing_points: Vec::new(),
+            difficulty_id: 0,
+            beatmap_id: 0,
+            thread_id: 0,
+            grade_std: Grade::default(),
+            grade_taiko: Grade::default(),
+            grade_catch: Grade::default(),
+            grade_mania: Grade::default(),
+            local_offset: 0,
+            stack_leniency: 0.0,
+            gameplay_mode: GameplayMode::default(),
+            song_source: None,
+            song_tags: None,
+            online_offset: 0,
+            font: None,
+            is_unplayed: true,
+            last_played: WINDOWS_EPOCH,
+            is_osz2: false,
+            folder_name: None,
+            last_checked_online: WINDOWS_EPOCH,
+            ignore_beatmap_hitsounds: false,
+            ignore_beatmap_skin: false,
+            disable_storyboard: false,
+            disable_video: false,
+            visual_override: false,
+            unknown_u16: None,
+            unknown_u32: 0,
+            mania_scroll_speed: 0,
+        }
     }
+}
 
-    /// Convenience method for reading the contents of an `osu.db` file and parsing it as a `BeatmapListing`.
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<BeatmapListing, Error> {
-        let data = std::fs::read(path)?;
-        Self::from_bytes(&data)
+impl BeatmapEntry {
+    /// Clock that [`BeatmapEntry::last_modification_time`] is stored from.
+    pub const LAST_MODIFICATION_TIME_KIND: TimestampKind = TimestampKind::Utc;
+
+    /// Clock that [`BeatmapEntry::last_played`] is stored from.
+    pub const LAST_PLAYED_KIND: TimestampKind = TimestampKind::Local;
+
+    /// Clock that [`BeatmapEntry::last_checked_online`] is stored from.
+    pub const LAST_CHECKED_ONLINE_KIND: TimestampKind = TimestampKind::Local;
+
+    /// Gets the artist to show, preferring the Unicode name if `prefer_unicode` is set. If the preferred name is missing
+    /// or empty, the other one is used instead, and `None` is only returned if both are.
+    pub fn display_artist(&self, prefer_unicode: bool) -> Option<&str> {
+        preferred_text(&self.artist_name, &self.artist_name_unicode, prefer_unicode)
     }
-}
 
-/// Parses an `osu.db` file.
-fn beatmap_listing(input: &[u8]) -> IResult<&[u8], BeatmapListing> {
-    let (i, version) = le_u32(input)?;
-    let (i, folder_count) = le_u32(i)?;
-    let (i, account_unlocked) = boolean(i)?;
-    let (i, account_unlock_date) = windows_datetime(i)?;
-    let (i, player_name) = osu_string(i)?;
-    let (i, beatmaps) = length_count(le_u32, beatmap_entry(version))(i)?;
-    let (i, user_permissions) = user_permissions(i)?;
+    /// Gets the title to show, preferring the Unicode title if `prefer_unicode` is set (see
+    /// [`BeatmapEntry::display_artist`]).
+    pub fn display_title(&self, prefer_unicode: bool) -> Option<&str> {
+        preferred_text(&self.song_title, &self.song_title_unicode, prefer_unicode)
+    }
 
-    Ok((
-        i,
-        BeatmapListing {
-            version,
-            folder_count,
-            account_unlocked,
-            account_unlock_date,
-            player_name,
-            beatmaps,
-            user_permissions,
-        },
-    ))
-}
+    /// Gets the beatmap's name in the same style as osu!'s song select, e.g. `Artist - Title [Difficulty]`, with the
+    /// artist and title in the chosen language. Control characters are replaced, so the name is safe to show or print.
+    pub fn display_name(&self, language: MetadataLanguage) -> String {
+        let prefer_unicode = language.prefers_unicode();
+
+        format!(
+            "{} - {} [{}]",
+            self.display_artist(prefer_unicode).unwrap_or_default(),
+            self.display_title(prefer_unicode).unwrap_or_default(),
+            self.difficulty.as_deref().unwrap_or_default()
+        )
+        .sanitized()
+        .into_owned()
+    }
 
-/// Parses a beatmap entry in an `osu.db` file.
-fn beatmap_entry(version: u32) -> impl Fn(&[u8]) -> IResult<&[u8], BeatmapEntry> {
-    let parse_difficulty: fn(&[u8]) -> IResult<&[u8], f32> = if version < 20140609 {
-        |i: &[u8]| map(u8, |b| b as f32)(i)
-    } else {
-        |i: &[u8]| le_f32(i)
-    };
+    /// Gets the star ratings calculated for a particular gameplay mode, if present.
+    pub fn star_ratings(&self, mode: GameplayMode) -> Option<&[StarRating]> {
+        match mode {
+            GameplayMode::Standard => self.star_ratings_std.as_deref(),
+            GameplayMode::Taiko => self.star_ratings_taiko.as_deref(),
+            GameplayMode::Catch => self.star_ratings_ctb.as_deref(),
+            GameplayMode::Mania => self.star_ratings_mania.as_deref(),
+        }
+    }
 
-    move |input| {
-        let (i, size) = cond(version < 20191106, le_u32)(input)?;
-        let (i, artist_name) = osu_string(i)?;
-        let (i, artist_name_unicode) = osu_string(i)?;
-        let (i, song_title) = osu_string(i)?;
-        let (i, song_title_unicode) = osu_string(i)?;
-        let (i, creator_name) = osu_string(i)?;
-        let (i, difficulty) = osu_string(i)?;
-        let (i, audio_filename) = osu_string(i)?;
-        let (i, md5) = osu_string(i)?;
-        let (i, beatmap_filename) = osu_string(i)?;
+    /// Gets the star rating for a particular gameplay mode and mod combination, if it has been calculated.
+    pub fn star_rating(&self, mode: GameplayMode, mods: FlagSet<Mods>) -> Option<f64> {
+        self.star_ratings(mode)?
+            .iter()
+            .find(|star_rating| star_rating.mods == mods)
+            .map(|star_rating| star_rating.rating)
+    }
 
-        let (i, ranked_status) = ranked_status(i)?;
-        let (i, hitcircle_count) = le_u16(i)?;
-        let (i, slider_count) = le_u16(i)?;
-        let (i, spinner_count) = le_u16(i)?;
-        let (i, last_modification_time) = windows_datetime(i)?;
-        let (i, approach_rate) = parse_difficulty(i)?;
-        let (i, circle_size) = parse_difficulty(i)?;
-        let (i, hp_drain) = parse_difficulty(i)?;
-        let (i, overall_difficulty) = parse_difficulty(i)?;
-        let (i, slider_velocity) = le_f64(i)?;
+    /// Gets the NoMod star rating for this beatmap's gameplay mode, if it has been calculated.
+    pub fn nomod_star_rating(&self) -> Option<f64> {
+        self.star_rating(self.gameplay_mode, Mods::none())
+    }
 
-        let (i, star_ratings_std) = cond(version >= 20140609, star_ratings)(i)?;
-        let (i, star_ratings_taiko) = cond(version >= 20140609, star_ratings)(i)?;
-        let (i, star_ratings_ctb) = cond(version >= 20140609, star_ratings)(i)?;
-        let (i, star_ratings_mania) = cond(version >= 20140609, star_ratings)(i)?;
-        let (i, drain_time) = le_u32(i)?;
-        let (i, total_time) = le_u32(i)?;
-        let (i, audio_preview_time) = le_u32(i)?;
-        let (i, timing_points) = length_count(le_u32, timing_point)(i)?;
-        let (i, difficulty_id) = le_u32(i)?;
-        let (i, beatmap_id) = le_u32(i)?;
+    /// Gets the NoMod star rating chosen by a [`StarPolicy`], or `None` if it hasn't been calculated and the policy
+    /// excludes beatmaps without a rating.
+    pub fn policy_star_rating(&self, policy: StarPolicy) -> Option<f64> {
+        let rating = match policy.source {
+            StarSource::NativeMode => self.nomod_star_rating(),
+            StarSource::FixedMode(mode) => self.star_rating(mode, Mods::none()),
+            StarSource::PreferredWithFallback(mode) => self
+                .star_rating(mode, Mods::none())
+                .or_else(|| self.nomod_star_rating()),
+        };
+
+        policy.resolve(rating)
+    }
 
-        let (i, thread_id) = le_u32(i)?;
-        let (i, grade_std) = grade(i)?;
-        let (i, grade_taiko) = grade(i)?;
-        let (i, grade_catch) = grade(i)?;
-        let (i, grade_mania) = grade(i)?;
-        let (i, local_offset) = le_u16(i)?;
-        let (i, stack_leniency) = le_f32(i)?;
-        let (i, gameplay_mode) = gameplay_mode(i)?;
-        let (i, song_source) = osu_string(i)?;
-        let (i, song_tags) = osu_string(i)?;
+    /// Checks whether this beatmap might have an update (or local changes) that osu! hasn't caught up with, because its
+    /// `.osu` file was modified more than `tolerance` before or after it was last checked online.
+    ///
+    /// This is only a hint, since it doesn't ask the osu! website, but it's a cheap way to find candidates to check.
+    /// Unsubmitted beatmaps (and those with an unknown status), which have nothing to check against, and beatmaps with
+    /// either time unset are never outdated.
+    pub fn is_possibly_outdated(&self, tolerance: Duration) -> bool {
+        if matches!(
+            self.ranked_status,
+            RankedStatus::Unknown | RankedStatus::Unsubmitted
+        ) || self.last_modification_time == WINDOWS_EPOCH
+            || self.last_checked_online == WINDOWS_EPOCH
+        {
+            return false;
+        }
 
-        let (i, online_offset) = le_u16(i)?;
-        let (i, font) = osu_string(i)?;
-        let (i, is_unplayed) = boolean(i)?;
-        let (i, last_played) = windows_datetime(i)?;
-        let (i, is_osz2) = boolean(i)?;
-        let (i, folder_name) = osu_string(i)?;
-        let (i, last_checked_online) = windows_datetime(i)?;
-        let (i, ignore_beatmap_hitsounds) = boolean(i)?;
-        let (i, ignore_beatmap_skin) = boolean(i)?;
-        let (i, disable_storyboard) = boolean(i)?;
-
-        let (i, disable_video) = boolean(i)?;
-        let (i, visual_override) = boolean(i)?;
+        (self.last_modification_time - self.last_checked_online).abs() > tolerance
+    }
 
-        // NOTE: Unused u16 optional field, only present if version is less than 20140609
-        let (i, unknown_u16) = cond(version < 20140609, le_u16)(i)?;
+    /// Approximates when this beatmap was added to the library, which `osu!.db` doesn't store. When `songs_dir` is
+    /// given, the time the beatmap's folder was created is preferred over the last time its `.osu` file was modified
+    /// (see [`added`](crate::added)).
+    ///
+    /// Use [`AddedDates`] instead when looking at many beatmaps, so that each folder is only looked at once.
+    pub fn approximate_added_date(&self, songs_dir: Option<&Path>) -> Option<OffsetDateTime> {
+        AddedDates::new(songs_dir).added_date(self)
+    }
 
-        // NOTE: Unused u32 field (appears to be last modification time as well)
-        let (i, unknown_u32) = le_u32(i)?;
+    /// Gets the grade achieved on this beatmap for a particular gameplay mode.
+    pub fn grade(&self, mode: GameplayMode) -> Grade {
+        match mode {
+            GameplayMode::Standard => self.grade_std,
+            GameplayMode::Taiko => self.grade_taiko,
+            GameplayMode::Catch => self.grade_catch,
+            GameplayMode::Mania => self.grade_mania,
+        }
+    }
 
-        let (i, mania_scroll_speed) = u8(i)?;
+    /// Gets the lowest and highest BPM used in this beatmap, if it has any valid uninherited timing points.
+    pub fn bpm_range(&self) -> Option<(f64, f64)> {
+        self.timing_points
+            .iter()
+            .filter_map(|point| point.bpm())
+            .fold(None, |range, bpm| match range {
+                Some((min, max)) => Some((bpm.min(min), bpm.max(max))),
+                None => Some((bpm, bpm)),
+            })
+    }
 
-        Ok((
-            i,
-            BeatmapEntry {
-                size,
-                artist_name,
-                artist_name_unicode,
-                song_title,
-                song_title_unicode,
-                creator_name,
-                difficulty,
-                audio_filename,
-                md5,
-                beatmap_filename,
-                ranked_status,
-                hitcircle_count,
-                slider_count,
-                spinner_count,
-                last_modification_time,
-                approach_rate,
-                circle_size,
-                hp_drain,
-                overall_difficulty,
-                slider_velocity,
-                star_ratings_std,
-                star_ratings_taiko,
-                star_ratings_ctb,
-                star_ratings_mania,
-                drain_time,
-                total_time,
-                audio_preview_time,
-                timing_points,
-                difficulty_id,
-                beatmap_id,
-                thread_id,
-                grade_std,
-                grade_taiko,
-                grade_catch,
-                grade_mania,
-                local_offset,
-                stack_leniency,
-                gameplay_mode,
-                song_source,
-                song_tags,
-                online_offset,
-                font,
-                is_unplayed,
-                last_played,
-                is_osz2,
-                folder_name,
-                last_checked_online,
-                ignore_beatmap_hitsounds,
-                ignore_beatmap_skin,
-                disable_storyboard,
-                disable_video,
-                visual_override,
-                unknown_u16,
-                unknown_u32,
-                mania_scroll_speed,
-            },
-        ))
+    /// Gets the main BPM of this beatmap, i.e. the BPM used for the longest total duration (as shown in osu!).
+    pub fn main_bpm(&self) -> Option<f64> {
+        let sections = self
+            .timing_points
+            .iter()
+            .filter_map(|point| point.bpm().map(|bpm| (point.song_offset, bpm)))
+            .collect::<Vec<_>>();
+
+        // Each section lasts until the next one starts, with the last section lasting until the end of the beatmap
+        let mut durations: Vec<(f64, f64)> = Vec::new();
+
+        for (i, &(offset, bpm)) in sections.iter().enumerate() {
+            let end = sections
+                .get(i + 1)
+                .map(|&(next_offset, _)| next_offset)
+                .unwrap_or(self.total_time as f64);
+            let duration = (end - offset).max(0.0);
+
+            // Treat BPMs which only differ by rounding errors as the same
+            match durations
+                .iter_mut()
+                .find(|(other, _)| (other - bpm).abs() < 0.001)
+            {
+                Some((_, total)) => *total += duration,
+                None => durations.push((bpm, duration)),
+            }
+        }
+
+        durations
+            .into_iter()
+            .reduce(|main, other| if other.1 > main.1 { other } else { main })
+            .map(|(bpm, _)| bpm)
     }
-}
 
-/// Parses a ranked status value.
-fn ranked_status(input: &[u8]) -> IResult<&[u8], RankedStatus> {
-    use RankedStatus::*;
+    /// Summarises the timing points of this beatmap.
+    ///
+    /// Uninherited timing points with a negative beat length are skipped, since they don't set a valid BPM. Beatmaps
+    /// where every timing point is inherited shouldn't exist, but are summarised as having no timing sections.
+    pub fn timing_summary(&self) -> TimingSummary {
+        let sections = self
+            .timing_points
+            .iter()
+            .filter_map(|point| point.bpm().map(|bpm| (point.song_offset, bpm.round())))
+            .collect::<Vec<_>>();
+
+        let variable_bpm = sections.windows(2).any(|pair| pair[0].1 != pair[1].1);
+
+        // Neighbouring sections with the same BPM (e.g. to reset the metronome) count as a single constant section
+        let mut longest_constant_bpm: f64 = 0.0;
+        let mut constant = 0.0;
+
+        for (i, &(offset, bpm)) in sections.iter().enumerate() {
+            let end = sections
+                .get(i + 1)
+                .map(|&(next_offset, _)| next_offset)
+                .unwrap_or(self.total_time as f64);
+            let duration = (end - offset).max(0.0);
+
+            if i > 0 && sections[i - 1].1 == bpm {
+                constant += duration;
+            } else {
+                constant = duration;
+            }
+
+            longest_constant_bpm = longest_constant_bpm.max(constant);
+        }
 
-    let (i, status) = u8(input)?;
-    let status = match status {
-        0 => Unknown,
-        1 => Unsubmitted,
-        2 => Pending,
-        4 => Ranked,
-        5 => Approved,
-        6 => Qualified,
-        7 => Loved,
-        _ => {
-            return Err(nom::Err::Error(nom::error::Error {
-                input,
-                code: nom::error::ErrorKind::Switch,
-            }))
+        let multipliers = self
+            .timing_points
+            .iter()
+            .filter_map(|point| point.slider_velocity_multiplier())
+            .collect::<Vec<_>>();
+
+        TimingSummary {
+            sections: sections.len(),
+            variable_bpm,
+            longest_constant_bpm,
+            average_slider_velocity: (!multipliers.is_empty())
+                .then(|| multipliers.iter().sum::<f64>() / multipliers.len() as f64),
         }
-    };
+    }
 
-    Ok((i, status))
+    /// Gets the total number of hit objects in this beatmap.
+    pub fn object_count(&self) -> u32 {
+        self.hitcircle_count as u32 + self.slider_count as u32 + self.spinner_count as u32
+    }
+
+    /// Gets the number of keys this beatmap is played with in osu!mania. osu!standard beatmaps can be converted to
+    /// osu!mania, and get a key count from their difficulty settings and how many of their objects are sliders or
+    /// spinners, in the same way as osu! does. Beatmaps for the other modes can't be played in osu!mania.
+    pub fn mania_key_count(&self) -> Option<u32> {
+        // osu! rounds halves to even
+        let circle_size = (self.circle_size as f64).round_ties_even();
+        let overall_difficulty = (self.overall_difficulty as f64).round_ties_even();
+
+        match self.gameplay_mode {
+            GameplayMode::Mania => Some(circle_size.max(1.0) as u32),
+            GameplayMode::Standard => {
+                let long_objects = (self.slider_count as u32 + self.spinner_count as u32) as f32;
+                let long_ratio = long_objects / self.object_count() as f32;
+
+                let keys = if long_ratio < 0.2 {
+                    7.0
+                } else if long_ratio < 0.3 || circle_size >= 5.0 {
+                    if overall_difficulty > 5.0 {
+                        7.0
+                    } else {
+                        6.0
+                    }
+                } else if long_ratio > 0.6 {
+                    if overall_difficulty > 4.0 {
+                        5.0
+                    } else {
+                        4.0
+                    }
+                } else {
+                    (overall_difficulty + 1.0).clamp(4.0, 7.0)
+                };
+
+                Some(keys as u32)
+            }
+            GameplayMode::Taiko | GameplayMode::Catch => None,
+        }
+    }
 }
 
-/// Parses a grade value.
-fn grade(input: &[u8]) -> IResult<&[u8], Grade> {
-    use Grade::*;
+impl TimingPoint {
+    /// Checks whether this is an uninherited timing point (i.e. a red line), which sets the BPM.
+    pub fn is_uninherited(&self) -> bool {
+        self.inherited
+    }
 
-    let (i, grade) = u8(input)?;
-    let grade = match grade {
-        0 => SilverSS,
-        1 => SilverS,
-        2 => SS,
-        3 => S,
-        4 => A,
-        5 => B,
-        6 => C,
-        7 => D,
-        9 => Unplayed,
-        _ => {
-            return Err(nom::Err::Error(nom::error::Error {
-                input,
-                code: nom::error::ErrorKind::Switch,
-            }))
-        }
-    };
+    /// Gets the BPM set by this timing point, if it is a valid uninherited timing point.
+    pub fn bpm(&self) -> Option<f64> {
+        (self.is_uninherited() && self.bpm > 0.0).then(|| 60_000.0 / self.bpm)
+    }
 
-    Ok((i, grade))
+    /// Gets the slider velocity multiplier set by this timing point, if it is a valid inherited timing point.
+    pub fn slider_velocity_multiplier(&self) -> Option<f64> {
+        (!self.is_uninherited() && self.bpm < 0.0).then(|| -100.0 / self.bpm)
+    }
 }
 
-/// Parses a integer-double pair found in `osu.db`.
-fn int_double_pair(input: &[u8]) -> IResult<&[u8], (u32, f64)> {
-    let (i, int) = preceded(tag(&[0x08]), le_u32)(input)?;
-    let (i, double) = preceded(tag(&[0x0d]), le_f64)(i)?;
+/// Represents the result of leniently parsing an `osu.db` file with [`BeatmapListing::from_bytes_lenient`].
+#[derive(Debug)]
+pub struct PartialListing {
+    /// Listing containing the beatmaps that were parsed successfully
+    pub listing: BeatmapListing,
 
-    Ok((i, (int, double)))
+    /// Number of beatmaps that were skipped because of `error`
+    pub skipped: usize,
+
+    /// Error which stopped the remaining beatmaps from being parsed, if any
+    pub error: Option<Error>,
+
+    /// Byte range of each beatmap in `listing` within the parsed data, including the entry size for versions that store
+    /// it. Only recorded if [`ParseOptions::record_spans`] is set, otherwise this is empty.
+    pub spans: Vec<Range<usize>>,
 }
 
-/// Parses a timing point found in `osu.db`.
-fn timing_point(input: &[u8]) -> IResult<&[u8], TimingPoint> {
-    map(
-        tuple((le_f64, le_f64, boolean)),
-        |(bpm, song_offset, inherited)| TimingPoint {
-            bpm,
-            song_offset,
-            inherited,
-        },
-    )(input)
+impl PartialListing {
+    /// Gets the byte range of a beatmap within the parsed data, if spans were recorded.
+    pub fn span(&self, index: usize) -> Option<Range<usize>> {
+        self.spans.get(index).cloned()
+    }
+}
+
+/// Represents which version of a beatmap's artist and title to show, when both a romanised and a Unicode version are
+/// stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MetadataLanguage {
+    /// The romanised artist and title, as shown by osu! by default
+    #[default]
+    Romanised,
+
+    /// The artist and title in their original language, e.g. Japanese
+    Unicode,
+}
+
+impl MetadataLanguage {
+    /// Checks whether the Unicode artist and title are preferred.
+    pub fn prefers_unicode(self) -> bool {
+        self == MetadataLanguage::Unicode
+    }
+}
+
+/// Represents which gameplay mode's star rating is used when a beatmap needs a single star rating, e.g. for the `stars`
+/// filter or the Stars column.
+///
+/// osu!standard beatmaps have star ratings calculated for every mode they can be converted to, while beatmaps for the
+/// other modes only have a star rating for their own mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StarSource {
+    /// The star rating for the beatmap's own gameplay mode, e.g. osu!mania beatmaps by their osu!mania rating and
+    /// osu!standard beatmaps by their osu!standard rating
+    #[default]
+    NativeMode,
+
+    /// The star rating for a particular gameplay mode, which beatmaps for the other modes don't have
+    FixedMode(GameplayMode),
+
+    /// The star rating for a particular gameplay mode if it has been calculated, otherwise the beatmap's own mode
+    PreferredWithFallback(GameplayMode),
+}
+
+/// Represents how a single star rating is chosen for each beatmap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct StarPolicy {
+    pub source: StarSource,
+
+    /// Whether beatmaps without the chosen star rating are treated as 0 stars, rather than having no star rating (so
+    /// they never match a `stars` filter, and are sorted last)
+    pub missing_as_zero: bool,
+}
+
+impl StarPolicy {
+    /// Applies this policy's handling of missing star ratings to a star rating.
+    pub fn resolve(&self, rating: Option<f64>) -> Option<f64> {
+        match rating {
+            None if self.missing_as_zero => Some(0.0),
+            rating => rating,
+        }
+    }
+}
+
+/// Represents how NaN and infinite values are handled when parsing star ratings, slider velocities and stack
+/// leniencies, which some real databases contain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FloatPolicy {
+    /// Keep the values as they are
+    #[default]
+    Preserve,
+
+    /// Drop non-finite star ratings, so that the beatmap has no star rating for those mods. Slider velocities and stack
+    /// leniencies can't be missing, so they're set to 0 instead.
+    ReplaceWithNone,
+
+    /// Treat a beatmap with a non-finite value as invalid, so parsing stops there (see [`Error::NonFiniteFloat`])
+    Error,
+}
+
+impl FloatPolicy {
+    /// Applies this policy to a parsed beatmap. If the policy is [`FloatPolicy::Error`], the name of the first
+    /// non-finite field is returned as the error.
+    pub fn apply(&self, beatmap: &mut BeatmapEntry) -> Result<(), &'static str> {
+        match self {
+            FloatPolicy::Preserve => Ok(()),
+            FloatPolicy::ReplaceWithNone => {
+                for star_ratings in [
+                    &mut beatmap.star_ratings_std,
+                    &mut beatmap.star_ratings_taiko,
+                    &mut beatmap.star_ratings_ctb,
+                    &mut beatmap.star_ratings_mania,
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    star_ratings.retain(|star_rating| star_rating.rating.is_finite());
+                }
+
+                if !beatmap.slider_velocity.is_finite() {
+                    beatmap.slider_velocity = 0.0;
+                }
+
+                if !beatmap.stack_leniency.is_finite() {
+                    beatmap.stack_leniency = 0.0;
+                }
+
+                Ok(())
+            }
+            FloatPolicy::Error => {
+                let star_ratings = [
+                    &beatmap.star_ratings_std,
+                    &beatmap.star_ratings_taiko,
+                    &beatmap.star_ratings_ctb,
+                    &beatmap.star_ratings_mania,
+                ];
+
+                if star_ratings
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .any(|star_rating| !star_rating.rating.is_finite())
+                {
+                    Err("star rating")
+                } else if !beatmap.slider_velocity.is_finite() {
+                    Err("slider velocity")
+                } else if !beatmap.stack_leniency.is_finite() {
+                    Err("stack leniency")
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Options for parsing an `osu.db` file with [`BeatmapListing::from_bytes_with_options`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Record where each beatmap entry is in the data, in [`PartialListing::spans`]
+    pub record_spans: bool,
+
+    /// Number of threads to parse beatmap entries on. By default (or with 0 or 1 threads), the entries are parsed on
+    /// the calling thread.
+    ///
+    /// The result is always identical to parsing on one thread, with the beatmaps in file order. If the entries can't
+    /// be split up (e.g. the file is damaged), they're parsed on one thread instead, so that errors are reported in the
+    /// same way. Progress is reported after each chunk of entries is parsed, rather than after each entry. Threads
+    /// aren't available on the web, so this is ignored there.
+    pub threads: usize,
+
+    /// How NaN and infinite star ratings, slider velocities and stack leniencies are handled
+    pub float_policy: FloatPolicy,
+}
+
+/// Options for writing an `osu.db` file with [`BeatmapListing::to_bytes_with_options`].
+#[cfg(feature = "writer")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Correct header values that osu! doesn't keep up to date, i.e. write the number of distinct folders as the folder
+    /// count (see [`BeatmapListing::fix_folder_count`])
+    pub normalize: bool,
+}
+
+/// Represents a beatmapset, i.e. the difficulties that share a folder, as returned by [`BeatmapListing::beatmapsets`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Beatmapset {
+    /// Name of the folder containing the difficulties
+    pub folder_name: OsuString,
+
+    /// Indices of the difficulties in the listing, in listing order
+    pub beatmaps: Vec<usize>,
+}
+
+impl BeatmapListing {
+    /// Clock that [`BeatmapListing::account_unlock_date`] is stored from.
+    pub const ACCOUNT_UNLOCK_DATE_KIND: TimestampKind = ListingHeader::ACCOUNT_UNLOCK_DATE_KIND;
+
+    /// Parses the contents of an `osu.db` file.
+    pub fn from_bytes(data: &[u8]) -> Result<BeatmapListing, Error> {
+        Self::from_bytes_with_progress(data, |_, _| true)
+    }
+
+    /// Parses the contents of an `osu.db` file, reporting progress after each beatmap.
+    ///
+    /// `progress` is called with the number of beatmaps parsed so far and the total number of beatmaps. Returning
+    /// `false` cancels parsing, in which case [`Error::Cancelled`] is returned.
+    ///
+    /// Files from versions newer than [`NEWEST_KNOWN_VERSION`] are parsed leniently (see
+    /// [`BeatmapListing::from_bytes_lenient`]), since an entry that can't be parsed probably means that osu! changed the
+    /// layout rather than that the file is damaged. Check [`BeatmapListing::format_confidence`] before trusting them.
+    pub fn from_bytes_with_progress<F>(data: &[u8], progress: F) -> Result<BeatmapListing, Error>
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        let partial = beatmap_listing(data, ParseOptions::default(), progress)?;
+
+        match partial.error {
+            Some(e) if partial.listing.format_confidence() == FormatConfidence::Known => Err(e),
+            Some(_e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    version = partial.listing.version,
+                    skipped = partial.skipped,
+                    error = %_e,
+                    "skipped beatmaps from a newer version of osu! than is known"
+                );
+
+                Ok(partial.listing)
+            }
+            None => Ok(partial.listing),
+        }
+    }
+
+    /// Parses the contents of an `osu.db` file, keeping the beatmaps before the first one that can't be parsed.
+    ///
+    /// Since beatmap entries don't record their length, parsing can't continue after an invalid entry, so all of the
+    /// beatmaps after it are skipped. Errors are only returned if the file header is invalid or parsing is cancelled
+    /// (see [`BeatmapListing::from_bytes_with_progress`]).
+    pub fn from_bytes_lenient<F>(data: &[u8], progress: F) -> Result<PartialListing, Error>
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        Self::from_bytes_with_options(data, ParseOptions::default(), progress)
+    }
+
+    /// Leniently parses the contents of an `osu.db` file like [`BeatmapListing::from_bytes_lenient`], with the given
+    /// options.
+    pub fn from_bytes_with_options<F>(
+        data: &[u8],
+        options: ParseOptions,
+        progress: F,
+    ) -> Result<PartialListing, Error>
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        let partial = beatmap_listing(data, options, progress)?;
+
+        #[cfg(feature = "tracing")]
+        if let Some(e) = &partial.error {
+            tracing::debug!(
+                skipped = partial.skipped,
+                error = %e,
+                "skipped beatmaps after an invalid entry"
+            );
+        }
+
+        Ok(partial)
+    }
+
+    /// Convenience method for reading the contents of an `osu.db` file and parsing it as a `BeatmapListing`.
+    ///
+    /// The file can be read while osu! has it open, and is tried again for a short while if osu! is writing it (see
+    /// [`shared`](crate::shared)).
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<BeatmapListing, Error> {
+        let data = crate::shared::read_database(path, FileKind::Beatmaps)?;
+        Self::from_bytes(&data)
+    }
+
+    /// Encodes the listing in the `osu.db` format, using the field layout for the listing's version.
+    ///
+    /// Entry sizes (for versions before 20191106) are recalculated rather than using [`BeatmapEntry::size`], so that
+    /// edited entries are written correctly.
+    ///
+    /// The version isn't checked, so listings from versions newer than [`NEWEST_KNOWN_VERSION`] are encoded with the
+    /// newest known layout. Only [`BeatmapListing::to_file`] refuses to write them.
+    #[cfg(feature = "writer")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(beatmaps = self.beatmaps.len()))
+    )]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_options(WriteOptions::default())
+    }
+
+    /// Encodes the listing in the `osu.db` format like [`BeatmapListing::to_bytes`], with the given options. The listing
+    /// itself isn't changed.
+    #[cfg(feature = "writer")]
+    pub fn to_bytes_with_options(&self, options: WriteOptions) -> Vec<u8> {
+        let folder_count = if options.normalize {
+            self.distinct_folder_count()
+        } else {
+            self.folder_count
+        };
+
+        let mut output = Vec::new();
+        output.extend_from_slice(&self.version.to_le_bytes());
+        output.extend_from_slice(&folder_count.to_le_bytes());
+        write_boolean(&mut output, self.account_unlocked);
+        write_windows_datetime(&mut output, self.account_unlock_date);
+        write_osu_string(&mut output, &self.player_name);
+        output.extend_from_slice(&(self.beatmaps.len() as u32).to_le_bytes());
+
+        for beatmap in &self.beatmaps {
+            let entry = beatmap_entry_bytes(self.version, beatmap);
+
+            if self.version < 20191106 {
+                output.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+            }
+
+            output.extend_from_slice(&entry);
+        }
+
+        output.extend_from_slice(&self.user_permissions.bits().to_le_bytes());
+        output
+    }
+
+    /// Writes the listing in the `osu.db` format, e.g. into an archive, with the given options. Like
+    /// [`BeatmapListing::to_file`], listings from versions newer than [`NEWEST_KNOWN_VERSION`] aren't written.
+    #[cfg(feature = "writer")]
+    pub fn write_to<W: Write>(&self, mut writer: W, options: WriteOptions) -> Result<(), Error> {
+        self.check_writable()?;
+        writer.write_all(&self.to_bytes_with_options(options))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the listing to an `osu.db` file.
+    ///
+    /// The listing is written to a temporary file next to `path`, which then replaces the original file. This means
+    /// that the original file is left untouched if anything goes wrong while writing.
+    ///
+    /// Listings from versions newer than [`NEWEST_KNOWN_VERSION`] aren't written, since osu! might not be able to read
+    /// them back, in which case [`Error::UnsupportedVersionForWrite`] is returned.
+    #[cfg(all(feature = "writer", not(target_arch = "wasm32")))]
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.to_file_with_options(path, WriteOptions::default())
+    }
+
+    /// Writes the listing to an `osu.db` file like [`BeatmapListing::to_file`], with the given options.
+    #[cfg(all(feature = "writer", not(target_arch = "wasm32")))]
+    pub fn to_file_with_options<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: WriteOptions,
+    ) -> Result<(), Error> {
+        self.check_writable()?;
+        write_file_replacing(path.as_ref(), &self.to_bytes_with_options(options))
+            .map_err(Error::from)
+    }
+
+    /// Gets how far the listing can be trusted, depending on whether its version is known (see [`FormatConfidence`]).
+    pub fn format_confidence(&self) -> FormatConfidence {
+        FormatConfidence::of_version(self.version)
+    }
+
+    /// Checks whether the listing can be written, i.e. its version is known.
+    #[cfg(feature = "writer")]
+    pub fn check_writable(&self) -> Result<(), Error> {
+        match self.format_confidence() {
+            FormatConfidence::Known => Ok(()),
+            FormatConfidence::BestEffort => Err(Error::UnsupportedVersionForWrite(self.version)),
+        }
+    }
+
+    /// Creates a copy of the listing which only contains the beatmaps that `keep` returns `true` for. `keep` is called
+    /// with each beatmap's index in the listing. The folder count is updated to match the remaining beatmaps.
+    ///
+    /// This can be used with [`BeatmapListing::to_file`] to write a cleaned up copy of the listing.
+    pub fn subset<F>(&self, mut keep: F) -> BeatmapListing
+    where
+        F: FnMut(usize, &BeatmapEntry) -> bool,
+    {
+        let mut subset = BeatmapListing {
+            beatmaps: self
+                .beatmaps
+                .iter()
+                .enumerate()
+                .filter(|(i, beatmap)| keep(*i, beatmap))
+                .map(|(_, beatmap)| beatmap.clone())
+                .collect(),
+            player_name: self.player_name.clone(),
+            ..*self
+        };
+
+        subset.fix_folder_count();
+        subset
+    }
+
+    /// Removes the beatmaps that `keep` returns `false` for, like [`BeatmapListing::subset`] but in place. The folder
+    /// count is updated to match the remaining beatmaps.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(usize, &BeatmapEntry) -> bool,
+    {
+        let mut i = 0;
+        self.beatmaps.retain(|beatmap| {
+            let kept = keep(i, beatmap);
+            i += 1;
+            kept
+        });
+
+        self.fix_folder_count();
+    }
+
+    /// Adds the beatmaps from `other` which aren't already in the listing, returning how many were added. Beatmaps are
+    /// matched by their MD5 hash, so beatmaps without one are always added. The folder count is updated to include the
+    /// added beatmaps.
+    ///
+    /// The header of `other` is ignored, so the listing keeps its own version, player name and permissions.
+    pub fn merge(&mut self, other: &BeatmapListing) -> usize {
+        let mut existing = self
+            .beatmaps
+            .iter()
+            .filter_map(|beatmap| beatmap.md5.clone())
+            .collect::<HashSet<_>>();
+
+        let before = self.beatmaps.len();
+        for beatmap in &other.beatmaps {
+            let new = match &beatmap.md5 {
+                Some(md5) => existing.insert(md5.clone()),
+                None => true,
+            };
+
+            if new {
+                self.beatmaps.push(beatmap.clone());
+            }
+        }
+
+        self.fix_folder_count();
+        self.beatmaps.len() - before
+    }
+
+    /// Counts the distinct folders that the beatmaps are stored in. Folder names are compared case-insensitively, since
+    /// they refer to the same folder on Windows. Beatmaps without a folder name aren't counted.
+    pub fn distinct_folder_count(&self) -> u32 {
+        self.beatmaps
+            .iter()
+            .filter_map(|beatmap| beatmap.folder_name.as_deref())
+            .filter(|folder| !folder.is_empty())
+            .map(str::to_lowercase)
+            .collect::<HashSet<_>>()
+            .len() as u32
+    }
+
+    /// Gets the stored folder count and the actual number of distinct folders (see
+    /// [`BeatmapListing::distinct_folder_count`]), if they don't match.
+    ///
+    /// osu! itself doesn't always keep the folder count up to date, so a mismatch doesn't mean the file is damaged, but
+    /// some third-party tools rely on it.
+    pub fn folder_count_mismatch(&self) -> Option<(u32, u32)> {
+        let actual = self.distinct_folder_count();
+        (self.folder_count != actual).then_some((self.folder_count, actual))
+    }
+
+    /// Gets whether the account is locked, and until when (see [`AccountStatus`]).
+    pub fn account_status(&self) -> AccountStatus {
+        AccountStatus::from_header(self.account_unlocked, self.account_unlock_date)
+    }
+
+    /// Gets the name of the player, or `None` if there isn't one.
+    ///
+    /// Fresh installations and guest sessions store either no name or an empty one, which are both treated as no name
+    /// here. [`player_name`](Self::player_name) keeps whichever was parsed, so that it's written back the same way.
+    pub fn player_name_str(&self) -> Option<&str> {
+        non_empty_name(self.player_name.as_deref())
+    }
+
+    /// Gets the beatmaps which might have an update that osu! hasn't downloaded, in file order (see
+    /// [`BeatmapEntry::is_possibly_outdated`]).
+    pub fn possibly_outdated(&self, tolerance: Duration) -> Vec<&BeatmapEntry> {
+        self.beatmaps
+            .iter()
+            .filter(|beatmap| beatmap.is_possibly_outdated(tolerance))
+            .collect()
+    }
+
+    /// Gets the beatmaps which were added at or after `since`, with when they were added, newest first (see
+    /// [`BeatmapEntry::approximate_added_date`]).
+    pub fn recently_added(
+        &self,
+        since: OffsetDateTime,
+        songs_dir: Option<&Path>,
+    ) -> Vec<(&BeatmapEntry, OffsetDateTime)> {
+        let mut dates = AddedDates::new(songs_dir);
+        let mut recent = self
+            .beatmaps
+            .iter()
+            .filter_map(|beatmap| Some((beatmap, dates.added_date(beatmap)?)))
+            .filter(|&(_, added)| added >= since)
+            .collect::<Vec<_>>();
+
+        recent.sort_by(|(_, a), (_, b)| b.cmp(a));
+        recent
+    }
+
+    /// Sets whether the account is locked, updating both the flag and the unlock date so that they're written
+    /// consistently.
+    pub fn set_account_status(&mut self, status: AccountStatus) {
+        (self.account_unlocked, self.account_unlock_date) = status.to_header();
+    }
+
+    /// Sets the folder count to the number of distinct folders (see [`BeatmapListing::distinct_folder_count`]).
+    pub fn fix_folder_count(&mut self) {
+        self.folder_count = self.distinct_folder_count();
+    }
+
+    /// Groups the beatmaps into beatmapsets by their folder name, in the order that each set first appears.
+    ///
+    /// Folder names are used rather than beatmapset IDs so that unsubmitted beatmaps are grouped too. Beatmaps without a
+    /// folder name can't be grouped, so each one is put in a set of its own.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(beatmaps = self.beatmaps.len()))
+    )]
+    pub fn beatmapsets(&self) -> Vec<Beatmapset> {
+        let mut sets: Vec<Beatmapset> = Vec::new();
+        let mut by_folder: HashMap<&str, usize> = HashMap::new();
+
+        for (i, beatmap) in self.beatmaps.iter().enumerate() {
+            let existing = beatmap
+                .folder_name
+                .as_deref()
+                .and_then(|folder| by_folder.get(folder).copied());
+
+            match existing {
+                Some(set) => sets[set].beatmaps.push(i),
+                None => {
+                    if let Some(folder) = beatmap.folder_name.as_deref() {
+                        by_folder.insert(folder, sets.len());
+                    }
+
+                    sets.push(Beatmapset {
+                        folder_name: beatmap.folder_name.clone(),
+                        beatmaps: vec![i],
+                    });
+                }
+            }
+        }
+
+        sets
+    }
+
+    /// Creates a lookup of beatmaps by their MD5 hash. Beatmaps without an MD5 hash are skipped.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(beatmaps = self.beatmaps.len()))
+    )]
+    pub fn beatmaps_by_md5(&self) -> HashMap<&str, &BeatmapEntry> {
+        self.beatmaps
+            .iter()
+            .filter_map(|b| b.md5.as_deref().map(|md5| (md5, b)))
+            .collect()
+    }
+}
+
+impl ListingHeader {
+    /// Parses the header at the start of an `osu.db` file's contents, checking that its version looks like the date of
+    /// an osu! release so that other files are rejected.
+    pub fn from_bytes(data: &[u8]) -> Result<ListingHeader, Error> {
+        let (_, header) = verify(listing_header, |header: &ListingHeader| {
+            (20070000..=29991231).contains(&header.version)
+        })(data)
+        .map_err(|e| Error::parse(FileKind::Beatmaps, data, e))?;
+
+        Ok(header)
+    }
+
+    /// Reads just the header of an `osu.db` file, e.g. to check that a file is an `osu.db` without reading and parsing
+    /// every beatmap.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ListingHeader, Error> {
+        let mut data = Vec::new();
+        crate::shared::open(path)?
+            .take(HEADER_PROBE_LENGTH)
+            .read_to_end(&mut data)?;
+
+        Self::from_bytes(&data)
+    }
+}
+
+/// Chooses between the romanised and Unicode versions of some text, falling back to the other version if the preferred
+/// one is missing or empty.
+fn preferred_text<'a>(
+    romanised: &'a OsuString,
+    unicode: &'a OsuString,
+    prefer_unicode: bool,
+) -> Option<&'a str> {
+    let (preferred, fallback) = if prefer_unicode {
+        (unicode, romanised)
+    } else {
+        (romanised, unicode)
+    };
+
+    [preferred, fallback]
+        .into_iter()
+        .filter_map(|text| text.as_deref())
+        .find(|text| !text.is_empty())
+}
+
+/// Treats a missing, empty or blank player name as no name.
+fn non_empty_name(name: Option<&str>) -> Option<&str> {
+    name.filter(|name| !name.trim().is_empty())
+}
+
+/// Parses the header of an `osu.db` file, which comes before the beatmaps.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", name = "parse_header", skip_all)
+)]
+pub(crate) fn listing_header(input: &[u8]) -> IResult<&[u8], ListingHeader> {
+    let (i, (version, folder_count, account_unlocked, account_unlock_date, player_name)) =
+        tuple((
+            le_u32,
+            le_u32,
+            parse_boolean,
+            parse_windows_datetime,
+            parse_osu_string,
+        ))(input)?;
+    let (i, beatmap_count) = le_u32(i)?;
+
+    Ok((
+        i,
+        ListingHeader {
+            version,
+            folder_count,
+            account_unlocked,
+            account_unlock_date,
+            player_name,
+            beatmap_count,
+        },
+    ))
+}
+
+/// Parses an `osu.db` file, one beatmap at a time so that progress can be reported.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "parse_osu_db", skip_all, fields(bytes = data.len()))
+)]
+fn beatmap_listing<F>(
+    data: &[u8],
+    options: ParseOptions,
+    mut progress: F,
+) -> Result<PartialListing, Error>
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    let (
+        mut i,
+        ListingHeader {
+            version,
+            folder_count,
+            account_unlocked,
+            account_unlock_date,
+            player_name,
+            beatmap_count,
+        },
+    ) = listing_header(data).map_err(|e| Error::parse(FileKind::Beatmaps, data, e))?;
+
+    let total = beatmap_count as usize;
+    let parse_entry = beatmap_entry(version);
+
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse_entries", version, total).entered();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let parallel = match options.threads {
+        0 | 1 => None,
+        threads => parse_entries_parallel(data, i, version, total, threads, &mut progress)?,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    let parallel: Option<ParsedEntries> = None;
+
+    // Non-finite values are left for the sequential parser to report, so that the beatmaps before them are kept
+    let parallel = parallel.and_then(|(mut beatmaps, spans)| {
+        beatmaps
+            .iter_mut()
+            .try_for_each(|beatmap| options.float_policy.apply(beatmap))
+            .ok()
+            .map(|()| (beatmaps, spans))
+    });
+
+    if let Some((beatmaps, spans)) = parallel {
+        let rest = spans.last().map_or(i, |span| &data[span.end..]);
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            parsed = beatmaps.len(),
+            total,
+            threads = options.threads,
+            "parsed beatmaps"
+        );
+
+        return Ok(PartialListing {
+            skipped: 0,
+            listing: BeatmapListing {
+                version,
+                folder_count,
+                account_unlocked,
+                account_unlock_date,
+                player_name,
+                beatmaps,
+                user_permissions: user_permissions(rest)
+                    .map_err(|e| Error::parse(FileKind::Beatmaps, data, e))?
+                    .1,
+            },
+            error: None,
+            spans: if options.record_spans {
+                spans
+            } else {
+                Vec::new()
+            },
+        });
+    }
+
+    // Don't trust the count too much when allocating, in case the file is corrupt
+    let mut beatmaps = Vec::with_capacity(total.min(data.len() / 64));
+    let mut spans = Vec::new();
+    let mut error = None;
+
+    for index in 0..total {
+        match parse_entry(i) {
+            Ok((rest, mut beatmap)) => {
+                if let Err(field) = options.float_policy.apply(&mut beatmap) {
+                    error = Some(Error::NonFiniteFloat {
+                        index,
+                        total,
+                        entry_offset: data.len() - i.len(),
+                        field,
+                    });
+
+                    break;
+                }
+
+                // Entry sizes aren't stored by newer versions, so spans are found from how much of the data was used
+                if options.record_spans {
+                    spans.push(data.len() - i.len()..data.len() - rest.len());
+                }
+
+                i = rest;
+                beatmaps.push(beatmap);
+            }
+            Err(e) => {
+                let (offset, kind) = match e {
+                    nom::Err::Error(e) | nom::Err::Failure(e) => {
+                        (data.len() - e.input.len(), e.code)
+                    }
+                    nom::Err::Incomplete(_) => (data.len(), nom::error::ErrorKind::Eof),
+                };
+
+                error = Some(Error::Beatmap {
+                    index,
+                    total,
+                    entry_offset: data.len() - i.len(),
+                    offset,
+                    kind,
+                });
+
+                break;
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        if (index + 1) % PROGRESS_EVENT_INTERVAL == 0 {
+            tracing::debug!(
+                parsed = index + 1,
+                total,
+                offset = data.len() - i.len(),
+                "parsing beatmaps"
+            );
+        }
+
+        if !progress(index + 1, total) {
+            return Err(Error::Cancelled);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(parsed = beatmaps.len(), total, "parsed beatmaps");
+
+    // User permissions are stored after the beatmaps, so they can't be found if any beatmap is invalid
+    let user_permissions = if error.is_some() {
+        FlagSet::default()
+    } else {
+        user_permissions(i)
+            .map_err(|e| Error::parse(FileKind::Beatmaps, data, e))?
+            .1
+    };
+
+    Ok(PartialListing {
+        skipped: total - beatmaps.len(),
+        listing: BeatmapListing {
+            version,
+            folder_count,
+            account_unlocked,
+            account_unlock_date,
+            player_name,
+            beatmaps,
+            user_permissions,
+        },
+        error,
+        spans,
+    })
+}
+
+/// Beatmaps parsed from a file, in file order, and the span of each one within the file.
+type ParsedEntries = (Vec<BeatmapEntry>, Vec<Range<usize>>);
+
+/// Parses the beatmap entries starting at `input` on several threads, returning the beatmaps in file order and the span
+/// of each one within `data`.
+///
+/// The entries are split into chunks of [`PARALLEL_CHUNK_SIZE`], which the threads take in turn. `progress` is called on
+/// the calling thread after each chunk, and the threads stop taking chunks once it returns `false`.
+///
+/// Returns `None` if the entries can't be split up or any of them fails to parse, in which case they should be parsed
+/// one at a time instead, so that the valid beatmaps before the error are kept.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_entries_parallel<F>(
+    data: &[u8],
+    mut input: &[u8],
+    version: u32,
+    total: usize,
+    threads: usize,
+    progress: &mut F,
+) -> Result<Option<ParsedEntries>, Error>
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    // Finding where each entry starts is much faster than parsing them, since nothing needs to be allocated
+    let skip_entry = skip_beatmap_entry(version);
+    let mut spans = Vec::with_capacity(total.min(data.len() / 64));
+
+    for _ in 0..total {
+        let Ok((rest, ())) = skip_entry(input) else {
+            return Ok(None);
+        };
+
+        spans.push(data.len() - input.len()..data.len() - rest.len());
+        input = rest;
+    }
+
+    let chunks = spans.chunks(PARALLEL_CHUNK_SIZE).collect::<Vec<_>>();
+    let next_chunk = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+
+    let beatmaps = std::thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+
+        for _ in 0..threads.min(chunks.len()) {
+            let tx = tx.clone();
+            let (chunks, next_chunk, stop) = (&chunks, &next_chunk, &stop);
+
+            scope.spawn(move || {
+                let parse_entry = beatmap_entry(version);
+
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next_chunk.fetch_add(1, Ordering::Relaxed);
+                    let Some(chunk) = chunks.get(index) else {
+                        break;
+                    };
+
+                    // Values aren't checked when skipping over entries, so each entry has to parse and end where the
+                    // next one was expected to start, or the results could differ
+                    let beatmaps = chunk
+                        .iter()
+                        .map(|span| match parse_entry(&data[span.start..]) {
+                            Ok((rest, beatmap)) if data.len() - rest.len() == span.end => {
+                                Some(beatmap)
+                            }
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>();
+
+                    if tx.send((index, beatmaps)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        // Only the workers' senders are left, so this stops once they've all finished
+        drop(tx);
+
+        let mut parsed = (0..chunks.len()).map(|_| None).collect::<Vec<_>>();
+        let mut count = 0;
+
+        for (index, beatmaps) in rx {
+            let Some(beatmaps) = beatmaps else {
+                stop.store(true, Ordering::Relaxed);
+                return Ok(None);
+            };
+
+            count += beatmaps.len();
+            parsed[index] = Some(beatmaps);
+
+            if !progress(count, total) {
+                stop.store(true, Ordering::Relaxed);
+                return Err(Error::Cancelled);
+            }
+        }
+
+        // Chunks are put back in the order they were split up, which is file order
+        Ok(parsed
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .map(|chunks| chunks.into_iter().flatten().collect()))
+    })?;
+
+    Ok(beatmaps.map(|beatmaps| (beatmaps, spans)))
+}
+
+/// Parses a beatmap entry in an `osu.db` file.
+pub(crate) fn beatmap_entry(version: u32) -> impl Fn(&[u8]) -> IResult<&[u8], BeatmapEntry> {
+    let parse_difficulty: fn(&[u8]) -> IResult<&[u8], f32> = if version < 20140609 {
+        |i: &[u8]| map(u8, |b| b as f32)(i)
+    } else {
+        |i: &[u8]| le_f32(i)
+    };
+
+    let parse_star_ratings = versioned_star_ratings(version);
+
+    move |input| {
+        let (i, size) = cond(version < 20191106, le_u32)(input)?;
+        let (i, artist_name) = parse_osu_string(i)?;
+        let (i, artist_name_unicode) = parse_osu_string(i)?;
+        let (i, song_title) = parse_osu_string(i)?;
+        let (i, song_title_unicode) = parse_osu_string(i)?;
+        let (i, creator_name) = parse_osu_string(i)?;
+        let (i, difficulty) = parse_osu_string(i)?;
+        let (i, audio_filename) = parse_osu_string(i)?;
+        let (i, md5) = parse_osu_string(i)?;
+        let (i, beatmap_filename) = parse_osu_string(i)?;
+
+        let (i, ranked_status) = ranked_status(i)?;
+        let (i, hitcircle_count) = le_u16(i)?;
+        let (i, slider_count) = le_u16(i)?;
+        let (i, spinner_count) = le_u16(i)?;
+        let (i, last_modification_time) = parse_windows_datetime(i)?;
+        let (i, approach_rate) = parse_difficulty(i)?;
+        let (i, circle_size) = parse_difficulty(i)?;
+        let (i, hp_drain) = parse_difficulty(i)?;
+        let (i, overall_difficulty) = parse_difficulty(i)?;
+        let (i, slider_velocity) = le_f64(i)?;
+
+        let (i, star_ratings_std) = cond(version >= 20140609, &parse_star_ratings)(i)?;
+        let (i, star_ratings_taiko) = cond(version >= 20140609, &parse_star_ratings)(i)?;
+        let (i, star_ratings_ctb) = cond(version >= 20140609, &parse_star_ratings)(i)?;
+        let (i, star_ratings_mania) = cond(version >= 20140609, &parse_star_ratings)(i)?;
+        let (i, drain_time) = le_u32(i)?;
+        let (i, total_time) = le_u32(i)?;
+        let (i, audio_preview_time) = le_u32(i)?;
+        let (i, timing_points) = length_count(le_u32, timing_point)(i)?;
+        let (i, difficulty_id) = le_u32(i)?;
+        let (i, beatmap_id) = le_u32(i)?;
+
+        let (i, thread_id) = le_u32(i)?;
+        let (i, grade_std) = grade(i)?;
+        let (i, grade_taiko) = grade(i)?;
+        let (i, grade_catch) = grade(i)?;
+        let (i, grade_mania) = grade(i)?;
+        let (i, local_offset) = le_u16(i)?;
+        let (i, stack_leniency) = le_f32(i)?;
+        let (i, gameplay_mode) = parse_gameplay_mode(i)?;
+        let (i, song_source) = parse_osu_string(i)?;
+        let (i, song_tags) = parse_osu_string(i)?;
+
+        let (i, online_offset) = le_u16(i)?;
+        let (i, font) = parse_osu_string(i)?;
+        let (i, is_unplayed) = parse_boolean(i)?;
+        let (i, last_played) = parse_windows_datetime(i)?;
+        let (i, is_osz2) = parse_boolean(i)?;
+        let (i, folder_name) = parse_osu_string(i)?;
+        let (i, last_checked_online) = parse_windows_datetime(i)?;
+        let (i, ignore_beatmap_hitsounds) = parse_boolean(i)?;
+        let (i, ignore_beatmap_skin) = parse_boolean(i)?;
+        let (i, disable_storyboard) = parse_boolean(i)?;
+
+        let (i, disable_video) = parse_boolean(i)?;
+        let (i, visual_override) = parse_boolean(i)?;
+
+        // NOTE: Unused u16 optional field, only present if version is less than 20140609
+        let (i, unknown_u16) = cond(version < 20140609, le_u16)(i)?;
+
+        // NOTE: Unused u32 field (appears to be last modification time as well)
+        let (i, unknown_u32) = le_u32(i)?;
+
+        let (i, mania_scroll_speed) = u8(i)?;
+
+        Ok((
+            i,
+            BeatmapEntry {
+                size,
+                artist_name,
+                artist_name_unicode,
+                song_title,
+                song_title_unicode,
+                creator_name,
+                difficulty,
+                audio_filename,
+                md5,
+                beatmap_filename,
+                ranked_status,
+                hitcircle_count,
+                slider_count,
+                spinner_count,
+                last_modification_time,
+                approach_rate,
+                circle_size,
+                hp_drain,
+                overall_difficulty,
+                slider_velocity,
+                star_ratings_std,
+                star_ratings_taiko,
+                star_ratings_ctb,
+                star_ratings_mania,
+                drain_time,
+                total_time,
+                audio_preview_time,
+                timing_points,
+                difficulty_id,
+                beatmap_id,
+                thread_id,
+                grade_std,
+                grade_taiko,
+                grade_catch,
+                grade_mania,
+                local_offset,
+                stack_leniency,
+                gameplay_mode,
+                song_source,
+                song_tags,
+                online_offset,
+                font,
+                is_unplayed,
+                last_played,
+                is_osz2,
+                folder_name,
+                last_checked_online,
+                ignore_beatmap_hitsounds,
+                ignore_beatmap_skin,
+                disable_storyboard,
+                disable_video,
+                visual_override,
+                unknown_u16,
+                unknown_u32,
+                mania_scroll_speed,
+            },
+        ))
+    }
+}
+
+/// Encodes a beatmap entry in the `osu.db` format, excluding the entry size.
+#[cfg(feature = "writer")]
+fn beatmap_entry_bytes(version: u32, beatmap: &BeatmapEntry) -> Vec<u8> {
+    let mut output = Vec::new();
+
+    let write_difficulty = |output: &mut Vec<u8>, value: f32| {
+        if version < 20140609 {
+            output.push(value as u8);
+        } else {
+            output.extend_from_slice(&value.to_le_bytes());
+        }
+    };
+
+    for string in [
+        &beatmap.artist_name,
+        &beatmap.artist_name_unicode,
+        &beatmap.song_title,
+        &beatmap.song_title_unicode,
+        &beatmap.creator_name,
+        &beatmap.difficulty,
+        &beatmap.audio_filename,
+        &beatmap.md5,
+        &beatmap.beatmap_filename,
+    ] {
+        write_osu_string(&mut output, string);
+    }
+
+    output.push(beatmap.ranked_status as u8);
+    output.extend_from_slice(&beatmap.hitcircle_count.to_le_bytes());
+    output.extend_from_slice(&beatmap.slider_count.to_le_bytes());
+    output.extend_from_slice(&beatmap.spinner_count.to_le_bytes());
+    write_windows_datetime(&mut output, beatmap.last_modification_time);
+    write_difficulty(&mut output, beatmap.approach_rate);
+    write_difficulty(&mut output, beatmap.circle_size);
+    write_difficulty(&mut output, beatmap.hp_drain);
+    write_difficulty(&mut output, beatmap.overall_difficulty);
+    output.extend_from_slice(&beatmap.slider_velocity.to_le_bytes());
+
+    if version >= 20140609 {
+        for star_ratings in [
+            &beatmap.star_ratings_std,
+            &beatmap.star_ratings_taiko,
+            &beatmap.star_ratings_ctb,
+            &beatmap.star_ratings_mania,
+        ] {
+            let star_ratings = star_ratings.as_deref().unwrap_or_default();
+            output.extend_from_slice(&(star_ratings.len() as u32).to_le_bytes());
+
+            for star_rating in star_ratings {
+                let mods = star_rating.mods.bits();
+
+                if version < 20250108 {
+                    write_int_double_pair(&mut output, mods, star_rating.rating);
+                } else {
+                    write_int_float_pair(&mut output, mods, star_rating.rating as f32);
+                }
+            }
+        }
+    }
+
+    output.extend_from_slice(&beatmap.drain_time.to_le_bytes());
+    output.extend_from_slice(&beatmap.total_time.to_le_bytes());
+    output.extend_from_slice(&beatmap.audio_preview_time.to_le_bytes());
+    output.extend_from_slice(&(beatmap.timing_points.len() as u32).to_le_bytes());
+
+    for timing_point in &beatmap.timing_points {
+        output.extend_from_slice(&timing_point.bpm.to_le_bytes());
+        output.extend_from_slice(&timing_point.song_offset.to_le_bytes());
+        write_boolean(&mut output, timing_point.inherited);
+    }
+
+    output.extend_from_slice(&beatmap.difficulty_id.to_le_bytes());
+    output.extend_from_slice(&beatmap.beatmap_id.to_le_bytes());
+    output.extend_from_slice(&beatmap.thread_id.to_le_bytes());
+    output.push(beatmap.grade_std as u8);
+    output.push(beatmap.grade_taiko as u8);
+    output.push(beatmap.grade_catch as u8);
+    output.push(beatmap.grade_mania as u8);
+    output.extend_from_slice(&beatmap.local_offset.to_le_bytes());
+    output.extend_from_slice(&beatmap.stack_leniency.to_le_bytes());
+    write_gameplay_mode(&mut output, beatmap.gameplay_mode);
+    write_osu_string(&mut output, &beatmap.song_source);
+    write_osu_string(&mut output, &beatmap.song_tags);
+
+    output.extend_from_slice(&beatmap.online_offset.to_le_bytes());
+    write_osu_string(&mut output, &beatmap.font);
+    write_boolean(&mut output, beatmap.is_unplayed);
+    write_windows_datetime(&mut output, beatmap.last_played);
+    write_boolean(&mut output, beatmap.is_osz2);
+    write_osu_string(&mut output, &beatmap.folder_name);
+    write_windows_datetime(&mut output, beatmap.last_checked_online);
+    write_boolean(&mut output, beatmap.ignore_beatmap_hitsounds);
+    write_boolean(&mut output, beatmap.ignore_beatmap_skin);
+    write_boolean(&mut output, beatmap.disable_storyboard);
+    write_boolean(&mut output, beatmap.disable_video);
+    write_boolean(&mut output, beatmap.visual_override);
+
+    if version < 20140609 {
+        output.extend_from_slice(&beatmap.unknown_u16.unwrap_or_default().to_le_bytes());
+    }
+
+    output.extend_from_slice(&beatmap.unknown_u32.to_le_bytes());
+    output.push(beatmap.mania_scroll_speed);
+    output
+}
+
+/// Parses a ranked status value.
+fn ranked_status(input: &[u8]) -> IResult<&[u8], RankedStatus> {
+    use RankedStatus::*;
+
+    let (i, status) = u8(input)?;
+    let status = match status {
+        0 => Unknown,
+        1 => Unsubmitted,
+        2 => Pending,
+        4 => Ranked,
+        5 => Approved,
+        6 => Qualified,
+        7 => Loved,
+        _ => {
+            return Err(nom::Err::Error(nom::error::Error {
+                input,
+                code: nom::error::ErrorKind::Switch,
+            }))
+        }
+    };
+
+    Ok((i, status))
+}
+
+/// Parses a grade value.
+fn grade(input: &[u8]) -> IResult<&[u8], Grade> {
+    use Grade::*;
+
+    let (i, grade) = u8(input)?;
+    let grade = match grade {
+        0 => SilverSS,
+        1 => SilverS,
+        2 => SS,
+        3 => S,
+        4 => A,
+        5 => B,
+        6 => C,
+        7 => D,
+        9 => Unplayed,
+        _ => {
+            return Err(nom::Err::Error(nom::error::Error {
+                input,
+                code: nom::error::ErrorKind::Switch,
+            }))
+        }
+    };
+
+    Ok((i, grade))
+}
+
+/// Parses a timing point found in `osu.db`.
+fn timing_point(input: &[u8]) -> IResult<&[u8], TimingPoint> {
+    map(
+        tuple((le_f64, le_f64, parse_boolean)),
+        |(bpm, song_offset, inherited)| TimingPoint {
+            bpm,
+            song_offset,
+            inherited,
+        },
+    )(input)
 }
 
 /// Parses a list of star ratings.
 fn star_ratings(input: &[u8]) -> IResult<&[u8], Vec<StarRating>> {
     length_count(
         le_u32,
-        map(int_double_pair, |(i, d)| StarRating {
+        map(parse_int_double_pair, |(i, d)| StarRating {
             mods: FlagSet::<Mods>::new_truncated(i),
             rating: d,
         }),
     )(input)
 }
 
-/// Parses a set of user permissions.
-fn user_permissions(input: &[u8]) -> IResult<&[u8], FlagSet<UserPermissions>> {
-    map(le_u32, FlagSet::<UserPermissions>::new_truncated)(input)
-}
+/// Parses a list of star ratings stored as Singles, which is used from version 20250108.
+fn float_star_ratings(input: &[u8]) -> IResult<&[u8], Vec<StarRating>> {
+    length_count(
+        le_u32,
+        map(parse_int_float_pair, |(i, f)| StarRating {
+            mods: FlagSet::<Mods>::new_truncated(i),
+            rating: f as f64,
+        }),
+    )(input)
+}
+
+/// Parses a list of star ratings, using the layout for the given version.
+fn versioned_star_ratings(version: u32) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<StarRating>> {
+    move |input| {
+        if version < 20250108 {
+            star_ratings(input)
+        } else {
+            float_star_ratings(input)
+        }
+    }
+}
+
+/// Parses a set of user permissions.
+pub(crate) fn user_permissions(input: &[u8]) -> IResult<&[u8], FlagSet<UserPermissions>> {
+    map(le_u32, FlagSet::<UserPermissions>::new_truncated)(input)
+}
+
+#[cfg(all(test, feature = "writer"))]
+pub mod tests {
+    use super::*;
+
+    fn timing_point_at(beat_length: f64, song_offset: f64, uninherited: bool) -> TimingPoint {
+        TimingPoint {
+            bpm: beat_length,
+            song_offset,
+            inherited: uninherited,
+        }
+    }
+
+    /// Builds the bytes for a beatmap entry with every field left empty.
+    fn empty_entry() -> Vec<u8> {
+        let mut entry = vec![0x00; 9]; // Strings
+        entry.push(4); // Ranked status
+        entry.extend([0; 3 * 2 + 8]); // Object counts, last modification time
+        entry.extend([0; 4 * 4 + 8]); // Difficulty settings, slider velocity
+        entry.extend([0; 4 * 4]); // Star ratings
+        entry.extend([0; 3 * 4 + 4]); // Times, timing points
+        entry.extend([0; 3 * 4]); // IDs
+        entry.extend([9; 4]); // Grades
+        entry.extend([0; 2 + 4 + 1]); // Local offset, stack leniency, gameplay mode
+        entry.extend([0x00, 0x00]); // Source, tags
+        entry.extend([0, 0, 0x00, 1]); // Online offset, font, unplayed
+        entry.extend([0; 8 + 1]); // Last played, osz2
+        entry.push(0x00); // Folder name
+        entry.extend([0; 8 + 5 + 4 + 1]); // Last checked, flags, unknown, scroll speed
+        entry
+    }
+
+    /// Builds the bytes for an `osu.db` file containing the given entries.
+    fn listing(total: u32, entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = 20191106u32.to_le_bytes().to_vec();
+        data.extend([0; 4 + 1 + 8]); // Folder count, account unlocked, unlock date
+        data.push(0x00); // Player name
+        data.extend(total.to_le_bytes());
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
+        for entry in entries {
+            data.extend(entry);
+        }
+
+        data.extend(FlagSet::from(UserPermissions::Normal).bits().to_le_bytes());
+        data
+    }
 
     #[test]
-    fn ranked_status_decoding_works() {
-        use RankedStatus::*;
+    fn listing_progress_is_reported() {
+        let data = listing(3, &[empty_entry(), empty_entry(), empty_entry()]);
 
-        assert_eq!(ranked_status(&[0]), Ok((&[][..], Unknown)));
-        assert_eq!(ranked_status(&[1]), Ok((&[][..], Unsubmitted)));
-        assert_eq!(ranked_status(&[2]), Ok((&[][..], Pending)));
-        assert_eq!(ranked_status(&[4]), Ok((&[][..], Ranked)));
-        assert_eq!(ranked_status(&[5]), Ok((&[][..], Approved)));
-        assert_eq!(ranked_status(&[6]), Ok((&[][..], Qualified)));
-        assert_eq!(ranked_status(&[7]), Ok((&[][..], Loved)));
+        let mut calls = Vec::new();
+        let listing = BeatmapListing::from_bytes_with_progress(&data, |parsed, total| {
+            calls.push((parsed, total));
+            true
+        })
+        .unwrap();
 
+        assert_eq!(listing.beatmaps.len(), 3);
         assert_eq!(
-            ranked_status(&[10]),
-            Err(nom::Err::Error(nom::error::Error {
-                input: &[10][..],
-                code: nom::error::ErrorKind::Switch
-            }))
+            listing.user_permissions,
+            FlagSet::from(UserPermissions::Normal)
+        );
+        assert_eq!(calls, vec![(1, 3), (2, 3), (3, 3)]);
+
+        // Parsing stops as soon as the callback asks it to
+        let result = BeatmapListing::from_bytes_with_progress(&data, |parsed, _| parsed < 2);
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn parallel_progress_is_reported_after_each_chunk() {
+        let total = PARALLEL_CHUNK_SIZE * 4;
+        let data = listing(total as u32, &vec![empty_entry(); total]);
+        let options = ParseOptions {
+            threads: 2,
+            ..Default::default()
+        };
+
+        let mut calls = Vec::new();
+        let partial = BeatmapListing::from_bytes_with_options(&data, options, |parsed, total| {
+            calls.push((parsed, total));
+            true
+        })
+        .unwrap();
+
+        assert_eq!(partial.listing.beatmaps.len(), total);
+        assert_eq!(
+            calls,
+            (1..=4)
+                .map(|chunks| (chunks * PARALLEL_CHUNK_SIZE, total))
+                .collect::<Vec<_>>()
+        );
+
+        // Cancelling part way through stops the threads from taking any more chunks
+        let mut calls = Vec::new();
+        let result = BeatmapListing::from_bytes_with_options(&data, options, |parsed, total| {
+            calls.push((parsed, total));
+            parsed < PARALLEL_CHUNK_SIZE * 2
+        });
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert_eq!(
+            calls,
+            vec![
+                (PARALLEL_CHUNK_SIZE, total),
+                (PARALLEL_CHUNK_SIZE * 2, total)
+            ]
         );
     }
 
     #[test]
-    fn int_double_pair_decoding_works() {
-        let int: u32 = 100;
-        let double: f64 = 1234.56;
-        let extra = [0x01, 0x02, 0x03];
+    fn header_is_read_without_beatmaps() {
+        let data = listing(2, &[empty_entry(), empty_entry()]);
+
+        let header = ListingHeader::from_bytes(&data).unwrap();
+        assert_eq!(header.version, 20191106);
+        assert_eq!(header.beatmap_count, 2);
+
+        // Only the start of the file is read, so the rest of it can be damaged or missing
+        let path =
+            std::env::temp_dir().join(format!("osu-db-parser-header-{}.db", std::process::id()));
+        std::fs::write(&path, &data[..30]).unwrap();
+        assert_eq!(ListingHeader::from_file(&path).unwrap(), header);
+        std::fs::remove_file(&path).unwrap();
+
+        // Other files are rejected, since their first value isn't a plausible version
+        assert!(
+            ListingHeader::from_bytes(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\0\0\0\0\0").is_err()
+        );
+        assert!(ListingHeader::from_bytes(&data[..10]).is_err());
+    }
+
+    #[test]
+    fn invalid_beatmaps_are_reported() {
+        let mut invalid = empty_entry();
+        invalid[3] = 0x05; // Invalid string marker for the song title unicode
+
+        let data = listing(3, &[empty_entry(), invalid, empty_entry()]);
+        let entry_offset = data.len() - 4 - 2 * empty_entry().len();
+
+        let result = BeatmapListing::from_bytes(&data);
+        assert!(matches!(
+            result,
+            Err(Error::Beatmap { index: 1, total: 3, entry_offset: e, offset: o, .. })
+                if e == entry_offset && o == entry_offset + 3
+        ));
+
+        // Lenient parsing keeps the beatmaps before the invalid one
+        let partial = BeatmapListing::from_bytes_lenient(&data, |_, _| true).unwrap();
+        assert_eq!(partial.listing.beatmaps.len(), 1);
+        assert_eq!(partial.skipped, 2);
+        assert!(matches!(
+            partial.error,
+            Some(Error::Beatmap { index: 1, .. })
+        ));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn parsing_is_traced() {
+        use std::sync::{Arc, Mutex};
+
+        use tracing::{field::Field, span::Attributes, span::Id, Event, Subscriber};
+        use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
 
-        let mut pair = Vec::new();
-        pair.push(0x08);
-        pair.extend_from_slice(&int.to_le_bytes());
-        pair.push(0x0d);
-        pair.extend_from_slice(&double.to_le_bytes());
-        pair.extend_from_slice(&extra);
+        /// Records the names of spans and the messages of events, in the order they happen.
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<String>>>);
 
-        let mut missing_front_tag = Vec::new();
-        missing_front_tag.extend_from_slice(&int.to_le_bytes());
-        missing_front_tag.extend_from_slice(&double.to_le_bytes());
+        struct Message(String);
 
-        let mut missing_middle_tag = Vec::new();
-        missing_middle_tag.push(0x08);
-        missing_middle_tag.extend_from_slice(&int.to_le_bytes());
-        missing_middle_tag.extend_from_slice(&double.to_le_bytes());
+        impl tracing::field::Visit for Message {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "message" {
+                    self.0 = format!("{:?}", value);
+                }
+            }
+        }
+
+        impl<S: Subscriber> Layer<S> for Recorder {
+            fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(attrs.metadata().name().to_string());
+            }
+
+            fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+                let mut message = Message(String::new());
+                event.record(&mut message);
+                self.0.lock().unwrap().push(message.0);
+            }
+        }
 
-        assert_eq!(int_double_pair(&pair), Ok((&extra[..], ((int, double)))));
+        let recorder = Recorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+
+        let mut invalid = empty_entry();
+        invalid[3] = 0x05;
+        let data = listing(3, &[empty_entry(), invalid, empty_entry()]);
+
+        tracing::subscriber::with_default(subscriber, || {
+            BeatmapListing::from_bytes_lenient(&data, |_, _| true).unwrap();
+        });
 
         assert_eq!(
-            int_double_pair(&missing_front_tag),
-            Err(nom::Err::Error(nom::error::Error {
-                input: &missing_front_tag[..],
-                code: nom::error::ErrorKind::Tag
-            }))
+            *recorder.0.lock().unwrap(),
+            [
+                "parse_osu_db",
+                "parse_header",
+                "parse_entries",
+                "parsed beatmaps",
+                "skipped beatmaps after an invalid entry"
+            ]
+        );
+    }
+
+    /// Builds a listing for a version, with a beatmap using every field that is present in that version.
+    fn sample_listing(version: u32) -> BeatmapListing {
+        let modern = version >= 20140609;
+        let star_ratings = modern.then(|| {
+            vec![StarRating {
+                mods: Mods::DoubleTime | Mods::HardRock,
+                rating: 7.25,
+            }]
+        });
+
+        let beatmap = BeatmapEntry {
+            artist_name: Some("Camellia".to_string()),
+            artist_name_unicode: Some("かめりあ".to_string()),
+            song_title: Some("Exit This Earth's Atomosphere".to_string()),
+            creator_name: Some("Mapper".to_string()),
+            difficulty: Some("Insane".to_string()),
+            audio_filename: Some("audio.mp3".to_string()),
+            md5: Some("0123456789abcdef0123456789abcdef".to_string()),
+            ranked_status: RankedStatus::Loved,
+            hitcircle_count: 500,
+            slider_count: 250,
+            spinner_count: 2,
+            last_modification_time: datetime!(2023-07-28 15:30:20 UTC),
+            approach_rate: if modern { 9.3 } else { 9.0 },
+            circle_size: 4.0,
+            hp_drain: 6.0,
+            overall_difficulty: 8.0,
+            slider_velocity: 1.8,
+            star_ratings_std: star_ratings.clone(),
+            star_ratings_taiko: star_ratings.clone().map(|_| Vec::new()),
+            star_ratings_ctb: star_ratings.clone().map(|_| Vec::new()),
+            star_ratings_mania: star_ratings,
+            drain_time: 180,
+            total_time: 200_000,
+            audio_preview_time: 60_000,
+            timing_points: vec![
+                timing_point_at(300.0, 0.0, true),
+                timing_point_at(-50.0, 1000.0, false),
+            ],
+            difficulty_id: 1,
+            beatmap_id: 2,
+            grade_std: Grade::A,
+            gameplay_mode: GameplayMode::Standard,
+            folder_name: Some("1 Camellia - Exit This Earth's Atomosphere".to_string()),
+            is_unplayed: false,
+            unknown_u16: (!modern).then_some(7),
+            mania_scroll_speed: 20,
+            ..Default::default()
+        };
+
+        // Optional fields are always present in the versions that have them
+        let empty = BeatmapEntry {
+            star_ratings_std: modern.then(Vec::new),
+            star_ratings_taiko: modern.then(Vec::new),
+            star_ratings_ctb: modern.then(Vec::new),
+            star_ratings_mania: modern.then(Vec::new),
+            unknown_u16: (!modern).then_some(0),
+            ..Default::default()
+        };
+
+        BeatmapListing {
+            version,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: Some("Player".to_string()),
+            beatmaps: vec![beatmap, empty],
+            user_permissions: UserPermissions::Normal | UserPermissions::Supporter,
+        }
+    }
+
+    #[test]
+    fn listing_round_trip_works() {
+        for version in [20250108, 20191106, 20150203, 20140101] {
+            let listing = sample_listing(version);
+            let parsed = BeatmapListing::from_bytes(&listing.to_bytes()).unwrap();
+
+            // Entry sizes are only stored by older versions, and are recalculated when writing
+            let mut expected = listing.clone();
+            for (beatmap, parsed) in expected.beatmaps.iter_mut().zip(&parsed.beatmaps) {
+                assert_eq!(parsed.size.is_some(), version < 20191106);
+                beatmap.size = parsed.size;
+            }
+
+            assert_eq!(parsed, expected, "version {}", version);
+        }
+    }
+
+    #[test]
+    fn float_policy_is_applied_when_parsing() {
+        let mut listing = sample_listing(20191106);
+        listing.beatmaps[0]
+            .star_ratings_std
+            .as_mut()
+            .unwrap()
+            .push(StarRating {
+                mods: Mods::none(),
+                rating: f64::NAN,
+            });
+        listing.beatmaps[1].slider_velocity = f64::INFINITY;
+        let data = listing.to_bytes();
+
+        let parse = |float_policy, threads| {
+            let options = ParseOptions {
+                float_policy,
+                threads,
+                ..Default::default()
+            };
+            BeatmapListing::from_bytes_with_options(&data, options, |_, _| true).unwrap()
+        };
+
+        for threads in [1, 2] {
+            let preserved = parse(FloatPolicy::Preserve, threads).listing;
+            assert!(preserved.beatmaps[0].star_ratings_std.as_ref().unwrap()[1]
+                .rating
+                .is_nan());
+            assert_eq!(preserved.beatmaps[1].slider_velocity, f64::INFINITY);
+
+            let replaced = parse(FloatPolicy::ReplaceWithNone, threads).listing;
+            assert_eq!(
+                replaced.beatmaps[0].star_ratings_std,
+                sample_listing(20191106).beatmaps[0].star_ratings_std
+            );
+            assert_eq!(replaced.beatmaps[1].slider_velocity, 0.0);
+
+            // Beatmaps with non-finite values are treated like invalid entries
+            let partial = parse(FloatPolicy::Error, threads);
+            assert!(partial.listing.beatmaps.is_empty());
+            assert_eq!(partial.skipped, 2);
+            assert!(matches!(
+                partial.error,
+                Some(Error::NonFiniteFloat {
+                    index: 0,
+                    field: "star rating",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn non_finite_floats_are_exported_as_null() {
+        let mut beatmap = sample_listing(20191106).beatmaps.remove(0);
+        beatmap.star_ratings_std = Some(vec![StarRating {
+            mods: Mods::none(),
+            rating: f64::NAN,
+        }]);
+        beatmap.stack_leniency = f32::NEG_INFINITY;
+
+        let json = serde_json::to_value(&beatmap).unwrap();
+        assert_eq!(
+            json["star_ratings_std"][0]["rating"],
+            serde_json::Value::Null
+        );
+        assert_eq!(json["stack_leniency"], serde_json::Value::Null);
+        assert_eq!(json["slider_velocity"], 1.8);
+
+        let parsed = serde_json::from_value::<BeatmapEntry>(json).unwrap();
+        assert!(parsed.star_ratings_std.unwrap()[0].rating.is_nan());
+        assert!(parsed.stack_leniency.is_nan());
+    }
+
+    #[test]
+    fn newer_versions_are_parsed_on_a_best_effort_basis() {
+        let listing = sample_listing(NEWEST_KNOWN_VERSION + 1);
+        let data = listing.to_bytes();
+
+        let parsed = BeatmapListing::from_bytes(&data).unwrap();
+        assert_eq!(parsed.format_confidence(), FormatConfidence::BestEffort);
+        assert_eq!(parsed.beatmaps.len(), 2);
+        assert_eq!(
+            ListingHeader::from_bytes(&data)
+                .unwrap()
+                .format_confidence(),
+            FormatConfidence::BestEffort
+        );
+
+        // Entries that don't fit the newest known layout are skipped rather than failing, as if the file was damaged
+        let truncated = &data[..data.len() - 10];
+        assert_eq!(
+            BeatmapListing::from_bytes(truncated)
+                .unwrap()
+                .beatmaps
+                .len(),
+            1
+        );
+
+        let known = sample_listing(NEWEST_KNOWN_VERSION).to_bytes();
+        assert!(BeatmapListing::from_bytes(&known[..known.len() - 10]).is_err());
+    }
+
+    #[test]
+    fn newer_versions_are_not_written() {
+        let path = std::env::temp_dir().join(format!(
+            "osu-db-parser-best-effort-{}.db",
+            std::process::id()
+        ));
+
+        let listing = sample_listing(NEWEST_KNOWN_VERSION + 1);
+        assert!(matches!(
+            listing.to_file(&path),
+            Err(Error::UnsupportedVersionForWrite(version)) if version == NEWEST_KNOWN_VERSION + 1
+        ));
+        assert!(matches!(
+            listing.to_file_with_options(&path, WriteOptions { normalize: true }),
+            Err(Error::UnsupportedVersionForWrite(_))
+        ));
+        assert!(!path.exists());
+
+        sample_listing(NEWEST_KNOWN_VERSION).to_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn account_statuses_round_trip() {
+        let until = datetime!(2031-05-04 12:30:15.1234567 UTC);
+
+        for status in [
+            AccountStatus::Unlocked,
+            AccountStatus::LockedUntil(until),
+            AccountStatus::LockedIndefinitely,
+        ] {
+            let mut listing = sample_listing(20250108);
+            listing.set_account_status(status);
+
+            let parsed = BeatmapListing::from_bytes(&listing.to_bytes()).unwrap();
+            assert_eq!(parsed.account_status(), status);
+            assert_eq!(parsed.account_unlock_date, listing.account_unlock_date);
+        }
+    }
+
+    #[test]
+    fn player_names_keep_their_encoding() {
+        for (player_name, name, encoding) in [
+            (None, None, vec![0x00]),
+            (Some(String::new()), None, vec![0x0b, 0x00]),
+            (
+                Some("Player".to_string()),
+                Some("Player"),
+                [&[0x0b, 0x06][..], b"Player"].concat(),
+            ),
+        ] {
+            let mut listing = sample_listing(20250108);
+            listing.player_name = player_name.clone();
+
+            let data = listing.to_bytes();
+            assert_eq!(&data[17..17 + encoding.len()], encoding.as_slice());
+
+            let parsed = BeatmapListing::from_bytes(&data).unwrap();
+            assert_eq!(parsed.player_name, player_name);
+            assert_eq!(parsed.player_name_str(), name);
+            assert_eq!(
+                ListingHeader::from_bytes(&data).unwrap().player_name_str(),
+                name
+            );
+            assert_eq!(parsed.to_bytes(), data);
+        }
+    }
+
+    #[test]
+    fn outdated_beatmaps_are_found_on_both_sides_of_the_tolerance() {
+        let checked = datetime!(2024-06-01 0:00 UTC);
+        let beatmap =
+            |md5: &str, modified: OffsetDateTime, ranked_status: RankedStatus| BeatmapEntry {
+                md5: Some(md5.to_string()),
+                last_modification_time: modified,
+                last_checked_online: checked,
+                ranked_status,
+                ..Default::default()
+            };
+
+        let mut listing = sample_listing(20250108);
+        listing.beatmaps = vec![
+            beatmap("recent", checked - Duration::days(29), RankedStatus::Ranked),
+            beatmap("old", checked - Duration::days(31), RankedStatus::Ranked),
+            beatmap("edited", checked + Duration::days(31), RankedStatus::Loved),
+            beatmap(
+                "edited recently",
+                checked + Duration::days(1),
+                RankedStatus::Loved,
+            ),
+            beatmap(
+                "unsubmitted",
+                checked - Duration::days(365),
+                RankedStatus::Unsubmitted,
+            ),
+            beatmap("never modified", WINDOWS_EPOCH, RankedStatus::Ranked),
+        ];
+
+        let md5s = |tolerance| {
+            listing
+                .possibly_outdated(tolerance)
+                .into_iter()
+                .map(|beatmap| beatmap.md5.as_deref().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(md5s(DEFAULT_OUTDATED_TOLERANCE), ["old", "edited"]);
+        assert_eq!(
+            md5s(Duration::hours(1)),
+            ["recent", "old", "edited", "edited recently"]
+        );
+        assert!(md5s(Duration::days(60)).is_empty());
+    }
+
+    #[test]
+    fn account_status_sentinels_are_recognised() {
+        let date = datetime!(2024-01-01 0:00 UTC);
+
+        // The unlock date of an unlocked account is ignored
+        assert_eq!(
+            AccountStatus::from_header(true, date),
+            AccountStatus::Unlocked
+        );
+        assert_eq!(
+            AccountStatus::from_header(false, date),
+            AccountStatus::LockedUntil(date)
+        );
+        assert_eq!(
+            AccountStatus::from_header(false, WINDOWS_EPOCH),
+            AccountStatus::LockedIndefinitely
+        );
+        assert_eq!(
+            AccountStatus::from_header(false, datetime!(9999-12-31 0:00 UTC)),
+            AccountStatus::LockedIndefinitely
+        );
+
+        // DateTime.MaxValue, as written by osu!
+        let mut data = 3155378975999999999u64.to_le_bytes().to_vec();
+        data.push(0);
+        let (_, max_value) = parse_windows_datetime::<()>(&data).unwrap();
+        assert_eq!(max_value, LOCKED_INDEFINITELY_DATE);
+
+        let mut output = Vec::new();
+        write_windows_datetime(&mut output, LOCKED_INDEFINITELY_DATE);
+        assert_eq!(output, data[..8]);
+    }
+
+    #[test]
+    fn spans_reconstruct_entries() {
+        for version in [20250108, 20191106, 20150203, 20140101] {
+            let data = sample_listing(version).to_bytes();
+            let options = ParseOptions {
+                record_spans: true,
+                ..Default::default()
+            };
+            let partial =
+                BeatmapListing::from_bytes_with_options(&data, options, |_, _| true).unwrap();
+
+            assert_eq!(partial.spans.len(), 2, "version {}", version);
+
+            // Entries follow each other, from the end of the header up to the user permissions
+            let header_length = 4 + 4 + 1 + 8 + "Player".len() + 2 + 4;
+            assert_eq!(partial.spans[0].start, header_length);
+            assert_eq!(partial.spans[0].end, partial.spans[1].start);
+            assert_eq!(partial.spans[1].end, data.len() - 4);
+
+            for (index, beatmap) in partial.listing.beatmaps.iter().enumerate() {
+                let mut entry = Vec::new();
+                if let Some(size) = beatmap.size {
+                    entry.extend_from_slice(&size.to_le_bytes());
+                }
+                entry.extend(beatmap_entry_bytes(version, beatmap));
+
+                let span = partial.span(index).unwrap();
+                assert_eq!(&data[span], &entry[..], "version {}", version);
+            }
+        }
+    }
+
+    #[test]
+    fn spans_are_only_recorded_when_requested() {
+        let data = sample_listing(20191106).to_bytes();
+        let partial = BeatmapListing::from_bytes_lenient(&data, |_, _| true).unwrap();
+
+        assert!(partial.spans.is_empty());
+        assert_eq!(partial.span(0), None);
+
+        // Beatmaps after an invalid entry don't have spans
+        let mut invalid = empty_entry();
+        invalid[3] = 0x05;
+        let data = listing(3, &[empty_entry(), invalid, empty_entry()]);
+
+        let options = ParseOptions {
+            record_spans: true,
+            ..Default::default()
+        };
+        let partial = BeatmapListing::from_bytes_with_options(&data, options, |_, _| true).unwrap();
+        let header_length = data.len() - 4 - 3 * empty_entry().len();
+        assert_eq!(partial.spans.len(), 1);
+        assert_eq!(
+            partial.span(0),
+            Some(header_length..header_length + empty_entry().len())
+        );
+    }
+
+    fn metadata(
+        artist: Option<&str>,
+        artist_unicode: Option<&str>,
+        title: Option<&str>,
+        title_unicode: Option<&str>,
+    ) -> BeatmapEntry {
+        BeatmapEntry {
+            artist_name: artist.map(|a| a.to_string()),
+            artist_name_unicode: artist_unicode.map(|a| a.to_string()),
+            song_title: title.map(|t| t.to_string()),
+            song_title_unicode: title_unicode.map(|t| t.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn display_metadata_uses_preferred_language() {
+        let beatmap = metadata(
+            Some("Camellia"),
+            Some("かめりあ"),
+            Some("Exit"),
+            Some("エグジット"),
+        );
+
+        assert_eq!(beatmap.display_artist(false), Some("Camellia"));
+        assert_eq!(beatmap.display_artist(true), Some("かめりあ"));
+        assert_eq!(beatmap.display_title(false), Some("Exit"));
+        assert_eq!(beatmap.display_title(true), Some("エグジット"));
+    }
+
+    #[test]
+    fn display_metadata_falls_back_from_empty_unicode() {
+        let beatmap = metadata(Some("Camellia"), Some(""), Some("Exit"), None);
+
+        assert_eq!(beatmap.display_artist(true), Some("Camellia"));
+        assert_eq!(beatmap.display_title(true), Some("Exit"));
+    }
+
+    #[test]
+    fn display_metadata_falls_back_from_empty_romanised() {
+        let beatmap = metadata(None, Some("かめりあ"), Some(""), Some("エグジット"));
+
+        assert_eq!(beatmap.display_artist(false), Some("かめりあ"));
+        assert_eq!(beatmap.display_title(false), Some("エグジット"));
+    }
+
+    #[test]
+    fn display_name_uses_preferred_language() {
+        let beatmap = BeatmapEntry {
+            difficulty: Some("Insane\u{7}".to_string()),
+            ..metadata(Some("Camellia"), Some("かめりあ"), Some("Exit"), None)
+        };
+
+        assert_eq!(
+            beatmap.display_name(MetadataLanguage::Romanised),
+            "Camellia - Exit [Insane\u{FFFD}]"
+        );
+        assert_eq!(
+            beatmap.display_name(MetadataLanguage::Unicode),
+            "かめりあ - Exit [Insane\u{FFFD}]"
+        );
+    }
+
+    #[test]
+    fn display_metadata_is_missing_when_both_are_empty() {
+        for beatmap in [
+            metadata(None, None, None, None),
+            metadata(Some(""), Some(""), Some(""), None),
+        ] {
+            for prefer_unicode in [false, true] {
+                assert_eq!(beatmap.display_artist(prefer_unicode), None);
+                assert_eq!(beatmap.display_title(prefer_unicode), None);
+            }
+        }
+    }
+
+    #[test]
+    fn subset_keeps_header() {
+        let listing = sample_listing(20191106);
+        let subset = listing.subset(|i, _| i != 0);
+
+        assert_eq!(subset.beatmaps, listing.beatmaps[1..]);
+        assert_eq!(subset.player_name, listing.player_name);
+        assert_eq!(subset.user_permissions, listing.user_permissions);
+        assert_eq!(subset.version, listing.version);
+    }
+
+    /// Builds a listing with a beatmap in each of the given folders, and a stored folder count of 1.
+    fn listing_with_folders(folders: &[Option<&str>]) -> BeatmapListing {
+        BeatmapListing {
+            beatmaps: folders
+                .iter()
+                .enumerate()
+                .map(|(i, folder)| BeatmapEntry {
+                    md5: Some(format!("{:032x}", i)),
+                    folder_name: folder.map(|f| f.to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+            ..sample_listing(20191106)
+        }
+    }
+
+    #[test]
+    fn folder_names_are_compared_case_insensitively() {
+        let listing = listing_with_folders(&[
+            Some("1 Camellia - Exit"),
+            Some("1 CAMELLIA - exit"),
+            Some("2 Other - Song"),
+            Some(""),
+            None,
+        ]);
+
+        assert_eq!(listing.distinct_folder_count(), 2);
+        assert_eq!(listing.folder_count_mismatch(), Some((1, 2)));
+    }
+
+    #[test]
+    fn folder_count_is_fixed() {
+        let mut listing = listing_with_folders(&[Some("1 A - B"), Some("1 a - b")]);
+        assert_eq!(listing.folder_count_mismatch(), None);
+
+        listing.folder_count = 5;
+        assert_eq!(listing.folder_count_mismatch(), Some((5, 1)));
+
+        listing.fix_folder_count();
+        assert_eq!(listing.folder_count, 1);
+        assert_eq!(listing.folder_count_mismatch(), None);
+    }
+
+    #[test]
+    fn normalized_writes_fix_folder_count() {
+        let listing = listing_with_folders(&[Some("1 A - B"), Some("2 C - D"), Some("2 c - d")]);
+
+        let parsed = BeatmapListing::from_bytes(&listing.to_bytes()).unwrap();
+        assert_eq!(parsed.folder_count, 1);
+
+        let options = WriteOptions { normalize: true };
+        let parsed = BeatmapListing::from_bytes(&listing.to_bytes_with_options(options)).unwrap();
+        assert_eq!(parsed.folder_count, 2);
+        assert_eq!(parsed.beatmaps.len(), 3);
+        assert_eq!(listing.folder_count, 1);
+    }
+
+    #[test]
+    fn mutations_keep_folder_count_consistent() {
+        let listing = listing_with_folders(&[Some("1 A - B"), Some("1 A - B"), Some("2 C - D")]);
+
+        let subset = listing.subset(|i, _| i != 2);
+        assert_eq!(subset.folder_count, 1);
+
+        let mut retained = listing.clone();
+        retained.retain(|i, _| i == 2);
+        assert_eq!(retained.beatmaps, listing.beatmaps[2..]);
+        assert_eq!(retained.folder_count, 1);
+
+        let mut merged = subset.clone();
+        let added = merged.merge(&listing_with_folders(&[
+            Some("1 A - B"),
+            Some("3 E - F"),
+            Some("3 e - f"),
+            Some("4 G - H"),
+        ]));
+
+        // The first two beatmaps share MD5 hashes with the beatmaps already in the listing
+        assert_eq!(added, 2);
+        assert_eq!(merged.beatmaps.len(), 4);
+        assert_eq!(merged.folder_count, 3);
+        assert_eq!(merged.folder_count_mismatch(), None);
+    }
+
+    #[test]
+    fn beatmapsets_are_grouped_by_folder() {
+        let beatmap = |folder: Option<&str>| BeatmapEntry {
+            folder_name: folder.map(|f| f.to_string()),
+            ..Default::default()
+        };
+
+        let listing = BeatmapListing {
+            beatmaps: vec![
+                beatmap(Some("1 A - B")),
+                beatmap(Some("2 C - D")),
+                beatmap(None),
+                beatmap(Some("1 A - B")),
+                beatmap(None),
+            ],
+            ..sample_listing(20191106)
+        };
+
+        let sets = listing.beatmapsets();
+        let grouped = sets
+            .iter()
+            .map(|set| (set.folder_name.as_deref(), set.beatmaps.as_slice()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            grouped,
+            vec![
+                (Some("1 A - B"), &[0, 3][..]),
+                (Some("2 C - D"), &[1][..]),
+                (None, &[2][..]),
+                (None, &[4][..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn timing_point_values_are_correct() {
+        let red_line = timing_point_at(500.0, 0.0, true);
+        assert_eq!(red_line.bpm(), Some(120.0));
+        assert_eq!(red_line.slider_velocity_multiplier(), None);
+
+        let green_line = timing_point_at(-50.0, 1000.0, false);
+        assert_eq!(green_line.bpm(), None);
+        assert_eq!(green_line.slider_velocity_multiplier(), Some(2.0));
+
+        // Negative beat lengths on uninherited timing points are invalid
+        assert_eq!(timing_point_at(-500.0, 0.0, true).bpm(), None);
+    }
+
+    #[test]
+    fn main_bpm_is_longest_section() {
+        let beatmap = BeatmapEntry {
+            total_time: 55_000,
+            timing_points: vec![
+                timing_point_at(500.0, 0.0, true),      // 120 BPM for 10s
+                timing_point_at(-50.0, 5_000.0, false), // SV changes are ignored
+                timing_point_at(300.0, 10_000.0, true), // 200 BPM for 25s
+                timing_point_at(500.0, 35_000.0, true), // 120 BPM for 5s
+                timing_point_at(375.0, 40_000.0, true), // 160 BPM for 15s
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(beatmap.main_bpm(), Some(200.0));
+        assert_eq!(beatmap.bpm_range(), Some((120.0, 200.0)));
+
+        let no_timing_points = BeatmapEntry::default();
+        assert_eq!(no_timing_points.main_bpm(), None);
+        assert_eq!(no_timing_points.bpm_range(), None);
+    }
+
+    #[test]
+    fn timing_summary_is_correct() {
+        let beatmap = BeatmapEntry {
+            total_time: 60_000,
+            timing_points: vec![
+                timing_point_at(500.0, 0.0, true),        // 120 BPM for 10s
+                timing_point_at(-50.0, 5_000.0, false),   // 2x SV
+                timing_point_at(300.0, 10_000.0, true),   // 200 BPM for 15s
+                timing_point_at(-200.0, 12_000.0, false), // 0.5x SV
+                timing_point_at(300.1, 25_000.0, true), // ~200 BPM for 10s, rounded to the same BPM
+                timing_point_at(-500.0, 30_000.0, true), // Invalid beat length
+                timing_point_at(375.0, 35_000.0, true), // 160 BPM for 25s
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            beatmap.timing_summary(),
+            TimingSummary {
+                sections: 4,
+                variable_bpm: true,
+                longest_constant_bpm: 25_000.0,
+                average_slider_velocity: Some(1.25),
+            }
+        );
+
+        // Sections with the same BPM are joined together
+        let constant = BeatmapEntry {
+            total_time: 60_000,
+            timing_points: vec![
+                timing_point_at(500.0, 0.0, true),
+                timing_point_at(500.0, 20_000.0, true),
+                timing_point_at(499.9, 45_000.0, true),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            constant.timing_summary(),
+            TimingSummary {
+                sections: 3,
+                variable_bpm: false,
+                longest_constant_bpm: 60_000.0,
+                average_slider_velocity: None,
+            }
+        );
+    }
+
+    #[test]
+    fn mania_key_counts_include_converts() {
+        let beatmap =
+            |gameplay_mode, circles, sliders, circle_size, overall_difficulty| BeatmapEntry {
+                gameplay_mode,
+                hitcircle_count: circles,
+                slider_count: sliders,
+                circle_size,
+                overall_difficulty,
+                ..Default::default()
+            };
+
+        let mania = beatmap(GameplayMode::Mania, 100, 0, 4.0, 8.0);
+        assert_eq!(mania.mania_key_count(), Some(4));
+
+        // Mostly circles
+        let std = |sliders, circle_size, overall_difficulty| {
+            beatmap(
+                GameplayMode::Standard,
+                100 - sliders,
+                sliders,
+                circle_size,
+                overall_difficulty,
+            )
+            .mania_key_count()
+        };
+        assert_eq!(std(10, 4.0, 5.0), Some(7));
+        assert_eq!(std(25, 4.0, 5.0), Some(6));
+        assert_eq!(std(25, 4.0, 6.0), Some(7));
+        assert_eq!(std(40, 5.0, 5.0), Some(6));
+        assert_eq!(std(70, 4.0, 4.0), Some(4));
+        assert_eq!(std(70, 4.0, 5.0), Some(5));
+        assert_eq!(std(50, 4.0, 2.0), Some(4));
+        assert_eq!(std(50, 4.0, 5.0), Some(6));
+
+        // Halves are rounded to even, so OD 4.5 is treated as 4
+        assert_eq!(std(70, 4.0, 4.5), Some(4));
+
+        let taiko = beatmap(GameplayMode::Taiko, 100, 0, 4.0, 8.0);
+        assert_eq!(taiko.mania_key_count(), None);
+    }
+
+    #[test]
+    fn timing_summary_handles_few_timing_points() {
+        assert_eq!(
+            BeatmapEntry::default().timing_summary(),
+            TimingSummary::default()
+        );
+
+        let single = BeatmapEntry {
+            total_time: 90_000,
+            timing_points: vec![timing_point_at(400.0, 1_000.0, true)],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            single.timing_summary(),
+            TimingSummary {
+                sections: 1,
+                variable_bpm: false,
+                longest_constant_bpm: 89_000.0,
+                average_slider_velocity: None,
+            }
         );
 
+        // Some beatmaps in the wild only have inherited timing points
+        let all_inherited = BeatmapEntry {
+            total_time: 90_000,
+            timing_points: vec![
+                timing_point_at(-100.0, 0.0, false),
+                timing_point_at(-50.0, 1_000.0, false),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            all_inherited.timing_summary(),
+            TimingSummary {
+                sections: 0,
+                variable_bpm: false,
+                longest_constant_bpm: 0.0,
+                average_slider_velocity: Some(1.5),
+            }
+        );
+    }
+
+    #[test]
+    fn ranked_status_decoding_works() {
+        use RankedStatus::*;
+
+        assert_eq!(ranked_status(&[0]), Ok((&[][..], Unknown)));
+        assert_eq!(ranked_status(&[1]), Ok((&[][..], Unsubmitted)));
+        assert_eq!(ranked_status(&[2]), Ok((&[][..], Pending)));
+        assert_eq!(ranked_status(&[4]), Ok((&[][..], Ranked)));
+        assert_eq!(ranked_status(&[5]), Ok((&[][..], Approved)));
+        assert_eq!(ranked_status(&[6]), Ok((&[][..], Qualified)));
+        assert_eq!(ranked_status(&[7]), Ok((&[][..], Loved)));
+
         assert_eq!(
-            int_double_pair(&missing_middle_tag),
+            ranked_status(&[10]),
             Err(nom::Err::Error(nom::error::Error {
-                input: &double.to_le_bytes()[..],
-                code: nom::error::ErrorKind::Tag
+                input: &[10][..],
+                code: nom::error::ErrorKind::Switch
             }))
         );
     }
@@ -661,4 +3145,30 @@ pub mod tests {
 
         assert_eq!(star_ratings(&input), Ok((&[][..], ratings)));
     }
+
+    #[test]
+    fn float_star_ratings_decoding_works() {
+        let ratings = vec![
+            StarRating {
+                mods: Mods::none(),
+                rating: 1.25,
+            },
+            StarRating {
+                mods: Mods::NoFail.into(),
+                rating: 2.5,
+            },
+        ];
+
+        let length = ratings.len() as u32;
+        let mut input = length.to_le_bytes().to_vec();
+
+        for StarRating { mods, rating } in ratings.iter() {
+            input.push(0x08);
+            input.extend_from_slice(&mods.bits().to_le_bytes());
+            input.push(0x0c);
+            input.extend_from_slice(&(*rating as f32).to_le_bytes());
+        }
+
+        assert_eq!(float_star_ratings(&input), Ok((&[][..], ratings)));
+    }
 }
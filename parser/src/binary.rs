@@ -0,0 +1,696 @@
+//! Primitive values shared by osu!'s binary file formats (`osu.db`, `collection.db`, `scores.db` and `.osr` replays).
+//!
+//! Each primitive has a `parse_*` function, which decodes it with [`nom`], and a `write_*` function, which encodes it
+//! in exactly the form the matching parser accepts. The file format parsers and writers are built on top of these, so
+//! an encoding bug only needs to be fixed here.
+//!
+//! The parsers are generic over their error type. With [`nom::error::Error`], a malformed primitive is reported by its
+//! [`ErrorKind`](nom::error::ErrorKind) only; with [`BinaryError`], the [`PrimitiveError`] describing what was wrong is
+//! kept as well.
+
+use flagset::FlagSet;
+use nom::{
+    bytes::complete::take,
+    error::{ErrorKind, FromExternalError, ParseError},
+    number::complete::{le_f32, le_f64, le_u32, le_u64, u8},
+    IResult,
+};
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+
+use crate::common::{GameplayMode, Mods, OsuString, WINDOWS_EPOCH};
+
+/// Flag byte for a missing string (see [`parse_osu_string`]).
+const STRING_ABSENT: u8 = 0x00;
+
+/// Flag byte for a present string (see [`parse_osu_string`]).
+const STRING_PRESENT: u8 = 0x0b;
+
+/// Tag byte before the integer in an int-double or int-float pair.
+const INT_TAG: u8 = 0x08;
+
+/// Tag byte before the float in an int-float pair.
+const FLOAT_TAG: u8 = 0x0c;
+
+/// Tag byte before the double in an int-double pair.
+const DOUBLE_TAG: u8 = 0x0d;
+
+/// Describes why a primitive value couldn't be parsed.
+#[derive(Error, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrimitiveError {
+    #[error("ULEB128 value is too large")]
+    OverlongUleb128,
+
+    #[error("Unexpected string flag 0x{0:02x} (expected 0x00 or 0x0b)")]
+    BadStringFlag(u8),
+
+    #[error("Unexpected pair tag 0x{found:02x} (expected 0x{expected:02x})")]
+    BadPairTag { expected: u8, found: u8 },
+
+    #[error("String is not valid UTF-8")]
+    InvalidUtf8,
+
+    #[error("Date is out of range")]
+    DateOutOfRange,
+
+    #[error("Unknown gameplay mode {0}")]
+    UnknownGameplayMode(u8),
+}
+
+impl PrimitiveError {
+    /// Gets the nom error kind reported for this error, for error types which can't hold a [`PrimitiveError`].
+    pub fn kind(&self) -> ErrorKind {
+        use PrimitiveError::*;
+
+        match self {
+            OverlongUleb128 => ErrorKind::TooLarge,
+            BadStringFlag(_) | BadPairTag { .. } => ErrorKind::Tag,
+            InvalidUtf8 => ErrorKind::MapRes,
+            DateOutOfRange => ErrorKind::MapOpt,
+            UnknownGameplayMode(_) => ErrorKind::Switch,
+        }
+    }
+}
+
+/// A parse error which keeps the [`PrimitiveError`] for malformed primitives.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinaryError<I> {
+    /// The input at the point where parsing failed
+    pub input: I,
+
+    /// The kind of error
+    pub kind: ErrorKind,
+
+    /// What was wrong with the primitive, if a primitive was malformed
+    pub primitive: Option<PrimitiveError>,
+}
+
+impl<I> ParseError<I> for BinaryError<I> {
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        BinaryError {
+            input,
+            kind,
+            primitive: None,
+        }
+    }
+
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<I> FromExternalError<I, PrimitiveError> for BinaryError<I> {
+    fn from_external_error(input: I, kind: ErrorKind, e: PrimitiveError) -> Self {
+        BinaryError {
+            input,
+            kind,
+            primitive: Some(e),
+        }
+    }
+}
+
+/// Error types which the primitive parsers can report errors with.
+pub trait PrimitiveParseError<I>: ParseError<I> + FromExternalError<I, PrimitiveError> {}
+
+impl<I, E> PrimitiveParseError<I> for E where E: ParseError<I> + FromExternalError<I, PrimitiveError>
+{}
+
+/// Fails at `input` with a [`PrimitiveError`].
+fn malformed<'a, O, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+    error: PrimitiveError,
+) -> IResult<&'a [u8], O, E> {
+    Err(nom::Err::Error(E::from_external_error(
+        input,
+        error.kind(),
+        error,
+    )))
+}
+
+/// Parses a boolean. Any non-zero byte is treated as `true`.
+pub fn parse_boolean<'a, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], bool, E> {
+    let (i, byte) = u8(input)?;
+    Ok((i, byte != 0))
+}
+
+/// Encodes a boolean as a single byte (see [`parse_boolean`]).
+#[cfg(feature = "writer")]
+pub fn write_boolean(output: &mut Vec<u8>, value: bool) {
+    output.push(value as u8);
+}
+
+/// Decodes a ULEB128 value into an unsigned pointer-sized integer.
+///
+/// Values which don't fit into a `usize` are rejected with [`PrimitiveError::OverlongUleb128`].
+pub fn parse_uleb128<'a, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], usize, E> {
+    let mut i = input;
+    let mut result = 0usize;
+    let mut shift = 0;
+
+    loop {
+        let (rest, byte) = u8(i)?;
+        let bits = (byte & 0x7F) as usize;
+
+        if bits != 0 && (shift >= usize::BITS || (bits << shift) >> shift != bits) {
+            return malformed(input, PrimitiveError::OverlongUleb128);
+        }
+
+        if shift < usize::BITS {
+            result |= bits << shift;
+        }
+
+        i = rest;
+
+        if byte & 0x80 == 0 {
+            return Ok((i, result));
+        }
+
+        shift = shift.saturating_add(7);
+    }
+}
+
+/// Encodes an unsigned pointer-sized integer as a ULEB128 value (see [`parse_uleb128`]).
+#[cfg(feature = "writer")]
+pub fn write_uleb128(output: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            output.push(byte);
+            break;
+        }
+
+        output.push(byte | 0x80);
+    }
+}
+
+/// Decodes a string found in osu!'s binary file formats.
+///
+/// - If the first byte is 0x00, then no string value is present.
+/// - If the first byte is 0x0b, then this is followed by a ULEB128 value indicating the length, then the UTF-8 string bytes.
+///
+///  **NOTE**: There are two possible values for empty strings; these can be distinguished as follows:
+///
+/// - `0x00` => Empty string marker; output is `None`
+/// - `0x0b, 0x00` => Zero length string; output is `Some("")`
+///
+/// Any other first byte is rejected with [`PrimitiveError::BadStringFlag`].
+pub fn parse_osu_string<'a, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], OsuString, E> {
+    let (i, flag) = u8(input)?;
+
+    match flag {
+        STRING_ABSENT => Ok((i, None)),
+        STRING_PRESENT => {
+            let (i, length) = parse_uleb128(i)?;
+            let (rest, bytes) = take(length)(i)?;
+
+            match std::str::from_utf8(bytes) {
+                Ok(value) => Ok((rest, Some(value.to_string()))),
+                Err(_) => malformed(i, PrimitiveError::InvalidUtf8),
+            }
+        }
+        _ => malformed(input, PrimitiveError::BadStringFlag(flag)),
+    }
+}
+
+/// Skips over a string (see [`parse_osu_string`]) without checking or copying it.
+pub fn skip_osu_string<'a, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], (), E> {
+    let (i, flag) = u8(input)?;
+
+    match flag {
+        STRING_ABSENT => Ok((i, ())),
+        STRING_PRESENT => {
+            let (i, length) = parse_uleb128(i)?;
+            let (i, _) = take(length)(i)?;
+            Ok((i, ()))
+        }
+        _ => malformed(input, PrimitiveError::BadStringFlag(flag)),
+    }
+}
+
+/// Encodes a string (see [`parse_osu_string`]).
+#[cfg(feature = "writer")]
+pub fn write_osu_string(output: &mut Vec<u8>, value: &OsuString) {
+    match value {
+        None => output.push(STRING_ABSENT),
+        Some(value) => {
+            output.push(STRING_PRESENT);
+            write_uleb128(output, value.len());
+            output.extend_from_slice(value.as_bytes());
+        }
+    }
+}
+
+/// Parses a date from .NET's [`DateTime.Ticks`](https://learn.microsoft.com/en-us/dotnet/api/system.datetime.ticks?view=netframework-4.7.2).
+///
+/// Dates which can't be represented are rejected with [`PrimitiveError::DateOutOfRange`].
+pub fn parse_windows_datetime<'a, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], OffsetDateTime, E> {
+    let (i, ticks) = le_u64(input)?;
+
+    // In .NET, there are 10,000 ticks per millisecond
+    // So 10 ticks / microsecond, 0.01 ticks per nanosecond
+    let duration = Duration::microseconds((ticks / 10) as i64)
+        + Duration::nanoseconds(((ticks % 10) * 100) as i64);
+
+    match WINDOWS_EPOCH.checked_add(duration) {
+        Some(datetime) => Ok((i, datetime)),
+        None => malformed(input, PrimitiveError::DateOutOfRange),
+    }
+}
+
+/// Encodes a date as .NET's `DateTime.Ticks` (see [`parse_windows_datetime`]). Dates before the Windows epoch are
+/// written as the epoch itself.
+#[cfg(feature = "writer")]
+pub fn write_windows_datetime(output: &mut Vec<u8>, value: OffsetDateTime) {
+    let ticks = ((value - WINDOWS_EPOCH).whole_nanoseconds() / 100).max(0) as u64;
+    output.extend_from_slice(&ticks.to_le_bytes());
+}
+
+/// Parses a single tag byte, which must be `expected`.
+fn pair_tag<'a, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+    expected: u8,
+) -> IResult<&'a [u8], (), E> {
+    let (i, found) = u8(input)?;
+
+    if found == expected {
+        Ok((i, ()))
+    } else {
+        malformed(input, PrimitiveError::BadPairTag { expected, found })
+    }
+}
+
+/// Parses an int-double pair, i.e. `0x08`, an integer, `0x0d`, then a double.
+///
+/// Unexpected tags are rejected with [`PrimitiveError::BadPairTag`].
+pub fn parse_int_double_pair<'a, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], (u32, f64), E> {
+    let (i, _) = pair_tag(input, INT_TAG)?;
+    let (i, int) = le_u32(i)?;
+    let (i, _) = pair_tag(i, DOUBLE_TAG)?;
+    let (i, double) = le_f64(i)?;
+
+    Ok((i, (int, double)))
+}
+
+/// Encodes an int-double pair (see [`parse_int_double_pair`]).
+#[cfg(feature = "writer")]
+pub fn write_int_double_pair(output: &mut Vec<u8>, int: u32, double: f64) {
+    output.push(INT_TAG);
+    output.extend_from_slice(&int.to_le_bytes());
+    output.push(DOUBLE_TAG);
+    output.extend_from_slice(&double.to_le_bytes());
+}
+
+/// Parses an int-float pair, i.e. `0x08`, an integer, `0x0c`, then a float.
+///
+/// Unexpected tags are rejected with [`PrimitiveError::BadPairTag`].
+pub fn parse_int_float_pair<'a, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], (u32, f32), E> {
+    let (i, _) = pair_tag(input, INT_TAG)?;
+    let (i, int) = le_u32(i)?;
+    let (i, _) = pair_tag(i, FLOAT_TAG)?;
+    let (i, float) = le_f32(i)?;
+
+    Ok((i, (int, float)))
+}
+
+/// Encodes an int-float pair (see [`parse_int_float_pair`]).
+#[cfg(feature = "writer")]
+pub fn write_int_float_pair(output: &mut Vec<u8>, int: u32, float: f32) {
+    output.push(INT_TAG);
+    output.extend_from_slice(&int.to_le_bytes());
+    output.push(FLOAT_TAG);
+    output.extend_from_slice(&float.to_le_bytes());
+}
+
+/// Parses a gameplay mode, stored as a single byte.
+///
+/// Unknown modes are rejected with [`PrimitiveError::UnknownGameplayMode`].
+pub fn parse_gameplay_mode<'a, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], GameplayMode, E> {
+    use GameplayMode::*;
+
+    let (i, mode) = u8(input)?;
+    let mode = match mode {
+        0 => Standard,
+        1 => Taiko,
+        2 => Catch,
+        3 => Mania,
+        _ => return malformed(input, PrimitiveError::UnknownGameplayMode(mode)),
+    };
+
+    Ok((i, mode))
+}
+
+/// Encodes a gameplay mode (see [`parse_gameplay_mode`]).
+#[cfg(feature = "writer")]
+pub fn write_gameplay_mode(output: &mut Vec<u8>, value: GameplayMode) {
+    output.push(value as u8);
+}
+
+/// Parses a set of gameplay modifiers, stored as a 32-bit bit field. Unknown bits are dropped.
+pub fn parse_mods<'a, E: PrimitiveParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], FlagSet<Mods>, E> {
+    let (i, bits) = le_u32(input)?;
+    Ok((i, FlagSet::<Mods>::new_truncated(bits)))
+}
+
+/// Encodes a set of gameplay modifiers (see [`parse_mods`]).
+#[cfg(feature = "writer")]
+pub fn write_mods(output: &mut Vec<u8>, value: FlagSet<Mods>) {
+    output.extend_from_slice(&value.bits().to_le_bytes());
+}
+
+#[cfg(all(test, feature = "writer"))]
+mod tests {
+    use proptest::prelude::*;
+    use time::macros::datetime;
+
+    use super::*;
+
+    type Result<'a, T> = IResult<&'a [u8], T, BinaryError<&'a [u8]>>;
+
+    fn primitive_error<T: std::fmt::Debug>(result: Result<'_, T>) -> Option<PrimitiveError> {
+        match result {
+            Err(nom::Err::Error(e)) => e.primitive,
+            other => panic!("expected an error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn boolean_decoding_works() {
+        // Any non-zero byte should result in true
+        assert_eq!(parse_boolean::<()>(&[0x00]), Ok(((&[][..]), false)));
+        assert_eq!(parse_boolean::<()>(&[0x01]), Ok(((&[][..]), true)));
+
+        assert_eq!(
+            parse_boolean::<()>(&[0xFF, 0x01, 0x02]),
+            Ok(((&[0x01, 0x02][..]), true))
+        );
+    }
+
+    #[test]
+    fn gameplay_mode_decoding_works() {
+        use GameplayMode::*;
+
+        assert_eq!(parse_gameplay_mode::<()>(&[0]), Ok((&[][..], Standard)));
+        assert_eq!(parse_gameplay_mode::<()>(&[1]), Ok((&[][..], Taiko)));
+        assert_eq!(parse_gameplay_mode::<()>(&[2]), Ok((&[][..], Catch)));
+        assert_eq!(parse_gameplay_mode::<()>(&[3]), Ok((&[][..], Mania)));
+
+        assert_eq!(
+            parse_gameplay_mode(&[10]),
+            Err(nom::Err::Error(nom::error::Error {
+                input: &[10][..],
+                code: ErrorKind::Switch
+            }))
+        );
+        assert_eq!(
+            primitive_error(parse_gameplay_mode(&[10])),
+            Some(PrimitiveError::UnknownGameplayMode(10))
+        );
+    }
+
+    #[test]
+    fn uleb128_decoding_works() {
+        // 0xE5, 0x8E, 0x26 ==> 624485
+        // ULEB128 value by itself
+        assert_eq!(
+            parse_uleb128::<()>(&[0xE5, 0x8E, 0x26]),
+            Ok((&[][..], 624485))
+        );
+
+        // ULEB128 value followed by other bytes
+        assert_eq!(
+            parse_uleb128::<()>(&[0xE5, 0x8E, 0x26, 0x80, 0x81, 0x82]),
+            Ok((&[0x80, 0x81, 0x82][..], 624485))
+        );
+
+        // Empty value
+        assert_eq!(
+            parse_uleb128(&[]),
+            Err(nom::Err::Error(nom::error::Error {
+                input: &[][..],
+                code: ErrorKind::Eof
+            }))
+        );
+    }
+
+    #[test]
+    fn overlong_uleb128_values_are_rejected() {
+        let mut too_large = vec![0xFF; usize::BITS as usize / 7];
+        too_large.push(0x7F);
+        assert_eq!(
+            primitive_error(parse_uleb128(&too_large)),
+            Some(PrimitiveError::OverlongUleb128)
+        );
+
+        // Redundant zero bytes don't change the value, so they're accepted
+        assert_eq!(parse_uleb128::<()>(&[0x81, 0x80, 0x00]), Ok((&[][..], 1)));
+    }
+
+    #[test]
+    fn osu_string_decoding_works() {
+        let mut test_string_bytes = vec![0x0b, 0x04];
+        test_string_bytes.extend_from_slice(b"test");
+
+        // Append a few other bytes that aren't part of the string
+        test_string_bytes.extend_from_slice(&[0x01, 0x02, 0x03]);
+
+        assert_eq!(parse_osu_string::<()>(&[0x00]), Ok((&[][..], None)));
+        assert_eq!(
+            parse_osu_string::<()>(&[0x0b, 0x00]),
+            Ok((&[][..], Some("".to_string())))
+        );
+        assert_eq!(
+            parse_osu_string::<()>(&test_string_bytes),
+            Ok((&[0x01, 0x02, 0x03][..], Some("test".to_string())))
+        );
+    }
+
+    #[test]
+    fn malformed_strings_are_rejected() {
+        assert_eq!(
+            primitive_error(parse_osu_string(&[0x0c, 0x00])),
+            Some(PrimitiveError::BadStringFlag(0x0c))
+        );
+        assert_eq!(
+            primitive_error(skip_osu_string(&[0x0c, 0x00])),
+            Some(PrimitiveError::BadStringFlag(0x0c))
+        );
+        assert_eq!(
+            primitive_error(parse_osu_string(&[0x0b, 0x02, 0xC3, 0x28])),
+            Some(PrimitiveError::InvalidUtf8)
+        );
+
+        // A length running past the end of the input isn't a malformed primitive, just a truncated one
+        assert_eq!(primitive_error(parse_osu_string(&[0x0b, 0x05, b'a'])), None);
+    }
+
+    #[test]
+    fn uleb128_encoding_is_correct() {
+        let mut output = Vec::new();
+        write_uleb128(&mut output, 624485);
+        assert_eq!(output, [0xE5, 0x8E, 0x26]);
+    }
+
+    #[test]
+    fn windows_datetime_encoding_works() {
+        let mut output = Vec::new();
+        write_windows_datetime(&mut output, datetime!(2023-07-28 15:30:20 UTC));
+        write_windows_datetime(&mut output, WINDOWS_EPOCH);
+
+        let mut expected = 638261550200000000u64.to_le_bytes().to_vec();
+        expected.extend(0u64.to_le_bytes());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn windows_datetime_decoding_works() {
+        // 07/28/2023 15:30:20 +00:00 ==> 638261550200000000 ticks
+        let datetime = datetime!(2023-07-28 15:30:20 UTC);
+        let ticks = 638261550200000000u64;
+
+        // Should only parse the first 8 bytes
+        let mut input = ticks.to_le_bytes().to_vec();
+        input.extend_from_slice(&[0x01, 0x02, 0x03]);
+
+        assert_eq!(
+            parse_windows_datetime::<()>(&input),
+            Ok((&[0x01, 0x02, 0x03][..], datetime))
+        );
+    }
+
+    #[test]
+    fn out_of_range_dates_are_rejected() {
+        assert_eq!(
+            primitive_error(parse_windows_datetime(&u64::MAX.to_le_bytes())),
+            Some(PrimitiveError::DateOutOfRange)
+        );
+    }
+
+    #[test]
+    fn int_double_pair_decoding_works() {
+        let int: u32 = 100;
+        let double: f64 = 1234.56;
+        let extra = [0x01, 0x02, 0x03];
+
+        let mut pair = Vec::new();
+        pair.push(0x08);
+        pair.extend_from_slice(&int.to_le_bytes());
+        pair.push(0x0d);
+        pair.extend_from_slice(&double.to_le_bytes());
+        pair.extend_from_slice(&extra);
+
+        let mut missing_front_tag = Vec::new();
+        missing_front_tag.extend_from_slice(&int.to_le_bytes());
+        missing_front_tag.extend_from_slice(&double.to_le_bytes());
+
+        let mut missing_middle_tag = Vec::new();
+        missing_middle_tag.push(0x08);
+        missing_middle_tag.extend_from_slice(&int.to_le_bytes());
+        missing_middle_tag.extend_from_slice(&double.to_le_bytes());
+
+        assert_eq!(
+            parse_int_double_pair::<()>(&pair),
+            Ok((&extra[..], (int, double)))
+        );
+
+        assert_eq!(
+            parse_int_double_pair(&missing_front_tag),
+            Err(nom::Err::Error(nom::error::Error {
+                input: &missing_front_tag[..],
+                code: ErrorKind::Tag
+            }))
+        );
+
+        assert_eq!(
+            parse_int_double_pair(&missing_middle_tag),
+            Err(nom::Err::Error(nom::error::Error {
+                input: &double.to_le_bytes()[..],
+                code: ErrorKind::Tag
+            }))
+        );
+    }
+
+    #[test]
+    fn bad_pair_tags_are_rejected() {
+        let mut pair = Vec::new();
+        write_int_double_pair(&mut pair, 100, 1234.56);
+
+        let mut float_pair = pair.clone();
+        float_pair[5] = FLOAT_TAG;
+        assert_eq!(
+            primitive_error(parse_int_double_pair(&float_pair)),
+            Some(PrimitiveError::BadPairTag {
+                expected: DOUBLE_TAG,
+                found: FLOAT_TAG
+            })
+        );
+
+        let missing_front_tag = &pair[1..];
+        assert_eq!(
+            parse_int_double_pair(missing_front_tag),
+            Err(nom::Err::Error(nom::error::Error {
+                input: missing_front_tag,
+                code: ErrorKind::Tag
+            }))
+        );
+        assert_eq!(
+            primitive_error(parse_int_float_pair(&pair)),
+            Some(PrimitiveError::BadPairTag {
+                expected: FLOAT_TAG,
+                found: DOUBLE_TAG
+            })
+        );
+    }
+
+    fn any_gameplay_mode() -> impl Strategy<Value = GameplayMode> {
+        prop_oneof![
+            Just(GameplayMode::Standard),
+            Just(GameplayMode::Taiko),
+            Just(GameplayMode::Catch),
+            Just(GameplayMode::Mania),
+        ]
+    }
+
+    /// Checks that `parse` reads back exactly what `write` wrote, leaving the bytes after it untouched.
+    fn round_trips<T, W, P>(value: T, write: W, parse: P) -> std::result::Result<(), TestCaseError>
+    where
+        T: PartialEq + std::fmt::Debug,
+        W: Fn(&mut Vec<u8>, &T),
+        P: for<'a> Fn(&'a [u8]) -> Result<'a, T>,
+    {
+        let mut output = Vec::new();
+        write(&mut output, &value);
+        output.push(0xAB);
+
+        prop_assert_eq!(parse(&output), Ok((&[0xAB][..], value)));
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn booleans_round_trip(value: bool) {
+            round_trips(value, |o, v| write_boolean(o, *v), |i| parse_boolean(i))?;
+        }
+
+        #[test]
+        fn uleb128_values_round_trip(value: usize) {
+            round_trips(value, |o, v| write_uleb128(o, *v), |i| parse_uleb128(i))?;
+        }
+
+        #[test]
+        fn osu_strings_round_trip(value in proptest::option::of(".{0,200}")) {
+            round_trips(value.clone(), write_osu_string, |i| parse_osu_string(i))?;
+
+            let mut output = Vec::new();
+            write_osu_string(&mut output, &value);
+            prop_assert_eq!(skip_osu_string::<()>(&output), Ok((&[][..], ())));
+        }
+
+        #[test]
+        fn windows_datetimes_round_trip(ticks in 0u64..3155378975999999999) {
+            let datetime = WINDOWS_EPOCH + Duration::microseconds((ticks / 10) as i64)
+                + Duration::nanoseconds(((ticks % 10) * 100) as i64);
+            round_trips(datetime, |o, v| write_windows_datetime(o, *v), |i| parse_windows_datetime(i))?;
+        }
+
+        #[test]
+        fn int_double_pairs_round_trip(int: u32, double in proptest::num::f64::NORMAL | proptest::num::f64::ZERO) {
+            round_trips((int, double), |o, (i, d)| write_int_double_pair(o, *i, *d), |i| parse_int_double_pair(i))?;
+        }
+
+        #[test]
+        fn int_float_pairs_round_trip(int: u32, float in proptest::num::f32::NORMAL | proptest::num::f32::ZERO) {
+            round_trips((int, float), |o, (i, f)| write_int_float_pair(o, *i, *f), |i| parse_int_float_pair(i))?;
+        }
+
+        #[test]
+        fn gameplay_modes_round_trip(mode in any_gameplay_mode()) {
+            round_trips(mode, |o, v| write_gameplay_mode(o, *v), |i| parse_gameplay_mode(i))?;
+        }
+
+        #[test]
+        fn mods_round_trip(bits: u32) {
+            let mods = FlagSet::<Mods>::new_truncated(bits);
+            round_trips(mods, |o, v| write_mods(o, *v), |i| parse_mods(i))?;
+        }
+    }
+}
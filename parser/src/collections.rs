@@ -1,15 +1,32 @@
 //! Models for the `collection.db` database file, which contains information on beatmap collections.
+//!
+//! Collections can also be shared as plain text, with the MD5 hash of one beatmap on each line (see
+//! [`Collection::write_text`]).
 
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
+use std::{
+    collections::HashSet,
+    fmt,
+    io::{self, Write},
+};
 
 use nom::{multi::length_count, number::complete::le_u32, IResult};
 
+#[cfg(feature = "writer")]
+use crate::binary::write_osu_string;
+#[cfg(all(feature = "writer", not(target_arch = "wasm32")))]
+use crate::common::write_file_replacing;
 use crate::{
-    common::{osu_string, OsuString},
+    beatmaps::{BeatmapEntry, BeatmapListing},
+    binary::parse_osu_string,
+    common::OsuString,
+    detect::FileKind,
     error::Error,
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CollectionListing {
     /// Version (e.g. 20150203)
     pub version: u32,
@@ -18,7 +35,8 @@ pub struct CollectionListing {
     pub collections: Vec<Collection>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Collection {
     /// Name of the collection
     pub name: OsuString,
@@ -27,20 +45,179 @@ pub struct Collection {
     pub beatmap_md5s: Vec<OsuString>,
 }
 
+/// Represents the lines of a text collection which aren't MD5 hashes (see [`Collection::from_text`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidLines {
+    /// Line number (starting from 1) and text of each invalid line
+    pub lines: Vec<(usize, String)>,
+}
+
 impl CollectionListing {
     /// Parses the contents of a `collection.db` file.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "parse_collections", skip_all, fields(bytes = data.len()))
+    )]
     pub fn from_bytes(data: &[u8]) -> Result<CollectionListing, Error> {
-        let (_, listing) = collection_listing(data).map_err(|e| e.to_owned())?;
+        let (_, listing) =
+            collection_listing(data).map_err(|e| Error::parse(FileKind::Collections, data, e))?;
         Ok(listing)
     }
 
     /// Convenience method for reading the contents of an `collection.db` file and parsing it as a `CollectionListing`.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<CollectionListing, Error> {
-        let data = std::fs::read(path)?;
+        let data = crate::shared::read_database(path, FileKind::Collections)?;
         Self::from_bytes(&data)
     }
+
+    /// Encodes the listing in the `collection.db` format.
+    #[cfg(feature = "writer")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        output.extend_from_slice(&self.version.to_le_bytes());
+        output.extend_from_slice(&(self.collections.len() as u32).to_le_bytes());
+
+        for collection in &self.collections {
+            write_osu_string(&mut output, &collection.name);
+            output.extend_from_slice(&(collection.beatmap_md5s.len() as u32).to_le_bytes());
+
+            for md5 in &collection.beatmap_md5s {
+                write_osu_string(&mut output, md5);
+            }
+        }
+
+        output
+    }
+
+    /// Writes the listing in the `collection.db` format, e.g. into an archive (see [`export::bundle`](crate::export)).
+    #[cfg(feature = "writer")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())?;
+        writer.flush()
+    }
+
+    /// Writes the listing to a `collection.db` file.
+    ///
+    /// The listing is written to a temporary file next to `path`, which then replaces the original file. This means
+    /// that the original file is left untouched if anything goes wrong while writing.
+    #[cfg(all(feature = "writer", not(target_arch = "wasm32")))]
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        write_file_replacing(path.as_ref(), &self.to_bytes()).map_err(Error::from)
+    }
+}
+
+impl Collection {
+    /// Creates a collection containing some beatmaps. Beatmaps without an MD5 hash can't be added to collections, so
+    /// they're skipped, as are any beatmaps listed more than once.
+    pub fn from_beatmaps<'a>(
+        name: String,
+        beatmaps: impl IntoIterator<Item = &'a BeatmapEntry>,
+    ) -> Collection {
+        let mut seen = HashSet::new();
+
+        Collection {
+            name: Some(name),
+            beatmap_md5s: beatmaps
+                .into_iter()
+                .filter_map(|beatmap| beatmap.md5.as_deref())
+                .filter(|md5| seen.insert(*md5))
+                .map(|md5| Some(md5.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Writes the collection as text, with the MD5 hash of one beatmap on each line. If `listing` is given, each
+    /// beatmap found in it is followed by a comment naming it, e.g. `# Artist - Title [Difficulty]`.
+    pub fn write_text<W: Write>(
+        &self,
+        listing: Option<&BeatmapListing>,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let beatmaps = listing
+            .map(BeatmapListing::beatmaps_by_md5)
+            .unwrap_or_default();
+
+        for md5 in self.beatmap_md5s.iter().flatten() {
+            match beatmaps.get(md5.as_str()) {
+                Some(beatmap) => writeln!(
+                    writer,
+                    "{} # {} - {} [{}]",
+                    md5,
+                    beatmap.display_artist(false).unwrap_or_default(),
+                    beatmap.display_title(false).unwrap_or_default(),
+                    beatmap.difficulty.as_deref().unwrap_or_default()
+                )?,
+                None => writeln!(writer, "{}", md5)?,
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// Parses a collection written as text, e.g. by [`Collection::write_text`]. Each line has the MD5 hash of one
+    /// beatmap, optionally followed by a comment starting with `#`. Blank lines and lines that are only a comment are
+    /// skipped, as are beatmaps listed more than once.
+    ///
+    /// Returns every line that isn't a 32 character hexadecimal hash if there are any, so that they can all be fixed
+    /// at once.
+    pub fn from_text(name: String, text: &str) -> Result<Collection, InvalidLines> {
+        let mut seen = HashSet::new();
+        let mut beatmap_md5s = Vec::new();
+        let mut invalid = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let md5 = line.split('#').next().unwrap_or_default().trim();
+
+            if md5.is_empty() {
+                continue;
+            }
+
+            if md5.len() != 32 || !md5.chars().all(|c| c.is_ascii_hexdigit()) {
+                invalid.push((i + 1, line.to_string()));
+                continue;
+            }
+
+            let md5 = md5.to_ascii_lowercase();
+
+            if seen.insert(md5.clone()) {
+                beatmap_md5s.push(Some(md5));
+            }
+        }
+
+        if !invalid.is_empty() {
+            return Err(InvalidLines { lines: invalid });
+        }
+
+        Ok(Collection {
+            name: Some(name),
+            beatmap_md5s,
+        })
+    }
+}
+
+impl fmt::Display for InvalidLines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((line, text)) = self.lines.first() else {
+            return write!(f, "No invalid lines");
+        };
+
+        write!(f, "Line {} isn't an MD5 hash: '{}'", line, text.trim())?;
+
+        if self.lines.len() > 1 {
+            write!(f, " (and {} more invalid lines)", self.lines.len() - 1)?;
+        }
+
+        Ok(())
+    }
 }
 
+impl std::error::Error for InvalidLines {}
+
 /// Parses a `collection.db` file.
 fn collection_listing(input: &[u8]) -> IResult<&[u8], CollectionListing> {
     let (i, version) = le_u32(input)?;
@@ -57,8 +234,141 @@ fn collection_listing(input: &[u8]) -> IResult<&[u8], CollectionListing> {
 
 /// Parses a collection entry in the `collection.db` file.
 fn collection(input: &[u8]) -> IResult<&[u8], Collection> {
-    let (i, name) = osu_string(input)?;
-    let (i, beatmap_md5s) = length_count(le_u32, osu_string)(i)?;
+    let (i, name) = parse_osu_string(input)?;
+    let (i, beatmap_md5s) = length_count(le_u32, parse_osu_string)(i)?;
 
     Ok((i, Collection { name, beatmap_md5s }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing() -> CollectionListing {
+        CollectionListing {
+            version: 20150203,
+            collections: vec![
+                Collection {
+                    name: Some("Favourites".to_string()),
+                    beatmap_md5s: vec![
+                        Some("0123456789abcdef0123456789abcdef".to_string()),
+                        Some("fedcba9876543210fedcba9876543210".to_string()),
+                    ],
+                },
+                Collection {
+                    name: Some("Empty".to_string()),
+                    beatmap_md5s: vec![],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn collection_listing_round_trips() {
+        let listing = listing();
+        let parsed = CollectionListing::from_bytes(&listing.to_bytes()).unwrap();
+
+        assert_eq!(parsed.version, listing.version);
+        assert_eq!(parsed.collections.len(), 2);
+        assert_eq!(parsed.collections[0].name, listing.collections[0].name);
+        assert_eq!(
+            parsed.collections[0].beatmap_md5s,
+            listing.collections[0].beatmap_md5s
+        );
+        assert!(parsed.collections[1].beatmap_md5s.is_empty());
+    }
+
+    #[test]
+    fn collection_listing_is_written_to_file() {
+        let dir = std::env::temp_dir().join(format!("osu-db-parser-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("collection.db");
+
+        // Existing files are replaced, and the temporary file is cleaned up
+        std::fs::write(&path, b"old").unwrap();
+        listing().to_file(&path).unwrap();
+
+        let parsed = CollectionListing::from_file(&path).unwrap();
+        assert_eq!(parsed.collections.len(), 2);
+        assert!(!dir.join("collection.db.tmp").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn text_collections_round_trip() {
+        let collection = listing().collections.remove(0);
+        let beatmaps = BeatmapListing {
+            beatmaps: vec![BeatmapEntry {
+                artist_name: Some("Camellia".to_string()),
+                song_title: Some("Exit This Earth's Atomosphere".to_string()),
+                difficulty: Some("#1 Extra".to_string()),
+                md5: Some("0123456789abcdef0123456789abcdef".to_string()),
+                ..Default::default()
+            }],
+            version: 20150203,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: crate::common::WINDOWS_EPOCH,
+            player_name: None,
+            user_permissions: Default::default(),
+        };
+
+        let mut text = Vec::new();
+        collection.write_text(Some(&beatmaps), &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+
+        // Only beatmaps in the listing get a comment
+        assert_eq!(
+            text,
+            "0123456789abcdef0123456789abcdef # Camellia - Exit This Earth's Atomosphere [#1 Extra]\n\
+             fedcba9876543210fedcba9876543210\n"
+        );
+        assert_eq!(
+            Collection::from_text("Favourites".to_string(), &text),
+            Ok(collection.clone())
+        );
+
+        let mut text = Vec::new();
+        collection.write_text(None, &mut text).unwrap();
+        assert_eq!(
+            Collection::from_text("Favourites".to_string(), &String::from_utf8(text).unwrap()),
+            Ok(collection)
+        );
+    }
+
+    #[test]
+    fn text_collections_skip_comments_and_blank_lines() {
+        let text = "# Mappool for round 1\r\n\
+                    \r\n\
+                    0123456789ABCDEF0123456789ABCDEF   # NM1\r\n\
+                    \t0123456789abcdef0123456789abcdef\r\n";
+        let collection = Collection::from_text("Round 1".to_string(), text).unwrap();
+
+        assert_eq!(
+            collection.beatmap_md5s,
+            vec![Some("0123456789abcdef0123456789abcdef".to_string())]
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_reported() {
+        let text = "0123456789abcdef0123456789abcdef\n\
+                    0123456789abcdef0123456789abcde # too short\n\
+                    \n\
+                    https://osu.ppy.sh/b/129891\n";
+        let error = Collection::from_text("Broken".to_string(), text).unwrap_err();
+
+        assert_eq!(
+            error.lines,
+            vec![
+                (2, "0123456789abcdef0123456789abcde # too short".to_string()),
+                (4, "https://osu.ppy.sh/b/129891".to_string()),
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Line 2 isn't an MD5 hash: '0123456789abcdef0123456789abcde # too short' (and 1 more invalid lines)"
+        );
+    }
+}
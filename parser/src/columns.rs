@@ -0,0 +1,486 @@
+//! Named columns of beatmap information, shared by anything which displays or exports beatmaps as a table.
+//!
+//! Each [`Column`] has a short, stable name (e.g. `stars_std`) which can be used to refer to it in templates or
+//! configuration, along with a human-readable header.
+
+use std::{borrow::Cow, str::FromStr};
+
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::{
+    beatmaps::{BeatmapEntry, MetadataLanguage, StarPolicy},
+    common::{GameplayMode, Grade as BeatmapGrade, Mods, OsuStr, TimestampKind},
+    format, links,
+};
+
+/// Represents a column of information about a beatmap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Column {
+    Artist,
+    ArtistUnicode,
+    Title,
+    TitleUnicode,
+    Creator,
+    Difficulty,
+    Source,
+    Tags,
+    Md5,
+    BeatmapsetId,
+    DifficultyId,
+    Mode,
+    Status,
+
+    /// NoMod star rating chosen by a [`StarPolicy`], which is the beatmap's own gameplay mode by default
+    Stars,
+    StarsStd,
+    StarsTaiko,
+    StarsCatch,
+    StarsMania,
+
+    /// Main BPM (i.e. the BPM used for the longest duration)
+    Bpm,
+
+    /// Whether the beatmap uses more than one BPM
+    VariableBpm,
+
+    /// Total length, in seconds
+    Length,
+
+    /// Drain time, in seconds
+    Drain,
+    ApproachRate,
+    CircleSize,
+    OverallDifficulty,
+    HpDrain,
+    Objects,
+    LastPlayed,
+    LastModified,
+
+    /// When the beatmap was added, approximated from the times stored in `osu!.db` (see
+    /// [`BeatmapEntry::approximate_added_date`]). Folder creation times need an [`AddedDates`](crate::added::AddedDates)
+    /// cache, so they aren't used here.
+    Added,
+
+    /// Grade achieved in the beatmap's own gameplay mode
+    Grade,
+    GradeStd,
+    GradeTaiko,
+    GradeCatch,
+    GradeMania,
+    Folder,
+    Url,
+}
+
+/// Represents the value of a column for a particular beatmap.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnValue<'a> {
+    Text(Cow<'a, str>),
+    Integer(u64),
+    Float(f64),
+
+    /// A duration, in seconds
+    Duration(u32),
+    DateTime(OffsetDateTime),
+
+    /// A grade, which is sorted from best to worst
+    Grade(BeatmapGrade),
+
+    /// The value is missing (e.g. the beatmap has never been played)
+    Empty,
+}
+
+impl Column {
+    /// Every available column, in their default display order.
+    pub const ALL: [Column; 37] = [
+        Column::Artist,
+        Column::ArtistUnicode,
+        Column::Title,
+        Column::TitleUnicode,
+        Column::Creator,
+        Column::Difficulty,
+        Column::Source,
+        Column::Tags,
+        Column::Md5,
+        Column::BeatmapsetId,
+        Column::DifficultyId,
+        Column::Mode,
+        Column::Status,
+        Column::Stars,
+        Column::StarsStd,
+        Column::StarsTaiko,
+        Column::StarsCatch,
+        Column::StarsMania,
+        Column::Bpm,
+        Column::VariableBpm,
+        Column::Length,
+        Column::Drain,
+        Column::ApproachRate,
+        Column::CircleSize,
+        Column::OverallDifficulty,
+        Column::HpDrain,
+        Column::Objects,
+        Column::LastPlayed,
+        Column::LastModified,
+        Column::Added,
+        Column::Grade,
+        Column::GradeStd,
+        Column::GradeTaiko,
+        Column::GradeCatch,
+        Column::GradeMania,
+        Column::Folder,
+        Column::Url,
+    ];
+
+    /// Gets the short name used to refer to this column (e.g. `stars_std`).
+    pub fn name(&self) -> &'static str {
+        use Column::*;
+
+        match self {
+            Artist => "artist",
+            ArtistUnicode => "artist_unicode",
+            Title => "title",
+            TitleUnicode => "title_unicode",
+            Creator => "creator",
+            Difficulty => "difficulty",
+            Source => "source",
+            Tags => "tags",
+            Md5 => "md5",
+            BeatmapsetId => "beatmapset_id",
+            DifficultyId => "difficulty_id",
+            Mode => "mode",
+            Status => "status",
+            Stars => "stars",
+            StarsStd => "stars_std",
+            StarsTaiko => "stars_taiko",
+            StarsCatch => "stars_catch",
+            StarsMania => "stars_mania",
+            Bpm => "bpm",
+            VariableBpm => "variable_bpm",
+            Length => "length",
+            Drain => "drain",
+            ApproachRate => "ar",
+            CircleSize => "cs",
+            OverallDifficulty => "od",
+            HpDrain => "hp",
+            Objects => "objects",
+            LastPlayed => "last_played",
+            LastModified => "last_modified",
+            Added => "added",
+            Grade => "grade",
+            GradeStd => "grade_std",
+            GradeTaiko => "grade_taiko",
+            GradeCatch => "grade_catch",
+            GradeMania => "grade_mania",
+            Folder => "folder",
+            Url => "url",
+        }
+    }
+
+    /// Gets the human-readable header for this column.
+    pub fn header(&self) -> &'static str {
+        use Column::*;
+
+        match self {
+            Artist => "Artist",
+            ArtistUnicode => "Artist (Unicode)",
+            Title => "Title",
+            TitleUnicode => "Title (Unicode)",
+            Creator => "Creator",
+            Difficulty => "Difficulty",
+            Source => "Source",
+            Tags => "Tags",
+            Md5 => "MD5",
+            BeatmapsetId => "Beatmapset ID",
+            DifficultyId => "Difficulty ID",
+            Mode => "Mode",
+            Status => "Status",
+            Stars => "Stars",
+            StarsStd => "Stars (osu!)",
+            StarsTaiko => "Stars (Taiko)",
+            StarsCatch => "Stars (Catch)",
+            StarsMania => "Stars (Mania)",
+            Bpm => "BPM",
+            VariableBpm => "Variable BPM",
+            Length => "Length",
+            Drain => "Drain",
+            ApproachRate => "AR",
+            CircleSize => "CS",
+            OverallDifficulty => "OD",
+            HpDrain => "HP",
+            Objects => "Objects",
+            LastPlayed => "Last Played",
+            LastModified => "Last Modified",
+            Added => "Added",
+            Grade => "Grade",
+            GradeStd => "Grade (osu!)",
+            GradeTaiko => "Grade (Taiko)",
+            GradeCatch => "Grade (Catch)",
+            GradeMania => "Grade (Mania)",
+            Folder => "Folder",
+            Url => "URL",
+        }
+    }
+
+    /// Gets which clock this column's dates were stored from (see [`TimestampKind`]), or `None` if it doesn't hold
+    /// dates.
+    pub fn timestamp_kind(&self) -> Option<TimestampKind> {
+        match self {
+            Column::LastPlayed => Some(BeatmapEntry::LAST_PLAYED_KIND),
+            Column::LastModified | Column::Added => Some(BeatmapEntry::LAST_MODIFICATION_TIME_KIND),
+            _ => None,
+        }
+    }
+
+    /// Gets the value of this column for a beatmap, showing the romanised artist and title.
+    pub fn value<'a>(&self, beatmap: &'a BeatmapEntry) -> ColumnValue<'a> {
+        self.value_in(beatmap, MetadataLanguage::default())
+    }
+
+    /// Gets the value of this column for a beatmap, showing the artist and title in `language` (see
+    /// [`BeatmapEntry::display_artist`]). The Unicode artist and title columns always show the Unicode values.
+    pub fn value_in<'a>(
+        &self,
+        beatmap: &'a BeatmapEntry,
+        language: MetadataLanguage,
+    ) -> ColumnValue<'a> {
+        self.value_with(beatmap, language, StarPolicy::default())
+    }
+
+    /// Gets the value of this column for a beatmap, showing the artist and title in `language` and choosing the Stars
+    /// column's star rating using `stars`. The columns for a particular mode's star rating ignore `stars`.
+    pub fn value_with<'a>(
+        &self,
+        beatmap: &'a BeatmapEntry,
+        language: MetadataLanguage,
+        stars: StarPolicy,
+    ) -> ColumnValue<'a> {
+        use Column::*;
+
+        // Control characters are replaced, so that values stay on one line wherever they're shown or exported
+        let text = |value: &'a Option<String>| match value {
+            Some(value) => ColumnValue::Text(value.sanitized()),
+            None => ColumnValue::Empty,
+        };
+        // Some databases have NaN star ratings, which are shown (and exported) as missing rather than as "NaN"
+        let star_rating = |rating: Option<f64>| match rating {
+            Some(rating) if rating.is_finite() => ColumnValue::Float(rating),
+            _ => ColumnValue::Empty,
+        };
+        let mode_stars = |mode| star_rating(beatmap.star_rating(mode, Mods::none()));
+        let grade = |mode| match beatmap.grade(mode) {
+            BeatmapGrade::Unplayed => ColumnValue::Empty,
+            grade => ColumnValue::Grade(grade),
+        };
+        let datetime = |value: OffsetDateTime| match format::known(value) {
+            Some(value) => ColumnValue::DateTime(value),
+            None => ColumnValue::Empty,
+        };
+
+        let display = |value: Option<&'a str>| match value {
+            Some(value) => ColumnValue::Text(value.sanitized()),
+            None => ColumnValue::Empty,
+        };
+        // Unsubmitted beatmaps have no ID, rather than showing the 0 or -1 stored in the file
+        let id = |value: Option<u32>| match value {
+            Some(value) => ColumnValue::Integer(value as u64),
+            None => ColumnValue::Empty,
+        };
+
+        match self {
+            Artist => display(beatmap.display_artist(language.prefers_unicode())),
+            ArtistUnicode => text(&beatmap.artist_name_unicode),
+            Title => display(beatmap.display_title(language.prefers_unicode())),
+            TitleUnicode => text(&beatmap.song_title_unicode),
+            Creator => text(&beatmap.creator_name),
+            Difficulty => text(&beatmap.difficulty),
+            Source => text(&beatmap.song_source),
+            Tags => text(&beatmap.song_tags),
+            Md5 => text(&beatmap.md5),
+            BeatmapsetId => id(beatmap.beatmapset_id().map(u32::from)),
+            DifficultyId => id(beatmap.difficulty_id().map(u32::from)),
+            Mode => ColumnValue::Text(beatmap.gameplay_mode.to_string().into()),
+            Status => ColumnValue::Text(beatmap.ranked_status.to_string().into()),
+            Stars => star_rating(beatmap.policy_star_rating(stars)),
+            StarsStd => mode_stars(GameplayMode::Standard),
+            StarsTaiko => mode_stars(GameplayMode::Taiko),
+            StarsCatch => mode_stars(GameplayMode::Catch),
+            StarsMania => mode_stars(GameplayMode::Mania),
+            Bpm => match beatmap.main_bpm() {
+                Some(bpm) if bpm.is_finite() => ColumnValue::Float(bpm),
+                _ => ColumnValue::Empty,
+            },
+            VariableBpm => {
+                let summary = beatmap.timing_summary();
+
+                match (summary.sections, summary.variable_bpm) {
+                    (0, _) => ColumnValue::Empty,
+                    (_, true) => ColumnValue::Text("Yes".into()),
+                    (_, false) => ColumnValue::Text("No".into()),
+                }
+            }
+            Length => ColumnValue::Duration(beatmap.total_time / 1000),
+            Drain => ColumnValue::Duration(beatmap.drain_time),
+            ApproachRate => ColumnValue::Float(beatmap.approach_rate as f64),
+            CircleSize => ColumnValue::Float(beatmap.circle_size as f64),
+            OverallDifficulty => ColumnValue::Float(beatmap.overall_difficulty as f64),
+            HpDrain => ColumnValue::Float(beatmap.hp_drain as f64),
+            Objects => ColumnValue::Integer(beatmap.object_count() as u64),
+            LastPlayed if beatmap.is_unplayed => ColumnValue::Empty,
+            LastPlayed => datetime(beatmap.last_played),
+            LastModified => datetime(beatmap.last_modification_time),
+            Added => match beatmap.approximate_added_date(None) {
+                Some(added) => ColumnValue::DateTime(added),
+                None => ColumnValue::Empty,
+            },
+            Grade => grade(beatmap.gameplay_mode),
+            GradeStd => grade(GameplayMode::Standard),
+            GradeTaiko => grade(GameplayMode::Taiko),
+            GradeCatch => grade(GameplayMode::Catch),
+            GradeMania => grade(GameplayMode::Mania),
+            Folder => text(&beatmap.folder_name),
+            Url => match links::beatmap_url(beatmap) {
+                Some(url) => ColumnValue::Text(url.into()),
+                None => ColumnValue::Empty,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.header())
+    }
+}
+
+impl FromStr for Column {
+    type Err = ();
+
+    /// Parses a column from its short name (see [`Column::name`]).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Column::ALL
+            .into_iter()
+            .find(|column| column.name() == s)
+            .ok_or(())
+    }
+}
+
+impl ColumnValue<'_> {
+    /// Copies any borrowed text, so that the value can outlive the beatmap it came from.
+    pub fn into_owned(self) -> ColumnValue<'static> {
+        use ColumnValue::*;
+
+        match self {
+            Text(text) => Text(Cow::Owned(text.into_owned())),
+            Integer(value) => Integer(value),
+            Float(value) => Float(value),
+            Duration(seconds) => Duration(seconds),
+            DateTime(datetime) => DateTime(datetime),
+            Grade(grade) => Grade(grade),
+            Empty => Empty,
+        }
+    }
+}
+
+impl std::fmt::Display for ColumnValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ColumnValue::*;
+
+        match self {
+            Text(text) => write!(f, "{}", text),
+            Integer(value) => write!(f, "{}", value),
+            Float(value) => match f.precision() {
+                Some(precision) => write!(f, "{:.*}", precision, value),
+                None => {
+                    // Show up to two decimal places by default, since most values are converted from `f32`
+                    let text = format!("{:.2}", value);
+                    write!(f, "{}", text.trim_end_matches('0').trim_end_matches('.'))
+                }
+            },
+            Duration(seconds) => write!(
+                f,
+                "{}",
+                format::duration_mmss(time::Duration::seconds(*seconds as i64))
+            ),
+            Grade(grade) => write!(f, "{}", grade),
+            DateTime(datetime) => write!(f, "{}", format::date_time(*datetime, UtcOffset::UTC)),
+            Empty => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{beatmaps::StarRating, common::Grade};
+    use time::macros::datetime;
+
+    #[test]
+    fn column_names_are_unique_and_round_trip() {
+        for column in Column::ALL {
+            assert_eq!(column.name().parse::<Column>(), Ok(column));
+        }
+
+        assert_eq!("not_a_column".parse::<Column>(), Err(()));
+    }
+
+    #[test]
+    fn column_values_are_correct() {
+        let beatmap = BeatmapEntry {
+            artist_name: Some("Artist".to_string()),
+            gameplay_mode: GameplayMode::Taiko,
+            star_ratings_std: Some(vec![StarRating {
+                mods: Mods::none(),
+                rating: 3.5,
+            }]),
+            star_ratings_taiko: Some(vec![
+                StarRating {
+                    mods: Mods::DoubleTime.into(),
+                    rating: 6.0,
+                },
+                StarRating {
+                    mods: Mods::none(),
+                    rating: 4.25,
+                },
+            ]),
+            total_time: 95_500,
+            last_modification_time: datetime!(2024-03-05 12:34:56 UTC),
+            grade_taiko: Grade::A,
+            beatmap_id: u32::MAX,
+            difficulty_id: 129891,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Column::Artist.value(&beatmap),
+            ColumnValue::Text("Artist".into())
+        );
+        assert_eq!(Column::Title.value(&beatmap), ColumnValue::Empty);
+        assert_eq!(Column::Stars.value(&beatmap), ColumnValue::Float(4.25));
+        assert_eq!(Column::StarsStd.value(&beatmap), ColumnValue::Float(3.5));
+        assert_eq!(Column::StarsMania.value(&beatmap), ColumnValue::Empty);
+        assert_eq!(Column::Grade.value(&beatmap), ColumnValue::Grade(Grade::A));
+        assert_eq!(Column::GradeStd.value(&beatmap), ColumnValue::Empty);
+        assert_eq!(Column::LastPlayed.value(&beatmap), ColumnValue::Empty);
+        assert_eq!(Column::Url.value(&beatmap), ColumnValue::Empty);
+        assert_eq!(Column::VariableBpm.value(&beatmap), ColumnValue::Empty);
+        assert_eq!(Column::BeatmapsetId.value(&beatmap), ColumnValue::Empty);
+        assert_eq!(
+            Column::DifficultyId.value(&beatmap),
+            ColumnValue::Integer(129891)
+        );
+
+        assert_eq!(Column::Length.value(&beatmap).to_string(), "1:35");
+        assert_eq!(
+            Column::LastModified.value(&beatmap).to_string(),
+            "2024-03-05 12:34:56"
+        );
+        assert_eq!(
+            Column::Added.value(&beatmap),
+            Column::LastModified.value(&beatmap)
+        );
+        assert_eq!(format!("{:.3}", Column::Stars.value(&beatmap)), "4.250");
+        assert_eq!(ColumnValue::Float(9.3f32 as f64).to_string(), "9.3");
+        assert_eq!(ColumnValue::Float(5.0).to_string(), "5");
+    }
+}
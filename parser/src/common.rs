@@ -1,17 +1,117 @@
+use std::borrow::Cow;
+#[cfg(all(feature = "writer", not(target_arch = "wasm32")))]
+use std::path::Path;
+
 use flagset::{flags, FlagSet};
-use nom::{
-    bytes::complete::{take, take_while},
-    combinator::{fail, map, map_opt, map_res},
-    number::complete::{le_u32, le_u64, u8},
-    IResult,
-};
-use time::{macros::datetime, Duration, OffsetDateTime};
+use nom::IResult;
+use time::{macros::datetime, OffsetDateTime};
+
+use crate::binary;
 
 pub type OsuString = Option<String>;
 
+/// Represents how [`OsuStr::sanitized_with`] handles control characters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sanitize {
+    /// Replaces each control character with `�` (U+FFFD), so that it's clear something was there
+    #[default]
+    Replace,
+
+    /// Removes control characters
+    Strip,
+}
+
+/// Text read from a database, which is kept exactly as it was stored so that it's written back unchanged, but may need
+/// cleaning up before it's shown or exported.
+///
+/// Some old or tampered databases have control characters in their text, e.g. NULs and line breaks in tags, or
+/// bidirectional overrides in titles. These break terminal output, clipboard copies and formats that expect each value
+/// to be on one line, and can make text appear in a different order than it's stored.
+pub trait OsuStr {
+    /// Checks whether the text contains control characters, including the invisible ones that change the direction of
+    /// text.
+    fn has_control_characters(&self) -> bool;
+
+    /// Gets the text with any control characters replaced with `�`, borrowing it if there aren't any.
+    fn sanitized(&self) -> Cow<'_, str> {
+        self.sanitized_with(Sanitize::Replace)
+    }
+
+    /// Gets the text with any control characters replaced or removed, borrowing it if there aren't any.
+    fn sanitized_with(&self, sanitize: Sanitize) -> Cow<'_, str>;
+}
+
+impl OsuStr for str {
+    fn has_control_characters(&self) -> bool {
+        self.chars().any(is_control_character)
+    }
+
+    fn sanitized_with(&self, sanitize: Sanitize) -> Cow<'_, str> {
+        if !self.has_control_characters() {
+            return Cow::Borrowed(self);
+        }
+
+        let sanitized = match sanitize {
+            Sanitize::Replace => self
+                .chars()
+                .map(|c| match is_control_character(c) {
+                    true => char::REPLACEMENT_CHARACTER,
+                    false => c,
+                })
+                .collect(),
+            Sanitize::Strip => self.chars().filter(|c| !is_control_character(*c)).collect(),
+        };
+
+        Cow::Owned(sanitized)
+    }
+}
+
+/// Checks whether a character is a control character (e.g. NUL, a tab or a line break), or one of the formatting
+/// characters that override the direction of the text around them.
+fn is_control_character(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+        )
+}
+
+/// The epoch used by .NET's `DateTime.Ticks`, which is also used as the "unset" value for dates in osu!'s databases.
+pub const WINDOWS_EPOCH: OffsetDateTime = datetime!(0001-01-01 0:00 UTC);
+
+/// Represents which clock osu! read a stored date from. The databases don't record this, so it depends on the field.
+///
+/// Dates are always stored as .NET ticks, but osu! stable takes some of them from UTC and others from the computer's
+/// local time, so the same ticks can refer to moments hours apart. Showing every date as UTC shifted by the user's
+/// offset is then wrong for the local ones. From databases written by osu! stable, the fields are:
+///
+/// | File | Field | Kind |
+/// |---|---|---|
+/// | `osu!.db` | `account_unlock_date` | UTC |
+/// | `osu!.db` | `last_modification_time` | UTC |
+/// | `osu!.db` | `last_played` | Local |
+/// | `osu!.db` | `last_checked_online` | Local |
+/// | `scores.db` and `.osr` | `timestamp` | UTC |
+///
+/// Each field's kind is also given by a constant next to it, e.g. `BeatmapEntry::LAST_PLAYED_KIND`. Dates are kept
+/// exactly as they're stored, so that they're written back with the same ticks, and only converted when they're shown
+/// (see [`TimeZone::zoned`](crate::format::TimeZone::zoned)).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimestampKind {
+    /// The ticks are UTC, so they refer to the same moment wherever they're shown
+    Utc,
+
+    /// The ticks are the wall clock time on the computer that osu! ran on, whose time zone isn't known. They're
+    /// assumed to be in the time zone that they're shown in.
+    Local,
+}
+
 /// Represents the different gameplay modes for a beatmap.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameplayMode {
+    #[default]
     Standard = 0,
     Taiko = 1,
     Catch = 2,
@@ -19,7 +119,10 @@ pub enum GameplayMode {
 }
 
 /// Represents a grade achieved on a beatmap.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///
+/// Grades are ordered from best to worst (i.e. `SilverSS` first), with `Unplayed` last.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Grade {
     SilverSS = 0,
     SilverS = 1,
@@ -29,6 +132,7 @@ pub enum Grade {
     B = 5,
     C = 6,
     D = 7,
+    #[default]
     Unplayed = 9,
 }
 
@@ -106,91 +210,167 @@ impl Mods {
     pub fn none() -> FlagSet<Mods> {
         FlagSet::<Mods>::new_truncated(0)
     }
+
+    /// Gets the two-letter acronym shown in-game for this modifier (e.g. `HD` for Hidden).
+    ///
+    /// Returns an empty string for combined flags which don't have their own acronym (i.e. [`Mods::KeyMod`]).
+    pub fn acronym(&self) -> &'static str {
+        use Mods::*;
+
+        match self {
+            NoFail => "NF",
+            Easy => "EZ",
+            TouchDevice => "TD",
+            Hidden => "HD",
+            HardRock => "HR",
+            SuddenDeath => "SD",
+            DoubleTime => "DT",
+            Relax => "RX",
+            HalfTime => "HT",
+            Nightcore => "NC",
+            Flashlight => "FL",
+            Autoplay => "AT",
+            SpunOut => "SO",
+            Autopilot => "AP",
+            Perfect => "PF",
+            Key4 => "4K",
+            Key5 => "5K",
+            Key6 => "6K",
+            Key7 => "7K",
+            Key8 => "8K",
+            KeyMod => "",
+            FadeIn => "FI",
+            Random => "RD",
+            Cinema => "CN",
+            TargetPractice => "TP",
+            Key9 => "9K",
+            Coop => "CO",
+            Key1 => "1K",
+            Key3 => "3K",
+            Key2 => "2K",
+            ScoreV2 => "V2",
+            Mirror => "MR",
+        }
+    }
+
+    /// Renders a set of gameplay modifiers as a string of acronyms, as shown in-game (e.g. `HDDT`).
+    ///
+    /// Implied modifiers are omitted (e.g. `NC` rather than `DTNC`), and an empty set is rendered as `NM`.
+    pub fn acronyms(mods: FlagSet<Mods>) -> String {
+        if mods.is_empty() {
+            return "NM".to_string();
+        }
+
+        mods.into_iter()
+            .filter(|m| !(*m == Mods::DoubleTime && mods.contains(Mods::Nightcore)))
+            .filter(|m| !(*m == Mods::SuddenDeath && mods.contains(Mods::Perfect)))
+            .map(|m| m.acronym())
+            .collect()
+    }
+}
+
+/// Gets a float, or `None` if it's NaN or infinite. Exporters write these values as missing, e.g. as `null` in JSON or
+/// `NULL` in SQLite, since most formats can't represent them.
+pub fn finite<T: Copy + Into<f64>>(value: T) -> Option<T> {
+    Some(value).filter(|value| (*value).into().is_finite())
+}
+
+/// Serializes floats with NaN and infinities as `null` (see [`finite`]), which formats like JSON can't represent
+/// otherwise. `null` is read back as NaN.
+///
+/// Use with `#[serde(with = "crate::common::non_finite_as_null")]`.
+#[cfg(feature = "serde")]
+pub(crate) mod non_finite_as_null {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Floats which can be written as `null`.
+    pub trait NonFinite: Copy + Into<f64> + Serialize + for<'de> Deserialize<'de> {
+        const NAN: Self;
+    }
+
+    impl NonFinite for f32 {
+        const NAN: Self = f32::NAN;
+    }
+
+    impl NonFinite for f64 {
+        const NAN: Self = f64::NAN;
+    }
+
+    pub fn serialize<T: NonFinite, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        super::finite(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: NonFinite, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        Ok(Option::<T>::deserialize(deserializer)?.unwrap_or(T::NAN))
+    }
 }
 
 /// Parses a boolean value in osu!'s database file formats.
+#[deprecated(note = "use `binary::parse_boolean` instead")]
 pub fn boolean(input: &[u8]) -> IResult<&[u8], bool> {
-    map(u8, |byte| byte != 0)(input)
+    binary::parse_boolean(input)
 }
 
 /// Parses a gameplay mode value.
+#[deprecated(note = "use `binary::parse_gameplay_mode` instead")]
 pub fn gameplay_mode(input: &[u8]) -> IResult<&[u8], GameplayMode> {
-    use GameplayMode::*;
-
-    let (i, status) = u8(input)?;
-    let status = match status {
-        0 => Standard,
-        1 => Taiko,
-        2 => Catch,
-        3 => Mania,
-        _ => {
-            return Err(nom::Err::Error(nom::error::Error {
-                input,
-                code: nom::error::ErrorKind::Switch,
-            }))
-        }
-    };
-
-    Ok((i, status))
+    binary::parse_gameplay_mode(input)
 }
 
 /// Parses a set of gameplay modifiers.
+#[deprecated(note = "use `binary::parse_mods` instead")]
 pub fn modifiers(input: &[u8]) -> IResult<&[u8], FlagSet<Mods>> {
-    map(le_u32, FlagSet::<Mods>::new_truncated)(input)
+    binary::parse_mods(input)
 }
 
 /// Decodes a ULEB128 value into an unsigned pointer-sized integer.
+#[deprecated(note = "use `binary::parse_uleb128` instead")]
 pub fn uleb128(input: &[u8]) -> IResult<&[u8], usize> {
-    let (i, uleb_start) = take_while(|byte| byte & 0x80 > 1)(input)?;
-    let (i, uleb_final) = u8(i)?;
-
-    let mut result = 0;
-    let mut shift = 0;
-
-    for byte in uleb_start {
-        result |= ((*byte & 0x7F) as usize) << shift;
-        shift += 7;
-    }
-
-    result |= ((uleb_final & 0x7F) as usize) << shift;
-    Ok((i, result))
+    binary::parse_uleb128(input)
 }
 
 /// Decodes a string found in osu!'s database file formats.
-///
-/// - If the first byte is 0x00, then no string value is present.
-/// - If the first byte is 0x0b, then this is followed by a ULEB128 value indicating the length, then the UTF-8 string bytes.
-///
-///  **NOTE**: There are two possible values for empty strings; these can be distinguished as follows:
-///
-/// - `0x00` => Empty string marker; output is `None`
-/// - `0x0b, 0x00` => Zero length string; output is `Some("")`
+#[deprecated(note = "use `binary::parse_osu_string` instead")]
 pub fn osu_string(input: &[u8]) -> IResult<&[u8], OsuString> {
-    let (i, head) = u8(input)?;
-
-    match head {
-        0x00 => Ok((i, None)),
-        0x0b => {
-            let (i, length) = uleb128(i)?;
-            map(map_res(take(length), std::str::from_utf8), |s| {
-                Some(s.to_string())
-            })(i)
-        }
-        _ => fail(input),
-    }
+    binary::parse_osu_string(input)
+}
+
+/// Skips over a string found in osu!'s database file formats without checking or copying it.
+#[deprecated(note = "use `binary::skip_osu_string` instead")]
+pub fn skip_osu_string(input: &[u8]) -> IResult<&[u8], ()> {
+    binary::skip_osu_string(input)
 }
 
 /// Parses a DateTime from .NET's [`DateTime.Ticks`](https://learn.microsoft.com/en-us/dotnet/api/system.datetime.ticks?view=netframework-4.7.2).
+#[deprecated(note = "use `binary::parse_windows_datetime` instead")]
 pub fn windows_datetime(input: &[u8]) -> IResult<&[u8], OffsetDateTime> {
-    const WINDOWS_EPOCH: OffsetDateTime = datetime!(0001-01-01 0:00 UTC);
-
-    map_opt(le_u64, |ticks| {
-        // In .NET, there are 10,000 ticks per millisecond
-        // So 10 ticks / microsecond, 0.01 ticks per nanosecond
-        let duration = Duration::microseconds((ticks / 10) as i64)
-            + Duration::nanoseconds(((ticks % 10) * 100) as i64);
-        WINDOWS_EPOCH.checked_add(duration)
-    })(input)
+    binary::parse_windows_datetime(input)
+}
+
+/// Writes a file by writing to a temporary file next to `path`, which then replaces the original file. This means
+/// that the original file is left untouched if anything goes wrong while writing.
+#[cfg(all(feature = "writer", not(target_arch = "wasm32")))]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(path = %path.display(), bytes = data.len()))
+)]
+pub(crate) fn write_file_replacing(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = std::fs::write(&temp_path, data).and_then(|_| std::fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        std::fs::remove_file(&temp_path).ok();
+    }
+
+    result
 }
 
 #[cfg(test)]
@@ -198,99 +378,47 @@ mod tests {
     use super::*;
 
     #[test]
-    fn boolean_decoding_works() {
-        // Any non-zero byte should result in true
-        assert_eq!(boolean(&[0x00]), Ok(((&[][..]), false)));
-        assert_eq!(boolean(&[0x01]), Ok(((&[][..]), true)));
-
-        assert_eq!(
-            boolean(&[0xFF, 0x01, 0x02]),
-            Ok(((&[0x01, 0x02][..]), true))
-        );
-    }
-
-    #[test]
-    fn gameplay_mode_decoding_works() {
-        use GameplayMode::*;
+    fn control_characters_are_sanitized() {
+        assert!(matches!("Camellia".sanitized(), Cow::Borrowed("Camellia")));
+        assert!(matches!("かめりあ".sanitized(), Cow::Borrowed("かめりあ")));
 
-        assert_eq!(gameplay_mode(&[0]), Ok((&[][..], Standard)));
-        assert_eq!(gameplay_mode(&[1]), Ok((&[][..], Taiko)));
-        assert_eq!(gameplay_mode(&[2]), Ok((&[][..], Catch)));
-        assert_eq!(gameplay_mode(&[3]), Ok((&[][..], Mania)));
+        let tampered = "A\0B\nC\u{202E}D\u{2066}";
+        assert!(tampered.has_control_characters());
+        assert_eq!(tampered.sanitized(), "A\u{FFFD}B\u{FFFD}C\u{FFFD}D\u{FFFD}");
+        assert_eq!(tampered.sanitized_with(Sanitize::Strip), "ABCD");
 
-        assert_eq!(
-            gameplay_mode(&[10]),
-            Err(nom::Err::Error(nom::error::Error {
-                input: &[10][..],
-                code: nom::error::ErrorKind::Switch
-            }))
-        );
+        // Joiners are used in emoji and some scripts, so they're kept
+        assert!(!"👩\u{200D}💻".has_control_characters());
     }
 
     #[test]
-    fn uleb128_decoding_works() {
-        // 0xE5, 0x8E, 0x26 ==> 624485
-        // ULEB128 value by itself
-        assert_eq!(uleb128(&[0xE5, 0x8E, 0x26]), Ok((&[][..], 624485)));
-
-        // ULEB128 value followed by other bytes
-        assert_eq!(
-            uleb128(&[0xE5, 0x8E, 0x26, 0x80, 0x81, 0x82]),
-            Ok((&[0x80, 0x81, 0x82][..], 624485))
-        );
-
-        // Empty value
-        assert_eq!(
-            uleb128(&[]),
-            Err(nom::Err::Error(nom::error::Error {
-                input: &[][..],
-                code: nom::error::ErrorKind::Eof
-            }))
-        );
+    fn mod_acronyms_are_correct() {
+        assert_eq!(Mods::acronyms(Mods::none()), "NM");
+        assert_eq!(Mods::acronyms(Mods::Hidden | Mods::DoubleTime), "HDDT");
+        assert_eq!(Mods::acronyms(Mods::HardRock | Mods::Hidden), "HDHR");
+        assert_eq!(Mods::acronyms(Mods::Nightcore | Mods::Flashlight), "NCFL");
+        assert_eq!(Mods::acronyms(Mods::Perfect | Mods::SuddenDeath), "PF");
+        assert_eq!(Mods::acronyms(Mods::Key4 | Mods::ScoreV2), "4KV2");
     }
 
     #[test]
-    fn osu_string_decoding_works() {
-        let empty = vec![0x00];
-        let zero_length = vec![0x0b, 0x00];
-        let test_string = String::from("test");
-        let mut test_string_bytes = vec![0x0b, 0x04];
-
-        for byte in test_string.as_bytes() {
-            test_string_bytes.push(*byte);
-        }
-
-        // Append a few other bytes that aren't part of the string
-        test_string_bytes.push(0x01);
-        test_string_bytes.push(0x02);
-        test_string_bytes.push(0x03);
-
-        assert_eq!(osu_string(&empty), Ok((&[][..], None)));
+    #[allow(deprecated)]
+    fn deprecated_parsers_still_parse() {
+        assert_eq!(boolean(&[0x01]), Ok((&[][..], true)));
+        assert_eq!(gameplay_mode(&[0x03]), Ok((&[][..], GameplayMode::Mania)));
         assert_eq!(
-            osu_string(&zero_length),
-            Ok((&[][..], Some("".to_string())))
+            modifiers(&[0x08, 0x00, 0x00, 0x00]),
+            Ok((&[][..], FlagSet::from(Mods::Hidden)))
         );
+        assert_eq!(uleb128(&[0xe5, 0x8e, 0x26]), Ok((&[][..], 624485)));
         assert_eq!(
-            osu_string(&test_string_bytes),
-            Ok((&[0x01, 0x02, 0x03][..], Some(test_string)))
+            osu_string(&[0x0b, 0x01, b'a']),
+            Ok((&[][..], Some("a".to_string())))
         );
-    }
-
-    #[test]
-    fn windows_datetime_decoding_works() {
-        // 07/28/2023 15:30:20 +00:00 ==> 638261550200000000 ticks
-        let datetime = datetime!(2023-07-28 15:30:20 UTC);
-        let ticks = 638261550200000000u64;
-
-        // Should only parse the first 8 bytes
-        let mut input = ticks.to_le_bytes().to_vec();
-        input.push(0x01);
-        input.push(0x02);
-        input.push(0x03);
-
+        assert_eq!(skip_osu_string(&[0x0b, 0x01, b'a']), Ok((&[][..], ())));
         assert_eq!(
-            windows_datetime(&input),
-            Ok((&[0x01, 0x02, 0x03][..], datetime))
+            windows_datetime(&0u64.to_le_bytes()),
+            Ok((&[][..], WINDOWS_EPOCH))
         );
     }
 }
@@ -0,0 +1,267 @@
+//! Detection of the kind of an osu! file, e.g. when it has been renamed or dragged in from elsewhere.
+
+use std::fmt;
+
+use nom::{
+    combinator::verify,
+    number::complete::{le_u32, le_u64, u8},
+    sequence::tuple,
+    IResult,
+};
+
+use crate::{
+    binary::{parse_gameplay_mode, parse_osu_string},
+    common::OsuString,
+};
+
+/// Represents the kinds of files that can be parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    /// `osu!.db`
+    Beatmaps,
+
+    /// `collection.db`
+    Collections,
+
+    /// `scores.db`
+    Scores,
+
+    /// `.osr` replay
+    Replay,
+}
+
+impl FileKind {
+    /// Guesses the kind of file from its name, using the names that osu! gives them.
+    pub fn from_file_name(name: &str) -> Option<FileKind> {
+        let name = name.to_lowercase();
+
+        match name.as_str() {
+            "osu!.db" | "osu.db" => Some(FileKind::Beatmaps),
+            "collection.db" => Some(FileKind::Collections),
+            "scores.db" => Some(FileKind::Scores),
+            _ if name.ends_with(".osr") => Some(FileKind::Replay),
+            _ => None,
+        }
+    }
+
+    /// Guesses the kind of file by checking whether the start of its contents looks like each format.
+    ///
+    /// A `scores.db` without any scores can't be told apart from an empty `collection.db`, so it is detected as the
+    /// latter.
+    pub fn from_contents(data: &[u8]) -> Option<FileKind> {
+        if replay_header(data).is_ok() {
+            Some(FileKind::Replay)
+        } else if beatmaps_header(data).is_ok() {
+            Some(FileKind::Beatmaps)
+        } else if scores_header(data).is_ok() {
+            Some(FileKind::Scores)
+        } else if collections_header(data).is_ok() {
+            Some(FileKind::Collections)
+        } else {
+            None
+        }
+    }
+
+    /// Checks whether the start of some data looks like this kind of file, e.g. to tell whether a file was read while
+    /// it was being written.
+    pub(crate) fn has_header(self, data: &[u8]) -> bool {
+        match self {
+            FileKind::Beatmaps => beatmaps_header(data).is_ok(),
+            FileKind::Collections => collections_header(data).is_ok(),
+            FileKind::Scores => scores_header(data).is_ok() || empty_scores_header(data).is_ok(),
+            FileKind::Replay => replay_header(data).is_ok(),
+        }
+    }
+
+    /// Guesses the kind of file from its name if possible, and otherwise from its contents.
+    pub fn detect(name: Option<&str>, data: &[u8]) -> Option<FileKind> {
+        name.and_then(FileKind::from_file_name)
+            .or_else(|| FileKind::from_contents(data))
+    }
+}
+
+impl fmt::Display for FileKind {
+    /// Formats the kind of file using the name that osu! gives it, e.g. in error messages.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileKind::Beatmaps => write!(f, "osu!.db"),
+            FileKind::Collections => write!(f, "collection.db"),
+            FileKind::Scores => write!(f, "scores.db"),
+            FileKind::Replay => write!(f, "the replay"),
+        }
+    }
+}
+
+/// Parses a version number, which is the date of the osu! release that wrote the file (e.g. 20150203).
+fn version(input: &[u8]) -> IResult<&[u8], u32> {
+    verify(le_u32, |version: &u32| {
+        (20070000..=29991231).contains(version)
+    })(input)
+}
+
+/// Parses an MD5 hash, which is always stored as a 32 character string.
+fn md5(input: &[u8]) -> IResult<&[u8], ()> {
+    let (i, _) = verify(parse_osu_string, |md5: &OsuString| {
+        md5.as_ref()
+            .is_some_and(|md5| md5.len() == 32 && md5.bytes().all(|b| b.is_ascii_hexdigit()))
+    })(input)?;
+
+    Ok((i, ()))
+}
+
+/// Parses the start of a `.osr` replay: gameplay mode, version and beatmap MD5.
+fn replay_header(input: &[u8]) -> IResult<&[u8], ()> {
+    let (i, _) = tuple((parse_gameplay_mode, version, md5))(input)?;
+    Ok((i, ()))
+}
+
+/// Parses the start of an `osu!.db` file: version, folder count, account unlocked, unlock date, player name.
+fn beatmaps_header(input: &[u8]) -> IResult<&[u8], ()> {
+    let (i, _) = tuple((
+        version,
+        le_u32,
+        verify(u8, |b: &u8| *b <= 1),
+        le_u64,
+        parse_osu_string,
+    ))(input)?;
+    Ok((i, ()))
+}
+
+/// Parses the start of a non-empty `scores.db` file, up to the start of the first score.
+fn scores_header(input: &[u8]) -> IResult<&[u8], ()> {
+    let (i, _) = tuple((
+        version,
+        verify(le_u32, |count: &u32| *count > 0),
+        md5,
+        verify(le_u32, |count: &u32| *count > 0),
+        parse_gameplay_mode,
+        version,
+    ))(input)?;
+
+    Ok((i, ()))
+}
+
+/// Parses a `scores.db` file without any scores.
+fn empty_scores_header(input: &[u8]) -> IResult<&[u8], ()> {
+    let (i, _) = tuple((version, verify(le_u32, |count: &u32| *count == 0)))(input)?;
+    Ok((i, ()))
+}
+
+/// Parses the start of a `collection.db` file, up to the first beatmap of the first collection.
+fn collections_header(input: &[u8]) -> IResult<&[u8], ()> {
+    let (i, (_, count)) = tuple((version, le_u32))(input)?;
+
+    if count == 0 {
+        return Ok((i, ()));
+    }
+
+    let (i, (_, beatmaps)) = tuple((parse_osu_string, le_u32))(i)?;
+
+    if beatmaps == 0 {
+        return Ok((i, ()));
+    }
+
+    md5(i)
+}
+
+#[cfg(all(test, feature = "collections", feature = "writer"))]
+mod tests {
+    use super::*;
+    use crate::{
+        binary::write_osu_string,
+        collections::{Collection, CollectionListing},
+    };
+
+    const MD5: &str = "0123456789abcdef0123456789abcdef";
+
+    fn collections() -> Vec<u8> {
+        CollectionListing {
+            version: 20150203,
+            collections: vec![Collection {
+                name: Some("Favourites".to_string()),
+                beatmap_md5s: vec![Some(MD5.to_string())],
+            }],
+        }
+        .to_bytes()
+    }
+
+    fn scores() -> Vec<u8> {
+        let mut data = 20150203u32.to_le_bytes().to_vec();
+        data.extend(1u32.to_le_bytes());
+        write_osu_string(&mut data, &Some(MD5.to_string()));
+        data.extend(1u32.to_le_bytes());
+        data.push(0); // Gameplay mode
+        data.extend(20150203u32.to_le_bytes());
+        data
+    }
+
+    fn replay() -> Vec<u8> {
+        let mut data = vec![3]; // Gameplay mode
+        data.extend(20150203u32.to_le_bytes());
+        write_osu_string(&mut data, &Some(MD5.to_string()));
+        data
+    }
+
+    fn beatmaps() -> Vec<u8> {
+        let mut data = 20191106u32.to_le_bytes().to_vec();
+        data.extend([0; 4 + 1 + 8]); // Folder count, account unlocked, unlock date
+        write_osu_string(&mut data, &Some("Player".to_string()));
+        data.extend(0u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn file_names_are_detected() {
+        assert_eq!(
+            FileKind::from_file_name("osu!.db"),
+            Some(FileKind::Beatmaps)
+        );
+        assert_eq!(
+            FileKind::from_file_name("Collection.db"),
+            Some(FileKind::Collections)
+        );
+        assert_eq!(
+            FileKind::from_file_name("scores.db"),
+            Some(FileKind::Scores)
+        );
+        assert_eq!(
+            FileKind::from_file_name("Player - Song [Hard] (2023-07-28) Osu.osr"),
+            Some(FileKind::Replay)
+        );
+        assert_eq!(FileKind::from_file_name("presence.db"), None);
+    }
+
+    #[test]
+    fn contents_are_detected() {
+        assert_eq!(
+            FileKind::from_contents(&beatmaps()),
+            Some(FileKind::Beatmaps)
+        );
+        assert_eq!(
+            FileKind::from_contents(&collections()),
+            Some(FileKind::Collections)
+        );
+        assert_eq!(FileKind::from_contents(&scores()), Some(FileKind::Scores));
+        assert_eq!(FileKind::from_contents(&replay()), Some(FileKind::Replay));
+
+        assert_eq!(FileKind::from_contents(b""), None);
+        assert_eq!(FileKind::from_contents(b"not an osu! file"), None);
+    }
+
+    #[test]
+    fn file_names_take_priority() {
+        // Renamed files fall back to their contents
+        assert_eq!(
+            FileKind::detect(Some("backup.db"), &scores()),
+            Some(FileKind::Scores)
+        );
+        assert_eq!(
+            FileKind::detect(Some("scores.db"), &collections()),
+            Some(FileKind::Scores)
+        );
+        assert_eq!(
+            FileKind::detect(None, &collections()),
+            Some(FileKind::Collections)
+        );
+    }
+}
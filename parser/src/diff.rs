@@ -0,0 +1,262 @@
+//! Comparison of two beatmap listings, e.g. to find out what changed after osu! rewrote `osu!.db`.
+
+use crate::beatmaps::{BeatmapEntry, BeatmapListing};
+
+/// Represents the differences between two beatmap listings.
+///
+/// Beatmaps are matched up using their MD5 hashes; beatmaps without an MD5 hash are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct ListingDiff<'a> {
+    /// Beatmaps only present in the new listing, in the order they appear in the new listing
+    pub added: Vec<&'a BeatmapEntry>,
+
+    /// Beatmaps only present in the old listing, in the order they appear in the old listing
+    pub removed: Vec<&'a BeatmapEntry>,
+
+    /// Beatmaps present in both listings with different details, in the order they appear in the new listing
+    pub changed: Vec<EntryChanges<'a>>,
+}
+
+/// Represents the changes made to a beatmap entry.
+#[derive(Clone, Debug)]
+pub struct EntryChanges<'a> {
+    /// The entry in the old listing
+    pub old: &'a BeatmapEntry,
+
+    /// The entry in the new listing
+    pub new: &'a BeatmapEntry,
+
+    /// The fields that changed between the two entries
+    pub fields: Vec<FieldChange>,
+}
+
+/// Represents a change to a single field of a beatmap entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    /// Name of the field
+    pub name: &'static str,
+
+    /// Previous value of the field
+    pub old: String,
+
+    /// New value of the field
+    pub new: String,
+}
+
+/// Compares the listed fields of two beatmap entries, returning the ones that have changed.
+///
+/// Values are compared using their debug representation as well, so that `NaN` values aren't reported as changes.
+macro_rules! changed_fields {
+    ($old:expr, $new:expr, [$($field:ident),* $(,)?]) => {{
+        let mut changes = Vec::new();
+
+        $(
+            if $old.$field != $new.$field {
+                let old = format!("{:?}", $old.$field);
+                let new = format!("{:?}", $new.$field);
+
+                if old != new {
+                    changes.push(FieldChange { name: stringify!($field), old, new });
+                }
+            }
+        )*
+
+        changes
+    }};
+}
+
+impl<'a> ListingDiff<'a> {
+    /// Compares two beatmap listings.
+    pub fn new(old: &'a BeatmapListing, new: &'a BeatmapListing) -> ListingDiff<'a> {
+        let old_beatmaps = old.beatmaps_by_md5();
+        let new_beatmaps = new.beatmaps_by_md5();
+
+        let mut diff = ListingDiff::default();
+
+        for beatmap in &new.beatmaps {
+            let Some(md5) = beatmap.md5.as_deref() else {
+                continue;
+            };
+
+            match old_beatmaps.get(md5).copied() {
+                Some(old_beatmap) => {
+                    let fields = entry_changes(old_beatmap, beatmap);
+
+                    if !fields.is_empty() {
+                        diff.changed.push(EntryChanges {
+                            old: old_beatmap,
+                            new: beatmap,
+                            fields,
+                        });
+                    }
+                }
+                None => diff.added.push(beatmap),
+            }
+        }
+
+        diff.removed = old
+            .beatmaps
+            .iter()
+            .filter(|b| {
+                b.md5
+                    .as_deref()
+                    .is_some_and(|md5| !new_beatmaps.contains_key(md5))
+            })
+            .collect();
+
+        diff
+    }
+
+    /// Checks whether there are no differences between the two listings.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Finds the fields that differ between two versions of a beatmap entry.
+fn entry_changes(old: &BeatmapEntry, new: &BeatmapEntry) -> Vec<FieldChange> {
+    // NOTE: The entry size is skipped, since it changes whenever any of the string fields change
+    changed_fields!(
+        old,
+        new,
+        [
+            artist_name,
+            artist_name_unicode,
+            song_title,
+            song_title_unicode,
+            creator_name,
+            difficulty,
+            audio_filename,
+            beatmap_filename,
+            ranked_status,
+            hitcircle_count,
+            slider_count,
+            spinner_count,
+            last_modification_time,
+            approach_rate,
+            circle_size,
+            hp_drain,
+            overall_difficulty,
+            slider_velocity,
+            star_ratings_std,
+            star_ratings_taiko,
+            star_ratings_ctb,
+            star_ratings_mania,
+            drain_time,
+            total_time,
+            audio_preview_time,
+            timing_points,
+            difficulty_id,
+            beatmap_id,
+            thread_id,
+            grade_std,
+            grade_taiko,
+            grade_catch,
+            grade_mania,
+            local_offset,
+            stack_leniency,
+            gameplay_mode,
+            song_source,
+            song_tags,
+            online_offset,
+            font,
+            is_unplayed,
+            last_played,
+            is_osz2,
+            folder_name,
+            last_checked_online,
+            ignore_beatmap_hitsounds,
+            ignore_beatmap_skin,
+            disable_storyboard,
+            disable_video,
+            visual_override,
+            unknown_u16,
+            unknown_u32,
+            mania_scroll_speed,
+        ]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{beatmaps::UserPermissions, common::WINDOWS_EPOCH};
+
+    fn listing(beatmaps: Vec<BeatmapEntry>) -> BeatmapListing {
+        BeatmapListing {
+            version: 20250108,
+            folder_count: beatmaps.len() as u32,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps,
+            user_permissions: UserPermissions::Normal.into(),
+        }
+    }
+
+    fn beatmap(md5: &str) -> BeatmapEntry {
+        BeatmapEntry {
+            md5: Some(md5.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn identical_listings_have_no_differences() {
+        let old = listing(vec![beatmap("a"), beatmap("b")]);
+        let new = old.clone();
+
+        assert!(ListingDiff::new(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn added_removed_and_changed_beatmaps_are_found() {
+        let old = listing(vec![beatmap("a"), beatmap("b"), beatmap("c")]);
+
+        let mut changed = beatmap("b");
+        changed.is_unplayed = false;
+        changed.stack_leniency = f32::NAN;
+
+        let new = listing(vec![
+            beatmap("d"),
+            changed,
+            beatmap("c"),
+            BeatmapEntry::default(),
+        ]);
+        let diff = ListingDiff::new(&old, &new);
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].md5.as_deref(), Some("d"));
+
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].md5.as_deref(), Some("a"));
+
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            diff.changed[0].fields,
+            vec![
+                FieldChange {
+                    name: "stack_leniency",
+                    old: "0.0".to_string(),
+                    new: "NaN".to_string(),
+                },
+                FieldChange {
+                    name: "is_unplayed",
+                    old: "true".to_string(),
+                    new: "false".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn nan_values_are_not_reported_as_changes() {
+        let mut old = beatmap("a");
+        old.slider_velocity = f64::NAN;
+
+        let old = listing(vec![old]);
+        let new = old.clone();
+
+        assert!(ListingDiff::new(&old, &new).is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+//! Difficulty settings adjusted for gameplay modifiers, as shown in osu!'s song select.
+
+use flagset::FlagSet;
+
+use crate::{beatmaps::BeatmapEntry, common::Mods};
+
+/// Modifiers which change a beatmap's difficulty settings or star rating. Star ratings in `osu.db` are only
+/// calculated for combinations of these.
+const DIFFICULTY_MODS: [Mods; 4] = [Mods::Easy, Mods::HardRock, Mods::DoubleTime, Mods::HalfTime];
+
+/// Represents a beatmap's difficulty settings, on the usual 0-10 scale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DifficultySettings {
+    pub approach_rate: f32,
+    pub circle_size: f32,
+    pub overall_difficulty: f32,
+    pub hp_drain: f32,
+}
+
+impl DifficultySettings {
+    /// Gets the difficulty settings of a beatmap, without any modifiers.
+    pub fn of(beatmap: &BeatmapEntry) -> DifficultySettings {
+        DifficultySettings {
+            approach_rate: beatmap.approach_rate,
+            circle_size: beatmap.circle_size,
+            overall_difficulty: beatmap.overall_difficulty,
+            hp_drain: beatmap.hp_drain,
+        }
+    }
+
+    /// Gets the settings with modifiers applied, using osu!standard's rules.
+    ///
+    /// Hard Rock and Easy scale the settings, then rate-changing modifiers adjust the approach rate and overall
+    /// difficulty to match their timing at the new speed. This means that the adjusted values can go above 10 (or below
+    /// 0), as they do in-game. Other modifiers have no effect.
+    pub fn with_mods(&self, mods: FlagSet<Mods>) -> DifficultySettings {
+        let mut settings = *self;
+
+        if mods.contains(Mods::HardRock) {
+            settings.approach_rate = (settings.approach_rate * 1.4).min(10.0);
+            settings.circle_size = (settings.circle_size * 1.3).min(10.0);
+            settings.overall_difficulty = (settings.overall_difficulty * 1.4).min(10.0);
+            settings.hp_drain = (settings.hp_drain * 1.4).min(10.0);
+        } else if mods.contains(Mods::Easy) {
+            settings.approach_rate *= 0.5;
+            settings.circle_size *= 0.5;
+            settings.overall_difficulty *= 0.5;
+            settings.hp_drain *= 0.5;
+        }
+
+        let rate = clock_rate(mods);
+
+        if rate != 1.0 {
+            settings.approach_rate = approach_rate(approach_time(settings.approach_rate) / rate);
+            settings.overall_difficulty =
+                overall_difficulty(hit_window(settings.overall_difficulty) / rate);
+        }
+
+        settings
+    }
+}
+
+/// Gets how much faster the song plays with a set of modifiers, e.g. 1.5 for Double Time.
+pub fn clock_rate(mods: FlagSet<Mods>) -> f64 {
+    if mods.contains(Mods::DoubleTime) {
+        1.5
+    } else if mods.contains(Mods::HalfTime) {
+        0.75
+    } else {
+        1.0
+    }
+}
+
+/// Gets the modifiers which affect the star rating, i.e. the combination that star ratings are stored for in `osu.db`.
+///
+/// Nightcore is treated as Double Time, since it's stored as both.
+pub fn star_rating_mods(mods: FlagSet<Mods>) -> FlagSet<Mods> {
+    DIFFICULTY_MODS
+        .into_iter()
+        .filter(|&m| mods.contains(m))
+        .fold(Mods::none(), |combined, m| combined | m)
+}
+
+/// Checks whether two modifiers can't be used together, e.g. Hard Rock and Easy.
+pub fn conflicts(a: Mods, b: Mods) -> bool {
+    use Mods::*;
+
+    matches!(
+        (a, b),
+        (HardRock, Easy)
+            | (Easy, HardRock)
+            | (DoubleTime, HalfTime)
+            | (HalfTime, DoubleTime)
+            | (Nightcore, HalfTime)
+            | (HalfTime, Nightcore)
+    )
+}
+
+/// Gets the time in milliseconds that hit objects appear before they need to be hit, for an approach rate.
+fn approach_time(approach_rate: f32) -> f64 {
+    let approach_rate = approach_rate as f64;
+
+    if approach_rate < 5.0 {
+        1800.0 - 120.0 * approach_rate
+    } else {
+        1200.0 - 150.0 * (approach_rate - 5.0)
+    }
+}
+
+/// Gets the approach rate for an approach time. This is the inverse of [`approach_time`].
+fn approach_rate(approach_time: f64) -> f32 {
+    let approach_rate = if approach_time > 1200.0 {
+        (1800.0 - approach_time) / 120.0
+    } else {
+        5.0 + (1200.0 - approach_time) / 150.0
+    };
+
+    approach_rate as f32
+}
+
+/// Gets the hit window in milliseconds for a 300, for an overall difficulty.
+fn hit_window(overall_difficulty: f32) -> f64 {
+    80.0 - 6.0 * overall_difficulty as f64
+}
+
+/// Gets the overall difficulty for a hit window. This is the inverse of [`hit_window`].
+fn overall_difficulty(hit_window: f64) -> f32 {
+    ((80.0 - hit_window) / 6.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(value: f32) -> DifficultySettings {
+        DifficultySettings {
+            approach_rate: value,
+            circle_size: value,
+            overall_difficulty: value,
+            hp_drain: value,
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn hard_rock_is_capped() {
+        let adjusted = settings(8.0).with_mods(Mods::HardRock.into());
+
+        assert_close(adjusted.approach_rate, 10.0);
+        assert_close(adjusted.circle_size, 10.0);
+        assert_close(adjusted.hp_drain, 10.0);
+
+        let adjusted = settings(5.0).with_mods(Mods::HardRock.into());
+        assert_close(adjusted.approach_rate, 7.0);
+        assert_close(adjusted.circle_size, 6.5);
+    }
+
+    #[test]
+    fn easy_halves_settings() {
+        let adjusted = settings(8.0).with_mods(Mods::Easy.into());
+        assert_eq!(adjusted, settings(4.0));
+    }
+
+    #[test]
+    fn double_time_adjusts_timing() {
+        let adjusted = settings(9.0).with_mods(Mods::DoubleTime.into());
+
+        // AR9 is 600ms, which becomes 400ms, and OD9 is a 26ms window, which becomes 17.33ms
+        assert_close(adjusted.approach_rate, 10.33);
+        assert_close(adjusted.overall_difficulty, 10.44);
+
+        // CS and HP aren't affected by the rate
+        assert_close(adjusted.circle_size, 9.0);
+        assert_close(adjusted.hp_drain, 9.0);
+    }
+
+    #[test]
+    fn half_time_adjusts_timing() {
+        let adjusted = settings(5.0).with_mods(Mods::HalfTime.into());
+
+        // AR5 is 1200ms, which becomes 1600ms, and OD5 is a 50ms window, which becomes 66.67ms
+        assert_close(adjusted.approach_rate, 1.67);
+        assert_close(adjusted.overall_difficulty, 2.22);
+    }
+
+    #[test]
+    fn star_rating_mods_are_filtered() {
+        let mods = Mods::Hidden | Mods::HardRock | Mods::Nightcore;
+
+        assert_eq!(star_rating_mods(mods), Mods::HardRock | Mods::DoubleTime);
+        assert_eq!(clock_rate(mods), 1.5);
+    }
+
+    #[test]
+    fn conflicting_mods_are_detected() {
+        assert!(conflicts(Mods::HardRock, Mods::Easy));
+        assert!(conflicts(Mods::HalfTime, Mods::DoubleTime));
+        assert!(!conflicts(Mods::HardRock, Mods::DoubleTime));
+    }
+}
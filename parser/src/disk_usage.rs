@@ -0,0 +1,165 @@
+//! Measuring how much disk space beatmap folders use, broken down by the kind of file.
+
+use std::{io, path::Path};
+
+/// Represents the kinds of file that disk usage is broken down by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    Audio,
+    Video,
+    Image,
+    Other,
+}
+
+/// Represents the disk space used by a folder, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub audio: u64,
+    pub video: u64,
+    pub images: u64,
+    pub other: u64,
+}
+
+impl FileKind {
+    pub const ALL: [FileKind; 4] = [
+        FileKind::Audio,
+        FileKind::Video,
+        FileKind::Image,
+        FileKind::Other,
+    ];
+
+    /// Gets the kind of a file from its extension, ignoring case.
+    pub fn of<P: AsRef<Path>>(path: P) -> FileKind {
+        let extension = path
+            .as_ref()
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "mp3" | "ogg" | "wav" | "flac" | "m4a" => FileKind::Audio,
+            "mp4" | "avi" | "flv" | "mkv" | "webm" | "mov" | "wmv" | "m4v" | "mpg" | "mpeg" => {
+                FileKind::Video
+            }
+            "jpg" | "jpeg" | "png" | "bmp" | "gif" | "webp" => FileKind::Image,
+            _ => FileKind::Other,
+        }
+    }
+}
+
+impl Usage {
+    /// Gets the total disk space used.
+    pub fn total(&self) -> u64 {
+        self.audio + self.video + self.images + self.other
+    }
+
+    /// Gets the disk space used by one kind of file.
+    pub fn get(&self, kind: FileKind) -> u64 {
+        match kind {
+            FileKind::Audio => self.audio,
+            FileKind::Video => self.video,
+            FileKind::Image => self.images,
+            FileKind::Other => self.other,
+        }
+    }
+
+    /// Adds a file's size to the usage for its kind.
+    pub fn add(&mut self, kind: FileKind, size: u64) {
+        match kind {
+            FileKind::Audio => self.audio += size,
+            FileKind::Video => self.video += size,
+            FileKind::Image => self.images += size,
+            FileKind::Other => self.other += size,
+        }
+    }
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Usage) {
+        self.audio += other.audio;
+        self.video += other.video;
+        self.images += other.images;
+        self.other += other.other;
+    }
+}
+
+/// Measures the disk space used by the files in a folder, including any subfolders (e.g. skin elements).
+///
+/// Symbolic links aren't followed, so that a link to a large folder isn't counted more than once. Files which can't
+/// be read are skipped, but an error is returned if the folder itself can't be read, e.g. because it doesn't exist.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(folder = %folder.as_ref().display()))
+)]
+pub fn folder_usage<P: AsRef<Path>>(folder: P) -> io::Result<Usage> {
+    let mut usage = Usage::default();
+    let mut pending = vec![folder.as_ref().to_path_buf()];
+    let mut root = true;
+
+    while let Some(folder) = pending.pop() {
+        let entries = match std::fs::read_dir(&folder) {
+            Ok(entries) => entries,
+            Err(e) if root => return Err(e),
+            Err(_) => continue,
+        };
+
+        root = false;
+
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else if metadata.is_file() {
+                usage.add(FileKind::of(entry.path()), metadata.len());
+            }
+        }
+    }
+
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_kinds_are_detected() {
+        assert_eq!(FileKind::of("audio.MP3"), FileKind::Audio);
+        assert_eq!(FileKind::of("bg.jpg"), FileKind::Image);
+        assert_eq!(FileKind::of("video.avi"), FileKind::Video);
+        assert_eq!(FileKind::of("map.osu"), FileKind::Other);
+        assert_eq!(FileKind::of("no extension"), FileKind::Other);
+    }
+
+    #[test]
+    fn folder_usage_is_measured() {
+        let dir =
+            std::env::temp_dir().join(format!("osu-db-parser-disk-usage-{}", std::process::id()));
+        let skin = dir.join("skin");
+
+        std::fs::create_dir_all(&skin).unwrap();
+        std::fs::write(dir.join("audio.mp3"), [0; 100]).unwrap();
+        std::fs::write(dir.join("bg.png"), [0; 20]).unwrap();
+        std::fs::write(dir.join("map.osu"), [0; 5]).unwrap();
+        std::fs::write(skin.join("hitcircle.png"), [0; 10]).unwrap();
+
+        let usage = folder_usage(&dir).unwrap();
+
+        assert_eq!(
+            usage,
+            Usage {
+                audio: 100,
+                video: 0,
+                images: 30,
+                other: 5
+            }
+        );
+        assert_eq!(usage.total(), 135);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(folder_usage(&dir).is_err());
+    }
+}
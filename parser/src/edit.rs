@@ -0,0 +1,445 @@
+//! Changing the per-beatmap settings of many beatmaps at once, such as the local offset or whether the video is shown.
+//!
+//! osu! only lets these be changed one beatmap at a time. Only the settings are edited, so every other field (and
+//! therefore every other byte of the written entry) is left exactly as it was.
+//!
+//! The grades shown in song select can also be recalculated from the local scores in `scores.db`, e.g. after scores
+//! were deleted or copied from another installation.
+
+use std::collections::HashMap;
+
+use crate::{
+    beatmaps::{BeatmapEntry, BeatmapListing},
+    common::{GameplayMode, Grade},
+    query::BeatmapFilter,
+    scores::{ScoreListing, ScoreSummary},
+};
+
+/// Gameplay modes in the order of the grades stored for each beatmap.
+const MODES: [GameplayMode; 4] = [
+    GameplayMode::Standard,
+    GameplayMode::Taiko,
+    GameplayMode::Catch,
+    GameplayMode::Mania,
+];
+
+/// Represents a change to a beatmap's local offset, in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OffsetEdit {
+    /// Replace the offset with this value
+    Set(i16),
+
+    /// Add this to the current offset
+    Shift(i16),
+}
+
+/// Changes to make to the per-beatmap settings in [`BeatmapListing::bulk_edit`]. Settings which are `None` are left as
+/// they are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BulkEdit {
+    pub local_offset: Option<OffsetEdit>,
+    pub ignore_beatmap_hitsounds: Option<bool>,
+    pub ignore_beatmap_skin: Option<bool>,
+    pub disable_storyboard: Option<bool>,
+    pub disable_video: Option<bool>,
+    pub visual_override: Option<bool>,
+    pub mania_scroll_speed: Option<u8>,
+}
+
+impl OffsetEdit {
+    /// Applies the change to an offset as stored in a [`BeatmapEntry`]. osu! treats the stored value as signed, so
+    /// negative offsets wrap around, and shifting saturates rather than wrapping past the largest offset.
+    pub fn apply(&self, offset: u16) -> u16 {
+        match *self {
+            OffsetEdit::Set(value) => value as u16,
+            OffsetEdit::Shift(delta) => (offset as i16).saturating_add(delta) as u16,
+        }
+    }
+}
+
+impl BulkEdit {
+    /// Checks whether this edit doesn't change anything.
+    pub fn is_empty(&self) -> bool {
+        *self == BulkEdit::default()
+    }
+
+    /// Applies the changes to a single beatmap, returning `true` if anything was actually changed.
+    pub fn apply(&self, beatmap: &mut BeatmapEntry) -> bool {
+        let mut changed = false;
+
+        if let Some(offset) = self.local_offset {
+            let offset = offset.apply(beatmap.local_offset);
+            changed |= set(&mut beatmap.local_offset, offset);
+        }
+
+        let flags = [
+            (
+                &mut beatmap.ignore_beatmap_hitsounds,
+                self.ignore_beatmap_hitsounds,
+            ),
+            (&mut beatmap.ignore_beatmap_skin, self.ignore_beatmap_skin),
+            (&mut beatmap.disable_storyboard, self.disable_storyboard),
+            (&mut beatmap.disable_video, self.disable_video),
+            (&mut beatmap.visual_override, self.visual_override),
+        ];
+
+        for (flag, value) in flags {
+            if let Some(value) = value {
+                changed |= set(flag, value);
+            }
+        }
+
+        if let Some(speed) = self.mania_scroll_speed {
+            changed |= set(&mut beatmap.mania_scroll_speed, speed);
+        }
+
+        changed
+    }
+}
+
+impl BeatmapListing {
+    /// Applies an edit to every beatmap matching `filter`, returning how many beatmaps were changed. Beatmaps which
+    /// already had the new settings aren't counted.
+    pub fn bulk_edit(&mut self, filter: &BeatmapFilter, edit: BulkEdit) -> usize {
+        self.beatmaps
+            .iter_mut()
+            .filter(|beatmap| filter.matches(beatmap))
+            .map(|beatmap| edit.apply(beatmap))
+            .filter(|&changed| changed)
+            .count()
+    }
+
+    /// Applies an edit to the beatmaps at the given indices, e.g. the ones selected in a table, returning how many
+    /// beatmaps were changed. Indices outside the listing are ignored.
+    pub fn bulk_edit_indices(
+        &mut self,
+        indices: impl IntoIterator<Item = usize>,
+        edit: BulkEdit,
+    ) -> usize {
+        let mut changed = 0;
+
+        for i in indices {
+            if let Some(beatmap) = self.beatmaps.get_mut(i) {
+                changed += usize::from(edit.apply(beatmap));
+            }
+        }
+
+        changed
+    }
+
+    /// Sets each beatmap's grades to the best grade of its local scores in each gameplay mode, returning how many
+    /// beatmaps were changed. Grades in modes without any scores are left as they are.
+    pub fn recompute_grades(&mut self, scores: &ScoreListing) -> usize {
+        let grades = BestGrades::new(scores);
+
+        self.beatmaps
+            .iter_mut()
+            .map(|beatmap| grades.apply(beatmap))
+            .filter(|&changed| changed)
+            .count()
+    }
+}
+
+/// The best grade of the local scores on each beatmap, keyed by the beatmap's MD5 hash, for recalculating the grades
+/// stored in `osu!.db`.
+pub(crate) struct BestGrades<'a> {
+    grades: HashMap<&'a str, [Option<Grade>; 4]>,
+}
+
+impl<'a> BestGrades<'a> {
+    /// Finds the best grade in each gameplay mode for every beatmap with local scores.
+    pub(crate) fn new(scores: &'a ScoreListing) -> Self {
+        let grades = scores
+            .beatmap_scores
+            .iter()
+            .filter_map(|beatmap| Some((beatmap.md5.as_deref()?, &beatmap.scores)))
+            .map(|(md5, scores)| {
+                let grades = MODES
+                    .map(|mode| ScoreSummary::of(scores, mode).map(|summary| summary.best_grade));
+
+                (md5, grades)
+            })
+            .collect();
+
+        Self { grades }
+    }
+
+    /// Sets a beatmap's grades to the best grade of its scores in each mode, returning `true` if any grade changed.
+    /// Modes without any scores keep their current grade, since `scores.db` may not have every score.
+    pub(crate) fn apply(&self, beatmap: &mut BeatmapEntry) -> bool {
+        let Some(grades) = beatmap.md5.as_deref().and_then(|md5| self.grades.get(md5)) else {
+            return false;
+        };
+
+        let fields = [
+            &mut beatmap.grade_std,
+            &mut beatmap.grade_taiko,
+            &mut beatmap.grade_catch,
+            &mut beatmap.grade_mania,
+        ];
+
+        let mut changed = false;
+        for (field, grade) in fields.into_iter().zip(grades) {
+            if let Some(grade) = grade {
+                changed |= set(field, *grade);
+            }
+        }
+
+        changed
+    }
+}
+
+/// Replaces a value, returning `true` if it was different.
+fn set<T: PartialEq>(field: &mut T, value: T) -> bool {
+    let changed = *field != value;
+    *field = value;
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        beatmaps::ParseOptions,
+        common::WINDOWS_EPOCH,
+        scores::{BeatmapScores, ScoreReplay},
+    };
+
+    use super::*;
+
+    /// Builds a listing in each layout that stores entries differently, so that edits are checked against every
+    /// writer.
+    fn listings() -> Vec<BeatmapListing> {
+        [20121008, 20140609, 20191106, 20250108]
+            .into_iter()
+            .map(|version| {
+                let modern = version >= 20140609;
+                let beatmaps = (0..4)
+                    .map(|i| BeatmapEntry {
+                        artist_name: Some(format!("Artist {}", i)),
+                        song_title: Some("Title".to_string()),
+                        difficulty: Some("Insane".to_string()),
+                        md5: Some(format!("{:032x}", i)),
+                        gameplay_mode: if i % 2 == 0 {
+                            GameplayMode::Standard
+                        } else {
+                            GameplayMode::Mania
+                        },
+                        local_offset: i * 5,
+                        disable_video: i == 2,
+                        star_ratings_std: modern.then(Vec::new),
+                        star_ratings_taiko: modern.then(Vec::new),
+                        star_ratings_ctb: modern.then(Vec::new),
+                        star_ratings_mania: modern.then(Vec::new),
+                        unknown_u16: (!modern).then_some(0),
+                        ..Default::default()
+                    })
+                    .collect();
+
+                BeatmapListing {
+                    version,
+                    folder_count: 4,
+                    account_unlocked: true,
+                    account_unlock_date: WINDOWS_EPOCH,
+                    player_name: Some("Player".to_string()),
+                    beatmaps,
+                    user_permissions: Default::default(),
+                }
+            })
+            .collect()
+    }
+
+    /// Writes a listing, returning the bytes along with where each entry is.
+    fn encode(listing: &BeatmapListing) -> (Vec<u8>, Vec<std::ops::Range<usize>>) {
+        let data = listing.to_bytes();
+        let options = ParseOptions {
+            record_spans: true,
+            ..Default::default()
+        };
+        let partial = BeatmapListing::from_bytes_with_options(&data, options, |_, _| true).unwrap();
+        (data, partial.spans)
+    }
+
+    #[test]
+    fn offsets_are_set_and_shifted() {
+        assert_eq!(OffsetEdit::Set(12).apply(40), 12);
+        assert_eq!(OffsetEdit::Set(-12).apply(40), -12i16 as u16);
+        assert_eq!(OffsetEdit::Shift(12).apply(40), 52);
+        assert_eq!(OffsetEdit::Shift(-50).apply(40), -10i16 as u16);
+        assert_eq!(OffsetEdit::Shift(10).apply(-25i16 as u16), -15i16 as u16);
+        assert_eq!(
+            OffsetEdit::Shift(100).apply(i16::MAX as u16),
+            i16::MAX as u16
+        );
+    }
+
+    #[test]
+    fn only_matching_beatmaps_are_changed() {
+        let mut listing = listings().remove(2);
+        let filter = BeatmapFilter::parse("mode=std").unwrap();
+
+        let edit = BulkEdit {
+            disable_video: Some(true),
+            local_offset: Some(OffsetEdit::Shift(12)),
+            ..Default::default()
+        };
+
+        assert_eq!(listing.bulk_edit(&filter, edit), 2);
+
+        let offsets = listing
+            .beatmaps
+            .iter()
+            .map(|b| b.local_offset)
+            .collect::<Vec<_>>();
+        let videos = listing
+            .beatmaps
+            .iter()
+            .map(|b| b.disable_video)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![12, 5, 22, 15]);
+        assert_eq!(videos, vec![true, false, true, false]);
+    }
+
+    #[test]
+    fn unchanged_beatmaps_are_not_counted() {
+        let mut listing = listings().remove(2);
+        let edit = BulkEdit {
+            disable_video: Some(true),
+            ..Default::default()
+        };
+
+        // The third beatmap already has its video disabled
+        assert_eq!(listing.bulk_edit(&BeatmapFilter::default(), edit), 3);
+        assert_eq!(listing.bulk_edit(&BeatmapFilter::default(), edit), 0);
+        assert_eq!(
+            listing.bulk_edit(&BeatmapFilter::default(), BulkEdit::default()),
+            0
+        );
+        assert!(BulkEdit::default().is_empty());
+        assert!(!edit.is_empty());
+    }
+
+    #[test]
+    fn selected_beatmaps_are_changed() {
+        let mut listing = listings().remove(2);
+        let edit = BulkEdit {
+            ignore_beatmap_skin: Some(true),
+            ..Default::default()
+        };
+
+        assert_eq!(listing.bulk_edit_indices([1, 3, 10], edit), 2);
+
+        let skins = listing
+            .beatmaps
+            .iter()
+            .map(|b| b.ignore_beatmap_skin)
+            .collect::<Vec<_>>();
+        assert_eq!(skins, vec![false, true, false, true]);
+    }
+
+    #[test]
+    fn grades_are_recomputed_from_scores() {
+        let mut listing = listings().remove(2);
+        listing.beatmaps[0].grade_std = Grade::A;
+        listing.beatmaps[1].grade_std = Grade::B;
+        listing.beatmaps[1].grade_mania = Grade::C;
+
+        let score = |mode, hits_300, misses| ScoreReplay {
+            gameplay_mode: mode,
+            hits_300,
+            misses,
+            ..Default::default()
+        };
+
+        let scores = ScoreListing {
+            version: 20240101,
+            beatmap_scores: vec![
+                BeatmapScores {
+                    md5: listing.beatmaps[0].md5.clone(),
+                    scores: vec![
+                        score(GameplayMode::Standard, 100, 0),
+                        score(GameplayMode::Standard, 90, 10),
+                    ],
+                },
+                BeatmapScores {
+                    md5: listing.beatmaps[1].md5.clone(),
+                    scores: vec![score(GameplayMode::Mania, 100, 0)],
+                },
+                BeatmapScores {
+                    md5: Some("not in the listing".to_string()),
+                    scores: vec![score(GameplayMode::Standard, 100, 0)],
+                },
+            ],
+        };
+
+        assert_eq!(listing.recompute_grades(&scores), 2);
+        assert_eq!(listing.beatmaps[0].grade_std, Grade::SS);
+
+        // Only the mode with scores is recalculated
+        assert_eq!(listing.beatmaps[1].grade_std, Grade::B);
+        assert_eq!(listing.beatmaps[1].grade_mania, Grade::SS);
+        assert_eq!(listing.beatmaps[2].grade_std, Grade::Unplayed);
+
+        assert_eq!(listing.recompute_grades(&scores), 0);
+    }
+
+    #[test]
+    fn untouched_bytes_are_identical() {
+        let edit = BulkEdit {
+            local_offset: Some(OffsetEdit::Set(-20)),
+            ignore_beatmap_hitsounds: Some(true),
+            ignore_beatmap_skin: Some(true),
+            disable_storyboard: Some(true),
+            disable_video: Some(false),
+            visual_override: Some(true),
+            mania_scroll_speed: Some(24),
+        };
+
+        for original in listings() {
+            let version = original.version;
+            let mut edited = original.clone();
+            assert_eq!(
+                edited.bulk_edit(&BeatmapFilter::parse("mode=mania").unwrap(), edit),
+                2
+            );
+
+            let (before, before_spans) = encode(&original);
+            let (after, after_spans) = encode(&edited);
+
+            // The settings have a fixed size, so every entry stays in the same place
+            assert_eq!(before.len(), after.len(), "version {}", version);
+            assert_eq!(before_spans, after_spans, "version {}", version);
+
+            // The header, the skipped entries and the permissions are untouched
+            let first = before_spans[0].start;
+            let last = before_spans[3].end;
+            assert_eq!(before[..first], after[..first], "version {}", version);
+            assert_eq!(before[last..], after[last..], "version {}", version);
+
+            for i in [0, 2] {
+                let span = before_spans[i].clone();
+                assert_eq!(before[span.clone()], after[span], "version {}", version);
+            }
+
+            // The edited entries parse back to the same beatmap apart from the settings
+            let parsed_before = BeatmapListing::from_bytes(&before).unwrap();
+            let parsed_after = BeatmapListing::from_bytes(&after).unwrap();
+
+            for i in [1, 3] {
+                let mut beatmap = parsed_after.beatmaps[i].clone();
+                let original = &parsed_before.beatmaps[i];
+                assert_eq!(beatmap.local_offset, -20i16 as u16);
+                assert!(beatmap.visual_override);
+                assert_eq!(beatmap.mania_scroll_speed, 24);
+
+                beatmap.local_offset = original.local_offset;
+                beatmap.ignore_beatmap_hitsounds = original.ignore_beatmap_hitsounds;
+                beatmap.ignore_beatmap_skin = original.ignore_beatmap_skin;
+                beatmap.disable_storyboard = original.disable_storyboard;
+                beatmap.disable_video = original.disable_video;
+                beatmap.visual_override = original.visual_override;
+                beatmap.mania_scroll_speed = original.mania_scroll_speed;
+                assert_eq!(&beatmap, original, "version {}", version);
+            }
+        }
+    }
+}
@@ -1,11 +1,200 @@
+//! The error type returned by the parser's public functions.
+//!
+//! Errors from smaller steps (e.g. [`QueryError`] from parsing a search) are kept as their own types, so that callers
+//! which only do that step can match on them precisely. They all convert into [`Error`] with `?`, and are available
+//! from [`std::error::Error::source`].
+//!
+//! Messages are written to be shown to users as they are, e.g. in the viewer's error dialogs.
+
 use thiserror::Error;
 
+use crate::detect::FileKind;
+#[cfg(feature = "tools")]
+use crate::{expr::ExprError, ids::IdError, query::QueryError};
+
 /// Represents an error that can occur when reading an osu! file.
 #[derive(Error, Debug)]
 pub enum Error {
-    #[error("Unable to parse file: {}", .0)]
-    Parser(#[from] nom::Err<nom::error::Error<Vec<u8>>>),
+    #[error(
+        "Unable to read {}: the data at byte {} isn't valid ({})",
+        .file,
+        .offset,
+        .kind.description()
+    )]
+    Parse {
+        /// Kind of file being parsed
+        file: FileKind,
+
+        /// Byte offset where parsing failed
+        offset: usize,
+
+        /// What was being parsed when it failed
+        kind: nom::error::ErrorKind,
+    },
+
+    #[error(
+        "Unable to read beatmap {} of {} in osu!.db (starting at byte {}): the data at byte {} isn't valid ({})",
+        .index + 1,
+        .total,
+        .entry_offset,
+        .offset,
+        .kind.description()
+    )]
+    Beatmap {
+        /// Index of the beatmap which couldn't be parsed
+        index: usize,
+
+        /// Total number of beatmaps in the file
+        total: usize,
+
+        /// Byte offset of the start of the beatmap
+        entry_offset: usize,
+
+        /// Byte offset where parsing failed
+        offset: usize,
+
+        kind: nom::error::ErrorKind,
+    },
+
+    #[error(
+        "Beatmap {} of {} in osu!.db (starting at byte {}) has a non-finite {}",
+        .index + 1,
+        .total,
+        .entry_offset,
+        .field
+    )]
+    NonFiniteFloat {
+        /// Index of the beatmap with the non-finite value
+        index: usize,
+
+        /// Total number of beatmaps in the file
+        total: usize,
+
+        /// Byte offset of the start of the beatmap
+        entry_offset: usize,
+
+        /// Name of the field with the non-finite value
+        field: &'static str,
+    },
+
+    #[error("The operation was cancelled")]
+    Cancelled,
+
+    #[cfg(feature = "writer")]
+    #[error(
+        "osu!.db version {} is newer than the newest known version ({}), so it can't be written safely",
+        .0,
+        crate::beatmaps::NEWEST_KNOWN_VERSION
+    )]
+    UnsupportedVersionForWrite(u32),
+
+    #[cfg(feature = "tools")]
+    #[error("Invalid search: {}", .0)]
+    Query(#[from] QueryError),
+
+    #[cfg(feature = "tools")]
+    #[error("Invalid expression: {}", .0)]
+    Expr(#[from] ExprError),
+
+    #[cfg(feature = "tools")]
+    #[error("Invalid ID: {}", .0)]
+    Id(#[from] IdError),
 
     #[error("I/O error occurred: {}", .0)]
     IO(#[from] std::io::Error),
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error(
+        "Unable to read {}: it's being written by another program (probably osu!). Try again in a moment, and close osu! before saving changes to it",
+        .0.display()
+    )]
+    DatabaseBusy(std::path::PathBuf),
+
+    #[cfg(all(feature = "backups", not(target_arch = "wasm32")))]
+    #[error("There's no backup with the ID {}", .0)]
+    BackupNotFound(String),
+
+    #[cfg(all(feature = "backups", not(target_arch = "wasm32")))]
+    #[error("Backup {} has changed since it was made, so it can't be restored", .0)]
+    BackupChanged(String),
+
+    #[cfg(feature = "zip")]
+    #[error("Unable to write archive: {}", .0)]
+    Zip(#[from] zip::result::ZipError),
+}
+
+impl Error {
+    /// Converts an error from parsing the contents of a file, finding the offset from the input left when it failed.
+    pub(crate) fn parse(
+        file: FileKind,
+        data: &[u8],
+        error: nom::Err<nom::error::Error<&[u8]>>,
+    ) -> Error {
+        let (offset, kind) = match error {
+            nom::Err::Error(e) | nom::Err::Failure(e) => (data.len() - e.input.len(), e.code),
+            nom::Err::Incomplete(_) => (data.len(), nom::error::ErrorKind::Eof),
+        };
+
+        Error::Parse { file, offset, kind }
+    }
+}
+
+#[cfg(all(test, feature = "tools"))]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+    use crate::{beatmaps::BeatmapListing, collections::CollectionListing, query::BeatmapFilter};
+
+    #[test]
+    fn parse_errors_mention_the_file_and_offset() {
+        let error = CollectionListing::from_bytes(&[0x03, 0x8b, 0x33, 0x01, 0x02]).unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::Parse {
+                file: FileKind::Collections,
+                offset: 4,
+                ..
+            }
+        ));
+
+        let message = error.to_string();
+        assert!(message.contains("collection.db"), "{}", message);
+        assert!(message.contains("byte 4"), "{}", message);
+    }
+
+    #[test]
+    fn truncated_headers_are_reported() {
+        let error = BeatmapListing::from_bytes(&[0x03, 0x8b, 0x33]).unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::Parse {
+                file: FileKind::Beatmaps,
+                offset: 0,
+                ..
+            }
+        ));
+        assert!(error.to_string().starts_with("Unable to read osu!.db"));
+    }
+
+    #[test]
+    fn conversions_keep_the_underlying_error() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "osu!.db is missing");
+        let error = Error::from(io);
+
+        assert!(error.to_string().contains("osu!.db is missing"));
+        let source = error.source().unwrap().downcast_ref::<std::io::Error>();
+        assert_eq!(source.map(|e| e.kind()), Some(std::io::ErrorKind::NotFound));
+
+        let query = BeatmapFilter::parse("colour=red").unwrap_err();
+        let error = Error::from(query.clone());
+
+        assert_eq!(error.to_string(), format!("Invalid search: {}", query));
+        assert_eq!(
+            error.source().unwrap().downcast_ref::<QueryError>(),
+            Some(&query)
+        );
+    }
 }
@@ -0,0 +1,476 @@
+//! Exporting beatmaps as text, e.g. to open them in a spreadsheet or share them with others.
+//!
+//! With the `zip` feature, several exports can be written into one archive (see [`bundle`]).
+
+use std::{collections::HashSet, io::Write};
+
+#[cfg(feature = "zip")]
+pub use self::bundle::{bundle, BundleEntry, MANIFEST_NAME};
+
+use crate::{
+    beatmaps::{BeatmapEntry, MetadataLanguage, RankedStatus},
+    columns::Column,
+    common::OsuStr,
+    error::Error,
+    expr::CustomColumn,
+    links::{self, Mirror},
+};
+
+#[cfg(feature = "zip")]
+mod bundle;
+
+/// Represents the formats that beatmaps can be exported as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Comma-separated values, with a header row
+    Csv,
+
+    /// Tab-separated values, with a header row, which can be pasted straight into a spreadsheet
+    Tsv,
+
+    /// A Markdown table
+    Markdown,
+
+    /// An HTML `<table>` element, with each beatmap's title linking to its page on the osu! website
+    Html,
+
+    /// One download link for each beatmapset, skipping beatmaps which haven't been submitted
+    Links(Mirror),
+}
+
+/// Writes beatmaps in a format, using the values of `columns` for each row of a table, with the artist and title in
+/// `language`. Columns aren't used for the [`Format::Links`] format.
+///
+/// Control characters in the beatmaps' text are replaced (see [`OsuStr::sanitized`]), so each beatmap is always one
+/// row, and one line of text.
+///
+/// Beatmaps are written in the order they're given, so a listing's beatmaps come out in file order unless they've been
+/// sorted first.
+///
+/// [`Format::Html`] only writes the table, so that it can be placed in a larger document, e.g. a
+/// [report](crate::report).
+///
+/// `progress` is called with the number of beatmaps written so far and the total number of beatmaps. Returning
+/// `false` cancels the export, in which case [`Error::Cancelled`] is returned.
+pub fn write<W, F>(
+    writer: W,
+    format: Format,
+    columns: &[Column],
+    language: MetadataLanguage,
+    beatmaps: &[&BeatmapEntry],
+    progress: F,
+) -> Result<(), Error>
+where
+    W: Write,
+    F: FnMut(usize, usize) -> bool,
+{
+    write_with_custom(writer, format, columns, &[], language, beatmaps, progress)
+}
+
+/// Writes beatmaps like [`write`], with the values of some [custom columns](CustomColumn) after the other columns.
+/// Custom columns with no value for a beatmap (e.g. from dividing by zero) are left blank.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "export",
+        skip_all,
+        fields(?format, columns = columns.len() + custom.len(), beatmaps = beatmaps.len())
+    )
+)]
+pub fn write_with_custom<W, F>(
+    mut writer: W,
+    format: Format,
+    columns: &[Column],
+    custom: &[CustomColumn],
+    language: MetadataLanguage,
+    beatmaps: &[&BeatmapEntry],
+    mut progress: F,
+) -> Result<(), Error>
+where
+    W: Write,
+    F: FnMut(usize, usize) -> bool,
+{
+    let headers = columns
+        .iter()
+        .map(|column| column.header().to_string())
+        .chain(
+            custom
+                .iter()
+                .map(|column| column.name.sanitized().into_owned()),
+        );
+
+    match format {
+        Format::Csv | Format::Tsv => writeln!(writer, "{}", row(format, headers))?,
+        Format::Markdown => {
+            writeln!(writer, "{}", row(format, headers))?;
+            writeln!(writer, "|{}", " --- |".repeat(columns.len() + custom.len()))?;
+        }
+        Format::Html => {
+            writeln!(writer, "<table>")?;
+            let headers = headers
+                .map(|header| format!("<th>{}</th>", html_escape(&header)))
+                .collect::<String>();
+
+            writeln!(writer, "<thead><tr>{}</tr></thead>", headers)?;
+            writeln!(writer, "<tbody>")?;
+        }
+        Format::Links(_) => {}
+    }
+
+    let mut seen = HashSet::new();
+
+    for (i, beatmap) in beatmaps.iter().enumerate() {
+        match format {
+            Format::Links(mirror) => {
+                if let Some(beatmapset_id) = beatmap
+                    .beatmapset_id()
+                    .filter(|_| beatmap.ranked_status != RankedStatus::Unsubmitted)
+                {
+                    if seen.insert(beatmapset_id) {
+                        writeln!(writer, "{}", mirror.download_url(beatmapset_id))?;
+                    }
+                }
+            }
+            Format::Html => {
+                let url = links::beatmap_url(beatmap);
+                let values = columns
+                    .iter()
+                    .map(|column| {
+                        let value = html_escape(&column.value_in(beatmap, language).to_string());
+
+                        match (column, &url) {
+                            (Column::Title | Column::TitleUnicode | Column::Url, Some(url)) => {
+                                format!("<a href=\"{}\">{}</a>", html_escape(url), value)
+                            }
+                            _ => value,
+                        }
+                    })
+                    .chain(custom_values(custom, beatmap));
+
+                writeln!(writer, "{}", row(format, values))?;
+            }
+            _ => {
+                let values = columns
+                    .iter()
+                    .map(|column| column.value_in(beatmap, language).to_string())
+                    .chain(custom_values(custom, beatmap));
+
+                writeln!(writer, "{}", row(format, values))?;
+            }
+        }
+
+        if !progress(i + 1, beatmaps.len()) {
+            return Err(Error::Cancelled);
+        }
+    }
+
+    if format == Format::Html {
+        writeln!(writer, "</tbody>")?;
+        writeln!(writer, "</table>")?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Gets the values of custom columns for a beatmap, as text.
+fn custom_values<'a>(
+    custom: &'a [CustomColumn],
+    beatmap: &'a BeatmapEntry,
+) -> impl Iterator<Item = String> + 'a {
+    custom
+        .iter()
+        .map(|column| column.value(beatmap).to_string())
+}
+
+/// Joins the cells of a table row, escaping them as needed for the format.
+fn row(format: Format, cells: impl Iterator<Item = String>) -> String {
+    match format {
+        Format::Csv => cells
+            .map(|cell| csv_escape(&cell))
+            .collect::<Vec<_>>()
+            .join(","),
+        Format::Tsv => cells
+            .map(|cell| cell.replace(['\t', '\r', '\n'], " "))
+            .collect::<Vec<_>>()
+            .join("\t"),
+        Format::Markdown => {
+            let cells = cells
+                .map(|cell| cell.replace('|', "\\|").replace(['\r', '\n'], " "))
+                .collect::<Vec<_>>();
+
+            format!("| {} |", cells.join(" | "))
+        }
+
+        // Cells are escaped by the caller, since some of them contain links
+        Format::Html => format!(
+            "<tr>{}</tr>",
+            cells
+                .map(|cell| format!("<td>{}</td>", cell))
+                .collect::<String>()
+        ),
+        Format::Links(_) => String::new(),
+    }
+}
+
+/// Escapes text for use in an HTML document.
+pub fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Quotes a CSV cell if it contains a separator, quote or line break.
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beatmap(artist: &str, beatmap_id: u32, ranked_status: RankedStatus) -> BeatmapEntry {
+        BeatmapEntry {
+            artist_name: Some(artist.to_string()),
+            song_title: Some("Title".to_string()),
+            beatmap_id,
+            ranked_status,
+            ..Default::default()
+        }
+    }
+
+    fn export(format: Format, beatmaps: &[BeatmapEntry]) -> String {
+        let beatmaps = beatmaps.iter().collect::<Vec<_>>();
+        let mut output = Vec::new();
+
+        write(
+            &mut output,
+            format,
+            &[Column::Artist, Column::Title],
+            MetadataLanguage::default(),
+            &beatmaps,
+            |_, _| true,
+        )
+        .unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn csv_cells_are_quoted() {
+        let beatmaps = [beatmap("Artist, \"The\"", 1, RankedStatus::Ranked)];
+
+        assert_eq!(
+            export(Format::Csv, &beatmaps),
+            "Artist,Title\n\"Artist, \"\"The\"\"\",Title\n"
+        );
+    }
+
+    #[test]
+    fn tsv_cells_have_no_tabs() {
+        let beatmaps = [beatmap("Tab\tArtist", 1, RankedStatus::Ranked)];
+
+        assert_eq!(
+            export(Format::Tsv, &beatmaps),
+            "Artist\tTitle\nTab\u{FFFD}Artist\tTitle\n"
+        );
+    }
+
+    #[test]
+    fn control_characters_are_replaced_in_every_format() {
+        let tampered = [BeatmapEntry {
+            creator_name: Some("\u{202E}reppam".to_string()),
+            song_tags: Some("tag\r\nanother\ttag".to_string()),
+            ..beatmap("Null\0Artist", 1, RankedStatus::Ranked)
+        }];
+        let tampered = tampered.iter().collect::<Vec<_>>();
+        let custom = [CustomColumn::new("Stars\nper minute", "stars").unwrap()];
+
+        for format in [Format::Csv, Format::Tsv, Format::Markdown, Format::Html] {
+            let mut output = Vec::new();
+            write_with_custom(
+                &mut output,
+                format,
+                &[Column::Artist, Column::Creator, Column::Tags],
+                &custom,
+                MetadataLanguage::default(),
+                &tampered,
+                |_, _| true,
+            )
+            .unwrap();
+            let output = String::from_utf8(output).unwrap();
+
+            // Only the line breaks between rows and the tabs between TSV cells are left, and nothing is quoted over
+            // several lines
+            let lines = match format {
+                Format::Csv | Format::Tsv => 2,
+                Format::Markdown => 3,
+                _ => 6,
+            };
+            assert_eq!(output.lines().count(), lines, "{:?}: {}", format, output);
+            assert!(
+                !output
+                    .lines()
+                    .flat_map(|line| line.split('\t'))
+                    .any(|cell| cell.has_control_characters()),
+                "{:?}: {:?}",
+                format,
+                output
+            );
+            assert!(
+                output.contains("Null\u{FFFD}Artist"),
+                "{:?}: {}",
+                format,
+                output
+            );
+        }
+
+        assert_eq!(
+            export(
+                Format::Csv,
+                &[beatmap("Null\0Artist", 1, RankedStatus::Ranked)]
+            ),
+            "Artist,Title\nNull\u{FFFD}Artist,Title\n"
+        );
+    }
+
+    #[test]
+    fn custom_columns_are_exported_after_the_others() {
+        let beatmaps = [
+            BeatmapEntry {
+                hitcircle_count: 450,
+                drain_time: 120,
+                ..beatmap("Artist", 1, RankedStatus::Ranked)
+            },
+            beatmap("Empty", 2, RankedStatus::Ranked),
+        ];
+        let beatmaps = beatmaps.iter().collect::<Vec<_>>();
+        let custom = [CustomColumn::new("Objects/s", "objects / drain_time").unwrap()];
+        let mut output = Vec::new();
+
+        write_with_custom(
+            &mut output,
+            Format::Csv,
+            &[Column::Artist],
+            &custom,
+            MetadataLanguage::default(),
+            &beatmaps,
+            |_, _| true,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Artist,Objects/s\nArtist,3.75\nEmpty,\n"
+        );
+    }
+
+    #[test]
+    fn markdown_table_is_escaped() {
+        let beatmaps = [beatmap("A|B", 1, RankedStatus::Ranked)];
+
+        assert_eq!(
+            export(Format::Markdown, &beatmaps),
+            "| Artist | Title |\n| --- | --- |\n| A\\|B | Title |\n"
+        );
+    }
+
+    #[test]
+    fn html_table_is_escaped_and_linked() {
+        let beatmaps = [
+            beatmap("<Artist>", 1, RankedStatus::Ranked),
+            beatmap("A & B", 0, RankedStatus::Unsubmitted),
+        ];
+
+        assert_eq!(
+            export(Format::Html, &beatmaps),
+            "<table>\n\
+             <thead><tr><th>Artist</th><th>Title</th></tr></thead>\n\
+             <tbody>\n\
+             <tr><td>&lt;Artist&gt;</td><td><a href=\"https://osu.ppy.sh/beatmapsets/1\">Title</a></td></tr>\n\
+             <tr><td>A &amp; B</td><td>Title</td></tr>\n\
+             </tbody>\n\
+             </table>\n"
+        );
+    }
+
+    #[test]
+    fn metadata_language_is_used() {
+        let beatmaps = [
+            BeatmapEntry {
+                artist_name_unicode: Some("かめりあ".to_string()),
+                song_title_unicode: Some("".to_string()),
+                ..beatmap("Camellia", 1, RankedStatus::Ranked)
+            },
+            beatmap("", 2, RankedStatus::Ranked),
+        ];
+        let beatmaps = beatmaps.iter().collect::<Vec<_>>();
+
+        let mut output = Vec::new();
+        write(
+            &mut output,
+            Format::Csv,
+            &[Column::Artist, Column::Title, Column::ArtistUnicode],
+            MetadataLanguage::Unicode,
+            &beatmaps,
+            |_, _| true,
+        )
+        .unwrap();
+
+        // Empty Unicode values fall back to the romanised ones, except in the Unicode columns themselves
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Artist,Title,Artist (Unicode)
+かめりあ,Title,かめりあ
+,Title,
+"
+        );
+    }
+
+    #[test]
+    fn links_are_unique_beatmapsets() {
+        let beatmaps = [
+            beatmap("A", 1, RankedStatus::Ranked),
+            beatmap("B", 1, RankedStatus::Ranked),
+            beatmap("C", 0, RankedStatus::Unsubmitted),
+            beatmap("D", 2, RankedStatus::Loved),
+        ];
+
+        assert_eq!(
+            export(Format::Links(Mirror::Beatconnect), &beatmaps),
+            "https://beatconnect.io/b/1\nhttps://beatconnect.io/b/2\n"
+        );
+    }
+
+    #[test]
+    fn export_can_be_cancelled() {
+        let beatmaps = [beatmap("A", 1, RankedStatus::Ranked)];
+        let beatmaps = beatmaps.iter().collect::<Vec<_>>();
+
+        let result = write(
+            Vec::new(),
+            Format::Csv,
+            &[Column::Artist],
+            MetadataLanguage::default(),
+            &beatmaps,
+            |_, _| false,
+        );
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+}
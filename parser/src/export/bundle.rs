@@ -0,0 +1,393 @@
+//! Writing several exports into one zip archive, e.g. to share a whole library at once.
+//!
+//! Each export is streamed straight into the archive as it's written, so large tables don't have to be held in memory.
+//! The archive starts with a `manifest.json` file describing the other files.
+
+use std::{
+    collections::HashSet,
+    io::{Seek, Write},
+};
+
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use super::Format;
+use crate::{
+    beatmaps::{BeatmapEntry, BeatmapListing, MetadataLanguage},
+    collections::Collection,
+    columns::Column,
+    error::Error,
+    links::Mirror,
+    report::{self, ReportOptions, TOP_MAPPERS},
+    stats::LibraryStats,
+};
+
+/// Name of the file in a bundle which describes the other files.
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Represents one export to include in a bundle.
+pub enum BundleEntry<'a> {
+    /// Summary statistics for the whole library as JSON, in `stats.json`
+    Stats,
+
+    /// A table of beatmaps as CSV, using the values of `columns` for each row, in `beatmaps.csv`
+    Table {
+        columns: Vec<Column>,
+        beatmaps: Vec<&'a BeatmapEntry>,
+    },
+
+    /// One download link for each beatmapset, in `links.txt`
+    Links {
+        mirror: Mirror,
+        beatmaps: Vec<&'a BeatmapEntry>,
+    },
+
+    /// A collection as text (see [`Collection::write_text`]), in the `collections` folder
+    Collection(&'a Collection),
+
+    /// A report summarising the whole library, in `report.html`
+    Report(ReportOptions<'a>),
+}
+
+/// Represents the contents of the manifest.
+#[derive(serde::Serialize)]
+struct Manifest<'a> {
+    generator: &'static str,
+    osu_db_version: u32,
+    player_name: Option<&'a str>,
+    beatmaps: usize,
+    files: Vec<ManifestFile>,
+}
+
+/// Represents one file described by the manifest.
+#[derive(serde::Serialize)]
+struct ManifestFile {
+    name: String,
+    kind: &'static str,
+
+    /// Number of beatmaps the file was written from
+    beatmaps: usize,
+}
+
+impl BundleEntry<'_> {
+    /// Gets the name of the kind of export, as written in the manifest.
+    fn kind(&self) -> &'static str {
+        match self {
+            BundleEntry::Stats => "stats",
+            BundleEntry::Table { .. } => "table",
+            BundleEntry::Links { .. } => "links",
+            BundleEntry::Collection(_) => "collection",
+            BundleEntry::Report(_) => "report",
+        }
+    }
+
+    /// Gets the name of the file the export is written to, before any clashes are resolved.
+    fn file_name(&self) -> String {
+        match self {
+            BundleEntry::Stats => "stats.json".to_string(),
+            BundleEntry::Table { .. } => "beatmaps.csv".to_string(),
+            BundleEntry::Links { .. } => "links.txt".to_string(),
+            BundleEntry::Collection(collection) => format!(
+                "collections/{}.txt",
+                safe_file_name(collection.name.as_deref().unwrap_or_default())
+            ),
+            BundleEntry::Report(_) => "report.html".to_string(),
+        }
+    }
+
+    /// Gets the number of beatmaps the export is written from.
+    fn beatmap_count(&self, listing: &BeatmapListing) -> usize {
+        match self {
+            BundleEntry::Stats | BundleEntry::Report(_) => listing.beatmaps.len(),
+            BundleEntry::Table { beatmaps, .. } | BundleEntry::Links { beatmaps, .. } => {
+                beatmaps.len()
+            }
+            BundleEntry::Collection(collection) => collection.beatmap_md5s.len(),
+        }
+    }
+}
+
+/// Writes several exports of `listing` into a zip archive, with the artist and title of beatmaps in `language`, and
+/// returns the writer once the archive is finished.
+///
+/// Each entry is written to its own file, after a manifest describing them. Entries that would be written to the same
+/// file (e.g. collections with the same name) are numbered, so that none of them are lost.
+///
+/// `progress` is called with the number of entries written so far and the total number of entries. Returning `false`
+/// cancels the bundle, in which case [`Error::Cancelled`] is returned.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "bundle", skip_all, fields(entries = entries.len()))
+)]
+pub fn bundle<W, F>(
+    writer: W,
+    listing: &BeatmapListing,
+    entries: &[BundleEntry],
+    language: MetadataLanguage,
+    mut progress: F,
+) -> Result<W, Error>
+where
+    W: Write + Seek,
+    F: FnMut(usize, usize) -> bool,
+{
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let names = unique_names(entries.iter().map(BundleEntry::file_name));
+
+    let manifest = Manifest {
+        generator: "osu-db-viewer",
+        osu_db_version: listing.version,
+        player_name: listing.player_name_str(),
+        beatmaps: listing.beatmaps.len(),
+        files: entries
+            .iter()
+            .zip(&names)
+            .map(|(entry, name)| ManifestFile {
+                name: name.clone(),
+                kind: entry.kind(),
+                beatmaps: entry.beatmap_count(listing),
+            })
+            .collect(),
+    };
+
+    let mut zip = ZipWriter::new(writer);
+    zip.start_file(MANIFEST_NAME, options)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest).map_err(std::io::Error::from)?;
+
+    for (i, (entry, name)) in entries.iter().zip(names).enumerate() {
+        zip.start_file(name, options)?;
+
+        match entry {
+            BundleEntry::Stats => {
+                let stats = LibraryStats::from_beatmaps(&listing.beatmaps, TOP_MAPPERS);
+                serde_json::to_writer_pretty(&mut zip, &stats).map_err(std::io::Error::from)?;
+            }
+            BundleEntry::Table { columns, beatmaps } => {
+                super::write(
+                    &mut zip,
+                    Format::Csv,
+                    columns,
+                    language,
+                    beatmaps,
+                    |_, _| true,
+                )?;
+            }
+            BundleEntry::Links { mirror, beatmaps } => {
+                super::write(
+                    &mut zip,
+                    Format::Links(*mirror),
+                    &[],
+                    language,
+                    beatmaps,
+                    |_, _| true,
+                )?;
+            }
+            BundleEntry::Collection(collection) => {
+                collection.write_text(Some(listing), &mut zip)?
+            }
+            BundleEntry::Report(options) => {
+                report::write(&mut zip, &listing.beatmaps, options, |_, _| true)?
+            }
+        }
+
+        if !progress(i + 1, entries.len()) {
+            return Err(Error::Cancelled);
+        }
+    }
+
+    Ok(zip.finish()?)
+}
+
+/// Replaces the characters that can't be used in file names on Windows, so that the archive can be extracted anywhere.
+fn safe_file_name(name: &str) -> String {
+    let name = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+
+    if name.is_empty() {
+        "Unnamed".to_string()
+    } else {
+        name
+    }
+}
+
+/// Numbers any repeated file names, e.g. `collections/Farm (2).txt`, keeping the first one as it is.
+fn unique_names(names: impl Iterator<Item = String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    names
+        .map(|name| {
+            if seen.insert(name.to_lowercase()) {
+                return name;
+            }
+
+            let (stem, extension) = name.rsplit_once('.').unwrap_or((&name, ""));
+
+            (2..)
+                .map(|n| format!("{} ({}).{}", stem, n, extension))
+                .find(|numbered| seen.insert(numbered.to_lowercase()))
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use time::macros::datetime;
+    use zip::ZipArchive;
+
+    use super::*;
+    use crate::beatmaps::RankedStatus;
+
+    fn listing() -> BeatmapListing {
+        let beatmap = |md5: &str, beatmap_id: u32| BeatmapEntry {
+            md5: Some(md5.to_string()),
+            artist_name: Some("Artist".to_string()),
+            song_title: Some(format!("Song {}", beatmap_id)),
+            creator_name: Some("Mapper".to_string()),
+            beatmap_id,
+            ranked_status: RankedStatus::Ranked,
+            ..Default::default()
+        };
+
+        BeatmapListing {
+            version: 20250108,
+            folder_count: 2,
+            account_unlocked: true,
+            account_unlock_date: crate::common::WINDOWS_EPOCH,
+            player_name: Some("Player".to_string()),
+            beatmaps: vec![beatmap("a", 1), beatmap("b", 2)],
+            user_permissions: Default::default(),
+        }
+    }
+
+    fn read(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn bundles_contain_a_manifest_and_each_export() {
+        let listing = listing();
+        let beatmaps = listing.beatmaps.iter().collect::<Vec<_>>();
+        let favourites = Collection::from_beatmaps("Favourites".to_string(), &listing.beatmaps);
+        let other = Collection::from_beatmaps("Favourites".to_string(), &listing.beatmaps[..1]);
+
+        let entries = [
+            BundleEntry::Stats,
+            BundleEntry::Table {
+                columns: vec![Column::Title],
+                beatmaps: beatmaps.clone(),
+            },
+            BundleEntry::Links {
+                mirror: Mirror::Official,
+                beatmaps: beatmaps.clone(),
+            },
+            BundleEntry::Collection(&favourites),
+            BundleEntry::Collection(&other),
+            BundleEntry::Report(ReportOptions {
+                title: "Library".to_string(),
+                player_name: None,
+                top_count: 5,
+                table: None,
+                language: MetadataLanguage::default(),
+                generated: datetime!(2024-01-02 3:04:05 UTC),
+            }),
+        ];
+
+        let mut written = Vec::new();
+        let output = bundle(
+            Cursor::new(Vec::new()),
+            &listing,
+            &entries,
+            MetadataLanguage::default(),
+            |done, total| {
+                written.push((done, total));
+                true
+            },
+        )
+        .unwrap();
+
+        assert_eq!(written.last(), Some(&(6, 6)));
+
+        let mut archive = ZipArchive::new(Cursor::new(output.into_inner())).unwrap();
+        assert_eq!(archive.len(), entries.len() + 1);
+
+        let manifest =
+            serde_json::from_str::<serde_json::Value>(&read(&mut archive, MANIFEST_NAME)).unwrap();
+        let files = manifest["files"].as_array().unwrap();
+
+        assert_eq!(manifest["player_name"], "Player");
+        assert_eq!(manifest["beatmaps"], 2);
+        assert_eq!(
+            files
+                .iter()
+                .map(|file| file["name"].as_str().unwrap())
+                .collect::<Vec<_>>(),
+            [
+                "stats.json",
+                "beatmaps.csv",
+                "links.txt",
+                "collections/Favourites.txt",
+                "collections/Favourites (2).txt",
+                "report.html"
+            ]
+        );
+        assert_eq!(files[4]["kind"], "collection");
+        assert_eq!(files[4]["beatmaps"], 1);
+
+        // Every file in the manifest is in the archive
+        for file in files {
+            archive.by_name(file["name"].as_str().unwrap()).unwrap();
+        }
+
+        let stats = serde_json::from_str::<serde_json::Value>(&read(&mut archive, "stats.json"));
+        assert_eq!(stats.unwrap()["total"], 2);
+        assert_eq!(read(&mut archive, "beatmaps.csv").lines().count(), 3);
+        assert_eq!(read(&mut archive, "links.txt").lines().count(), 2);
+        assert_eq!(
+            read(&mut archive, "collections/Favourites (2).txt"),
+            "a # Artist - Song 1 []\n"
+        );
+        assert!(read(&mut archive, "report.html").contains("<h1>Library</h1>"));
+    }
+
+    #[test]
+    fn bundles_can_be_cancelled() {
+        let listing = listing();
+        let result = bundle(
+            Cursor::new(Vec::new()),
+            &listing,
+            &[BundleEntry::Stats, BundleEntry::Stats],
+            MetadataLanguage::default(),
+            |done, _| done < 1,
+        );
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn file_names_are_safe_and_unique() {
+        assert_eq!(safe_file_name(" a/b:c? "), "a_b_c_");
+        assert_eq!(safe_file_name(""), "Unnamed");
+        assert_eq!(
+            unique_names(
+                ["x.txt", "X.txt", "x.txt", "y.csv"]
+                    .into_iter()
+                    .map(str::to_string)
+            ),
+            ["x.txt", "X (2).txt", "x (3).txt", "y.csv"]
+        );
+    }
+}
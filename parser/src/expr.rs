@@ -0,0 +1,556 @@
+//! Arithmetic expressions over a beatmap's numeric fields, which are used to define custom columns.
+//!
+//! An expression combines numbers and [`Field`]s (e.g. `objects / drain_time`) using `+`, `-`, `*` and `/`, with the
+//! usual precedence, and parentheses for grouping. Expressions are checked when they're parsed, so evaluating one
+//! can't fail: fields with no value (e.g. a star rating that hasn't been calculated) and division by zero give NaN.
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use crate::{
+    beatmaps::BeatmapEntry,
+    columns::ColumnValue,
+    common::{GameplayMode, Mods},
+};
+
+/// Represents the numeric fields of a beatmap that can be used in an expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// NoMod star rating in the beatmap's own gameplay mode
+    Stars,
+    StarsStd,
+    StarsTaiko,
+    StarsCatch,
+    StarsMania,
+
+    /// Main BPM (i.e. the BPM used for the longest duration)
+    Bpm,
+
+    /// Total length, in milliseconds
+    TotalTime,
+
+    /// Drain time, in seconds
+    DrainTime,
+    Objects,
+    Circles,
+    Sliders,
+    Spinners,
+    ApproachRate,
+    CircleSize,
+    OverallDifficulty,
+    HpDrain,
+    SliderVelocity,
+}
+
+/// Represents a parsed expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Field(Field),
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, Operator, Box<Expr>),
+}
+
+/// Represents the arithmetic operators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+/// Represents the ways that an expression can be invalid. Positions are the column of the offending character,
+/// starting from 1.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum ExprError {
+    #[error("Expression is empty")]
+    Empty,
+
+    #[error("Unknown field '{}' at column {}", .name, .position)]
+    UnknownField { name: String, position: usize },
+
+    #[error("Invalid number '{}' at column {}", .text, .position)]
+    InvalidNumber { text: String, position: usize },
+
+    #[error("Unexpected '{}' at column {}", .character, .position)]
+    UnexpectedCharacter { character: char, position: usize },
+
+    #[error("Expected a number, field or '(' at column {}", .position)]
+    ExpectedValue { position: usize },
+
+    #[error("Expected an operator at column {}", .position)]
+    ExpectedOperator { position: usize },
+
+    #[error("Unclosed '(' at column {}", .position)]
+    UnclosedParenthesis { position: usize },
+}
+
+/// Represents a column whose values are calculated from an expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomColumn {
+    /// Name shown in the column's header
+    pub name: String,
+    pub expr: Expr,
+}
+
+/// Represents a token of an expression, along with its position.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Field(Field),
+    Operator(Operator),
+    Open,
+    Close,
+}
+
+impl Field {
+    /// Every available field, in the order they're listed.
+    pub const ALL: [Field; 17] = [
+        Field::Stars,
+        Field::StarsStd,
+        Field::StarsTaiko,
+        Field::StarsCatch,
+        Field::StarsMania,
+        Field::Bpm,
+        Field::TotalTime,
+        Field::DrainTime,
+        Field::Objects,
+        Field::Circles,
+        Field::Sliders,
+        Field::Spinners,
+        Field::ApproachRate,
+        Field::CircleSize,
+        Field::OverallDifficulty,
+        Field::HpDrain,
+        Field::SliderVelocity,
+    ];
+
+    /// Gets the name used to refer to this field in an expression (e.g. `stars_std`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Stars => "stars",
+            Field::StarsStd => "stars_std",
+            Field::StarsTaiko => "stars_taiko",
+            Field::StarsCatch => "stars_catch",
+            Field::StarsMania => "stars_mania",
+            Field::Bpm => "bpm",
+            Field::TotalTime => "total_time",
+            Field::DrainTime => "drain_time",
+            Field::Objects => "objects",
+            Field::Circles => "circles",
+            Field::Sliders => "sliders",
+            Field::Spinners => "spinners",
+            Field::ApproachRate => "ar",
+            Field::CircleSize => "cs",
+            Field::OverallDifficulty => "od",
+            Field::HpDrain => "hp",
+            Field::SliderVelocity => "slider_velocity",
+        }
+    }
+
+    /// Gets this field's value for a beatmap, which is NaN if the beatmap doesn't have one.
+    pub fn value(&self, beatmap: &BeatmapEntry) -> f64 {
+        let stars = |mode| beatmap.star_rating(mode, Mods::none()).unwrap_or(f64::NAN);
+
+        match self {
+            Field::Stars => stars(beatmap.gameplay_mode),
+            Field::StarsStd => stars(GameplayMode::Standard),
+            Field::StarsTaiko => stars(GameplayMode::Taiko),
+            Field::StarsCatch => stars(GameplayMode::Catch),
+            Field::StarsMania => stars(GameplayMode::Mania),
+            Field::Bpm => beatmap.main_bpm().unwrap_or(f64::NAN),
+            Field::TotalTime => beatmap.total_time as f64,
+            Field::DrainTime => beatmap.drain_time as f64,
+            Field::Objects => beatmap.object_count() as f64,
+            Field::Circles => beatmap.hitcircle_count as f64,
+            Field::Sliders => beatmap.slider_count as f64,
+            Field::Spinners => beatmap.spinner_count as f64,
+            Field::ApproachRate => beatmap.approach_rate as f64,
+            Field::CircleSize => beatmap.circle_size as f64,
+            Field::OverallDifficulty => beatmap.overall_difficulty as f64,
+            Field::HpDrain => beatmap.hp_drain as f64,
+            Field::SliderVelocity => beatmap.slider_velocity,
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Field {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Field::ALL
+            .into_iter()
+            .find(|field| field.name().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+impl Operator {
+    /// Gets the symbol for this operator.
+    pub fn symbol(&self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Subtract => '-',
+            Operator::Multiply => '*',
+            Operator::Divide => '/',
+        }
+    }
+
+    /// Applies this operator. Dividing by zero gives NaN, rather than infinity, so that the result is treated as
+    /// missing.
+    pub fn apply(&self, a: f64, b: f64) -> f64 {
+        match self {
+            Operator::Add => a + b,
+            Operator::Subtract => a - b,
+            Operator::Multiply => a * b,
+            Operator::Divide if b == 0.0 => f64::NAN,
+            Operator::Divide => a / b,
+        }
+    }
+
+    /// Checks whether this operator is applied before addition and subtraction.
+    fn binds_tightly(&self) -> bool {
+        matches!(self, Operator::Multiply | Operator::Divide)
+    }
+}
+
+impl Expr {
+    /// Parses an expression, e.g. `stars_std * 1000 / total_time`.
+    pub fn parse(text: &str) -> Result<Expr, ExprError> {
+        let tokens = tokenize(text)?;
+        let end = text.chars().count() + 1;
+
+        if tokens.is_empty() {
+            return Err(ExprError::Empty);
+        }
+
+        let mut parser = Parser {
+            tokens: &tokens,
+            next: 0,
+            end,
+        };
+
+        let expr = parser.sum()?;
+
+        match parser.peek() {
+            None => Ok(expr),
+            Some((Token::Close, position)) => Err(ExprError::UnexpectedCharacter {
+                character: ')',
+                position,
+            }),
+            Some((_, position)) => Err(ExprError::ExpectedOperator { position }),
+        }
+    }
+
+    /// Evaluates this expression for a beatmap.
+    pub fn eval(&self, beatmap: &BeatmapEntry) -> f64 {
+        match self {
+            Expr::Number(value) => *value,
+            Expr::Field(field) => field.value(beatmap),
+            Expr::Negate(expr) => -expr.eval(beatmap),
+            Expr::Binary(a, op, b) => op.apply(a.eval(beatmap), b.eval(beatmap)),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(value) => write!(f, "{}", value),
+            Expr::Field(field) => write!(f, "{}", field),
+            Expr::Negate(expr) => write!(f, "-{}", expr),
+            Expr::Binary(a, op, b) => write!(f, "({} {} {})", a, op.symbol(), b),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expr::parse(s)
+    }
+}
+
+impl CustomColumn {
+    /// Creates a custom column, parsing its expression.
+    pub fn new(name: impl Into<String>, expression: &str) -> Result<CustomColumn, ExprError> {
+        Ok(CustomColumn {
+            name: name.into(),
+            expr: Expr::parse(expression)?,
+        })
+    }
+
+    /// Gets this column's value for a beatmap. Values that aren't finite (e.g. from dividing by zero) are missing.
+    pub fn value(&self, beatmap: &BeatmapEntry) -> ColumnValue<'static> {
+        match self.expr.eval(beatmap) {
+            value if value.is_finite() => ColumnValue::Float(value),
+            _ => ColumnValue::Empty,
+        }
+    }
+
+    /// Evaluates this column for every beatmap in a listing, so that the values can be shown and sorted by without
+    /// evaluating the expression again.
+    pub fn values(&self, beatmaps: &[BeatmapEntry]) -> Vec<f64> {
+        beatmaps
+            .iter()
+            .map(|beatmap| self.expr.eval(beatmap))
+            .collect()
+    }
+}
+
+/// Splits an expression into tokens, along with the column each one starts at.
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().enumerate().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let position = i + 1;
+
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Operator(Operator::Add),
+            '-' => Token::Operator(Operator::Subtract),
+            '*' => Token::Operator(Operator::Multiply),
+            '/' => Token::Operator(Operator::Divide),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while let Some((_, c)) =
+                    chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '.')
+                {
+                    number.push(c);
+                }
+
+                match number.parse() {
+                    Ok(value) => Token::Number(value),
+                    Err(_) => {
+                        return Err(ExprError::InvalidNumber {
+                            text: number,
+                            position,
+                        })
+                    }
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+
+                match name.parse() {
+                    Ok(field) => Token::Field(field),
+                    Err(()) => return Err(ExprError::UnknownField { name, position }),
+                }
+            }
+            character => {
+                return Err(ExprError::UnexpectedCharacter {
+                    character,
+                    position,
+                })
+            }
+        };
+
+        tokens.push((token, position));
+    }
+
+    Ok(tokens)
+}
+
+/// A recursive descent parser over the tokens of an expression.
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    next: usize,
+
+    /// Position just after the end of the expression, which is reported when a value is missing from the end
+    end: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<(Token, usize)> {
+        self.tokens.get(self.next).cloned()
+    }
+
+    /// Takes the next operator, if it's one that `accept` allows.
+    fn operator(&mut self, accept: impl Fn(Operator) -> bool) -> Option<Operator> {
+        match self.peek() {
+            Some((Token::Operator(op), _)) if accept(op) => {
+                self.next += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses terms separated by `+` and `-`.
+    fn sum(&mut self) -> Result<Expr, ExprError> {
+        let mut expr = self.product()?;
+
+        while let Some(op) = self.operator(|op| !op.binds_tightly()) {
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.product()?));
+        }
+
+        Ok(expr)
+    }
+
+    /// Parses values separated by `*` and `/`.
+    fn product(&mut self) -> Result<Expr, ExprError> {
+        let mut expr = self.value()?;
+
+        while let Some(op) = self.operator(|op| op.binds_tightly()) {
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.value()?));
+        }
+
+        Ok(expr)
+    }
+
+    /// Parses a number, field, negated value or parenthesised expression.
+    fn value(&mut self) -> Result<Expr, ExprError> {
+        let Some((token, position)) = self.peek() else {
+            return Err(ExprError::ExpectedValue { position: self.end });
+        };
+
+        self.next += 1;
+
+        match token {
+            Token::Number(value) => Ok(Expr::Number(value)),
+            Token::Field(field) => Ok(Expr::Field(field)),
+            Token::Operator(Operator::Subtract) => Ok(Expr::Negate(Box::new(self.value()?))),
+            Token::Open => {
+                let expr = self.sum()?;
+
+                match self.peek() {
+                    Some((Token::Close, _)) => {
+                        self.next += 1;
+                        Ok(expr)
+                    }
+                    _ => Err(ExprError::UnclosedParenthesis { position }),
+                }
+            }
+            Token::Operator(_) | Token::Close => Err(ExprError::ExpectedValue { position }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beatmaps::StarRating;
+
+    fn beatmap() -> BeatmapEntry {
+        BeatmapEntry {
+            star_ratings_std: Some(vec![StarRating {
+                mods: Mods::none(),
+                rating: 5.5,
+            }]),
+            hitcircle_count: 300,
+            slider_count: 150,
+            spinner_count: 2,
+            drain_time: 120,
+            total_time: 125_000,
+            ..Default::default()
+        }
+    }
+
+    fn eval(text: &str) -> f64 {
+        Expr::parse(text).unwrap().eval(&beatmap())
+    }
+
+    #[test]
+    fn expressions_are_parsed_with_precedence() {
+        for (text, parsed) in [
+            ("1 + 2 * 3", "(1 + (2 * 3))"),
+            ("(1 + 2) * 3", "((1 + 2) * 3)"),
+            ("8 / 4 / 2", "((8 / 4) / 2)"),
+            ("1 - 2 + 3", "((1 - 2) + 3)"),
+            ("-objects * 2", "(-objects * 2)"),
+            ("objects / drain_time", "(objects / drain_time)"),
+            (
+                "STARS_STD*1000/total_time",
+                "((stars_std * 1000) / total_time)",
+            ),
+            (" 0.5 ", "0.5"),
+        ] {
+            assert_eq!(Expr::parse(text).unwrap().to_string(), parsed, "{}", text);
+        }
+    }
+
+    #[test]
+    fn invalid_expressions_are_explained() {
+        for (text, error) in [
+            ("", ExprError::Empty),
+            ("   ", ExprError::Empty),
+            (
+                "objects / length",
+                ExprError::UnknownField {
+                    name: "length".to_string(),
+                    position: 11,
+                },
+            ),
+            (
+                "1.2.3 * bpm",
+                ExprError::InvalidNumber {
+                    text: "1.2.3".to_string(),
+                    position: 1,
+                },
+            ),
+            (
+                "bpm ^ 2",
+                ExprError::UnexpectedCharacter {
+                    character: '^',
+                    position: 5,
+                },
+            ),
+            ("bpm *", ExprError::ExpectedValue { position: 6 }),
+            ("* bpm", ExprError::ExpectedValue { position: 1 }),
+            ("bpm 2", ExprError::ExpectedOperator { position: 5 }),
+            ("(bpm + 2", ExprError::UnclosedParenthesis { position: 1 }),
+            (
+                "bpm + 2)",
+                ExprError::UnexpectedCharacter {
+                    character: ')',
+                    position: 8,
+                },
+            ),
+        ] {
+            assert_eq!(Expr::parse(text), Err(error), "{}", text);
+        }
+
+        assert_eq!(
+            Expr::parse("objects / length").unwrap_err().to_string(),
+            "Unknown field 'length' at column 11"
+        );
+    }
+
+    #[test]
+    fn expressions_are_evaluated() {
+        assert_eq!(eval("objects / drain_time"), 452.0 / 120.0);
+        assert_eq!(eval("stars_std * 1000 / total_time"), 0.044);
+        assert_eq!(eval("circles + sliders * 2 - -spinners"), 602.0);
+        assert_eq!(eval("(circles + sliders) * 2"), 900.0);
+
+        // Missing values and dividing by zero (including zero by zero) give NaN
+        assert!(eval("stars_mania * 2").is_nan());
+        assert!(eval("objects / (drain_time - 120)").is_nan());
+        assert!(eval("0 / 0").is_nan());
+    }
+
+    #[test]
+    fn custom_columns_skip_values_that_are_not_finite() {
+        let column = CustomColumn::new("Density", "objects / (drain_time - 120)").unwrap();
+        assert_eq!(column.value(&beatmap()), ColumnValue::Empty);
+
+        let column = CustomColumn::new("Density", "objects / drain_time * 0").unwrap();
+        assert_eq!(column.value(&beatmap()), ColumnValue::Float(0.0));
+        assert_eq!(column.values(&[beatmap(), beatmap()]), vec![0.0, 0.0]);
+    }
+}
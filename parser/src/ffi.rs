@@ -0,0 +1,502 @@
+//! A C ABI for reading `osu!.db` files from other languages (e.g. C, C++ or C#), enabled with the `ffi` feature.
+//!
+//! The header for these functions is in `include/osu_db.h`, and is generated with
+//! `cbindgen --config cbindgen.toml --output include/osu_db.h` from the parser's folder.
+//!
+//! # Memory ownership
+//!
+//! - [`osudb_parse`] copies what it needs from the input, so the input can be freed as soon as it returns.
+//! - A handle from [`osudb_parse`] must be freed with [`osudb_free`] exactly once, and can't be used after that.
+//! - Strings returned by the accessors are owned by the handle. They're valid until the handle is freed, and must not
+//!   be freed or modified by the caller.
+//! - The message from [`osudb_last_error_message`] is owned by the library, and is valid until the next error on the
+//!   same thread.
+//!
+//! All strings are UTF-8 and NUL-terminated. osu! doesn't write NUL characters in strings, but if one is found, the
+//! string is cut off at the NUL.
+//!
+//! A handle can be read from several threads at once, since none of the accessors modify it.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use time::OffsetDateTime;
+
+use crate::{
+    beatmaps::{BeatmapEntry, BeatmapListing},
+    common::{OsuString, WINDOWS_EPOCH},
+};
+
+/// Number of variants in [`OsuDbField`].
+const FIELD_COUNT: usize = 12;
+
+thread_local! {
+    /// Message for the most recent error on this thread.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Represents a parsed `osu!.db` file.
+pub struct OsuDbHandle {
+    listing: BeatmapListing,
+    player_name: Option<CString>,
+
+    /// Each beatmap's string fields, in the order of [`OsuDbField`]
+    strings: Vec<[Option<CString>; FIELD_COUNT]>,
+}
+
+/// Represents the result of a function.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OsuDbStatus {
+    Ok = 0,
+
+    /// A required pointer was NULL
+    NullArgument = 1,
+
+    /// The data isn't a valid `osu!.db` file
+    InvalidData = 2,
+
+    /// The beatmap index is past the end of the listing, or the field isn't one of [`OsuDbField`]
+    OutOfRange = 3,
+
+    /// Something unexpected went wrong inside the library
+    InternalError = 4,
+}
+
+/// Represents the string fields of a beatmap, which can be read with [`osudb_beatmap_string`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OsuDbField {
+    Artist = 0,
+    ArtistUnicode = 1,
+    Title = 2,
+    TitleUnicode = 3,
+    Creator = 4,
+    Difficulty = 5,
+    Source = 6,
+    Tags = 7,
+    Md5 = 8,
+    AudioFilename = 9,
+    BeatmapFilename = 10,
+
+    /// Name of the beatmap's folder, relative to the `Songs` folder
+    FolderName = 11,
+}
+
+/// The numeric fields of a beatmap, which can be read with [`osudb_beatmap_info`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OsuDbBeatmapInfo {
+    pub beatmapset_id: u32,
+    pub difficulty_id: u32,
+
+    /// Ranked status, using the values from the file (e.g. 4 for ranked, 7 for loved)
+    pub ranked_status: u8,
+
+    /// Gameplay mode (0 for osu!standard, 1 for osu!taiko, 2 for osu!catch and 3 for osu!mania)
+    pub gameplay_mode: u8,
+
+    pub hitcircle_count: u16,
+    pub slider_count: u16,
+    pub spinner_count: u16,
+    pub approach_rate: f32,
+    pub circle_size: f32,
+    pub hp_drain: f32,
+    pub overall_difficulty: f32,
+    pub slider_velocity: f64,
+
+    /// NoMod star rating in the beatmap's own gameplay mode, or NaN if it hasn't been calculated
+    pub star_rating: f64,
+
+    /// Drain time, in seconds
+    pub drain_time: u32,
+
+    /// Total time, in milliseconds
+    pub total_time: u32,
+
+    /// Time that the audio preview starts, in milliseconds
+    pub audio_preview_time: u32,
+
+    /// When the beatmap was last modified, as a Unix timestamp in seconds, or 0 if it's unknown
+    pub last_modified: i64,
+
+    /// When the beatmap was last played, as a Unix timestamp in seconds, or 0 if it's unknown
+    pub last_played: i64,
+
+    pub is_unplayed: bool,
+}
+
+/// Parses the contents of an `osu!.db` file.
+///
+/// On success, `*out` is set to a handle which must be freed with [`osudb_free`]. Otherwise, `*out` is set to NULL
+/// and the reason can be read with [`osudb_last_error_message`].
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, and `out` must be a valid pointer to write the handle to.
+#[no_mangle]
+pub unsafe extern "C" fn osudb_parse(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut OsuDbHandle,
+) -> OsuDbStatus {
+    if out.is_null() {
+        return fail(OsuDbStatus::NullArgument, "out is NULL");
+    }
+
+    *out = ptr::null_mut();
+
+    if data.is_null() {
+        return fail(OsuDbStatus::NullArgument, "data is NULL");
+    }
+
+    let data = std::slice::from_raw_parts(data, len);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| BeatmapListing::from_bytes(data)));
+
+    match result {
+        Ok(Ok(listing)) => {
+            *out = Box::into_raw(Box::new(OsuDbHandle::new(listing)));
+            OsuDbStatus::Ok
+        }
+        Ok(Err(e)) => fail(OsuDbStatus::InvalidData, &e.to_string()),
+        Err(_) => fail(OsuDbStatus::InternalError, "the parser panicked"),
+    }
+}
+
+/// Frees a handle from [`osudb_parse`], including the strings returned by its accessors. Does nothing if `handle` is
+/// NULL.
+///
+/// # Safety
+///
+/// `handle` must be NULL or a handle from [`osudb_parse`] which hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn osudb_free(handle: *mut OsuDbHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Gets a description of the most recent error on this thread, or NULL if there hasn't been one.
+///
+/// The message is owned by the library, and is valid until the next error on this thread.
+#[no_mangle]
+pub extern "C" fn osudb_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Gets the version of the file (e.g. 20211031), or 0 if `handle` is NULL.
+///
+/// # Safety
+///
+/// `handle` must be NULL or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn osudb_version(handle: *const OsuDbHandle) -> u32 {
+    handle.as_ref().map_or(0, |h| h.listing.version)
+}
+
+/// Gets the name of the player who owns the file, or NULL if it isn't set or `handle` is NULL.
+///
+/// The string is owned by the handle.
+///
+/// # Safety
+///
+/// `handle` must be NULL or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn osudb_player_name(handle: *const OsuDbHandle) -> *const c_char {
+    handle
+        .as_ref()
+        .and_then(|h| h.player_name.as_ref())
+        .map_or(ptr::null(), |name| name.as_ptr())
+}
+
+/// Gets the number of beatmaps in the file, or 0 if `handle` is NULL.
+///
+/// # Safety
+///
+/// `handle` must be NULL or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn osudb_beatmap_count(handle: *const OsuDbHandle) -> usize {
+    handle.as_ref().map_or(0, |h| h.listing.beatmaps.len())
+}
+
+/// Reads the numeric fields of the beatmap at `index` into `*out`.
+///
+/// # Safety
+///
+/// `handle` must be NULL or a valid handle, and `out` must be NULL or a valid pointer to write to.
+#[no_mangle]
+pub unsafe extern "C" fn osudb_beatmap_info(
+    handle: *const OsuDbHandle,
+    index: usize,
+    out: *mut OsuDbBeatmapInfo,
+) -> OsuDbStatus {
+    let (Some(handle), Some(out)) = (handle.as_ref(), out.as_mut()) else {
+        return fail(OsuDbStatus::NullArgument, "handle or out is NULL");
+    };
+
+    match handle.listing.beatmaps.get(index) {
+        Some(beatmap) => {
+            *out = OsuDbBeatmapInfo::from(beatmap);
+            OsuDbStatus::Ok
+        }
+        None => out_of_range(index, handle),
+    }
+}
+
+/// Reads a string field (one of [`OsuDbField`]) of the beatmap at `index`, setting `*out` to the string, or to NULL
+/// if the beatmap doesn't have a value for the field.
+///
+/// The string is owned by the handle.
+///
+/// # Safety
+///
+/// `handle` must be NULL or a valid handle, and `out` must be NULL or a valid pointer to write to.
+#[no_mangle]
+pub unsafe extern "C" fn osudb_beatmap_string(
+    handle: *const OsuDbHandle,
+    index: usize,
+    field: u32,
+    out: *mut *const c_char,
+) -> OsuDbStatus {
+    let (Some(handle), Some(out)) = (handle.as_ref(), out.as_mut()) else {
+        return fail(OsuDbStatus::NullArgument, "handle or out is NULL");
+    };
+
+    *out = ptr::null();
+
+    let Some(strings) = handle.strings.get(index) else {
+        return out_of_range(index, handle);
+    };
+
+    match strings.get(field as usize) {
+        Some(value) => {
+            *out = value.as_ref().map_or(ptr::null(), |value| value.as_ptr());
+            OsuDbStatus::Ok
+        }
+        None => fail(
+            OsuDbStatus::OutOfRange,
+            &format!("{} isn't a valid field", field),
+        ),
+    }
+}
+
+impl OsuDbHandle {
+    fn new(listing: BeatmapListing) -> Self {
+        let strings = listing
+            .beatmaps
+            .iter()
+            .map(|beatmap| {
+                [
+                    &beatmap.artist_name,
+                    &beatmap.artist_name_unicode,
+                    &beatmap.song_title,
+                    &beatmap.song_title_unicode,
+                    &beatmap.creator_name,
+                    &beatmap.difficulty,
+                    &beatmap.song_source,
+                    &beatmap.song_tags,
+                    &beatmap.md5,
+                    &beatmap.audio_filename,
+                    &beatmap.beatmap_filename,
+                    &beatmap.folder_name,
+                ]
+                .map(to_c_string)
+            })
+            .collect();
+
+        OsuDbHandle {
+            player_name: to_c_string(&listing.player_name),
+            strings,
+            listing,
+        }
+    }
+}
+
+impl From<&BeatmapEntry> for OsuDbBeatmapInfo {
+    fn from(beatmap: &BeatmapEntry) -> Self {
+        let timestamp = |date: OffsetDateTime| {
+            if date == WINDOWS_EPOCH {
+                0
+            } else {
+                date.unix_timestamp()
+            }
+        };
+
+        OsuDbBeatmapInfo {
+            beatmapset_id: beatmap.beatmap_id,
+            difficulty_id: beatmap.difficulty_id,
+            ranked_status: beatmap.ranked_status as u8,
+            gameplay_mode: beatmap.gameplay_mode as u8,
+            hitcircle_count: beatmap.hitcircle_count,
+            slider_count: beatmap.slider_count,
+            spinner_count: beatmap.spinner_count,
+            approach_rate: beatmap.approach_rate,
+            circle_size: beatmap.circle_size,
+            hp_drain: beatmap.hp_drain,
+            overall_difficulty: beatmap.overall_difficulty,
+            slider_velocity: beatmap.slider_velocity,
+            star_rating: beatmap.nomod_star_rating().unwrap_or(f64::NAN),
+            drain_time: beatmap.drain_time,
+            total_time: beatmap.total_time,
+            audio_preview_time: beatmap.audio_preview_time,
+            last_modified: timestamp(beatmap.last_modification_time),
+            last_played: timestamp(beatmap.last_played),
+            is_unplayed: beatmap.is_unplayed,
+        }
+    }
+}
+
+/// Converts a string to a C string, cutting it off at the first NUL character.
+fn to_c_string(value: &OsuString) -> Option<CString> {
+    let value = value.as_deref()?;
+    let end = value.find('\0').unwrap_or(value.len());
+
+    CString::new(&value[..end]).ok()
+}
+
+/// Records an error as the most recent error on this thread, and returns its status.
+fn fail(status: OsuDbStatus, message: &str) -> OsuDbStatus {
+    LAST_ERROR.with(|error| *error.borrow_mut() = CString::new(message.replace('\0', "")).ok());
+    status
+}
+
+fn out_of_range(index: usize, handle: &OsuDbHandle) -> OsuDbStatus {
+    fail(
+        OsuDbStatus::OutOfRange,
+        &format!(
+            "beatmap {} is out of range, since there are {} beatmaps",
+            index,
+            handle.listing.beatmaps.len()
+        ),
+    )
+}
+
+// These tests only use the C ABI, so that leaks and invalid accesses are caught when running them under Miri:
+// `cargo +nightly miri test -p osu-db-parser --features ffi ffi::`
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use super::*;
+
+    const OSU_DB: &[u8] = include_bytes!("../testdata/osu.db");
+
+    unsafe fn parse(data: &[u8]) -> (OsuDbStatus, *mut OsuDbHandle) {
+        let mut handle = ptr::null_mut();
+        let status = osudb_parse(data.as_ptr(), data.len(), &mut handle);
+        (status, handle)
+    }
+
+    unsafe fn string(
+        handle: *const OsuDbHandle,
+        index: usize,
+        field: OsuDbField,
+    ) -> Option<&'static str> {
+        let mut value = ptr::null();
+        assert_eq!(
+            osudb_beatmap_string(handle, index, field as u32, &mut value),
+            OsuDbStatus::Ok
+        );
+
+        (!value.is_null()).then(|| CStr::from_ptr(value).to_str().unwrap())
+    }
+
+    #[test]
+    fn fixture_is_read() {
+        unsafe {
+            let (status, handle) = parse(OSU_DB);
+            assert_eq!(status, OsuDbStatus::Ok);
+
+            assert_eq!(osudb_version(handle), 20211031);
+            assert_eq!(
+                CStr::from_ptr(osudb_player_name(handle)).to_str(),
+                Ok("Player")
+            );
+            assert_eq!(osudb_beatmap_count(handle), 2);
+
+            assert_eq!(string(handle, 0, OsuDbField::Artist), Some("Camellia"));
+            assert_eq!(
+                string(handle, 0, OsuDbField::ArtistUnicode),
+                Some("かめりあ")
+            );
+            assert_eq!(string(handle, 0, OsuDbField::Tags), None);
+            assert_eq!(string(handle, 1, OsuDbField::Title), None);
+
+            let mut info = OsuDbBeatmapInfo::default();
+            assert_eq!(osudb_beatmap_info(handle, 0, &mut info), OsuDbStatus::Ok);
+            assert_eq!(info.ranked_status, 7);
+            assert_eq!(info.hitcircle_count, 500);
+            assert_eq!(info.last_modified, 1690558220);
+            assert_eq!(info.last_played, 0);
+            assert!(info.star_rating.is_nan());
+
+            osudb_free(handle);
+        }
+    }
+
+    #[test]
+    fn invalid_data_is_rejected() {
+        unsafe {
+            let (status, handle) = parse(b"not a database");
+            assert_eq!(status, OsuDbStatus::InvalidData);
+            assert!(handle.is_null());
+            assert!(!osudb_last_error_message().is_null());
+
+            osudb_free(handle);
+        }
+    }
+
+    #[test]
+    fn out_of_range_accesses_are_rejected() {
+        unsafe {
+            let (_, handle) = parse(OSU_DB);
+            let mut info = OsuDbBeatmapInfo::default();
+            let mut value = ptr::null();
+
+            assert_eq!(
+                osudb_beatmap_info(handle, 2, &mut info),
+                OsuDbStatus::OutOfRange
+            );
+            assert_eq!(
+                osudb_beatmap_string(handle, 0, FIELD_COUNT as u32, &mut value),
+                OsuDbStatus::OutOfRange
+            );
+            assert!(value.is_null());
+
+            let message = CStr::from_ptr(osudb_last_error_message()).to_str().unwrap();
+            assert_eq!(message, "12 isn't a valid field");
+
+            osudb_free(handle);
+        }
+    }
+
+    #[test]
+    fn null_arguments_are_rejected() {
+        unsafe {
+            let mut handle = ptr::null_mut();
+            assert_eq!(
+                osudb_parse(ptr::null(), 0, &mut handle),
+                OsuDbStatus::NullArgument
+            );
+            assert_eq!(
+                osudb_parse(OSU_DB.as_ptr(), OSU_DB.len(), ptr::null_mut()),
+                OsuDbStatus::NullArgument
+            );
+            assert_eq!(
+                osudb_beatmap_info(ptr::null(), 0, &mut OsuDbBeatmapInfo::default()),
+                OsuDbStatus::NullArgument
+            );
+            assert_eq!(osudb_beatmap_count(ptr::null()), 0);
+            assert!(osudb_player_name(ptr::null()).is_null());
+        }
+    }
+}
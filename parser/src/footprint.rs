@@ -0,0 +1,169 @@
+//! Estimating how much memory parsed databases use.
+//!
+//! Estimates are the size of each value plus the heap memory it owns, i.e. the capacity of its strings and lists. The
+//! allocator's own overhead isn't known, so the actual memory used is somewhat higher.
+
+use std::mem::size_of;
+
+use crate::{
+    beatmaps::{BeatmapEntry, BeatmapListing, StarRating, TimingPoint},
+    collections::{Collection, CollectionListing},
+    scores::{BeatmapScores, LifebarGraph, ScoreListing, ScoreReplay},
+};
+
+/// A value whose memory usage can be estimated.
+pub trait Footprint {
+    /// Estimates the heap memory owned by the value in bytes, not including the value itself.
+    fn heap_size(&self) -> usize;
+
+    /// Estimates the memory used by the value in bytes, including the heap memory it owns.
+    fn footprint(&self) -> usize
+    where
+        Self: Sized,
+    {
+        size_of::<Self>() + self.heap_size()
+    }
+}
+
+impl Footprint for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: Footprint> Footprint for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, Footprint::heap_size)
+    }
+}
+
+impl<T: Footprint> Footprint for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(Footprint::heap_size).sum::<usize>()
+    }
+}
+
+/// Implements [`Footprint`] for types which don't own any heap memory.
+macro_rules! no_heap {
+    ($($ty:ty),*) => {
+        $(
+            impl Footprint for $ty {
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+no_heap!(u8, (u32, f32), StarRating, TimingPoint);
+
+impl Footprint for BeatmapListing {
+    fn heap_size(&self) -> usize {
+        self.player_name.heap_size() + self.beatmaps.heap_size()
+    }
+}
+
+impl Footprint for BeatmapEntry {
+    fn heap_size(&self) -> usize {
+        let strings = [
+            &self.artist_name,
+            &self.artist_name_unicode,
+            &self.song_title,
+            &self.song_title_unicode,
+            &self.creator_name,
+            &self.difficulty,
+            &self.audio_filename,
+            &self.md5,
+            &self.beatmap_filename,
+            &self.song_source,
+            &self.song_tags,
+            &self.font,
+            &self.folder_name,
+        ];
+
+        let star_ratings = [
+            &self.star_ratings_std,
+            &self.star_ratings_taiko,
+            &self.star_ratings_ctb,
+            &self.star_ratings_mania,
+        ];
+
+        strings.iter().map(|s| s.heap_size()).sum::<usize>()
+            + star_ratings.iter().map(|s| s.heap_size()).sum::<usize>()
+            + self.timing_points.heap_size()
+    }
+}
+
+impl Footprint for CollectionListing {
+    fn heap_size(&self) -> usize {
+        self.collections.heap_size()
+    }
+}
+
+impl Footprint for Collection {
+    fn heap_size(&self) -> usize {
+        self.name.heap_size() + self.beatmap_md5s.heap_size()
+    }
+}
+
+impl Footprint for ScoreListing {
+    fn heap_size(&self) -> usize {
+        self.beatmap_scores.heap_size()
+    }
+}
+
+impl Footprint for BeatmapScores {
+    fn heap_size(&self) -> usize {
+        self.md5.heap_size() + self.scores.heap_size()
+    }
+}
+
+impl Footprint for ScoreReplay {
+    fn heap_size(&self) -> usize {
+        self.beatmap_md5.heap_size()
+            + self.player_name.heap_size()
+            + self.replay_md5.heap_size()
+            + self.lifebar_graph.heap_size()
+            + self.replay_data.heap_size()
+    }
+}
+
+impl Footprint for LifebarGraph {
+    fn heap_size(&self) -> usize {
+        self.points.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_and_lists_are_counted() {
+        let collection = Collection {
+            name: Some(String::with_capacity(10)),
+            beatmap_md5s: vec![Some(String::from("abc")), None],
+        };
+
+        assert_eq!(
+            collection.heap_size(),
+            10 + 2 * size_of::<Option<String>>() + 3
+        );
+        assert_eq!(
+            collection.footprint(),
+            size_of::<Collection>() + collection.heap_size()
+        );
+    }
+
+    #[test]
+    fn empty_values_only_count_themselves() {
+        let listing = CollectionListing {
+            version: 20150203,
+            collections: Vec::new(),
+        };
+
+        assert_eq!(listing.heap_size(), 0);
+        assert_eq!(listing.footprint(), size_of::<CollectionListing>());
+    }
+}
@@ -0,0 +1,493 @@
+//! Human-friendly formatting of the dates, times and durations stored in osu!'s databases.
+//!
+//! osu! stores a missing date (e.g. the last time an unplayed beatmap was played) as the start of the Windows epoch
+//! ([`WINDOWS_EPOCH`]) rather than leaving it out. Every function here shows these as [`NEVER`], as well as `None` for
+//! dates that are already optional.
+//!
+//! Some dates are stored as UTC and others as local time (see [`TimestampKind`]). [`TimeZone::zoned`] finds the moment a
+//! stored date refers to in the user's time zone, which [`local_date`] and [`local_date_time`] show.
+
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset, Weekday};
+
+use crate::common::{TimestampKind, WINDOWS_EPOCH};
+
+/// Text shown for a date that's missing.
+pub const NEVER: &str = "never";
+
+/// Seconds in each unit used by [`relative`], largest first. Months and years are approximate.
+const UNITS: [(&str, i64); 5] = [
+    ("year", 365 * 86400),
+    ("month", 30 * 86400),
+    ("day", 86400),
+    ("hour", 3600),
+    ("minute", 60),
+];
+
+/// Represents the daylight saving rules that a [`TimeZone`] follows, which move its clocks forward an hour for part of
+/// the year. Only the current rules are known, so dates from years with other rules may be an hour out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DaylightSaving {
+    /// The offset is the same all year
+    #[default]
+    None,
+
+    /// From 01:00 UTC on the last Sunday of March to 01:00 UTC on the last Sunday of October, as in the EU and UK
+    Europe,
+
+    /// From 02:00 local time on the second Sunday of March to 02:00 local time on the first Sunday of November, as in
+    /// most of the US and Canada
+    NorthAmerica,
+}
+
+/// Represents a time zone that dates are shown in: an offset from UTC, and whether clocks go forward an hour from it
+/// for part of the year.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimeZone {
+    /// Offset outside of daylight saving time
+    pub offset: UtcOffset,
+    pub daylight_saving: DaylightSaving,
+}
+
+impl Default for TimeZone {
+    fn default() -> Self {
+        TimeZone::UTC
+    }
+}
+
+impl From<UtcOffset> for TimeZone {
+    fn from(offset: UtcOffset) -> Self {
+        TimeZone::fixed(offset)
+    }
+}
+
+impl TimeZone {
+    pub const UTC: TimeZone = TimeZone::fixed(UtcOffset::UTC);
+
+    /// Creates a time zone which is always `offset` ahead of UTC.
+    pub const fn fixed(offset: UtcOffset) -> TimeZone {
+        TimeZone {
+            offset,
+            daylight_saving: DaylightSaving::None,
+        }
+    }
+
+    /// Gets the offset from UTC at a moment, which is an hour more than [`TimeZone::offset`] during daylight saving
+    /// time.
+    pub fn offset_at(&self, moment: OffsetDateTime) -> UtcOffset {
+        let daylight = self.daylight_offset();
+
+        let in_daylight_saving = daylight.is_some()
+            && moment
+                .checked_to_offset(self.offset)
+                .and_then(|local| self.daylight_saving_period(local.year()))
+                .is_some_and(|(start, end)| start <= moment && moment < end);
+
+        match daylight {
+            Some(daylight) if in_daylight_saving => daylight,
+            _ => self.offset,
+        }
+    }
+
+    /// Finds the moment that a local time in this time zone refers to.
+    ///
+    /// Local times that are skipped when clocks go forward are taken as standard time, so they're shown an hour later,
+    /// and those that happen twice when clocks go back are taken as the first of the two.
+    pub fn local_to_utc(&self, local: PrimitiveDateTime) -> Option<OffsetDateTime> {
+        if let Some(daylight) = self.daylight_offset() {
+            let moment = local.assume_offset(daylight);
+
+            if self.offset_at(moment) == daylight {
+                return moment.checked_to_offset(UtcOffset::UTC);
+            }
+        }
+
+        local
+            .assume_offset(self.offset)
+            .checked_to_offset(UtcOffset::UTC)
+    }
+
+    /// Finds the moment that a stored date refers to, with this time zone's offset at that moment, or `None` if it
+    /// can't be represented (e.g. the sentinel dates at the end of year 9999 in time zones ahead of UTC).
+    ///
+    /// UTC dates are moved to this time zone, while local dates keep their wall clock time and are taken to be in it.
+    /// The stored date itself isn't changed, so it's still written back with the same ticks.
+    pub fn zoned(&self, stored: OffsetDateTime, kind: TimestampKind) -> Option<OffsetDateTime> {
+        let moment = match kind {
+            TimestampKind::Utc => stored,
+            TimestampKind::Local => {
+                let stored = stored.checked_to_offset(UtcOffset::UTC)?;
+                self.local_to_utc(PrimitiveDateTime::new(stored.date(), stored.time()))?
+            }
+        };
+
+        moment.checked_to_offset(self.offset_at(moment))
+    }
+
+    /// Gets the offset during daylight saving time, if this time zone has it.
+    fn daylight_offset(&self) -> Option<UtcOffset> {
+        match self.daylight_saving {
+            DaylightSaving::None => None,
+            _ => UtcOffset::from_whole_seconds(self.offset.whole_seconds() + 3600).ok(),
+        }
+    }
+
+    /// Gets when daylight saving time starts and ends in a year, as moments in UTC.
+    fn daylight_saving_period(&self, year: i32) -> Option<(OffsetDateTime, OffsetDateTime)> {
+        let sunday = |month, after: u8, n: u8| {
+            Date::from_calendar_date(year, month, after)
+                .ok()
+                .map(|date| date.nth_next_occurrence(Weekday::Sunday, n))
+        };
+        let at = |date: Date, hour, offset| {
+            Some(
+                date.with_time(Time::from_hms(hour, 0, 0).ok()?)
+                    .assume_offset(offset),
+            )
+        };
+
+        match self.daylight_saving {
+            DaylightSaving::None => None,
+            DaylightSaving::Europe => {
+                // The last Sunday of the month is the first one after the 24th in both March and October
+                let start = at(sunday(Month::March, 24, 1)?, 1, UtcOffset::UTC)?;
+                let end = at(sunday(Month::October, 24, 1)?, 1, UtcOffset::UTC)?;
+                Some((start, end))
+            }
+            DaylightSaving::NorthAmerica => {
+                // Counted from the last day of February, so that a Sunday on the 1st of March is the first one
+                let start = Date::from_calendar_date(year, Month::March, 1)
+                    .ok()?
+                    .previous_day()?
+                    .nth_next_occurrence(Weekday::Sunday, 2);
+                let end = sunday(Month::October, 31, 1)?;
+
+                Some((
+                    at(start, 2, self.offset)?,
+                    at(end, 2, self.daylight_offset()?)?,
+                ))
+            }
+        }
+    }
+}
+
+/// Gets a date, or `None` if it's missing (i.e. the Windows epoch).
+pub fn known(datetime: impl Into<Option<OffsetDateTime>>) -> Option<OffsetDateTime> {
+    datetime
+        .into()
+        .filter(|&datetime| datetime != WINDOWS_EPOCH)
+}
+
+/// Formats how long before `now` a date was, e.g. `just now`, `3 days ago` or `2 years ago`.
+///
+/// Only the largest whole unit is shown, so 47 hours is `1 day ago`. Dates less than a minute before `now` (or after
+/// it, e.g. due to a wrong clock) are `just now`.
+pub fn relative(datetime: impl Into<Option<OffsetDateTime>>, now: OffsetDateTime) -> String {
+    let Some(datetime) = known(datetime) else {
+        return NEVER.to_string();
+    };
+
+    let seconds = (now - datetime).whole_seconds();
+
+    UNITS
+        .iter()
+        .find(|(_, length)| seconds >= *length)
+        .map(|(unit, length)| match seconds / length {
+            1 => format!("1 {} ago", unit),
+            count => format!("{} {}s ago", count, unit),
+        })
+        .unwrap_or_else(|| "just now".to_string())
+}
+
+/// Formats the date part of a date in a time zone, e.g. `2024-03-25`.
+pub fn short_date(datetime: impl Into<Option<OffsetDateTime>>, offset: UtcOffset) -> String {
+    let Some(datetime) = known(datetime) else {
+        return NEVER.to_string();
+    };
+
+    let datetime = datetime.to_offset(offset);
+    format!(
+        "{}-{:02}-{:02}",
+        datetime.year(),
+        datetime.month() as u8,
+        datetime.day()
+    )
+}
+
+/// Formats a date and time in a time zone, e.g. `2024-03-25 18:30:00`.
+pub fn date_time(datetime: impl Into<Option<OffsetDateTime>>, offset: UtcOffset) -> String {
+    let Some(datetime) = known(datetime) else {
+        return NEVER.to_string();
+    };
+
+    let datetime = datetime.to_offset(offset);
+    format!(
+        "{} {:02}:{:02}:{:02}",
+        short_date(datetime, offset),
+        datetime.hour(),
+        datetime.minute(),
+        datetime.second()
+    )
+}
+
+/// Formats the date part of a stored date in a time zone, e.g. `2024-03-25` (see [`TimeZone::zoned`]).
+pub fn local_date(stored: OffsetDateTime, kind: TimestampKind, zone: &TimeZone) -> String {
+    match known(stored).and_then(|stored| zone.zoned(stored, kind)) {
+        Some(zoned) => short_date(zoned, zoned.offset()),
+        None => NEVER.to_string(),
+    }
+}
+
+/// Formats a stored date and time in a time zone, e.g. `2024-03-25 18:30:00` (see [`TimeZone::zoned`]).
+pub fn local_date_time(stored: OffsetDateTime, kind: TimestampKind, zone: &TimeZone) -> String {
+    match known(stored).and_then(|stored| zone.zoned(stored, kind)) {
+        Some(zoned) => date_time(zoned, zoned.offset()),
+        None => NEVER.to_string(),
+    }
+}
+
+/// Formats a duration as minutes and seconds, e.g. `3:05`. Minutes aren't carried over into hours, so long durations
+/// are e.g. `95:30`, and any fraction of a second is dropped.
+pub fn duration_mmss(duration: Duration) -> String {
+    let seconds = duration.whole_seconds();
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.unsigned_abs();
+
+    format!("{}{}:{:02}", sign, seconds / 60, seconds % 60)
+}
+
+/// Formats a duration as hours, minutes and seconds, e.g. `2:03:05`, for durations which may be too long for
+/// [`duration_mmss`]. Hours aren't carried over into days, and any fraction of a second is dropped.
+pub fn duration_hmmss(duration: Duration) -> String {
+    let seconds = duration.whole_seconds();
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.unsigned_abs();
+
+    format!(
+        "{}{}:{:02}:{:02}",
+        sign,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{datetime, offset};
+
+    use super::*;
+
+    const NOW: OffsetDateTime = datetime!(2024-03-25 18:30:00 UTC);
+
+    fn ago(duration: Duration) -> String {
+        relative(NOW - duration, NOW)
+    }
+
+    #[test]
+    fn relative_dates_are_bucketed() {
+        assert_eq!(ago(Duration::ZERO), "just now");
+        assert_eq!(ago(Duration::seconds(59)), "just now");
+        assert_eq!(ago(Duration::seconds(-30)), "just now");
+        assert_eq!(ago(Duration::seconds(60)), "1 minute ago");
+        assert_eq!(ago(Duration::seconds(119)), "1 minute ago");
+        assert_eq!(ago(Duration::minutes(59)), "59 minutes ago");
+        assert_eq!(ago(Duration::minutes(60)), "1 hour ago");
+        assert_eq!(ago(Duration::hours(23)), "23 hours ago");
+        assert_eq!(ago(Duration::hours(24)), "1 day ago");
+        assert_eq!(ago(Duration::hours(47)), "1 day ago");
+        assert_eq!(ago(Duration::days(3)), "3 days ago");
+        assert_eq!(ago(Duration::days(29)), "29 days ago");
+        assert_eq!(ago(Duration::days(30)), "1 month ago");
+        assert_eq!(ago(Duration::days(364)), "12 months ago");
+        assert_eq!(ago(Duration::days(365)), "1 year ago");
+        assert_eq!(ago(Duration::days(800)), "2 years ago");
+    }
+
+    #[test]
+    fn missing_dates_are_never() {
+        assert_eq!(relative(WINDOWS_EPOCH, NOW), NEVER);
+        assert_eq!(relative(None, NOW), NEVER);
+        assert_eq!(short_date(WINDOWS_EPOCH, UtcOffset::UTC), NEVER);
+        assert_eq!(date_time(None, UtcOffset::UTC), NEVER);
+        assert_eq!(known(NOW), Some(NOW));
+    }
+
+    #[test]
+    fn dates_are_shown_in_the_time_zone() {
+        assert_eq!(short_date(NOW, UtcOffset::UTC), "2024-03-25");
+        assert_eq!(short_date(NOW, offset!(+9)), "2024-03-26");
+        assert_eq!(date_time(NOW, offset!(+9)), "2024-03-26 03:30:00");
+        assert_eq!(date_time(Some(NOW), offset!(-5:30)), "2024-03-25 13:00:00");
+    }
+
+    /// 2024-03-31 00:30:00, half an hour before clocks go forward in Europe.
+    const TICKS: u64 = 638_474_418_000_000_000;
+
+    const LONDON: TimeZone = TimeZone {
+        offset: UtcOffset::UTC,
+        daylight_saving: DaylightSaving::Europe,
+    };
+
+    const NEW_YORK: TimeZone = TimeZone {
+        offset: offset!(-5),
+        daylight_saving: DaylightSaving::NorthAmerica,
+    };
+
+    fn stored(ticks: u64) -> OffsetDateTime {
+        crate::binary::parse_windows_datetime::<()>(&ticks.to_le_bytes())
+            .unwrap()
+            .1
+    }
+
+    /// Gets the stored date an hour after [`TICKS`], which is after clocks go forward in Europe.
+    fn hour_later() -> OffsetDateTime {
+        stored(TICKS + 3600 * 10_000_000)
+    }
+
+    #[test]
+    fn utc_dates_are_moved_to_the_time_zone() {
+        let tokyo = TimeZone::fixed(offset!(+9));
+        let utc = TimestampKind::Utc;
+
+        assert_eq!(stored(TICKS), datetime!(2024-03-31 00:30 UTC));
+        assert_eq!(
+            local_date_time(stored(TICKS), utc, &LONDON),
+            "2024-03-31 00:30:00"
+        );
+        assert_eq!(
+            local_date_time(hour_later(), utc, &LONDON),
+            "2024-03-31 02:30:00"
+        );
+        assert_eq!(
+            local_date_time(stored(TICKS), utc, &tokyo),
+            "2024-03-31 09:30:00"
+        );
+        assert_eq!(
+            local_date_time(hour_later(), utc, &tokyo),
+            "2024-03-31 10:30:00"
+        );
+        assert_eq!(local_date(stored(TICKS), utc, &NEW_YORK), "2024-03-30");
+
+        assert_eq!(
+            LONDON.zoned(hour_later(), utc).map(|date| date.offset()),
+            Some(offset!(+1))
+        );
+    }
+
+    #[test]
+    fn local_dates_keep_their_wall_clock_time() {
+        let tokyo = TimeZone::fixed(offset!(+9));
+        let local = TimestampKind::Local;
+
+        assert_eq!(
+            local_date_time(stored(TICKS), local, &LONDON),
+            "2024-03-31 00:30:00"
+        );
+        assert_eq!(
+            local_date_time(stored(TICKS), local, &tokyo),
+            "2024-03-31 00:30:00"
+        );
+        assert_eq!(
+            tokyo.zoned(stored(TICKS), local),
+            Some(datetime!(2024-03-31 00:30 +9))
+        );
+
+        // 01:30 is skipped when clocks go forward, so it's taken as 01:30 GMT
+        assert_eq!(
+            local_date_time(hour_later(), local, &LONDON),
+            "2024-03-31 02:30:00"
+        );
+        assert_eq!(
+            LONDON.zoned(hour_later(), local),
+            Some(datetime!(2024-03-31 01:30 UTC).to_offset(offset!(+1)))
+        );
+
+        // 01:30 happens twice when clocks go back, and the first time is during daylight saving time
+        assert_eq!(
+            LONDON.zoned(datetime!(2024-10-27 01:30 UTC), local),
+            Some(datetime!(2024-10-27 01:30 +1))
+        );
+    }
+
+    #[test]
+    fn daylight_saving_starts_and_ends_on_the_rules_dates() {
+        let offset_at = |zone: &TimeZone, moment| zone.offset_at(moment);
+
+        assert_eq!(
+            offset_at(&LONDON, datetime!(2024-03-31 00:59 UTC)),
+            offset!(+0)
+        );
+        assert_eq!(
+            offset_at(&LONDON, datetime!(2024-03-31 01:00 UTC)),
+            offset!(+1)
+        );
+        assert_eq!(
+            offset_at(&LONDON, datetime!(2024-10-27 00:59 UTC)),
+            offset!(+1)
+        );
+        assert_eq!(
+            offset_at(&LONDON, datetime!(2024-10-27 01:00 UTC)),
+            offset!(+0)
+        );
+
+        // Clocks change at 02:00 local time, so 07:00 UTC in March and 06:00 UTC in November
+        assert_eq!(
+            offset_at(&NEW_YORK, datetime!(2024-03-10 06:59 UTC)),
+            offset!(-5)
+        );
+        assert_eq!(
+            offset_at(&NEW_YORK, datetime!(2024-03-10 07:00 UTC)),
+            offset!(-4)
+        );
+        assert_eq!(
+            offset_at(&NEW_YORK, datetime!(2024-11-03 05:59 UTC)),
+            offset!(-4)
+        );
+        assert_eq!(
+            offset_at(&NEW_YORK, datetime!(2024-11-03 06:00 UTC)),
+            offset!(-5)
+        );
+
+        // The second Sunday of March 2026 is the 8th, since the 1st is a Sunday
+        assert_eq!(
+            offset_at(&NEW_YORK, datetime!(2026-03-08 07:00 UTC)),
+            offset!(-4)
+        );
+        assert_eq!(
+            offset_at(&NEW_YORK, datetime!(2026-03-01 12:00 UTC)),
+            offset!(-5)
+        );
+    }
+
+    #[test]
+    fn unrepresentable_dates_are_never() {
+        let tokyo = TimeZone::fixed(offset!(+9));
+        let end = datetime!(9999-12-31 23:59:59 UTC);
+
+        assert_eq!(
+            local_date(WINDOWS_EPOCH, TimestampKind::Local, &tokyo),
+            NEVER
+        );
+        assert_eq!(local_date_time(end, TimestampKind::Utc, &tokyo), NEVER);
+        assert_eq!(local_date(end, TimestampKind::Local, &tokyo), "9999-12-31");
+    }
+
+    #[test]
+    fn durations_are_minutes_and_seconds() {
+        assert_eq!(duration_mmss(Duration::ZERO), "0:00");
+        assert_eq!(duration_mmss(Duration::seconds(185)), "3:05");
+        assert_eq!(duration_mmss(Duration::milliseconds(95_999)), "1:35");
+        assert_eq!(duration_mmss(Duration::minutes(95)), "95:00");
+        assert_eq!(duration_mmss(Duration::seconds(-65)), "-1:05");
+    }
+
+    #[test]
+    fn long_durations_are_hours_minutes_and_seconds() {
+        assert_eq!(duration_hmmss(Duration::ZERO), "0:00:00");
+        assert_eq!(duration_hmmss(Duration::seconds(185)), "0:03:05");
+        assert_eq!(duration_hmmss(Duration::seconds(7385)), "2:03:05");
+        assert_eq!(duration_hmmss(Duration::days(2)), "48:00:00");
+        assert_eq!(duration_hmmss(Duration::seconds(-3605)), "-1:00:05");
+    }
+}
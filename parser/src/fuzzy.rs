@@ -0,0 +1,309 @@
+//! Approximate matching of search text, so that typos (e.g. `fredom dive`) and small differences in romanisation still
+//! find the intended beatmap.
+//!
+//! Text is folded before it's compared: it's lowercased, and full-width Latin letters, digits and punctuation (common in
+//! Unicode titles) are replaced with their ASCII equivalents. Each word in the query is then scored against the closest
+//! word in the beatmap's artist, title, difficulty and creator, using a normalised Levenshtein distance.
+//!
+//! Folding every beatmap's text is the slowest part, so a [`FuzzyIndex`] does it once when a listing is loaded.
+
+use crate::beatmaps::BeatmapEntry;
+
+/// Minimum similarity (from 0 to 1) that every word in a query needs for a beatmap to match.
+pub const DEFAULT_THRESHOLD: f64 = 0.7;
+
+/// A search query which has been folded and split into words, ready to be scored against beatmaps.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FuzzyQuery {
+    words: Vec<Vec<char>>,
+}
+
+/// The folded words of each beatmap in a listing, for scoring queries against the whole listing quickly.
+#[derive(Clone, Debug, Default)]
+pub struct FuzzyIndex {
+    entries: Vec<Vec<Vec<char>>>,
+}
+
+impl FuzzyQuery {
+    /// Folds and splits free text terms (e.g. [`BeatmapFilter::text`](crate::query::BeatmapFilter::text)) into words.
+    pub fn new<S: AsRef<str>>(terms: &[S]) -> Self {
+        Self {
+            words: terms.iter().flat_map(|term| words(term.as_ref())).collect(),
+        }
+    }
+
+    /// Checks whether the query has no words, in which case it matches every beatmap.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+impl FuzzyIndex {
+    /// Folds the text of every beatmap in a listing.
+    pub fn new(beatmaps: &[BeatmapEntry]) -> Self {
+        Self {
+            entries: beatmaps.iter().map(beatmap_words).collect(),
+        }
+    }
+
+    /// Gets the number of beatmaps in the index.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether the index has no beatmaps.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Scores the beatmap at `index` against a query (see [`score_words`]). Returns `None` if the beatmap doesn't
+    /// match, or isn't in the index.
+    pub fn score(&self, index: usize, query: &FuzzyQuery, threshold: f64) -> Option<f64> {
+        score_words(self.entries.get(index)?, query, threshold)
+    }
+
+    /// Finds which of the `candidates` (indices into the listing) match a query, ranked from best to worst. Beatmaps
+    /// with the same score are kept in the order they were given.
+    pub fn rank(
+        &self,
+        candidates: impl IntoIterator<Item = usize>,
+        query: &FuzzyQuery,
+        threshold: f64,
+    ) -> Vec<usize> {
+        let mut scored = candidates
+            .into_iter()
+            .filter_map(|i| Some((i, self.score(i, query, threshold)?)))
+            .collect::<Vec<_>>();
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.into_iter().map(|(i, _)| i).collect()
+    }
+}
+
+/// Scores a single beatmap against a query, without an index (see [`score_words`]).
+pub fn score_beatmap(beatmap: &BeatmapEntry, query: &FuzzyQuery, threshold: f64) -> Option<f64> {
+    score_words(&beatmap_words(beatmap), query, threshold)
+}
+
+/// Scores a beatmap's folded words against a query.
+///
+/// Each word in the query is compared with the most similar of the beatmap's words. The beatmap only matches if every
+/// query word is at least `threshold` similar, and its score is the average similarity.
+fn score_words(words: &[Vec<char>], query: &FuzzyQuery, threshold: f64) -> Option<f64> {
+    if query.words.is_empty() {
+        return Some(1.0);
+    }
+
+    let mut total = 0.0;
+
+    for query_word in &query.words {
+        let best = words
+            .iter()
+            .map(|word| word_similarity(query_word, word))
+            .fold(0.0, f64::max);
+
+        if best < threshold {
+            return None;
+        }
+
+        total += best;
+    }
+
+    Some(total / query.words.len() as f64)
+}
+
+/// Folds text for comparison, lowercasing it and replacing full-width characters with their ASCII equivalents.
+pub fn fold(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            // Full-width forms of the printable ASCII characters
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            c => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Gets how similar a query word is to a word, from 0 (nothing in common) to 1 (the word contains the query word).
+/// Both are folded first.
+pub fn similarity(query_word: &str, word: &str) -> f64 {
+    let query_word = fold(query_word).chars().collect::<Vec<_>>();
+    let word = fold(word).chars().collect::<Vec<_>>();
+
+    word_similarity(&query_word, &word)
+}
+
+/// Folds text and splits it into words, on anything that isn't a letter or digit.
+fn words(text: &str) -> Vec<Vec<char>> {
+    fold(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.chars().collect())
+        .collect()
+}
+
+/// Gets the folded words in the fields of a beatmap that fuzzy search looks at.
+fn beatmap_words(beatmap: &BeatmapEntry) -> Vec<Vec<char>> {
+    let mut result = [
+        &beatmap.artist_name,
+        &beatmap.artist_name_unicode,
+        &beatmap.song_title,
+        &beatmap.song_title_unicode,
+        &beatmap.difficulty,
+        &beatmap.creator_name,
+    ]
+    .into_iter()
+    .filter_map(|field| field.as_deref())
+    .flat_map(words)
+    .collect::<Vec<_>>();
+
+    // The romanised and Unicode metadata are often the same
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+/// Gets the similarity of two folded words (see [`similarity`]).
+fn word_similarity(query_word: &[char], word: &[char]) -> f64 {
+    if query_word.is_empty() || word.windows(query_word.len()).any(|w| w == query_word) {
+        return 1.0;
+    }
+
+    let longest = query_word.len().max(word.len()) as f64;
+    1.0 - levenshtein(query_word, word) as f64 / longest
+}
+
+/// Counts the single character insertions, deletions and substitutions needed to turn `a` into `b`.
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, &ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beatmap(artist: &str, title: &str, difficulty: &str, creator: &str) -> BeatmapEntry {
+        BeatmapEntry {
+            artist_name: Some(artist.to_string()),
+            song_title: Some(title.to_string()),
+            difficulty: Some(difficulty.to_string()),
+            creator_name: Some(creator.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn listing() -> Vec<BeatmapEntry> {
+        vec![
+            beatmap("xi", "Blue Zenith", "FOUR DIMENSIONS", "Asphyxia"),
+            beatmap(
+                "Camellia",
+                "Exit This Earth's Atomosphere",
+                "Evolution",
+                "ProfessionalBox",
+            ),
+            beatmap("xi", "FREEDOM DiVE", "FOUR DIMENSIONS", "Nakagawa-Kanon"),
+            beatmap(
+                "DragonForce",
+                "Through the Fire and Flames",
+                "Legend",
+                "Ponoyoshi",
+            ),
+            beatmap("Frederic", "oddloop", "Insane", "Sotarks"),
+            beatmap("Halozy", "Freedam", "Hard", "Kite"),
+        ]
+    }
+
+    fn search(beatmaps: &[BeatmapEntry], query: &str) -> Vec<usize> {
+        let index = FuzzyIndex::new(beatmaps);
+        let query = FuzzyQuery::new(&[query]);
+        index.rank(0..beatmaps.len(), &query, DEFAULT_THRESHOLD)
+    }
+
+    #[test]
+    fn levenshtein_distance_is_counted() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+
+        assert_eq!(levenshtein(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(levenshtein(&chars(""), &chars("abc")), 3);
+        assert_eq!(levenshtein(&chars("fredom"), &chars("freedom")), 1);
+        assert_eq!(levenshtein(&chars("same"), &chars("same")), 0);
+    }
+
+    #[test]
+    fn text_is_case_and_width_folded() {
+        assert_eq!(fold("ＦＲＥＥＤＯＭ　ＤｉＶＥ"), "freedom dive");
+        assert_eq!(fold("Ｒｅ：ＺＥＲＯ！"), "re:zero!");
+        assert_eq!(fold("夜に駆ける"), "夜に駆ける");
+        assert_eq!(similarity("Freedom", "ＦＲＥＥＤＯＭ"), 1.0);
+    }
+
+    #[test]
+    fn typos_rank_the_intended_beatmap_first() {
+        let beatmaps = listing();
+
+        assert_eq!(search(&beatmaps, "fredom dive")[0], 2);
+        assert_eq!(search(&beatmaps, "freedom div")[0], 2);
+        assert_eq!(search(&beatmaps, "camelia atmosphere")[0], 1);
+        assert_eq!(search(&beatmaps, "dragonforse flames"), vec![3]);
+        assert_eq!(search(&beatmaps, "blue zenth"), vec![0]);
+    }
+
+    #[test]
+    fn closer_matches_rank_higher() {
+        let beatmaps = listing();
+
+        // "Freedom" and "Freedam" are one letter apart, so each query matches both, but the exact one first
+        assert_eq!(search(&beatmaps, "freedom"), vec![2, 5]);
+        assert_eq!(search(&beatmaps, "freedam"), vec![5, 2]);
+    }
+
+    #[test]
+    fn every_word_must_match() {
+        let beatmaps = listing();
+
+        assert!(search(&beatmaps, "freedom sotarks").is_empty());
+        assert!(search(&beatmaps, "completely unrelated").is_empty());
+        assert_eq!(search(&beatmaps, "xi dimensions").len(), 2);
+    }
+
+    #[test]
+    fn full_width_unicode_titles_are_matched() {
+        let mut beatmaps = listing();
+        beatmaps[2].song_title_unicode = Some("ＦＲＥＥＤＯＭ ＤｉＶＥ".to_string());
+        beatmaps[2].song_title = Some("Freedom".to_string());
+
+        assert_eq!(search(&beatmaps, "dive")[0], 2);
+        assert_eq!(search(&beatmaps, "ＤＩＶ")[0], 2);
+    }
+
+    #[test]
+    fn indexed_and_unindexed_scores_agree() {
+        let beatmaps = listing();
+        let index = FuzzyIndex::new(&beatmaps);
+        let query = FuzzyQuery::new(&["fredom", "dive"]);
+
+        for (i, beatmap) in beatmaps.iter().enumerate() {
+            assert_eq!(
+                index.score(i, &query, DEFAULT_THRESHOLD),
+                score_beatmap(beatmap, &query, DEFAULT_THRESHOLD)
+            );
+        }
+
+        assert_eq!(index.score(beatmaps.len(), &query, DEFAULT_THRESHOLD), None);
+    }
+}
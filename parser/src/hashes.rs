@@ -0,0 +1,601 @@
+//! Hashing the `.osu` files of a beatmap library, e.g. to check them against the MD5 hashes stored in `osu!.db` or to
+//! match beatmaps that have been modified on disk.
+//!
+//! Hashing every file in a large library takes a while, so hashes are kept in a [`HashCache`] keyed by each file's
+//! path, size and modification time, and only files which have changed since they were last hashed are read again.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use md5::{Digest, Md5 as Hasher};
+
+use crate::{beatmaps::BeatmapEntry, error::Error, paths};
+
+/// Number of files hashed between progress reports when hashing in parallel.
+const FILES_PER_BATCH: usize = 256;
+
+/// Represents the MD5 hash of a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Md5(pub [u8; 16]);
+
+/// Represents a file that has been hashed, and what the file looked like at the time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct CachedHash {
+    size: u64,
+
+    /// Modification time, as the time since the Unix epoch
+    modified: Duration,
+
+    md5: Md5,
+}
+
+/// Size and modification time of a file (if it's after the Unix epoch), which tell whether it has changed.
+type FileKey = (u64, Option<Duration>);
+
+/// Represents how many files were found in a [`HashCache`] the last time it was used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HashStats {
+    /// Files which hadn't changed since they were last hashed
+    pub hits: usize,
+
+    /// Files which were hashed, because they're new or have changed
+    pub misses: usize,
+
+    /// Files which don't exist or couldn't be read
+    pub missing: usize,
+}
+
+/// Remembers the MD5 hashes of files, along with each file's size and modification time, so that files are only hashed
+/// again once they've changed.
+///
+/// The cache can be saved to a file and loaded again later, e.g. in the application's data folder.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HashCache {
+    entries: HashMap<PathBuf, CachedHash>,
+    stats: HashStats,
+}
+
+impl Md5 {
+    /// Hashes some bytes.
+    pub fn of(bytes: &[u8]) -> Md5 {
+        Md5(Hasher::digest(bytes).into())
+    }
+
+    /// Hashes the contents of a file.
+    pub fn of_file<P: AsRef<Path>>(path: P) -> io::Result<Md5> {
+        let mut hasher = Hasher::new();
+        io::copy(&mut File::open(path)?, &mut hasher)?;
+        Ok(Md5(hasher.finalize().into()))
+    }
+
+    /// Checks whether this is the same hash as a hexadecimal one, e.g. [`BeatmapEntry::md5`]. Case is ignored.
+    pub fn matches(&self, hex: &str) -> bool {
+        hex.parse::<Md5>().is_ok_and(|md5| md5 == *self)
+    }
+}
+
+impl fmt::Display for Md5 {
+    /// Formats the hash as 32 lowercase hexadecimal characters, the same as osu! stores it.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Md5 {
+    type Err = ();
+
+    /// Parses a hash from 32 hexadecimal characters.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 32 || !s.is_ascii() {
+            return Err(());
+        }
+
+        let mut bytes = [0; 16];
+
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).map_err(|_| ())?;
+        }
+
+        Ok(Md5(bytes))
+    }
+}
+
+/// Hashes are serialized as hexadecimal strings, the same as they're shown.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Md5 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Md5 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = <String as serde::Deserialize>::deserialize(deserializer)?;
+
+        hex.parse()
+            .map_err(|_| serde::de::Error::custom(format!("invalid MD5 hash '{}'", hex)))
+    }
+}
+
+impl HashCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a cache saved with [`HashCache::save`]. A file that doesn't exist is treated as an empty cache, and lines
+    /// that can't be read are skipped.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<HashCache> {
+        match File::open(path) {
+            Ok(file) => HashCache::read_from(BufReader::new(file)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HashCache::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Saves the cache to a file, creating its folder if needed.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Reads a cache written by [`HashCache::write_to`].
+    pub fn read_from<R: BufRead>(reader: R) -> io::Result<HashCache> {
+        let mut cache = HashCache::new();
+
+        for line in reader.lines() {
+            if let Some((path, entry)) = parse_line(&line?) {
+                cache.entries.insert(path, entry);
+            }
+        }
+
+        Ok(cache)
+    }
+
+    /// Writes the cache as text, with one file on each line.
+    ///
+    /// Each line has the hash, size, and modification time (as seconds and nanoseconds since the Unix epoch) separated
+    /// by tabs, followed by the path, which comes last so that it can contain tabs itself.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (path, entry) in entries {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}",
+                entry.md5,
+                entry.size,
+                entry.modified.as_secs(),
+                entry.modified.subsec_nanos(),
+                path.display()
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the number of files in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether the cache has no files.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets how many files were found in the cache the last time it was used with [`hash_osu_files`].
+    pub fn stats(&self) -> HashStats {
+        self.stats
+    }
+
+    /// Forgets every file.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Gets the hash of a file, from the cache if it hasn't changed since it was last hashed, otherwise by reading it.
+    /// Returns `None` if the file doesn't exist or can't be read.
+    pub fn hash<P: AsRef<Path>>(&mut self, path: P) -> Option<Md5> {
+        match self.lookup(path.as_ref()) {
+            Lookup::Hit(md5) => Some(md5),
+            Lookup::Miss(key) => self.insert(path.as_ref(), key, Md5::of_file(path.as_ref())),
+            Lookup::Missing => None,
+        }
+    }
+
+    /// Checks whether a file's hash is in the cache, returning its size and modification time if it needs hashing.
+    fn lookup(&mut self, path: &Path) -> Lookup {
+        let Some(key) = file_key(path) else {
+            self.entries.remove(path);
+            return Lookup::Missing;
+        };
+
+        match self.entries.get(path) {
+            Some(entry) if (entry.size, Some(entry.modified)) == key => Lookup::Hit(entry.md5),
+            _ => Lookup::Miss(key),
+        }
+    }
+
+    /// Saves the hash of a file that was just read, or forgets the file if it couldn't be read.
+    fn insert(
+        &mut self,
+        path: &Path,
+        (size, modified): FileKey,
+        md5: io::Result<Md5>,
+    ) -> Option<Md5> {
+        let Ok(md5) = md5 else {
+            self.entries.remove(path);
+            return None;
+        };
+
+        // Files with modification times before 1970 can't be stored, so they're always hashed
+        match modified {
+            Some(modified) => {
+                let entry = CachedHash {
+                    size,
+                    modified,
+                    md5,
+                };
+                self.entries.insert(path.to_path_buf(), entry);
+            }
+            None => {
+                self.entries.remove(path);
+            }
+        }
+
+        Some(md5)
+    }
+}
+
+/// Represents whether a file's hash was found in the cache.
+enum Lookup {
+    Hit(Md5),
+
+    /// The file needs to be hashed, and has this size and modification time
+    Miss(FileKey),
+
+    Missing,
+}
+
+/// Hashes the `.osu` file of each beatmap in a `Songs` folder (see [`paths::songs_dir_guess`]), returning the index
+/// of each beatmap whose file was found along with its hash, in listing order.
+///
+/// Only files which aren't in `cache`, or have changed since they were cached, are read; afterwards,
+/// [`HashCache::stats`] gives the number of files that were hashed. Beatmaps whose file doesn't exist or can't be read
+/// are left out, and forgotten by the cache. If `parallel` is set, the files are hashed on several threads.
+///
+/// `progress` is called with the number of beatmaps done so far and the total number of beatmaps. Returning `false`
+/// cancels hashing, in which case [`Error::Cancelled`] is returned and the cache keeps the files hashed so far.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(beatmaps = beatmaps.len(), parallel))
+)]
+pub fn hash_osu_files<P, F>(
+    songs_dir: P,
+    beatmaps: &[BeatmapEntry],
+    cache: &mut HashCache,
+    parallel: bool,
+    mut progress: F,
+) -> Result<Vec<(usize, Md5)>, Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(usize, usize) -> bool,
+{
+    let total = beatmaps.len();
+    let mut hashes = Vec::new();
+    let mut changed = Vec::new();
+    let mut done = 0;
+    cache.stats = HashStats::default();
+
+    // Checking which files have changed is much quicker than hashing them, so that's done first
+    for (index, beatmap) in beatmaps.iter().enumerate() {
+        let path = paths::beatmap_file_in_songs(&songs_dir, beatmap);
+
+        match path.as_deref().map(|path| cache.lookup(path)) {
+            Some(Lookup::Hit(md5)) => {
+                hashes.push((index, md5));
+                cache.stats.hits += 1;
+                done += 1;
+            }
+            Some(Lookup::Miss(key)) => changed.push((index, path.unwrap_or_default(), key)),
+
+            // Beatmaps without a file name can't be hashed either
+            Some(Lookup::Missing) | None => {
+                cache.stats.missing += 1;
+                done += 1;
+            }
+        }
+    }
+
+    if !progress(done, total) {
+        return Err(Error::Cancelled);
+    }
+
+    let threads = if parallel { thread_count() } else { 1 };
+    let batch_size = if threads > 1 { FILES_PER_BATCH } else { 1 };
+
+    for batch in changed.chunks(batch_size) {
+        let results = hash_batch(batch, threads);
+
+        for ((index, path, key), md5) in batch.iter().zip(results) {
+            match cache.insert(path, *key, md5) {
+                Some(md5) => {
+                    hashes.push((*index, md5));
+                    cache.stats.misses += 1;
+                }
+                None => cache.stats.missing += 1,
+            }
+        }
+
+        done += batch.len();
+
+        if !progress(done, total) {
+            return Err(Error::Cancelled);
+        }
+    }
+
+    hashes.sort_unstable_by_key(|(index, _)| *index);
+    Ok(hashes)
+}
+
+/// Hashes a batch of files, splitting them between `threads` threads, and returns the results in the same order.
+fn hash_batch(files: &[(usize, PathBuf, FileKey)], threads: usize) -> Vec<io::Result<Md5>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if threads > 1 && files.len() > 1 {
+        let chunk_size = files.len().div_ceil(threads);
+
+        return std::thread::scope(|scope| {
+            let workers = files
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|(_, path, _)| Md5::of_file(path))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            // Joining the workers in the order they were spawned keeps the results in order
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|_| vec![Err(io::Error::other("Hashing thread panicked"))])
+                })
+                .collect()
+        });
+    }
+
+    files
+        .iter()
+        .map(|(_, path, _)| Md5::of_file(path))
+        .collect()
+}
+
+/// Gets the number of threads to hash files on.
+fn thread_count() -> usize {
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    #[cfg(target_arch = "wasm32")]
+    1
+}
+
+/// Gets a file's size and modification time (if it's after the Unix epoch), or `None` if it doesn't exist.
+fn file_key(path: &Path) -> Option<FileKey> {
+    let metadata = std::fs::metadata(path).ok().filter(|m| m.is_file())?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+
+    Some((metadata.len(), modified))
+}
+
+/// Parses one line of a saved cache.
+fn parse_line(line: &str) -> Option<(PathBuf, CachedHash)> {
+    let mut parts = line.splitn(5, '\t');
+    let md5 = parts.next()?.parse().ok()?;
+    let size = parts.next()?.parse().ok()?;
+    let secs = parts.next()?.parse().ok()?;
+    let nanos = parts.next()?.parse().ok()?;
+    let path = parts.next().filter(|path| !path.is_empty())?;
+
+    // Make sure the time can be compared with the file's modification time later on
+    SystemTime::UNIX_EPOCH.checked_add(Duration::new(secs, nanos))?;
+
+    let entry = CachedHash {
+        size,
+        modified: Duration::new(secs, nanos),
+        md5,
+    };
+
+    Some((PathBuf::from(path), entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty `Songs` folder for a test.
+    fn songs_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "osu-db-parser-hashes-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("1 Artist - Title")).unwrap();
+        dir
+    }
+
+    fn beatmap(file_name: &str) -> BeatmapEntry {
+        BeatmapEntry {
+            folder_name: Some("1 Artist - Title".to_string()),
+            beatmap_filename: Some(file_name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn write(dir: &Path, file_name: &str, contents: &str) -> PathBuf {
+        let path = dir.join("1 Artist - Title").join(file_name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn hash_all(dir: &Path, beatmaps: &[BeatmapEntry], cache: &mut HashCache) -> Vec<(usize, Md5)> {
+        hash_osu_files(dir, beatmaps, cache, false, |_, _| true).unwrap()
+    }
+
+    #[test]
+    fn md5_is_formatted_as_hex() {
+        let md5 = Md5::of(b"abc");
+
+        assert_eq!(md5.to_string(), "900150983cd24fb0d6963f7d28e17f72");
+        assert!(md5.matches("900150983CD24FB0D6963F7D28E17F72"));
+        assert!(!md5.matches("900150983cd24fb0d6963f7d28e17f7"));
+        assert_eq!("not a hash".parse::<Md5>(), Err(()));
+    }
+
+    #[test]
+    fn unchanged_files_are_cached() {
+        let dir = songs_dir("cached");
+        write(&dir, "a.osu", "osu file format v14");
+        write(&dir, "b.osu", "osu file format v9");
+        let beatmaps = [beatmap("a.osu"), beatmap("b.osu")];
+        let mut cache = HashCache::new();
+
+        let first = hash_all(&dir, &beatmaps, &mut cache);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0], (0, Md5::of(b"osu file format v14")));
+        assert_eq!(
+            cache.stats(),
+            HashStats {
+                hits: 0,
+                misses: 2,
+                missing: 0
+            }
+        );
+
+        for parallel in [false, true] {
+            let second =
+                hash_osu_files(&dir, &beatmaps, &mut cache, parallel, |_, _| true).unwrap();
+            assert_eq!(second, first);
+            assert_eq!(cache.stats().hits, 2);
+            assert_eq!(cache.stats().misses, 0);
+        }
+
+        // The cache is the same after saving and loading it
+        let mut saved = Vec::new();
+        cache.write_to(&mut saved).unwrap();
+        let loaded = HashCache::read_from(saved.as_slice()).unwrap();
+        assert_eq!(loaded.entries, cache.entries);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn changed_files_are_hashed_again() {
+        let dir = songs_dir("stale");
+        let path = write(&dir, "a.osu", "before");
+        let beatmaps = [beatmap("a.osu")];
+        let mut cache = HashCache::new();
+        hash_all(&dir, &beatmaps, &mut cache);
+
+        // Same size, but a different modification time
+        write(&dir, "a.osu", "after!");
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+
+        let hashes = hash_all(&dir, &beatmaps, &mut cache);
+        assert_eq!(hashes, vec![(0, Md5::of(b"after!"))]);
+        assert_eq!(cache.stats().misses, 1);
+        assert_eq!(cache.stats().hits, 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_files_are_skipped_and_forgotten() {
+        let dir = songs_dir("missing");
+        let path = write(&dir, "a.osu", "osu file format v14");
+        write(&dir, "b.osu", "osu file format v14");
+        let beatmaps = [
+            beatmap("a.osu"),
+            beatmap("missing.osu"),
+            beatmap("b.osu"),
+            BeatmapEntry::default(),
+        ];
+        let mut cache = HashCache::new();
+
+        let hashes = hash_all(&dir, &beatmaps, &mut cache);
+        assert_eq!(
+            hashes.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        assert_eq!(cache.stats().missing, 2);
+        assert_eq!(cache.len(), 2);
+
+        std::fs::remove_file(path).unwrap();
+        let hashes = hash_all(&dir, &beatmaps, &mut cache);
+        assert_eq!(hashes.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(cache.stats().missing, 3);
+        assert_eq!(cache.len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn hashing_can_be_cancelled() {
+        let dir = songs_dir("cancelled");
+        write(&dir, "a.osu", "osu file format v14");
+        let mut cache = HashCache::new();
+
+        let result = hash_osu_files(&dir, &[beatmap("a.osu")], &mut cache, false, |_, _| false);
+        assert!(matches!(result, Err(Error::Cancelled)));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_lines_are_skipped() {
+        let text = "900150983cd24fb0d6963f7d28e17f72\t3\t1700000000\t5\tSongs/a\tb.osu\n\
+                    not a hash\t3\t1700000000\t5\tSongs/c.osu\n\
+                    900150983cd24fb0d6963f7d28e17f72\t3\n";
+        let cache = HashCache::read_from(text.as_bytes()).unwrap();
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache.entries[Path::new("Songs/a\tb.osu")].modified,
+            Duration::new(1_700_000_000, 5)
+        );
+    }
+}
@@ -0,0 +1,546 @@
+//! Health checks for a beatmap library, which find entries that may need cleaning up.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    beatmaps::{BeatmapEntry, BeatmapListing},
+    common::OsuStr,
+    error::Error,
+    paths,
+    reconcile::FieldMismatch,
+};
+
+/// Represents a group of beatmaps which share the same MD5 hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Duplicate {
+    pub md5: String,
+
+    /// Indices of the beatmaps with this hash, in listing order. The first one is treated as the original.
+    pub indices: Vec<usize>,
+}
+
+/// Represents a problem with the values stored for a beatmap, or in the listing's header.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// The beatmap doesn't have an MD5 hash, so it can't be matched with scores or collections
+    MissingMd5,
+
+    /// The beatmap's MD5 hash isn't 32 hexadecimal characters
+    InvalidMd5(String),
+
+    /// The beatmap doesn't have a folder or `.osu` file name, so its files can't be found
+    MissingFileName,
+
+    /// A text field contains control characters (e.g. NULs, line breaks or bidirectional overrides), which are
+    /// replaced when it's shown or exported (see [`OsuStr::sanitized`])
+    ControlCharacters { field: &'static str },
+
+    /// A difficulty setting is outside of the range osu! allows
+    InvalidDifficulty { setting: &'static str, value: f32 },
+
+    /// A star rating is negative or not a number
+    InvalidStarRating(f64),
+
+    /// The beatmap doesn't have any hit objects
+    NoHitObjects,
+
+    /// The listing's folder count doesn't match the number of distinct folders
+    FolderCountMismatch { stored: u32, actual: u32 },
+}
+
+/// Represents a beatmap whose `.osu` file couldn't be found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingFile {
+    /// Index of the beatmap in the listing
+    pub index: usize,
+
+    /// Path where the `.osu` file was expected
+    pub path: PathBuf,
+}
+
+/// Represents a beatmap whose metadata in `osu!.db` doesn't match its `.osu` file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StaleMetadata {
+    /// Index of the beatmap in the listing
+    pub index: usize,
+
+    pub mismatches: Vec<FieldMismatch>,
+}
+
+impl Warning {
+    /// Gets a short description of the kind of problem, without any values, e.g. to group warnings together.
+    pub fn title(&self) -> &'static str {
+        use Warning::*;
+
+        match self {
+            MissingMd5 => "Missing MD5 hash",
+            InvalidMd5(_) => "Invalid MD5 hash",
+            MissingFileName => "Missing folder or .osu file name",
+            ControlCharacters { .. } => "Control characters in text",
+            InvalidDifficulty { .. } => "Difficulty setting out of range",
+            InvalidStarRating(_) => "Invalid star rating",
+            NoHitObjects => "No hit objects",
+            FolderCountMismatch { .. } => "Folder count mismatch",
+        }
+    }
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Warning::*;
+
+        match self {
+            MissingMd5 => write!(f, "Missing MD5 hash"),
+            InvalidMd5(md5) => write!(f, "Invalid MD5 hash '{}'", md5),
+            MissingFileName => write!(f, "Missing folder or .osu file name"),
+            ControlCharacters { field } => write!(f, "{} contains control characters", field),
+            InvalidDifficulty { setting, value } => {
+                write!(f, "{} is out of range ({})", setting, value)
+            }
+            InvalidStarRating(rating) => write!(f, "Invalid star rating ({})", rating),
+            NoHitObjects => write!(f, "No hit objects"),
+            FolderCountMismatch { stored, actual } => write!(
+                f,
+                "Folder count is {}, but the beatmaps are in {} folders",
+                stored, actual
+            ),
+        }
+    }
+}
+
+/// Finds beatmaps which share the same MD5 hash, in the order that each hash first appears.
+///
+/// Beatmaps without an MD5 hash are skipped, since they are reported by [`validate`] instead.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(beatmaps = beatmaps.len()))
+)]
+pub fn duplicates(beatmaps: &[BeatmapEntry]) -> Vec<Duplicate> {
+    let mut groups = HashMap::<&str, Vec<usize>>::new();
+    let mut order = Vec::new();
+
+    for (i, beatmap) in beatmaps.iter().enumerate() {
+        let Some(md5) = beatmap.md5.as_deref().filter(|m| !m.is_empty()) else {
+            continue;
+        };
+
+        let indices = groups.entry(md5).or_insert_with(|| {
+            order.push(md5);
+            Vec::new()
+        });
+
+        indices.push(i);
+    }
+
+    order
+        .into_iter()
+        .filter_map(|md5| {
+            let indices = groups.remove(md5)?;
+            (indices.len() > 1).then(|| Duplicate {
+                md5: md5.to_string(),
+                indices,
+            })
+        })
+        .collect()
+}
+
+/// Checks a beatmap's values for anything that looks invalid, e.g. because the entry is corrupt.
+pub fn validate(beatmap: &BeatmapEntry) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    match beatmap.md5.as_deref().filter(|m| !m.is_empty()) {
+        None => warnings.push(Warning::MissingMd5),
+        Some(md5) if md5.len() != 32 || !md5.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            warnings.push(Warning::InvalidMd5(md5.to_string()))
+        }
+        Some(_) => {}
+    }
+
+    let has_name = |name: &Option<String>| name.as_deref().is_some_and(|n| !n.is_empty());
+    if !has_name(&beatmap.folder_name) || !has_name(&beatmap.beatmap_filename) {
+        warnings.push(Warning::MissingFileName);
+    }
+
+    for (field, value) in [
+        ("Artist", &beatmap.artist_name),
+        ("Artist (Unicode)", &beatmap.artist_name_unicode),
+        ("Title", &beatmap.song_title),
+        ("Title (Unicode)", &beatmap.song_title_unicode),
+        ("Creator", &beatmap.creator_name),
+        ("Difficulty", &beatmap.difficulty),
+        ("Source", &beatmap.song_source),
+        ("Tags", &beatmap.song_tags),
+        ("Audio file name", &beatmap.audio_filename),
+        (".osu file name", &beatmap.beatmap_filename),
+        ("Folder", &beatmap.folder_name),
+    ] {
+        if value.as_deref().is_some_and(OsuStr::has_control_characters) {
+            warnings.push(Warning::ControlCharacters { field });
+        }
+    }
+
+    for (setting, value) in [
+        ("AR", beatmap.approach_rate),
+        ("CS", beatmap.circle_size),
+        ("HP", beatmap.hp_drain),
+        ("OD", beatmap.overall_difficulty),
+    ] {
+        if !(0.0..=10.0).contains(&value) {
+            warnings.push(Warning::InvalidDifficulty { setting, value });
+        }
+    }
+
+    let star_ratings = [
+        &beatmap.star_ratings_std,
+        &beatmap.star_ratings_taiko,
+        &beatmap.star_ratings_ctb,
+        &beatmap.star_ratings_mania,
+    ];
+
+    if let Some(rating) = star_ratings
+        .into_iter()
+        .flat_map(|ratings| ratings.as_deref().unwrap_or_default())
+        .map(|star_rating| star_rating.rating)
+        .find(|rating| !rating.is_finite() || *rating < 0.0)
+    {
+        warnings.push(Warning::InvalidStarRating(rating));
+    }
+
+    if beatmap.object_count() == 0 {
+        warnings.push(Warning::NoHitObjects);
+    }
+
+    warnings
+}
+
+/// Checks a listing's header for anything that doesn't match its beatmaps. The beatmaps themselves are checked with
+/// [`validate`].
+///
+/// osu! doesn't always keep the folder count up to date, so a mismatch doesn't mean the file is damaged. It can be
+/// corrected with [`BeatmapListing::fix_folder_count`].
+pub fn validate_listing(listing: &BeatmapListing) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    if let Some((stored, actual)) = listing.folder_count_mismatch() {
+        warnings.push(Warning::FolderCountMismatch { stored, actual });
+    }
+
+    warnings
+}
+
+/// Finds beatmaps whose `.osu` file doesn't exist in the osu! installation folder.
+///
+/// `progress` is called with the number of beatmaps checked so far and the total number of beatmaps. Returning `false`
+/// cancels the check, in which case [`Error::Cancelled`] is returned.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(beatmaps = beatmaps.len())))]
+pub fn missing_files<P, F>(
+    osu_directory: P,
+    beatmaps: &[BeatmapEntry],
+    mut progress: F,
+) -> Result<Vec<MissingFile>, Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(usize, usize) -> bool,
+{
+    let mut missing = Vec::new();
+
+    for (index, beatmap) in beatmaps.iter().enumerate() {
+        // Beatmaps without a file name are reported by `validate` instead
+        if let Some(path) = paths::beatmap_file(&osu_directory, beatmap) {
+            if !path.is_file() {
+                missing.push(MissingFile { index, path });
+            }
+        }
+
+        if !progress(index + 1, beatmaps.len()) {
+            return Err(Error::Cancelled);
+        }
+    }
+
+    Ok(missing)
+}
+
+/// Finds beatmaps whose metadata or difficulty settings in `osu!.db` are different from their `.osu` file in a `Songs`
+/// folder (see [`BeatmapEntry::reconcile_with_osu_file`]).
+///
+/// Beatmaps whose `.osu` file can't be read are skipped, since they're reported by [`missing_files`] instead.
+///
+/// `progress` is called with the number of beatmaps checked so far and the total number of beatmaps. Returning `false`
+/// cancels the check, in which case [`Error::Cancelled`] is returned.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(beatmaps = beatmaps.len())))]
+pub fn stale_metadata<P, F>(
+    songs_dir: P,
+    beatmaps: &[BeatmapEntry],
+    mut progress: F,
+) -> Result<Vec<StaleMetadata>, Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(usize, usize) -> bool,
+{
+    let mut stale = Vec::new();
+
+    for (index, beatmap) in beatmaps.iter().enumerate() {
+        if let Ok(mismatches) = beatmap.reconcile_with_osu_file(&songs_dir) {
+            if !mismatches.is_empty() {
+                stale.push(StaleMetadata { index, mismatches });
+            }
+        }
+
+        if !progress(index + 1, beatmaps.len()) {
+            return Err(Error::Cancelled);
+        }
+    }
+
+    Ok(stale)
+}
+
+/// Finds folders in the `Songs` folder which aren't used by any beatmap, e.g. because the beatmaps were deleted
+/// while osu! wasn't running.
+///
+/// `progress` is called with the number of folders checked so far and the total number of folders. Returning `false`
+/// cancels the check, in which case [`Error::Cancelled`] is returned.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(beatmaps = beatmaps.len())))]
+pub fn orphan_folders<P, F>(
+    osu_directory: P,
+    beatmaps: &[BeatmapEntry],
+    mut progress: F,
+) -> Result<Vec<PathBuf>, Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(usize, usize) -> bool,
+{
+    // Windows file names are case-insensitive, and folder names stored by osu! may still contain characters that were
+    // removed from the folder's actual name, so folder names may not match exactly
+    let folder_key = |name: &str| paths::sanitize_filename(name).to_lowercase();
+    let used = beatmaps
+        .iter()
+        .filter_map(|b| b.folder_name.as_deref())
+        .map(folder_key)
+        .collect::<HashSet<_>>();
+
+    let folders = std::fs::read_dir(osu_directory.as_ref().join("Songs"))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+
+    let mut orphans = Vec::new();
+
+    for (i, folder) in folders.iter().enumerate() {
+        let name = folder
+            .file_name()
+            .map(|name| folder_key(&name.to_string_lossy()))
+            .unwrap_or_default();
+
+        if !used.contains(&name) {
+            orphans.push(folder.clone());
+        }
+
+        if !progress(i + 1, folders.len()) {
+            return Err(Error::Cancelled);
+        }
+    }
+
+    orphans.sort();
+    Ok(orphans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beatmaps::StarRating;
+
+    const MD5: &str = "0123456789abcdef0123456789abcdef";
+
+    fn beatmap(md5: &str, folder_name: &str) -> BeatmapEntry {
+        BeatmapEntry {
+            md5: Some(md5.to_string()),
+            folder_name: Some(folder_name.to_string()),
+            beatmap_filename: Some("map.osu".to_string()),
+            hitcircle_count: 100,
+            approach_rate: 9.0,
+            circle_size: 4.0,
+            hp_drain: 5.0,
+            overall_difficulty: 8.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn duplicates_are_grouped() {
+        let other = "fedcba9876543210fedcba9876543210";
+        let beatmaps = vec![
+            beatmap(MD5, "a"),
+            beatmap(other, "b"),
+            BeatmapEntry::default(),
+            beatmap(MD5, "a"),
+            BeatmapEntry::default(),
+            beatmap(MD5, "c"),
+        ];
+
+        assert_eq!(
+            duplicates(&beatmaps),
+            vec![Duplicate {
+                md5: MD5.to_string(),
+                indices: vec![0, 3, 5]
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_reported() {
+        assert_eq!(validate(&beatmap(MD5, "a")), vec![]);
+
+        let mut invalid = beatmap("not an md5", "");
+        invalid.approach_rate = 11.0;
+        invalid.hitcircle_count = 0;
+        invalid.star_ratings_std = Some(vec![StarRating {
+            mods: crate::common::Mods::none(),
+            rating: f64::NAN,
+        }]);
+
+        let warnings = validate(&invalid);
+        assert_eq!(warnings.len(), 5);
+        assert_eq!(warnings[0], Warning::InvalidMd5("not an md5".to_string()));
+        assert_eq!(warnings[1], Warning::MissingFileName);
+        assert_eq!(
+            warnings[2],
+            Warning::InvalidDifficulty {
+                setting: "AR",
+                value: 11.0
+            }
+        );
+        assert!(matches!(warnings[3], Warning::InvalidStarRating(r) if r.is_nan()));
+        assert_eq!(warnings[4], Warning::NoHitObjects);
+        assert_eq!(warnings[2].title(), "Difficulty setting out of range");
+
+        assert!(validate(&BeatmapEntry::default()).contains(&Warning::MissingMd5));
+    }
+
+    #[test]
+    fn control_characters_are_reported() {
+        let mut tampered = beatmap(MD5, "a");
+        tampered.song_title = Some("Title\0".to_string());
+        tampered.song_tags = Some("tag\nanother\u{202E}".to_string());
+
+        let warnings = validate(&tampered);
+        assert_eq!(
+            warnings,
+            [
+                Warning::ControlCharacters { field: "Title" },
+                Warning::ControlCharacters { field: "Tags" }
+            ]
+        );
+        assert_eq!(warnings[1].to_string(), "Tags contains control characters");
+    }
+
+    #[test]
+    fn folder_count_mismatch_is_reported() {
+        let mut listing = BeatmapListing {
+            version: 20191106,
+            folder_count: 2,
+            account_unlocked: true,
+            account_unlock_date: crate::common::WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps: vec![
+                beatmap(MD5, "1 Artist - Title"),
+                beatmap(MD5, "1 ARTIST - TITLE"),
+            ],
+            user_permissions: Default::default(),
+        };
+
+        let warnings = validate_listing(&listing);
+        assert_eq!(
+            warnings,
+            [Warning::FolderCountMismatch {
+                stored: 2,
+                actual: 1
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "Folder count is 2, but the beatmaps are in 1 folders"
+        );
+
+        listing.fix_folder_count();
+        assert_eq!(validate_listing(&listing), []);
+    }
+
+    #[test]
+    fn missing_files_and_orphan_folders_are_found() {
+        let dir = std::env::temp_dir().join(format!("osu-db-parser-health-{}", std::process::id()));
+        let songs = dir.join("Songs");
+
+        for folder in ["Present", "Missing File", "Orphan", "1 Artist - What"] {
+            std::fs::create_dir_all(songs.join(folder)).unwrap();
+        }
+
+        std::fs::write(songs.join("Present").join("map.osu"), b"").unwrap();
+
+        let beatmaps = vec![
+            beatmap(MD5, "present"),
+            beatmap(MD5, "Missing File"),
+            beatmap(MD5, "Deleted"),
+            beatmap(MD5, "1 Artist - What?"),
+        ];
+
+        let mut calls = 0;
+        let missing = missing_files(&dir, &beatmaps, |_, _| {
+            calls += 1;
+            true
+        })
+        .unwrap();
+
+        // The first beatmap is only found on case-insensitive file systems
+        assert!(missing.iter().any(|m| m.index == 1));
+        assert!(missing.iter().any(|m| m.index == 2));
+        assert_eq!(calls, 4);
+
+        assert_eq!(
+            orphan_folders(&dir, &beatmaps, |_, _| true).unwrap(),
+            vec![songs.join("Orphan")]
+        );
+
+        assert!(matches!(
+            missing_files(&dir, &beatmaps, |_, _| false),
+            Err(Error::Cancelled)
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stale_metadata_is_found() {
+        let songs =
+            std::env::temp_dir().join(format!("osu-db-parser-stale-{}", std::process::id()));
+
+        for (folder, contents) in [
+            ("Current", "[Difficulty]\nApproachRate:9\nCircleSize:4\n"),
+            ("Edited", "[Difficulty]\nApproachRate:10\nCircleSize:4\n"),
+        ] {
+            std::fs::create_dir_all(songs.join(folder)).unwrap();
+            std::fs::write(songs.join(folder).join("map.osu"), contents).unwrap();
+        }
+
+        let beatmaps = vec![
+            beatmap(MD5, "Current"),
+            beatmap(MD5, "Edited"),
+            beatmap(MD5, "Deleted"),
+        ];
+
+        let stale = stale_metadata(&songs, &beatmaps, |_, _| true).unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].index, 1);
+        assert_eq!(stale[0].mismatches[0].in_file, "10");
+
+        assert!(matches!(
+            stale_metadata(&songs, &beatmaps, |_, _| false),
+            Err(Error::Cancelled)
+        ));
+
+        std::fs::remove_dir_all(&songs).unwrap();
+    }
+}
@@ -0,0 +1,300 @@
+//! Reading only the MD5 hash and IDs of each beatmap in an `osu.db` file, for tools such as mirrors and downloaders
+//! which need nothing else.
+//!
+//! Rather than parsing each entry, the entries are walked using the layout of each field (see
+//! [`entry_fields`](crate::raw::entry_fields)). Strings are skipped using their length without being checked or
+//! copied, and star ratings and timing points are skipped by multiplying their count by the size of an item, so the
+//! only memory used is the map itself.
+
+use std::collections::HashMap;
+
+use nom::{bytes::complete::take, error::ErrorKind, number::complete::le_u32, IResult};
+
+use crate::{
+    beatmaps::{listing_header, BeatmapListing},
+    binary::{parse_uleb128, skip_osu_string},
+    detect::FileKind,
+    error::Error,
+    hashes::Md5,
+    ids::{BeatmapSetId, DifficultyId},
+    raw::{entry_fields, FieldKind},
+};
+
+/// Represents the IDs of a beatmap on the osu! website. Either ID is `None` if the beatmap hasn't been submitted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BeatmapIds {
+    pub difficulty_id: Option<DifficultyId>,
+    pub beatmapset_id: Option<BeatmapSetId>,
+}
+
+/// Represents one step of walking a beatmap entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    /// Skips a field, or several fixed size fields in a row
+    Skip(FieldKind),
+
+    Md5,
+    DifficultyId,
+    BeatmapSetId,
+}
+
+impl BeatmapListing {
+    /// Reads the MD5 hash and IDs of each beatmap from the contents of an `osu.db` file, without parsing anything else.
+    /// This is much faster than [`BeatmapListing::from_bytes`], and only uses memory for the returned map.
+    ///
+    /// Beatmaps without a valid MD5 hash are left out. If more than one beatmap has the same hash, the last one is kept,
+    /// the same as [`BeatmapListing::beatmaps_by_md5`].
+    ///
+    /// Only the layout of the file is checked, so a file that [`BeatmapListing::from_bytes`] rejects because of an
+    /// invalid value (e.g. text that isn't UTF-8) may still be read.
+    pub fn parse_id_map(data: &[u8]) -> Result<HashMap<Md5, BeatmapIds>, Error> {
+        let (mut i, header) =
+            listing_header(data).map_err(|e| Error::parse(FileKind::Beatmaps, data, e))?;
+
+        let steps = steps(header.version);
+        let total = header.beatmap_count as usize;
+
+        // Each entry is at least a few dozen bytes, so a damaged count can't reserve more than the file could hold
+        let mut map = HashMap::with_capacity(total.min(data.len() / 64));
+
+        for index in 0..total {
+            let (rest, (md5, ids)) = walk_entry(&steps)(i).map_err(|e| {
+                let (offset, kind) = match e {
+                    nom::Err::Error(e) | nom::Err::Failure(e) => {
+                        (data.len() - e.input.len(), e.code)
+                    }
+                    nom::Err::Incomplete(_) => (data.len(), ErrorKind::Eof),
+                };
+
+                Error::Beatmap {
+                    index,
+                    total,
+                    entry_offset: data.len() - i.len(),
+                    offset,
+                    kind,
+                }
+            })?;
+
+            if let Some(md5) = md5 {
+                map.insert(md5, ids);
+            }
+
+            i = rest;
+        }
+
+        Ok(map)
+    }
+}
+
+/// Gets the steps for walking a beatmap entry in a version, with fixed size fields in a row merged into one step.
+fn steps(version: u32) -> Vec<Step> {
+    let mut steps = Vec::new();
+
+    for (name, kind) in entry_fields(version) {
+        let step = match name {
+            "md5" => Step::Md5,
+            "difficulty_id" => Step::DifficultyId,
+            "beatmap_id" => Step::BeatmapSetId,
+            _ => Step::Skip(kind),
+        };
+
+        match (steps.last_mut(), step) {
+            (Some(Step::Skip(FieldKind::Fixed(size))), Step::Skip(FieldKind::Fixed(next_size))) => {
+                *size += next_size
+            }
+            _ => steps.push(step),
+        }
+    }
+
+    steps
+}
+
+/// Walks a beatmap entry, reading its MD5 hash (if it's valid) and IDs.
+fn walk_entry(steps: &[Step]) -> impl Fn(&[u8]) -> IResult<&[u8], (Option<Md5>, BeatmapIds)> + '_ {
+    move |mut i| {
+        let mut md5 = None;
+        let mut ids = BeatmapIds::default();
+
+        for step in steps {
+            i = match step {
+                Step::Skip(FieldKind::Fixed(size)) => take(*size)(i)?.0,
+                Step::Skip(FieldKind::String) => skip_osu_string(i)?.0,
+                Step::Skip(FieldKind::List(item_size)) => {
+                    let (i, count) = le_u32(i)?;
+                    take((count as usize).saturating_mul(*item_size))(i)?.0
+                }
+                Step::Md5 => {
+                    let (rest, bytes) = string_bytes(i)?;
+                    md5 = bytes
+                        .and_then(|bytes| std::str::from_utf8(bytes).ok())
+                        .and_then(|hex| hex.parse().ok());
+                    rest
+                }
+                Step::DifficultyId => {
+                    let (rest, id) = le_u32(i)?;
+                    ids.difficulty_id = DifficultyId::new(id);
+                    rest
+                }
+                Step::BeatmapSetId => {
+                    let (rest, id) = le_u32(i)?;
+                    ids.beatmapset_id = BeatmapSetId::new(id);
+                    rest
+                }
+            };
+        }
+
+        Ok((i, (md5, ids)))
+    }
+}
+
+/// Gets the bytes of a string without checking or copying them, or `None` if it's absent. Invalid markers are rejected
+/// the same way as by [`skip_osu_string`].
+fn string_bytes(input: &[u8]) -> IResult<&[u8], Option<&[u8]>> {
+    let (rest, ()) = skip_osu_string(input)?;
+
+    if input[0] == 0 {
+        return Ok((rest, None));
+    }
+
+    let (i, length) = parse_uleb128(&input[1..])?;
+    Ok((rest, Some(&i[..length])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        beatmaps::{BeatmapEntry, StarRating, TimingPoint, UserPermissions},
+        common::{Mods, WINDOWS_EPOCH},
+    };
+
+    /// Gets the map the same way from the full parser's results.
+    fn expected(listing: &BeatmapListing) -> HashMap<Md5, BeatmapIds> {
+        listing
+            .beatmaps
+            .iter()
+            .filter_map(|b| {
+                let md5 = b.md5.as_deref()?.parse().ok()?;
+                let ids = BeatmapIds {
+                    difficulty_id: b.difficulty_id(),
+                    beatmapset_id: b.beatmapset_id(),
+                };
+
+                Some((md5, ids))
+            })
+            .collect()
+    }
+
+    fn listing(version: u32) -> BeatmapListing {
+        let star_ratings = (version >= 20140609).then(|| {
+            vec![
+                StarRating {
+                    mods: Mods::DoubleTime.into(),
+                    rating: 5.0,
+                };
+                3
+            ]
+        });
+
+        let beatmaps = (0..50u32)
+            .map(|i| BeatmapEntry {
+                artist_name: Some("アーティスト".repeat(i as usize % 4)),
+                song_title: Some(format!("Song {}", i)),
+                // Every tenth beatmap has the same hash as the one before it, and some don't have one
+                md5: (i % 7 != 3).then(|| format!("{:032x}", i - (i % 10 == 9) as u32)),
+                difficulty_id: [0, u32::MAX, 1000 + i][i as usize % 3],
+                beatmap_id: if i % 5 == 0 { 0 } else { 100 + i / 4 },
+                star_ratings_std: star_ratings.clone(),
+                star_ratings_mania: star_ratings.clone(),
+                timing_points: vec![
+                    TimingPoint {
+                        bpm: 180.0,
+                        song_offset: 0.0,
+                        inherited: false,
+                    };
+                    i as usize
+                ],
+                song_tags: Some("tags ".repeat(i as usize)),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        BeatmapListing {
+            version,
+            folder_count: beatmaps.len() as u32,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: Some("Player".to_string()),
+            beatmaps,
+            user_permissions: UserPermissions::Normal.into(),
+        }
+    }
+
+    #[test]
+    fn ids_match_the_full_parser() {
+        for version in [20131231, 20140609, 20191106, 20250108] {
+            let data = listing(version).to_bytes();
+            let parsed = BeatmapListing::from_bytes(&data).unwrap();
+            let map = BeatmapListing::parse_id_map(&data).unwrap();
+
+            assert_eq!(map, expected(&parsed), "version {}", version);
+            assert!(map.len() < parsed.beatmaps.len());
+        }
+    }
+
+    #[test]
+    fn fixture_ids_match_the_full_parser() {
+        let data = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/osu.db")).unwrap();
+        let parsed = BeatmapListing::from_bytes(&data).unwrap();
+        let map = BeatmapListing::parse_id_map(&data).unwrap();
+
+        assert!(!map.is_empty());
+        assert_eq!(map, expected(&parsed));
+    }
+
+    #[test]
+    fn truncated_entries_are_reported() {
+        let data = listing(20191106).to_bytes();
+        let error = BeatmapListing::parse_id_map(&data[..data.len() - 10]).unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::Beatmap {
+                index: 49,
+                total: 50,
+                kind: ErrorKind::Eof,
+                ..
+            }
+        ));
+
+        let error = BeatmapListing::parse_id_map(&[0x03, 0x8b]).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Parse {
+                file: FileKind::Beatmaps,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn fixed_size_fields_are_merged() {
+        let steps = steps(20250108);
+
+        assert!(steps.len() < entry_fields(20250108).len());
+        assert!(steps.windows(2).all(|pair| !matches!(
+            pair,
+            [
+                Step::Skip(FieldKind::Fixed(_)),
+                Step::Skip(FieldKind::Fixed(_))
+            ]
+        )));
+        assert_eq!(
+            steps
+                .iter()
+                .filter(|step| !matches!(step, Step::Skip(_)))
+                .count(),
+            3
+        );
+    }
+}
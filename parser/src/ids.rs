@@ -0,0 +1,203 @@
+//! Typed IDs for beatmaps on the osu! website, so that e.g. a beatmapset ID can't be passed where a difficulty ID is
+//! expected.
+//!
+//! osu!'s databases store each ID as a bare `u32`, which is kept in fields such as [`BeatmapEntry::beatmap_id`] so that
+//! files round-trip exactly. Unsubmitted beatmaps use either `0` or `-1` (stored as `u32::MAX`) instead of an ID, so
+//! these values can't be made into a typed ID. The accessors on [`BeatmapEntry`] (e.g. [`BeatmapEntry::beatmapset_id`])
+//! return `None` for them.
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use crate::beatmaps::BeatmapEntry;
+
+/// Represents an error that can occur when making a typed ID from a number or text.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum IdError {
+    #[error("'{0}' is not a valid ID")]
+    NotANumber(String),
+
+    #[error("{0} is not the ID of a submitted beatmap")]
+    Unsubmitted(u32),
+}
+
+macro_rules! id_type {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(try_from = "u32", into = "u32")
+        )]
+        pub struct $name(u32);
+
+        impl $name {
+            /// Makes an ID from the value stored in osu!'s databases, returning `None` for `0` and `-1`.
+            pub fn new(id: u32) -> Option<Self> {
+                is_submitted(id).then_some(Self(id))
+            }
+
+            /// Gets the ID as a number.
+            pub fn get(self) -> u32 {
+                self.0
+            }
+        }
+
+        impl TryFrom<u32> for $name {
+            type Error = IdError;
+
+            fn try_from(id: u32) -> Result<Self, IdError> {
+                Self::new(id).ok_or(IdError::Unsubmitted(id))
+            }
+        }
+
+        impl From<$name> for u32 {
+            fn from(id: $name) -> u32 {
+                id.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = IdError;
+
+            fn from_str(s: &str) -> Result<Self, IdError> {
+                let id = s
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| IdError::NotANumber(s.to_string()))?;
+
+                Self::try_from(id)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+id_type!(
+    /// ID of a beatmapset, i.e. the `/beatmapsets/<id>` and `/s/<id>` links on the osu! website.
+    BeatmapSetId
+);
+
+id_type!(
+    /// ID of a single difficulty in a beatmapset, i.e. the `/b/<id>` links on the osu! website.
+    DifficultyId
+);
+
+id_type!(
+    /// ID of a beatmapset's forum thread.
+    ThreadId
+);
+
+/// The osu! website calls difficulty IDs "beatmap IDs" (e.g. in `/beatmaps/<id>` links and the API), so this is the
+/// same type rather than a separate one. A separate type would only split one ID into two types depending on what the
+/// caller called it. The mix-up worth catching is passing a beatmapset ID (kept in [`BeatmapEntry::beatmap_id`]) where
+/// a difficulty ID is expected, which [`BeatmapSetId`] already prevents.
+pub type BeatmapId = DifficultyId;
+
+/// Checks whether an ID read from one of osu!'s databases refers to a submitted beatmap, i.e. isn't `0` or `-1` (stored
+/// as `u32::MAX`).
+pub fn is_submitted(id: u32) -> bool {
+    id != 0 && id != u32::MAX
+}
+
+impl BeatmapEntry {
+    /// Gets the ID of the beatmap's beatmapset (stored in [`BeatmapEntry::beatmap_id`]), or `None` if it hasn't been
+    /// submitted.
+    pub fn beatmapset_id(&self) -> Option<BeatmapSetId> {
+        BeatmapSetId::new(self.beatmap_id)
+    }
+
+    /// Gets the beatmap's difficulty ID, or `None` if it hasn't been submitted.
+    pub fn difficulty_id(&self) -> Option<DifficultyId> {
+        DifficultyId::new(self.difficulty_id)
+    }
+
+    /// Gets the beatmap's ID on the osu! website, which is its difficulty ID (see [`BeatmapId`]), or `None` if it
+    /// hasn't been submitted.
+    ///
+    /// **NOTE**: This doesn't read the [`BeatmapEntry::beatmap_id`] field, which holds the beatmapset ID (see
+    /// [`BeatmapEntry::beatmapset_id`]).
+    pub fn beatmap_id(&self) -> Option<BeatmapId> {
+        self.difficulty_id()
+    }
+
+    /// Gets the ID of the beatmapset's forum thread, or `None` if it doesn't have one.
+    pub fn thread_id(&self) -> Option<ThreadId> {
+        ThreadId::new(self.thread_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentinel_values_are_unsubmitted() {
+        for id in [0, u32::MAX] {
+            assert_eq!(DifficultyId::new(id), None);
+            assert_eq!(BeatmapSetId::try_from(id), Err(IdError::Unsubmitted(id)));
+        }
+
+        assert_eq!(DifficultyId::new(129891).map(u32::from), Some(129891));
+        assert_eq!(
+            BeatmapSetId::new(u32::MAX - 1).map(BeatmapSetId::get),
+            Some(u32::MAX - 1)
+        );
+    }
+
+    #[test]
+    fn ids_are_parsed_and_displayed() {
+        assert_eq!(
+            "39804".parse::<BeatmapSetId>().unwrap().to_string(),
+            "39804"
+        );
+        assert_eq!(" 129891 ".parse::<DifficultyId>().unwrap().get(), 129891);
+        assert_eq!("0".parse::<ThreadId>(), Err(IdError::Unsubmitted(0)));
+        assert_eq!(
+            "-1".parse::<BeatmapSetId>(),
+            Err(IdError::NotANumber("-1".to_string()))
+        );
+        assert_eq!(
+            "abc".parse::<DifficultyId>(),
+            Err(IdError::NotANumber("abc".to_string()))
+        );
+    }
+
+    #[test]
+    fn beatmap_ids_are_typed() {
+        let beatmap = BeatmapEntry {
+            beatmap_id: 39804,
+            difficulty_id: u32::MAX,
+            thread_id: 0,
+            ..Default::default()
+        };
+
+        assert_eq!(beatmap.beatmapset_id(), BeatmapSetId::new(39804));
+        assert_eq!(beatmap.difficulty_id(), None);
+        assert_eq!(beatmap.beatmap_id(), None);
+        assert_eq!(beatmap.thread_id(), None);
+
+        let beatmap = BeatmapEntry {
+            difficulty_id: 129891,
+            ..beatmap
+        };
+
+        assert_eq!(beatmap.beatmap_id(), BeatmapId::new(129891));
+        assert_eq!(beatmap.beatmap_id(), beatmap.difficulty_id());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn unsubmitted_ids_are_not_deserialized() {
+        let id = serde_json::from_str::<DifficultyId>("129891").unwrap();
+        assert_eq!(serde_json::to_string(&id).unwrap(), "129891");
+        assert!(serde_json::from_str::<DifficultyId>("0").is_err());
+    }
+}
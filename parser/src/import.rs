@@ -0,0 +1,265 @@
+//! Importing of plain text lists of beatmaps, such as tournament mappools or lists of recommended beatmaps.
+//!
+//! Each line of a list is one beatmap, given as its MD5 hash, its beatmap ID, or a link to it on the osu! website (e.g.
+//! `https://osu.ppy.sh/beatmapsets/39804#osu/129891`). Links to a whole beatmapset stand for every difficulty in it.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    beatmaps::BeatmapListing,
+    ids::{BeatmapSetId, DifficultyId},
+};
+
+/// Represents a beatmap given on a line of a list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListEntry {
+    /// A beatmap's MD5 hash, in lowercase
+    Md5(String),
+
+    /// A beatmap ID, or a link to a beatmap
+    Beatmap(DifficultyId),
+
+    /// A link to a beatmapset, which stands for each of its difficulties
+    Beatmapset(BeatmapSetId),
+}
+
+/// Represents a list of beatmaps which has been looked up in a beatmap listing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResolvedList {
+    /// Line number (starting from 1) and index in the listing of each beatmap that was found, in the order they're
+    /// listed. Beatmaps listed more than once are only included the first time.
+    pub found: Vec<(usize, usize)>,
+
+    /// Line number of each beatmap that isn't in the listing
+    pub missing: Vec<(usize, ListEntry)>,
+
+    /// Line number and text of each line that isn't a beatmap
+    pub unparseable: Vec<(usize, String)>,
+}
+
+impl ListEntry {
+    /// Recognises the beatmap given on a line of a list, returning `None` if the line doesn't look like a beatmap.
+    pub fn parse(line: &str) -> Option<ListEntry> {
+        let line = line.trim();
+
+        if line.len() == 32 && line.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Some(ListEntry::Md5(line.to_ascii_lowercase()));
+        }
+
+        if line.chars().all(|c| c.is_ascii_digit()) {
+            return line.parse().ok().map(ListEntry::Beatmap);
+        }
+
+        parse_link(line)
+    }
+}
+
+impl fmt::Display for ListEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListEntry::Md5(md5) => write!(f, "MD5 hash {}", md5),
+            ListEntry::Beatmap(id) => write!(f, "beatmap {}", id),
+            ListEntry::Beatmapset(id) => write!(f, "beatmapset {}", id),
+        }
+    }
+}
+
+impl ResolvedList {
+    /// Gets the indices of the beatmaps that were found, in the order they're listed.
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.found.iter().map(|(_, index)| *index)
+    }
+}
+
+/// Recognises each non-empty line of a list as a beatmap, and looks them up in a listing.
+pub fn resolve_list(text: &str, listing: &BeatmapListing) -> ResolvedList {
+    let mut by_md5 = HashMap::new();
+    let mut by_difficulty_id = HashMap::new();
+    let mut by_beatmapset_id = HashMap::<_, Vec<_>>::new();
+
+    for (i, beatmap) in listing.beatmaps.iter().enumerate() {
+        if let Some(md5) = beatmap.md5.as_deref().filter(|md5| !md5.is_empty()) {
+            by_md5.entry(md5.to_ascii_lowercase()).or_insert(i);
+        }
+
+        if let Some(difficulty_id) = beatmap.difficulty_id() {
+            by_difficulty_id.entry(difficulty_id).or_insert(i);
+        }
+
+        if let Some(beatmapset_id) = beatmap.beatmapset_id() {
+            by_beatmapset_id.entry(beatmapset_id).or_default().push(i);
+        }
+    }
+
+    let mut resolved = ResolvedList::default();
+    let mut seen = HashSet::new();
+
+    for (line_number, line) in (1..).zip(text.lines()) {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some(entry) = ListEntry::parse(line) else {
+            resolved
+                .unparseable
+                .push((line_number, line.trim().to_string()));
+            continue;
+        };
+
+        let indices = match &entry {
+            ListEntry::Md5(md5) => by_md5.get(md5).copied().into_iter().collect(),
+            ListEntry::Beatmap(id) => by_difficulty_id.get(id).copied().into_iter().collect(),
+            ListEntry::Beatmapset(id) => by_beatmapset_id.get(id).cloned().unwrap_or_default(),
+        };
+
+        if indices.is_empty() {
+            resolved.missing.push((line_number, entry));
+            continue;
+        }
+
+        resolved.found.extend(
+            indices
+                .into_iter()
+                .filter(|&index| seen.insert(index))
+                .map(|index| (line_number, index)),
+        );
+    }
+
+    resolved
+}
+
+/// Recognises a link to a beatmap or beatmapset on the osu! website, or an `osu://` link to one.
+fn parse_link(link: &str) -> Option<ListEntry> {
+    let path = link
+        .strip_prefix("https://")
+        .or_else(|| link.strip_prefix("http://"))
+        .and_then(|rest| {
+            let (host, path) = rest.split_once('/')?;
+            matches!(host, "osu.ppy.sh" | "old.ppy.sh" | "new.ppy.sh").then_some(path)
+        })
+        .or_else(|| link.strip_prefix("osu://"))?;
+
+    // Links to a difficulty on a beatmapset's page give its ID after the mode, e.g. `beatmapsets/1#osu/2`
+    let (path, fragment) = path.split_once('#').unwrap_or((path, ""));
+    let path = path.split(['?', '&']).next().unwrap_or_default();
+
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+    let kind = segments.next()?;
+    let id = segments.next()?;
+
+    match kind {
+        "b" | "beatmaps" => id.parse().ok().map(ListEntry::Beatmap),
+        "s" | "beatmapsets" => match fragment.rsplit_once('/') {
+            Some((_, difficulty_id)) if !difficulty_id.is_empty() => {
+                difficulty_id.parse().ok().map(ListEntry::Beatmap)
+            }
+            _ => id.parse().ok().map(ListEntry::Beatmapset),
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{beatmaps::BeatmapEntry, common::WINDOWS_EPOCH};
+
+    fn listing() -> BeatmapListing {
+        let beatmap = |md5: &str, beatmapset_id: u32, difficulty_id: u32| BeatmapEntry {
+            md5: Some(md5.to_string()),
+            beatmap_id: beatmapset_id,
+            difficulty_id,
+            ..Default::default()
+        };
+
+        BeatmapListing {
+            version: 20240101,
+            folder_count: 3,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps: vec![
+                beatmap("da8aae79c8f3306b5d65ec951874a7fb", 39804, 129891),
+                beatmap("0123456789abcdef0123456789abcdef", 39804, 129892),
+                beatmap("fedcba9876543210fedcba9876543210", 100, 200),
+                beatmap("ffffffffffffffffffffffffffffffff", 0, 0),
+            ],
+            user_permissions: Default::default(),
+        }
+    }
+
+    fn beatmap(id: u32) -> ListEntry {
+        ListEntry::Beatmap(DifficultyId::new(id).unwrap())
+    }
+
+    #[test]
+    fn lines_are_recognised() {
+        let beatmapset = |id| ListEntry::Beatmapset(BeatmapSetId::new(id).unwrap());
+
+        for (line, entry) in [
+            (
+                " DA8AAE79C8F3306B5D65EC951874A7FB ",
+                Some(ListEntry::Md5(
+                    "da8aae79c8f3306b5d65ec951874a7fb".to_string(),
+                )),
+            ),
+            ("129891", Some(beatmap(129891))),
+            ("https://osu.ppy.sh/b/129891", Some(beatmap(129891))),
+            ("https://osu.ppy.sh/b/129891?m=0", Some(beatmap(129891))),
+            ("https://osu.ppy.sh/beatmaps/129891", Some(beatmap(129891))),
+            (
+                "https://osu.ppy.sh/beatmapsets/39804#osu/129891",
+                Some(beatmap(129891)),
+            ),
+            ("http://osu.ppy.sh/s/39804", Some(beatmapset(39804))),
+            (
+                "https://osu.ppy.sh/beatmapsets/39804",
+                Some(beatmapset(39804)),
+            ),
+            ("osu://b/129891", Some(beatmap(129891))),
+            ("0", None),
+            ("99999999999", None),
+            ("-5", None),
+            ("https://osu.ppy.sh/users/2", None),
+            ("https://example.com/b/129891", None),
+            ("NM1: FREEDOM DiVE", None),
+            ("da8aae79c8f3306b5d65ec951874a7f", None),
+        ] {
+            assert_eq!(ListEntry::parse(line), entry, "{}", line);
+        }
+    }
+
+    #[test]
+    fn mixed_lists_are_resolved() {
+        let text = "# Mappool\n\
+                    DA8AAE79C8F3306B5D65EC951874A7FB\n\
+                    \n\
+                    https://osu.ppy.sh/beatmapsets/39804\n\
+                    200\n\
+                    https://osu.ppy.sh/b/300\n\
+                    0123456789abcdef0123456789abcdef\n\
+                    not a beatmap\n\
+                    https://osu.ppy.sh/beatmapsets/1#osu/200\n\
+                    0\n";
+
+        let resolved = resolve_list(text, &listing());
+
+        // The first difficulty was already found, so the beatmapset only adds the other one
+        assert_eq!(resolved.found, vec![(2, 0), (4, 1), (5, 2)]);
+        assert_eq!(resolved.indices().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(resolved.missing, vec![(6, beatmap(300))]);
+        assert_eq!(
+            resolved.unparseable,
+            vec![
+                (1, "# Mappool".to_string()),
+                (8, "not a beatmap".to_string()),
+                (10, "0".to_string()),
+            ]
+        );
+
+        assert_eq!(resolve_list("", &listing()), ResolvedList::default());
+    }
+}
@@ -1,8 +1,137 @@
+//! Parsing the database files of osu! (stable): `osu!.db`, `collection.db`, `scores.db` and `.osr` replays.
+//!
+//! # Features
+//!
+//! Only parsing `osu!.db` is built by default, which depends on nothing besides `nom` and `time`, along with `flagset`
+//! for mod flags and `thiserror` for [`Error`]. Everything else is opt-in:
+//!
+//! - `writer`: encoding databases and writing them to files, e.g. `BeatmapListing::to_file`
+//! - `collections`: `collection.db`, in the `collections` module
+//! - `scores`: `scores.db`, in the `scores` module, and `osr` for reading `.osr` replays as well
+//! - `tools`: everything the viewer and the command line tools use to search, sort, export, edit and check libraries,
+//!   which turns on all of the above
+//! - `async`, `backups`, `ffi`, `serde`, `synthetic`, `tracing`, `wasm` and `zip`, which are described where they're
+//!   used
+//!
+//! Exporting to SQLite, the viewer and the command line tools are in the `osu-db-viewer` and `osu-db-cli` crates, so
+//! depending on this crate never builds them or their dependencies.
+
+pub mod added;
+#[cfg(feature = "tools")]
+pub mod analysis;
+#[cfg(feature = "tools")]
+pub mod anonymise;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub mod asynchronous;
+#[cfg(all(feature = "backups", not(target_arch = "wasm32")))]
+pub mod backups;
 pub mod beatmaps;
+pub mod binary;
+#[cfg(feature = "collections")]
 pub mod collections;
+#[cfg(feature = "tools")]
+pub mod columns;
 pub mod common;
+pub mod detect;
+#[cfg(feature = "tools")]
+pub mod diff;
+#[cfg(feature = "tools")]
+pub mod difficulty;
+#[cfg(feature = "tools")]
+pub mod disk_usage;
+#[cfg(feature = "tools")]
+pub mod edit;
 pub mod error;
+#[cfg(feature = "tools")]
+pub mod export;
+#[cfg(feature = "tools")]
+pub mod expr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "tools")]
+pub mod footprint;
+pub mod format;
+#[cfg(feature = "tools")]
+pub mod fuzzy;
+#[cfg(feature = "tools")]
+pub mod hashes;
+#[cfg(feature = "tools")]
+pub mod health;
+#[cfg(feature = "tools")]
+pub mod id_map;
+#[cfg(feature = "tools")]
+pub mod ids;
+#[cfg(feature = "tools")]
+pub mod import;
+#[cfg(feature = "tools")]
+pub mod links;
+#[cfg(feature = "tools")]
+pub mod notes;
+#[cfg(feature = "tools")]
+pub mod osu_cfg;
+#[cfg(feature = "tools")]
+pub mod osu_file;
+#[cfg(feature = "tools")]
+pub mod paging;
+#[cfg(feature = "tools")]
+pub mod paths;
+#[cfg(feature = "tools")]
+pub mod pinboard;
 pub mod prelude;
+#[cfg(feature = "tools")]
+pub mod query;
+pub mod raw;
+#[cfg(feature = "tools")]
+pub mod reconcile;
+#[cfg(feature = "tools")]
+pub mod repair;
+#[cfg(feature = "tools")]
+pub mod report;
+#[cfg(feature = "scores")]
 pub mod scores;
+#[cfg(feature = "tools")]
+pub mod session;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shared;
+#[cfg(feature = "tools")]
+pub mod sort;
+#[cfg(feature = "tools")]
+pub mod stats;
+#[cfg(feature = "synthetic")]
+pub mod synthetic;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "tools")]
+pub mod workspace;
 
+pub use error::Error;
 pub use flagset;
+
+/// Checks that the optional modules are left out without their features, so that the default build only has the
+/// parsing API. Each example only fails to compile while its feature is off.
+#[cfg_attr(
+    not(feature = "writer"),
+    doc = "```compile_fail\nlet _ = osu_db_parser::beatmaps::BeatmapListing::to_bytes;\n```"
+)]
+#[cfg_attr(
+    not(feature = "collections"),
+    doc = "```compile_fail\nuse osu_db_parser::collections::CollectionListing;\n```"
+)]
+#[cfg_attr(
+    not(feature = "scores"),
+    doc = "```compile_fail\nuse osu_db_parser::scores::ScoreListing;\n```"
+)]
+#[cfg_attr(
+    not(feature = "osr"),
+    doc = "```compile_fail\nlet _ = osu_db_parser::scores::ScoreReplay::from_bytes;\n```"
+)]
+#[cfg_attr(
+    not(feature = "tools"),
+    doc = "```compile_fail\nuse osu_db_parser::query::BeatmapFilter;\n```"
+)]
+#[cfg_attr(
+    not(feature = "tools"),
+    doc = "```compile_fail\nuse osu_db_parser::export::Format;\n```"
+)]
+#[cfg(doctest)]
+mod feature_checks {}
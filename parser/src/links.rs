@@ -0,0 +1,193 @@
+//! Helpers for building links to beatmaps and scores on the osu! website.
+
+use crate::{
+    beatmaps::BeatmapEntry,
+    common::GameplayMode,
+    ids::{self, BeatmapSetId},
+    scores::ScoreReplay,
+};
+
+/// Base URL for the osu! website.
+pub const OSU_WEBSITE: &str = "https://osu.ppy.sh";
+
+/// Represents a site that beatmapsets can be downloaded from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mirror {
+    /// The official osu! website (requires being logged in)
+    Official,
+    Beatconnect,
+    Nerinyan,
+    Catboy,
+    Sayobot,
+}
+
+impl Mirror {
+    /// Gets the download link for a beatmapset on this mirror.
+    pub fn download_url(&self, beatmapset_id: BeatmapSetId) -> String {
+        match self {
+            Mirror::Official => format!("{}/beatmapsets/{}/download", OSU_WEBSITE, beatmapset_id),
+            Mirror::Beatconnect => format!("https://beatconnect.io/b/{}", beatmapset_id),
+            Mirror::Nerinyan => format!("https://api.nerinyan.moe/d/{}", beatmapset_id),
+            Mirror::Catboy => format!("https://catboy.best/d/{}", beatmapset_id),
+            Mirror::Sayobot => format!(
+                "https://dl.sayobot.cn/beatmaps/download/full/{}",
+                beatmapset_id
+            ),
+        }
+    }
+}
+
+/// Gets the ruleset name used by the osu! website for a gameplay mode (e.g. `osu`, `fruits`).
+pub fn ruleset_name(mode: GameplayMode) -> &'static str {
+    match mode {
+        GameplayMode::Standard => "osu",
+        GameplayMode::Taiko => "taiko",
+        GameplayMode::Catch => "fruits",
+        GameplayMode::Mania => "mania",
+    }
+}
+
+/// Checks whether an ID read from one of osu!'s databases refers to a submitted beatmap or score.
+///
+/// Unsubmitted beatmaps use either `0` or `-1` (stored as `u32::MAX`) for their IDs. Prefer the typed accessors (e.g.
+/// [`BeatmapEntry::beatmapset_id`]) for beatmaps, which handle this already.
+pub fn is_submitted_id(id: u32) -> bool {
+    ids::is_submitted(id)
+}
+
+/// Gets the link to a beatmap's page on the osu! website.
+///
+/// Returns `None` if the beatmap has not been submitted.
+pub fn beatmap_url(beatmap: &BeatmapEntry) -> Option<String> {
+    // Fields to populate are:
+    // - Beatmapset ID
+    // - Gameplay Mode - #osu, #taiko, #fruits, #mania
+    // - Difficulty ID
+    let beatmapset_id = beatmap.beatmapset_id()?;
+
+    match beatmap.difficulty_id() {
+        Some(difficulty_id) => Some(format!(
+            "{}/beatmapsets/{}#{}/{}",
+            OSU_WEBSITE,
+            beatmapset_id,
+            ruleset_name(beatmap.gameplay_mode),
+            difficulty_id
+        )),
+        None => Some(beatmapset_url(beatmapset_id)),
+    }
+}
+
+/// Gets the link to a beatmapset's page on the osu! website.
+pub fn beatmapset_url(beatmapset_id: BeatmapSetId) -> String {
+    format!("{}/beatmapsets/{}", OSU_WEBSITE, beatmapset_id)
+}
+
+/// Gets the `osu://` link which opens a beatmap in osu!direct from within the game.
+///
+/// Returns `None` if the beatmap has not been submitted.
+pub fn osu_direct_url(beatmap: &BeatmapEntry) -> Option<String> {
+    if let Some(difficulty_id) = beatmap.difficulty_id() {
+        Some(format!("osu://b/{}", difficulty_id))
+    } else {
+        beatmap
+            .beatmapset_id()
+            .map(|beatmapset_id| format!("osu://s/{}", beatmapset_id))
+    }
+}
+
+/// Gets the link to a score's page on the osu! website.
+///
+/// Returns `None` if the score was not submitted online.
+pub fn score_url(score: &ScoreReplay) -> Option<String> {
+    // Fields to populate are:
+    // - Gameplay Mode - osu, taiko, fruits, mania
+    // - Online Score ID
+    if score.online_score_id == 0 || score.online_score_id == u64::MAX {
+        return None;
+    }
+
+    Some(format!(
+        "{}/scores/{}/{}",
+        OSU_WEBSITE,
+        ruleset_name(score.gameplay_mode),
+        score.online_score_id
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beatmap_url_is_correct() {
+        let beatmap = BeatmapEntry {
+            beatmap_id: 39804,
+            difficulty_id: 129891,
+            gameplay_mode: GameplayMode::Mania,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            beatmap_url(&beatmap),
+            Some("https://osu.ppy.sh/beatmapsets/39804#mania/129891".to_string())
+        );
+
+        // Falls back to the beatmapset page if the difficulty ID is unknown
+        let beatmap = BeatmapEntry {
+            beatmap_id: 39804,
+            difficulty_id: 0,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            beatmap_url(&beatmap),
+            Some("https://osu.ppy.sh/beatmapsets/39804".to_string())
+        );
+    }
+
+    #[test]
+    fn osu_direct_url_is_correct() {
+        let beatmap = BeatmapEntry {
+            beatmap_id: 39804,
+            difficulty_id: 129891,
+            ..Default::default()
+        };
+
+        assert_eq!(osu_direct_url(&beatmap), Some("osu://b/129891".to_string()));
+
+        // Falls back to the beatmapset if the difficulty ID is unknown
+        let beatmap = BeatmapEntry {
+            difficulty_id: u32::MAX,
+            ..beatmap
+        };
+
+        assert_eq!(osu_direct_url(&beatmap), Some("osu://s/39804".to_string()));
+        assert_eq!(osu_direct_url(&BeatmapEntry::default()), None);
+    }
+
+    #[test]
+    fn mirror_urls_are_correct() {
+        assert_eq!(
+            Mirror::Official.download_url(BeatmapSetId::new(39804).unwrap()),
+            "https://osu.ppy.sh/beatmapsets/39804/download"
+        );
+
+        assert_eq!(
+            Mirror::Beatconnect.download_url(BeatmapSetId::new(39804).unwrap()),
+            "https://beatconnect.io/b/39804"
+        );
+    }
+
+    #[test]
+    fn unsubmitted_beatmaps_have_no_url() {
+        for beatmap_id in [0, u32::MAX] {
+            let beatmap = BeatmapEntry {
+                beatmap_id,
+                difficulty_id: 129891,
+                ..Default::default()
+            };
+
+            assert_eq!(beatmap_url(&beatmap), None);
+        }
+    }
+}
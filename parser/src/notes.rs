@@ -0,0 +1,161 @@
+//! Personal notes and bookmarks for beatmaps, which are kept separately from osu!'s own files.
+//!
+//! Notes are keyed by MD5 hash rather than by position or ID, so they stay attached to a beatmap across rebuilds of
+//! `osu!.db` and can be moved between machines.
+
+use std::collections::BTreeMap;
+
+use crate::beatmaps::BeatmapEntry;
+
+/// Represents the user's note about a beatmap.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Note {
+    /// Whether the beatmap has been bookmarked
+    pub starred: bool,
+
+    /// Free text, e.g. `accuracy practice`
+    pub text: String,
+}
+
+/// A set of notes, by the MD5 hash of the beatmap they're about.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Notes {
+    pub notes: BTreeMap<String, Note>,
+}
+
+/// Represents a note whose beatmap isn't in the beatmap listing, e.g. because it was deleted or updated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrphanedNote<'a> {
+    pub md5: &'a str,
+    pub note: &'a Note,
+}
+
+impl Note {
+    /// Checks whether the note has nothing in it, in which case it doesn't need to be kept.
+    pub fn is_empty(&self) -> bool {
+        !self.starred && self.text.trim().is_empty()
+    }
+}
+
+impl Notes {
+    /// Gets the note for a beatmap, if there is one.
+    pub fn get(&self, beatmap: &BeatmapEntry) -> Option<&Note> {
+        beatmap.md5.as_ref().and_then(|md5| self.notes.get(md5))
+    }
+
+    /// Sets the note for a beatmap by its MD5 hash, removing it if it's empty.
+    pub fn set(&mut self, md5: &str, note: Note) {
+        if note.is_empty() {
+            self.notes.remove(md5);
+        } else {
+            self.notes.insert(md5.to_string(), note);
+        }
+    }
+
+    /// Adds notes from another set, e.g. one exported from a different machine. Imported notes replace any existing
+    /// notes for the same beatmap.
+    ///
+    /// Returns the number of notes that were added or changed.
+    pub fn merge(&mut self, other: Notes) -> usize {
+        let mut changed = 0;
+
+        for (md5, note) in other.notes {
+            if note.is_empty() || self.notes.get(&md5) == Some(&note) {
+                continue;
+            }
+
+            self.notes.insert(md5, note);
+            changed += 1;
+        }
+
+        changed
+    }
+
+    /// Finds the notes whose beatmaps aren't in `beatmaps`, in order of MD5 hash.
+    pub fn orphaned(&self, beatmaps: &[BeatmapEntry]) -> Vec<OrphanedNote<'_>> {
+        let mut remaining = self
+            .notes
+            .iter()
+            .map(|(md5, note)| (md5.as_str(), note))
+            .collect::<BTreeMap<_, _>>();
+
+        for md5 in beatmaps.iter().filter_map(|beatmap| beatmap.md5.as_deref()) {
+            remaining.remove(md5);
+        }
+
+        remaining
+            .into_iter()
+            .map(|(md5, note)| OrphanedNote { md5, note })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(starred: bool, text: &str) -> Note {
+        Note {
+            starred,
+            text: text.to_string(),
+        }
+    }
+
+    fn beatmap(md5: &str) -> BeatmapEntry {
+        BeatmapEntry {
+            md5: Some(md5.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn empty_notes_are_removed() {
+        let mut notes = Notes::default();
+
+        notes.set("a", note(true, ""));
+        notes.set("b", note(false, "farm"));
+        assert_eq!(notes.notes.len(), 2);
+
+        notes.set("a", note(false, "  "));
+        assert_eq!(notes.notes.len(), 1);
+        assert_eq!(notes.get(&beatmap("b")), Some(&note(false, "farm")));
+        assert_eq!(notes.get(&beatmap("a")), None);
+    }
+
+    #[test]
+    fn imported_notes_replace_existing_ones() {
+        let mut notes = Notes::default();
+        notes.set("a", note(true, "old"));
+        notes.set("b", note(false, "same"));
+
+        let mut imported = Notes::default();
+        imported.set("a", note(false, "new"));
+        imported.set("b", note(false, "same"));
+        imported.set("c", note(true, ""));
+
+        assert_eq!(notes.merge(imported), 2);
+        assert_eq!(notes.notes["a"], note(false, "new"));
+        assert_eq!(notes.notes.len(), 3);
+    }
+
+    #[test]
+    fn orphaned_notes_are_found() {
+        let mut notes = Notes::default();
+        notes.set("a", note(true, ""));
+        notes.set("b", note(false, "remap"));
+
+        let orphaned = notes.orphaned(&[beatmap("a"), beatmap("c")]);
+
+        assert_eq!(
+            orphaned,
+            vec![OrphanedNote {
+                md5: "b",
+                note: &note(false, "remap")
+            }]
+        );
+    }
+}
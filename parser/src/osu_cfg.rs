@@ -0,0 +1,330 @@
+//! Reading of the few settings needed from osu!'s user config files, i.e. `osu!.<user>.cfg` in the installation folder.
+//!
+//! Each Windows user that has run osu! has their own config file, named after their Windows user name, which holds
+//! lines of the form `Key = Value`. Only `Username` (the osu! account that was last logged in) and `BeatmapDirectory`
+//! (where beatmaps are kept, if they've been moved out of the `Songs` folder) are read.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Represents the settings read from a user config file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OsuConfig {
+    /// Name of the osu! account that was last logged in
+    pub username: Option<String>,
+
+    /// Folder that beatmaps are kept in, exactly as written in the file
+    pub beatmap_directory: Option<String>,
+}
+
+impl OsuConfig {
+    /// Parses the settings from the contents of a config file. Missing or empty settings are `None`.
+    pub fn parse(text: &str) -> OsuConfig {
+        let mut config = OsuConfig::default();
+
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+
+            let key = key.trim();
+            let value = Some(value.trim().to_string()).filter(|value| !value.is_empty());
+
+            if key.eq_ignore_ascii_case("Username") {
+                config.username = value;
+            } else if key.eq_ignore_ascii_case("BeatmapDirectory") {
+                config.beatmap_directory = value;
+            }
+        }
+
+        config
+    }
+
+    /// Reads the settings from a config file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> std::io::Result<OsuConfig> {
+        Ok(OsuConfig::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Gets the folder that beatmaps are kept in for an installation in `root`, if it has been set (see
+    /// [`resolve_directory`]).
+    pub fn songs_folder<P: AsRef<Path>>(&self, root: P) -> Option<PathBuf> {
+        let directory = self.beatmap_directory.as_deref()?;
+        Some(resolve_directory(root.as_ref(), directory))
+    }
+}
+
+/// Finds and reads the user config file for an installation in `root`.
+///
+/// If there's more than one (i.e. several Windows users share the installation), the one logged in to `username` is
+/// used if given, otherwise the most recently modified one. An empty name (e.g. from a guest session) counts as no
+/// name. `osu!.cfg` holds the settings shared by every user, so it's never used. Returns `None` if there's no readable
+/// user config file.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn user_config<P: AsRef<Path>>(root: P, username: Option<&str>) -> Option<OsuConfig> {
+    let mut configs = std::fs::read_dir(root)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_ascii_lowercase();
+            name.starts_with("osu!.") && name.ends_with(".cfg") && name != "osu!.cfg"
+        })
+        .filter_map(|entry| {
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            let config = OsuConfig::from_file(entry.path()).ok()?;
+            Some((modified, entry.file_name(), config))
+        })
+        .collect::<Vec<_>>();
+
+    // Most recently modified first, using the name to break ties so that the choice doesn't depend on the file system
+    configs.sort_by(|(a_modified, a_name, _), (b_modified, b_name, _)| {
+        b_modified.cmp(a_modified).then_with(|| a_name.cmp(b_name))
+    });
+
+    let logged_in = username
+        .filter(|username| !username.trim().is_empty())
+        .and_then(|username| {
+            configs.iter().position(|(_, _, config)| {
+                config
+                    .username
+                    .as_deref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(username))
+            })
+        });
+
+    let (_, _, config) = configs.into_iter().nth(logged_in.unwrap_or(0))?;
+    Some(config)
+}
+
+/// Resolves a folder from a config file against the installation folder `root`.
+///
+/// osu! runs on Windows, so the folder may use either kind of slash. Relative folders (e.g. `Beatmaps` or
+/// `..\Beatmaps`) are relative to the installation, which is how portable installations usually keep their beatmaps.
+/// Absolute Windows folders (e.g. `D:\Beatmaps`) are used as they are on Windows, and found inside the Wine prefix
+/// elsewhere if the installation is in one.
+pub fn resolve_directory(root: &Path, directory: &str) -> PathBuf {
+    let directory = directory.trim().trim_matches('"');
+
+    if let Some((drive, rest)) = windows_drive(directory) {
+        if cfg!(windows) {
+            return PathBuf::from(directory);
+        }
+
+        return match wine_drive(root, drive) {
+            Some(drive) => join_components(drive, rest),
+            None => PathBuf::from(directory),
+        };
+    }
+
+    if Path::new(directory).is_absolute() {
+        return PathBuf::from(directory);
+    }
+
+    join_components(root.to_path_buf(), directory)
+}
+
+/// Splits an absolute Windows path (e.g. `D:\Beatmaps` or `d:/Beatmaps`) into its drive letter and the rest.
+fn windows_drive(path: &str) -> Option<(char, &str)> {
+    let mut chars = path.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+
+    let rest = chars.as_str().strip_prefix(':')?;
+    rest.starts_with(['\\', '/'])
+        .then(|| (drive.to_ascii_lowercase(), rest))
+}
+
+/// Finds the folder that a Windows drive is mapped to in the Wine prefix containing `root`, i.e. `drive_c` for the C:
+/// drive and the links in `dosdevices` for the others.
+fn wine_drive(root: &Path, drive: char) -> Option<PathBuf> {
+    let prefix = root
+        .ancestors()
+        .find(|path| path.file_name().is_some_and(|name| name == "drive_c"))?
+        .parent()?;
+
+    Some(match drive {
+        'c' => prefix.join("drive_c"),
+        drive => prefix.join("dosdevices").join(format!("{}:", drive)),
+    })
+}
+
+/// Joins a path using either kind of slash onto `base`, resolving `.` and `..` without touching the file system.
+fn join_components(mut base: PathBuf, path: &str) -> PathBuf {
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if !matches!(base.components().next_back(), Some(Component::Normal(_)))
+                    || !base.pop()
+                {
+                    base.push("..");
+                }
+            }
+            component => base.push(component),
+        }
+    }
+
+    base
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+
+    /// Creates an empty folder in the temp dir for a test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("osu-db-cfg-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a config file, setting its modification time to `age` seconds ago.
+    fn write_config(root: &Path, name: &str, contents: &str, age: u64) {
+        let path = root.join(name);
+        std::fs::write(&path, contents).unwrap();
+
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(age))
+            .unwrap();
+    }
+
+    #[test]
+    fn settings_are_parsed() {
+        let config = OsuConfig::parse(
+            "# osu! configuration for Player\n\
+             # last updated on Tuesday, 2 January 2024\n\
+             \n\
+             VolumeUniversal = 100\n\
+             username = peppy\n\
+             BeatmapDirectory = D:\\osu! Songs\n\
+             Password = \n",
+        );
+
+        assert_eq!(
+            config,
+            OsuConfig {
+                username: Some("peppy".to_string()),
+                beatmap_directory: Some("D:\\osu! Songs".to_string()),
+            }
+        );
+        assert_eq!(
+            OsuConfig::parse("BeatmapDirectory =\n"),
+            OsuConfig::default()
+        );
+    }
+
+    #[test]
+    fn directories_are_resolved() {
+        let root = Path::new("/home/player/osu!");
+
+        // Relative folders, with either kind of slash
+        for directory in ["Beatmaps", "Beatmaps\\", "./Beatmaps", "\"Beatmaps\""] {
+            assert_eq!(
+                resolve_directory(root, directory),
+                PathBuf::from("/home/player/osu!/Beatmaps")
+            );
+        }
+
+        for directory in ["Beatmaps\\Ranked", "Beatmaps/Ranked", "Beatmaps\\.\\Ranked"] {
+            assert_eq!(
+                resolve_directory(root, directory),
+                PathBuf::from("/home/player/osu!/Beatmaps/Ranked")
+            );
+        }
+
+        for directory in ["..\\Beatmaps", "../Beatmaps"] {
+            assert_eq!(
+                resolve_directory(root, directory),
+                PathBuf::from("/home/player/Beatmaps")
+            );
+        }
+
+        assert_eq!(
+            resolve_directory(Path::new("osu!"), "..\\..\\Beatmaps"),
+            PathBuf::from("../Beatmaps")
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn absolute_directories_are_resolved() {
+        let root = Path::new("/home/player/.wine/drive_c/osu!");
+
+        assert_eq!(
+            resolve_directory(root, "/mnt/games/Beatmaps"),
+            PathBuf::from("/mnt/games/Beatmaps")
+        );
+
+        // Windows drives are found inside the Wine prefix
+        assert_eq!(
+            resolve_directory(root, "C:\\Games\\Beatmaps"),
+            PathBuf::from("/home/player/.wine/drive_c/Games/Beatmaps")
+        );
+        assert_eq!(
+            resolve_directory(root, "d:/Beatmaps"),
+            PathBuf::from("/home/player/.wine/dosdevices/d:/Beatmaps")
+        );
+
+        // Without a Wine prefix, there's nowhere to look for the drive
+        assert_eq!(
+            resolve_directory(Path::new("/home/player/osu!"), "D:\\Beatmaps"),
+            PathBuf::from("D:\\Beatmaps")
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn absolute_directories_are_resolved() {
+        assert_eq!(
+            resolve_directory(Path::new(r"C:\osu!"), r"D:\Beatmaps"),
+            PathBuf::from(r"D:\Beatmaps")
+        );
+        assert_eq!(
+            resolve_directory(Path::new(r"C:\osu!"), "D:/Beatmaps"),
+            PathBuf::from("D:/Beatmaps")
+        );
+    }
+
+    #[test]
+    fn user_configs_are_found() {
+        let root = temp_dir("users");
+
+        // The shared config file isn't a user config file
+        write_config(&root, "osu!.cfg", "BeatmapDirectory = Shared\n", 0);
+        let none = user_config(&root, None);
+
+        write_config(
+            &root,
+            "osu!.Alice.cfg",
+            "Username = alice\nBeatmapDirectory = Alice\n",
+            300,
+        );
+        write_config(
+            &root,
+            "osu!.Bob.cfg",
+            "Username = bob\nBeatmapDirectory = D:\\Bob\n",
+            60,
+        );
+
+        // The most recently modified config is used, unless another one is logged in to the player's account
+        let recent = user_config(&root, None);
+        let alice = user_config(&root, Some("Alice"));
+        let unknown = user_config(&root, Some("carol"));
+        let guest = user_config(&root, Some(""));
+
+        std::fs::remove_dir_all(&root).ok();
+
+        assert_eq!(none, None);
+        assert_eq!(recent.unwrap().username.as_deref(), Some("bob"));
+        assert_eq!(alice.unwrap().songs_folder(&root), Some(root.join("Alice")));
+        assert_eq!(unknown.unwrap().username.as_deref(), Some("bob"));
+        assert_eq!(guest.unwrap().username.as_deref(), Some("bob"));
+    }
+}
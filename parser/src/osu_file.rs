@@ -0,0 +1,252 @@
+//! Minimal reading of `.osu` beatmap files, for the few details that aren't stored in `osu!.db`.
+//!
+//! This isn't a full `.osu` parser - only the lines needed for each detail are looked at.
+
+use std::{io, path::Path};
+
+/// Represents the metadata and difficulty settings stored in a `.osu` file, which `osu!.db` keeps a copy of. Keys
+/// missing from the file are `None`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OsuMetadata {
+    pub title: Option<String>,
+    pub title_unicode: Option<String>,
+    pub artist: Option<String>,
+    pub artist_unicode: Option<String>,
+    pub creator: Option<String>,
+
+    /// Name of the difficulty
+    pub version: Option<String>,
+
+    /// Difficulty ID, stored as in `osu!.db` (i.e. `-1` is `u32::MAX`)
+    pub beatmap_id: Option<u32>,
+
+    /// Beatmapset ID, stored as in `osu!.db` (i.e. `-1` is `u32::MAX`)
+    pub beatmapset_id: Option<u32>,
+
+    pub hp_drain: Option<f32>,
+    pub circle_size: Option<f32>,
+    pub overall_difficulty: Option<f32>,
+
+    /// Approach rate, which is the same as the overall difficulty in files written before it had its own setting
+    pub approach_rate: Option<f32>,
+}
+
+/// Reads the `[Metadata]` and `[Difficulty]` sections of a `.osu` file. Unknown keys and values which can't be parsed
+/// are ignored.
+pub fn metadata(contents: &str) -> OsuMetadata {
+    let contents = contents.trim_start_matches('\u{feff}');
+    let mut metadata = OsuMetadata::default();
+
+    let text = |value: &str| Some(value.to_string());
+    let id = |value: &str| value.parse::<i64>().ok().map(|id| id as u32);
+    let setting = |value: &str| value.parse::<f32>().ok().filter(|v| v.is_finite());
+
+    for (key, value) in key_values(contents, "Metadata") {
+        match key {
+            "Title" => metadata.title = text(value),
+            "TitleUnicode" => metadata.title_unicode = text(value),
+            "Artist" => metadata.artist = text(value),
+            "ArtistUnicode" => metadata.artist_unicode = text(value),
+            "Creator" => metadata.creator = text(value),
+            "Version" => metadata.version = text(value),
+            "BeatmapID" => metadata.beatmap_id = id(value),
+            "BeatmapSetID" => metadata.beatmapset_id = id(value),
+            _ => {}
+        }
+    }
+
+    for (key, value) in key_values(contents, "Difficulty") {
+        match key {
+            "HPDrainRate" => metadata.hp_drain = setting(value),
+            "CircleSize" => metadata.circle_size = setting(value),
+            "OverallDifficulty" => metadata.overall_difficulty = setting(value),
+            "ApproachRate" => metadata.approach_rate = setting(value),
+            _ => {}
+        }
+    }
+
+    metadata.approach_rate = metadata.approach_rate.or(metadata.overall_difficulty);
+    metadata
+}
+
+/// Reads the `[Metadata]` and `[Difficulty]` sections of a `.osu` file on disk (see [`metadata`]).
+///
+/// Very old beatmaps may not be UTF-8, so invalid characters are replaced rather than failing.
+pub fn read_metadata<P: AsRef<Path>>(path: P) -> io::Result<OsuMetadata> {
+    let contents = std::fs::read(path)?;
+    Ok(metadata(&String::from_utf8_lossy(&contents)))
+}
+
+/// Finds the file name of a beatmap's background image from the contents of its `.osu` file.
+///
+/// Returns `None` if the beatmap doesn't have a background image, e.g. because it only has a video.
+pub fn background_filename(contents: &str) -> Option<String> {
+    section_lines(contents, "Events").find_map(|line| {
+        let fields = split_fields(line);
+
+        match fields.as_slice() {
+            [kind, _, filename, ..] if *kind == "0" || *kind == "Background" => {
+                Some(filename.to_string()).filter(|f| !f.is_empty())
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Gets the `Key: Value` pairs in a section (e.g. `[Metadata]`), with spaces around the key and value removed.
+fn key_values<'a>(contents: &'a str, section: &'a str) -> impl Iterator<Item = (&'a str, &'a str)> {
+    section_lines(contents, section)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+/// Gets the lines of a section (e.g. `[Events]`), skipping blank lines and comments.
+fn section_lines<'a>(contents: &'a str, section: &'a str) -> impl Iterator<Item = &'a str> {
+    contents
+        .lines()
+        .map(str::trim)
+        .skip_while(move |line| {
+            line.strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+                != Some(section)
+        })
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+}
+
+/// Splits a comma separated line into its fields, removing the quotes from quoted fields (which may contain commas).
+fn split_fields(line: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut rest = line;
+
+    loop {
+        let field;
+
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            field = &quoted[..end];
+            rest = quoted.get(end + 1..).unwrap_or_default();
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            field = &rest[..end];
+            rest = &rest[end..];
+        }
+
+        fields.push(field.trim());
+
+        match rest.find(',') {
+            Some(comma) => rest = rest[comma + 1..].trim_start(),
+            None => return fields,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backgrounds_are_found() {
+        let contents = r#"osu file format v14
+
+[General]
+AudioFilename: audio.mp3
+
+[Events]
+//Background and Video events
+Video,0,"intro.mp4"
+0,0,"bg, but with a comma.jpg",0,0
+//Break Periods
+2,84566,91316
+
+[TimingPoints]
+0,"not a background.jpg",0
+"#;
+
+        assert_eq!(
+            background_filename(contents),
+            Some("bg, but with a comma.jpg".to_string())
+        );
+
+        // Older beatmaps may not quote the file name
+        assert_eq!(
+            background_filename("[Events]\r\nBackground,0,bg.png\r\n"),
+            Some("bg.png".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_backgrounds_are_handled() {
+        assert_eq!(background_filename(""), None);
+        assert_eq!(
+            background_filename("[Events]\n1,0,\"video.avi\"\n[TimingPoints]\n0,0,\"bg.jpg\""),
+            None
+        );
+        assert_eq!(background_filename("[General]\n0,0,\"bg.jpg\""), None);
+    }
+
+    #[test]
+    fn metadata_is_read() {
+        let contents = "\u{feff}osu file format v14\r\n\
+            \r\n\
+            [Metadata]\r\n\
+            Title:Exit This Earth's Atomosphere\r\n\
+            TitleUnicode:Exit This Earth's Atomosphere\r\n\
+            Artist:Camellia\r\n\
+            Creator: Sotarks \r\n\
+            Version:Time: 2:30\r\n\
+            BeatmapID:-1\r\n\
+            BeatmapSetID:1016297\r\n\
+            \r\n\
+            [Difficulty]\r\n\
+            HPDrainRate:5\r\n\
+            CircleSize:4.2\r\n\
+            OverallDifficulty:9\r\n\
+            ApproachRate:9.5\r\n";
+
+        assert_eq!(
+            metadata(contents),
+            OsuMetadata {
+                title: Some("Exit This Earth's Atomosphere".to_string()),
+                title_unicode: Some("Exit This Earth's Atomosphere".to_string()),
+                artist: Some("Camellia".to_string()),
+                artist_unicode: None,
+                creator: Some("Sotarks".to_string()),
+                version: Some("Time: 2:30".to_string()),
+                beatmap_id: Some(u32::MAX),
+                beatmapset_id: Some(1016297),
+                hp_drain: Some(5.0),
+                circle_size: Some(4.2),
+                overall_difficulty: Some(9.0),
+                approach_rate: Some(9.5),
+            }
+        );
+    }
+
+    #[test]
+    fn missing_metadata_is_handled() {
+        assert_eq!(metadata(""), OsuMetadata::default());
+
+        // Old beatmaps use the overall difficulty as the approach rate
+        let old = metadata(
+            "[Difficulty]\nOverallDifficulty:7\nCircleSize:lots\n[Events]\nTitle:Not metadata",
+        );
+        assert_eq!(old.approach_rate, Some(7.0));
+        assert_eq!(old.circle_size, None);
+        assert_eq!(old.title, None);
+    }
+
+    #[test]
+    fn fields_are_split() {
+        assert_eq!(
+            split_fields(r#"0,0,"a, b.jpg",0,0"#),
+            vec!["0", "0", "a, b.jpg", "0", "0"]
+        );
+        assert_eq!(split_fields("0, 0 ,bg.jpg"), vec!["0", "0", "bg.jpg"]);
+        assert_eq!(
+            split_fields(r#"0,0,"unterminated"#),
+            vec!["0", "0", "unterminated"]
+        );
+    }
+}
@@ -0,0 +1,413 @@
+//! Paging through the beatmaps matching a filter in a sorted order, e.g. for a web service which shows one page of
+//! results at a time.
+//!
+//! Pages can be requested by position, or by continuing from a [`Cursor`] at the end of the previous page. Cursors hold
+//! the last beatmap's sort value and MD5 hash, so continuing from one doesn't skip or repeat beatmaps even if they tie
+//! on the sort column, or beatmaps before the cursor are added or removed in the meantime.
+
+use std::cmp::Ordering;
+
+use crate::{
+    beatmaps::{BeatmapEntry, BeatmapListing},
+    columns::ColumnValue,
+    query::BeatmapFilter,
+    sort::SortKey,
+};
+
+/// Number of sorted results kept by a [`PageCache`], so that switching between a few searches doesn't sort again.
+const CACHED_QUERIES: usize = 8;
+
+/// Represents where a page starts, and how many beatmaps it has at most.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageRequest {
+    pub start: PageStart,
+    pub limit: usize,
+}
+
+/// Represents where a page starts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PageStart {
+    /// The number of matching beatmaps to skip
+    Offset(usize),
+
+    /// Straight after the beatmap that a previous page ended with
+    After(Cursor),
+}
+
+/// Marks the position of a beatmap in a sorted list of results, to continue from in the next page.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cursor {
+    value: ColumnValue<'static>,
+    md5: Option<String>,
+
+    /// Index of the beatmap in the listing, which only separates beatmaps with the same MD5 hash (or none)
+    index: usize,
+}
+
+/// Represents one page of results.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Page<'a> {
+    /// Beatmaps on the page, in order
+    pub beatmaps: Vec<&'a BeatmapEntry>,
+
+    /// Index of each beatmap in the listing
+    pub indices: Vec<usize>,
+
+    /// Position of the first beatmap on the page among all the results
+    pub offset: usize,
+
+    /// Total number of beatmaps matching the filter
+    pub total: usize,
+
+    /// Where the next page starts, or `None` if this is the last page
+    pub next: Option<Cursor>,
+}
+
+/// Keeps the sorted results of recent queries, so that each page of a query doesn't filter and sort the whole listing
+/// again.
+///
+/// Results are thrown away when the listing changes, which is noticed from the beatmaps' MD5 hashes and the times they
+/// were modified and played. Other changes (e.g. editing a beatmap's local offset) aren't noticed, so call
+/// [`PageCache::clear`] after making them.
+#[derive(Clone, Debug, Default)]
+pub struct PageCache {
+    /// Fingerprint of the listing the results are from
+    fingerprint: u64,
+
+    /// Results of each query, most recently used last
+    queries: Vec<CachedQuery>,
+}
+
+/// Represents the sorted indices of the beatmaps matching a filter.
+#[derive(Clone, Debug)]
+struct CachedQuery {
+    filter: BeatmapFilter,
+    sort: SortKey,
+    indices: Vec<usize>,
+}
+
+impl BeatmapListing {
+    /// Gets a page of the beatmaps matching `filter`, sorted by `sort`. Beatmaps with the same sort value are ordered
+    /// by MD5 hash, so the order is the same every time.
+    ///
+    /// This filters and sorts the whole listing, so use a [`PageCache`] when requesting several pages.
+    pub fn query_page(
+        &self,
+        filter: &BeatmapFilter,
+        sort: SortKey,
+        page: &PageRequest,
+    ) -> Page<'_> {
+        PageCache::default().page(self, filter, sort, page)
+    }
+}
+
+impl PageCache {
+    /// Gets a page of the beatmaps matching `filter`, sorted by `sort`, like [`BeatmapListing::query_page`]. The results
+    /// are only filtered and sorted the first time, unless the listing has changed.
+    pub fn page<'a>(
+        &mut self,
+        listing: &'a BeatmapListing,
+        filter: &BeatmapFilter,
+        sort: SortKey,
+        page: &PageRequest,
+    ) -> Page<'a> {
+        let fingerprint = fingerprint(listing);
+        if fingerprint != self.fingerprint {
+            self.fingerprint = fingerprint;
+            self.queries.clear();
+        }
+
+        let position = self
+            .queries
+            .iter()
+            .position(|query| query.sort == sort && query.filter == *filter);
+
+        let query = match position {
+            Some(position) => self.queries.remove(position),
+            None => CachedQuery {
+                filter: filter.clone(),
+                sort,
+                indices: sorted_matches(&listing.beatmaps, filter, sort),
+            },
+        };
+
+        if self.queries.len() >= CACHED_QUERIES {
+            self.queries.remove(0);
+        }
+
+        self.queries.push(query);
+        let indices = &self.queries[self.queries.len() - 1].indices;
+
+        let offset = match &page.start {
+            PageStart::Offset(offset) => (*offset).min(indices.len()),
+            PageStart::After(cursor) => {
+                indices.partition_point(|&i| compare(&listing.beatmaps, sort, i, cursor).is_le())
+            }
+        };
+
+        let end = offset.saturating_add(page.limit).min(indices.len());
+        let page_indices = indices[offset..end].to_vec();
+
+        let next = page_indices
+            .last()
+            .filter(|_| end < indices.len())
+            .map(|&i| Cursor {
+                value: sort_value(&listing.beatmaps[i], sort).into_owned(),
+                md5: listing.beatmaps[i].md5.clone(),
+                index: i,
+            });
+
+        Page {
+            beatmaps: page_indices.iter().map(|&i| &listing.beatmaps[i]).collect(),
+            indices: page_indices,
+            offset,
+            total: indices.len(),
+            next,
+        }
+    }
+
+    /// Forgets every cached result, e.g. after changing the listing in a way that isn't noticed automatically.
+    pub fn clear(&mut self) {
+        self.queries.clear();
+    }
+}
+
+/// Gets the value a beatmap is sorted by.
+fn sort_value(beatmap: &BeatmapEntry, sort: SortKey) -> ColumnValue<'_> {
+    sort.column.value_with(beatmap, sort.language, sort.stars)
+}
+
+/// Finds the beatmaps matching a filter, in the order given by [`compare`].
+fn sorted_matches(beatmaps: &[BeatmapEntry], filter: &BeatmapFilter, sort: SortKey) -> Vec<usize> {
+    let mut keyed = beatmaps
+        .iter()
+        .enumerate()
+        .filter(|(_, beatmap)| filter.matches(beatmap))
+        .map(|(i, beatmap)| (sort_value(beatmap, sort), beatmap.md5.as_deref(), i))
+        .collect::<Vec<_>>();
+
+    keyed.sort_by(|a, b| {
+        sort.compare_values(&a.0, &b.0)
+            .then_with(|| a.1.cmp(&b.1))
+            .then_with(|| a.2.cmp(&b.2))
+    });
+
+    keyed.into_iter().map(|(_, _, i)| i).collect()
+}
+
+/// Compares the beatmap at index `i` with the position of a cursor, in the same order as [`sorted_matches`].
+fn compare(beatmaps: &[BeatmapEntry], sort: SortKey, i: usize, cursor: &Cursor) -> Ordering {
+    let beatmap = &beatmaps[i];
+
+    sort.compare_values(&sort_value(beatmap, sort), &cursor.value)
+        .then_with(|| beatmap.md5.as_deref().cmp(&cursor.md5.as_deref()))
+        .then_with(|| i.cmp(&cursor.index))
+}
+
+/// Hashes the parts of a listing that usually change when osu! updates it, using FNV-1a.
+fn fingerprint(listing: &BeatmapListing) -> u64 {
+    let mut hash = 0xcbf29ce484222325_u64;
+    let mut add = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3);
+        }
+    };
+
+    add(&listing.version.to_le_bytes());
+    add(&(listing.beatmaps.len() as u64).to_le_bytes());
+
+    for beatmap in &listing.beatmaps {
+        add(beatmap.md5.as_deref().unwrap_or_default().as_bytes());
+        add(&beatmap
+            .last_modification_time
+            .unix_timestamp()
+            .to_le_bytes());
+        add(&beatmap.last_played.unix_timestamp().to_le_bytes());
+    }
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{beatmaps::RankedStatus, columns::Column, common::WINDOWS_EPOCH, sort::SortOrder};
+
+    fn listing() -> BeatmapListing {
+        // Many beatmaps share each creator, so most of them tie on the sort column
+        let beatmaps = (0..50)
+            .map(|i| BeatmapEntry {
+                md5: Some(format!("{:032x}", (i * 7919) % 50)),
+                creator_name: Some(["Mapper", "mapper", "Other", "Zed"][i % 4].to_string()),
+                ranked_status: if i % 5 == 0 {
+                    RankedStatus::Unsubmitted
+                } else {
+                    RankedStatus::Ranked
+                },
+                ..Default::default()
+            })
+            .collect();
+
+        BeatmapListing {
+            version: 20250108,
+            folder_count: 50,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps,
+            user_permissions: Default::default(),
+        }
+    }
+
+    /// Follows cursors from the first page until the last, collecting every beatmap's index.
+    fn every_page(
+        cache: &mut PageCache,
+        listing: &BeatmapListing,
+        filter: &BeatmapFilter,
+        sort: SortKey,
+        limit: usize,
+    ) -> Vec<usize> {
+        let mut request = PageRequest {
+            start: PageStart::Offset(0),
+            limit,
+        };
+        let mut indices = Vec::new();
+
+        loop {
+            let page = cache.page(listing, filter, sort, &request);
+            assert!(page.indices.len() <= limit);
+            assert_eq!(page.offset, indices.len());
+            indices.extend(&page.indices);
+
+            match page.next {
+                Some(cursor) => request.start = PageStart::After(cursor),
+                None => return indices,
+            }
+        }
+    }
+
+    #[test]
+    fn cursors_continue_without_duplicates_or_gaps() {
+        let listing = listing();
+        let filter = BeatmapFilter::parse("status=ranked").unwrap();
+        let mut cache = PageCache::default();
+
+        for order in [SortOrder::Ascending, SortOrder::Descending] {
+            let sort = SortKey {
+                order,
+                ..SortKey::new(Column::Creator)
+            };
+            let all = listing
+                .query_page(
+                    &filter,
+                    sort,
+                    &PageRequest {
+                        start: PageStart::Offset(0),
+                        limit: usize::MAX,
+                    },
+                )
+                .indices;
+
+            assert_eq!(all.len(), 40);
+
+            for limit in [1, 3, 7, 40, 100] {
+                let paged = every_page(&mut cache, &listing, &filter, sort, limit);
+                assert_eq!(paged, all, "{:?} {}", order, limit);
+                assert_eq!(paged.iter().collect::<HashSet<_>>().len(), paged.len());
+            }
+
+            // Beatmaps are sorted by creator, then by MD5 hash
+            let keys = all
+                .iter()
+                .map(|&i| {
+                    let beatmap = &listing.beatmaps[i];
+                    (
+                        beatmap.creator_name.as_deref().unwrap().to_lowercase(),
+                        beatmap.md5.clone(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let mut sorted = keys.clone();
+            sorted.sort();
+
+            if order == SortOrder::Descending {
+                sorted.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+            }
+
+            assert_eq!(keys, sorted);
+        }
+    }
+
+    #[test]
+    fn cursors_survive_removed_beatmaps() {
+        let mut listing = listing();
+        let filter = BeatmapFilter::default();
+        let sort = SortKey::new(Column::Creator);
+        let mut cache = PageCache::default();
+
+        let first = cache.page(
+            &listing,
+            &filter,
+            sort,
+            &PageRequest {
+                start: PageStart::Offset(0),
+                limit: 10,
+            },
+        );
+        let cursor = first.next.clone().unwrap();
+        let last_md5 = first.beatmaps.last().unwrap().md5.clone();
+        let seen = first
+            .beatmaps
+            .iter()
+            .map(|b| b.md5.clone())
+            .collect::<Vec<_>>();
+
+        // The last beatmap on the page is removed before the next page is requested
+        listing.beatmaps.retain(|beatmap| beatmap.md5 != last_md5);
+
+        let second = cache.page(
+            &listing,
+            &filter,
+            sort,
+            &PageRequest {
+                start: PageStart::After(cursor),
+                limit: 100,
+            },
+        );
+
+        assert_eq!(second.offset, 9);
+        assert_eq!(second.total, 49);
+        assert_eq!(second.next, None);
+        assert!(second.beatmaps.iter().all(|b| !seen.contains(&b.md5)));
+        assert_eq!(seen.len() - 1 + second.beatmaps.len(), 49);
+    }
+
+    #[test]
+    fn results_are_cached_until_the_listing_changes() {
+        let mut listing = listing();
+        let filter = BeatmapFilter::default();
+        let sort = SortKey::new(Column::Creator);
+        let request = PageRequest {
+            start: PageStart::Offset(45),
+            limit: 10,
+        };
+        let mut cache = PageCache::default();
+
+        assert_eq!(
+            cache.page(&listing, &filter, sort, &request).beatmaps.len(),
+            5
+        );
+        assert_eq!(cache.queries.len(), 1);
+
+        cache.page(&listing, &filter, sort, &request);
+        assert_eq!(cache.queries.len(), 1);
+
+        listing.beatmaps.truncate(40);
+        let page = cache.page(&listing, &filter, sort, &request);
+        assert_eq!((page.offset, page.total), (40, 40));
+        assert!(page.beatmaps.is_empty());
+        assert_eq!(page.next, None);
+    }
+}
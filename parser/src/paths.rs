@@ -0,0 +1,483 @@
+//! Helpers for locating files inside an osu! installation folder.
+
+use std::path::{Path, PathBuf};
+
+use time::{macros::datetime, OffsetDateTime};
+
+use crate::{beatmaps::BeatmapEntry, ids::BeatmapSetId, osu_cfg, scores::ScoreReplay};
+
+/// The epoch used by Windows file times, which osu! uses when naming replay files.
+const FILE_TIME_EPOCH: OffsetDateTime = datetime!(1601-01-01 0:00 UTC);
+
+/// Characters which can't be used in Windows file names, other than control characters. osu! removes these when naming
+/// files and folders.
+const INVALID_FILE_NAME_CHARS: [char; 9] = ['"', '*', '/', ':', '<', '>', '?', '\\', '|'];
+
+/// Represents the files found in an osu! installation folder.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OsuFolder {
+    /// The installation folder itself
+    pub root: PathBuf,
+
+    /// `osu!.db`, if it exists
+    pub beatmaps: Option<PathBuf>,
+
+    /// `collection.db`, if it exists
+    pub collections: Option<PathBuf>,
+
+    /// `scores.db`, if it exists
+    pub scores: Option<PathBuf>,
+
+    /// `Songs` folder, or the folder set by `BeatmapDirectory` in the user's config file, if it exists
+    pub songs: Option<PathBuf>,
+}
+
+impl OsuFolder {
+    /// Looks for osu!'s database files and `Songs` folder in a folder.
+    ///
+    /// Returns `None` if none of the database files were found, since the folder is unlikely to be an osu!
+    /// installation.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn discover<P: AsRef<Path>>(root: P) -> Option<OsuFolder> {
+        let root = root.as_ref();
+        let file = |name: &str| Some(root.join(name)).filter(|path| path.is_file());
+
+        let folder = OsuFolder {
+            root: root.to_path_buf(),
+            beatmaps: file("osu!.db"),
+            collections: file("collection.db"),
+            scores: file("scores.db"),
+            songs: Some(songs_dir_guess(root, None)).filter(|path| path.is_dir()),
+        };
+
+        if folder.beatmaps.is_none() && folder.collections.is_none() && folder.scores.is_none() {
+            return None;
+        }
+
+        Some(folder)
+    }
+
+    /// Looks for an osu! installation in the default locations, i.e. `%LOCALAPPDATA%\osu!` on Windows, and the
+    /// usual Wine prefixes on Linux.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn find_installed() -> Option<OsuFolder> {
+        default_locations()
+            .into_iter()
+            .find_map(OsuFolder::discover)
+    }
+}
+
+/// Gets the folders osu! is usually installed to on this platform, in the order they should be searched.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn default_locations() -> Vec<PathBuf> {
+    let mut locations = Vec::new();
+
+    if cfg!(target_os = "windows") {
+        if let Some(local_app_data) = std::env::var_os("LOCALAPPDATA") {
+            locations.push(PathBuf::from(local_app_data).join("osu!"));
+        }
+
+        locations.push(PathBuf::from(r"C:\Program Files\osu!"));
+        locations.push(PathBuf::from(r"C:\Program Files (x86)\osu!"));
+    } else if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        let user = std::env::var_os("USER").unwrap_or_default();
+
+        // Wine prefixes, including the one set for the current session
+        let prefixes = std::env::var_os("WINEPREFIX")
+            .map(PathBuf::from)
+            .into_iter()
+            .chain([
+                home.join(".wine"),
+                home.join(".local/share/wineprefixes/osu"),
+            ]);
+
+        for prefix in prefixes {
+            let drive_c = prefix.join("drive_c");
+            locations.push(drive_c.join("users").join(&user).join("AppData/Local/osu!"));
+            locations.push(
+                drive_c
+                    .join("users")
+                    .join(&user)
+                    .join("Local Settings/Application Data/osu!"),
+            );
+            locations.push(drive_c.join("osu!"));
+        }
+
+        // Installations from osu-winello and Lutris
+        locations.push(home.join(".local/share/osu-wine/osu!"));
+        locations.push(home.join("Games/osu/drive_c/osu!"));
+    }
+
+    locations
+}
+
+/// Reads the folder that beatmaps are kept in from the `BeatmapDirectory` setting in an installation's user config
+/// file, i.e. `osu!.<user>.cfg`, which is set when the user has moved their beatmaps out of the `Songs` folder.
+///
+/// If several Windows users share the installation, the config file logged in to `username` is used if given,
+/// otherwise the most recently modified one (see [`osu_cfg::user_config`]). Returns `None` if there's no user config
+/// file or it doesn't set the folder.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn configured_songs_folder<P: AsRef<Path>>(root: P, username: Option<&str>) -> Option<PathBuf> {
+    let root = root.as_ref();
+    osu_cfg::user_config(root, username)?.songs_folder(root)
+}
+
+/// Gets the folder that beatmaps are most likely kept in for an installation, i.e. the folder set in the user's config
+/// file, or the `Songs` folder otherwise. The folder isn't checked to exist.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn songs_dir_guess<P: AsRef<Path>>(root: P, username: Option<&str>) -> PathBuf {
+    let root = root.as_ref();
+    configured_songs_folder(root, username).unwrap_or_else(|| root.join("Songs"))
+}
+
+/// Gets the path to the folder containing a beatmap's files, i.e. `Songs/<folder name>`.
+///
+/// Returns `None` if the beatmap doesn't have a folder name.
+pub fn beatmap_folder<P: AsRef<Path>>(osu_directory: P, beatmap: &BeatmapEntry) -> Option<PathBuf> {
+    let folder_name = beatmap.folder_name.as_deref().filter(|f| !f.is_empty())?;
+    Some(osu_directory.as_ref().join("Songs").join(folder_name))
+}
+
+/// Gets the path to a beatmap's audio file, i.e. `Songs/<folder name>/<audio file name>`.
+///
+/// Returns `None` if the beatmap doesn't have a folder or audio file name, or the audio file name isn't a plain file
+/// name (e.g. it tries to escape the beatmap's folder).
+pub fn audio_file<P: AsRef<Path>>(osu_directory: P, beatmap: &BeatmapEntry) -> Option<PathBuf> {
+    let audio_filename = beatmap
+        .audio_filename
+        .as_deref()
+        .filter(|f| is_plain_file_name(f))?;
+    Some(beatmap_folder(osu_directory, beatmap)?.join(audio_filename))
+}
+
+/// Gets the path to a beatmap's `.osu` file, i.e. `Songs/<folder name>/<beatmap file name>`.
+///
+/// Returns `None` if the beatmap doesn't have a folder or file name, or the file name isn't a plain file name.
+pub fn beatmap_file<P: AsRef<Path>>(osu_directory: P, beatmap: &BeatmapEntry) -> Option<PathBuf> {
+    beatmap_file_in_songs(osu_directory.as_ref().join("Songs"), beatmap)
+}
+
+/// Gets the path to a beatmap's `.osu` file in a `Songs` folder, e.g. one set by `BeatmapDirectory` in the user's
+/// config file (see [`songs_dir_guess`]).
+///
+/// Returns `None` if the beatmap doesn't have a folder or file name, or the file name isn't a plain file name.
+pub fn beatmap_file_in_songs<P: AsRef<Path>>(
+    songs_dir: P,
+    beatmap: &BeatmapEntry,
+) -> Option<PathBuf> {
+    let folder_name = beatmap.folder_name.as_deref().filter(|f| !f.is_empty())?;
+    let beatmap_filename = beatmap
+        .beatmap_filename
+        .as_deref()
+        .filter(|f| is_plain_file_name(f))?;
+    Some(songs_dir.as_ref().join(folder_name).join(beatmap_filename))
+}
+
+/// Gets the path to a file referenced by a beatmap's `.osu` file (e.g. its background image), given the beatmap's
+/// folder. The file name is relative to the beatmap's folder, and may be in a subfolder.
+///
+/// Returns `None` if the file is outside of the beatmap's folder.
+pub fn beatmap_resource<P: AsRef<Path>>(folder: P, file_name: &str) -> Option<PathBuf> {
+    // osu! is a Windows program, so the file name may use backslashes
+    let components = file_name.split(['/', '\\']).collect::<Vec<_>>();
+
+    if components.iter().any(|c| !is_plain_file_name(c)) {
+        return None;
+    }
+
+    Some(
+        components
+            .into_iter()
+            .fold(folder.as_ref().to_path_buf(), |path, c| path.join(c)),
+    )
+}
+
+/// Checks whether a file name refers to a file in the current folder, rather than a parent folder or another drive.
+fn is_plain_file_name(file_name: &str) -> bool {
+    !file_name.is_empty()
+        && file_name != "."
+        && file_name != ".."
+        && !file_name.contains(['/', '\\', ':'])
+}
+
+/// Removes the characters that osu! removes from file and folder names, which are the ones Windows doesn't allow: control
+/// characters and `"*/:<>?\|`. Trailing dots and spaces are also removed, since Windows drops them from file names.
+///
+/// This follows osu!'s behaviour as closely as practical, but there are some known differences:
+///
+/// - Reserved device names such as `CON` and `NUL` are kept, although Windows won't create files with these names.
+/// - Names aren't shortened to fit Windows' path length limit, which osu! may do for very long titles.
+/// - Unicode text is kept as it is, without normalisation. Look-alike characters such as the full-width `？` are valid
+///   in file names, so they're kept too.
+pub fn sanitize_filename(name: &str) -> String {
+    let sanitized = name
+        .chars()
+        .filter(|c| !c.is_control() && !INVALID_FILE_NAME_CHARS.contains(c))
+        .collect::<String>();
+
+    sanitized.trim_end_matches(['.', ' ']).to_string()
+}
+
+/// Gets the name of the folder osu! is expected to put a beatmapset in, given its romanised artist and title.
+///
+/// Submitted beatmapsets are named after their download, i.e. `<set ID> <artist> - <title>`. Beatmapsets which haven't
+/// been submitted are named after the `.osz` file exported by the editor, which includes the creator instead, i.e.
+/// `<artist> - <title> (<creator>)`. Either way, the name is sanitized using [`sanitize_filename`].
+///
+/// The actual folder may still differ, e.g. if the beatmapset was imported from a renamed `.osz` file, or osu! added a
+/// number to avoid replacing an existing folder.
+pub fn expected_folder_name(
+    artist: &str,
+    title: &str,
+    creator: &str,
+    set_id: Option<BeatmapSetId>,
+) -> String {
+    let name = match set_id {
+        Some(set_id) => format!("{} {} - {}", set_id, artist, title),
+        None => format!("{} - {} ({})", artist, title, creator),
+    };
+
+    sanitize_filename(&name)
+}
+
+/// Gets the path that osu! saves a local score's replay to, i.e. `Data/r/<beatmap MD5>-<file time>.osr`.
+///
+/// Returns `None` if the score doesn't have a beatmap MD5, or its date can't be represented as a file time.
+pub fn replay_file<P: AsRef<Path>>(osu_directory: P, score: &ScoreReplay) -> Option<PathBuf> {
+    let beatmap_md5 = score.beatmap_md5.as_deref().filter(|m| !m.is_empty())?;
+    let file_time = file_time(score.timestamp)?;
+
+    Some(
+        osu_directory
+            .as_ref()
+            .join("Data")
+            .join("r")
+            .join(format!("{}-{}.osr", beatmap_md5, file_time)),
+    )
+}
+
+/// Converts a date into a Windows file time, i.e. the number of 100ns intervals since 1601-01-01.
+fn file_time(datetime: OffsetDateTime) -> Option<u64> {
+    let intervals = (datetime - FILE_TIME_EPOCH).whole_nanoseconds() / 100;
+    u64::try_from(intervals).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osu_folders_are_discovered() {
+        let dir = std::env::temp_dir().join(format!("osu-db-parser-paths-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Songs")).unwrap();
+
+        // Folders without any database files aren't osu! installations
+        assert_eq!(OsuFolder::discover(&dir), None);
+
+        std::fs::write(dir.join("osu!.db"), b"").unwrap();
+        std::fs::write(dir.join("scores.db"), b"").unwrap();
+
+        assert_eq!(
+            OsuFolder::discover(&dir),
+            Some(OsuFolder {
+                root: dir.clone(),
+                beatmaps: Some(dir.join("osu!.db")),
+                collections: None,
+                scores: Some(dir.join("scores.db")),
+                songs: Some(dir.join("Songs")),
+            })
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn configured_songs_folders_are_read() {
+        let dir = std::env::temp_dir().join(format!("osu-db-parser-cfg-{}", std::process::id()));
+        let songs = dir.join("Beatmaps");
+        std::fs::create_dir_all(&songs).unwrap();
+        std::fs::write(dir.join("osu!.db"), b"").unwrap();
+
+        // Without a user config file, beatmaps are in the default folder
+        std::fs::write(dir.join("osu!.cfg"), "BeatmapDirectory = Ignored\n").unwrap();
+        assert_eq!(configured_songs_folder(&dir, None), None);
+
+        std::fs::write(
+            dir.join("osu!.Player.cfg"),
+            "# osu! configuration for Player\nVolumeUniversal = 100\nBeatmapDirectory = Beatmaps\n",
+        )
+        .unwrap();
+        assert_eq!(configured_songs_folder(&dir, None), Some(songs.clone()));
+        assert_eq!(
+            OsuFolder::discover(&dir).unwrap().songs,
+            Some(songs.clone())
+        );
+
+        // Absolute paths are used as they are
+        let line = format!("BeatmapDirectory = {}\n", songs.display());
+        std::fs::write(dir.join("osu!.Player.cfg"), line).unwrap();
+        assert_eq!(configured_songs_folder(&dir, None), Some(songs.clone()));
+
+        // Portable installations often keep their beatmaps in a folder next to the installation
+        std::fs::write(
+            dir.join("osu!.Player.cfg"),
+            "BeatmapDirectory = ..\\Beatmaps\n",
+        )
+        .unwrap();
+        assert_eq!(
+            songs_dir_guess(&dir, None),
+            dir.parent().unwrap().join("Beatmaps")
+        );
+
+        std::fs::write(dir.join("osu!.Player.cfg"), "BeatmapDirectory =\n").unwrap();
+        assert_eq!(configured_songs_folder(&dir, None), None);
+        assert_eq!(songs_dir_guess(&dir, None), dir.join("Songs"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_names_are_sanitized() {
+        for c in [
+            '"', '*', '/', ':', '<', '>', '?', '\\', '|', '\0', '\t', '\n', '\x1f',
+        ] {
+            assert_eq!(sanitize_filename(&format!("a{}b", c)), "ab", "{:?}", c);
+        }
+
+        // Only trailing dots and spaces are removed
+        assert_eq!(sanitize_filename("  Title... . "), "  Title");
+        assert_eq!(sanitize_filename("...Title?."), "...Title");
+        assert_eq!(sanitize_filename("Title (TV Size)"), "Title (TV Size)");
+        assert_eq!(sanitize_filename("?*."), "");
+
+        // Unicode, including full-width look-alikes of the invalid characters, is kept
+        assert_eq!(
+            sanitize_filename("DECO*27 - ゴーストルール？ feat. 初音ミク"),
+            "DECO27 - ゴーストルール？ feat. 初音ミク"
+        );
+    }
+
+    #[test]
+    fn expected_folder_names_are_sanitized() {
+        assert_eq!(
+            expected_folder_name("DECO*27", "Ghost Rule", "Mapper", BeatmapSetId::new(123456)),
+            "123456 DECO27 - Ghost Rule"
+        );
+        assert_eq!(
+            expected_folder_name("Artist", "What?", "Mapper", None),
+            "Artist - What (Mapper)"
+        );
+        assert_eq!(
+            expected_folder_name("Artist", "Title...", "", BeatmapSetId::new(1)),
+            "1 Artist - Title"
+        );
+    }
+
+    #[test]
+    fn beatmap_folders_are_resolved() {
+        let beatmap = BeatmapEntry {
+            folder_name: Some("1032103 Camellia - Exit This Earth's Atomosphere".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            beatmap_folder("osu!", &beatmap),
+            Some(PathBuf::from(
+                "osu!/Songs/1032103 Camellia - Exit This Earth's Atomosphere"
+            ))
+        );
+        assert_eq!(beatmap_folder("osu!", &BeatmapEntry::default()), None);
+    }
+
+    #[test]
+    fn audio_files_are_resolved() {
+        let beatmap = BeatmapEntry {
+            folder_name: Some("1032103 Camellia - Exit This Earth's Atomosphere".to_string()),
+            audio_filename: Some("audio.mp3".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            audio_file("osu!", &beatmap),
+            Some(PathBuf::from(
+                "osu!/Songs/1032103 Camellia - Exit This Earth's Atomosphere/audio.mp3"
+            ))
+        );
+
+        // Beatmaps without a folder or audio file can't be resolved
+        let no_folder = BeatmapEntry {
+            folder_name: None,
+            ..beatmap.clone()
+        };
+        assert_eq!(audio_file("osu!", &no_folder), None);
+
+        let no_audio = BeatmapEntry {
+            audio_filename: Some(String::new()),
+            ..beatmap.clone()
+        };
+        assert_eq!(audio_file("osu!", &no_audio), None);
+
+        // Audio file names must stay inside the beatmap's folder
+        for audio_filename in ["../audio.mp3", "..\\audio.ogg", ".."] {
+            let escaping = BeatmapEntry {
+                audio_filename: Some(audio_filename.to_string()),
+                ..beatmap.clone()
+            };
+            assert_eq!(audio_file("osu!", &escaping), None);
+        }
+    }
+
+    #[test]
+    fn beatmap_resources_are_resolved() {
+        let beatmap = BeatmapEntry {
+            folder_name: Some("1 Artist - Title".to_string()),
+            beatmap_filename: Some("Artist - Title (Mapper) [Insane].osu".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            beatmap_file("osu!", &beatmap),
+            Some(PathBuf::from(
+                "osu!/Songs/1 Artist - Title/Artist - Title (Mapper) [Insane].osu"
+            ))
+        );
+        assert_eq!(
+            beatmap_resource("Songs/1 Artist - Title", "bg.jpg"),
+            Some(PathBuf::from("Songs/1 Artist - Title/bg.jpg"))
+        );
+        assert_eq!(
+            beatmap_resource("Songs/1 Artist - Title", r"sb\bg.jpg"),
+            Some(PathBuf::from("Songs/1 Artist - Title/sb/bg.jpg"))
+        );
+
+        // Files must stay inside the beatmap's folder
+        for file_name in ["", "../bg.jpg", r"sb\..\..\bg.jpg", "/bg.jpg", r"C:\bg.jpg"] {
+            assert_eq!(beatmap_resource("Songs/1 Artist - Title", file_name), None);
+        }
+    }
+
+    #[test]
+    fn replay_files_are_resolved() {
+        // 2018-06-21 09:11:33.8396283 UTC ==> 131740458938396283 file time
+        let score = ScoreReplay {
+            beatmap_md5: Some("0123456789abcdef0123456789abcdef".to_string()),
+            timestamp: datetime!(2018-06-21 09:11:33.8396283 UTC),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            replay_file("osu!", &score),
+            Some(PathBuf::from(
+                "osu!/Data/r/0123456789abcdef0123456789abcdef-131740458938396283.osr"
+            ))
+        );
+
+        // Dates before the file time epoch (e.g. unset dates) can't be resolved
+        let score = ScoreReplay {
+            timestamp: crate::common::WINDOWS_EPOCH,
+            ..score
+        };
+        assert_eq!(replay_file("osu!", &score), None);
+    }
+}
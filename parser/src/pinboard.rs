@@ -0,0 +1,355 @@
+//! Comparing the stats of a handful of beatmaps side by side, e.g. to choose between similar difficulties.
+//!
+//! Beatmaps are pinned to a [`Pinboard`], then a [`Comparison`] lines up their values for each of [`COMPARED`],
+//! marking the best value in each row.
+
+use std::cmp::Ordering;
+
+use crate::{
+    beatmaps::{BeatmapEntry, MetadataLanguage, StarPolicy},
+    columns::{Column, ColumnValue},
+    export::{self, Format},
+    sort::{SortKey, SortOrder},
+};
+
+/// Maximum number of beatmaps that can be pinned at once.
+pub const MAX_PINS: usize = 5;
+
+/// Columns compared for the pinned beatmaps, in the order they're shown.
+pub const COMPARED: [Column; 9] = [
+    Column::ApproachRate,
+    Column::CircleSize,
+    Column::OverallDifficulty,
+    Column::HpDrain,
+    Column::Stars,
+    Column::Bpm,
+    Column::Length,
+    Column::Objects,
+    Column::Grade,
+];
+
+/// Columns identifying each beatmap in an exported comparison, before the compared columns.
+const EXPORTED_NAMES: [Column; 4] = [
+    Column::Artist,
+    Column::Title,
+    Column::Difficulty,
+    Column::Mode,
+];
+
+/// Beatmaps pinned for comparison, in the order they were pinned.
+///
+/// Beatmaps are identified by their MD5 hash, so they stay pinned when the listing is filtered or reloaded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pinboard {
+    md5s: Vec<String>,
+}
+
+/// Represents the pinned beatmaps' values for one column.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComparisonRow<'a> {
+    pub column: Column,
+
+    /// Value for each beatmap, in the same order as the beatmaps
+    pub values: Vec<ColumnValue<'a>>,
+
+    /// Positions of the beatmaps with the best value, which is the highest value, or the best grade. This is empty if
+    /// the values can't be compared, or they're all the same.
+    pub best: Vec<usize>,
+
+    /// Whether the values mean the same thing for every beatmap. Difficulty settings and star ratings aren't
+    /// comparable between beatmaps for different gameplay modes, e.g. CS is the number of keys in osu!mania.
+    pub comparable: bool,
+}
+
+/// Represents the compared values of some beatmaps, with one row for each of [`COMPARED`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison<'a> {
+    pub beatmaps: Vec<&'a BeatmapEntry>,
+    pub rows: Vec<ComparisonRow<'a>>,
+}
+
+impl Pinboard {
+    /// Gets the MD5 hashes of the pinned beatmaps.
+    pub fn md5s(&self) -> &[String] {
+        &self.md5s
+    }
+
+    pub fn len(&self) -> usize {
+        self.md5s.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.md5s.is_empty()
+    }
+
+    /// Checks whether no more beatmaps can be pinned.
+    pub fn is_full(&self) -> bool {
+        self.md5s.len() >= MAX_PINS
+    }
+
+    pub fn is_pinned(&self, beatmap: &BeatmapEntry) -> bool {
+        beatmap
+            .md5
+            .as_ref()
+            .is_some_and(|md5| self.md5s.contains(md5))
+    }
+
+    /// Pins a beatmap, or unpins it if it's already pinned. Returns `false` if nothing changed, because the beatmap has
+    /// no MD5 hash or the pinboard is full.
+    pub fn toggle(&mut self, beatmap: &BeatmapEntry) -> bool {
+        let Some(md5) = &beatmap.md5 else {
+            return false;
+        };
+
+        if let Some(position) = self.md5s.iter().position(|pinned| pinned == md5) {
+            self.md5s.remove(position);
+        } else if self.is_full() {
+            return false;
+        } else {
+            self.md5s.push(md5.clone());
+        }
+
+        true
+    }
+
+    /// Unpins the beatmap with this MD5 hash.
+    pub fn unpin(&mut self, md5: &str) {
+        self.md5s.retain(|pinned| pinned != md5);
+    }
+
+    pub fn clear(&mut self) {
+        self.md5s.clear();
+    }
+}
+
+impl<'a> Comparison<'a> {
+    /// Compares some beatmaps, using `stars` to choose each beatmap's star rating.
+    pub fn new(beatmaps: Vec<&'a BeatmapEntry>, stars: StarPolicy) -> Self {
+        let same_mode = beatmaps
+            .windows(2)
+            .all(|pair| pair[0].gameplay_mode == pair[1].gameplay_mode);
+
+        let rows = COMPARED
+            .into_iter()
+            .map(|column| {
+                let values = beatmaps
+                    .iter()
+                    .map(|beatmap| column.value_with(beatmap, MetadataLanguage::default(), stars))
+                    .collect::<Vec<_>>();
+
+                let comparable = same_mode || !depends_on_mode(column);
+                let best = if comparable {
+                    best_values(column, &values)
+                } else {
+                    Vec::new()
+                };
+
+                ComparisonRow {
+                    column,
+                    values,
+                    best,
+                    comparable,
+                }
+            })
+            .collect();
+
+        Self { beatmaps, rows }
+    }
+
+    /// Writes the comparison as a Markdown table with a row for each beatmap, using the same format as
+    /// [`export::write`]. As in other exports, the Stars column is each beatmap's star rating in its own mode.
+    pub fn to_markdown(&self, language: MetadataLanguage) -> String {
+        let columns = EXPORTED_NAMES
+            .into_iter()
+            .chain(COMPARED)
+            .collect::<Vec<_>>();
+        let mut output = Vec::new();
+
+        // Writing to memory can't fail, and there's no progress to cancel
+        export::write(
+            &mut output,
+            Format::Markdown,
+            &columns,
+            language,
+            &self.beatmaps,
+            |_, _| true,
+        )
+        .ok();
+
+        String::from_utf8_lossy(&output).into_owned()
+    }
+}
+
+/// Checks whether a column's values mean something different depending on the beatmap's gameplay mode.
+fn depends_on_mode(column: Column) -> bool {
+    matches!(
+        column,
+        Column::ApproachRate
+            | Column::CircleSize
+            | Column::OverallDifficulty
+            | Column::HpDrain
+            | Column::Stars
+    )
+}
+
+/// Finds the positions of the best values, which sort first: the highest values, or the best grade. Missing values
+/// are never the best, and nothing is best if every value is the same.
+fn best_values(column: Column, values: &[ColumnValue]) -> Vec<usize> {
+    let key = SortKey {
+        order: match column {
+            Column::Grade => SortOrder::Ascending,
+            _ => SortOrder::Descending,
+        },
+        ..SortKey::new(column)
+    };
+
+    let missing = |value: &ColumnValue| match value {
+        ColumnValue::Empty => true,
+        ColumnValue::Float(value) => !value.is_finite(),
+        _ => false,
+    };
+
+    let Some(best) = values
+        .iter()
+        .filter(|value| !missing(value))
+        .min_by(|a, b| key.compare_values(a, b))
+    else {
+        return Vec::new();
+    };
+
+    let positions = (0..values.len())
+        .filter(|&i| {
+            !missing(&values[i]) && key.compare_values(&values[i], best) == Ordering::Equal
+        })
+        .collect::<Vec<_>>();
+
+    if positions.len() == values.len() {
+        Vec::new()
+    } else {
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{GameplayMode, Grade};
+
+    fn beatmap(md5: &str, mode: GameplayMode, approach_rate: f32, grade: Grade) -> BeatmapEntry {
+        BeatmapEntry {
+            md5: Some(md5.to_string()),
+            artist_name: Some("Artist".to_string()),
+            song_title: Some("Title".to_string()),
+            difficulty: Some(md5.to_string()),
+            gameplay_mode: mode,
+            approach_rate,
+            circle_size: 4.0,
+            hitcircle_count: (approach_rate * 100.0) as u16,
+            grade_std: grade,
+            grade_mania: grade,
+            ..Default::default()
+        }
+    }
+
+    fn row<'a>(comparison: &'a Comparison, column: Column) -> &'a ComparisonRow<'a> {
+        comparison
+            .rows
+            .iter()
+            .find(|row| row.column == column)
+            .unwrap()
+    }
+
+    #[test]
+    fn pins_are_limited_and_toggled() {
+        let mut pinboard = Pinboard::default();
+
+        for i in 0..MAX_PINS {
+            assert!(pinboard.toggle(&beatmap(
+                &i.to_string(),
+                GameplayMode::Standard,
+                9.0,
+                Grade::A
+            )));
+        }
+
+        let extra = beatmap("extra", GameplayMode::Standard, 9.0, Grade::A);
+        assert!(pinboard.is_full());
+        assert!(!pinboard.toggle(&extra));
+        assert!(!pinboard.is_pinned(&extra));
+
+        // Unpinning makes room again
+        assert!(pinboard.toggle(&beatmap("1", GameplayMode::Standard, 9.0, Grade::A)));
+        assert!(pinboard.toggle(&extra));
+        assert_eq!(pinboard.md5s(), ["0", "2", "3", "4", "extra"]);
+
+        // Beatmaps without a hash can't be found again, so they can't be pinned
+        pinboard.unpin("extra");
+        assert!(!pinboard.toggle(&BeatmapEntry::default()));
+        assert_eq!(pinboard.len(), 4);
+    }
+
+    #[test]
+    fn best_values_are_marked() {
+        let beatmaps = [
+            beatmap("a", GameplayMode::Standard, 9.0, Grade::A),
+            beatmap("b", GameplayMode::Standard, 9.3, Grade::S),
+            beatmap("c", GameplayMode::Standard, 9.3, Grade::Unplayed),
+        ];
+        let comparison = Comparison::new(beatmaps.iter().collect(), StarPolicy::default());
+
+        assert_eq!(comparison.rows.len(), COMPARED.len());
+
+        // Ties are all marked, and unplayed beatmaps have no grade to compare
+        let approach_rate = row(&comparison, Column::ApproachRate);
+        assert!(approach_rate.comparable);
+        assert_eq!(approach_rate.best, [1, 2]);
+        assert_eq!(row(&comparison, Column::Objects).best, [1, 2]);
+        assert_eq!(row(&comparison, Column::Grade).best, [1]);
+
+        // Nothing stands out when the values are all the same, or all missing
+        assert!(row(&comparison, Column::CircleSize).best.is_empty());
+        assert!(row(&comparison, Column::Stars).best.is_empty());
+    }
+
+    #[test]
+    fn mode_specific_rows_are_not_compared_across_modes() {
+        let beatmaps = [
+            beatmap("a", GameplayMode::Standard, 9.0, Grade::A),
+            beatmap("b", GameplayMode::Mania, 8.0, Grade::S),
+        ];
+        let comparison = Comparison::new(beatmaps.iter().collect(), StarPolicy::default());
+
+        for row in &comparison.rows {
+            assert_eq!(
+                row.comparable,
+                !depends_on_mode(row.column),
+                "{}",
+                row.column
+            );
+            assert_eq!(row.values.len(), 2);
+
+            if !row.comparable {
+                assert!(row.best.is_empty());
+            }
+        }
+
+        assert_eq!(row(&comparison, Column::Objects).best, [0]);
+        assert_eq!(row(&comparison, Column::Grade).best, [1]);
+    }
+
+    #[test]
+    fn comparisons_are_exported_as_markdown() {
+        let beatmaps = [
+            beatmap("a", GameplayMode::Standard, 9.0, Grade::A),
+            beatmap("b", GameplayMode::Standard, 9.5, Grade::S),
+        ];
+        let comparison = Comparison::new(beatmaps.iter().collect(), StarPolicy::default());
+        let markdown = comparison.to_markdown(MetadataLanguage::default());
+        let lines = markdown.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("| Artist | Title | Difficulty | Mode | AR |"));
+        assert!(lines[2].starts_with("| Artist | Title | a | Standard | 9 |"));
+        assert!(lines[3].starts_with("| Artist | Title | b | Standard | 9.5 |"));
+    }
+}
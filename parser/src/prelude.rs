@@ -1,7 +1,35 @@
 pub use {
-    crate::beatmaps::{BeatmapEntry, BeatmapListing, RankedStatus, StarRating, TimingPoint},
-    crate::collections::{Collection, CollectionListing},
-    crate::common::{GameplayMode, Mods, OsuString},
+    crate::beatmaps::{
+        AccountStatus, BeatmapEntry, BeatmapListing, Beatmapset, FloatPolicy, FormatConfidence,
+        ListingHeader, MetadataLanguage, ParseOptions, PartialListing, RankedStatus, StarPolicy,
+        StarRating, StarSource, TimingPoint, TimingSummary,
+    },
+    crate::common::{GameplayMode, Grade, Mods, OsuStr, OsuString, Sanitize, TimestampKind},
     crate::error::Error,
-    crate::scores::{BeatmapScores, ScoreListing, ScoreReplay},
+};
+
+#[cfg(feature = "writer")]
+pub use crate::beatmaps::WriteOptions;
+
+#[cfg(feature = "collections")]
+pub use crate::collections::{Collection, CollectionListing};
+
+#[cfg(feature = "scores")]
+pub use crate::scores::{BeatmapScores, ScoreListing, ScoreReplay, ScoreSummary};
+
+#[cfg(feature = "tools")]
+pub use {
+    crate::columns::Column,
+    crate::diff::ListingDiff,
+    crate::edit::{BulkEdit, OffsetEdit},
+    crate::expr::{CustomColumn, ExprError},
+    crate::hashes::{HashCache, Md5},
+    crate::ids::{BeatmapId, BeatmapSetId, DifficultyId, ThreadId},
+    crate::notes::{Note, Notes},
+    crate::paging::{Page, PageCache, PageRequest, PageStart},
+    crate::pinboard::{Comparison, Pinboard},
+    crate::query::{BeatmapFilter, FilterAggregates, QueryError},
+    crate::session::EditSession,
+    crate::sort::{SortKey, SortOrder},
+    crate::workspace::{SourceId, Workspace},
 };
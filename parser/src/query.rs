@@ -0,0 +1,1492 @@
+//! Filtering of beatmap entries using a search syntax similar to osu!'s song select.
+//!
+//! A query is a whitespace-separated list of terms:
+//!
+//! - Terms of the form `key<op>value` (e.g. `stars>5.5`, `mode=mania`, `creator=peppy`) are conditions on a particular field.
+//! - Any other term is treated as free text, which must appear in the beatmap's artist, title, creator, difficulty, source or tags.
+//!
+//! Text is matched against both the romanised and Unicode artist and title, so the results don't depend on which
+//! [`MetadataLanguage`](crate::beatmaps::MetadataLanguage) is being shown.
+//!
+//! Starting a query with `~` (e.g. `~fredom dive`) matches the free text approximately instead, using [`fuzzy`]
+//! matching, so that typos still find the intended beatmap. [`BeatmapFilter::search`] ranks these results by how
+//! closely they match, and can also fall back to fuzzy matching when nothing matches exactly.
+//!
+//! `stars` conditions use the star rating chosen by the filter's [`StarPolicy`], which is the beatmap's own gameplay mode
+//! by default, while `stars_<mode>` (e.g. `stars_mania>4`) always uses a particular mode's star rating.
+//!
+//! [`BeatmapFilter::aggregate`] summarises the matching beatmaps (e.g. their mean star rating and total drain time) in
+//! the same pass as filtering them.
+//!
+//! `outdated=true` matches beatmaps that might have an update osu! hasn't downloaded, using
+//! [`BeatmapEntry::is_possibly_outdated`] with the [default tolerance](DEFAULT_OUTDATED_TOLERANCE).
+//!
+//! `added` conditions compare the date a beatmap was added (e.g. `added>=2024-01-01`), approximated from the times
+//! stored in `osu!.db` using [`BeatmapEntry::approximate_added_date`]. Dates are in UTC.
+//!
+//! Conditions on the user's own [notes](crate::notes) (`note=` and `starred=`) only match when the notes are given, using
+//! [`BeatmapFilter::matches_with_notes`].
+//!
+//! Double quotes can be used to include spaces in a term (e.g. `artist="the quick brown fox"`), or to treat a term
+//! containing an operator as free text (e.g. `"re:zero"`).
+
+use std::str::FromStr;
+
+use thiserror::Error;
+use time::{Date, Month};
+
+use crate::{
+    beatmaps::{
+        BeatmapEntry, BeatmapListing, RankedStatus, StarPolicy, StarSource,
+        DEFAULT_OUTDATED_TOLERANCE,
+    },
+    common::GameplayMode,
+    fuzzy::{self, FuzzyIndex, FuzzyQuery},
+    notes::Notes,
+};
+
+/// A filter for beatmap entries, parsed from a search query.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BeatmapFilter {
+    /// Conditions on particular fields of a beatmap
+    pub conditions: Vec<Condition>,
+
+    /// Free text terms, stored in lowercase
+    pub text: Vec<String>,
+
+    /// Whether the free text is matched approximately, from a query starting with `~`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fuzzy: bool,
+
+    /// Which star rating `stars` conditions use
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stars: StarPolicy,
+}
+
+/// Represents a summary of a set of beatmaps, such as those matching a filter (see [`BeatmapFilter::aggregate`]).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FilterAggregates {
+    /// Number of beatmaps
+    pub count: usize,
+
+    /// Number of beatmaps with a star rating, and the sum of those ratings
+    pub rated: usize,
+    pub star_total: f64,
+
+    /// Total drain time in seconds
+    pub drain_time: u64,
+
+    /// Lowest and highest main BPM, if any beatmaps have timing points
+    pub bpm_range: Option<(f64, f64)>,
+
+    /// Number of beatmaps with each ranked status, ordered by status and skipping statuses without any beatmaps
+    pub statuses: Vec<(RankedStatus, usize)>,
+}
+
+/// Represents a condition on a particular field of a beatmap.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Condition {
+    /// Compares a numeric field against a value.
+    Numeric {
+        key: NumericKey,
+        op: Operator,
+        value: f64,
+    },
+
+    /// Checks the gameplay mode of a beatmap.
+    Mode { op: Operator, mode: GameplayMode },
+
+    /// Checks the ranked status of a beatmap.
+    Status { op: Operator, status: RankedStatus },
+
+    /// Checks whether a beatmap can be played in any of a set of gameplay modes.
+    ///
+    /// If `converts` is set, osu!standard beatmaps also match any mode, since they can be converted.
+    AnyMode {
+        modes: Vec<GameplayMode>,
+        converts: bool,
+    },
+
+    /// Checks whether a beatmap has any of a set of ranked statuses.
+    AnyStatus(Vec<RankedStatus>),
+
+    /// Checks whether a text field contains a value (case-insensitive).
+    Text {
+        key: TextKey,
+        op: Operator,
+        value: String,
+    },
+
+    /// Checks whether a beatmap has been played.
+    Unplayed(bool),
+
+    /// Checks whether a beatmap uses more than one BPM (see [`BeatmapEntry::timing_summary`]).
+    VariableBpm(bool),
+
+    /// Checks whether a beatmap might have an update that osu! hasn't downloaded (see
+    /// [`BeatmapEntry::is_possibly_outdated`]).
+    Outdated(bool),
+
+    /// Compares the date a beatmap was added against a date (see [`BeatmapEntry::approximate_added_date`]). Beatmaps
+    /// without a known date never match.
+    Added { op: Operator, date: Date },
+
+    /// Checks whether the user's note about a beatmap contains a value (case-insensitive).
+    Note { op: Operator, value: String },
+
+    /// Checks whether a beatmap has been bookmarked by the user.
+    Starred(bool),
+}
+
+/// Represents the numeric fields that can be filtered on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NumericKey {
+    /// NoMod star rating chosen by the filter's [`StarPolicy`]
+    Stars,
+
+    /// NoMod star rating for a particular gameplay mode, including converts
+    ModeStars(GameplayMode),
+    ApproachRate,
+    CircleSize,
+    OverallDifficulty,
+    HpDrain,
+
+    /// Total length, in seconds
+    Length,
+
+    /// Drain time, in seconds
+    Drain,
+
+    /// Number of hit objects
+    Objects,
+
+    /// Key count (osu!mania only)
+    Keys,
+
+    /// Key count in osu!mania, including osu!standard beatmaps converted to it (see
+    /// [`BeatmapEntry::mania_key_count`])
+    ConvertedKeys,
+}
+
+/// Represents the text fields that can be filtered on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextKey {
+    Artist,
+    Title,
+    Creator,
+    Difficulty,
+    Source,
+    Tags,
+    Md5,
+
+    /// Name of the beatmap's folder, e.g. to find the difficulties in a beatmapset
+    Folder,
+}
+
+/// Represents a comparison operator in a condition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// Represents an error that can occur when parsing a search query.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum QueryError {
+    #[error("Unknown filter key '{}' in '{}'", .key, .token)]
+    UnknownKey { token: String, key: String },
+
+    #[error("Invalid value '{}' for filter key '{}' in '{}'", .value, .key, .token)]
+    InvalidValue {
+        token: String,
+        key: String,
+        value: String,
+    },
+
+    #[error("Operator '{}' cannot be used with filter key '{}' in '{}'", .op, .key, .token)]
+    UnsupportedOperator {
+        token: String,
+        key: String,
+        op: String,
+    },
+
+    #[error("Unterminated quote in '{}'", .token)]
+    UnterminatedQuote { token: String },
+}
+
+/// Tolerance used when comparing floating point values for equality.
+const FLOAT_TOLERANCE: f64 = 0.005;
+
+/// Operators recognised in conditions, in the order they should be matched.
+const OPERATORS: [(&str, Operator); 8] = [
+    (">=", Operator::GreaterOrEqual),
+    ("<=", Operator::LessOrEqual),
+    ("!=", Operator::NotEqual),
+    ("==", Operator::Equal),
+    ("=", Operator::Equal),
+    (":", Operator::Equal),
+    (">", Operator::Greater),
+    ("<", Operator::Less),
+];
+
+/// Valid keys for conditions, used for help text.
+pub const QUERY_KEYS: [&str; 26] = [
+    "stars",
+    "stars_<mode>",
+    "ar",
+    "cs",
+    "od",
+    "hp",
+    "length",
+    "drain",
+    "objects",
+    "keys",
+    "mode",
+    "status",
+    "unplayed",
+    "variable_bpm",
+    "outdated",
+    "added",
+    "artist",
+    "title",
+    "creator",
+    "difficulty",
+    "source",
+    "tag",
+    "md5",
+    "folder",
+    "note",
+    "starred",
+];
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Operator::*;
+
+        match self {
+            Equal => write!(f, "="),
+            NotEqual => write!(f, "!="),
+            Less => write!(f, "<"),
+            LessOrEqual => write!(f, "<="),
+            Greater => write!(f, ">"),
+            GreaterOrEqual => write!(f, ">="),
+        }
+    }
+}
+
+impl QueryError {
+    /// Gets the term in the query that caused this error.
+    pub fn token(&self) -> &str {
+        match self {
+            QueryError::UnknownKey { token, .. }
+            | QueryError::InvalidValue { token, .. }
+            | QueryError::UnsupportedOperator { token, .. }
+            | QueryError::UnterminatedQuote { token } => token,
+        }
+    }
+}
+
+impl FromStr for BeatmapFilter {
+    type Err = QueryError;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        Self::parse(query)
+    }
+}
+
+impl Operator {
+    /// Compares two values using this operator.
+    pub fn compare<T: PartialOrd>(&self, lhs: T, rhs: T) -> bool {
+        use Operator::*;
+
+        match self {
+            Equal => lhs == rhs,
+            NotEqual => lhs != rhs,
+            Less => lhs < rhs,
+            LessOrEqual => lhs <= rhs,
+            Greater => lhs > rhs,
+            GreaterOrEqual => lhs >= rhs,
+        }
+    }
+
+    /// Compares two floating point values using this operator, allowing for a small tolerance in equality checks.
+    pub fn compare_float(&self, lhs: f64, rhs: f64) -> bool {
+        use Operator::*;
+
+        let equal = (lhs - rhs).abs() < FLOAT_TOLERANCE;
+
+        match self {
+            Equal => equal,
+            NotEqual => !equal,
+            Less => lhs < rhs && !equal,
+            LessOrEqual => lhs < rhs || equal,
+            Greater => lhs > rhs && !equal,
+            GreaterOrEqual => lhs > rhs || equal,
+        }
+    }
+}
+
+impl NumericKey {
+    /// Gets the value of this field for a beatmap, if present, using the star rating for the beatmap's own gameplay
+    /// mode.
+    pub fn value(&self, beatmap: &BeatmapEntry) -> Option<f64> {
+        self.value_with(beatmap, StarPolicy::default())
+    }
+
+    /// Gets the value of this field for a beatmap, if present, choosing its star rating using `stars`. Star ratings for
+    /// a particular mode only use the policy's handling of missing star ratings.
+    pub fn value_with(&self, beatmap: &BeatmapEntry, stars: StarPolicy) -> Option<f64> {
+        use NumericKey::*;
+
+        match self {
+            Stars => beatmap.policy_star_rating(stars),
+            ModeStars(mode) => beatmap.policy_star_rating(StarPolicy {
+                source: StarSource::FixedMode(*mode),
+                ..stars
+            }),
+            ApproachRate => Some(beatmap.approach_rate as f64),
+            CircleSize => Some(beatmap.circle_size as f64),
+            OverallDifficulty => Some(beatmap.overall_difficulty as f64),
+            HpDrain => Some(beatmap.hp_drain as f64),
+            Length => Some(beatmap.total_time as f64 / 1000.0),
+            Drain => Some(beatmap.drain_time as f64),
+            Objects => Some(beatmap.object_count() as f64),
+            Keys => {
+                (beatmap.gameplay_mode == GameplayMode::Mania).then_some(beatmap.circle_size as f64)
+            }
+            ConvertedKeys => beatmap.mania_key_count().map(f64::from),
+        }
+    }
+}
+
+impl TextKey {
+    /// Gets the values of this field for a beatmap.
+    pub fn values<'a>(&self, beatmap: &'a BeatmapEntry) -> Vec<&'a str> {
+        use TextKey::*;
+
+        let fields = match self {
+            Artist => vec![&beatmap.artist_name, &beatmap.artist_name_unicode],
+            Title => vec![&beatmap.song_title, &beatmap.song_title_unicode],
+            Creator => vec![&beatmap.creator_name],
+            Difficulty => vec![&beatmap.difficulty],
+            Source => vec![&beatmap.song_source],
+            Tags => vec![&beatmap.song_tags],
+            Md5 => vec![&beatmap.md5],
+            Folder => vec![&beatmap.folder_name],
+        };
+
+        fields.into_iter().filter_map(|f| f.as_deref()).collect()
+    }
+}
+
+impl Condition {
+    /// Checks whether a beatmap satisfies this condition, treating it as having no note.
+    pub fn matches(&self, beatmap: &BeatmapEntry) -> bool {
+        self.matches_with_notes(beatmap, &Notes::default())
+    }
+
+    /// Checks whether a beatmap satisfies this condition, using `notes` for conditions on the user's notes.
+    pub fn matches_with_notes(&self, beatmap: &BeatmapEntry, notes: &Notes) -> bool {
+        self.matches_with_stars(beatmap, notes, StarPolicy::default())
+    }
+
+    /// Checks whether a beatmap satisfies this condition, using `notes` for conditions on the user's notes and `stars`
+    /// to choose the star rating for `stars` conditions.
+    pub fn matches_with_stars(
+        &self,
+        beatmap: &BeatmapEntry,
+        notes: &Notes,
+        stars: StarPolicy,
+    ) -> bool {
+        match self {
+            Condition::Numeric { key, op, value } => key
+                .value_with(beatmap, stars)
+                .is_some_and(|v| op.compare_float(v, *value)),
+            Condition::Mode { op, mode } => op.compare(beatmap.gameplay_mode == *mode, true),
+            Condition::Status { op, status } => op.compare(beatmap.ranked_status == *status, true),
+            Condition::AnyMode { modes, converts } => {
+                modes.contains(&beatmap.gameplay_mode)
+                    || (*converts && beatmap.gameplay_mode == GameplayMode::Standard)
+            }
+            Condition::AnyStatus(statuses) => statuses.contains(&beatmap.ranked_status),
+            Condition::Text { key, op, value } => {
+                let contains = key
+                    .values(beatmap)
+                    .iter()
+                    .any(|v| v.to_lowercase().contains(value.as_str()));
+
+                op.compare(contains, true)
+            }
+            Condition::Unplayed(unplayed) => beatmap.is_unplayed == *unplayed,
+            Condition::VariableBpm(variable) => beatmap.timing_summary().variable_bpm == *variable,
+            Condition::Outdated(outdated) => {
+                beatmap.is_possibly_outdated(DEFAULT_OUTDATED_TOLERANCE) == *outdated
+            }
+            Condition::Added { op, date } => beatmap
+                .approximate_added_date(None)
+                .is_some_and(|added| op.compare(added.date(), *date)),
+            Condition::Note { op, value } => {
+                let contains = notes
+                    .get(beatmap)
+                    .is_some_and(|note| note.text.to_lowercase().contains(value.as_str()));
+
+                op.compare(contains, true)
+            }
+            Condition::Starred(starred) => {
+                notes.get(beatmap).is_some_and(|note| note.starred) == *starred
+            }
+        }
+    }
+
+    /// Checks whether this condition depends on the user's notes.
+    pub fn uses_notes(&self) -> bool {
+        matches!(self, Condition::Note { .. } | Condition::Starred(_))
+    }
+}
+
+impl FilterAggregates {
+    /// Summarises some beatmaps, using the star ratings chosen by `stars`.
+    pub fn from_beatmaps<'a>(
+        beatmaps: impl IntoIterator<Item = &'a BeatmapEntry>,
+        stars: StarPolicy,
+    ) -> FilterAggregates {
+        let mut aggregates = FilterAggregates::default();
+
+        for beatmap in beatmaps {
+            aggregates.add(beatmap, stars);
+        }
+
+        aggregates
+    }
+
+    /// Adds a beatmap to the summary, using the star rating chosen by `stars`. Star ratings and BPMs which aren't
+    /// finite (e.g. from a damaged `osu!.db`) are skipped, so that they don't affect the other beatmaps'.
+    pub fn add(&mut self, beatmap: &BeatmapEntry, stars: StarPolicy) {
+        self.count += 1;
+        self.drain_time += u64::from(beatmap.drain_time);
+
+        if let Some(rating) = beatmap.policy_star_rating(stars).filter(|r| r.is_finite()) {
+            self.rated += 1;
+            self.star_total += rating;
+        }
+
+        if let Some(bpm) = beatmap.main_bpm().filter(|bpm| bpm.is_finite()) {
+            self.bpm_range = Some(match self.bpm_range {
+                Some((min, max)) => (min.min(bpm), max.max(bpm)),
+                None => (bpm, bpm),
+            });
+        }
+
+        let status = beatmap.ranked_status;
+        match self
+            .statuses
+            .binary_search_by_key(&(status as u8), |(s, _)| *s as u8)
+        {
+            Ok(i) => self.statuses[i].1 += 1,
+            Err(i) => self.statuses.insert(i, (status, 1)),
+        }
+    }
+
+    /// Gets the mean star rating of the beatmaps with one.
+    pub fn mean_stars(&self) -> Option<f64> {
+        (self.rated > 0).then(|| self.star_total / self.rated as f64)
+    }
+}
+
+impl BeatmapFilter {
+    /// Parses a search query into a filter, whose `stars` conditions use the beatmap's own gameplay mode.
+    pub fn parse(query: &str) -> Result<BeatmapFilter, QueryError> {
+        Self::parse_with(query, StarPolicy::default())
+    }
+
+    /// Parses a search query into a filter, whose `stars` conditions choose the star rating using `stars`.
+    pub fn parse_with(query: &str, stars: StarPolicy) -> Result<BeatmapFilter, QueryError> {
+        let mut filter = BeatmapFilter {
+            stars,
+            ..Default::default()
+        };
+
+        let query = match query.trim_start().strip_prefix('~') {
+            Some(rest) => {
+                filter.fuzzy = true;
+                rest
+            }
+            None => query,
+        };
+
+        for (term, quoted) in tokenize(query)? {
+            match split_condition(&term).filter(|_| !quoted) {
+                Some((key, op, value)) => filter.conditions.push(condition(&term, key, op, value)?),
+                None => filter.text.push(term.to_lowercase()),
+            }
+        }
+
+        Ok(filter)
+    }
+
+    /// Adapts this filter to beatmaps played in a single gameplay mode, e.g. for a tab only showing that mode. `stars`
+    /// conditions use that mode's star rating, and in osu!mania, `keys` conditions include converted osu!standard
+    /// beatmaps.
+    pub fn for_mode(mut self, mode: GameplayMode) -> BeatmapFilter {
+        self.stars.source = StarSource::FixedMode(mode);
+
+        for condition in &mut self.conditions {
+            if let Condition::Numeric { key, .. } = condition {
+                *key = match *key {
+                    NumericKey::Stars => NumericKey::ModeStars(mode),
+                    NumericKey::Keys if mode == GameplayMode::Mania => NumericKey::ConvertedKeys,
+                    key => key,
+                };
+            }
+        }
+
+        self
+    }
+
+    /// Checks whether this filter has no conditions or free text, i.e. it will match every beatmap.
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty() && self.text.is_empty()
+    }
+
+    /// Checks whether any of this filter's conditions depend on the user's notes, i.e. whether the matches can change
+    /// when the notes do.
+    pub fn uses_notes(&self) -> bool {
+        self.conditions.iter().any(Condition::uses_notes)
+    }
+
+    /// Checks whether a beatmap satisfies every condition and free text term in this filter, treating it as having no
+    /// note.
+    pub fn matches(&self, beatmap: &BeatmapEntry) -> bool {
+        self.matches_with_notes(beatmap, &Notes::default())
+    }
+
+    /// Checks whether a beatmap satisfies every condition and free text term in this filter, using `notes` for
+    /// conditions on the user's notes.
+    pub fn matches_with_notes(&self, beatmap: &BeatmapEntry, notes: &Notes) -> bool {
+        self.matches_conditions(beatmap, notes) && self.matches_text(beatmap)
+    }
+
+    /// Checks whether a beatmap satisfies every condition in this filter, ignoring the free text.
+    pub fn matches_conditions(&self, beatmap: &BeatmapEntry, notes: &Notes) -> bool {
+        self.conditions
+            .iter()
+            .all(|c| c.matches_with_stars(beatmap, notes, self.stars))
+    }
+
+    /// Checks whether a beatmap contains every free text term in this filter, or matches them approximately if this is
+    /// a fuzzy filter.
+    pub fn matches_text(&self, beatmap: &BeatmapEntry) -> bool {
+        if self.text.is_empty() {
+            return true;
+        }
+
+        if self.fuzzy {
+            let query = FuzzyQuery::new(&self.text);
+            return fuzzy::score_beatmap(beatmap, &query, fuzzy::DEFAULT_THRESHOLD).is_some();
+        }
+
+        self.matches_exact_text(beatmap)
+    }
+
+    /// Checks whether a beatmap contains every free text term in this filter.
+    fn matches_exact_text(&self, beatmap: &BeatmapEntry) -> bool {
+        let haystack = [
+            &beatmap.artist_name,
+            &beatmap.artist_name_unicode,
+            &beatmap.song_title,
+            &beatmap.song_title_unicode,
+            &beatmap.creator_name,
+            &beatmap.difficulty,
+            &beatmap.song_source,
+            &beatmap.song_tags,
+        ]
+        .into_iter()
+        .filter_map(|f| f.as_deref())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+        self.text.iter().all(|t| haystack.contains(t.as_str()))
+    }
+
+    /// Summarises the beatmaps in a listing which match this filter, in the same pass as finding them. Like
+    /// [`matches`](Self::matches), beatmaps are treated as having no note.
+    pub fn aggregate(&self, listing: &BeatmapListing) -> FilterAggregates {
+        FilterAggregates::from_beatmaps(
+            listing.beatmaps.iter().filter(|b| self.matches(b)),
+            self.stars,
+        )
+    }
+
+    /// Finds the indices of the beatmaps matching this filter, using `notes` for conditions on the user's notes.
+    ///
+    /// Exact matches are returned in the order of the listing. Fuzzy filters are matched using `index`, which must have
+    /// been built from the same `beatmaps`, and ranked from the closest match to the furthest. If `fuzzy_fallback` is
+    /// set, a filter whose free text doesn't match anything exactly is matched approximately instead.
+    pub fn search(
+        &self,
+        beatmaps: &[BeatmapEntry],
+        notes: &Notes,
+        index: &FuzzyIndex,
+        fuzzy_fallback: bool,
+    ) -> Vec<usize> {
+        let candidates = beatmaps
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| self.matches_conditions(b, notes))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        if self.text.is_empty() {
+            return candidates;
+        }
+
+        if !self.fuzzy {
+            let exact = candidates
+                .iter()
+                .copied()
+                .filter(|&i| self.matches_exact_text(&beatmaps[i]))
+                .collect::<Vec<_>>();
+
+            if !exact.is_empty() || !fuzzy_fallback {
+                return exact;
+            }
+        }
+
+        index.rank(
+            candidates,
+            &FuzzyQuery::new(&self.text),
+            fuzzy::DEFAULT_THRESHOLD,
+        )
+    }
+}
+
+/// Splits a query into its terms, along with whether each term was quoted in its entirety.
+fn tokenize(query: &str) -> Result<Vec<(String, bool)>, QueryError> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut quoted = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => {
+                // A term is only treated as quoted if it starts with a quote (e.g. "re:zero", but not artist="...")
+                if !in_quotes && current.is_empty() {
+                    quoted = true;
+                }
+
+                in_quotes = !in_quotes;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    terms.push((std::mem::take(&mut current), quoted));
+                }
+
+                quoted = false;
+            }
+            c => current.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(QueryError::UnterminatedQuote { token: current });
+    }
+
+    if !current.is_empty() {
+        terms.push((current, quoted));
+    }
+
+    Ok(terms)
+}
+
+/// Attempts to split a term into a `key`, operator and `value`.
+///
+/// Returns `None` if the term doesn't look like a condition, in which case it should be treated as free text.
+pub(crate) fn split_condition(term: &str) -> Option<(&str, Operator, &str)> {
+    let op_start = term.find(['=', '<', '>', '!', ':'])?;
+    let (key, rest) = term.split_at(op_start);
+
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphabetic() || c == '_') {
+        return None;
+    }
+
+    let (symbol, op) = OPERATORS
+        .iter()
+        .find(|(symbol, _)| rest.starts_with(symbol))?;
+    let value = &rest[symbol.len()..];
+
+    if value.is_empty() {
+        return None;
+    }
+
+    Some((key, *op, value))
+}
+
+/// Parses a condition from its individual parts.
+pub(crate) fn condition(
+    token: &str,
+    key: &str,
+    op: Operator,
+    value: &str,
+) -> Result<Condition, QueryError> {
+    let invalid_value = || QueryError::InvalidValue {
+        token: token.to_string(),
+        key: key.to_string(),
+        value: value.to_string(),
+    };
+
+    let unsupported_operator = || QueryError::UnsupportedOperator {
+        token: token.to_string(),
+        key: key.to_string(),
+        op: op.to_string(),
+    };
+
+    let numeric = |key: NumericKey| {
+        value
+            .parse::<f64>()
+            .map(|value| Condition::Numeric { key, op, value })
+            .map_err(|_| invalid_value())
+    };
+
+    let text = |key: TextKey| match op {
+        Operator::Equal | Operator::NotEqual => Ok(Condition::Text {
+            key,
+            op,
+            value: value.to_lowercase(),
+        }),
+        _ => Err(unsupported_operator()),
+    };
+
+    let key_lower = key.to_lowercase();
+    if let Some(mode) = key_lower.strip_prefix("stars_") {
+        let mode = parse_mode(mode).ok_or_else(|| QueryError::UnknownKey {
+            token: token.to_string(),
+            key: key.to_string(),
+        })?;
+
+        return numeric(NumericKey::ModeStars(mode));
+    }
+
+    match key_lower.as_str() {
+        "stars" | "star" | "sr" => numeric(NumericKey::Stars),
+        "ar" => numeric(NumericKey::ApproachRate),
+        "cs" => numeric(NumericKey::CircleSize),
+        "od" => numeric(NumericKey::OverallDifficulty),
+        "hp" => numeric(NumericKey::HpDrain),
+        "objects" => numeric(NumericKey::Objects),
+        "keys" => numeric(NumericKey::Keys),
+        "length" | "drain" => {
+            let key = if key.eq_ignore_ascii_case("length") {
+                NumericKey::Length
+            } else {
+                NumericKey::Drain
+            };
+
+            parse_seconds(value)
+                .map(|value| Condition::Numeric { key, op, value })
+                .ok_or_else(invalid_value)
+        }
+        "mode" | "m" => match op {
+            Operator::Equal | Operator::NotEqual => parse_mode(value)
+                .map(|mode| Condition::Mode { op, mode })
+                .ok_or_else(invalid_value),
+            _ => Err(unsupported_operator()),
+        },
+        "status" | "s" => match op {
+            Operator::Equal | Operator::NotEqual => parse_status(value)
+                .map(|status| Condition::Status { op, status })
+                .ok_or_else(invalid_value),
+            _ => Err(unsupported_operator()),
+        },
+        "unplayed" | "played" => {
+            let unplayed = parse_bool(value).ok_or_else(invalid_value)?;
+            let unplayed = if key.eq_ignore_ascii_case("played") {
+                !unplayed
+            } else {
+                unplayed
+            };
+
+            match op {
+                Operator::Equal => Ok(Condition::Unplayed(unplayed)),
+                Operator::NotEqual => Ok(Condition::Unplayed(!unplayed)),
+                _ => Err(unsupported_operator()),
+            }
+        }
+        "variable_bpm" | "vbpm" => {
+            let variable = parse_bool(value).ok_or_else(invalid_value)?;
+
+            match op {
+                Operator::Equal => Ok(Condition::VariableBpm(variable)),
+                Operator::NotEqual => Ok(Condition::VariableBpm(!variable)),
+                _ => Err(unsupported_operator()),
+            }
+        }
+        "outdated" => {
+            let outdated = parse_bool(value).ok_or_else(invalid_value)?;
+
+            match op {
+                Operator::Equal => Ok(Condition::Outdated(outdated)),
+                Operator::NotEqual => Ok(Condition::Outdated(!outdated)),
+                _ => Err(unsupported_operator()),
+            }
+        }
+        "added" => parse_date(value)
+            .map(|date| Condition::Added { op, date })
+            .ok_or_else(invalid_value),
+        "artist" => text(TextKey::Artist),
+        "title" => text(TextKey::Title),
+        "creator" | "mapper" => text(TextKey::Creator),
+        "difficulty" | "diff" | "version" => text(TextKey::Difficulty),
+        "source" => text(TextKey::Source),
+        "tag" | "tags" => text(TextKey::Tags),
+        "md5" => text(TextKey::Md5),
+        "folder" => text(TextKey::Folder),
+        "note" | "notes" => match op {
+            Operator::Equal | Operator::NotEqual => Ok(Condition::Note {
+                op,
+                value: value.to_lowercase(),
+            }),
+            _ => Err(unsupported_operator()),
+        },
+        "starred" | "bookmarked" => {
+            let starred = parse_bool(value).ok_or_else(invalid_value)?;
+
+            match op {
+                Operator::Equal => Ok(Condition::Starred(starred)),
+                Operator::NotEqual => Ok(Condition::Starred(!starred)),
+                _ => Err(unsupported_operator()),
+            }
+        }
+        _ => Err(QueryError::UnknownKey {
+            token: token.to_string(),
+            key: key.to_string(),
+        }),
+    }
+}
+
+/// Parses a duration in seconds, e.g. `90`, `90s`, `1.5m` or `1:30`.
+fn parse_seconds(value: &str) -> Option<f64> {
+    if let Some((minutes, seconds)) = value.split_once(':') {
+        let minutes = minutes.parse::<f64>().ok()?;
+        let seconds = seconds.parse::<f64>().ok()?;
+        return Some(minutes * 60.0 + seconds);
+    }
+
+    let (number, multiplier) = match value.char_indices().last()? {
+        (i, 's') => (&value[..i], 1.0),
+        (i, 'm') => (&value[..i], 60.0),
+        (i, 'h') => (&value[..i], 3600.0),
+        _ => (value, 1.0),
+    };
+
+    number.parse::<f64>().ok().map(|n| n * multiplier)
+}
+
+/// Parses a gameplay mode name.
+fn parse_mode(value: &str) -> Option<GameplayMode> {
+    use GameplayMode::*;
+
+    match value.to_lowercase().as_str() {
+        "osu" | "std" | "standard" | "o" | "0" => Some(Standard),
+        "taiko" | "t" | "1" => Some(Taiko),
+        "catch" | "ctb" | "fruits" | "c" | "f" | "2" => Some(Catch),
+        "mania" | "m" | "3" => Some(Mania),
+        _ => None,
+    }
+}
+
+/// Parses a ranked status name.
+fn parse_status(value: &str) -> Option<RankedStatus> {
+    use RankedStatus::*;
+
+    match value.to_lowercase().as_str() {
+        "unknown" => Some(Unknown),
+        "unsubmitted" | "notsubmitted" | "u" => Some(Unsubmitted),
+        "pending" | "wip" | "graveyard" | "p" | "g" => Some(Pending),
+        "ranked" | "r" => Some(Ranked),
+        "approved" | "a" => Some(Approved),
+        "qualified" | "q" => Some(Qualified),
+        "loved" | "l" => Some(Loved),
+        _ => None,
+    }
+}
+
+/// Parses a date in the form `YYYY-MM-DD`.
+fn parse_date(value: &str) -> Option<Date> {
+    let mut parts = value.splitn(3, '-');
+    let year = parts.next()?.parse::<i32>().ok()?;
+    let month = parts.next()?.parse::<u8>().ok()?;
+    let day = parts.next()?.parse::<u8>().ok()?;
+
+    Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()
+}
+
+/// Parses a boolean value.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" => Some(true),
+        "false" | "no" | "n" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{macros::datetime, Duration};
+
+    use super::*;
+    use crate::{beatmaps::StarRating, common::Mods};
+
+    fn beatmap(mode: GameplayMode, stars: f64, total_time: u32) -> BeatmapEntry {
+        let ratings = Some(vec![StarRating {
+            mods: Mods::none(),
+            rating: stars,
+        }]);
+
+        let mut beatmap = BeatmapEntry {
+            artist_name: Some("Camellia".to_string()),
+            song_title: Some("Exit This Earth's Atomosphere".to_string()),
+            creator_name: Some("Mapper".to_string()),
+            difficulty: Some("Insane".to_string()),
+            gameplay_mode: mode,
+            ranked_status: RankedStatus::Ranked,
+            total_time,
+            ..Default::default()
+        };
+
+        match mode {
+            GameplayMode::Standard => beatmap.star_ratings_std = ratings,
+            GameplayMode::Taiko => beatmap.star_ratings_taiko = ratings,
+            GameplayMode::Catch => beatmap.star_ratings_ctb = ratings,
+            GameplayMode::Mania => beatmap.star_ratings_mania = ratings,
+        }
+
+        beatmap
+    }
+
+    #[test]
+    fn query_parsing_works() {
+        let filter =
+            BeatmapFilter::parse("stars>5.5 mode=std status=ranked length<4m camellia").unwrap();
+
+        assert_eq!(
+            filter.conditions,
+            vec![
+                Condition::Numeric {
+                    key: NumericKey::Stars,
+                    op: Operator::Greater,
+                    value: 5.5
+                },
+                Condition::Mode {
+                    op: Operator::Equal,
+                    mode: GameplayMode::Standard
+                },
+                Condition::Status {
+                    op: Operator::Equal,
+                    status: RankedStatus::Ranked
+                },
+                Condition::Numeric {
+                    key: NumericKey::Length,
+                    op: Operator::Less,
+                    value: 240.0
+                },
+            ]
+        );
+
+        assert_eq!(filter.text, vec!["camellia".to_string()]);
+    }
+
+    #[test]
+    fn quoted_terms_are_parsed() {
+        let filter = BeatmapFilter::parse(r#"artist="the quick" "re:zero""#).unwrap();
+
+        assert_eq!(
+            filter.conditions,
+            vec![Condition::Text {
+                key: TextKey::Artist,
+                op: Operator::Equal,
+                value: "the quick".to_string()
+            }]
+        );
+
+        assert_eq!(filter.text, vec!["re:zero".to_string()]);
+
+        assert_eq!(
+            BeatmapFilter::parse(r#"artist="the quick"#),
+            Err(QueryError::UnterminatedQuote {
+                token: "artist=the quick".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_queries_are_rejected() {
+        assert_eq!(
+            BeatmapFilter::parse("stars>5 bpm>200"),
+            Err(QueryError::UnknownKey {
+                token: "bpm>200".to_string(),
+                key: "bpm".to_string()
+            })
+        );
+
+        assert_eq!(
+            BeatmapFilter::parse("stars>five"),
+            Err(QueryError::InvalidValue {
+                token: "stars>five".to_string(),
+                key: "stars".to_string(),
+                value: "five".to_string()
+            })
+        );
+
+        assert_eq!(
+            BeatmapFilter::parse("mode>mania").map_err(|e| e.token().to_string()),
+            Err("mode>mania".to_string())
+        );
+    }
+
+    #[test]
+    fn filter_matching_works() {
+        let std_5 = beatmap(GameplayMode::Standard, 5.75, 200_000);
+        let std_6 = beatmap(GameplayMode::Standard, 6.2, 200_000);
+        let mania_5 = beatmap(GameplayMode::Mania, 5.75, 300_000);
+
+        let filter = BeatmapFilter::parse("stars>5.5 stars<6 mode=std length<240").unwrap();
+        assert!(filter.matches(&std_5));
+        assert!(!filter.matches(&std_6));
+        assert!(!filter.matches(&mania_5));
+
+        let filter = BeatmapFilter::parse("mode!=osu creator=MAP atomosphere").unwrap();
+        assert!(!filter.matches(&std_5));
+        assert!(filter.matches(&mania_5));
+
+        let filter = BeatmapFilter::parse("stars=5.75 unplayed=yes").unwrap();
+        assert!(filter.matches(&std_5));
+        assert!(!filter.matches(&std_6));
+
+        assert!(BeatmapFilter::default().matches(&std_6));
+    }
+
+    #[test]
+    fn metadata_is_matched_in_either_language() {
+        let beatmap = BeatmapEntry {
+            artist_name: Some(String::new()),
+            artist_name_unicode: Some("かめりあ".to_string()),
+            song_title_unicode: Some("エグジット".to_string()),
+            ..beatmap(GameplayMode::Standard, 5.0, 200_000)
+        };
+
+        for query in [
+            "artist=かめりあ",
+            "title=exit",
+            "title=エグジット",
+            "atomosphere",
+        ] {
+            let filter = BeatmapFilter::parse(query).unwrap();
+            assert!(filter.matches(&beatmap), "{}", query);
+        }
+    }
+
+    #[test]
+    fn fuzzy_queries_are_parsed() {
+        let filter = BeatmapFilter::parse(" ~fredom dive mode=std").unwrap();
+
+        assert!(filter.fuzzy);
+        assert_eq!(filter.text, vec!["fredom".to_string(), "dive".to_string()]);
+        assert_eq!(filter.conditions.len(), 1);
+
+        assert!(!BeatmapFilter::parse("freedom").unwrap().fuzzy);
+
+        let beatmap = beatmap(GameplayMode::Standard, 5.0, 200_000);
+        assert!(BeatmapFilter::parse("~camelia atmosphere")
+            .unwrap()
+            .matches(&beatmap));
+        assert!(!BeatmapFilter::parse("camelia atmosphere")
+            .unwrap()
+            .matches(&beatmap));
+    }
+
+    #[test]
+    fn fuzzy_search_is_ranked() {
+        let titled = |title: &str, mode| BeatmapEntry {
+            artist_name: Some("xi".to_string()),
+            song_title: Some(title.to_string()),
+            ..beatmap(mode, 5.0, 200_000)
+        };
+
+        let beatmaps = vec![
+            titled("Freedom", GameplayMode::Standard),
+            titled("Blue Zenith", GameplayMode::Standard),
+            titled("FREEDOM DiVE", GameplayMode::Mania),
+            titled("FREEDOM DiVE", GameplayMode::Standard),
+        ];
+        let index = FuzzyIndex::new(&beatmaps);
+        let notes = Notes::default();
+
+        let search = |query: &str, fallback| {
+            BeatmapFilter::parse(query)
+                .unwrap()
+                .search(&beatmaps, &notes, &index, fallback)
+        };
+
+        // Conditions still apply exactly, and the closest match comes first
+        assert_eq!(search("~fredom dive", false), vec![2, 3]);
+        assert_eq!(search("~fredom dive mode=std", false), vec![3]);
+        assert_eq!(search("~fredom", false), vec![0, 2, 3]);
+
+        // Exact matches stay in file order, and only fall back to fuzzy matching when enabled
+        assert_eq!(search("dive", true), vec![2, 3]);
+        assert!(search("fredom dive", false).is_empty());
+        assert_eq!(search("fredom dive", true), vec![2, 3]);
+        assert_eq!(search("mode=std", true), vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn mode_and_status_sets_work() {
+        let std = beatmap(GameplayMode::Standard, 5.75, 200_000);
+        let mania = beatmap(GameplayMode::Mania, 5.75, 300_000);
+        let loved = BeatmapEntry {
+            ranked_status: RankedStatus::Loved,
+            ..beatmap(GameplayMode::Taiko, 4.0, 100_000)
+        };
+
+        let modes = Condition::AnyMode {
+            modes: vec![GameplayMode::Taiko, GameplayMode::Mania],
+            converts: false,
+        };
+        assert!(!modes.matches(&std));
+        assert!(modes.matches(&mania));
+        assert!(modes.matches(&loved));
+
+        // osu!standard beatmaps can be converted to any mode
+        let converts = Condition::AnyMode {
+            modes: vec![GameplayMode::Mania],
+            converts: true,
+        };
+        assert!(converts.matches(&std));
+        assert!(!converts.matches(&loved));
+
+        let statuses = Condition::AnyStatus(vec![RankedStatus::Loved, RankedStatus::Qualified]);
+        assert!(!statuses.matches(&std));
+        assert!(statuses.matches(&loved));
+    }
+
+    #[test]
+    fn note_conditions_use_notes() {
+        let mut noted = beatmap(GameplayMode::Standard, 5.75, 200_000);
+        noted.md5 = Some("abc".to_string());
+        let other = beatmap(GameplayMode::Standard, 5.75, 200_000);
+
+        let mut notes = Notes::default();
+        notes.set(
+            "abc",
+            crate::notes::Note {
+                starred: true,
+                text: "Good for Aim practice".to_string(),
+            },
+        );
+
+        let filter = BeatmapFilter::parse("note=aim starred=yes").unwrap();
+        assert!(filter.uses_notes());
+        assert!(filter.matches_with_notes(&noted, &notes));
+        assert!(!filter.matches_with_notes(&other, &notes));
+        assert!(!filter.matches(&noted));
+
+        let filter = BeatmapFilter::parse("note!=aim starred=no").unwrap();
+        assert!(!filter.matches_with_notes(&noted, &notes));
+        assert!(filter.matches_with_notes(&other, &notes));
+        assert!(!BeatmapFilter::parse("stars>5").unwrap().uses_notes());
+    }
+
+    #[test]
+    fn mode_star_ratings_are_used() {
+        let mut std = beatmap(GameplayMode::Standard, 5.75, 200_000);
+        std.star_ratings_mania = Some(vec![StarRating {
+            mods: Mods::none(),
+            rating: 3.5,
+        }]);
+
+        assert_eq!(NumericKey::Stars.value(&std), Some(5.75));
+        assert_eq!(
+            NumericKey::ModeStars(GameplayMode::Mania).value(&std),
+            Some(3.5)
+        );
+        assert_eq!(NumericKey::ModeStars(GameplayMode::Taiko).value(&std), None);
+    }
+
+    #[test]
+    fn star_policies_choose_ratings() {
+        // An osu!standard beatmap with a mania convert, and native mania and taiko beatmaps
+        let mut convert = beatmap(GameplayMode::Standard, 5.75, 200_000);
+        convert.star_ratings_mania = Some(vec![StarRating {
+            mods: Mods::none(),
+            rating: 3.5,
+        }]);
+        let mania = beatmap(GameplayMode::Mania, 4.5, 200_000);
+        let taiko = beatmap(GameplayMode::Taiko, 6.25, 200_000);
+        let beatmaps = [convert, mania, taiko];
+
+        let matching = |query: &str, source: StarSource, missing_as_zero: bool| {
+            let stars = StarPolicy {
+                source,
+                missing_as_zero,
+            };
+            let filter = BeatmapFilter::parse_with(query, stars).unwrap();
+
+            (0..beatmaps.len())
+                .filter(|&i| filter.matches(&beatmaps[i]))
+                .collect::<Vec<_>>()
+        };
+
+        let mania_mode = StarSource::FixedMode(GameplayMode::Mania);
+        let prefer_mania = StarSource::PreferredWithFallback(GameplayMode::Mania);
+
+        // Each beatmap is rated for its own mode
+        assert_eq!(
+            matching("stars>4", StarSource::NativeMode, false),
+            vec![0, 1, 2]
+        );
+        assert_eq!(matching("stars<5", StarSource::NativeMode, false), vec![1]);
+
+        // Only beatmaps with a mania rating can match, unless missing ratings are treated as 0
+        assert_eq!(matching("stars<5", mania_mode, false), vec![0, 1]);
+        assert_eq!(matching("stars<5", mania_mode, true), vec![0, 1, 2]);
+        assert_eq!(matching("stars>=0", mania_mode, false), vec![0, 1]);
+
+        // Beatmaps without a mania rating fall back to their own mode
+        assert_eq!(matching("stars<5", prefer_mania, false), vec![0, 1]);
+        assert_eq!(matching("stars>6", prefer_mania, false), vec![2]);
+
+        // `stars_<mode>` always uses that mode, whatever the policy
+        assert_eq!(
+            matching("stars_mania<5", StarSource::NativeMode, false),
+            vec![0, 1]
+        );
+        assert_eq!(matching("stars_taiko>=0", prefer_mania, false), vec![2]);
+        assert_eq!(matching("stars_taiko<1", prefer_mania, true), vec![0, 1]);
+
+        assert_eq!(
+            BeatmapFilter::parse("stars_ctb>5").unwrap().conditions,
+            vec![Condition::Numeric {
+                key: NumericKey::ModeStars(GameplayMode::Catch),
+                op: Operator::Greater,
+                value: 5.0,
+            }]
+        );
+        assert!(matches!(
+            BeatmapFilter::parse("stars_pippi>5"),
+            Err(QueryError::UnknownKey { .. })
+        ));
+    }
+
+    #[test]
+    fn filters_are_adapted_to_a_mode() {
+        let mut convert = beatmap(GameplayMode::Standard, 5.75, 200_000);
+        convert.star_ratings_mania = Some(vec![StarRating {
+            mods: Mods::none(),
+            rating: 3.5,
+        }]);
+        convert.hitcircle_count = 100;
+        convert.overall_difficulty = 8.0;
+        let mut mania = beatmap(GameplayMode::Mania, 4.5, 200_000);
+        mania.circle_size = 4.0;
+
+        let filter = BeatmapFilter::parse("stars<4 keys=7").unwrap();
+        assert!(!filter.matches(&convert));
+
+        let filter = filter.for_mode(GameplayMode::Mania);
+        assert_eq!(
+            filter.stars.source,
+            StarSource::FixedMode(GameplayMode::Mania)
+        );
+        assert!(filter.matches(&convert));
+        assert!(!filter.matches(&mania));
+
+        // `keys` only counts converts in osu!mania
+        let filter = BeatmapFilter::parse("keys=7")
+            .unwrap()
+            .for_mode(GameplayMode::Standard);
+        assert!(!filter.matches(&convert));
+    }
+
+    #[test]
+    fn variable_bpm_is_matched() {
+        use crate::beatmaps::TimingPoint;
+
+        let red_line = |beat_length, song_offset| TimingPoint {
+            bpm: beat_length,
+            song_offset,
+            inherited: true,
+        };
+
+        let constant = BeatmapEntry {
+            timing_points: vec![red_line(500.0, 0.0), red_line(500.0, 10_000.0)],
+            ..beatmap(GameplayMode::Standard, 5.75, 200_000)
+        };
+        let variable = BeatmapEntry {
+            timing_points: vec![red_line(500.0, 0.0), red_line(300.0, 10_000.0)],
+            ..beatmap(GameplayMode::Standard, 5.75, 200_000)
+        };
+
+        let filter = BeatmapFilter::parse("variable_bpm=yes").unwrap();
+        assert_eq!(filter.conditions, [Condition::VariableBpm(true)]);
+        assert!(!filter.matches(&constant));
+        assert!(filter.matches(&variable));
+
+        let filter = BeatmapFilter::parse("vbpm!=yes").unwrap();
+        assert!(filter.matches(&constant));
+        assert!(!filter.matches(&variable));
+
+        assert!(BeatmapFilter::parse("variable_bpm>1").is_err());
+    }
+
+    #[test]
+    fn outdated_beatmaps_are_filtered() {
+        let checked = datetime!(2024-06-01 0:00 UTC);
+        let current = BeatmapEntry {
+            ranked_status: RankedStatus::Ranked,
+            last_modification_time: checked - Duration::days(2),
+            last_checked_online: checked,
+            ..Default::default()
+        };
+        let outdated = BeatmapEntry {
+            last_modification_time: checked + Duration::days(90),
+            ..current.clone()
+        };
+
+        let filter = BeatmapFilter::parse("outdated=true").unwrap();
+        assert_eq!(filter.conditions, [Condition::Outdated(true)]);
+        assert!(!filter.matches(&current));
+        assert!(filter.matches(&outdated));
+
+        let filter = BeatmapFilter::parse("outdated!=yes").unwrap();
+        assert!(filter.matches(&current));
+        assert!(!filter.matches(&outdated));
+
+        assert!(BeatmapFilter::parse("outdated>1").is_err());
+    }
+
+    #[test]
+    fn added_dates_are_filtered() {
+        let old = BeatmapEntry {
+            last_modification_time: datetime!(2023-12-31 23:00 UTC),
+            ..Default::default()
+        };
+        let new = BeatmapEntry {
+            last_modification_time: datetime!(2024-01-01 0:30 UTC),
+            ..Default::default()
+        };
+
+        let filter = BeatmapFilter::parse("added>=2024-01-01").unwrap();
+        assert_eq!(
+            filter.conditions,
+            [Condition::Added {
+                op: Operator::GreaterOrEqual,
+                date: datetime!(2024-01-01 0:00 UTC).date(),
+            }]
+        );
+        assert!(!filter.matches(&old));
+        assert!(filter.matches(&new));
+
+        let filter = BeatmapFilter::parse("added=2023-12-31").unwrap();
+        assert!(filter.matches(&old));
+        assert!(!filter.matches(&new));
+
+        // Beatmaps without a modification time have no date to compare
+        assert!(!BeatmapFilter::parse("added<2100-01-01")
+            .unwrap()
+            .matches(&BeatmapEntry::default()));
+
+        assert!(BeatmapFilter::parse("added>yesterday").is_err());
+        assert!(BeatmapFilter::parse("added>2024-02-30").is_err());
+    }
+
+    #[test]
+    fn duration_parsing_works() {
+        assert_eq!(parse_seconds("90"), Some(90.0));
+        assert_eq!(parse_seconds("90s"), Some(90.0));
+        assert_eq!(parse_seconds("1.5m"), Some(90.0));
+        assert_eq!(parse_seconds("1:30"), Some(90.0));
+        assert_eq!(parse_seconds("1h"), Some(3600.0));
+        assert_eq!(parse_seconds("abc"), None);
+    }
+
+    #[test]
+    fn matching_beatmaps_are_aggregated() {
+        use crate::{beatmaps::TimingPoint, common::WINDOWS_EPOCH};
+
+        let beatmap =
+            |stars: f64, drain_time: u32, bpm: f64, ranked_status: RankedStatus| BeatmapEntry {
+                drain_time,
+                ranked_status,
+                timing_points: vec![TimingPoint {
+                    bpm: 60_000.0 / bpm,
+                    song_offset: 0.0,
+                    inherited: true,
+                }],
+                ..beatmap(GameplayMode::Standard, stars, drain_time * 1000)
+            };
+
+        // A rating that isn't a number, and a beatmap without a rating or timing points
+        let mut unrated = beatmap(0.0, 60, 0.0, RankedStatus::Pending);
+        unrated.star_ratings_std = None;
+        unrated.timing_points.clear();
+
+        let listing = BeatmapListing {
+            version: 20240101,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps: vec![
+                beatmap(5.0, 100, 180.0, RankedStatus::Ranked),
+                beatmap(4.0, 200, 150.0, RankedStatus::Loved),
+                beatmap(f64::NAN, 50, 200.0, RankedStatus::Ranked),
+                unrated,
+                beatmap(9.0, 1000, 300.0, RankedStatus::Ranked),
+            ],
+            user_permissions: Default::default(),
+        };
+
+        let aggregates = BeatmapFilter::parse("stars<8").unwrap().aggregate(&listing);
+        assert_eq!(aggregates.count, 2);
+        assert_eq!(aggregates.mean_stars(), Some(4.5));
+        assert_eq!(aggregates.drain_time, 300);
+        assert_eq!(aggregates.bpm_range, Some((150.0, 180.0)));
+        assert_eq!(
+            aggregates.statuses,
+            vec![(RankedStatus::Ranked, 1), (RankedStatus::Loved, 1)]
+        );
+
+        // Beatmaps without a usable rating are still counted, without affecting the mean
+        let aggregates = BeatmapFilter::default().aggregate(&listing);
+        assert_eq!(aggregates.count, 5);
+        assert_eq!(aggregates.rated, 3);
+        assert_eq!(aggregates.mean_stars(), Some(6.0));
+        assert_eq!(aggregates.drain_time, 1410);
+        assert_eq!(aggregates.bpm_range, Some((150.0, 300.0)));
+        assert_eq!(
+            aggregates.statuses,
+            vec![
+                (RankedStatus::Pending, 1),
+                (RankedStatus::Ranked, 3),
+                (RankedStatus::Loved, 1),
+            ]
+        );
+
+        assert_eq!(
+            FilterAggregates::from_beatmaps([], StarPolicy::default()),
+            FilterAggregates::default()
+        );
+        assert_eq!(FilterAggregates::default().mean_stars(), None);
+    }
+}
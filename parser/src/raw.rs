@@ -0,0 +1,356 @@
+//! Inspecting the raw bytes of beatmap entries, for working out where the parser and an `osu.db` file disagree, e.g.
+//! when a new version of osu! changes the format or an entry is damaged.
+//!
+//! Entries are walked using the layout of each field rather than being decoded, so the fields of an entry can be found
+//! even if their values are invalid.
+
+use std::ops::Range;
+
+use nom::{bytes::complete::take, number::complete::le_u32, IResult};
+
+use crate::{
+    beatmaps::{listing_header, BeatmapListing},
+    binary::skip_osu_string,
+};
+
+/// Represents how a field of a beatmap entry is stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    /// A fixed number of bytes
+    Fixed(usize),
+
+    /// A string, starting with a marker byte and its length if it's present (see
+    /// [`parse_osu_string`](crate::binary::parse_osu_string))
+    String,
+
+    /// A list, starting with the number of items as a `u32`, where each item is the given number of bytes
+    List(usize),
+}
+
+/// Represents where one field of a beatmap entry is stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawField {
+    /// Name of the field in [`BeatmapEntry`](crate::beatmaps::BeatmapEntry)
+    pub name: &'static str,
+
+    pub kind: FieldKind,
+
+    /// Byte range of the field within the entry
+    pub range: Range<usize>,
+}
+
+/// Represents the fields found in the bytes of a beatmap entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EntryLayout {
+    /// Fields in the order they're stored, up to the last one that fits in the bytes
+    pub fields: Vec<RawField>,
+
+    /// Name of the first field that didn't fit, if the bytes ran out (or a string was invalid) before the end of the
+    /// entry
+    pub incomplete: Option<&'static str>,
+}
+
+impl FieldKind {
+    /// Skips over a field of this kind.
+    fn skip(self, input: &[u8]) -> IResult<&[u8], ()> {
+        let (i, _) = match self {
+            FieldKind::Fixed(size) => take(size)(input)?,
+            FieldKind::String => return skip_osu_string(input),
+            FieldKind::List(item_size) => {
+                let (i, count) = le_u32(input)?;
+                take((count as usize).saturating_mul(item_size))(i)?
+            }
+        };
+
+        Ok((i, ()))
+    }
+}
+
+impl EntryLayout {
+    /// Gets the length of the entry, if every field was found.
+    pub fn entry_len(&self) -> Option<usize> {
+        match (&self.incomplete, self.fields.last()) {
+            (None, Some(field)) => Some(field.range.end),
+            _ => None,
+        }
+    }
+
+    /// Gets the field stored at a position within the entry, if any.
+    pub fn field_at(&self, offset: usize) -> Option<&RawField> {
+        self.fields
+            .iter()
+            .find(|field| field.range.contains(&offset))
+    }
+}
+
+impl BeatmapListing {
+    /// Gets the bytes of one beatmap entry from the data this listing was parsed from, including the entry size for
+    /// versions that store it. Returns `None` if there is no beatmap at `index`, or `original_input` isn't the data the
+    /// listing was parsed from.
+    ///
+    /// The entry is found by skipping over the ones before it, which is much faster than parsing them. If the listing
+    /// was parsed with [`ParseOptions::record_spans`](crate::beatmaps::ParseOptions::record_spans), the same range is
+    /// given by [`PartialListing::span`](crate::beatmaps::PartialListing::span).
+    pub fn raw_entry_bytes<'a>(&self, index: usize, original_input: &'a [u8]) -> Option<&'a [u8]> {
+        if index >= self.beatmaps.len() {
+            return None;
+        }
+
+        let (mut i, header) = listing_header(original_input).ok()?;
+
+        if header.version != self.version {
+            return None;
+        }
+
+        let skip_entry = skip_beatmap_entry(self.version);
+
+        for _ in 0..index {
+            i = skip_entry(i).ok()?.0;
+        }
+
+        let (rest, ()) = skip_entry(i).ok()?;
+        Some(&i[..i.len() - rest.len()])
+    }
+}
+
+/// Gets the name and kind of each field of a beatmap entry, in the order they're stored in a version.
+pub fn entry_fields(version: u32) -> Vec<(&'static str, FieldKind)> {
+    use FieldKind::*;
+
+    let difficulty = if version < 20140609 { 1 } else { 4 };
+    let star_rating = if version < 20250108 { 14 } else { 10 };
+
+    let mut fields = Vec::new();
+
+    if version < 20191106 {
+        fields.push(("size", Fixed(4)));
+    }
+
+    fields.extend([
+        ("artist_name", String),
+        ("artist_name_unicode", String),
+        ("song_title", String),
+        ("song_title_unicode", String),
+        ("creator_name", String),
+        ("difficulty", String),
+        ("audio_filename", String),
+        ("md5", String),
+        ("beatmap_filename", String),
+        ("ranked_status", Fixed(1)),
+        ("hitcircle_count", Fixed(2)),
+        ("slider_count", Fixed(2)),
+        ("spinner_count", Fixed(2)),
+        ("last_modification_time", Fixed(8)),
+        ("approach_rate", Fixed(difficulty)),
+        ("circle_size", Fixed(difficulty)),
+        ("hp_drain", Fixed(difficulty)),
+        ("overall_difficulty", Fixed(difficulty)),
+        ("slider_velocity", Fixed(8)),
+    ]);
+
+    if version >= 20140609 {
+        fields.extend([
+            ("star_ratings_std", List(star_rating)),
+            ("star_ratings_taiko", List(star_rating)),
+            ("star_ratings_ctb", List(star_rating)),
+            ("star_ratings_mania", List(star_rating)),
+        ]);
+    }
+
+    fields.extend([
+        ("drain_time", Fixed(4)),
+        ("total_time", Fixed(4)),
+        ("audio_preview_time", Fixed(4)),
+        ("timing_points", List(8 + 8 + 1)),
+        ("difficulty_id", Fixed(4)),
+        ("beatmap_id", Fixed(4)),
+        ("thread_id", Fixed(4)),
+        ("grade_std", Fixed(1)),
+        ("grade_taiko", Fixed(1)),
+        ("grade_catch", Fixed(1)),
+        ("grade_mania", Fixed(1)),
+        ("local_offset", Fixed(2)),
+        ("stack_leniency", Fixed(4)),
+        ("gameplay_mode", Fixed(1)),
+        ("song_source", String),
+        ("song_tags", String),
+        ("online_offset", Fixed(2)),
+        ("font", String),
+        ("is_unplayed", Fixed(1)),
+        ("last_played", Fixed(8)),
+        ("is_osz2", Fixed(1)),
+        ("folder_name", String),
+        ("last_checked_online", Fixed(8)),
+        ("ignore_beatmap_hitsounds", Fixed(1)),
+        ("ignore_beatmap_skin", Fixed(1)),
+        ("disable_storyboard", Fixed(1)),
+        ("disable_video", Fixed(1)),
+        ("visual_override", Fixed(1)),
+    ]);
+
+    if version < 20140609 {
+        fields.push(("unknown_u16", Fixed(2)));
+    }
+
+    fields.extend([("unknown_u32", Fixed(4)), ("mania_scroll_speed", Fixed(1))]);
+    fields
+}
+
+/// Finds where each field is stored in the bytes of a beatmap entry, e.g. from
+/// [`BeatmapListing::raw_entry_bytes`]. Any bytes after the entry are ignored, so this can be given the rest of a file
+/// starting at an entry which couldn't be parsed.
+pub fn entry_layout(version: u32, entry: &[u8]) -> EntryLayout {
+    let mut layout = EntryLayout::default();
+    let mut i = entry;
+
+    for (name, kind) in entry_fields(version) {
+        let Ok((rest, ())) = kind.skip(i) else {
+            layout.incomplete = Some(name);
+            break;
+        };
+
+        let start = entry.len() - i.len();
+        let end = entry.len() - rest.len();
+
+        layout.fields.push(RawField {
+            name,
+            kind,
+            range: start..end,
+        });
+        i = rest;
+    }
+
+    layout
+}
+
+/// Skips over a beatmap entry without decoding it, which is used to find where each entry starts. Every field is walked
+/// (rather than trusting the entry size, for versions that store it), so that entries end in the same place as when
+/// they're parsed.
+pub(crate) fn skip_beatmap_entry(version: u32) -> impl Fn(&[u8]) -> IResult<&[u8], ()> {
+    let kinds = entry_fields(version)
+        .into_iter()
+        .map(|(_, kind)| kind)
+        .collect::<Vec<_>>();
+
+    move |mut input| {
+        for kind in &kinds {
+            input = kind.skip(input)?.0;
+        }
+
+        Ok((input, ()))
+    }
+}
+
+#[cfg(all(test, feature = "writer"))]
+mod tests {
+    use super::*;
+    use crate::{
+        beatmaps::{BeatmapEntry, StarRating, TimingPoint},
+        common::{Mods, WINDOWS_EPOCH},
+    };
+
+    fn listing(version: u32) -> BeatmapListing {
+        let beatmap = |md5: &str, timing_points: usize| BeatmapEntry {
+            md5: Some(md5.to_string()),
+            star_ratings_std: (version >= 20140609).then(|| {
+                vec![StarRating {
+                    mods: Mods::DoubleTime.into(),
+                    rating: 5.0,
+                }]
+            }),
+            star_ratings_taiko: (version >= 20140609).then(Vec::new),
+            star_ratings_ctb: (version >= 20140609).then(Vec::new),
+            star_ratings_mania: (version >= 20140609).then(Vec::new),
+            timing_points: vec![
+                TimingPoint {
+                    bpm: 500.0,
+                    song_offset: 0.0,
+                    inherited: true,
+                };
+                timing_points
+            ],
+            unknown_u16: (version < 20140609).then_some(0),
+            ..Default::default()
+        };
+
+        BeatmapListing {
+            version,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: Some("Player".to_string()),
+            beatmaps: vec![beatmap("first", 0), beatmap("second", 3)],
+            user_permissions: Default::default(),
+        }
+    }
+
+    #[test]
+    fn raw_entries_match_the_parsed_spans() {
+        for version in [20121008, 20150203, 20191106, 20250108] {
+            let listing = listing(version);
+            let data = listing.to_bytes();
+            let partial = BeatmapListing::from_bytes_with_options(
+                &data,
+                crate::beatmaps::ParseOptions {
+                    record_spans: true,
+                    ..Default::default()
+                },
+                |_, _| true,
+            )
+            .unwrap();
+
+            for index in 0..2 {
+                let span = partial.span(index).unwrap();
+                let entry = listing.raw_entry_bytes(index, &data).unwrap();
+
+                assert_eq!(entry, &data[span], "version {}", version);
+
+                let layout = entry_layout(version, entry);
+                assert_eq!(layout.entry_len(), Some(entry.len()), "version {}", version);
+                assert_eq!(layout.incomplete, None);
+            }
+
+            assert_eq!(listing.raw_entry_bytes(2, &data), None);
+            assert_eq!(listing.raw_entry_bytes(0, &data[..10]), None);
+        }
+    }
+
+    #[test]
+    fn fields_are_annotated() {
+        let listing = listing(20250108);
+        let data = listing.to_bytes();
+        let entry = listing.raw_entry_bytes(1, &data).unwrap();
+        let layout = entry_layout(listing.version, entry);
+
+        // Artist and title strings are empty, so the MD5 hash starts after 7 single-byte strings
+        let md5 = layout
+            .fields
+            .iter()
+            .find(|field| field.name == "md5")
+            .unwrap();
+        assert_eq!(md5.range, 7..7 + 2 + "second".len());
+        assert_eq!(&entry[md5.range.start + 2..md5.range.end], b"second");
+        assert_eq!(layout.field_at(md5.range.start).unwrap().name, "md5");
+
+        let timing_points = layout
+            .fields
+            .iter()
+            .find(|field| field.name == "timing_points")
+            .unwrap();
+        assert_eq!(timing_points.range.len(), 4 + 3 * 17);
+
+        assert_eq!(layout.fields.last().unwrap().name, "mania_scroll_speed");
+    }
+
+    #[test]
+    fn truncated_entries_are_incomplete() {
+        let listing = listing(20191106);
+        let data = listing.to_bytes();
+        let entry = listing.raw_entry_bytes(1, &data).unwrap();
+        let layout = entry_layout(listing.version, &entry[..16]);
+
+        assert_eq!(layout.entry_len(), None);
+        assert_eq!(layout.incomplete, Some("ranked_status"));
+        assert_eq!(layout.fields.last().unwrap().name, "beatmap_filename");
+    }
+}
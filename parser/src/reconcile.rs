@@ -0,0 +1,387 @@
+//! Comparing the metadata stored in `osu!.db` with the beatmaps' `.osu` files.
+//!
+//! osu! copies each beatmap's metadata and difficulty settings into `osu!.db` when it reads the `.osu` file. If the file
+//! is edited by hand (rather than in the editor), the copy can go stale until osu! notices the change. The stale fields
+//! are found with [`BeatmapEntry::reconcile_with_osu_file`], and corrected with [`BeatmapEntry::apply_osu_metadata`]
+//! before writing the listing.
+
+use std::{fmt, io, path::Path};
+
+use crate::{
+    beatmaps::BeatmapEntry,
+    ids,
+    osu_file::{self, OsuMetadata},
+    paths,
+};
+
+/// Difference allowed between difficulty settings, since `osu!.db` may store them with less precision.
+const SETTING_TOLERANCE: f32 = 0.005;
+
+/// Represents the fields that are compared with a `.osu` file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MetadataField {
+    Title,
+    TitleUnicode,
+    Artist,
+    ArtistUnicode,
+    Creator,
+    Difficulty,
+    ApproachRate,
+    CircleSize,
+    OverallDifficulty,
+    HpDrain,
+    DifficultyId,
+    BeatmapsetId,
+}
+
+/// Represents a field whose value in `osu!.db` is different from the beatmap's `.osu` file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldMismatch {
+    pub field: MetadataField,
+
+    /// Value stored in `osu!.db`
+    pub stored: String,
+
+    /// Value in the `.osu` file
+    pub in_file: String,
+}
+
+impl MetadataField {
+    pub fn name(&self) -> &'static str {
+        use MetadataField::*;
+
+        match self {
+            Title => "Title",
+            TitleUnicode => "Title (Unicode)",
+            Artist => "Artist",
+            ArtistUnicode => "Artist (Unicode)",
+            Creator => "Creator",
+            Difficulty => "Difficulty",
+            ApproachRate => "AR",
+            CircleSize => "CS",
+            OverallDifficulty => "OD",
+            HpDrain => "HP",
+            DifficultyId => "Difficulty ID",
+            BeatmapsetId => "Beatmapset ID",
+        }
+    }
+}
+
+impl fmt::Display for MetadataField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for FieldMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is '{}' in osu!.db, but '{}' in the .osu file",
+            self.field, self.stored, self.in_file
+        )
+    }
+}
+
+impl BeatmapEntry {
+    /// Reads the beatmap's `.osu` file from a `Songs` folder, and finds the fields whose value in `osu!.db` is
+    /// different (see [`BeatmapEntry::metadata_mismatches`]).
+    ///
+    /// Returns an error of kind [`io::ErrorKind::NotFound`] if the beatmap doesn't have a file name.
+    pub fn reconcile_with_osu_file<P: AsRef<Path>>(
+        &self,
+        songs_dir: P,
+    ) -> io::Result<Vec<FieldMismatch>> {
+        let path = paths::beatmap_file_in_songs(songs_dir, self)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+
+        Ok(self.metadata_mismatches(&osu_file::read_metadata(path)?))
+    }
+
+    /// Finds the fields whose value is different from the metadata read from the beatmap's `.osu` file. Fields missing
+    /// from the file aren't compared.
+    ///
+    /// Unsubmitted beatmaps may have an ID of either `0` or `-1`, so these are treated as the same.
+    pub fn metadata_mismatches(&self, metadata: &OsuMetadata) -> Vec<FieldMismatch> {
+        let mut mismatches = Vec::new();
+
+        let texts = [
+            (MetadataField::Title, &self.song_title, &metadata.title),
+            (
+                MetadataField::TitleUnicode,
+                &self.song_title_unicode,
+                &metadata.title_unicode,
+            ),
+            (MetadataField::Artist, &self.artist_name, &metadata.artist),
+            (
+                MetadataField::ArtistUnicode,
+                &self.artist_name_unicode,
+                &metadata.artist_unicode,
+            ),
+            (
+                MetadataField::Creator,
+                &self.creator_name,
+                &metadata.creator,
+            ),
+            (
+                MetadataField::Difficulty,
+                &self.difficulty,
+                &metadata.version,
+            ),
+        ];
+
+        for (field, stored, in_file) in texts {
+            if let Some(in_file) = in_file {
+                let stored = stored.as_deref().unwrap_or_default();
+
+                if stored != in_file {
+                    mismatches.push(FieldMismatch {
+                        field,
+                        stored: stored.to_string(),
+                        in_file: in_file.clone(),
+                    });
+                }
+            }
+        }
+
+        let settings = [
+            (
+                MetadataField::ApproachRate,
+                self.approach_rate,
+                metadata.approach_rate,
+            ),
+            (
+                MetadataField::CircleSize,
+                self.circle_size,
+                metadata.circle_size,
+            ),
+            (
+                MetadataField::OverallDifficulty,
+                self.overall_difficulty,
+                metadata.overall_difficulty,
+            ),
+            (MetadataField::HpDrain, self.hp_drain, metadata.hp_drain),
+        ];
+
+        for (field, stored, in_file) in settings {
+            if let Some(in_file) = in_file {
+                if (stored - in_file).abs() > SETTING_TOLERANCE {
+                    mismatches.push(FieldMismatch {
+                        field,
+                        stored: stored.to_string(),
+                        in_file: in_file.to_string(),
+                    });
+                }
+            }
+        }
+
+        let ids = [
+            (
+                MetadataField::DifficultyId,
+                self.difficulty_id,
+                metadata.beatmap_id,
+            ),
+            (
+                MetadataField::BeatmapsetId,
+                self.beatmap_id,
+                metadata.beatmapset_id,
+            ),
+        ];
+
+        for (field, stored, in_file) in ids {
+            if let Some(in_file) = in_file {
+                if same_id(stored, in_file) {
+                    continue;
+                }
+
+                mismatches.push(FieldMismatch {
+                    field,
+                    stored: id_text(stored),
+                    in_file: id_text(in_file),
+                });
+            }
+        }
+
+        mismatches
+    }
+
+    /// Updates the fields whose value is different from the metadata read from the beatmap's `.osu` file, e.g. before
+    /// writing the listing. Fields missing from the file are left alone. Returns `true` if anything changed.
+    pub fn apply_osu_metadata(&mut self, metadata: &OsuMetadata) -> bool {
+        let mismatches = self.metadata_mismatches(metadata);
+
+        for mismatch in &mismatches {
+            match mismatch.field {
+                MetadataField::Title => self.song_title = metadata.title.clone(),
+                MetadataField::TitleUnicode => {
+                    self.song_title_unicode = metadata.title_unicode.clone()
+                }
+                MetadataField::Artist => self.artist_name = metadata.artist.clone(),
+                MetadataField::ArtistUnicode => {
+                    self.artist_name_unicode = metadata.artist_unicode.clone()
+                }
+                MetadataField::Creator => self.creator_name = metadata.creator.clone(),
+                MetadataField::Difficulty => self.difficulty = metadata.version.clone(),
+                MetadataField::ApproachRate => {
+                    self.approach_rate = metadata.approach_rate.unwrap_or_default()
+                }
+                MetadataField::CircleSize => {
+                    self.circle_size = metadata.circle_size.unwrap_or_default()
+                }
+                MetadataField::OverallDifficulty => {
+                    self.overall_difficulty = metadata.overall_difficulty.unwrap_or_default()
+                }
+                MetadataField::HpDrain => self.hp_drain = metadata.hp_drain.unwrap_or_default(),
+                MetadataField::DifficultyId => {
+                    self.difficulty_id = metadata.beatmap_id.unwrap_or_default()
+                }
+                MetadataField::BeatmapsetId => {
+                    self.beatmap_id = metadata.beatmapset_id.unwrap_or_default()
+                }
+            }
+        }
+
+        !mismatches.is_empty()
+    }
+}
+
+/// Checks whether two IDs are the same, treating both ways of storing an unsubmitted beatmap's ID as the same.
+fn same_id(a: u32, b: u32) -> bool {
+    a == b || (!ids::is_submitted(a) && !ids::is_submitted(b))
+}
+
+/// Formats an ID as it appears in a `.osu` file.
+fn id_text(id: u32) -> String {
+    (id as i32).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    const OSU_FILE: &str = "\u{feff}osu file format v14\r\n\
+        \r\n\
+        [Metadata]\r\n\
+        Title:FREEDOM DiVE\r\n\
+        Artist:xi\r\n\
+        Creator:Nakagawa-Kanon\r\n\
+        Version:FOUR DIMENSIONS\r\n\
+        BeatmapID:129891\r\n\
+        BeatmapSetID:39804\r\n\
+        \r\n\
+        [Difficulty]\r\n\
+        HPDrainRate:6\r\n\
+        CircleSize:4\r\n\
+        OverallDifficulty:8\r\n\
+        ApproachRate:9\r\n";
+
+    fn beatmap() -> BeatmapEntry {
+        BeatmapEntry {
+            song_title: Some("FREEDOM DiVE".to_string()),
+            artist_name: Some("xi".to_string()),
+            creator_name: Some("Nakagawa-Kanon".to_string()),
+            difficulty: Some("FOUR DIMENSIONS".to_string()),
+            difficulty_id: 129891,
+            beatmap_id: 39804,
+            hp_drain: 6.0,
+            circle_size: 4.0,
+            overall_difficulty: 8.0,
+            approach_rate: 9.0,
+            folder_name: Some("39804 xi - FREEDOM DiVE".to_string()),
+            beatmap_filename: Some(
+                "xi - FREEDOM DiVE (Nakagawa-Kanon) [FOUR DIMENSIONS].osu".to_string(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matching_files_have_no_mismatches() {
+        let metadata = osu_file::metadata(OSU_FILE);
+        assert_eq!(beatmap().metadata_mismatches(&metadata), []);
+
+        // Keys missing from the file aren't compared
+        assert_eq!(
+            BeatmapEntry::default().metadata_mismatches(&OsuMetadata::default()),
+            []
+        );
+
+        // Unsubmitted beatmaps may store -1 in the file, but 0 in osu!.db
+        let unsubmitted = BeatmapEntry {
+            difficulty_id: 0,
+            ..beatmap()
+        };
+        let metadata = osu_file::metadata(&OSU_FILE.replace("BeatmapID:129891", "BeatmapID:-1"));
+        assert_eq!(unsubmitted.metadata_mismatches(&metadata), []);
+    }
+
+    #[test]
+    fn stale_fields_are_found_and_corrected() {
+        let songs =
+            std::env::temp_dir().join(format!("osu-db-parser-reconcile-{}", std::process::id()));
+        let mut beatmap = beatmap();
+        let folder = songs.join(beatmap.folder_name.as_deref().unwrap());
+
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(
+            folder.join(beatmap.beatmap_filename.as_deref().unwrap()),
+            OSU_FILE
+                .replace("Version:FOUR DIMENSIONS", "Version:FIVE DIMENSIONS")
+                .replace("ApproachRate:9", "ApproachRate:9.3")
+                .replace("BeatmapID:129891", "BeatmapID:-1"),
+        )
+        .unwrap();
+
+        let mismatches = beatmap.reconcile_with_osu_file(&songs).unwrap();
+        assert_eq!(
+            mismatches,
+            [
+                FieldMismatch {
+                    field: MetadataField::Difficulty,
+                    stored: "FOUR DIMENSIONS".to_string(),
+                    in_file: "FIVE DIMENSIONS".to_string(),
+                },
+                FieldMismatch {
+                    field: MetadataField::ApproachRate,
+                    stored: "9".to_string(),
+                    in_file: "9.3".to_string(),
+                },
+                FieldMismatch {
+                    field: MetadataField::DifficultyId,
+                    stored: "129891".to_string(),
+                    in_file: "-1".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            mismatches[0].to_string(),
+            "Difficulty is 'FOUR DIMENSIONS' in osu!.db, but 'FIVE DIMENSIONS' in the .osu file"
+        );
+
+        let metadata =
+            osu_file::read_metadata(folder.join(beatmap.beatmap_filename.as_deref().unwrap()))
+                .unwrap();
+        assert!(beatmap.apply_osu_metadata(&metadata));
+        assert_eq!(beatmap.difficulty.as_deref(), Some("FIVE DIMENSIONS"));
+        assert_eq!(beatmap.approach_rate, 9.3);
+        assert_eq!(beatmap.difficulty_id, u32::MAX);
+        assert_eq!(beatmap.overall_difficulty, 8.0);
+
+        assert!(!beatmap.apply_osu_metadata(&metadata));
+        assert_eq!(beatmap.reconcile_with_osu_file(&songs).unwrap(), []);
+
+        // Beatmaps without a file can't be compared
+        let missing = BeatmapEntry {
+            beatmap_filename: Some("deleted.osu".to_string()),
+            ..beatmap
+        };
+        assert_eq!(
+            missing.reconcile_with_osu_file(&songs).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        fs::remove_dir_all(&songs).ok();
+    }
+}
@@ -0,0 +1,385 @@
+//! Best-effort recovery of the beatmaps in a damaged `osu.db` file, e.g. after part of it was overwritten during a
+//! power loss.
+//!
+//! Entries are parsed in order until one is invalid, and then the file is scanned for the next position that looks
+//! like the start of an entry. The damaged entries themselves are lost, but keeping the rest of the library means that
+//! osu! doesn't need to rebuild it from scratch.
+
+use std::ops::Range;
+
+use flagset::FlagSet;
+use nom::IResult;
+
+use crate::{
+    beatmaps::{beatmap_entry, listing_header, user_permissions, BeatmapEntry, BeatmapListing},
+    binary::parse_osu_string,
+    detect::FileKind,
+    error::Error,
+};
+
+/// Represents the result of repairing an `osu.db` file with [`repair`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repair {
+    /// Listing containing the recovered beatmaps, which can be written as a new `osu.db` file
+    pub listing: BeatmapListing,
+
+    /// Number of beatmaps the file should contain, according to its header
+    pub expected: usize,
+
+    /// Byte ranges of the file that couldn't be recovered, in file order
+    pub unrecoverable: Vec<Range<usize>>,
+}
+
+impl Repair {
+    /// Gets the number of beatmaps that were recovered.
+    pub fn recovered(&self) -> usize {
+        self.listing.beatmaps.len()
+    }
+
+    /// Gets the number of beatmaps that were lost, assuming the header's beatmap count is correct.
+    pub fn lost(&self) -> usize {
+        self.expected.saturating_sub(self.recovered())
+    }
+}
+
+/// Recovers as many beatmaps as possible from a damaged `osu.db` file.
+///
+/// The header must be intact, since the version determines how entries are stored. Entries without a valid MD5 hash
+/// are treated as damaged, since osu! always stores one. User permissions are only kept if the end of the file is
+/// intact, and the folder count is recalculated if any beatmaps were lost.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(bytes = data.len())))]
+pub fn repair(data: &[u8]) -> Result<Repair, Error> {
+    let (rest, header) =
+        listing_header(data).map_err(|e| Error::parse(FileKind::Beatmaps, data, e))?;
+    let version = header.version;
+    let expected = header.beatmap_count as usize;
+    let parse_entry = beatmap_entry(version);
+
+    let mut offset = data.len() - rest.len();
+    let mut beatmaps = Vec::new();
+    let mut unrecoverable = Vec::new();
+
+    // The last 4 bytes are the user permissions, if the end of the file is intact
+    while beatmaps.len() < expected && data.len() - offset > 4 {
+        if let Some((beatmap, end)) = entry_at(data, offset, &parse_entry) {
+            beatmaps.push(beatmap);
+            offset = end;
+            continue;
+        }
+
+        // Skip ahead to the next entry that can be parsed, if there is one
+        let next = (offset + 1..data.len())
+            .filter(|&start| looks_like_entry(data, start, version))
+            .find_map(|start| entry_at(data, start, &parse_entry).map(|found| (start, found)));
+
+        match next {
+            Some((start, (beatmap, end))) => {
+                unrecoverable.push(offset..start);
+                beatmaps.push(beatmap);
+                offset = end;
+            }
+            None => {
+                unrecoverable.push(offset..data.len());
+                offset = data.len();
+            }
+        }
+    }
+
+    let user_permissions = match data.len() - offset {
+        0 => FlagSet::default(),
+        4 => user_permissions(&data[offset..])
+            .map(|(_, permissions)| permissions)
+            .unwrap_or_default(),
+        _ => {
+            unrecoverable.push(offset..data.len());
+            FlagSet::default()
+        }
+    };
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        recovered = beatmaps.len(),
+        expected,
+        regions = unrecoverable.len(),
+        "repaired listing"
+    );
+
+    let mut listing = BeatmapListing {
+        version,
+        folder_count: header.folder_count,
+        account_unlocked: header.account_unlocked,
+        account_unlock_date: header.account_unlock_date,
+        player_name: header.player_name,
+        beatmaps,
+        user_permissions,
+    };
+
+    // The stored folder count is kept for intact files, so that they're written back unchanged
+    if !unrecoverable.is_empty() {
+        listing.fix_folder_count();
+    }
+
+    Ok(Repair {
+        listing,
+        expected,
+        unrecoverable,
+    })
+}
+
+/// Parses the entry starting at `start`, returning it with the offset where it ends if it looks valid.
+fn entry_at<F>(data: &[u8], start: usize, parse_entry: &F) -> Option<(BeatmapEntry, usize)>
+where
+    F: Fn(&[u8]) -> IResult<&[u8], BeatmapEntry>,
+{
+    let (rest, beatmap) = parse_entry(&data[start..]).ok()?;
+    let end = data.len() - rest.len();
+
+    let valid_md5 = beatmap
+        .md5
+        .as_ref()
+        .is_some_and(|md5| md5.len() == 32 && md5.bytes().all(|b| b.is_ascii_hexdigit()));
+    let valid_size = beatmap
+        .size
+        .is_none_or(|size| size as usize == end - start - 4);
+
+    (valid_md5 && valid_size).then_some((beatmap, end))
+}
+
+/// Quickly checks whether an entry could start at `start`, before trying to parse it.
+///
+/// Older versions store the size of each entry, which must fit in the rest of the file. Newer versions don't, so the
+/// entry must start with an artist name made of printable characters.
+fn looks_like_entry(data: &[u8], start: usize, version: u32) -> bool {
+    let mut entry = &data[start..];
+
+    if version < 20191106 {
+        let Some((size, rest)) = entry.split_first_chunk::<4>() else {
+            return false;
+        };
+
+        let size = u32::from_le_bytes(*size) as usize;
+        if size == 0 || size > rest.len() {
+            return false;
+        }
+
+        entry = rest;
+    }
+
+    match parse_osu_string::<()>(entry) {
+        Ok((_, Some(artist))) => !artist.is_empty() && !artist.chars().any(char::is_control),
+        Ok((_, None)) => version < 20191106,
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::beatmaps::{ParseOptions, StarRating, UserPermissions};
+    use crate::common::Mods;
+
+    const VERSIONS: [u32; 4] = [20250108, 20191106, 20150203, 20140101];
+
+    /// Builds the bytes for a listing with `count` beatmaps, returning them with the byte range of each entry.
+    fn library(version: u32, count: usize) -> (Vec<u8>, Vec<Range<usize>>) {
+        let modern = version >= 20140609;
+        let star_ratings = modern.then(|| {
+            vec![StarRating {
+                mods: Mods::none(),
+                rating: 4.5,
+            }]
+        });
+
+        let beatmaps = (0..count)
+            .map(|i| BeatmapEntry {
+                artist_name: Some(format!("Artist {}", i)),
+                song_title: Some(format!("Song {}", i)),
+                difficulty: Some("Hard".repeat(i % 4)),
+                md5: Some(format!("{:032x}", i)),
+                beatmap_filename: Some(format!("Artist {} - Song {}.osu", i, i)),
+                hitcircle_count: i as u16,
+                last_modification_time: datetime!(2024-01-02 03:04:05 UTC),
+                star_ratings_std: star_ratings.clone(),
+                star_ratings_taiko: modern.then(Vec::new),
+                star_ratings_ctb: modern.then(Vec::new),
+                star_ratings_mania: modern.then(Vec::new),
+                folder_name: Some(format!("{} Artist {} - Song {}", i, i, i)),
+                unknown_u16: (!modern).then_some(0),
+                ..Default::default()
+            })
+            .collect();
+
+        let listing = BeatmapListing {
+            version,
+            folder_count: count as u32,
+            account_unlocked: true,
+            account_unlock_date: crate::common::WINDOWS_EPOCH,
+            player_name: Some("Player".to_string()),
+            beatmaps,
+            user_permissions: UserPermissions::Normal | UserPermissions::Supporter,
+        };
+
+        let data = listing.to_bytes();
+        let options = ParseOptions {
+            record_spans: true,
+            ..Default::default()
+        };
+        let spans = BeatmapListing::from_bytes_with_options(&data, options, |_, _| true)
+            .unwrap()
+            .spans;
+
+        (data, spans)
+    }
+
+    /// Gets the MD5 hashes of the recovered beatmaps, to check which ones were kept.
+    fn recovered_md5s(repair: &Repair) -> Vec<String> {
+        repair
+            .listing
+            .beatmaps
+            .iter()
+            .map(|beatmap| beatmap.md5.clone().unwrap())
+            .collect()
+    }
+
+    fn md5s(indices: impl IntoIterator<Item = usize>) -> Vec<String> {
+        indices.into_iter().map(|i| format!("{:032x}", i)).collect()
+    }
+
+    #[test]
+    fn intact_files_are_unchanged() {
+        for version in VERSIONS {
+            let (data, _) = library(version, 20);
+            let repair = repair(&data).unwrap();
+
+            assert_eq!(repair.recovered(), 20);
+            assert_eq!(repair.lost(), 0);
+            assert!(repair.unrecoverable.is_empty());
+            assert_eq!(repair.listing.to_bytes(), data, "version {}", version);
+        }
+    }
+
+    #[test]
+    fn damaged_entry_is_skipped() {
+        for version in VERSIONS {
+            let (mut data, spans) = library(version, 20);
+            data[spans[5].start..spans[5].start + 16].fill(0xff);
+
+            let repair = repair(&data).unwrap();
+
+            assert_eq!(repair.recovered(), 19, "version {}", version);
+            assert_eq!(recovered_md5s(&repair), md5s((0..20).filter(|&i| i != 5)));
+            assert_eq!(repair.unrecoverable, [spans[5].clone()]);
+            assert_eq!(
+                repair.listing.user_permissions,
+                UserPermissions::Normal | UserPermissions::Supporter
+            );
+        }
+    }
+
+    #[test]
+    fn zeroed_region_is_skipped() {
+        for version in VERSIONS {
+            let (mut data, spans) = library(version, 20);
+            data[spans[10].start..spans[13].start].fill(0);
+
+            let repair = repair(&data).unwrap();
+
+            assert_eq!(repair.recovered(), 17, "version {}", version);
+            assert_eq!(
+                recovered_md5s(&repair),
+                md5s((0..10).chain(13..20)),
+                "version {}",
+                version
+            );
+            let lost = spans[10].start..spans[13].start;
+            assert_eq!(repair.unrecoverable, [lost]);
+            assert_eq!(repair.listing.folder_count, 17);
+        }
+    }
+
+    #[test]
+    fn several_damaged_regions_are_skipped() {
+        for version in VERSIONS {
+            let (mut data, spans) = library(version, 30);
+            data[spans[2].start] = 0x05;
+            data[spans[15].start..spans[15].start + 8].fill(0xaa);
+            data[spans[16].start..spans[16].start + 8].fill(0x55);
+            data[spans[28].start] = 0x0c;
+
+            let repair = repair(&data).unwrap();
+
+            assert_eq!(repair.recovered(), 26, "version {}", version);
+            assert_eq!(repair.lost(), 4);
+            assert_eq!(
+                repair.unrecoverable,
+                [
+                    spans[2].clone(),
+                    spans[15].start..spans[17].start,
+                    spans[28].clone()
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn inserted_garbage_is_skipped() {
+        for version in VERSIONS {
+            let (data, spans) = library(version, 20);
+
+            // Bytes from a simple LCG, so that the garbage is the same each time
+            let mut state = 12345u32;
+            let garbage = (0..300).map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                (state >> 16) as u8
+            });
+
+            let mut damaged = data[..spans[7].start].to_vec();
+            damaged.extend(garbage);
+            damaged.extend(&data[spans[7].start..]);
+
+            let repair = repair(&damaged).unwrap();
+
+            assert_eq!(repair.recovered(), 20, "version {}", version);
+            let lost = spans[7].start..spans[7].start + 300;
+            assert_eq!(repair.unrecoverable, [lost]);
+            assert_eq!(repair.listing.to_bytes(), data);
+        }
+    }
+
+    #[test]
+    fn truncated_file_keeps_complete_entries() {
+        for version in VERSIONS {
+            let (data, spans) = library(version, 20);
+            let truncated = &data[..spans[19].start + 10];
+
+            let repair = repair(truncated).unwrap();
+
+            assert_eq!(repair.recovered(), 19, "version {}", version);
+            let lost = spans[19].start..truncated.len();
+            assert_eq!(repair.unrecoverable, [lost]);
+            assert_eq!(repair.listing.user_permissions, FlagSet::default());
+        }
+    }
+
+    #[test]
+    fn repaired_listing_can_be_parsed() {
+        for version in VERSIONS {
+            let (mut data, spans) = library(version, 20);
+            data[spans[3].start..spans[6].start + 5].fill(0xee);
+
+            let repair = repair(&data).unwrap();
+            let parsed = BeatmapListing::from_bytes(&repair.listing.to_bytes()).unwrap();
+
+            assert_eq!(parsed.beatmaps.len(), 16, "version {}", version);
+            assert_eq!(recovered_md5s(&repair), md5s((0..3).chain(7..20)));
+        }
+    }
+
+    #[test]
+    fn damaged_header_is_rejected() {
+        let (data, _) = library(20191106, 5);
+        assert!(repair(&data[..10]).is_err());
+    }
+}
@@ -0,0 +1,526 @@
+//! Shareable HTML reports summarising a beatmap library.
+//!
+//! A report is a single HTML file with an embedded stylesheet and charts drawn as inline SVG, so that it can be
+//! shared or opened without any other files.
+
+use std::io::Write;
+
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::{
+    beatmaps::{BeatmapEntry, MetadataLanguage},
+    columns::{Column, ColumnValue},
+    error::Error,
+    export::{self, html_escape, Format},
+    format, links,
+    stats::{LibraryStats, STAR_BUCKETS},
+};
+
+/// Number of mappers shown in the top mappers chart.
+pub(crate) const TOP_MAPPERS: usize = 10;
+
+/// Reports estimated to be larger than this many bytes should be confirmed before they're written, since most places
+/// won't accept files this large.
+pub const LARGE_REPORT_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Approximate size of a report without a table, in bytes.
+const BASE_SIZE: u64 = 16 * 1024;
+
+/// Approximate size of each cell of the table, in bytes.
+const CELL_SIZE: u64 = 40;
+
+/// Dimensions of the bar charts, in pixels.
+const CHART_WIDTH: f64 = 480.0;
+const LABEL_WIDTH: f64 = 110.0;
+const BAR_WIDTH: f64 = 300.0;
+const ROW_HEIGHT: f64 = 20.0;
+
+/// Stylesheet embedded in every report.
+const STYLESHEET: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2em auto; max-width: 1100px; padding: 0 1em; color: #222; }
+h1 { color: #c2185b; }
+a { color: #c2185b; }
+.charts, .lists { display: flex; flex-wrap: wrap; gap: 1.5em; }
+figure { margin: 0; }
+figcaption, h3 { font-weight: bold; margin-bottom: 0.5em; }
+svg text { font-size: 12px; fill: #222; }
+svg rect { fill: #f06292; }
+ol { padding-left: 1.5em; }
+.value { color: #777; }
+table { border-collapse: collapse; font-size: 0.9em; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.25em 0.5em; text-align: left; }
+footer { color: #777; font-size: 0.8em; margin-top: 2em; }";
+
+/// Represents what to include in a report.
+pub struct ReportOptions<'a> {
+    /// Title shown at the top of the report
+    pub title: String,
+
+    /// Name of the player whose library it is, shown under the title if given (see
+    /// [`BeatmapListing::player_name_str`](crate::beatmaps::BeatmapListing::player_name_str))
+    pub player_name: Option<String>,
+
+    /// Number of beatmaps in each of the top beatmap lists
+    pub top_count: usize,
+
+    /// Table of beatmaps to include at the end of the report, if any
+    pub table: Option<ReportTable<'a>>,
+
+    /// Which version of each beatmap's artist and title to show
+    pub language: MetadataLanguage,
+
+    /// When the report was generated, whose date is shown at the bottom of the report
+    pub generated: OffsetDateTime,
+}
+
+/// Represents a table of beatmaps in a report, e.g. the beatmaps matching a search.
+pub struct ReportTable<'a> {
+    /// Description of which beatmaps are in the table, e.g. the search query used to find them
+    pub description: String,
+    pub columns: Vec<Column>,
+    pub beatmaps: Vec<&'a BeatmapEntry>,
+}
+
+impl ReportOptions<'_> {
+    /// Estimates the size of the report in bytes, which is mostly determined by the size of the table.
+    pub fn estimated_size(&self) -> u64 {
+        let cells = self.table.as_ref().map_or(0, |table| {
+            table.beatmaps.len() as u64 * table.columns.len() as u64
+        });
+
+        BASE_SIZE + cells * CELL_SIZE
+    }
+}
+
+/// Writes a report summarising `beatmaps`, which should be every beatmap in the library.
+///
+/// `progress` is called with the number of table rows written so far and the total number of rows. Returning `false`
+/// cancels the report, in which case [`Error::Cancelled`] is returned.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "report", skip_all, fields(beatmaps = beatmaps.len()))
+)]
+pub fn write<W, F>(
+    mut writer: W,
+    beatmaps: &[BeatmapEntry],
+    options: &ReportOptions,
+    progress: F,
+) -> Result<(), Error>
+where
+    W: Write,
+    F: FnMut(usize, usize) -> bool,
+{
+    let title = html_escape(&options.title);
+    let stats = LibraryStats::from_beatmaps(beatmaps, TOP_MAPPERS);
+
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(writer, "<html lang=\"en\">")?;
+    writeln!(writer, "<head>")?;
+    writeln!(writer, "<meta charset=\"utf-8\">")?;
+    writeln!(
+        writer,
+        "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+    )?;
+    writeln!(writer, "<title>{}</title>", title)?;
+    writeln!(writer, "<style>\n{}\n</style>", STYLESHEET)?;
+    writeln!(writer, "</head>")?;
+    writeln!(writer, "<body>")?;
+    writeln!(writer, "<h1>{}</h1>", title)?;
+
+    if let Some(player_name) = options
+        .player_name
+        .as_deref()
+        .filter(|name| !name.trim().is_empty())
+    {
+        writeln!(writer, "<p>Player: {}</p>", html_escape(player_name))?;
+    }
+
+    writeln!(writer, "<section id=\"overview\">")?;
+    writeln!(writer, "<h2>Overview</h2>")?;
+    writeln!(
+        writer,
+        "<p>{} beatmaps, {} unplayed ({:.1}%)</p>",
+        stats.total,
+        stats.unplayed,
+        stats.unplayed_percentage()
+    )?;
+    writeln!(writer, "<div class=\"charts\">")?;
+
+    let buckets = (0..STAR_BUCKETS)
+        .map(|bucket| {
+            if bucket + 1 < STAR_BUCKETS {
+                format!("{}-{}★", bucket, bucket + 1)
+            } else {
+                format!("{}+★", bucket)
+            }
+        })
+        .zip(stats.star_histogram)
+        .collect::<Vec<_>>();
+
+    let years = stats
+        .per_year
+        .iter()
+        .map(|(year, count)| (year.to_string(), *count))
+        .collect::<Vec<_>>();
+
+    write_chart(&mut writer, "Star Rating (NoMod)", &buckets)?;
+    write_chart(&mut writer, "Ranked Status", &labelled(&stats.statuses))?;
+    write_chart(&mut writer, "Gameplay Mode", &labelled(&stats.modes))?;
+    write_chart(&mut writer, "Beatmaps Added per Year", &years)?;
+    write_chart(&mut writer, "Top Mappers", &stats.top_mappers)?;
+
+    writeln!(writer, "</div>")?;
+    writeln!(writer, "</section>")?;
+
+    writeln!(writer, "<section id=\"top\">")?;
+    writeln!(writer, "<h2>Top {}</h2>", options.top_count)?;
+    writeln!(writer, "<div class=\"lists\">")?;
+
+    let hardest = top(beatmaps, options.top_count, |beatmap| {
+        let stars = beatmap.nomod_star_rating()?;
+        Some((stars, format!("{:.2}★", stars)))
+    });
+
+    let longest = top(beatmaps, options.top_count, |beatmap| {
+        Some((
+            beatmap.total_time as f64,
+            ColumnValue::Duration(beatmap.total_time / 1000).to_string(),
+        ))
+    });
+
+    // osu! doesn't store when beatmaps were added, but they're rarely modified afterwards
+    let recent = top(beatmaps, options.top_count, |beatmap| {
+        let modified = beatmap.last_modification_time;
+
+        (modified.year() >= 2007).then(|| (modified.unix_timestamp() as f64, date(modified)))
+    });
+
+    let language = options.language;
+    write_list(&mut writer, "Hardest", &hardest, language)?;
+    write_list(&mut writer, "Longest", &longest, language)?;
+    write_list(&mut writer, "Most Recently Added", &recent, language)?;
+
+    writeln!(writer, "</div>")?;
+    writeln!(writer, "</section>")?;
+
+    if let Some(table) = &options.table {
+        writeln!(writer, "<section id=\"beatmaps\">")?;
+        writeln!(writer, "<h2>Beatmaps</h2>")?;
+        writeln!(
+            writer,
+            "<p>{} ({} beatmaps)</p>",
+            html_escape(&table.description),
+            table.beatmaps.len()
+        )?;
+
+        export::write(
+            &mut writer,
+            Format::Html,
+            &table.columns,
+            options.language,
+            &table.beatmaps,
+            progress,
+        )?;
+
+        writeln!(writer, "</section>")?;
+    }
+
+    writeln!(
+        writer,
+        "<footer>Generated by osu-db-viewer on {}</footer>",
+        date(options.generated)
+    )?;
+    writeln!(writer, "</body>")?;
+    writeln!(writer, "</html>")?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Formats the date part of a date and time, e.g. `2024-01-02`.
+fn date(datetime: OffsetDateTime) -> String {
+    format::short_date(datetime, UtcOffset::UTC)
+}
+
+/// Converts counts for each category into labelled rows for a chart.
+fn labelled<T: std::fmt::Display>(counts: &[(T, usize)]) -> Vec<(String, usize)> {
+    counts
+        .iter()
+        .map(|(category, count)| (category.to_string(), *count))
+        .collect()
+}
+
+/// Writes a horizontal bar chart as an SVG image, with a row for each category.
+fn write_chart<W: Write>(
+    writer: &mut W,
+    caption: &str,
+    rows: &[(String, usize)],
+) -> Result<(), Error> {
+    writeln!(writer, "<figure>")?;
+    writeln!(writer, "<figcaption>{}</figcaption>", html_escape(caption))?;
+
+    if rows.is_empty() {
+        writeln!(writer, "<p class=\"value\">No data</p>")?;
+        writeln!(writer, "</figure>")?;
+        return Ok(());
+    }
+
+    let max = rows
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or_default();
+    let height = rows.len() as f64 * ROW_HEIGHT;
+
+    writeln!(
+        writer,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" role=\"img\">",
+        CHART_WIDTH, height
+    )?;
+
+    for (i, (label, count)) in rows.iter().enumerate() {
+        let y = i as f64 * ROW_HEIGHT;
+        let width = if max == 0 {
+            0.0
+        } else {
+            *count as f64 / max as f64 * BAR_WIDTH
+        };
+
+        writeln!(
+            writer,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+            LABEL_WIDTH - 6.0,
+            y + 14.0,
+            html_escape(label)
+        )?;
+        writeln!(
+            writer,
+            "<rect x=\"{}\" y=\"{}\" width=\"{:.1}\" height=\"14\" rx=\"2\"/>",
+            LABEL_WIDTH,
+            y + 3.0,
+            width
+        )?;
+        writeln!(
+            writer,
+            "<text x=\"{:.1}\" y=\"{}\">{}</text>",
+            LABEL_WIDTH + width + 6.0,
+            y + 14.0,
+            count
+        )?;
+    }
+
+    writeln!(writer, "</svg>")?;
+    writeln!(writer, "</figure>")?;
+    Ok(())
+}
+
+/// Finds the `count` beatmaps with the highest values of `key`, skipping beatmaps where it's `None`. `key` returns
+/// the value to sort by and the text shown for it.
+fn top<F>(beatmaps: &[BeatmapEntry], count: usize, key: F) -> Vec<(&BeatmapEntry, String)>
+where
+    F: Fn(&BeatmapEntry) -> Option<(f64, String)>,
+{
+    let mut ranked = beatmaps
+        .iter()
+        .filter_map(|beatmap| key(beatmap).map(|(value, text)| (beatmap, value, text)))
+        .collect::<Vec<_>>();
+
+    // Stable, so ties keep the order of the listing
+    ranked.sort_by(|(_, a, _), (_, b, _)| b.total_cmp(a));
+    ranked.truncate(count);
+
+    ranked
+        .into_iter()
+        .map(|(beatmap, _, text)| (beatmap, text))
+        .collect()
+}
+
+/// Writes a numbered list of beatmaps, linking to the ones which have been submitted.
+fn write_list<W: Write>(
+    writer: &mut W,
+    heading: &str,
+    beatmaps: &[(&BeatmapEntry, String)],
+    language: MetadataLanguage,
+) -> Result<(), Error> {
+    writeln!(writer, "<div>")?;
+    writeln!(writer, "<h3>{}</h3>", html_escape(heading))?;
+
+    if beatmaps.is_empty() {
+        writeln!(writer, "<p class=\"value\">No data</p>")?;
+        writeln!(writer, "</div>")?;
+        return Ok(());
+    }
+
+    writeln!(writer, "<ol>")?;
+
+    for (beatmap, value) in beatmaps {
+        let name = html_escape(&format!(
+            "{} - {} [{}]",
+            beatmap
+                .display_artist(language.prefers_unicode())
+                .unwrap_or_default(),
+            beatmap
+                .display_title(language.prefers_unicode())
+                .unwrap_or_default(),
+            beatmap.difficulty.as_deref().unwrap_or_default()
+        ));
+
+        let name = match links::beatmap_url(beatmap) {
+            Some(url) => format!("<a href=\"{}\">{}</a>", html_escape(&url), name),
+            None => name,
+        };
+
+        writeln!(
+            writer,
+            "<li>{} <span class=\"value\">{}</span></li>",
+            name,
+            html_escape(value)
+        )?;
+    }
+
+    writeln!(writer, "</ol>")?;
+    writeln!(writer, "</div>")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::{
+        beatmaps::{RankedStatus, StarRating},
+        common::Mods,
+    };
+
+    fn beatmap(title: &str, beatmap_id: u32, stars: f64, total_time: u32) -> BeatmapEntry {
+        BeatmapEntry {
+            artist_name: Some("Artist".to_string()),
+            song_title: Some(title.to_string()),
+            difficulty: Some("Insane".to_string()),
+            creator_name: Some("Mapper".to_string()),
+            beatmap_id,
+            ranked_status: if beatmap_id == 0 {
+                RankedStatus::Unsubmitted
+            } else {
+                RankedStatus::Ranked
+            },
+            star_ratings_std: Some(vec![StarRating {
+                mods: Mods::none(),
+                rating: stars,
+            }]),
+            total_time,
+            last_modification_time: datetime!(2021-03-04 5:06 UTC),
+            ..Default::default()
+        }
+    }
+
+    fn report(beatmaps: &[BeatmapEntry], table: bool) -> String {
+        let options = ReportOptions {
+            title: "Library <Report>".to_string(),
+            player_name: None,
+            top_count: 2,
+            table: table.then(|| ReportTable {
+                description: "stars>4".to_string(),
+                columns: vec![Column::Artist, Column::Title],
+                beatmaps: beatmaps.iter().collect(),
+            }),
+            language: MetadataLanguage::default(),
+            generated: datetime!(2024-01-02 3:04:05 UTC),
+        };
+
+        let mut output = Vec::new();
+        write(&mut output, beatmaps, &options, |_, _| true).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn report_matches_golden_file() {
+        let beatmaps = [
+            beatmap("Easy & Short", 1, 2.5, 60_000),
+            beatmap("Hard", 2, 6.25, 185_000),
+            beatmap("Unsubmitted", 0, 4.0, 120_000),
+        ];
+
+        assert_eq!(
+            report(&beatmaps, true),
+            include_str!("../testdata/report.html")
+        );
+    }
+
+    #[test]
+    fn empty_report_matches_golden_file() {
+        assert_eq!(
+            report(&[], false),
+            include_str!("../testdata/report_empty.html")
+        );
+    }
+
+    #[test]
+    fn player_name_is_shown_under_the_title() {
+        let options = |player_name: Option<&str>| ReportOptions {
+            title: "Library".to_string(),
+            player_name: player_name.map(str::to_string),
+            top_count: 10,
+            table: None,
+            language: MetadataLanguage::default(),
+            generated: datetime!(2024-01-02 3:04:05 UTC),
+        };
+
+        let write_report = |options: &ReportOptions| {
+            let mut output = Vec::new();
+            write(&mut output, &[], options, |_, _| true).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        assert!(write_report(&options(Some("<Player>")))
+            .contains("<h1>Library</h1>\n<p>Player: &lt;Player&gt;</p>\n"));
+        assert!(!write_report(&options(Some(""))).contains("Player:"));
+        assert!(!write_report(&options(None)).contains("Player:"));
+    }
+
+    #[test]
+    fn report_size_is_estimated_from_the_table() {
+        let beatmaps = [beatmap("Title", 1, 5.0, 60_000)];
+
+        let mut options = ReportOptions {
+            title: String::new(),
+            player_name: None,
+            top_count: 10,
+            table: None,
+            language: MetadataLanguage::default(),
+            generated: datetime!(2024-01-02 3:04:05 UTC),
+        };
+
+        assert_eq!(options.estimated_size(), BASE_SIZE);
+
+        options.table = Some(ReportTable {
+            description: String::new(),
+            columns: vec![Column::Artist, Column::Title],
+            beatmaps: beatmaps.iter().collect(),
+        });
+
+        assert_eq!(options.estimated_size(), BASE_SIZE + 2 * CELL_SIZE);
+    }
+
+    #[test]
+    fn report_can_be_cancelled() {
+        let beatmaps = [beatmap("Title", 1, 5.0, 60_000)];
+
+        let options = ReportOptions {
+            title: String::new(),
+            player_name: None,
+            top_count: 10,
+            table: Some(ReportTable {
+                description: String::new(),
+                columns: vec![Column::Title],
+                beatmaps: beatmaps.iter().collect(),
+            }),
+            language: MetadataLanguage::default(),
+            generated: datetime!(2024-01-02 3:04:05 UTC),
+        };
+
+        let result = write(Vec::new(), &beatmaps, &options, |_, _| false);
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+}
@@ -6,6 +6,7 @@
 //! [osu! wiki]: https://github.com/ppy/osu/wiki/Legacy-database-file-structure#scoresdb
 //! [replay format]: https://osu.ppy.sh/wiki/en/Client/File_formats/osr_%28file_format%29
 
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
 
 use flagset::FlagSet;
@@ -21,15 +22,17 @@ use nom::{
 use time::OffsetDateTime;
 
 use crate::{
-    common::{
-        boolean, gameplay_mode, modifiers, osu_string, windows_datetime, GameplayMode, Grade, Mods,
-        OsuString,
+    binary::{
+        parse_boolean, parse_gameplay_mode, parse_mods, parse_osu_string, parse_windows_datetime,
     },
+    common::{GameplayMode, Grade, Mods, OsuString, TimestampKind, WINDOWS_EPOCH},
+    detect::FileKind,
     error::Error,
 };
 
 /// Represents the `scores.db` file.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScoreListing {
     /// Version (e.g. 20150204)
     pub version: u32,
@@ -40,6 +43,7 @@ pub struct ScoreListing {
 
 /// Represents a list of scores for a beatmap in the `scores.db` file.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BeatmapScores {
     /// Beatmap MD5 hash
     pub md5: OsuString,
@@ -52,6 +56,7 @@ pub struct BeatmapScores {
 ///
 /// Note that the compressed replay data may not be present, e.g. if this came from the `scores.db` file.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScoreReplay {
     /// osu! gameplay mode
     pub gameplay_mode: GameplayMode,
@@ -102,7 +107,7 @@ pub struct ScoreReplay {
     /// Only present when parsing a `.osr` replay file.
     pub lifebar_graph: Option<LifebarGraph>,
 
-    /// Timestamp of replay
+    /// Timestamp of replay, stored as UTC
     pub timestamp: OffsetDateTime,
 
     /// LZMA Compressed replay data. Only present when parsing a `.osr` replay file.
@@ -118,8 +123,26 @@ pub struct ScoreReplay {
     pub additional_mod_info: Option<f64>,
 }
 
+/// Summarises the local scores set on a beatmap in a single gameplay mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreSummary {
+    /// Number of scores set
+    pub count: usize,
+
+    /// Highest accuracy of any score, as a percentage
+    pub best_accuracy: f64,
+
+    /// Best grade of any score
+    pub best_grade: Grade,
+
+    /// When the most recent score was set
+    pub last_played: OffsetDateTime,
+}
+
 /// Represents the lifebar graph in a .osr replay file.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LifebarGraph {
     pub points: Vec<(u32, f32)>,
 }
@@ -139,30 +162,73 @@ impl std::fmt::Display for LifebarGraph {
     }
 }
 
+impl Default for ScoreReplay {
+    fn default() -> Self {
+        Self {
+            gameplay_mode: GameplayMode::Standard,
+            version: 0,
+            beatmap_md5: None,
+            player_name: None,
+            replay_md5: None,
+            hits_300: 0,
+            hits_100: 0,
+            hits_50: 0,
+            hits_geki: 0,
+            hits_katu: 0,
+            misses: 0,
+            score: 0,
+            max_combo: 0,
+            is_perfect_combo: false,
+            mods: Mods::none(),
+            lifebar_graph: None,
+            timestamp: WINDOWS_EPOCH,
+            replay_data: None,
+            online_score_id: 0,
+            additional_mod_info: None,
+        }
+    }
+}
+
 impl ScoreListing {
     /// Parses the contents of a `collection.db` file.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "parse_scores", skip_all, fields(bytes = data.len()))
+    )]
     pub fn from_bytes(data: &[u8]) -> Result<ScoreListing, Error> {
-        let (_, listing) = score_listing(data).map_err(|e| e.to_owned())?;
+        let (_, listing) =
+            score_listing(data).map_err(|e| Error::parse(FileKind::Scores, data, e))?;
         Ok(listing)
     }
 
     /// Convenience method for reading the contents of an `collection.db` file and parsing it as a `ScoreListing`.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ScoreListing, Error> {
-        let data = std::fs::read(path)?;
+        let data = crate::shared::read_database(path, FileKind::Scores)?;
         Self::from_bytes(&data)
     }
 }
 
 impl ScoreReplay {
+    /// Clock that [`ScoreReplay::timestamp`] is stored from.
+    pub const TIMESTAMP_KIND: TimestampKind = TimestampKind::Utc;
+
     /// Parses the contents of a `.osr` replay.
+    #[cfg(feature = "osr")]
     pub fn from_bytes(data: &[u8]) -> Result<ScoreReplay, Error> {
-        let (_, listing) = score_replay(data).map_err(|e| e.to_owned())?;
+        let (_, listing) =
+            score_replay(data).map_err(|e| Error::parse(FileKind::Replay, data, e))?;
         Ok(listing)
     }
 
-    /// Convenience method for reading the contents of an `collection.db` file and parsing it as a `ScoreListing`.
+    /// Convenience method for reading the contents of a `.osr` replay and parsing it as a `ScoreReplay`.
+    #[cfg(all(feature = "osr", not(target_arch = "wasm32")))]
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ScoreReplay, Error> {
-        let data = std::fs::read(path)?;
+        let data = crate::shared::read(path)?;
         Self::from_bytes(&data)
     }
 
@@ -349,6 +415,34 @@ impl ScoreReplay {
     }
 }
 
+impl ScoreSummary {
+    /// Summarises the scores set in a gameplay mode, returning `None` if there aren't any.
+    pub fn of(scores: &[ScoreReplay], mode: GameplayMode) -> Option<ScoreSummary> {
+        scores
+            .iter()
+            .filter(|score| score.gameplay_mode == mode)
+            .fold(None, |summary: Option<ScoreSummary>, score| {
+                // Scores without any hits have no accuracy, so they're ignored by `f64::max`
+                let accuracy = score.accuracy();
+
+                Some(match summary {
+                    Some(summary) => ScoreSummary {
+                        count: summary.count + 1,
+                        best_accuracy: summary.best_accuracy.max(accuracy),
+                        best_grade: summary.best_grade.min(score.grade()),
+                        last_played: summary.last_played.max(score.timestamp),
+                    },
+                    None => ScoreSummary {
+                        count: 1,
+                        best_accuracy: accuracy,
+                        best_grade: score.grade(),
+                        last_played: score.timestamp,
+                    },
+                })
+            })
+    }
+}
+
 /// Parses a `scores.db` file.
 fn score_listing(input: &[u8]) -> IResult<&[u8], ScoreListing> {
     let (i, version) = le_u32(input)?;
@@ -365,7 +459,7 @@ fn score_listing(input: &[u8]) -> IResult<&[u8], ScoreListing> {
 
 /// Parses the scores for a particular beatmap in the `scores.db` file.
 fn beatmap_scores(input: &[u8]) -> IResult<&[u8], BeatmapScores> {
-    let (i, md5) = osu_string(input)?;
+    let (i, md5) = parse_osu_string(input)?;
     let (i, scores) = length_count(le_u32, score_replay)(i)?;
 
     Ok((i, BeatmapScores { md5, scores }))
@@ -373,7 +467,7 @@ fn beatmap_scores(input: &[u8]) -> IResult<&[u8], BeatmapScores> {
 
 fn lifebar_graph(input: &[u8]) -> IResult<&[u8], Option<LifebarGraph>> {
     // The lifebar graph is stored as a string, so parse this first
-    let (i, lifebar) = osu_string(input)?;
+    let (i, lifebar) = parse_osu_string(input)?;
 
     if let Some(lifebar) = lifebar {
         // Then, parse the string values
@@ -397,11 +491,11 @@ fn lifebar_graph_points(input: &str) -> IResult<&str, Vec<(u32, f32)>> {
 
 /// Parses a score in the `scores.db` file or a `.osr` replay file.
 fn score_replay(input: &[u8]) -> IResult<&[u8], ScoreReplay> {
-    let (i, gameplay_mode) = gameplay_mode(input)?;
+    let (i, gameplay_mode) = parse_gameplay_mode(input)?;
     let (i, version) = le_u32(i)?;
-    let (i, beatmap_md5) = osu_string(i)?;
-    let (i, player_name) = osu_string(i)?;
-    let (i, replay_md5) = osu_string(i)?;
+    let (i, beatmap_md5) = parse_osu_string(i)?;
+    let (i, player_name) = parse_osu_string(i)?;
+    let (i, replay_md5) = parse_osu_string(i)?;
     let (i, hits_300) = le_u16(i)?;
     let (i, hits_100) = le_u16(i)?;
     let (i, hits_50) = le_u16(i)?;
@@ -411,10 +505,10 @@ fn score_replay(input: &[u8]) -> IResult<&[u8], ScoreReplay> {
 
     let (i, score) = le_u32(i)?;
     let (i, max_combo) = le_u16(i)?;
-    let (i, is_perfect_combo) = boolean(i)?;
-    let (i, mods) = modifiers(i)?;
+    let (i, is_perfect_combo) = parse_boolean(i)?;
+    let (i, mods) = parse_mods(i)?;
     let (i, lifebar_graph) = lifebar_graph(i)?;
-    let (i, timestamp) = windows_datetime(i)?;
+    let (i, timestamp) = parse_windows_datetime(i)?;
 
     // If replay data length is 0xFFFFFFFF (-1), then no replay data is present (e.g. comes from scores.db)
     let (i, replay_data_length) = le_u32(i)?;
@@ -474,7 +568,7 @@ mod tests {
                 &[][..],
                 Some("1676|1,3732|1,5805|1,7847|1,9909|1,".to_string())
             )),
-            osu_string(&non_empty_bytes)
+            parse_osu_string::<()>(&non_empty_bytes)
         );
 
         // Parsing the empty and zero-length strings
@@ -516,4 +610,36 @@ mod tests {
 
         assert_eq!("1676|1,3732|1,5805|1,7847|1,9909|1,", graph.to_string());
     }
+
+    #[test]
+    fn score_summary_uses_best_and_latest_scores_in_mode() {
+        let score = |gameplay_mode, hits_300, hits_100, timestamp| ScoreReplay {
+            gameplay_mode,
+            hits_300,
+            hits_100,
+            timestamp: WINDOWS_EPOCH + time::Duration::days(timestamp),
+            ..Default::default()
+        };
+
+        let scores = [
+            score(GameplayMode::Standard, 90, 10, 3),
+            score(GameplayMode::Standard, 100, 0, 1),
+            score(GameplayMode::Taiko, 50, 50, 7),
+        ];
+
+        let summary = ScoreSummary::of(&scores, GameplayMode::Standard).unwrap();
+
+        assert_eq!(2, summary.count);
+        assert_eq!(100.0, summary.best_accuracy);
+        assert_eq!(Grade::SS, summary.best_grade);
+        assert_eq!(WINDOWS_EPOCH + time::Duration::days(3), summary.last_played);
+
+        assert_eq!(
+            1,
+            ScoreSummary::of(&scores, GameplayMode::Taiko)
+                .unwrap()
+                .count
+        );
+        assert_eq!(None, ScoreSummary::of(&scores, GameplayMode::Mania));
+    }
 }
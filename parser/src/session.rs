@@ -0,0 +1,1101 @@
+//! Editing a beatmap listing (and optionally its collections) with undo and redo.
+//!
+//! An [`EditSession`] performs the same edits as the methods on [`BeatmapListing`] (e.g. [`BeatmapListing::retain`]
+//! and [`BeatmapListing::bulk_edit`]), but records each one in a [`Journal`] as an operation that can be reversed. Only
+//! what changed is recorded: removed beatmaps are kept in full, but edited beatmaps only keep the fields that changed.
+//!
+//! With the `serde` feature, the journal can be saved alongside an edited `osu!.db`, so that the edits can still be
+//! reverted after the session has ended using [`EditSession::resume`].
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{
+    beatmaps::{BeatmapEntry, BeatmapListing},
+    collections::{Collection, CollectionListing},
+    common::{GameplayMode, Grade, OsuString},
+    edit::{BestGrades, BulkEdit},
+    query::BeatmapFilter,
+    scores::ScoreListing,
+};
+
+/// A beatmap listing being edited, along with the edits that can be undone and redone.
+#[derive(Clone, Debug)]
+pub struct EditSession {
+    listing: BeatmapListing,
+    collections: Option<CollectionListing>,
+    journal: Journal,
+    saved: SavePoint,
+}
+
+/// Where the listing was last saved, relative to the journal.
+#[derive(Clone, Debug, Default)]
+struct SavePoint {
+    /// Number of edits that had been made when the listing was saved
+    depth: usize,
+
+    /// Set when saved edits were undone and then replaced by new edits, so the saved state can't be reached by undoing
+    /// and redoing. Holds the beatmaps those edits changed.
+    lost: Option<BTreeSet<usize>>,
+}
+
+/// The edits made in an [`EditSession`], in the order they were made.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Journal {
+    /// Edits that have been made, oldest first
+    pub done: Vec<Operation>,
+
+    /// Edits that have been undone, with the most recently undone last
+    pub undone: Vec<Operation>,
+}
+
+/// Represents a reversible edit to a beatmap listing or its collections.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operation {
+    /// Beatmaps were removed, which are stored with their indices before the removal (in ascending order)
+    RemoveBeatmaps {
+        removed: Vec<(usize, BeatmapEntry)>,
+        previous_folder_count: u32,
+    },
+
+    /// Beatmaps were added to the end of the listing
+    AddBeatmaps {
+        beatmaps: Vec<BeatmapEntry>,
+        previous_folder_count: u32,
+    },
+
+    /// Fields of existing beatmaps were changed
+    ChangeBeatmaps { changes: Vec<FieldChange> },
+
+    /// Beatmaps were added to the end of the collection at this index
+    AddToCollection {
+        collection: usize,
+        md5s: Vec<OsuString>,
+    },
+
+    /// Beatmaps were removed from the collection at this index, which are stored with their indices before the
+    /// removal (in ascending order)
+    RemoveFromCollection {
+        collection: usize,
+        removed: Vec<(usize, OsuString)>,
+    },
+
+    /// A collection was created at this index
+    CreateCollection {
+        index: usize,
+        collection: Collection,
+    },
+
+    /// The collection at this index was deleted
+    DeleteCollection {
+        index: usize,
+        collection: Collection,
+    },
+}
+
+/// Represents a change to one field of the beatmap at `index`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldChange {
+    pub index: usize,
+    pub before: FieldValue,
+    pub after: FieldValue,
+}
+
+/// Represents the value of a beatmap field that can be edited in a session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FieldValue {
+    LocalOffset(u16),
+    IgnoreBeatmapHitsounds(bool),
+    IgnoreBeatmapSkin(bool),
+    DisableStoryboard(bool),
+    DisableVideo(bool),
+    VisualOverride(bool),
+    ManiaScrollSpeed(u8),
+    Grade(GameplayMode, Grade),
+}
+
+impl FieldValue {
+    /// Gets the current value of every field that can be edited, in a fixed order.
+    fn all(beatmap: &BeatmapEntry) -> [FieldValue; 11] {
+        [
+            FieldValue::LocalOffset(beatmap.local_offset),
+            FieldValue::IgnoreBeatmapHitsounds(beatmap.ignore_beatmap_hitsounds),
+            FieldValue::IgnoreBeatmapSkin(beatmap.ignore_beatmap_skin),
+            FieldValue::DisableStoryboard(beatmap.disable_storyboard),
+            FieldValue::DisableVideo(beatmap.disable_video),
+            FieldValue::VisualOverride(beatmap.visual_override),
+            FieldValue::ManiaScrollSpeed(beatmap.mania_scroll_speed),
+            FieldValue::Grade(GameplayMode::Standard, beatmap.grade_std),
+            FieldValue::Grade(GameplayMode::Taiko, beatmap.grade_taiko),
+            FieldValue::Grade(GameplayMode::Catch, beatmap.grade_catch),
+            FieldValue::Grade(GameplayMode::Mania, beatmap.grade_mania),
+        ]
+    }
+
+    /// Writes the value to its field in a beatmap.
+    fn write(&self, beatmap: &mut BeatmapEntry) {
+        match *self {
+            FieldValue::LocalOffset(value) => beatmap.local_offset = value,
+            FieldValue::IgnoreBeatmapHitsounds(value) => beatmap.ignore_beatmap_hitsounds = value,
+            FieldValue::IgnoreBeatmapSkin(value) => beatmap.ignore_beatmap_skin = value,
+            FieldValue::DisableStoryboard(value) => beatmap.disable_storyboard = value,
+            FieldValue::DisableVideo(value) => beatmap.disable_video = value,
+            FieldValue::VisualOverride(value) => beatmap.visual_override = value,
+            FieldValue::ManiaScrollSpeed(value) => beatmap.mania_scroll_speed = value,
+            FieldValue::Grade(GameplayMode::Standard, grade) => beatmap.grade_std = grade,
+            FieldValue::Grade(GameplayMode::Taiko, grade) => beatmap.grade_taiko = grade,
+            FieldValue::Grade(GameplayMode::Catch, grade) => beatmap.grade_catch = grade,
+            FieldValue::Grade(GameplayMode::Mania, grade) => beatmap.grade_mania = grade,
+        }
+    }
+
+    /// Checks whether two values are for the same field.
+    fn same_field(&self, other: &FieldValue) -> bool {
+        match (self, other) {
+            (FieldValue::Grade(a, _), FieldValue::Grade(b, _)) => a == b,
+            (a, b) => std::mem::discriminant(a) == std::mem::discriminant(b),
+        }
+    }
+}
+
+impl Operation {
+    /// Gets the indices of the beatmaps whose fields were changed by this edit.
+    fn changed_beatmaps(&self) -> impl Iterator<Item = usize> + '_ {
+        let changes = match self {
+            Operation::ChangeBeatmaps { changes } => changes.as_slice(),
+            _ => &[],
+        };
+
+        changes.iter().map(|change| change.index)
+    }
+
+    /// Makes the edit, e.g. when it's redone.
+    fn apply(&self, listing: &mut BeatmapListing, collections: Option<&mut CollectionListing>) {
+        match self {
+            Operation::RemoveBeatmaps { removed, .. } => {
+                let indices = removed.iter().map(|(i, _)| *i).collect::<HashSet<_>>();
+                listing.retain(|i, _| !indices.contains(&i));
+            }
+            Operation::AddBeatmaps { beatmaps, .. } => {
+                listing.beatmaps.extend(beatmaps.iter().cloned());
+                listing.fix_folder_count();
+            }
+            Operation::ChangeBeatmaps { changes } => {
+                for change in changes {
+                    change.after.write(&mut listing.beatmaps[change.index]);
+                }
+            }
+            Operation::AddToCollection { collection, md5s } => {
+                if let Some(collections) = collections {
+                    collections.collections[*collection]
+                        .beatmap_md5s
+                        .extend(md5s.iter().cloned());
+                }
+            }
+            Operation::RemoveFromCollection {
+                collection,
+                removed,
+            } => {
+                if let Some(collections) = collections {
+                    let indices = removed.iter().map(|(i, _)| *i).collect::<HashSet<_>>();
+                    let mut i = 0;
+
+                    collections.collections[*collection]
+                        .beatmap_md5s
+                        .retain(|_| {
+                            let kept = !indices.contains(&i);
+                            i += 1;
+                            kept
+                        });
+                }
+            }
+            Operation::CreateCollection { index, collection } => {
+                if let Some(collections) = collections {
+                    collections.collections.insert(*index, collection.clone());
+                }
+            }
+            Operation::DeleteCollection { index, .. } => {
+                if let Some(collections) = collections {
+                    collections.collections.remove(*index);
+                }
+            }
+        }
+    }
+
+    /// Reverses the edit, e.g. when it's undone.
+    fn revert(&self, listing: &mut BeatmapListing, collections: Option<&mut CollectionListing>) {
+        match self {
+            Operation::RemoveBeatmaps {
+                removed,
+                previous_folder_count,
+            } => {
+                // Inserting in ascending order puts each beatmap back where it was
+                for (i, beatmap) in removed {
+                    listing.beatmaps.insert(*i, beatmap.clone());
+                }
+
+                listing.folder_count = *previous_folder_count;
+            }
+            Operation::AddBeatmaps {
+                beatmaps,
+                previous_folder_count,
+            } => {
+                let len = listing.beatmaps.len() - beatmaps.len();
+                listing.beatmaps.truncate(len);
+                listing.folder_count = *previous_folder_count;
+            }
+            Operation::ChangeBeatmaps { changes } => {
+                for change in changes.iter().rev() {
+                    change.before.write(&mut listing.beatmaps[change.index]);
+                }
+            }
+            Operation::AddToCollection { collection, md5s } => {
+                if let Some(collections) = collections {
+                    let md5s_in_collection = &mut collections.collections[*collection].beatmap_md5s;
+                    md5s_in_collection.truncate(md5s_in_collection.len() - md5s.len());
+                }
+            }
+            Operation::RemoveFromCollection {
+                collection,
+                removed,
+            } => {
+                if let Some(collections) = collections {
+                    let md5s_in_collection = &mut collections.collections[*collection].beatmap_md5s;
+
+                    for (i, md5) in removed {
+                        md5s_in_collection.insert(*i, md5.clone());
+                    }
+                }
+            }
+            Operation::CreateCollection { index, .. } => {
+                if let Some(collections) = collections {
+                    collections.collections.remove(*index);
+                }
+            }
+            Operation::DeleteCollection { index, collection } => {
+                if let Some(collections) = collections {
+                    collections.collections.insert(*index, collection.clone());
+                }
+            }
+        }
+    }
+}
+
+impl EditSession {
+    /// Starts editing a beatmap listing.
+    pub fn new(listing: BeatmapListing) -> Self {
+        Self {
+            listing,
+            collections: None,
+            journal: Journal::default(),
+            saved: SavePoint::default(),
+        }
+    }
+
+    /// Starts editing a beatmap listing along with its collections.
+    pub fn with_collections(listing: BeatmapListing, collections: CollectionListing) -> Self {
+        Self {
+            collections: Some(collections),
+            ..Self::new(listing)
+        }
+    }
+
+    /// Continues a session from its journal, e.g. to revert the edits in an `osu!.db` that was saved at the end of the
+    /// session. The listing and collections must be the ones the journal's edits were made to, in their edited state.
+    pub fn resume(
+        listing: BeatmapListing,
+        collections: Option<CollectionListing>,
+        journal: Journal,
+    ) -> Self {
+        Self {
+            saved: SavePoint {
+                depth: journal.done.len(),
+                lost: None,
+            },
+            listing,
+            collections,
+            journal,
+        }
+    }
+
+    /// Gets the beatmap listing, with the edits made so far.
+    pub fn listing(&self) -> &BeatmapListing {
+        &self.listing
+    }
+
+    /// Gets the collections, with the edits made so far, if they're being edited.
+    pub fn collections(&self) -> Option<&CollectionListing> {
+        self.collections.as_ref()
+    }
+
+    /// Gets the edits made so far, e.g. to save them so that they can be reverted later.
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Ends the session, returning the edited listing and collections.
+    pub fn into_parts(self) -> (BeatmapListing, Option<CollectionListing>) {
+        (self.listing, self.collections)
+    }
+
+    /// Records that the listing (and collections) have been saved as they are now, e.g. to `osu!.db`.
+    pub fn mark_saved(&mut self) {
+        self.saved = SavePoint {
+            depth: self.journal.done.len(),
+            lost: None,
+        };
+    }
+
+    /// Checks whether the listing has been edited since it was last saved (or since the session started). Undoing the
+    /// edits made since then counts as not being edited.
+    pub fn is_modified(&self) -> bool {
+        self.saved.lost.is_some() || self.saved.depth != self.journal.done.len()
+    }
+
+    /// Gets the indices of the beatmaps whose fields have been changed since the listing was last saved, e.g. to mark
+    /// them as edited. Beatmaps that were changed back to their saved values by another edit are still included.
+    pub fn edited_beatmaps(&self) -> BTreeSet<usize> {
+        let done = self.journal.done.len();
+        let since_saved = if done >= self.saved.depth {
+            &self.journal.done[self.saved.depth..]
+        } else {
+            // Edits that were undone are the most recent ones in the redo history
+            let undone = &self.journal.undone;
+            &undone[undone.len() - (self.saved.depth - done)..]
+        };
+
+        let mut edited = self.saved.lost.clone().unwrap_or_default();
+        edited.extend(since_saved.iter().flat_map(Operation::changed_beatmaps));
+        edited
+    }
+
+    /// Checks whether there are any edits to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.journal.done.is_empty()
+    }
+
+    /// Checks whether there are any undone edits to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.journal.undone.is_empty()
+    }
+
+    /// Undoes the most recent edit, returning `false` if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(operation) = self.journal.done.pop() else {
+            return false;
+        };
+
+        operation.revert(&mut self.listing, self.collections.as_mut());
+        self.journal.undone.push(operation);
+        true
+    }
+
+    /// Redoes the most recently undone edit, returning `false` if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(operation) = self.journal.undone.pop() else {
+            return false;
+        };
+
+        operation.apply(&mut self.listing, self.collections.as_mut());
+        self.journal.done.push(operation);
+        true
+    }
+
+    /// Undoes every edit made in the session, returning how many were undone.
+    pub fn revert(&mut self) -> usize {
+        let mut undone = 0;
+
+        while self.undo() {
+            undone += 1;
+        }
+
+        undone
+    }
+
+    /// Removes the beatmaps that `keep` returns `false` for (see [`BeatmapListing::retain`]), returning how many were
+    /// removed.
+    pub fn retain<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(usize, &BeatmapEntry) -> bool,
+    {
+        let removed = self
+            .listing
+            .beatmaps
+            .iter()
+            .enumerate()
+            .filter(|(i, beatmap)| !keep(*i, beatmap))
+            .map(|(i, beatmap)| (i, beatmap.clone()))
+            .collect::<Vec<_>>();
+
+        let count = removed.len();
+        self.perform(Operation::RemoveBeatmaps {
+            removed,
+            previous_folder_count: self.listing.folder_count,
+        });
+
+        count
+    }
+
+    /// Adds the beatmaps from `other` which aren't already in the listing (see [`BeatmapListing::merge`]), returning how
+    /// many were added.
+    pub fn merge(&mut self, other: &BeatmapListing) -> usize {
+        let previous_folder_count = self.listing.folder_count;
+        let before = self.listing.beatmaps.len();
+        let added = self.listing.merge(other);
+
+        self.record(Operation::AddBeatmaps {
+            beatmaps: self.listing.beatmaps[before..].to_vec(),
+            previous_folder_count,
+        });
+
+        added
+    }
+
+    /// Applies an edit to every beatmap matching `filter` (see [`BeatmapListing::bulk_edit`]), returning how many
+    /// beatmaps were changed.
+    pub fn bulk_edit(&mut self, filter: &BeatmapFilter, edit: BulkEdit) -> usize {
+        let indices = self
+            .listing
+            .beatmaps
+            .iter()
+            .enumerate()
+            .filter(|(_, beatmap)| filter.matches(beatmap))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        self.change_beatmaps(indices, |beatmap| {
+            edit.apply(beatmap);
+        })
+    }
+
+    /// Applies an edit to the beatmaps at the given indices (see [`BeatmapListing::bulk_edit_indices`]), returning how
+    /// many beatmaps were changed.
+    pub fn bulk_edit_indices(
+        &mut self,
+        indices: impl IntoIterator<Item = usize>,
+        edit: BulkEdit,
+    ) -> usize {
+        let indices = indices
+            .into_iter()
+            .filter(|&i| i < self.listing.beatmaps.len())
+            .collect();
+
+        self.change_beatmaps(indices, |beatmap| {
+            edit.apply(beatmap);
+        })
+    }
+
+    /// Applies an edit to a single beatmap, returning `false` if it didn't change anything.
+    ///
+    /// If the previous edit only changed the same fields of the same beatmap (and the listing hasn't been saved since),
+    /// this edit is merged into it, so that e.g. dragging the local offset can be undone in one step. Edits which undo
+    /// each other are removed entirely.
+    pub fn edit_beatmap(&mut self, index: usize, edit: BulkEdit) -> bool {
+        let Some(beatmap) = self.listing.beatmaps.get_mut(index) else {
+            return false;
+        };
+
+        let before = FieldValue::all(beatmap);
+        edit.apply(beatmap);
+        let changes = before
+            .into_iter()
+            .zip(FieldValue::all(beatmap))
+            .filter(|(before, after)| before != after)
+            .map(|(before, after)| FieldChange {
+                index,
+                before,
+                after,
+            })
+            .collect::<Vec<_>>();
+
+        if changes.is_empty() {
+            return false;
+        }
+
+        let mergeable =
+            self.journal.undone.is_empty() && self.journal.done.len() > self.saved.depth;
+
+        if let Some(Operation::ChangeBeatmaps { changes: previous }) =
+            self.journal.done.last_mut().filter(|_| mergeable)
+        {
+            let same_fields = previous.iter().all(|change| change.index == index)
+                && changes.iter().all(|change| {
+                    previous
+                        .iter()
+                        .any(|previous| previous.after.same_field(&change.after))
+                });
+
+            if same_fields {
+                for change in changes {
+                    if let Some(previous) = previous
+                        .iter_mut()
+                        .find(|previous| previous.after.same_field(&change.after))
+                    {
+                        previous.after = change.after;
+                    }
+                }
+
+                previous.retain(|change| change.before != change.after);
+
+                if previous.is_empty() {
+                    self.journal.done.pop();
+                }
+
+                return true;
+            }
+        }
+
+        self.record(Operation::ChangeBeatmaps { changes });
+        true
+    }
+
+    /// Recalculates each beatmap's grades from its local scores (see [`BeatmapListing::recompute_grades`]), returning
+    /// how many beatmaps were changed.
+    pub fn recompute_grades(&mut self, scores: &ScoreListing) -> usize {
+        let grades = BestGrades::new(scores);
+        let indices = (0..self.listing.beatmaps.len()).collect();
+
+        self.change_beatmaps(indices, |beatmap| {
+            grades.apply(beatmap);
+        })
+    }
+
+    /// Adds beatmaps to the collection at index `collection`, skipping any that it already contains. Returns how many
+    /// were added, which is 0 if collections aren't being edited.
+    pub fn add_to_collection(&mut self, collection: usize, md5s: &[String]) -> usize {
+        let Some(existing) = self
+            .collections
+            .as_ref()
+            .and_then(|collections| collections.collections.get(collection))
+        else {
+            return 0;
+        };
+
+        let mut seen = existing
+            .beatmap_md5s
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<HashSet<_>>();
+
+        let md5s = md5s
+            .iter()
+            .filter(|md5| seen.insert(md5.as_str()))
+            .map(|md5| Some(md5.clone()))
+            .collect::<Vec<_>>();
+
+        let count = md5s.len();
+        self.perform(Operation::AddToCollection { collection, md5s });
+        count
+    }
+
+    /// Removes beatmaps from the collection at index `collection`, returning how many were removed.
+    pub fn remove_from_collection(&mut self, collection: usize, md5s: &[String]) -> usize {
+        let Some(existing) = self
+            .collections
+            .as_ref()
+            .and_then(|collections| collections.collections.get(collection))
+        else {
+            return 0;
+        };
+
+        let removed = existing
+            .beatmap_md5s
+            .iter()
+            .enumerate()
+            .filter(|(_, md5)| md5.as_ref().is_some_and(|md5| md5s.contains(md5)))
+            .map(|(i, md5)| (i, md5.clone()))
+            .collect::<Vec<_>>();
+
+        let count = removed.len();
+        self.perform(Operation::RemoveFromCollection {
+            collection,
+            removed,
+        });
+        count
+    }
+
+    /// Creates an empty collection at the end of the list, returning its index, or `None` if collections aren't being
+    /// edited.
+    pub fn create_collection(&mut self, name: String) -> Option<usize> {
+        let index = self.collections.as_ref()?.collections.len();
+
+        self.perform(Operation::CreateCollection {
+            index,
+            collection: Collection {
+                name: Some(name),
+                beatmap_md5s: Vec::new(),
+            },
+        });
+
+        Some(index)
+    }
+
+    /// Deletes the collection at `index`, returning `false` if there's no such collection.
+    pub fn delete_collection(&mut self, index: usize) -> bool {
+        let Some(collection) = self
+            .collections
+            .as_ref()
+            .and_then(|collections| collections.collections.get(index))
+        else {
+            return false;
+        };
+
+        let collection = collection.clone();
+        self.perform(Operation::DeleteCollection { index, collection });
+        true
+    }
+
+    /// Runs `change` on the beatmaps at `indices`, recording the fields that it changed. Returns how many beatmaps were
+    /// changed.
+    fn change_beatmaps<F>(&mut self, indices: Vec<usize>, mut change: F) -> usize
+    where
+        F: FnMut(&mut BeatmapEntry),
+    {
+        let mut changes = Vec::new();
+        let mut changed = 0;
+
+        for index in indices {
+            let beatmap = &mut self.listing.beatmaps[index];
+            let before = FieldValue::all(beatmap);
+            change(beatmap);
+            let after = FieldValue::all(beatmap);
+
+            let count = changes.len();
+            changes.extend(
+                before
+                    .into_iter()
+                    .zip(after)
+                    .filter(|(before, after)| before != after)
+                    .map(|(before, after)| FieldChange {
+                        index,
+                        before,
+                        after,
+                    }),
+            );
+
+            if changes.len() > count {
+                changed += 1;
+            }
+        }
+
+        self.record(Operation::ChangeBeatmaps { changes });
+        changed
+    }
+
+    /// Makes an edit and records it in the journal.
+    fn perform(&mut self, operation: Operation) {
+        operation.apply(&mut self.listing, self.collections.as_mut());
+        self.record(operation);
+    }
+
+    /// Records an edit that has already been made, unless it didn't change anything. Any undone edits can no longer be
+    /// redone.
+    fn record(&mut self, operation: Operation) {
+        let empty = match &operation {
+            Operation::RemoveBeatmaps { removed, .. } => removed.is_empty(),
+            Operation::AddBeatmaps { beatmaps, .. } => beatmaps.is_empty(),
+            Operation::ChangeBeatmaps { changes } => changes.is_empty(),
+            Operation::AddToCollection { md5s, .. } => md5s.is_empty(),
+            Operation::RemoveFromCollection { removed, .. } => removed.is_empty(),
+            Operation::CreateCollection { .. } | Operation::DeleteCollection { .. } => false,
+        };
+
+        if !empty {
+            let depth = self.journal.done.len();
+
+            // Saved edits that were undone can't be redone after this, so remember which beatmaps they changed
+            if depth < self.saved.depth {
+                let undone = &self.journal.undone;
+                let lost = &undone[undone.len() - (self.saved.depth - depth)..];
+
+                self.saved
+                    .lost
+                    .get_or_insert_with(BTreeSet::new)
+                    .extend(lost.iter().flat_map(Operation::changed_beatmaps));
+                self.saved.depth = depth;
+            }
+
+            self.journal.done.push(operation);
+            self.journal.undone.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        beatmaps::ParseOptions,
+        common::WINDOWS_EPOCH,
+        edit::OffsetEdit,
+        scores::{BeatmapScores, ScoreReplay},
+    };
+
+    fn listing() -> BeatmapListing {
+        let beatmaps = (0..6)
+            .map(|i| BeatmapEntry {
+                artist_name: Some(format!("Artist {}", i)),
+                md5: Some(format!("{:032x}", i)),
+                folder_name: Some(format!("Folder {}", i / 2)),
+                gameplay_mode: if i < 3 {
+                    GameplayMode::Standard
+                } else {
+                    GameplayMode::Mania
+                },
+                local_offset: i as u16,
+                ..Default::default()
+            })
+            .collect();
+
+        BeatmapListing {
+            version: 20240101,
+            folder_count: 3,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: Some("Player".to_string()),
+            beatmaps,
+            user_permissions: Default::default(),
+        }
+    }
+
+    fn collections() -> CollectionListing {
+        CollectionListing {
+            version: 20240101,
+            collections: vec![
+                Collection {
+                    name: Some("Farm".to_string()),
+                    beatmap_md5s: vec![Some(format!("{:032x}", 1)), Some(format!("{:032x}", 4))],
+                },
+                Collection {
+                    name: Some("Practice".to_string()),
+                    beatmap_md5s: vec![Some(format!("{:032x}", 0))],
+                },
+            ],
+        }
+    }
+
+    fn scores() -> ScoreListing {
+        ScoreListing {
+            version: 20240101,
+            beatmap_scores: vec![BeatmapScores {
+                md5: Some(format!("{:032x}", 4)),
+                scores: vec![ScoreReplay {
+                    gameplay_mode: GameplayMode::Mania,
+                    hits_300: 100,
+                    ..Default::default()
+                }],
+            }],
+        }
+    }
+
+    /// Makes one of each kind of edit, returning the state after each one.
+    fn edit(session: &mut EditSession) -> Vec<(BeatmapListing, Option<CollectionListing>)> {
+        let snapshot =
+            |session: &EditSession| (session.listing().clone(), session.collections().cloned());
+
+        let mut states = vec![snapshot(session)];
+
+        let edit = BulkEdit {
+            disable_video: Some(true),
+            local_offset: Some(OffsetEdit::Shift(10)),
+            ..Default::default()
+        };
+        assert_eq!(
+            session.bulk_edit(&BeatmapFilter::parse("mode=mania").unwrap(), edit),
+            3
+        );
+        states.push(snapshot(session));
+
+        assert_eq!(session.recompute_grades(&scores()), 1);
+        states.push(snapshot(session));
+
+        assert_eq!(session.retain(|i, _| i % 3 != 1), 2);
+        states.push(snapshot(session));
+
+        let mut other = listing();
+        other.beatmaps[0].md5 = Some("new".to_string());
+        other.beatmaps[0].folder_name = Some("New".to_string());
+        assert_eq!(session.merge(&other), 3);
+        states.push(snapshot(session));
+
+        let md5s = [format!("{:032x}", 0), format!("{:032x}", 4)];
+        assert_eq!(session.add_to_collection(0, &md5s), 1);
+        states.push(snapshot(session));
+
+        assert_eq!(session.remove_from_collection(0, &md5s), 2);
+        states.push(snapshot(session));
+
+        assert_eq!(session.create_collection("New".to_string()), Some(2));
+        states.push(snapshot(session));
+
+        assert!(session.delete_collection(1));
+        states.push(snapshot(session));
+
+        assert_eq!(
+            session.bulk_edit_indices(
+                [0, 1, 100],
+                BulkEdit {
+                    visual_override: Some(true),
+                    ..Default::default()
+                }
+            ),
+            2
+        );
+        states.push(snapshot(session));
+
+        states
+    }
+
+    #[test]
+    fn edits_match_the_listing_methods() {
+        let mut session = EditSession::new(listing());
+        let mut expected = listing();
+
+        let filter = BeatmapFilter::parse("mode=mania").unwrap();
+        let edit = BulkEdit {
+            disable_storyboard: Some(true),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            session.bulk_edit(&filter, edit),
+            expected.bulk_edit(&filter, edit)
+        );
+        assert_eq!(
+            session.recompute_grades(&scores()),
+            expected.recompute_grades(&scores())
+        );
+        assert_eq!(session.retain(|i, _| i != 2), 1);
+        expected.retain(|i, _| i != 2);
+
+        assert_eq!(session.listing(), &expected);
+    }
+
+    #[test]
+    fn edits_are_undone_and_redone_in_order() {
+        let mut session = EditSession::with_collections(listing(), collections());
+        let states = edit(&mut session);
+        assert_eq!(session.journal().done.len(), states.len() - 1);
+
+        for state in states.iter().rev().skip(1) {
+            assert!(session.undo());
+            assert_eq!(session.listing(), &state.0);
+            assert_eq!(session.collections(), state.1.as_ref());
+        }
+
+        assert!(!session.undo());
+        assert_eq!(session.listing(), &listing());
+
+        for state in states.iter().skip(1) {
+            assert!(session.redo());
+            assert_eq!(session.listing(), &state.0);
+            assert_eq!(session.collections(), state.1.as_ref());
+        }
+
+        assert!(!session.redo());
+    }
+
+    #[test]
+    fn new_edits_clear_the_redo_history() {
+        let mut session = EditSession::new(listing());
+        session.retain(|i, _| i != 0);
+        session.undo();
+        assert!(session.can_redo());
+
+        session.retain(|i, _| i != 1);
+        assert!(!session.can_redo());
+        assert_eq!(session.listing().beatmaps.len(), 5);
+    }
+
+    #[test]
+    fn edits_without_changes_are_not_recorded() {
+        let mut session = EditSession::new(listing());
+
+        assert_eq!(session.retain(|_, _| true), 0);
+        assert_eq!(
+            session.bulk_edit(&BeatmapFilter::default(), BulkEdit::default()),
+            0
+        );
+        assert_eq!(session.merge(&listing()), 0);
+
+        // Collections aren't being edited
+        assert_eq!(session.add_to_collection(0, &["md5".to_string()]), 0);
+        assert_eq!(session.create_collection("New".to_string()), None);
+
+        assert!(!session.can_undo());
+    }
+
+    #[test]
+    fn reverting_restores_the_original() {
+        let mut session = EditSession::with_collections(listing(), collections());
+        edit(&mut session);
+
+        assert_eq!(session.revert(), 9);
+        let (reverted, reverted_collections) = session.into_parts();
+        assert_eq!(reverted, listing());
+        assert_eq!(reverted_collections, Some(collections()));
+    }
+
+    #[test]
+    fn sessions_are_resumed_from_the_journal() {
+        let mut session = EditSession::with_collections(listing(), collections());
+        edit(&mut session);
+
+        // Simulate saving the edited files and the journal, then reverting them in a later session
+        let journal = session.journal().clone();
+        let (listing_file, collection_file) = session.into_parts();
+        let listing_file = BeatmapListing::from_bytes(&listing_file.to_bytes()).unwrap();
+        let collection_file =
+            CollectionListing::from_bytes(&collection_file.unwrap().to_bytes()).unwrap();
+
+        let mut resumed = EditSession::resume(listing_file, Some(collection_file), journal);
+        resumed.revert();
+
+        let (reverted, reverted_collections) = resumed.into_parts();
+        assert_eq!(reverted.to_bytes(), listing().to_bytes());
+        assert_eq!(reverted_collections, Some(collections()));
+    }
+
+    #[test]
+    fn single_beatmap_edits_only_change_the_edited_fields() {
+        let original = listing();
+        let edit = BulkEdit {
+            local_offset: Some(OffsetEdit::Set(-15)),
+            ignore_beatmap_hitsounds: Some(true),
+            ignore_beatmap_skin: Some(true),
+            disable_storyboard: Some(true),
+            disable_video: Some(true),
+            mania_scroll_speed: Some(27),
+            ..Default::default()
+        };
+
+        let mut session = EditSession::new(original.clone());
+        assert!(session.edit_beatmap(4, edit));
+        assert!(!session.edit_beatmap(4, edit));
+        assert!(!session.edit_beatmap(100, edit));
+
+        let data = session.listing().to_bytes();
+        let written = BeatmapListing::from_bytes(&data).unwrap();
+
+        let mut expected = BeatmapListing::from_bytes(&original.to_bytes()).unwrap();
+        edit.apply(&mut expected.beatmaps[4]);
+        assert_eq!(written, expected);
+        assert_eq!(written.beatmaps[4].local_offset as i16, -15);
+        assert_eq!(written.beatmaps[4].mania_scroll_speed, 27);
+
+        // Every changed byte is in the edited beatmap's entry
+        let original_data = original.to_bytes();
+        let options = ParseOptions {
+            record_spans: true,
+            ..Default::default()
+        };
+        let spans = BeatmapListing::from_bytes_with_options(&original_data, options, |_, _| true)
+            .unwrap()
+            .spans;
+
+        assert_eq!(data.len(), original_data.len());
+        assert!(data
+            .iter()
+            .zip(&original_data)
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .all(|(offset, _)| spans[4].contains(&offset)));
+    }
+
+    #[test]
+    fn consecutive_edits_to_a_beatmap_are_merged() {
+        let offset = |offset| BulkEdit {
+            local_offset: Some(OffsetEdit::Set(offset)),
+            ..Default::default()
+        };
+        let video = BulkEdit {
+            disable_video: Some(true),
+            ..Default::default()
+        };
+
+        let mut session = EditSession::new(listing());
+
+        // Dragging the offset is undone in one step
+        for value in 10..20 {
+            session.edit_beatmap(1, offset(value));
+        }
+        assert_eq!(session.journal().done.len(), 1);
+
+        // Other fields and beatmaps are separate steps
+        session.edit_beatmap(1, video);
+        session.edit_beatmap(2, offset(19));
+        assert_eq!(session.journal().done.len(), 3);
+
+        session.undo();
+        session.undo();
+        assert_eq!(session.listing().beatmaps[1].local_offset, 19);
+        session.undo();
+        assert_eq!(session.listing(), &listing());
+
+        // Edits which cancel out aren't kept
+        session.edit_beatmap(3, video);
+        session.edit_beatmap(
+            3,
+            BulkEdit {
+                disable_video: Some(false),
+                ..Default::default()
+            },
+        );
+        assert!(!session.can_undo());
+    }
+
+    #[test]
+    fn edited_beatmaps_are_tracked_until_saved() {
+        let offset = |offset| BulkEdit {
+            local_offset: Some(OffsetEdit::Set(offset)),
+            ..Default::default()
+        };
+
+        let mut session = EditSession::new(listing());
+        assert!(!session.is_modified());
+
+        session.edit_beatmap(1, offset(50));
+        session.edit_beatmap(3, offset(50));
+        assert!(session.is_modified());
+        assert_eq!(session.edited_beatmaps(), BTreeSet::from([1, 3]));
+
+        session.mark_saved();
+        assert!(!session.is_modified());
+        assert!(session.edited_beatmaps().is_empty());
+
+        // Undoing past the save point changes the beatmaps back from their saved values
+        session.undo();
+        assert!(session.is_modified());
+        assert_eq!(session.edited_beatmaps(), BTreeSet::from([3]));
+        session.redo();
+        assert!(!session.is_modified());
+
+        // Saving isn't merged into, and the saved state can't be reached again once the undone edit is replaced
+        session.edit_beatmap(3, offset(60));
+        assert_eq!(session.journal().done.len(), 3);
+        session.undo();
+        session.undo();
+        session.edit_beatmap(5, offset(70));
+        assert!(session.is_modified());
+        assert_eq!(session.edited_beatmaps(), BTreeSet::from([3, 5]));
+
+        session.undo();
+        assert!(session.is_modified());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn journals_are_serialized() {
+        let mut session = EditSession::with_collections(listing(), collections());
+        edit(&mut session);
+        session.undo();
+
+        let json = serde_json::to_string(session.journal()).unwrap();
+        let journal: Journal = serde_json::from_str(&json).unwrap();
+        assert_eq!(&journal, session.journal());
+    }
+}
@@ -0,0 +1,383 @@
+//! Reading databases while osu! has them open.
+//!
+//! osu! keeps `osu!.db` open while it's running, and writes each database over itself when it saves (e.g. after
+//! importing beatmaps, or when it closes). Files are opened here without denying osu! any access, so that it can carry
+//! on while they're read, and a read that catches a database part way through being written is tried again after a
+//! short wait, for as long as the [`RetryPolicy`] allows.
+//!
+//! A read is taken to have caught a database mid-write when its header can't be parsed and the file's size no longer
+//! matches the amount read. A database with a broken header that isn't changing is returned as it is, so that parsing
+//! it reports the real problem. Once the tries run out, [`Error::DatabaseBusy`] is returned, which tells the user to
+//! try again in a moment, and to close osu! before writing to the file.
+//!
+//! # Windows share modes
+//!
+//! Windows only opens a file if the new handle's share mode allows the access of every handle already open, and each
+//! of those allows the new handle's access. Files are opened with [`OpenOptionsExt::share_mode`] set to allow reading,
+//! writing and deleting, so the viewer never stops osu! from saving. std's `File::open` happens to ask for the same
+//! share mode, but it's set explicitly so as not to depend on that. If osu! has a database open without sharing it,
+//! opening fails with `ERROR_SHARING_VIOLATION` (or `ERROR_LOCK_VIOLATION` for a locked range), which is tried again
+//! like a file being written.
+//!
+//! This path can't be tested automatically, so it's checked by hand on Windows:
+//!
+//! 1. Start osu! and wait for the main menu, when it has `osu!.db` open.
+//! 2. Open `osu!.db` in the viewer, or run `osu-db stats path/to/osu!.db`. It should be read as normal.
+//! 3. Import a large beatmap set in osu!, and open `osu!.db` again while osu! is saving. It should either be read
+//!    after a short delay or fail with the message for [`Error::DatabaseBusy`], never with a parse error.
+//! 4. Save a change with osu! still running. Replacing the file may fail while osu! holds it, and osu! may write over
+//!    the change when it closes, so osu! should be closed before writing.
+//!
+//! [`OpenOptionsExt::share_mode`]: https://doc.rust-lang.org/std/os/windows/fs/trait.OpenOptionsExt.html#tymethod.share_mode
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{detect::FileKind, error::Error};
+
+/// Windows error when another handle's share mode doesn't allow the access asked for.
+#[cfg(windows)]
+const ERROR_SHARING_VIOLATION: i32 = 32;
+
+/// Windows error when part of the file has been locked by another process.
+#[cfg(windows)]
+const ERROR_LOCK_VIOLATION: i32 = 33;
+
+/// How many times to try reading a database that's being written, and how long to wait between tries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of times to try reading the file, including the first
+    pub attempts: u32,
+
+    /// Time to wait after the first try that finds the file busy, which doubles after each one
+    pub initial_delay: Duration,
+
+    /// Longest time to wait between tries
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that only tries once, e.g. for files that osu! doesn't write.
+    pub fn once() -> RetryPolicy {
+        RetryPolicy {
+            attempts: 1,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Tries 6 times over about 1.5 seconds, which is long enough for osu! to finish writing a large `osu!.db`.
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 6,
+            initial_delay: Duration::from_millis(50),
+            max_delay: Duration::from_millis(800),
+        }
+    }
+}
+
+/// The outcome of one try at reading a file.
+#[derive(Debug)]
+enum Attempt<T> {
+    /// The file was read
+    Done(T),
+
+    /// The file looks like it's being written by another program, so it should be tried again
+    Busy,
+
+    /// The file couldn't be read, and trying again won't help
+    Failed(Error),
+}
+
+/// Keeps track of the tries made under a policy, giving the time to wait before each new one.
+#[derive(Debug)]
+struct Backoff {
+    policy: RetryPolicy,
+
+    /// Number of tries that have found the file busy so far
+    busy: u32,
+
+    /// Time to wait before the next try, before it's limited to the policy's longest delay
+    delay: Duration,
+}
+
+impl Backoff {
+    fn new(policy: RetryPolicy) -> Backoff {
+        Backoff {
+            policy,
+            busy: 0,
+            delay: policy.initial_delay,
+        }
+    }
+
+    /// Records a try that found the file busy, returning how long to wait before the next one, or `None` if there are
+    /// no tries left.
+    fn busy(&mut self) -> Option<Duration> {
+        self.busy += 1;
+
+        if self.busy >= self.policy.attempts {
+            return None;
+        }
+
+        let delay = self.delay.min(self.policy.max_delay);
+        self.delay = self.delay.saturating_mul(2);
+        Some(delay)
+    }
+}
+
+/// Makes tries until one is done or fails, waiting with `sleep` after each one that finds the file busy. The path is
+/// only used for the error once the policy's tries have run out.
+fn retry<T>(
+    path: &Path,
+    policy: RetryPolicy,
+    mut sleep: impl FnMut(Duration),
+    mut attempt: impl FnMut() -> Attempt<T>,
+) -> Result<T, Error> {
+    let mut backoff = Backoff::new(policy);
+
+    loop {
+        match attempt() {
+            Attempt::Done(value) => return Ok(value),
+            Attempt::Failed(e) => return Err(e),
+            Attempt::Busy => match backoff.busy() {
+                Some(delay) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        path = %path.display(),
+                        tries = backoff.busy,
+                        ?delay,
+                        "file is busy, trying again"
+                    );
+
+                    sleep(delay);
+                }
+                None => return Err(Error::DatabaseBusy(path.to_path_buf())),
+            },
+        }
+    }
+}
+
+/// Opens a file for reading without stopping other programs (i.e. osu!) from writing, replacing or deleting it.
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        // FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE
+        options.share_mode(0x1 | 0x2 | 0x4);
+    }
+
+    options.open(path)
+}
+
+/// Reads the whole of a file once, opening it with [`open`].
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    let mut file = open(path)?;
+    let mut data = Vec::with_capacity(file.metadata().map_or(0, |m| m.len() as usize));
+    file.read_to_end(&mut data)?;
+
+    Ok(data)
+}
+
+/// Reads a database of a known kind, trying again while it's being written with the default [`RetryPolicy`].
+pub fn read_database<P: AsRef<Path>>(path: P, kind: FileKind) -> Result<Vec<u8>, Error> {
+    read_database_with(path, kind, RetryPolicy::default())
+}
+
+/// Reads a database of a known kind like [`read_database`], with the given policy for trying again.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+)]
+pub fn read_database_with<P: AsRef<Path>>(
+    path: P,
+    kind: FileKind,
+    policy: RetryPolicy,
+) -> Result<Vec<u8>, Error> {
+    let path = path.as_ref();
+    retry(path, policy, std::thread::sleep, || try_read(path, kind))
+}
+
+/// Makes one try at reading a database, checking whether it was caught part way through being written.
+fn try_read(path: &Path, kind: FileKind) -> Attempt<Vec<u8>> {
+    let data = match read(path) {
+        Ok(data) => data,
+        Err(e) if is_sharing_violation(&e) => return Attempt::Busy,
+        Err(e) => return Attempt::Failed(e.into()),
+    };
+
+    if kind.has_header(&data) {
+        return Attempt::Done(data);
+    }
+
+    // osu! empties a database before writing it again, so while it's being written the header is missing or cut short
+    // and the file keeps growing
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.len() != data.len() as u64 => Attempt::Busy,
+        _ => Attempt::Done(data),
+    }
+}
+
+/// Checks whether opening or reading a file failed because another program has it open without sharing it.
+fn is_sharing_violation(error: &io::Error) -> bool {
+    #[cfg(windows)]
+    {
+        matches!(
+            error.raw_os_error(),
+            Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+        )
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = error;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        attempts: 5,
+        initial_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(40),
+    };
+
+    /// Runs the retry loop with tries that give each outcome in turn, returning the result and the delays waited.
+    fn run(outcomes: Vec<Attempt<u32>>) -> (Result<u32, Error>, Vec<Duration>, usize) {
+        let outcomes = RefCell::new(outcomes.into_iter());
+        let mut tries = 0;
+        let mut delays = Vec::new();
+
+        let result = retry(
+            Path::new("osu!.db"),
+            POLICY,
+            |delay| delays.push(delay),
+            || {
+                tries += 1;
+                outcomes
+                    .borrow_mut()
+                    .next()
+                    .expect("tried after the last outcome")
+            },
+        );
+
+        (result, delays, tries)
+    }
+
+    fn millis(delays: &[u64]) -> Vec<Duration> {
+        delays.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn first_try_is_returned_without_waiting() {
+        let (result, delays, tries) = run(vec![Attempt::Done(1)]);
+
+        assert_eq!(result.unwrap(), 1);
+        assert!(delays.is_empty());
+        assert_eq!(tries, 1);
+    }
+
+    #[test]
+    fn busy_tries_back_off_until_the_file_is_read() {
+        let (result, delays, tries) = run(vec![
+            Attempt::Busy,
+            Attempt::Busy,
+            Attempt::Busy,
+            Attempt::Done(2),
+        ]);
+
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(delays, millis(&[10, 20, 40]));
+        assert_eq!(tries, 4);
+    }
+
+    #[test]
+    fn database_is_busy_once_the_tries_run_out() {
+        let (result, delays, tries) = run((0..POLICY.attempts).map(|_| Attempt::Busy).collect());
+
+        // The delay stops doubling at the longest delay, and there's no wait after the last try
+        assert_eq!(delays, millis(&[10, 20, 40, 40]));
+        assert_eq!(tries, POLICY.attempts as usize);
+
+        let error = result.unwrap_err();
+        assert!(matches!(&error, Error::DatabaseBusy(path) if path == Path::new("osu!.db")));
+        assert!(error.to_string().contains("close osu!"), "{}", error);
+    }
+
+    #[test]
+    fn failures_are_not_tried_again() {
+        let (result, delays, tries) = run(vec![
+            Attempt::Busy,
+            Attempt::Failed(Error::Cancelled),
+            Attempt::Done(3),
+        ]);
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert_eq!(delays, millis(&[10]));
+        assert_eq!(tries, 2);
+    }
+
+    #[test]
+    fn single_try_policy_does_not_wait() {
+        let mut backoff = Backoff::new(RetryPolicy::once());
+        assert_eq!(backoff.busy(), None);
+    }
+
+    #[test]
+    #[cfg(all(feature = "collections", feature = "writer"))]
+    fn databases_are_read_from_files() {
+        use std::fs;
+
+        use crate::collections::{Collection, CollectionListing};
+
+        let dir = std::env::temp_dir().join(format!("osu-db-shared-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let collection_db = dir.join("collection.db");
+        let data = CollectionListing {
+            version: 20150203,
+            collections: vec![Collection {
+                name: Some("Favourites".to_string()),
+                beatmap_md5s: vec![Some("0123456789abcdef0123456789abcdef".to_string())],
+            }],
+        }
+        .to_bytes();
+        fs::write(&collection_db, &data).unwrap();
+
+        assert_eq!(
+            read_database(&collection_db, FileKind::Collections).unwrap(),
+            data
+        );
+
+        // A cut short header that isn't changing is returned straight away, so that parsing can say what's wrong
+        fs::write(&collection_db, &data[..6]).unwrap();
+        assert!(matches!(
+            try_read(&collection_db, FileKind::Collections),
+            Attempt::Done(read) if read == data[..6]
+        ));
+        assert!(matches!(
+            CollectionListing::from_file(&collection_db),
+            Err(Error::Parse { .. })
+        ));
+
+        assert!(matches!(
+            try_read(&dir.join("missing.db"), FileKind::Collections),
+            Attempt::Failed(Error::IO(_))
+        ));
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
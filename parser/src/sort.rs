@@ -0,0 +1,345 @@
+//! Sorting of beatmap entries by a [`Column`], or by precomputed values such as those of a
+//! [custom column](crate::expr::CustomColumn).
+
+use std::cmp::Ordering;
+
+use crate::{
+    beatmaps::{BeatmapEntry, MetadataLanguage, StarPolicy},
+    columns::{Column, ColumnValue},
+};
+
+/// Represents the direction to sort in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// Represents a column to sort beatmaps by, along with the direction to sort in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SortKey {
+    pub column: Column,
+    pub order: SortOrder,
+
+    /// Which version of the artist and title to sort by, which should match the version being shown
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub language: MetadataLanguage,
+
+    /// Which star rating to sort the Stars column by, which should match the star rating being shown
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stars: StarPolicy,
+}
+
+impl SortOrder {
+    /// Gets the opposite sort order.
+    pub fn reversed(&self) -> SortOrder {
+        match self {
+            SortOrder::Ascending => SortOrder::Descending,
+            SortOrder::Descending => SortOrder::Ascending,
+        }
+    }
+}
+
+impl SortKey {
+    /// Creates a sort key for a column, in ascending order.
+    pub fn new(column: Column) -> SortKey {
+        SortKey {
+            column,
+            order: SortOrder::Ascending,
+            language: MetadataLanguage::default(),
+            stars: StarPolicy::default(),
+        }
+    }
+
+    /// Compares two beatmaps.
+    pub fn compare(&self, a: &BeatmapEntry, b: &BeatmapEntry) -> Ordering {
+        self.compare_values(
+            &self.column.value_with(a, self.language, self.stars),
+            &self.column.value_with(b, self.language, self.stars),
+        )
+    }
+
+    /// Compares two values of this key's column.
+    ///
+    /// Text is compared case-insensitively, and missing values are always placed last regardless of the sort order.
+    /// Floats that aren't finite (e.g. NaN star ratings) are treated as missing.
+    pub fn compare_values(&self, a: &ColumnValue, b: &ColumnValue) -> Ordering {
+        use ColumnValue::*;
+
+        let missing = |value: &ColumnValue| match value {
+            Empty => true,
+            Float(value) => !value.is_finite(),
+            _ => false,
+        };
+
+        let ordering = match (a, b) {
+            _ if missing(a) || missing(b) => return missing(a).cmp(&missing(b)),
+            (Text(a), Text(b)) => a
+                .chars()
+                .flat_map(char::to_lowercase)
+                .cmp(b.chars().flat_map(char::to_lowercase)),
+            (Integer(a), Integer(b)) => a.cmp(b),
+            (Float(a), Float(b)) => a.total_cmp(b),
+            (Duration(a), Duration(b)) => a.cmp(b),
+            (DateTime(a), DateTime(b)) => a.cmp(b),
+            (Grade(a), Grade(b)) => a.cmp(b),
+            _ => Ordering::Equal,
+        };
+
+        match self.order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    }
+
+    /// Sorts a list of indices into `beatmaps` using this key.
+    ///
+    /// The sort is stable, and each beatmap's value is only looked up once, so this is suitable for large listings.
+    pub fn sort_indices(&self, beatmaps: &[BeatmapEntry], indices: &mut Vec<usize>) {
+        let mut keyed = indices
+            .iter()
+            .map(|&i| {
+                (
+                    self.column
+                        .value_with(&beatmaps[i], self.language, self.stars),
+                    i,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        keyed.sort_by(|(a, _), (b, _)| self.compare_values(a, b));
+
+        indices.clear();
+        indices.extend(keyed.into_iter().map(|(_, i)| i));
+    }
+}
+
+/// Sorts a list of indices into `values`, which holds a precomputed value for each beatmap (e.g. from
+/// [`CustomColumn::values`](crate::expr::CustomColumn::values)).
+///
+/// The sort is stable, and values that aren't finite (e.g. NaN from dividing by zero) are treated as missing, so
+/// they're always placed last regardless of the sort order.
+pub fn sort_indices_by_values(values: &[f64], order: SortOrder, indices: &mut [usize]) {
+    indices.sort_by(|&a, &b| {
+        let (a, b) = (values[a], values[b]);
+
+        match (a.is_finite(), b.is_finite()) {
+            (false, false) => Ordering::Equal,
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (true, true) => match order {
+                SortOrder::Ascending => a.total_cmp(&b),
+                SortOrder::Descending => b.total_cmp(&a),
+            },
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beatmaps::{StarRating, StarSource};
+    use crate::common::{GameplayMode, Grade, Mods};
+    use crate::expr::CustomColumn;
+
+    fn beatmap(title: Option<&str>, stars: Option<f64>) -> BeatmapEntry {
+        BeatmapEntry {
+            song_title: title.map(|t| t.to_string()),
+            star_ratings_std: stars.map(|rating| {
+                vec![StarRating {
+                    mods: Mods::none(),
+                    rating,
+                }]
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn sorted(beatmaps: &[BeatmapEntry], key: SortKey) -> Vec<usize> {
+        let mut indices = (0..beatmaps.len()).collect();
+        key.sort_indices(beatmaps, &mut indices);
+        indices
+    }
+
+    #[test]
+    fn text_is_sorted_case_insensitively() {
+        let beatmaps = [
+            beatmap(Some("banana"), None),
+            beatmap(None, None),
+            beatmap(Some("Apple"), None),
+            beatmap(Some("cherry"), None),
+        ];
+
+        let mut key = SortKey::new(Column::Title);
+        assert_eq!(sorted(&beatmaps, key), vec![2, 0, 3, 1]);
+
+        key.order = key.order.reversed();
+        assert_eq!(sorted(&beatmaps, key), vec![3, 0, 2, 1]);
+    }
+
+    #[test]
+    fn titles_are_sorted_in_the_metadata_language() {
+        let beatmaps = [
+            BeatmapEntry {
+                song_title_unicode: Some("Aaa".to_string()),
+                ..beatmap(Some("Zzz"), None)
+            },
+            beatmap(Some("Mmm"), None),
+            BeatmapEntry {
+                song_title_unicode: Some("Bbb".to_string()),
+                ..beatmap(Some(""), None)
+            },
+        ];
+
+        // Beatmaps without the preferred title are sorted by the other one
+        let key = SortKey::new(Column::Title);
+        assert_eq!(sorted(&beatmaps, key), vec![2, 1, 0]);
+
+        let key = SortKey {
+            language: MetadataLanguage::Unicode,
+            ..key
+        };
+        assert_eq!(sorted(&beatmaps, key), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn numbers_are_sorted_with_missing_values_last() {
+        let beatmaps = [
+            beatmap(None, Some(5.5)),
+            beatmap(None, None),
+            beatmap(None, Some(2.25)),
+            beatmap(None, Some(5.5)),
+        ];
+
+        let key = SortKey::new(Column::StarsStd);
+        assert_eq!(sorted(&beatmaps, key), vec![2, 0, 3, 1]);
+
+        // Ties should keep their original order
+        let key = SortKey {
+            order: SortOrder::Descending,
+            ..SortKey::new(Column::StarsStd)
+        };
+        assert_eq!(sorted(&beatmaps, key), vec![0, 3, 2, 1]);
+    }
+
+    #[test]
+    fn non_finite_floats_are_sorted_last() {
+        let values = [
+            ColumnValue::Float(f64::NAN),
+            ColumnValue::Float(2.0),
+            ColumnValue::Empty,
+            ColumnValue::Float(f64::INFINITY),
+            ColumnValue::Float(-1.0),
+            ColumnValue::Float(-f64::NAN),
+        ];
+
+        for order in [SortOrder::Ascending, SortOrder::Descending] {
+            let key = SortKey {
+                order,
+                ..SortKey::new(Column::Bpm)
+            };
+            let mut indices = (0..values.len()).collect::<Vec<_>>();
+            indices.sort_by(|&a, &b| key.compare_values(&values[a], &values[b]));
+
+            let finite = match order {
+                SortOrder::Ascending => [4, 1],
+                SortOrder::Descending => [1, 4],
+            };
+            assert_eq!(indices[..2], finite);
+            assert_eq!(indices[2..], [0, 2, 3, 5]);
+        }
+    }
+
+    #[test]
+    fn stars_are_sorted_using_the_star_policy() {
+        let convert = BeatmapEntry {
+            star_ratings_mania: Some(vec![StarRating {
+                mods: Mods::none(),
+                rating: 2.0,
+            }]),
+            ..beatmap(None, Some(5.0))
+        };
+        let mania = BeatmapEntry {
+            gameplay_mode: GameplayMode::Mania,
+            star_ratings_mania: Some(vec![StarRating {
+                mods: Mods::none(),
+                rating: 3.0,
+            }]),
+            ..Default::default()
+        };
+        let std = beatmap(None, Some(1.0));
+        let beatmaps = [convert, mania, std];
+
+        let key = |source, missing_as_zero| SortKey {
+            stars: StarPolicy {
+                source,
+                missing_as_zero,
+            },
+            ..SortKey::new(Column::Stars)
+        };
+
+        let mania_mode = StarSource::FixedMode(GameplayMode::Mania);
+        let prefer_mania = StarSource::PreferredWithFallback(GameplayMode::Mania);
+
+        assert_eq!(
+            sorted(&beatmaps, key(StarSource::NativeMode, false)),
+            vec![2, 1, 0]
+        );
+        assert_eq!(sorted(&beatmaps, key(mania_mode, false)), vec![0, 1, 2]);
+        assert_eq!(sorted(&beatmaps, key(mania_mode, true)), vec![2, 0, 1]);
+        assert_eq!(sorted(&beatmaps, key(prefer_mania, false)), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn grades_are_sorted_best_first() {
+        let beatmaps = [
+            Grade::A,
+            Grade::Unplayed,
+            Grade::SilverSS,
+            Grade::S,
+            Grade::SS,
+        ]
+        .map(|grade_std| BeatmapEntry {
+            grade_std,
+            ..Default::default()
+        });
+
+        let key = SortKey::new(Column::GradeStd);
+        assert_eq!(sorted(&beatmaps, key), vec![2, 4, 3, 0, 1]);
+    }
+
+    #[test]
+    fn custom_values_are_sorted_with_nan_last() {
+        let beatmaps = [
+            BeatmapEntry {
+                hitcircle_count: 300,
+                drain_time: 100,
+                ..Default::default()
+            },
+            BeatmapEntry {
+                hitcircle_count: 300,
+                drain_time: 0,
+                ..Default::default()
+            },
+            BeatmapEntry {
+                hitcircle_count: 100,
+                drain_time: 100,
+                ..Default::default()
+            },
+        ];
+
+        let density = CustomColumn::new("Density", "objects / drain_time").unwrap();
+        let values = density.values(&beatmaps);
+
+        let mut indices = vec![0, 1, 2];
+        sort_indices_by_values(&values, SortOrder::Ascending, &mut indices);
+        assert_eq!(indices, vec![2, 0, 1]);
+
+        // Dividing by zero gives NaN, which stays last when sorting in descending order too
+        sort_indices_by_values(&values, SortOrder::Descending, &mut indices);
+        assert_eq!(indices, vec![0, 2, 1]);
+    }
+}
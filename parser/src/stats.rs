@@ -0,0 +1,210 @@
+//! Summary statistics for a beatmap library, e.g. for an overview of what's installed.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    beatmaps::{BeatmapEntry, RankedStatus},
+    common::GameplayMode,
+};
+
+/// Number of buckets in the star rating histogram. Each bucket covers 1 star, except the last which includes
+/// everything from 10 stars upwards.
+pub const STAR_BUCKETS: usize = 11;
+
+/// Ranked statuses in the order they are counted, so that empty categories are still included.
+const STATUSES: [RankedStatus; 7] = [
+    RankedStatus::Ranked,
+    RankedStatus::Approved,
+    RankedStatus::Qualified,
+    RankedStatus::Loved,
+    RankedStatus::Pending,
+    RankedStatus::Unsubmitted,
+    RankedStatus::Unknown,
+];
+
+/// Gameplay modes in the order they are counted.
+const MODES: [GameplayMode; 4] = [
+    GameplayMode::Standard,
+    GameplayMode::Taiko,
+    GameplayMode::Catch,
+    GameplayMode::Mania,
+];
+
+/// Represents summary statistics for a list of beatmaps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LibraryStats {
+    /// Total number of beatmaps
+    pub total: usize,
+
+    /// Number of beatmaps that haven't been played
+    pub unplayed: usize,
+
+    /// Number of beatmaps in each bucket of NoMod star rating (see [`star_bucket`]). Beatmaps without a star rating
+    /// aren't counted.
+    pub star_histogram: [usize; STAR_BUCKETS],
+
+    /// Number of beatmaps with each ranked status, including statuses without any beatmaps
+    pub statuses: Vec<(RankedStatus, usize)>,
+
+    /// Number of beatmaps for each gameplay mode, including modes without any beatmaps
+    pub modes: Vec<(GameplayMode, usize)>,
+
+    /// Number of beatmaps last modified in each year. Beatmaps without a modification time aren't counted.
+    pub per_year: BTreeMap<i32, usize>,
+
+    /// Creators with the most beatmaps, along with their number of beatmaps, most first
+    pub top_mappers: Vec<(String, usize)>,
+}
+
+impl LibraryStats {
+    /// Calculates statistics for a list of beatmaps, including up to `top_mappers` creators with the most beatmaps.
+    pub fn from_beatmaps(beatmaps: &[BeatmapEntry], top_mappers: usize) -> LibraryStats {
+        let mut star_histogram = [0; STAR_BUCKETS];
+        let mut statuses = STATUSES.map(|status| (status, 0)).to_vec();
+        let mut modes = MODES.map(|mode| (mode, 0)).to_vec();
+        let mut per_year = BTreeMap::new();
+        let mut mappers = HashMap::<&str, usize>::new();
+
+        for beatmap in beatmaps {
+            if let Some(stars) = beatmap.nomod_star_rating() {
+                star_histogram[star_bucket(stars)] += 1;
+            }
+
+            if let Some((_, count)) = statuses
+                .iter_mut()
+                .find(|(s, _)| *s == beatmap.ranked_status)
+            {
+                *count += 1;
+            }
+
+            if let Some((_, count)) = modes.iter_mut().find(|(m, _)| *m == beatmap.gameplay_mode) {
+                *count += 1;
+            }
+
+            // Unset modification times are stored as the Windows epoch (year 1), and osu! was released in 2007
+            let year = beatmap.last_modification_time.year();
+            if year >= 2007 {
+                *per_year.entry(year).or_default() += 1;
+            }
+
+            if let Some(creator) = beatmap.creator_name.as_deref().filter(|c| !c.is_empty()) {
+                *mappers.entry(creator).or_default() += 1;
+            }
+        }
+
+        let mut mappers = mappers
+            .into_iter()
+            .map(|(creator, count)| (creator.to_string(), count))
+            .collect::<Vec<_>>();
+
+        mappers.sort_by(|(a_name, a_count), (b_name, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_name.cmp(b_name))
+        });
+        mappers.truncate(top_mappers);
+
+        LibraryStats {
+            total: beatmaps.len(),
+            unplayed: beatmaps.iter().filter(|b| b.is_unplayed).count(),
+            star_histogram,
+            statuses,
+            modes,
+            per_year,
+            top_mappers: mappers,
+        }
+    }
+
+    /// Gets the percentage of beatmaps that haven't been played, or 0 if there aren't any beatmaps.
+    pub fn unplayed_percentage(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.unplayed as f64 / self.total as f64 * 100.0
+        }
+    }
+}
+
+/// Gets the star rating histogram bucket for a star rating, i.e. `0` for 0-1 stars, `1` for 1-2 stars, and so on.
+pub fn star_bucket(stars: f64) -> usize {
+    (stars.max(0.0) as usize).min(STAR_BUCKETS - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::{beatmaps::StarRating, common::Mods};
+
+    fn beatmap(creator: &str, stars: f64, status: RankedStatus, year: i32) -> BeatmapEntry {
+        BeatmapEntry {
+            creator_name: Some(creator.to_string()),
+            ranked_status: status,
+            star_ratings_std: Some(vec![StarRating {
+                mods: Mods::none(),
+                rating: stars,
+            }]),
+            last_modification_time: datetime!(2020-01-01 0:00 UTC).replace_year(year).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn stats_are_calculated() {
+        let mut played = beatmap("peppy", 2.5, RankedStatus::Ranked, 2014);
+        played.is_unplayed = false;
+
+        let beatmaps = vec![
+            played,
+            beatmap("peppy", 2.9, RankedStatus::Ranked, 2014),
+            beatmap("Mapper", 12.0, RankedStatus::Loved, 2021),
+            beatmap("Another", 5.0, RankedStatus::Pending, 1),
+        ];
+
+        let stats = LibraryStats::from_beatmaps(&beatmaps, 2);
+
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.unplayed, 3);
+        assert_eq!(stats.unplayed_percentage(), 75.0);
+        assert_eq!(stats.star_histogram, [0, 0, 2, 0, 0, 1, 0, 0, 0, 0, 1]);
+
+        // Empty categories are still included
+        assert_eq!(stats.statuses.len(), STATUSES.len());
+        assert!(stats.statuses.contains(&(RankedStatus::Ranked, 2)));
+        assert!(stats.statuses.contains(&(RankedStatus::Qualified, 0)));
+        assert_eq!(stats.modes[0], (GameplayMode::Standard, 4));
+        assert_eq!(stats.modes[3], (GameplayMode::Mania, 0));
+
+        // Unset modification times are skipped
+        assert_eq!(stats.per_year, BTreeMap::from([(2014, 2), (2021, 1)]));
+
+        // Ties are broken by name
+        assert_eq!(
+            stats.top_mappers,
+            vec![("peppy".to_string(), 2), ("Another".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn empty_libraries_are_handled() {
+        let stats = LibraryStats::from_beatmaps(&[], 10);
+
+        assert_eq!(stats.total, 0);
+        assert_eq!(stats.unplayed_percentage(), 0.0);
+        assert_eq!(stats.star_histogram, [0; STAR_BUCKETS]);
+        assert!(stats.statuses.iter().all(|(_, count)| *count == 0));
+        assert!(stats.per_year.is_empty());
+        assert!(stats.top_mappers.is_empty());
+    }
+
+    #[test]
+    fn star_buckets_are_correct() {
+        assert_eq!(star_bucket(0.0), 0);
+        assert_eq!(star_bucket(0.99), 0);
+        assert_eq!(star_bucket(1.0), 1);
+        assert_eq!(star_bucket(9.99), 9);
+        assert_eq!(star_bucket(10.0), 10);
+        assert_eq!(star_bucket(25.0), 10);
+        assert_eq!(star_bucket(-1.0), 0);
+    }
+}
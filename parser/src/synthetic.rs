@@ -0,0 +1,662 @@
+//! Generation of made up but plausible beatmap libraries, e.g. for screenshots, demos and benchmarks, without using
+//! anyone's real `osu!.db`. Requires the `synthetic` feature.
+//!
+//! Libraries are generated from a seed, so the same [`SyntheticOptions`] always give the same beatmaps. Beatmaps are
+//! grouped into beatmapsets of 1 to 6 difficulties, and their values follow rough approximations of real libraries:
+//!
+//! - Star ratings follow a log-normal distribution around the median, so most beatmaps are easier than the mean, with a
+//!   long tail of hard ones.
+//! - Drain times follow a normal distribution around the mean, so most beatmaps last around 90-180 seconds (the length
+//!   of a TV size song).
+//! - Each beatmapset's gameplay mode is picked using the mode weights, and a fraction of beatmapsets have Japanese
+//!   (katakana) artists and titles, with romanised versions.
+//!
+//! Names are made from random syllables, so they never match real artists, titles or players.
+
+use std::ops::Range;
+
+use flagset::FlagSet;
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    anonymise::splitmix,
+    beatmaps::{
+        BeatmapEntry, BeatmapListing, RankedStatus, StarRating, TimingPoint, UserPermissions,
+    },
+    common::{GameplayMode, Grade, Mods, WINDOWS_EPOCH},
+    paths::sanitize_filename,
+};
+
+/// osu! version of generated listings.
+pub const VERSION: u32 = 20250108;
+
+/// Number of beatmaps in the listing generated for benchmarks (see [`SyntheticOptions::benchmark`]), which is larger
+/// than almost every real library.
+pub const BENCHMARK_BEATMAPS: usize = 100_000;
+
+/// Syllables used for names, with their katakana.
+const SYLLABLES: [(&str, &str); 30] = [
+    ("a", "ア"),
+    ("i", "イ"),
+    ("ka", "カ"),
+    ("ki", "キ"),
+    ("ku", "ク"),
+    ("ko", "コ"),
+    ("sa", "サ"),
+    ("shi", "シ"),
+    ("su", "ス"),
+    ("ta", "タ"),
+    ("chi", "チ"),
+    ("to", "ト"),
+    ("na", "ナ"),
+    ("ni", "ニ"),
+    ("no", "ノ"),
+    ("ha", "ハ"),
+    ("hi", "ヒ"),
+    ("ma", "マ"),
+    ("mi", "ミ"),
+    ("mo", "モ"),
+    ("ya", "ヤ"),
+    ("yu", "ユ"),
+    ("yo", "ヨ"),
+    ("ra", "ラ"),
+    ("ri", "リ"),
+    ("ru", "ル"),
+    ("re", "レ"),
+    ("ro", "ロ"),
+    ("wa", "ワ"),
+    ("n", "ン"),
+];
+
+/// Words used for English titles.
+const TITLE_WORDS: [&str; 24] = [
+    "Night", "Sky", "Dream", "Light", "Star", "Memory", "Heart", "Rain", "Fire", "Blue", "Eternal",
+    "Echo", "Garden", "Paradise", "Drive", "World", "Crystal", "Shadow", "Summer", "Galaxy",
+    "Horizon", "Signal", "Bloom", "Frontier",
+];
+
+/// Words used for tags.
+const TAGS: [&str; 24] = [
+    "electronic",
+    "rock",
+    "pop",
+    "vocaloid",
+    "anime",
+    "instrumental",
+    "japanese",
+    "english",
+    "dubstep",
+    "hardcore",
+    "jazz",
+    "metal",
+    "piano",
+    "chiptune",
+    "orchestral",
+    "trance",
+    "speedcore",
+    "tech",
+    "stream",
+    "jumps",
+    "marathon",
+    "alternate",
+    "tournament",
+    "contest",
+];
+
+/// Names of the difficulties in each mode, from easiest to hardest, with the star rating that each starts at.
+const DIFFICULTY_NAMES: [[(&str, f64); 6]; 4] = [
+    [
+        ("Easy", 0.0),
+        ("Normal", 2.0),
+        ("Hard", 2.7),
+        ("Insane", 4.0),
+        ("Expert", 5.3),
+        ("Extra", 6.5),
+    ],
+    [
+        ("Kantan", 0.0),
+        ("Futsuu", 2.0),
+        ("Muzukashii", 2.7),
+        ("Oni", 4.0),
+        ("Inner Oni", 5.3),
+        ("Ura Oni", 6.5),
+    ],
+    [
+        ("Cup", 0.0),
+        ("Salad", 2.0),
+        ("Platter", 2.7),
+        ("Rain", 4.0),
+        ("Overdose", 5.3),
+        ("Deluge", 6.5),
+    ],
+    [
+        ("EZ", 0.0),
+        ("NM", 2.0),
+        ("HD", 2.7),
+        ("MX", 4.0),
+        ("SC", 5.3),
+        ("SHD", 6.5),
+    ],
+];
+
+/// Ranked statuses of beatmapsets, with how likely each is.
+const RANKED_STATUSES: [(RankedStatus, f64); 6] = [
+    (RankedStatus::Ranked, 0.55),
+    (RankedStatus::Loved, 0.08),
+    (RankedStatus::Approved, 0.03),
+    (RankedStatus::Qualified, 0.02),
+    (RankedStatus::Pending, 0.2),
+    (RankedStatus::Unsubmitted, 0.12),
+];
+
+/// Spread of star ratings, as the standard deviation of their logarithm.
+const STAR_SPREAD: f64 = 0.45;
+
+/// Spread of drain times, as a fraction of the mean.
+const LENGTH_SPREAD: f64 = 0.22;
+
+/// Represents the shape of a generated library.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyntheticOptions {
+    /// Seed for the generator; the same seed always gives the same library
+    pub seed: u64,
+
+    /// Number of beatmaps (difficulties) to generate
+    pub beatmaps: usize,
+
+    /// Median NoMod star rating
+    pub median_stars: f64,
+
+    /// Mean drain time, in seconds
+    pub mean_drain_time: f64,
+
+    /// Relative number of beatmapsets in osu!standard, osu!taiko, osu!catch and osu!mania
+    pub mode_weights: [f64; 4],
+
+    /// Fraction of beatmapsets with a Japanese artist and title
+    pub unicode_fraction: f64,
+}
+
+impl Default for SyntheticOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            beatmaps: 1000,
+            median_stars: 3.0,
+            mean_drain_time: 135.0,
+            mode_weights: [0.75, 0.07, 0.06, 0.12],
+            unicode_fraction: 0.3,
+        }
+    }
+}
+
+impl SyntheticOptions {
+    /// Options for benchmarks, with [`BENCHMARK_BEATMAPS`] beatmaps.
+    pub fn benchmark() -> Self {
+        Self {
+            beatmaps: BENCHMARK_BEATMAPS,
+            ..Default::default()
+        }
+    }
+}
+
+/// Generates beatmaps, in the order they'd be stored in `osu!.db` (i.e. each beatmapset's difficulties together).
+pub fn beatmaps(options: &SyntheticOptions) -> Vec<BeatmapEntry> {
+    let mut rng = Rng(options.seed);
+    let mut beatmaps = Vec::with_capacity(options.beatmaps);
+
+    while beatmaps.len() < options.beatmaps {
+        let remaining = options.beatmaps - beatmaps.len();
+        let count = (rng.range(1..7) as usize).min(remaining);
+        beatmaps.extend(beatmapset(&mut rng, options, count));
+    }
+
+    beatmaps
+}
+
+/// Generates a listing of beatmaps (see [`beatmaps`]).
+pub fn listing(options: &SyntheticOptions) -> BeatmapListing {
+    let beatmaps = beatmaps(options);
+    let mut rng = Rng(options.seed ^ 0x5eed);
+    let mut listing = BeatmapListing {
+        version: VERSION,
+        folder_count: 0,
+        account_unlocked: true,
+        account_unlock_date: WINDOWS_EPOCH,
+        player_name: Some(rng.name(2..4).0),
+        beatmaps,
+        user_permissions: UserPermissions::Normal | UserPermissions::Supporter,
+    };
+
+    listing.fix_folder_count();
+    listing
+}
+
+/// Generates a listing of beatmaps (see [`beatmaps`]), encoded as an `osu!.db` file.
+pub fn listing_bytes(options: &SyntheticOptions) -> Vec<u8> {
+    listing(options).to_bytes()
+}
+
+/// Generates a beatmapset with `count` difficulties.
+fn beatmapset(rng: &mut Rng, options: &SyntheticOptions, count: usize) -> Vec<BeatmapEntry> {
+    let mode = match rng.weighted(&options.mode_weights) {
+        0 => GameplayMode::Standard,
+        1 => GameplayMode::Taiko,
+        2 => GameplayMode::Catch,
+        _ => GameplayMode::Mania,
+    };
+
+    let (artist, artist_unicode, title, title_unicode) = if rng.chance(options.unicode_fraction) {
+        let (artist, artist_unicode) = rng.name(2..5);
+        let (title, title_unicode) = rng.name(2..6);
+        (artist, artist_unicode, title, title_unicode)
+    } else {
+        let artist = rng.name(2..5).0;
+        let title = (0..rng.range(1..4))
+            .map(|_| *rng.pick(&TITLE_WORDS))
+            .collect::<Vec<_>>()
+            .join(" ");
+        (artist.clone(), artist, title.clone(), title)
+    };
+
+    let creator = rng.name(2..4).0;
+    let ranked_status = RANKED_STATUSES[rng.weighted(&RANKED_STATUSES.map(|(_, weight)| weight))].0;
+    let submitted = ranked_status != RankedStatus::Unsubmitted;
+    let set_id = if submitted {
+        rng.range(1..2_400_000)
+    } else {
+        u32::MAX
+    };
+
+    let mut tags = Vec::new();
+    for _ in 0..rng.range(2..7) {
+        let tag = *rng.pick(&TAGS);
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    // Beatmaps from the same set were added at the same time, and are usually played together
+    let modified = rng.date(2014..2025);
+    let played = rng
+        .chance(0.6)
+        .then(|| modified + Duration::days(rng.range(0..600).into()));
+    let bpm = rng.normal(170.0, 25.0).clamp(60.0, 300.0).round();
+    let drain_time = rng
+        .normal(
+            options.mean_drain_time,
+            options.mean_drain_time * LENGTH_SPREAD,
+        )
+        .clamp(20.0, 900.0)
+        .round() as u32;
+    let keys = if rng.chance(0.6) { 4 } else { 7 };
+
+    let folder_name = if submitted {
+        format!("{} {} - {}", set_id, artist, title)
+    } else {
+        format!("{} - {}", artist, title)
+    };
+
+    let mut stars = (0..count)
+        .map(|_| {
+            rng.log_normal(options.median_stars, STAR_SPREAD)
+                .clamp(0.5, 12.0)
+        })
+        .collect::<Vec<_>>();
+    stars.sort_by(f64::total_cmp);
+
+    let mut names = Vec::<String>::new();
+
+    stars
+        .into_iter()
+        .enumerate()
+        .map(|(index, stars)| {
+            // Store ratings as they'd be read back, since this version stores them as singles
+            let stars = f64::from(stars as f32);
+            let mut name = DIFFICULTY_NAMES[mode as usize]
+                .iter()
+                .rev()
+                .find(|(_, start)| stars >= *start)
+                .map_or("Easy", |(name, _)| name)
+                .to_string();
+
+            if mode == GameplayMode::Mania {
+                name = format!("{}K {}", keys, name);
+            }
+
+            if names.contains(&name) {
+                name = format!("{}'s {}", rng.name(2..4).0, name);
+            }
+            names.push(name.clone());
+
+            let objects = (f64::from(drain_time) * (0.8 + stars * 0.7)).min(f64::from(u16::MAX));
+            let circles = (objects * rng.uniform_in(0.55..0.75)) as u16;
+            let sliders = objects as u16 - circles;
+            let (circle_size, approach_rate, overall_difficulty) = match mode {
+                GameplayMode::Mania => (keys as f32, 5.0, rng.uniform_in(7.0..8.5) as f32),
+                GameplayMode::Taiko => (2.0, 5.0, (3.0 + stars * 0.9).min(10.0) as f32),
+                _ => {
+                    let approach_rate =
+                        (3.5 + stars * 1.1 + rng.uniform_in(-0.3..0.3)).clamp(0.0, 10.0);
+                    (
+                        rng.uniform_in(3.2..4.8) as f32,
+                        approach_rate as f32,
+                        (approach_rate - 1.0).max(0.0) as f32,
+                    )
+                }
+            };
+
+            let mut star_ratings = vec![
+                star_rating(Mods::none(), stars),
+                star_rating(Mods::DoubleTime.into(), stars * 1.38),
+                star_rating(Mods::HalfTime.into(), stars * 0.78),
+            ];
+            if matches!(mode, GameplayMode::Standard | GameplayMode::Catch) {
+                star_ratings.push(star_rating(Mods::HardRock.into(), stars * 1.09));
+            }
+
+            let ratings_for = |m: GameplayMode| {
+                Some(if m == mode {
+                    star_ratings.clone()
+                } else {
+                    Vec::new()
+                })
+            };
+            let total_time =
+                (f64::from(drain_time) * rng.uniform_in(1.05..1.25)) as u32 * 1000 + 2000;
+            let grade = match played {
+                Some(_) => *rng.pick(&[
+                    Grade::SS,
+                    Grade::S,
+                    Grade::S,
+                    Grade::A,
+                    Grade::A,
+                    Grade::B,
+                    Grade::C,
+                ]),
+                None => Grade::Unplayed,
+            };
+
+            BeatmapEntry {
+                artist_name: Some(artist.clone()),
+                artist_name_unicode: Some(artist_unicode.clone()),
+                song_title: Some(title.clone()),
+                song_title_unicode: Some(title_unicode.clone()),
+                creator_name: Some(creator.clone()),
+                difficulty: Some(name.clone()),
+                audio_filename: Some("audio.mp3".to_string()),
+                md5: Some(format!("{:016x}{:016x}", rng.next(), rng.next())),
+                beatmap_filename: Some(sanitize_filename(&format!(
+                    "{} - {} ({}) [{}].osu",
+                    artist, title, creator, name
+                ))),
+                ranked_status,
+                hitcircle_count: circles,
+                slider_count: sliders,
+                spinner_count: rng.range(0..3) as u16,
+                last_modification_time: modified,
+                approach_rate: round_setting(approach_rate),
+                circle_size: round_setting(circle_size),
+                hp_drain: round_setting((2.5 + stars * 0.6).min(10.0) as f32),
+                overall_difficulty: round_setting(overall_difficulty),
+                slider_velocity: f64::from(round_setting(rng.uniform_in(1.0..2.6) as f32)),
+                star_ratings_std: ratings_for(GameplayMode::Standard),
+                star_ratings_taiko: ratings_for(GameplayMode::Taiko),
+                star_ratings_ctb: ratings_for(GameplayMode::Catch),
+                star_ratings_mania: ratings_for(GameplayMode::Mania),
+                drain_time,
+                total_time,
+                audio_preview_time: total_time * 2 / 5,
+                timing_points: vec![TimingPoint {
+                    bpm: 60_000.0 / bpm,
+                    song_offset: f64::from(rng.range(0..2000)),
+                    inherited: true,
+                }],
+                difficulty_id: if submitted {
+                    set_id * 2 + index as u32
+                } else {
+                    u32::MAX
+                },
+                beatmap_id: set_id,
+                grade_std: if mode == GameplayMode::Standard {
+                    grade
+                } else {
+                    Grade::Unplayed
+                },
+                grade_taiko: if mode == GameplayMode::Taiko {
+                    grade
+                } else {
+                    Grade::Unplayed
+                },
+                grade_catch: if mode == GameplayMode::Catch {
+                    grade
+                } else {
+                    Grade::Unplayed
+                },
+                grade_mania: if mode == GameplayMode::Mania {
+                    grade
+                } else {
+                    Grade::Unplayed
+                },
+                stack_leniency: 0.7,
+                gameplay_mode: mode,
+                song_source: Some(String::new()),
+                song_tags: Some(tags.join(" ")),
+                font: Some(String::new()),
+                is_unplayed: played.is_none(),
+                last_played: played.unwrap_or(WINDOWS_EPOCH),
+                folder_name: Some(sanitize_filename(&folder_name)),
+                last_checked_online: if submitted {
+                    modified + Duration::days(1)
+                } else {
+                    WINDOWS_EPOCH
+                },
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn star_rating(mods: FlagSet<Mods>, rating: f64) -> StarRating {
+    StarRating {
+        mods,
+        rating: f64::from(rating as f32),
+    }
+}
+
+/// Rounds a difficulty setting to one decimal place, like the editor does.
+fn round_setting(value: f32) -> f32 {
+    (value * 10.0).round() / 10.0
+}
+
+/// A small pseudo-random number generator, so that the same seed gives the same library in every build.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        splitmix(&mut self.0)
+    }
+
+    /// Gets a number in `[0, 1)`.
+    fn uniform(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn uniform_in(&mut self, range: Range<f64>) -> f64 {
+        range.start + (range.end - range.start) * self.uniform()
+    }
+
+    fn range(&mut self, range: Range<u32>) -> u32 {
+        range.start + (self.next() % u64::from(range.end - range.start)) as u32
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        self.uniform() < probability
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0..items.len() as u32) as usize]
+    }
+
+    /// Picks an index with probability proportional to its weight.
+    fn weighted(&mut self, weights: &[f64]) -> usize {
+        let mut target = self.uniform() * weights.iter().sum::<f64>();
+
+        for (index, weight) in weights.iter().enumerate() {
+            if target < *weight {
+                return index;
+            }
+            target -= weight;
+        }
+
+        weights.len() - 1
+    }
+
+    /// Gets a number from a normal distribution, using the Box-Muller transform.
+    fn normal(&mut self, mean: f64, deviation: f64) -> f64 {
+        let u = 1.0 - self.uniform();
+        let v = self.uniform();
+        mean + deviation * (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+    }
+
+    fn log_normal(&mut self, median: f64, spread: f64) -> f64 {
+        self.normal(median.ln(), spread).exp()
+    }
+
+    /// Gets a time in a range of years, in whole seconds.
+    fn date(&mut self, years: Range<i32>) -> OffsetDateTime {
+        let start = time::Date::from_ordinal_date(years.start, 1)
+            .unwrap()
+            .midnight()
+            .assume_utc();
+        let seconds = (years.end - years.start) as u32 * 365 * 24 * 60 * 60;
+        start + Duration::seconds(self.range(0..seconds).into())
+    }
+
+    /// Makes a name from random syllables, e.g. "Kamiri", along with its katakana.
+    fn name(&mut self, syllables: Range<u32>) -> (String, String) {
+        let mut name = String::new();
+        let mut katakana = String::new();
+
+        for _ in 0..self.range(syllables) {
+            let (romaji, kana) = self.pick(&SYLLABLES);
+            name.push_str(romaji);
+            katakana.push_str(kana);
+        }
+
+        let mut chars = name.chars();
+        let name = chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default();
+
+        (name, katakana)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn median(mut values: Vec<f64>) -> f64 {
+        values.sort_by(f64::total_cmp);
+        values[values.len() / 2]
+    }
+
+    fn fraction<T>(items: &[T], predicate: impl Fn(&T) -> bool) -> f64 {
+        items.iter().filter(|item| predicate(item)).count() as f64 / items.len() as f64
+    }
+
+    #[test]
+    fn generated_listings_parse() {
+        let options = SyntheticOptions {
+            seed: 7,
+            beatmaps: 500,
+            ..Default::default()
+        };
+        let listing = listing(&options);
+
+        assert_eq!(listing.beatmaps.len(), 500);
+        assert_eq!(
+            BeatmapListing::from_bytes(&listing_bytes(&options)).unwrap(),
+            listing
+        );
+        assert!(listing.folder_count_mismatch().is_none());
+
+        // The same seed always gives the same library
+        assert_eq!(beatmaps(&options), listing.beatmaps);
+        assert_ne!(
+            beatmaps(&SyntheticOptions { seed: 8, ..options }),
+            listing.beatmaps
+        );
+    }
+
+    #[test]
+    fn distributions_match_options() {
+        let options = SyntheticOptions {
+            beatmaps: 5000,
+            ..Default::default()
+        };
+        let beatmaps = beatmaps(&options);
+
+        let stars = beatmaps
+            .iter()
+            .map(|b| b.nomod_star_rating().unwrap())
+            .collect::<Vec<_>>();
+        let median_stars = median(stars.clone());
+        let mean_stars = stars.iter().sum::<f64>() / stars.len() as f64;
+        assert!((median_stars - 3.0).abs() < 0.3, "median {}", median_stars);
+        assert!(
+            mean_stars > median_stars,
+            "star ratings should be skewed low"
+        );
+
+        let tv_size = fraction(&beatmaps, |b| (90..=180).contains(&b.drain_time));
+        assert!(tv_size > 0.75, "{} between 90-180s", tv_size);
+
+        let modes = [
+            GameplayMode::Standard,
+            GameplayMode::Taiko,
+            GameplayMode::Catch,
+            GameplayMode::Mania,
+        ];
+        for (mode, weight) in modes.into_iter().zip(options.mode_weights) {
+            let actual = fraction(&beatmaps, |b| b.gameplay_mode == mode);
+            assert!((actual - weight).abs() < 0.06, "{:?}: {}", mode, actual);
+        }
+
+        let unicode = fraction(&beatmaps, |b| b.song_title_unicode != b.song_title);
+        assert!((unicode - 0.3).abs() < 0.06, "{} unicode", unicode);
+        assert!(beatmaps
+            .iter()
+            .all(|b| b.song_tags.as_deref().is_some_and(|t| !t.is_empty())));
+    }
+
+    #[test]
+    fn options_change_distributions() {
+        let beatmaps = beatmaps(&SyntheticOptions {
+            beatmaps: 2000,
+            median_stars: 5.0,
+            mean_drain_time: 300.0,
+            mode_weights: [0.0, 0.0, 0.0, 1.0],
+            unicode_fraction: 0.0,
+            ..Default::default()
+        });
+
+        let stars = median(
+            beatmaps
+                .iter()
+                .map(|b| b.nomod_star_rating().unwrap())
+                .collect(),
+        );
+        let drain = median(beatmaps.iter().map(|b| f64::from(b.drain_time)).collect());
+        assert!((stars - 5.0).abs() < 0.5, "median {}", stars);
+        assert!((drain - 300.0).abs() < 30.0, "median {}s", drain);
+        assert!(beatmaps
+            .iter()
+            .all(|b| b.gameplay_mode == GameplayMode::Mania));
+        assert!(beatmaps
+            .iter()
+            .all(|b| b.song_title_unicode == b.song_title));
+    }
+}
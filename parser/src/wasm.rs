@@ -0,0 +1,80 @@
+//! Bindings for using the parser from JavaScript, when it's compiled to WebAssembly with `wasm-pack` and the `wasm`
+//! feature.
+//!
+//! Listings are converted to plain JavaScript objects using the same field names as the Rust types. Dates are
+//! represented as `[year, ordinal day, hour, minute, second, nanosecond, offset hours, offset minutes, offset seconds]`
+//! arrays, which is how the `time` crate serializes them.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{beatmaps::BeatmapEntry, beatmaps::BeatmapListing, query::BeatmapFilter};
+
+/// Parses the contents of an `osu!.db` file, returning the listing as an object.
+#[wasm_bindgen]
+pub fn parse_osu_db(bytes: &[u8]) -> Result<JsValue, JsError> {
+    let listing = BeatmapListing::from_bytes(bytes)?;
+    to_js(&listing)
+}
+
+/// Finds the beatmaps matching a search query, using the same syntax as osu!'s song select (e.g. `stars>5 mode=mania`).
+///
+/// `beatmaps` is an array of beatmaps returned by [`parse_osu_db`]. The matching beatmaps are returned in the same
+/// order.
+#[wasm_bindgen]
+pub fn filter_beatmaps(beatmaps: JsValue, query: &str) -> Result<JsValue, JsError> {
+    let filter = BeatmapFilter::parse(query)?;
+    let beatmaps: Vec<BeatmapEntry> = serde_wasm_bindgen::from_value(beatmaps)?;
+
+    let matching = beatmaps
+        .into_iter()
+        .filter(|beatmap| filter.matches(beatmap))
+        .collect::<Vec<_>>();
+
+    to_js(&matching)
+}
+
+/// Converts a value to a plain JavaScript object. Large integers are converted to numbers rather than `BigInt`s, and
+/// maps to objects rather than `Map`s, so that the result can be passed to `JSON.stringify`.
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(JsError::from)
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    const OSU_DB: &[u8] = include_bytes!("../testdata/osu.db");
+
+    #[wasm_bindgen_test]
+    fn fixture_is_parsed() {
+        let value = parse_osu_db(OSU_DB).unwrap();
+        let listing: BeatmapListing = serde_wasm_bindgen::from_value(value).unwrap();
+
+        assert_eq!(listing, BeatmapListing::from_bytes(OSU_DB).unwrap());
+        assert_eq!(listing.player_name.as_deref(), Some("Player"));
+        assert_eq!(listing.beatmaps.len(), 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn beatmaps_are_filtered() {
+        let value = parse_osu_db(OSU_DB).unwrap();
+        let beatmaps = js_sys::Reflect::get(&value, &"beatmaps".into()).unwrap();
+
+        let matching: Vec<BeatmapEntry> =
+            serde_wasm_bindgen::from_value(filter_beatmaps(beatmaps, "artist=camellia").unwrap())
+                .unwrap();
+
+        assert_eq!(matching.len(), 1);
+        assert_eq!(matching[0].artist_name.as_deref(), Some("Camellia"));
+    }
+
+    #[wasm_bindgen_test]
+    fn invalid_files_are_rejected() {
+        assert!(parse_osu_db(b"not a database").is_err());
+    }
+}
@@ -0,0 +1,384 @@
+//! Working with several beatmap listings at once, e.g. the `osu!.db` files from a desktop and a laptop, while keeping
+//! track of which listing each beatmap came from.
+//!
+//! Beatmaps are matched between listings using their MD5 hashes, the same as [`ListingDiff`](crate::diff::ListingDiff),
+//! so beatmaps without an MD5 hash are only included when every beatmap of a listing is asked for.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    beatmaps::{BeatmapEntry, BeatmapListing},
+    query::BeatmapFilter,
+};
+
+/// Identifies one of the listings in a [`Workspace`]. IDs are given out in the order listings are added.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceId(usize);
+
+/// Represents a beatmap listing in a [`Workspace`].
+#[derive(Clone, Debug)]
+pub struct Source {
+    /// Name shown for the listing, e.g. `Laptop`
+    pub name: String,
+
+    pub listing: Arc<BeatmapListing>,
+
+    /// Limits which of the listing's beatmaps are included, e.g. to only compare ranked beatmaps
+    pub filter: Option<BeatmapFilter>,
+}
+
+/// Represents several named beatmap listings, which can be combined and compared.
+#[derive(Clone, Debug, Default)]
+pub struct Workspace {
+    sources: Vec<Source>,
+}
+
+impl SourceId {
+    /// Gets the position of the listing in the order listings were added to the workspace.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl Source {
+    /// Gets the beatmaps in this listing which match its filter, in file order.
+    pub fn beatmaps(&self) -> impl Iterator<Item = &BeatmapEntry> {
+        self.listing
+            .beatmaps
+            .iter()
+            .filter(|beatmap| self.filter.as_ref().is_none_or(|f| f.matches(beatmap)))
+    }
+}
+
+impl Workspace {
+    /// Creates an empty workspace.
+    pub fn new() -> Workspace {
+        Workspace::default()
+    }
+
+    /// Adds a listing to the workspace, returning its ID.
+    pub fn add<S: Into<String>, L: Into<Arc<BeatmapListing>>>(
+        &mut self,
+        name: S,
+        listing: L,
+    ) -> SourceId {
+        self.sources.push(Source {
+            name: name.into(),
+            listing: listing.into(),
+            filter: None,
+        });
+
+        SourceId(self.sources.len() - 1)
+    }
+
+    /// Gets the number of listings in the workspace.
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Checks whether the workspace has no listings.
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Gets the IDs of every listing, in the order they were added.
+    pub fn ids(&self) -> Vec<SourceId> {
+        (0..self.sources.len()).map(SourceId).collect()
+    }
+
+    /// Gets a listing in the workspace.
+    pub fn source(&self, id: SourceId) -> Option<&Source> {
+        self.sources.get(id.0)
+    }
+
+    /// Gets a listing in the workspace for changing its name or filter.
+    pub fn source_mut(&mut self, id: SourceId) -> Option<&mut Source> {
+        self.sources.get_mut(id.0)
+    }
+
+    /// Gets every listing with its ID, in the order they were added.
+    pub fn sources(&self) -> impl Iterator<Item = (SourceId, &Source)> {
+        self.sources
+            .iter()
+            .enumerate()
+            .map(|(i, source)| (SourceId(i), source))
+    }
+
+    /// Finds a listing by its name.
+    pub fn find(&self, name: &str) -> Option<SourceId> {
+        self.sources
+            .iter()
+            .position(|source| source.name == name)
+            .map(SourceId)
+    }
+
+    /// Gets the name of a listing, or an empty string if it isn't in the workspace.
+    pub fn name(&self, id: SourceId) -> &str {
+        self.source(id).map_or("", |source| &source.name)
+    }
+
+    /// Sets the filter of a listing, or removes it if `filter` is `None` or matches every beatmap.
+    pub fn set_filter(&mut self, id: SourceId, filter: Option<BeatmapFilter>) {
+        if let Some(source) = self.source_mut(id) {
+            source.filter = filter.filter(|f| !f.is_empty());
+        }
+    }
+
+    /// Gets every beatmap in every listing which matches its listing's filter, along with the listing it came from.
+    /// Beatmaps in more than one listing are included once for each listing.
+    pub fn entries(&self) -> impl Iterator<Item = (SourceId, &BeatmapEntry)> {
+        self.sources()
+            .flat_map(|(id, source)| source.beatmaps().map(move |beatmap| (id, beatmap)))
+    }
+
+    /// Finds which of the listings each beatmap is in, by MD5 hash. Each beatmap's listings are in the order they were
+    /// added.
+    pub fn presence(&self) -> HashMap<&str, Vec<SourceId>> {
+        let mut presence = HashMap::<&str, Vec<SourceId>>::new();
+
+        for (id, beatmap) in self.entries() {
+            let Some(md5) = beatmap.md5.as_deref() else {
+                continue;
+            };
+
+            let sources = presence.entry(md5).or_default();
+
+            if sources.last() != Some(&id) {
+                sources.push(id);
+            }
+        }
+
+        presence
+    }
+
+    /// Gets the beatmaps which are in any of the listings, with each beatmap included once. A beatmap in more than one
+    /// listing is taken from the first of `ids` it's in.
+    pub fn union(&self, ids: &[SourceId]) -> Vec<(SourceId, &BeatmapEntry)> {
+        let mut seen = HashSet::new();
+
+        self.entries_of(ids.iter().copied())
+            .filter(|(_, beatmap)| beatmap.md5.as_deref().is_some_and(|md5| seen.insert(md5)))
+            .collect()
+    }
+
+    /// Gets the beatmaps which are in every one of the listings, with each beatmap included once. Beatmaps are taken
+    /// from the first of `ids`.
+    pub fn intersection(&self, ids: &[SourceId]) -> Vec<(SourceId, &BeatmapEntry)> {
+        let Some((&first, rest)) = ids.split_first() else {
+            return Vec::new();
+        };
+
+        let others = rest.iter().map(|&id| self.md5s(id)).collect::<Vec<_>>();
+        let mut seen = HashSet::new();
+
+        self.entries_of([first])
+            .filter(|(_, beatmap)| {
+                beatmap.md5.as_deref().is_some_and(|md5| {
+                    others.iter().all(|md5s| md5s.contains(md5)) && seen.insert(md5)
+                })
+            })
+            .collect()
+    }
+
+    /// Gets the beatmaps which are in one listing but none of the others, e.g. the beatmaps on a laptop which aren't on
+    /// a desktop. Each beatmap is included once.
+    pub fn difference(&self, id: SourceId, others: &[SourceId]) -> Vec<(SourceId, &BeatmapEntry)> {
+        let others = others
+            .iter()
+            .flat_map(|&other| self.md5s(other))
+            .collect::<HashSet<_>>();
+        let mut seen = HashSet::new();
+
+        self.entries_of([id])
+            .filter(|(_, beatmap)| {
+                beatmap
+                    .md5
+                    .as_deref()
+                    .is_some_and(|md5| !others.contains(md5) && seen.insert(md5))
+            })
+            .collect()
+    }
+
+    /// Gets the beatmaps of some listings which match their filters, in the order of `ids`.
+    fn entries_of<I: IntoIterator<Item = SourceId>>(
+        &self,
+        ids: I,
+    ) -> impl Iterator<Item = (SourceId, &BeatmapEntry)> {
+        ids.into_iter()
+            .filter_map(|id| Some((id, self.source(id)?)))
+            .flat_map(|(id, source)| source.beatmaps().map(move |beatmap| (id, beatmap)))
+    }
+
+    /// Gets the MD5 hashes of a listing's beatmaps which match its filter.
+    fn md5s(&self, id: SourceId) -> HashSet<&str> {
+        self.entries_of([id])
+            .filter_map(|(_, beatmap)| beatmap.md5.as_deref())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        beatmaps::{RankedStatus, UserPermissions},
+        common::WINDOWS_EPOCH,
+    };
+
+    fn listing(md5s: &[&str]) -> BeatmapListing {
+        let beatmaps = md5s
+            .iter()
+            .map(|md5| BeatmapEntry {
+                md5: Some(md5.to_string()),
+                song_title: Some(md5.to_uppercase()),
+                ranked_status: if md5.len() > 1 {
+                    RankedStatus::Ranked
+                } else {
+                    RankedStatus::Pending
+                },
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        BeatmapListing {
+            version: 20250108,
+            folder_count: beatmaps.len() as u32,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: None,
+            beatmaps,
+            user_permissions: UserPermissions::Normal.into(),
+        }
+    }
+
+    /// Makes a workspace with a desktop, a laptop and a backup, which have some beatmaps in common.
+    fn workspace() -> (Workspace, [SourceId; 3]) {
+        let mut workspace = Workspace::new();
+        let desktop = workspace.add("Desktop", listing(&["a", "bb", "c", "dd"]));
+        let laptop = workspace.add("Laptop", listing(&["bb", "dd", "e", "e", "ff"]));
+
+        let mut backup = listing(&["a", "bb", "ff"]);
+        backup.beatmaps.push(BeatmapEntry::default());
+        let backup = workspace.add("Backup", backup);
+
+        (workspace, [desktop, laptop, backup])
+    }
+
+    fn md5s(entries: &[(SourceId, &BeatmapEntry)]) -> Vec<(usize, String)> {
+        entries
+            .iter()
+            .map(|(id, beatmap)| (id.index(), beatmap.md5.clone().unwrap_or_default()))
+            .collect()
+    }
+
+    fn pairs(pairs: &[(usize, &str)]) -> Vec<(usize, String)> {
+        pairs.iter().map(|&(i, md5)| (i, md5.to_string())).collect()
+    }
+
+    #[test]
+    fn sources_are_named() {
+        let (workspace, [desktop, laptop, backup]) = workspace();
+
+        assert_eq!(workspace.len(), 3);
+        assert_eq!(workspace.ids(), vec![desktop, laptop, backup]);
+        assert_eq!(workspace.find("Laptop"), Some(laptop));
+        assert_eq!(workspace.find("Phone"), None);
+        assert_eq!(workspace.name(backup), "Backup");
+
+        // Every beatmap is included, even without an MD5 hash or when listed twice
+        assert_eq!(workspace.entries().count(), 13);
+    }
+
+    #[test]
+    fn union_includes_each_beatmap_once() {
+        let (workspace, [desktop, laptop, backup]) = workspace();
+
+        assert_eq!(
+            md5s(&workspace.union(&[desktop, laptop, backup])),
+            pairs(&[
+                (0, "a"),
+                (0, "bb"),
+                (0, "c"),
+                (0, "dd"),
+                (1, "e"),
+                (1, "ff")
+            ])
+        );
+
+        // Beatmaps come from the first listing they're in, in the order given
+        assert_eq!(
+            md5s(&workspace.union(&[backup, laptop])),
+            pairs(&[(2, "a"), (2, "bb"), (2, "ff"), (1, "dd"), (1, "e")])
+        );
+    }
+
+    #[test]
+    fn intersection_includes_beatmaps_in_every_source() {
+        let (workspace, [desktop, laptop, backup]) = workspace();
+
+        assert_eq!(
+            md5s(&workspace.intersection(&[desktop, laptop, backup])),
+            pairs(&[(0, "bb")])
+        );
+        assert_eq!(
+            md5s(&workspace.intersection(&[laptop, desktop])),
+            pairs(&[(1, "bb"), (1, "dd")])
+        );
+        assert!(workspace.intersection(&[]).is_empty());
+    }
+
+    #[test]
+    fn difference_excludes_beatmaps_in_other_sources() {
+        let (workspace, [desktop, laptop, backup]) = workspace();
+
+        assert_eq!(
+            md5s(&workspace.difference(laptop, &[desktop])),
+            pairs(&[(1, "e"), (1, "ff")])
+        );
+        assert_eq!(
+            md5s(&workspace.difference(laptop, &[desktop, backup])),
+            pairs(&[(1, "e")])
+        );
+        assert_eq!(
+            md5s(&workspace.difference(desktop, &[])),
+            pairs(&[(0, "a"), (0, "bb"), (0, "c"), (0, "dd")])
+        );
+    }
+
+    #[test]
+    fn presence_lists_each_source_once() {
+        let (workspace, [desktop, laptop, backup]) = workspace();
+        let presence = workspace.presence();
+
+        assert_eq!(presence.len(), 6);
+        assert_eq!(presence["bb"], vec![desktop, laptop, backup]);
+        assert_eq!(presence["e"], vec![laptop]);
+        assert_eq!(presence["ff"], vec![laptop, backup]);
+    }
+
+    #[test]
+    fn filters_apply_to_their_own_source() {
+        let (mut workspace, [desktop, laptop, backup]) = workspace();
+
+        // Only compare the laptop's ranked beatmaps
+        workspace.set_filter(laptop, Some(BeatmapFilter::parse("status=ranked").unwrap()));
+
+        assert_eq!(
+            md5s(&workspace.difference(laptop, &[desktop])),
+            pairs(&[(1, "ff")])
+        );
+        assert_eq!(
+            md5s(&workspace.union(&[laptop, backup])),
+            pairs(&[(1, "bb"), (1, "dd"), (1, "ff"), (2, "a")])
+        );
+        assert!(!workspace.presence().contains_key("e"));
+
+        // An empty filter is the same as no filter
+        workspace.set_filter(laptop, Some(BeatmapFilter::parse("").unwrap()));
+        assert!(workspace.source(laptop).unwrap().filter.is_none());
+        assert!(workspace.presence().contains_key("e"));
+    }
+}
@@ -0,0 +1,99 @@
+//! Runs the example programs against the fixture listing, so that they keep building and working as the library
+//! changes. Each example is included as a module, and its entry point is called with temporary output paths.
+
+#![cfg(feature = "tools")]
+
+#[allow(dead_code)]
+#[path = "../examples/export_stats.rs"]
+mod export_stats;
+
+#[allow(dead_code)]
+#[path = "../examples/find_duplicates.rs"]
+mod find_duplicates;
+
+#[allow(dead_code)]
+#[path = "../examples/make_collection.rs"]
+mod make_collection;
+
+use std::path::{Path, PathBuf};
+
+use osu_db_parser::prelude::*;
+
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/osu.db")
+}
+
+/// Gets a path for an example's output, removing anything left over from a previous run.
+fn output(name: &str) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::remove_file(&path).ok();
+    path
+}
+
+#[test]
+fn find_duplicates_removes_repeated_entries() {
+    let mut listing = BeatmapListing::from_file(fixture()).unwrap();
+    let original = listing.clone();
+    listing.beatmaps.push(listing.beatmaps[0].clone());
+
+    let input = output("duplicates.osu.db");
+    listing.to_file(&input).unwrap();
+
+    let cleaned = output("duplicates.cleaned.osu.db");
+    let report = find_duplicates::find_duplicates(&input, &cleaned).unwrap();
+
+    assert_eq!(report.duplicates.len(), 1);
+    assert_eq!(report.duplicates[0].1, 2);
+    assert_eq!(report.removed, 1);
+    assert_eq!(report.remaining, original.beatmaps.len());
+
+    let cleaned = BeatmapListing::from_file(&cleaned).unwrap();
+    assert_eq!(cleaned.beatmaps, original.beatmaps);
+}
+
+#[test]
+fn make_collection_adds_matching_beatmaps() {
+    let listing = BeatmapListing::from_file(fixture()).unwrap();
+    let path = output("examples.collection.db");
+
+    let count =
+        make_collection::make_collection(&fixture(), "camellia", "Camellia", &path).unwrap();
+    assert!(count > 0);
+
+    // Making a collection with the same name again replaces it, while others are kept
+    make_collection::make_collection(&fixture(), "", "Everything", &path).unwrap();
+    make_collection::make_collection(&fixture(), "camellia", "Camellia", &path).unwrap();
+
+    let collections = CollectionListing::from_file(&path).unwrap();
+    let names = collections
+        .collections
+        .iter()
+        .map(|collection| collection.name.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Everything", "Camellia"]);
+    assert_eq!(
+        collections.collections[0],
+        Collection::from_beatmaps("Everything".to_string(), &listing.beatmaps)
+    );
+    assert_eq!(collections.collections[1].beatmap_md5s.len(), count);
+
+    assert!(matches!(
+        make_collection::make_collection(&fixture(), "stars>hard", "Broken", &path),
+        Err(Error::Query(_))
+    ));
+}
+
+#[test]
+fn export_stats_writes_hardest_beatmaps() {
+    let listing = BeatmapListing::from_file(fixture()).unwrap();
+    let path = output("examples.hardest.csv");
+
+    let (stats, exported) = export_stats::export_stats(&fixture(), &path).unwrap();
+    assert_eq!(stats.total, listing.beatmaps.len());
+    assert_eq!(exported, listing.beatmaps.len());
+
+    let csv = std::fs::read_to_string(&path).unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), exported + 1);
+    assert!(lines[0].starts_with("Artist,Title,Difficulty"));
+}
@@ -0,0 +1,64 @@
+//! Checks that the default `parser` feature on its own only depends on the crates needed to parse `osu!.db`. The
+//! optional modules being left out is checked by the `feature_checks` doctests in `lib.rs`.
+
+use std::{collections::BTreeSet, process::Command};
+
+/// Crates that a build with only the `parser` feature may depend on, besides this one.
+const ALLOWED: [&str; 10] = [
+    "nom",
+    "memchr",
+    "minimal-lexical",
+    "time",
+    "deranged",
+    "powerfmt",
+    "num-conv",
+    "time-core",
+    "flagset",
+    "thiserror",
+];
+
+#[test]
+fn parser_feature_only_depends_on_nom_and_time() {
+    let output = Command::new(env!("CARGO"))
+        .args(["tree", "--manifest-path"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .args([
+            "--no-default-features",
+            "--features",
+            "parser",
+            "--edges",
+            "normal,no-proc-macro",
+            "--prefix",
+            "none",
+            "--format",
+            "{p}",
+        ])
+        .output()
+        .expect("unable to run cargo tree");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let dependencies = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|name| *name != env!("CARGO_PKG_NAME"))
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>();
+
+    let unexpected = dependencies
+        .iter()
+        .filter(|name| !ALLOWED.contains(&name.as_str()))
+        .collect::<Vec<_>>();
+
+    assert!(dependencies.contains("nom"), "{:?}", dependencies);
+    assert!(dependencies.contains("time"), "{:?}", dependencies);
+    assert!(
+        unexpected.is_empty(),
+        "unexpected dependencies: {:?}",
+        unexpected
+    );
+}
@@ -0,0 +1,57 @@
+//! Compiles and runs `tests/ffi/main.c` against the C API, using the C compiler from `$CC` (or `cc`).
+//!
+//! When the compiler supports it, the program is built with AddressSanitizer, so that leaks and invalid accesses on
+//! either side of the API fail the test.
+
+#![cfg(feature = "ffi")]
+
+use std::{path::PathBuf, process::Command};
+
+#[test]
+fn c_program_reads_fixture() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    // The library is built next to the test binary, in target/<profile>/deps
+    let library_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    let program = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("osu_db_ffi_test");
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+
+    let compile = |extra_args: &[&str]| {
+        Command::new(&compiler)
+            .args(extra_args)
+            .arg(manifest_dir.join("tests/ffi/main.c"))
+            .arg("-I")
+            .arg(manifest_dir.join("include"))
+            .arg("-L")
+            .arg(&library_dir)
+            .args(["-losu_db_parser", "-lm", "-o"])
+            .arg(&program)
+            .status()
+            .is_ok_and(|status| status.success())
+    };
+
+    assert!(
+        compile(&["-fsanitize=address", "-g"]) || compile(&[]),
+        "unable to compile tests/ffi/main.c with {}",
+        compiler
+    );
+
+    // Cargo adds target/<profile> to the library path, which may have a library built without the `ffi` feature
+    let output = Command::new(&program)
+        .arg(manifest_dir.join("testdata/osu.db"))
+        .env("LD_LIBRARY_PATH", &library_dir)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "ok");
+}
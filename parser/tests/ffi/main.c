@@ -0,0 +1,78 @@
+// Reads the osu!.db file given as the first argument through the C API, checking the results against the fixture in
+// testdata/osu.db. Run by tests/ffi.rs.
+
+#include <assert.h>
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "osu_db.h"
+
+static uint8_t *read_file(const char *path, size_t *len) {
+    FILE *file = fopen(path, "rb");
+    assert(file != NULL);
+
+    fseek(file, 0, SEEK_END);
+    *len = (size_t)ftell(file);
+    fseek(file, 0, SEEK_SET);
+
+    uint8_t *data = malloc(*len);
+    assert(data != NULL);
+    assert(fread(data, 1, *len, file) == *len);
+    fclose(file);
+
+    return data;
+}
+
+static const char *field(const OsuDbHandle *handle, size_t index, OsuDbField field) {
+    const char *value = NULL;
+    assert(osudb_beatmap_string(handle, index, field, &value) == OSU_DB_STATUS_OK);
+    return value;
+}
+
+int main(int argc, char **argv) {
+    assert(argc == 2);
+
+    size_t len;
+    uint8_t *data = read_file(argv[1], &len);
+
+    OsuDbHandle *handle = NULL;
+    OsuDbStatus status = osudb_parse(data, len, &handle);
+
+    // The handle doesn't borrow the input
+    free(data);
+
+    if (status != OSU_DB_STATUS_OK) {
+        fprintf(stderr, "unable to parse %s: %s\n", argv[1], osudb_last_error_message());
+        return 1;
+    }
+
+    assert(osudb_version(handle) == 20211031);
+    assert(strcmp(osudb_player_name(handle), "Player") == 0);
+    assert(osudb_beatmap_count(handle) == 2);
+
+    assert(strcmp(field(handle, 0, OSU_DB_FIELD_ARTIST), "Camellia") == 0);
+    assert(strcmp(field(handle, 0, OSU_DB_FIELD_ARTIST_UNICODE), "\xe3\x81\x8b\xe3\x82\x81\xe3\x82\x8a\xe3\x81\x82") == 0);
+    assert(field(handle, 1, OSU_DB_FIELD_TITLE) == NULL);
+
+    OsuDbBeatmapInfo info;
+    assert(osudb_beatmap_info(handle, 0, &info) == OSU_DB_STATUS_OK);
+    assert(info.ranked_status == 7);
+    assert(info.hitcircle_count == 500);
+    assert(info.total_time == 200000);
+    assert(isnan(info.star_rating));
+
+    assert(osudb_beatmap_info(handle, 2, &info) == OSU_DB_STATUS_OUT_OF_RANGE);
+    assert(osudb_last_error_message() != NULL);
+
+    osudb_free(handle);
+
+    // Invalid files are rejected without a handle being created
+    const uint8_t invalid[] = "not a database";
+    assert(osudb_parse(invalid, sizeof(invalid), &handle) == OSU_DB_STATUS_INVALID_DATA);
+    assert(handle == NULL);
+
+    puts("ok");
+    return 0;
+}
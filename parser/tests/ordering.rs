@@ -0,0 +1,174 @@
+//! Checks that beatmaps are always parsed in file order, whether the entries are parsed on one thread or several, for
+//! each range of versions that stores beatmaps differently.
+//!
+//! The listings are built and then encoded, so this needs the `writer` feature.
+
+#![cfg(feature = "writer")]
+
+use osu_db_parser::{
+    beatmaps::{
+        BeatmapEntry, BeatmapListing, ParseOptions, PartialListing, StarRating, TimingPoint,
+    },
+    common::{Mods, WINDOWS_EPOCH},
+};
+
+/// One version from each range that stores entries differently: with sizes, without sizes, and with Single star
+/// ratings.
+const VERSIONS: [u32; 3] = [20150203, 20191106, 20250108];
+
+/// Thread counts to parse with, including more threads than there are beatmaps.
+const THREADS: [usize; 5] = [0, 1, 2, 3, 64];
+
+/// Builds a listing whose beatmaps can be told apart, with entries of different lengths.
+fn listing(version: u32) -> BeatmapListing {
+    let beatmaps = (0..40)
+        .map(|i| BeatmapEntry {
+            artist_name: Some(format!("Artist {}", i)),
+            song_title: Some("Title".repeat(i % 5)),
+            md5: Some(format!("{:032x}", i)),
+            hitcircle_count: i as u16,
+            difficulty_id: 1000 + i as u32,
+            star_ratings_std: Some(
+                (0..i % 3)
+                    .map(|j| StarRating {
+                        mods: Mods::DoubleTime.into(),
+                        rating: j as f64 + 0.5,
+                    })
+                    .collect(),
+            ),
+            star_ratings_taiko: Some(Vec::new()),
+            star_ratings_ctb: Some(Vec::new()),
+            star_ratings_mania: Some(Vec::new()),
+            timing_points: (0..i % 4)
+                .map(|j| TimingPoint {
+                    bpm: 500.0,
+                    song_offset: j as f64 * 1000.0,
+                    inherited: true,
+                })
+                .collect(),
+            ..Default::default()
+        })
+        .collect();
+
+    BeatmapListing {
+        version,
+        folder_count: 40,
+        account_unlocked: true,
+        account_unlock_date: WINDOWS_EPOCH,
+        player_name: Some("Player".to_string()),
+        beatmaps,
+        user_permissions: Default::default(),
+    }
+}
+
+fn parse(data: &[u8], threads: usize, record_spans: bool) -> PartialListing {
+    let options = ParseOptions {
+        record_spans,
+        threads,
+        ..Default::default()
+    };
+
+    BeatmapListing::from_bytes_with_options(data, options, |_, _| true).unwrap()
+}
+
+#[test]
+fn beatmaps_are_parsed_in_file_order() {
+    for version in VERSIONS {
+        let bytes = listing(version).to_bytes();
+        let sequential = parse(&bytes, 0, true);
+
+        let md5s = sequential
+            .listing
+            .beatmaps
+            .iter()
+            .map(|beatmap| beatmap.md5.clone().unwrap())
+            .collect::<Vec<_>>();
+        let expected = (0..40).map(|i| format!("{:032x}", i)).collect::<Vec<_>>();
+        assert_eq!(md5s, expected, "version {}", version);
+
+        for threads in THREADS {
+            for record_spans in [false, true] {
+                let parsed = parse(&bytes, threads, record_spans);
+
+                assert_eq!(
+                    parsed.listing, sequential.listing,
+                    "version {}, {} threads",
+                    version, threads
+                );
+                assert_eq!(parsed.skipped, 0);
+                assert!(parsed.error.is_none());
+
+                if record_spans {
+                    assert_eq!(
+                        parsed.spans, sequential.spans,
+                        "version {}, {} threads",
+                        version, threads
+                    );
+                } else {
+                    assert!(parsed.spans.is_empty());
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn damaged_files_are_parsed_the_same_way() {
+    for version in VERSIONS {
+        let mut bytes = listing(version).to_bytes();
+
+        // Cut the file off part way through the beatmaps
+        bytes.truncate(bytes.len() * 2 / 3);
+
+        let sequential = parse(&bytes, 0, true);
+        assert!(sequential.error.is_some(), "version {}", version);
+
+        for threads in THREADS {
+            let parsed = parse(&bytes, threads, true);
+
+            assert_eq!(
+                parsed.listing, sequential.listing,
+                "version {}, {} threads",
+                version, threads
+            );
+            assert_eq!(parsed.skipped, sequential.skipped);
+            assert_eq!(parsed.spans, sequential.spans);
+            assert_eq!(
+                parsed.error.map(|e| e.to_string()),
+                sequential.error.as_ref().map(|e| e.to_string())
+            );
+        }
+    }
+}
+
+/// Writes each beatmap as a line of JSON, as a stand-in for an exporter that writes them in the order they're given.
+#[cfg(feature = "serde")]
+fn ndjson(beatmaps: &[BeatmapEntry]) -> Vec<u8> {
+    let mut output = Vec::new();
+
+    for beatmap in beatmaps {
+        serde_json::to_writer(&mut output, beatmap).unwrap();
+        output.push(b'\n');
+    }
+
+    output
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn ndjson_output_is_identical() {
+    for version in VERSIONS {
+        let bytes = listing(version).to_bytes();
+        let expected = ndjson(&parse(&bytes, 0, false).listing.beatmaps);
+
+        for threads in THREADS {
+            let output = ndjson(&parse(&bytes, threads, false).listing.beatmaps);
+            assert!(
+                output == expected,
+                "version {}, {} threads",
+                version,
+                threads
+            );
+        }
+    }
+}
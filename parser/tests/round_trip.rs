@@ -0,0 +1,637 @@
+//! Checks that listings survive being written and parsed again, for each range of versions that stores beatmaps
+//! differently.
+//!
+//! Random listings are generated with `proptest`, and should be shrunk to a minimal failing listing (e.g. a single
+//! beatmap with every other field left empty) when a round trip fails. The fixtures at the end of the file cover each
+//! of the version quirks with hand-written bytes.
+
+#![cfg(feature = "writer")]
+
+use std::ops::Range;
+
+use osu_db_parser::{
+    beatmaps::{
+        BeatmapEntry, BeatmapListing, RankedStatus, StarRating, TimingPoint, UserPermissions,
+    },
+    common::{GameplayMode, Grade, Mods, OsuString, WINDOWS_EPOCH},
+    flagset::FlagSet,
+    format::{self, DaylightSaving, TimeZone},
+};
+use proptest::{
+    array::{uniform4, uniform5},
+    collection::vec,
+    num,
+    prelude::*,
+    sample::select,
+    test_runner::TestCaseError,
+};
+use time::{Duration, OffsetDateTime, UtcOffset};
+
+/// Difficulty settings are stored as bytes, star ratings are missing, there is an extra `u16` and each entry starts with
+/// its size.
+const BYTE_DIFFICULTIES: Range<u32> = 20121008..20140609;
+
+/// Difficulty settings are stored as Singles, and star ratings as Doubles. Each entry still starts with its size.
+const DOUBLE_STAR_RATINGS: Range<u32> = 20140609..20191106;
+
+/// Entries no longer start with their size.
+const UNSIZED_ENTRIES: Range<u32> = 20191106..20250108;
+
+/// Star ratings are stored as Singles.
+const FLOAT_STAR_RATINGS: Range<u32> = 20250108..20260101;
+
+/// .NET's `DateTime.MaxValue.Ticks`, which is the last moment in the year 9999.
+const MAX_TICKS: u64 = 3_155_378_975_999_999_999;
+
+proptest! {
+    // Beatmaps have a lot of fields, so shrinking needs more iterations than usual to reach a minimal listing
+    #![proptest_config(ProptestConfig {
+        max_shrink_iters: 100_000,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn byte_difficulty_listings_round_trip(listing in listing(BYTE_DIFFICULTIES)) {
+        check_round_trip(&listing)?;
+    }
+
+    #[test]
+    fn double_star_rating_listings_round_trip(listing in listing(DOUBLE_STAR_RATINGS)) {
+        check_round_trip(&listing)?;
+    }
+
+    #[test]
+    fn unsized_entry_listings_round_trip(listing in listing(UNSIZED_ENTRIES)) {
+        check_round_trip(&listing)?;
+    }
+
+    #[test]
+    fn float_star_rating_listings_round_trip(listing in listing(FLOAT_STAR_RATINGS)) {
+        check_round_trip(&listing)?;
+    }
+}
+
+/// Writes and parses a listing, checking that the parsed listing matches. Beatmaps are compared one at a time, so that
+/// a failure only shows the beatmap that differs.
+fn check_round_trip(listing: &BeatmapListing) -> Result<(), TestCaseError> {
+    let bytes = listing.to_bytes();
+    let parsed = BeatmapListing::from_bytes(&bytes)
+        .map_err(|e| TestCaseError::fail(format!("unable to parse listing: {}", e)))?;
+
+    prop_assert_eq!(parsed.version, listing.version);
+    prop_assert_eq!(parsed.folder_count, listing.folder_count);
+    prop_assert_eq!(parsed.account_unlocked, listing.account_unlocked);
+    prop_assert_eq!(parsed.account_unlock_date, listing.account_unlock_date);
+    prop_assert_eq!(&parsed.player_name, &listing.player_name);
+    prop_assert_eq!(parsed.user_permissions, listing.user_permissions);
+    prop_assert_eq!(parsed.beatmaps.len(), listing.beatmaps.len());
+
+    for (index, (parsed, beatmap)) in parsed.beatmaps.iter().zip(&listing.beatmaps).enumerate() {
+        // Entry sizes are recalculated when writing, and are checked against the bytes below
+        let expected = BeatmapEntry {
+            size: parsed.size,
+            ..beatmap.clone()
+        };
+
+        prop_assert_eq!(parsed, &expected, "beatmap {}", index);
+    }
+
+    check_entry_sizes(&bytes, &parsed)?;
+
+    // Writing the parsed listing shouldn't change anything
+    prop_assert_eq!(parsed.to_bytes(), bytes);
+    Ok(())
+}
+
+/// Checks that each entry's size covers exactly the bytes of that entry, for versions which store sizes.
+fn check_entry_sizes(bytes: &[u8], parsed: &BeatmapListing) -> Result<(), TestCaseError> {
+    if parsed.version >= 20191106 {
+        prop_assert!(parsed.beatmaps.iter().all(|beatmap| beatmap.size.is_none()));
+        return Ok(());
+    }
+
+    let header = BeatmapListing {
+        beatmaps: Vec::new(),
+        ..parsed.clone()
+    };
+
+    // Skip the header, but not the user permissions at the end
+    let mut offset = header.to_bytes().len() - 4;
+
+    for (index, beatmap) in parsed.beatmaps.iter().enumerate() {
+        let size = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let entry = BeatmapListing {
+            beatmaps: vec![beatmap.clone()],
+            ..header.clone()
+        };
+
+        prop_assert_eq!(beatmap.size, Some(size), "beatmap {}", index);
+        prop_assert_eq!(
+            size as usize,
+            entry.to_bytes().len() - header.to_bytes().len() - 4,
+            "beatmap {}",
+            index
+        );
+
+        offset += 4 + size as usize;
+    }
+
+    prop_assert_eq!(offset + 4, bytes.len());
+    Ok(())
+}
+
+/// Generates a listing with a version from `versions`, only using the fields which are stored by those versions.
+fn listing(versions: Range<u32>) -> impl Strategy<Value = BeatmapListing> {
+    // Every version in the range stores beatmaps in the same way
+    let layout = versions.start;
+
+    (
+        versions,
+        any::<u32>(),
+        any::<bool>(),
+        datetime(),
+        osu_string(),
+        vec(beatmap_entry(layout), 0..4),
+        any::<u32>().prop_map(FlagSet::<UserPermissions>::new_truncated),
+    )
+        .prop_map(
+            |(
+                version,
+                folder_count,
+                account_unlocked,
+                account_unlock_date,
+                player_name,
+                beatmaps,
+                user_permissions,
+            )| BeatmapListing {
+                version,
+                folder_count,
+                account_unlocked,
+                account_unlock_date,
+                player_name,
+                beatmaps,
+                user_permissions,
+            },
+        )
+}
+
+prop_compose! {
+    /// Generates a beatmap entry for a version. The size is left empty, since it's calculated when writing.
+    fn beatmap_entry(version: u32)(
+        entry in beatmap_entry_start(version),
+        [song_source, song_tags, font, folder_name] in uniform4(osu_string()),
+        online_offset in any::<u16>(),
+        [last_played, last_checked_online] in [datetime(), datetime()],
+        [is_unplayed, is_osz2] in any::<[bool; 2]>(),
+        [
+            ignore_beatmap_hitsounds,
+            ignore_beatmap_skin,
+            disable_storyboard,
+            disable_video,
+            visual_override,
+        ] in uniform5(any::<bool>()),
+        unknown_u16 in any::<u16>().prop_map(move |value| (version < 20140609).then_some(value)),
+        unknown_u32 in any::<u32>(),
+        mania_scroll_speed in any::<u8>(),
+    ) -> BeatmapEntry {
+        BeatmapEntry {
+            song_source,
+            song_tags,
+            online_offset,
+            font,
+            is_unplayed,
+            last_played,
+            is_osz2,
+            folder_name,
+            last_checked_online,
+            ignore_beatmap_hitsounds,
+            ignore_beatmap_skin,
+            disable_storyboard,
+            disable_video,
+            visual_override,
+            unknown_u16,
+            unknown_u32,
+            mania_scroll_speed,
+            ..entry
+        }
+    }
+}
+
+prop_compose! {
+    /// Generates the fields up to and including the gameplay mode, since there are too many fields for a single
+    /// strategy.
+    fn beatmap_entry_start(version: u32)(
+        [
+            artist_name,
+            artist_name_unicode,
+            song_title,
+            song_title_unicode,
+            creator_name,
+            difficulty,
+            audio_filename,
+            md5,
+            beatmap_filename,
+        ] in [
+            osu_string(), osu_string(), osu_string(), osu_string(), osu_string(), osu_string(),
+            osu_string(), osu_string(), osu_string(),
+        ],
+        ranked_status in ranked_status(),
+        [hitcircle_count, slider_count, spinner_count] in any::<[u16; 3]>(),
+        last_modification_time in datetime(),
+        [approach_rate, circle_size, hp_drain, overall_difficulty] in uniform4(difficulty(version)),
+        slider_velocity in any_f64(),
+        star_ratings in star_ratings(version),
+        [drain_time, total_time, audio_preview_time] in any::<[u32; 3]>(),
+        timing_points in vec(timing_point(), 0..4),
+        [difficulty_id, beatmap_id, thread_id] in any::<[u32; 3]>(),
+        [grade_std, grade_taiko, grade_catch, grade_mania] in uniform4(grade()),
+        (local_offset, stack_leniency, gameplay_mode) in (any::<u16>(), any_f32(), gameplay_mode()),
+    ) -> BeatmapEntry {
+        let [star_ratings_std, star_ratings_taiko, star_ratings_ctb, star_ratings_mania] = match star_ratings {
+            Some(star_ratings) => star_ratings.map(Some),
+            None => Default::default(),
+        };
+
+        BeatmapEntry {
+            size: None,
+            artist_name,
+            artist_name_unicode,
+            song_title,
+            song_title_unicode,
+            creator_name,
+            difficulty,
+            audio_filename,
+            md5,
+            beatmap_filename,
+            ranked_status,
+            hitcircle_count,
+            slider_count,
+            spinner_count,
+            last_modification_time,
+            approach_rate,
+            circle_size,
+            hp_drain,
+            overall_difficulty,
+            slider_velocity,
+            star_ratings_std,
+            star_ratings_taiko,
+            star_ratings_ctb,
+            star_ratings_mania,
+            drain_time,
+            total_time,
+            audio_preview_time,
+            timing_points,
+            difficulty_id,
+            beatmap_id,
+            thread_id,
+            grade_std,
+            grade_taiko,
+            grade_catch,
+            grade_mania,
+            local_offset,
+            stack_leniency,
+            gameplay_mode,
+            ..Default::default()
+        }
+    }
+}
+
+/// Generates a string, which may be missing (`0x00`) or empty (`0x0b 0x00`).
+fn osu_string() -> impl Strategy<Value = OsuString> {
+    prop_oneof![
+        Just(None),
+        Just(Some(String::new())),
+        "\\PC{1,16}".prop_map(Some),
+    ]
+}
+
+/// Generates a date that can be stored as .NET ticks, so it has a precision of 100ns.
+fn datetime() -> impl Strategy<Value = OffsetDateTime> {
+    (0..=MAX_TICKS).prop_map(|ticks| {
+        WINDOWS_EPOCH
+            + Duration::microseconds((ticks / 10) as i64)
+            + Duration::nanoseconds(((ticks % 10) * 100) as i64)
+    })
+}
+
+/// Generates a difficulty setting, which can only be a whole number from 0 to 255 in versions that store it as a byte.
+fn difficulty(version: u32) -> BoxedStrategy<f32> {
+    if version < 20140609 {
+        any::<u8>().prop_map(f32::from).boxed()
+    } else {
+        any_f32().boxed()
+    }
+}
+
+/// Generates the star ratings for each gameplay mode, which are only present from version 20140609. Ratings only have
+/// the precision of a Single from version 20250108.
+fn star_ratings(version: u32) -> impl Strategy<Value = Option<[Vec<StarRating>; 4]>> {
+    let rating = if version < 20250108 {
+        any_f64().boxed()
+    } else {
+        any_f32().prop_map(f64::from).boxed()
+    };
+
+    let star_rating = (mods(), rating).prop_map(|(mods, rating)| StarRating { mods, rating });
+    let star_ratings = uniform4(vec(star_rating, 0..3));
+
+    if version < 20140609 {
+        Just(None).boxed()
+    } else {
+        star_ratings.prop_map(Some).boxed()
+    }
+}
+
+fn timing_point() -> impl Strategy<Value = TimingPoint> {
+    (any_f64(), any_f64(), any::<bool>()).prop_map(|(bpm, song_offset, inherited)| TimingPoint {
+        bpm,
+        song_offset,
+        inherited,
+    })
+}
+
+fn mods() -> impl Strategy<Value = FlagSet<Mods>> {
+    any::<u32>().prop_map(FlagSet::<Mods>::new_truncated)
+}
+
+fn ranked_status() -> impl Strategy<Value = RankedStatus> {
+    use RankedStatus::*;
+    select(vec![
+        Unknown,
+        Unsubmitted,
+        Pending,
+        Ranked,
+        Approved,
+        Qualified,
+        Loved,
+    ])
+}
+
+fn grade() -> impl Strategy<Value = Grade> {
+    use Grade::*;
+    select(vec![Unplayed, SilverSS, SilverS, SS, S, A, B, C, D])
+}
+
+fn gameplay_mode() -> impl Strategy<Value = GameplayMode> {
+    use GameplayMode::*;
+    select(vec![Standard, Taiko, Catch, Mania])
+}
+
+/// Generates any Single other than NaN, which never compares equal to itself.
+fn any_f32() -> impl Strategy<Value = f32> {
+    num::f32::POSITIVE
+        | num::f32::NEGATIVE
+        | num::f32::NORMAL
+        | num::f32::SUBNORMAL
+        | num::f32::ZERO
+        | num::f32::INFINITE
+}
+
+/// Generates any Double other than NaN, which never compares equal to itself.
+fn any_f64() -> impl Strategy<Value = f64> {
+    num::f64::POSITIVE
+        | num::f64::NEGATIVE
+        | num::f64::NORMAL
+        | num::f64::SUBNORMAL
+        | num::f64::ZERO
+        | num::f64::INFINITE
+}
+
+/// Builds the bytes for a beatmap entry with the given artist name, difficulty settings and star ratings. Everything
+/// else is zeroed, or empty.
+fn entry(version: u32, artist_name: &[u8], difficulties: &[u8], star_ratings: &[u8]) -> Vec<u8> {
+    let mut entry = artist_name.to_vec();
+    entry.extend([0x00; 8]); // Other strings
+    entry.push(4); // Ranked status
+    entry.extend([0; 3 * 2 + 8]); // Object counts, last modification time
+    entry.extend(difficulties);
+    entry.extend([0; 8]); // Slider velocity
+    entry.extend(star_ratings);
+    entry.extend([0; 3 * 4 + 4]); // Times, timing points
+    entry.extend([0; 3 * 4]); // IDs
+    entry.extend([9; 4]); // Grades
+    entry.extend([0; 2 + 4 + 1]); // Local offset, stack leniency, gameplay mode
+    entry.extend([0x00, 0x00]); // Source, tags
+    entry.extend([0, 0, 0x00, 1]); // Online offset, font, unplayed
+    entry.extend([0; 8 + 1]); // Last played, osz2
+    entry.push(0x00); // Folder name
+    entry.extend([0; 8 + 5]); // Last checked, flags
+
+    if version < 20140609 {
+        entry.extend([0; 2]); // Unknown u16
+    }
+
+    entry.extend([0; 4 + 1]); // Unknown u32, scroll speed
+    entry
+}
+
+/// Builds the bytes for an `osu.db` file containing a single entry, adding the entry size if the version stores it.
+fn listing_bytes(version: u32, entry: &[u8]) -> Vec<u8> {
+    let mut data = version.to_le_bytes().to_vec();
+    data.extend([0; 4 + 1 + 8]); // Folder count, account unlocked, unlock date
+    data.push(0x00); // Player name
+    data.extend(1u32.to_le_bytes());
+
+    if version < 20191106 {
+        data.extend((entry.len() as u32).to_le_bytes());
+    }
+
+    data.extend(entry);
+    data.extend(1u32.to_le_bytes()); // User permissions
+    data
+}
+
+/// Star ratings for every mode, where osu! standard has a single rating using the given pair and the others are empty.
+fn single_star_rating(pair: &[u8]) -> Vec<u8> {
+    let mut star_ratings = 1u32.to_le_bytes().to_vec();
+    star_ratings.extend(pair);
+    star_ratings.extend([0; 3 * 4]);
+    star_ratings
+}
+
+#[test]
+fn byte_difficulties_are_whole_numbers() {
+    let data = listing_bytes(20140101, &entry(20140101, &[0x00], &[9, 4, 6, 8], &[]));
+    let listing = BeatmapListing::from_bytes(&data).unwrap();
+    let beatmap = &listing.beatmaps[0];
+
+    assert_eq!(
+        [
+            beatmap.approach_rate,
+            beatmap.circle_size,
+            beatmap.hp_drain,
+            beatmap.overall_difficulty
+        ],
+        [9.0, 4.0, 6.0, 8.0]
+    );
+    assert_eq!(beatmap.star_ratings_std, None);
+    assert_eq!(beatmap.unknown_u16, Some(0));
+    assert_eq!(listing.to_bytes(), data);
+}
+
+#[test]
+fn control_characters_are_kept() {
+    // Text is only sanitized when it's shown or exported, so a tampered entry is written back as it was
+    let artist_name = "Null\0Artist\n\u{202E}";
+    let mut string = vec![0x0b, artist_name.len() as u8];
+    string.extend(artist_name.as_bytes());
+
+    let data = listing_bytes(
+        20191106,
+        &entry(20191106, &string, &[0; 4 * 4], &[0; 4 * 4]),
+    );
+    let listing = BeatmapListing::from_bytes(&data).unwrap();
+
+    assert_eq!(
+        listing.beatmaps[0].artist_name.as_deref(),
+        Some(artist_name)
+    );
+    assert_eq!(listing.to_bytes(), data);
+}
+
+#[test]
+fn dates_keep_their_ticks() {
+    // 2024-03-31 00:30:00, half an hour before clocks go forward in Europe
+    const TICKS: u64 = 638_474_418_000_000_000;
+    let stored = WINDOWS_EPOCH + Duration::microseconds((TICKS / 10) as i64);
+
+    let listing = BeatmapListing {
+        version: 20191106,
+        folder_count: 1,
+        account_unlocked: true,
+        account_unlock_date: WINDOWS_EPOCH,
+        player_name: None,
+        beatmaps: vec![BeatmapEntry {
+            last_modification_time: stored,
+            last_played: stored,
+            last_checked_online: stored,
+            ..Default::default()
+        }],
+        user_permissions: FlagSet::default(),
+    };
+
+    let data = listing.to_bytes();
+    let parsed = BeatmapListing::from_bytes(&data).unwrap();
+    let beatmap = &parsed.beatmaps[0];
+
+    // Local and UTC dates are both kept as stored, and only converted when they're shown
+    let london = TimeZone {
+        offset: UtcOffset::UTC,
+        daylight_saving: DaylightSaving::Europe,
+    };
+    let tokyo = TimeZone::fixed(UtcOffset::from_hms(9, 0, 0).unwrap());
+
+    for zone in [london, tokyo] {
+        assert_eq!(
+            format::local_date_time(beatmap.last_played, BeatmapEntry::LAST_PLAYED_KIND, &zone),
+            "2024-03-31 00:30:00"
+        );
+    }
+
+    assert_eq!(beatmap.last_played, stored);
+    assert_eq!(beatmap.last_modification_time, stored);
+    assert_eq!(parsed.to_bytes(), data);
+    assert_eq!(
+        data.windows(8)
+            .filter(|bytes| *bytes == TICKS.to_le_bytes())
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn entry_sizes_are_recalculated() {
+    let single_difficulties = [0; 4 * 4];
+    let empty_star_ratings = [0; 4 * 4];
+    let entry = entry(20150203, &[0x00], &single_difficulties, &empty_star_ratings);
+    let data = listing_bytes(20150203, &entry);
+
+    // The stored size isn't needed for parsing, so an incorrect size is kept as it is
+    let mut incorrect = data.clone();
+    incorrect[22..26].copy_from_slice(&1u32.to_le_bytes());
+
+    let mut listing = BeatmapListing::from_bytes(&incorrect).unwrap();
+    assert_eq!(listing.beatmaps[0].size, Some(1));
+    assert_eq!(listing.to_bytes(), data);
+
+    // Sizes are recalculated after editing an entry
+    listing.beatmaps[0].artist_name = Some("Camellia".to_string());
+    let edited = listing.to_bytes();
+    let reparsed = BeatmapListing::from_bytes(&edited).unwrap();
+    assert_eq!(reparsed.beatmaps[0].size, Some(entry.len() as u32 + 9));
+    assert_eq!(edited.len(), data.len() + 9);
+}
+
+#[test]
+fn star_ratings_are_floats_from_20250108() {
+    let mut double = vec![0x08];
+    double.extend(FlagSet::from(Mods::DoubleTime).bits().to_le_bytes());
+    double.push(0x0d);
+    double.extend(5.4321f64.to_le_bytes());
+
+    let mut float = vec![0x08];
+    float.extend(FlagSet::from(Mods::DoubleTime).bits().to_le_bytes());
+    float.push(0x0c);
+    float.extend(5.4321f32.to_le_bytes());
+
+    let single_difficulties = [0; 4 * 4];
+
+    for (version, pair, rating) in [
+        (20191106, &double, 5.4321),
+        (20250108, &float, f64::from(5.4321f32)),
+    ] {
+        let entry = entry(
+            version,
+            &[0x00],
+            &single_difficulties,
+            &single_star_rating(pair),
+        );
+        let data = listing_bytes(version, &entry);
+        let listing = BeatmapListing::from_bytes(&data).unwrap();
+
+        assert_eq!(
+            listing.beatmaps[0].star_ratings_std,
+            Some(vec![StarRating {
+                mods: Mods::DoubleTime.into(),
+                rating
+            }]),
+            "version {}",
+            version
+        );
+        assert_eq!(listing.to_bytes(), data, "version {}", version);
+    }
+
+    // Each version only accepts its own layout
+    for (version, pair) in [(20191106, &float), (20250108, &double)] {
+        let entry = entry(
+            version,
+            &[0x00],
+            &single_difficulties,
+            &single_star_rating(pair),
+        );
+        assert!(BeatmapListing::from_bytes(&listing_bytes(version, &entry)).is_err());
+    }
+}
+
+#[test]
+fn missing_and_empty_strings_are_distinct() {
+    let single_difficulties = [0; 4 * 4];
+    let empty_star_ratings = [0; 4 * 4];
+
+    for (artist_name, expected) in [
+        (&[0x00][..], None),
+        (&[0x0b, 0x00][..], Some(String::new())),
+    ] {
+        let entry = entry(
+            20191106,
+            artist_name,
+            &single_difficulties,
+            &empty_star_ratings,
+        );
+        let data = listing_bytes(20191106, &entry);
+        let listing = BeatmapListing::from_bytes(&data).unwrap();
+
+        assert_eq!(listing.beatmaps[0].artist_name, expected);
+        assert_eq!(listing.to_bytes(), data);
+    }
+}
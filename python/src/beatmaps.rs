@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+
+use osu_db_parser::{links, prelude::*};
+use pyo3::{prelude::*, types::PyDateTime};
+
+use crate::{parse_query, to_datetime};
+
+/// Gameplay modes, in the order their star ratings and grades are listed.
+const MODES: [GameplayMode; 4] = [
+    GameplayMode::Standard,
+    GameplayMode::Taiko,
+    GameplayMode::Catch,
+    GameplayMode::Mania,
+];
+
+/// The contents of an `osu!.db` file.
+#[pyclass(name = "BeatmapListing", module = "osu_db", frozen)]
+pub struct PyBeatmapListing {
+    #[pyo3(get)]
+    version: u32,
+
+    #[pyo3(get)]
+    folder_count: u32,
+
+    #[pyo3(get)]
+    account_unlocked: bool,
+
+    #[pyo3(get)]
+    account_unlock_date: Option<Py<PyDateTime>>,
+
+    #[pyo3(get)]
+    player_name: Option<String>,
+
+    beatmaps: Vec<Py<PyBeatmap>>,
+}
+
+/// A beatmap in an `osu!.db` file.
+#[pyclass(name = "Beatmap", module = "osu_db", frozen)]
+pub struct PyBeatmap {
+    pub entry: BeatmapEntry,
+}
+
+impl PyBeatmapListing {
+    /// Copies a parsed listing into Python objects.
+    pub fn new(py: Python<'_>, listing: BeatmapListing) -> PyResult<Self> {
+        let account_unlock_date = to_datetime(py, listing.account_unlock_date)?.map(Bound::unbind);
+
+        let beatmaps = listing
+            .beatmaps
+            .into_iter()
+            .map(|entry| Py::new(py, PyBeatmap { entry }))
+            .collect::<PyResult<_>>()?;
+
+        Ok(PyBeatmapListing {
+            version: listing.version,
+            folder_count: listing.folder_count,
+            account_unlocked: listing.account_unlocked,
+            account_unlock_date,
+            player_name: listing.player_name,
+            beatmaps,
+        })
+    }
+}
+
+#[pymethods]
+impl PyBeatmapListing {
+    /// Every beatmap in the listing, in the order they're stored in the file.
+    #[getter]
+    fn beatmaps(&self, py: Python<'_>) -> Vec<Py<PyBeatmap>> {
+        self.beatmaps.iter().map(|b| b.clone_ref(py)).collect()
+    }
+
+    /// Finds the beatmaps matching a search query, using the same syntax as osu!'s song select.
+    fn filter(&self, py: Python<'_>, query: &str) -> PyResult<Vec<Py<PyBeatmap>>> {
+        let filter = parse_query(query)?;
+
+        Ok(self
+            .beatmaps
+            .iter()
+            .filter(|beatmap| filter.matches(&beatmap.get().entry))
+            .map(|beatmap| beatmap.clone_ref(py))
+            .collect())
+    }
+
+    fn __len__(&self) -> usize {
+        self.beatmaps.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<BeatmapListing version={} beatmaps={}>",
+            self.version,
+            self.beatmaps.len()
+        )
+    }
+}
+
+#[pymethods]
+impl PyBeatmap {
+    #[getter]
+    fn artist(&self) -> Option<&str> {
+        self.entry.artist_name.as_deref()
+    }
+
+    #[getter]
+    fn artist_unicode(&self) -> Option<&str> {
+        self.entry.artist_name_unicode.as_deref()
+    }
+
+    #[getter]
+    fn title(&self) -> Option<&str> {
+        self.entry.song_title.as_deref()
+    }
+
+    #[getter]
+    fn title_unicode(&self) -> Option<&str> {
+        self.entry.song_title_unicode.as_deref()
+    }
+
+    #[getter]
+    fn creator(&self) -> Option<&str> {
+        self.entry.creator_name.as_deref()
+    }
+
+    #[getter]
+    fn difficulty(&self) -> Option<&str> {
+        self.entry.difficulty.as_deref()
+    }
+
+    #[getter]
+    fn source(&self) -> Option<&str> {
+        self.entry.song_source.as_deref()
+    }
+
+    #[getter]
+    fn tags(&self) -> Option<&str> {
+        self.entry.song_tags.as_deref()
+    }
+
+    #[getter]
+    fn md5(&self) -> Option<&str> {
+        self.entry.md5.as_deref()
+    }
+
+    #[getter]
+    fn audio_filename(&self) -> Option<&str> {
+        self.entry.audio_filename.as_deref()
+    }
+
+    #[getter]
+    fn beatmap_filename(&self) -> Option<&str> {
+        self.entry.beatmap_filename.as_deref()
+    }
+
+    /// Name of the beatmap's folder, relative to the Songs folder.
+    #[getter]
+    fn folder_name(&self) -> Option<&str> {
+        self.entry.folder_name.as_deref()
+    }
+
+    /// Ranked status, e.g. `"Ranked"` or `"Loved"`.
+    #[getter]
+    fn ranked_status(&self) -> String {
+        self.entry.ranked_status.to_string()
+    }
+
+    /// Gameplay mode, i.e. `"Standard"`, `"Taiko"`, `"Catch"` or `"Mania"`.
+    #[getter]
+    fn gameplay_mode(&self) -> String {
+        self.entry.gameplay_mode.to_string()
+    }
+
+    #[getter]
+    fn beatmapset_id(&self) -> u32 {
+        self.entry.beatmap_id
+    }
+
+    #[getter]
+    fn difficulty_id(&self) -> u32 {
+        self.entry.difficulty_id
+    }
+
+    #[getter]
+    fn thread_id(&self) -> u32 {
+        self.entry.thread_id
+    }
+
+    #[getter]
+    fn hitcircle_count(&self) -> u16 {
+        self.entry.hitcircle_count
+    }
+
+    #[getter]
+    fn slider_count(&self) -> u16 {
+        self.entry.slider_count
+    }
+
+    #[getter]
+    fn spinner_count(&self) -> u16 {
+        self.entry.spinner_count
+    }
+
+    #[getter]
+    fn approach_rate(&self) -> f32 {
+        self.entry.approach_rate
+    }
+
+    #[getter]
+    fn circle_size(&self) -> f32 {
+        self.entry.circle_size
+    }
+
+    #[getter]
+    fn hp_drain(&self) -> f32 {
+        self.entry.hp_drain
+    }
+
+    #[getter]
+    fn overall_difficulty(&self) -> f32 {
+        self.entry.overall_difficulty
+    }
+
+    #[getter]
+    fn slider_velocity(&self) -> f64 {
+        self.entry.slider_velocity
+    }
+
+    #[getter]
+    fn stack_leniency(&self) -> f32 {
+        self.entry.stack_leniency
+    }
+
+    /// NoMod star rating in the beatmap's own gameplay mode, if it has been calculated.
+    #[getter]
+    fn star_rating(&self) -> Option<f64> {
+        self.entry.nomod_star_rating()
+    }
+
+    /// Star ratings for each gameplay mode, as a dictionary of mods to star ratings.
+    #[getter]
+    fn star_ratings(&self) -> HashMap<String, HashMap<u32, f64>> {
+        MODES
+            .iter()
+            .map(|&mode| {
+                let ratings = self
+                    .entry
+                    .star_ratings(mode)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|rating| (rating.mods.bits(), rating.rating))
+                    .collect();
+
+                (mode.to_string(), ratings)
+            })
+            .collect()
+    }
+
+    /// Most common BPM, weighted by how long each timing point lasts.
+    #[getter]
+    fn bpm(&self) -> Option<f64> {
+        self.entry.main_bpm()
+    }
+
+    /// Drain time, in seconds.
+    #[getter]
+    fn drain_time(&self) -> u32 {
+        self.entry.drain_time
+    }
+
+    /// Total time, in milliseconds.
+    #[getter]
+    fn total_time(&self) -> u32 {
+        self.entry.total_time
+    }
+
+    /// Time that the audio preview starts, in milliseconds.
+    #[getter]
+    fn audio_preview_time(&self) -> u32 {
+        self.entry.audio_preview_time
+    }
+
+    /// Timing points, as tuples of the raw BPM value, offset in milliseconds, and whether the timing point is
+    /// uninherited.
+    #[getter]
+    fn timing_points(&self) -> Vec<(f64, f64, bool)> {
+        self.entry
+            .timing_points
+            .iter()
+            .map(|point| (point.bpm, point.song_offset, point.is_uninherited()))
+            .collect()
+    }
+
+    #[getter]
+    fn local_offset(&self) -> u16 {
+        self.entry.local_offset
+    }
+
+    #[getter]
+    fn online_offset(&self) -> u16 {
+        self.entry.online_offset
+    }
+
+    /// Best grade in the beatmap's own gameplay mode, e.g. `"S"`, or `"Unplayed"`.
+    #[getter]
+    fn grade(&self) -> String {
+        self.entry.grade(self.entry.gameplay_mode).to_string()
+    }
+
+    /// Best grades in each gameplay mode.
+    #[getter]
+    fn grades(&self) -> HashMap<String, String> {
+        MODES
+            .iter()
+            .map(|&mode| (mode.to_string(), self.entry.grade(mode).to_string()))
+            .collect()
+    }
+
+    #[getter]
+    fn is_unplayed(&self) -> bool {
+        self.entry.is_unplayed
+    }
+
+    #[getter]
+    fn last_played<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDateTime>>> {
+        to_datetime(py, self.entry.last_played)
+    }
+
+    #[getter]
+    fn last_modified<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDateTime>>> {
+        to_datetime(py, self.entry.last_modification_time)
+    }
+
+    #[getter]
+    fn last_checked_online<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Option<Bound<'py, PyDateTime>>> {
+        to_datetime(py, self.entry.last_checked_online)
+    }
+
+    /// Link to the beatmap's page on the osu! website, if it has been submitted.
+    #[getter]
+    fn url(&self) -> Option<String> {
+        links::beatmap_url(&self.entry)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Beatmap {} - {} [{}]>",
+            self.entry.artist_name.as_deref().unwrap_or_default(),
+            self.entry.song_title.as_deref().unwrap_or_default(),
+            self.entry.difficulty.as_deref().unwrap_or_default()
+        )
+    }
+}
@@ -0,0 +1,81 @@
+use osu_db_parser::prelude::*;
+use pyo3::prelude::*;
+
+/// The contents of a `collection.db` file.
+#[pyclass(name = "CollectionListing", module = "osu_db", frozen)]
+pub struct PyCollectionListing {
+    #[pyo3(get)]
+    version: u32,
+
+    collections: Vec<Py<PyCollection>>,
+}
+
+/// A collection of beatmaps, which are identified by their MD5 hashes.
+#[pyclass(name = "Collection", module = "osu_db", frozen)]
+pub struct PyCollection {
+    #[pyo3(get)]
+    name: Option<String>,
+
+    #[pyo3(get)]
+    beatmap_md5s: Vec<String>,
+}
+
+impl PyCollectionListing {
+    /// Copies a parsed listing into Python objects.
+    pub fn new(py: Python<'_>, listing: CollectionListing) -> PyResult<Self> {
+        let collections = listing
+            .collections
+            .into_iter()
+            .map(|collection| {
+                Py::new(
+                    py,
+                    PyCollection {
+                        name: collection.name,
+                        beatmap_md5s: collection.beatmap_md5s.into_iter().flatten().collect(),
+                    },
+                )
+            })
+            .collect::<PyResult<_>>()?;
+
+        Ok(PyCollectionListing {
+            version: listing.version,
+            collections,
+        })
+    }
+}
+
+#[pymethods]
+impl PyCollectionListing {
+    /// Every collection, in the order they're stored in the file.
+    #[getter]
+    fn collections(&self, py: Python<'_>) -> Vec<Py<PyCollection>> {
+        self.collections.iter().map(|c| c.clone_ref(py)).collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.collections.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<CollectionListing version={} collections={}>",
+            self.version,
+            self.collections.len()
+        )
+    }
+}
+
+#[pymethods]
+impl PyCollection {
+    fn __len__(&self) -> usize {
+        self.beatmap_md5s.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Collection {:?} beatmaps={}>",
+            self.name.as_deref().unwrap_or_default(),
+            self.beatmap_md5s.len()
+        )
+    }
+}
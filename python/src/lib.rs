@@ -0,0 +1,148 @@
+//! Python bindings for the parser, so that osu!'s database files can be read from Python (e.g. in a notebook).
+//!
+//! Everything is copied into Python objects when a file is parsed, so the objects can be kept around after the file is
+//! gone. Dates are converted to `datetime` objects in UTC, and mods are kept as integers, which can be converted to
+//! acronyms or names with [`mods_to_string`] and [`mod_names`].
+
+use std::path::PathBuf;
+
+use flagset::FlagSet;
+use osu_db_parser::{
+    common::WINDOWS_EPOCH,
+    prelude::{BeatmapFilter, BeatmapListing, CollectionListing, Error, Mods, ScoreListing},
+};
+use pyo3::{
+    create_exception,
+    exceptions::{PyOSError, PyValueError},
+    prelude::*,
+    types::{timezone_utc, PyDateTime},
+};
+use time::OffsetDateTime;
+
+mod beatmaps;
+mod collections;
+mod scores;
+
+use beatmaps::{PyBeatmap, PyBeatmapListing};
+use collections::{PyCollection, PyCollectionListing};
+use scores::{PyScore, PyScoreListing};
+
+create_exception!(
+    osu_db,
+    ParseError,
+    PyValueError,
+    "Raised when a file isn't a valid osu! database."
+);
+
+/// Parses an `osu!.db` file.
+#[pyfunction]
+fn parse_osu_db(py: Python<'_>, path: PathBuf) -> PyResult<PyBeatmapListing> {
+    let listing = py
+        .allow_threads(|| BeatmapListing::from_file(&path))
+        .map_err(to_py_err)?;
+
+    PyBeatmapListing::new(py, listing)
+}
+
+/// Parses a `collection.db` file.
+#[pyfunction]
+fn parse_collections(py: Python<'_>, path: PathBuf) -> PyResult<PyCollectionListing> {
+    let listing = py
+        .allow_threads(|| CollectionListing::from_file(&path))
+        .map_err(to_py_err)?;
+
+    PyCollectionListing::new(py, listing)
+}
+
+/// Parses a `scores.db` file.
+#[pyfunction]
+fn parse_scores(py: Python<'_>, path: PathBuf) -> PyResult<PyScoreListing> {
+    let listing = py
+        .allow_threads(|| ScoreListing::from_file(&path))
+        .map_err(to_py_err)?;
+
+    PyScoreListing::new(py, listing)
+}
+
+/// Finds the beatmaps matching a search query, using the same syntax as osu!'s song select (e.g. `stars>5 mode=mania`).
+#[pyfunction]
+fn filter_beatmaps<'py>(
+    beatmaps: Vec<Bound<'py, PyBeatmap>>,
+    query: &str,
+) -> PyResult<Vec<Bound<'py, PyBeatmap>>> {
+    let filter = parse_query(query)?;
+
+    Ok(beatmaps
+        .into_iter()
+        .filter(|beatmap| filter.matches(&beatmap.get().entry))
+        .collect())
+}
+
+/// Formats mods as they're shown in-game (e.g. `HDDT`), or `NM` if there aren't any.
+#[pyfunction]
+fn mods_to_string(mods: u32) -> String {
+    Mods::acronyms(FlagSet::new_truncated(mods))
+}
+
+/// Gets the names of the mods in a set of mods (e.g. `["Hidden", "DoubleTime"]`).
+#[pyfunction]
+fn mod_names(mods: u32) -> Vec<String> {
+    FlagSet::<Mods>::new_truncated(mods)
+        .into_iter()
+        .map(|m| format!("{:?}", m))
+        .collect()
+}
+
+/// Parses a search query, raising a `ValueError` if it's invalid.
+fn parse_query(query: &str) -> PyResult<BeatmapFilter> {
+    BeatmapFilter::parse(query).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Converts an error from the parser into a Python exception.
+fn to_py_err(error: Error) -> PyErr {
+    match error {
+        Error::IO(e) => PyOSError::new_err(e.to_string()),
+        e => ParseError::new_err(e.to_string()),
+    }
+}
+
+/// Converts a date to a `datetime` in UTC, or `None` if it's unset (i.e. stored as the Windows epoch).
+fn to_datetime(py: Python<'_>, value: OffsetDateTime) -> PyResult<Option<Bound<'_, PyDateTime>>> {
+    if value == WINDOWS_EPOCH {
+        return Ok(None);
+    }
+
+    PyDateTime::new(
+        py,
+        value.year(),
+        value.month() as u8,
+        value.day(),
+        value.hour(),
+        value.minute(),
+        value.second(),
+        value.microsecond(),
+        Some(&timezone_utc(py)),
+    )
+    .map(Some)
+}
+
+/// Reads osu!stable's database files.
+#[pymodule]
+fn osu_db(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse_osu_db, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_collections, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_scores, m)?)?;
+    m.add_function(wrap_pyfunction!(filter_beatmaps, m)?)?;
+    m.add_function(wrap_pyfunction!(mods_to_string, m)?)?;
+    m.add_function(wrap_pyfunction!(mod_names, m)?)?;
+
+    m.add_class::<PyBeatmapListing>()?;
+    m.add_class::<PyBeatmap>()?;
+    m.add_class::<PyCollectionListing>()?;
+    m.add_class::<PyCollection>()?;
+    m.add_class::<PyScoreListing>()?;
+    m.add_class::<PyScore>()?;
+    m.add("ParseError", m.py().get_type::<ParseError>())?;
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use osu_db_parser::prelude::*;
+use pyo3::{prelude::*, types::PyDateTime};
+
+use crate::{mods_to_string, to_datetime};
+
+/// The contents of a `scores.db` file.
+#[pyclass(name = "ScoreListing", module = "osu_db", frozen)]
+pub struct PyScoreListing {
+    #[pyo3(get)]
+    version: u32,
+
+    /// Scores on each beatmap, by the beatmap's MD5 hash
+    by_beatmap: Vec<(String, Vec<Py<PyScore>>)>,
+}
+
+/// A score set on a beatmap.
+#[pyclass(name = "Score", module = "osu_db", frozen)]
+pub struct PyScore {
+    score: ScoreReplay,
+}
+
+impl PyScoreListing {
+    /// Copies a parsed listing into Python objects.
+    pub fn new(py: Python<'_>, listing: ScoreListing) -> PyResult<Self> {
+        let by_beatmap = listing
+            .beatmap_scores
+            .into_iter()
+            .map(|beatmap| {
+                let scores = beatmap
+                    .scores
+                    .into_iter()
+                    .map(|score| Py::new(py, PyScore { score }))
+                    .collect::<PyResult<_>>()?;
+
+                Ok((beatmap.md5.unwrap_or_default(), scores))
+            })
+            .collect::<PyResult<_>>()?;
+
+        Ok(PyScoreListing {
+            version: listing.version,
+            by_beatmap,
+        })
+    }
+}
+
+#[pymethods]
+impl PyScoreListing {
+    /// Every score, in the order they're stored in the file.
+    #[getter]
+    fn scores(&self, py: Python<'_>) -> Vec<Py<PyScore>> {
+        self.by_beatmap
+            .iter()
+            .flat_map(|(_, scores)| scores)
+            .map(|score| score.clone_ref(py))
+            .collect()
+    }
+
+    /// Scores on each beatmap, as a dictionary of the beatmap's MD5 hash to its scores.
+    #[getter]
+    fn by_beatmap(&self, py: Python<'_>) -> HashMap<String, Vec<Py<PyScore>>> {
+        self.by_beatmap
+            .iter()
+            .map(|(md5, scores)| {
+                let scores = scores.iter().map(|score| score.clone_ref(py)).collect();
+                (md5.clone(), scores)
+            })
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.by_beatmap.iter().map(|(_, scores)| scores.len()).sum()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<ScoreListing version={} scores={}>",
+            self.version,
+            self.__len__()
+        )
+    }
+}
+
+#[pymethods]
+impl PyScore {
+    /// Gameplay mode, i.e. `"Standard"`, `"Taiko"`, `"Catch"` or `"Mania"`.
+    #[getter]
+    fn gameplay_mode(&self) -> String {
+        self.score.gameplay_mode.to_string()
+    }
+
+    #[getter]
+    fn version(&self) -> u32 {
+        self.score.version
+    }
+
+    #[getter]
+    fn beatmap_md5(&self) -> Option<&str> {
+        self.score.beatmap_md5.as_deref()
+    }
+
+    #[getter]
+    fn player_name(&self) -> Option<&str> {
+        self.score.player_name.as_deref()
+    }
+
+    #[getter]
+    fn replay_md5(&self) -> Option<&str> {
+        self.score.replay_md5.as_deref()
+    }
+
+    #[getter]
+    fn hits_300(&self) -> u16 {
+        self.score.hits_300
+    }
+
+    #[getter]
+    fn hits_100(&self) -> u16 {
+        self.score.hits_100
+    }
+
+    #[getter]
+    fn hits_50(&self) -> u16 {
+        self.score.hits_50
+    }
+
+    #[getter]
+    fn hits_geki(&self) -> u16 {
+        self.score.hits_geki
+    }
+
+    #[getter]
+    fn hits_katu(&self) -> u16 {
+        self.score.hits_katu
+    }
+
+    #[getter]
+    fn misses(&self) -> u16 {
+        self.score.misses
+    }
+
+    #[getter]
+    fn score(&self) -> u32 {
+        self.score.score
+    }
+
+    #[getter]
+    fn max_combo(&self) -> u16 {
+        self.score.max_combo
+    }
+
+    #[getter]
+    fn is_perfect_combo(&self) -> bool {
+        self.score.is_perfect_combo
+    }
+
+    /// Mods used, as an integer of osu!'s mod flags.
+    #[getter]
+    fn mods(&self) -> u32 {
+        self.score.mods.bits()
+    }
+
+    /// Mods used, as shown in-game (e.g. `HDDT`).
+    #[getter]
+    fn mods_string(&self) -> String {
+        mods_to_string(self.score.mods.bits())
+    }
+
+    /// Accuracy, as a percentage.
+    #[getter]
+    fn accuracy(&self) -> f64 {
+        self.score.accuracy()
+    }
+
+    #[getter]
+    fn grade(&self) -> String {
+        self.score.grade().to_string()
+    }
+
+    #[getter]
+    fn timestamp<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDateTime>>> {
+        to_datetime(py, self.score.timestamp)
+    }
+
+    #[getter]
+    fn online_score_id(&self) -> u64 {
+        self.score.online_score_id
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Score {} {} {:.2}% {}>",
+            self.score.player_name.as_deref().unwrap_or_default(),
+            self.score.score,
+            self.score.accuracy(),
+            mods_to_string(self.score.mods.bits())
+        )
+    }
+}
@@ -0,0 +1,140 @@
+"""Builds small database files for the tests, in the same formats that osu! writes them."""
+
+import struct
+from datetime import datetime, timedelta
+
+import pytest
+
+VERSION = 20211031
+
+# .NET ticks, i.e. 100ns intervals since 0001-01-01, which is how osu! stores dates
+TICKS = (datetime(2021, 3, 4, 5, 6, 7) - datetime(1, 1, 1)) // timedelta(microseconds=1) * 10
+
+HIDDEN = 1 << 3
+DOUBLE_TIME = 1 << 6
+
+
+def uleb128(value):
+    output = bytearray()
+
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+
+        if value == 0:
+            output.append(byte)
+            return bytes(output)
+
+        output.append(byte | 0x80)
+
+
+def string(value):
+    if value is None:
+        return b"\x00"
+
+    data = value.encode("utf-8")
+    return b"\x0b" + uleb128(len(data)) + data
+
+
+def star_ratings(ratings):
+    output = struct.pack("<I", len(ratings))
+
+    for mods, rating in ratings:
+        output += b"\x08" + struct.pack("<I", mods) + b"\x0d" + struct.pack("<d", rating)
+
+    return output
+
+
+def beatmap(artist, title, difficulty, md5, beatmapset_id, stars, mode=0, status=4):
+    return b"".join(
+        [
+            string(artist),
+            string(artist),
+            string(title),
+            string(title),
+            string("Mapper"),
+            string(difficulty),
+            string("audio.mp3"),
+            string(md5),
+            string(f"{artist} - {title} ({difficulty}).osu"),
+            struct.pack("<B3H", status, 100, 50, 1),
+            struct.pack("<Q", TICKS),
+            struct.pack("<4fd", 9.0, 4.0, 6.0, 8.0, 1.4),
+            star_ratings([(0, stars), (DOUBLE_TIME, stars * 1.4)] if mode == 0 else []),
+            star_ratings([]),
+            star_ratings([]),
+            star_ratings([(0, stars)] if mode == 3 else []),
+            struct.pack("<3I", 90, 95_000, 30_000),
+            struct.pack("<I", 1) + struct.pack("<dd?", 500.0, 0.0, True),  # 120 BPM
+            struct.pack("<3I", beatmapset_id * 10, beatmapset_id, 0),
+            struct.pack("<4B", 3, 9, 9, 9),  # Grades
+            struct.pack("<HfB", 0, 0.7, mode),
+            string("Source"),
+            string("tag1 tag2"),
+            struct.pack("<H", 0),
+            string(None),
+            struct.pack("<?Q?", False, TICKS, False),
+            string(f"{beatmapset_id} {artist} - {title}"),
+            struct.pack("<Q", 0),  # Never checked online
+            struct.pack("<5?", False, False, False, False, False),
+            struct.pack("<IB", 0, 0),
+        ]
+    )
+
+
+@pytest.fixture(scope="session")
+def osu_db_path(tmp_path_factory):
+    beatmaps = [
+        beatmap("Artist", "Easy Song", "Normal", "a" * 32, 1, 2.5),
+        beatmap("Artist", "Hard Song", "Insane", "b" * 32, 2, 5.75),
+        beatmap("Other", "Keys", "4K Hard", "c" * 32, 3, 3.2, mode=3, status=7),
+    ]
+
+    data = struct.pack("<II?Q", VERSION, 3, True, 0) + string("Player")
+    data += struct.pack("<I", len(beatmaps)) + b"".join(beatmaps) + struct.pack("<I", 1)
+
+    path = tmp_path_factory.mktemp("fixtures") / "osu!.db"
+    path.write_bytes(data)
+    return path
+
+
+@pytest.fixture(scope="session")
+def collection_db_path(tmp_path_factory):
+    data = struct.pack("<II", VERSION, 2)
+    data += string("Favourites") + struct.pack("<I", 2) + string("a" * 32) + string("b" * 32)
+    data += string("Empty") + struct.pack("<I", 0)
+
+    path = tmp_path_factory.mktemp("fixtures") / "collection.db"
+    path.write_bytes(data)
+    return path
+
+
+def score(md5, player, points, mods):
+    return b"".join(
+        [
+            struct.pack("<BI", 0, VERSION),
+            string(md5),
+            string(player),
+            string("f" * 32),
+            struct.pack("<6H", 95, 5, 0, 10, 2, 0),
+            struct.pack("<IH?I", points, 150, True, mods),
+            string(None),  # Lifebar graph
+            struct.pack("<Q", TICKS),
+            struct.pack("<i", -1),  # No replay data
+            struct.pack("<Q", 12345),
+        ]
+    )
+
+
+@pytest.fixture(scope="session")
+def scores_db_path(tmp_path_factory):
+    data = struct.pack("<II", VERSION, 2)
+    data += string("a" * 32) + struct.pack("<I", 2)
+    data += score("a" * 32, "Player", 1_000_000, HIDDEN | DOUBLE_TIME)
+    data += score("a" * 32, "Player", 500_000, 0)
+    data += string("b" * 32) + struct.pack("<I", 1)
+    data += score("b" * 32, "Player", 750_000, 0)
+
+    path = tmp_path_factory.mktemp("fixtures") / "scores.db"
+    path.write_bytes(data)
+    return path
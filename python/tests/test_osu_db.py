@@ -0,0 +1,100 @@
+from datetime import datetime, timezone
+
+import osu_db
+import pytest
+
+PLAYED = datetime(2021, 3, 4, 5, 6, 7, tzinfo=timezone.utc)
+
+
+def test_beatmaps_are_parsed(osu_db_path):
+    listing = osu_db.parse_osu_db(osu_db_path)
+
+    assert listing.version == 20211031
+    assert listing.player_name == "Player"
+    assert listing.account_unlock_date is None
+    assert len(listing) == 3
+
+    beatmap = listing.beatmaps[1]
+    assert beatmap.artist == "Artist"
+    assert beatmap.title == "Hard Song"
+    assert beatmap.difficulty == "Insane"
+    assert beatmap.md5 == "b" * 32
+    assert beatmap.beatmapset_id == 2
+    assert beatmap.difficulty_id == 20
+    assert beatmap.ranked_status == "Ranked"
+    assert beatmap.gameplay_mode == "Standard"
+    assert beatmap.star_rating == pytest.approx(5.75)
+    assert beatmap.star_ratings["Standard"][64] == pytest.approx(5.75 * 1.4)
+    assert beatmap.star_ratings["Taiko"] == {}
+    assert beatmap.bpm == pytest.approx(120.0)
+    assert beatmap.timing_points == [(500.0, 0.0, True)]
+    assert beatmap.grade == "S"
+    assert beatmap.grades["Mania"] == "Unplayed"
+    assert beatmap.url == "https://osu.ppy.sh/beatmapsets/2#osu/20"
+    assert repr(beatmap) == "<Beatmap Artist - Hard Song [Insane]>"
+
+
+def test_dates_are_datetimes(osu_db_path):
+    beatmap = osu_db.parse_osu_db(osu_db_path).beatmaps[0]
+
+    assert beatmap.last_played == PLAYED
+    assert beatmap.last_modified == PLAYED
+    assert beatmap.last_played.tzinfo == timezone.utc
+
+    # Unset dates are stored as 0001-01-01
+    assert beatmap.last_checked_online is None
+
+
+def test_beatmaps_can_be_filtered(osu_db_path):
+    listing = osu_db.parse_osu_db(osu_db_path)
+
+    assert [b.title for b in listing.filter("stars>3 mode=osu")] == ["Hard Song"]
+    assert [b.title for b in listing.filter("status=loved")] == ["Keys"]
+    assert [b.title for b in osu_db.filter_beatmaps(listing.beatmaps, "easy")] == ["Easy Song"]
+
+    with pytest.raises(ValueError):
+        listing.filter("colour=red")
+
+
+def test_collections_are_parsed(collection_db_path):
+    listing = osu_db.parse_collections(collection_db_path)
+
+    assert listing.version == 20211031
+    assert [c.name for c in listing.collections] == ["Favourites", "Empty"]
+    assert listing.collections[0].beatmap_md5s == ["a" * 32, "b" * 32]
+    assert len(listing.collections[1]) == 0
+
+
+def test_scores_are_parsed(scores_db_path):
+    listing = osu_db.parse_scores(scores_db_path)
+
+    assert len(listing) == 3
+    assert len(listing.by_beatmap["a" * 32]) == 2
+
+    score = listing.scores[0]
+    assert score.player_name == "Player"
+    assert score.beatmap_md5 == "a" * 32
+    assert score.score == 1_000_000
+    assert score.max_combo == 150
+    assert score.mods == (1 << 3) | (1 << 6)
+    assert score.mods_string == "HDDT"
+    assert score.accuracy == pytest.approx((95 * 300 + 5 * 100) / (100 * 300) * 100)
+    assert score.timestamp == PLAYED
+    assert score.online_score_id == 12345
+
+
+def test_mods_can_be_named():
+    assert osu_db.mods_to_string(0) == "NM"
+    assert osu_db.mods_to_string(8 | 16) == "HDHR"
+    assert osu_db.mod_names(8 | 64) == ["Hidden", "DoubleTime"]
+
+
+def test_invalid_files_raise_errors(tmp_path):
+    path = tmp_path / "osu!.db"
+    path.write_bytes(b"\x01\x02")
+
+    with pytest.raises(osu_db.ParseError):
+        osu_db.parse_osu_db(path)
+
+    with pytest.raises(OSError):
+        osu_db.parse_osu_db(tmp_path / "missing.db")
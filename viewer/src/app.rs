@@ -1,33 +1,218 @@
-use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use egui::Id;
-use osu_db_parser::{flagset, prelude::*};
+#[cfg(not(target_arch = "wasm32"))]
+use osu_db_parser::paths::OsuFolder;
+#[cfg(not(target_arch = "wasm32"))]
+use osu_db_parser::shared;
+#[cfg(not(target_arch = "wasm32"))]
+use osu_db_parser::synthetic::{self, SyntheticOptions};
+use osu_db_parser::{detect::FileKind, flagset, links, prelude::*};
 
-use crate::widgets::file_dialog::FileDialog;
+use crate::{
+    i18n::{self, tr},
+    widgets::file_dialog::FileDialog,
+};
 
 use self::{
-    beatmap_listing::BeatmapListingView, collection_listing::CollectionListingView,
-    replays::ReplaysView, score_details::ScoreDetailsWindow,
+    actions::{ActionContext, ActionId, Menu},
+    beatmap_listing::BeatmapListingView,
+    collection_listing::{CollectionListingView, CollectionRequest},
+    command_palette::CommandPalette,
+    comparison::ComparisonWindow,
+    filter_bar::FilterBar,
+    library::{LibrarySnapshot, LibraryWatch, SharedLibrary},
+    loader::{ListingLoader, LoadError, LoadErrorDialog, LoadErrorResponse},
+    notes::NoteStore,
+    overview::OverviewView,
+    performance::PerformanceOverlay,
+    replays::ReplaysView,
+    score_details::ScoreDetailsWindow,
+    score_listing::ScoreListingView,
+    settings::{Settings, SettingsWindow},
+    status_bar::{timed, StatusBar},
+    workspace::WorkspaceWindow,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use self::{
+    backups::RestoreBackupWindow,
+    disk_usage::DiskUsageView,
+    file_watcher::FileWatcher,
+    fonts::FontFallback,
+    health::HealthView,
+    setup_wizard::{SetupWizard, WizardResponse},
 };
 
+mod accessibility;
+mod actions;
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+mod audio;
+#[cfg(not(target_arch = "wasm32"))]
+mod backups;
+mod beatmap_actions;
 mod beatmap_details;
 mod beatmap_listing;
+mod beatmap_panel;
+mod beatmap_table;
+#[cfg(not(target_arch = "wasm32"))]
+mod bulk_edit;
 mod collection_listing;
+mod command_palette;
+mod comparison;
+#[cfg(not(target_arch = "wasm32"))]
+mod debug;
+mod diagnostics;
+#[cfg(not(target_arch = "wasm32"))]
+mod disk_usage;
+mod export;
+#[cfg(not(target_arch = "wasm32"))]
+mod file_watcher;
+mod filter_bar;
+#[cfg(not(target_arch = "wasm32"))]
+mod fonts;
+#[cfg(not(target_arch = "wasm32"))]
+mod health;
+mod import_list;
+mod jump_to;
+mod library;
+mod loader;
+mod mode_tabs;
+mod notes;
+mod overview;
+mod performance;
+mod pinboard;
+mod presets;
+mod random_pick;
+#[cfg(not(target_arch = "wasm32"))]
+mod raw_entry;
 mod replays;
+#[cfg(not(target_arch = "wasm32"))]
+mod report;
+mod score_columns;
 mod score_details;
+mod score_listing;
+mod search;
+mod settings;
+#[cfg(not(target_arch = "wasm32"))]
+mod setup_wizard;
+mod status_bar;
+mod text_cache;
+#[cfg(not(target_arch = "wasm32"))]
+mod thumbnails;
+mod timing_points;
+mod workspace;
+
+/// Storage key for the beatmap table's columns and sorting.
+const TABLE_LAYOUT_KEY: &str = "beatmap_table_layout";
+
+/// Storage key for the application settings. On native platforms, the settings are saved to the config folder instead.
+#[cfg(target_arch = "wasm32")]
+const SETTINGS_KEY: &str = "settings";
+
+/// Storage key for the user's notes on the web. On native platforms, the notes are saved to the data folder instead.
+#[cfg(target_arch = "wasm32")]
+const NOTES_KEY: &str = "notes";
+
+/// Storage key for the actions recently run from the menus and command palette, which the palette lists first.
+const RECENT_ACTIONS_KEY: &str = "recent_actions";
+
+/// Storage key for the recently opened files and folders.
+#[cfg(not(target_arch = "wasm32"))]
+const RECENT_FILES_KEY: &str = "recent_files";
+
+/// Number of recently opened files and folders to remember.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_RECENT_FILES: usize = 10;
+
+/// Number of beatmaps in the demo library.
+#[cfg(not(target_arch = "wasm32"))]
+const DEMO_BEATMAPS: usize = 5000;
 
 /// Holds the state for the main application.
 pub struct MainApp {
     // File Loading
     file_dialog: FileDialog,
     pending_file_operation: Option<FileOperation>,
+    loader: Option<ListingLoader>,
+    load_error: Option<LoadErrorDialog>,
+
+    /// How long the `osu.db` being loaded took to parse, kept until it's loaded since the user may be asked whether to
+    /// load a damaged file first
+    parse_time: Option<Duration>,
+
+    /// Comparison between the loaded `osu.db` and another one
+    comparison: Option<ComparisonWindow>,
+
+    /// The loaded `osu.db` combined with others, once one has been added
+    workspace: Option<WorkspaceWindow>,
+
+    settings: Settings,
+    settings_window: SettingsWindow,
+    status_bar: StatusBar,
+
+    /// Looks for the osu! installation the first time the viewer runs
+    #[cfg(not(target_arch = "wasm32"))]
+    setup_wizard: SetupWizard,
+
+    /// Lists the backups made of the open databases before they were saved
+    #[cfg(not(target_arch = "wasm32"))]
+    restore_backup: RestoreBackupWindow,
+
+    /// Frame times and text cache hit rate, for noticing when scrolling gets slower
+    performance: PerformanceOverlay,
+
+    /// Runs any action by name, opened with Ctrl+Shift+P
+    palette: CommandPalette,
+
+    /// The user's notes and bookmarks for beatmaps
+    notes: NoteStore,
+
+    /// Fallback font for Chinese, Japanese and Korean text
+    #[cfg(not(target_arch = "wasm32"))]
+    fonts: FontFallback,
+
+    /// `osu.db` file that is being loaded, whose folder is used as the osu! installation folder
+    #[cfg(not(target_arch = "wasm32"))]
+    loading_path: Option<PathBuf>,
+
+    /// Recently opened files and folders, most recent first
+    #[cfg(not(target_arch = "wasm32"))]
+    recent_files: Vec<PathBuf>,
+
+    /// Watches the loaded files for changes made while the viewer is open, e.g. by osu!
+    #[cfg(not(target_arch = "wasm32"))]
+    watcher: FileWatcher,
+
+    /// `osu.db` file being reloaded in the background after it changed on disk, along with its path
+    #[cfg(not(target_arch = "wasm32"))]
+    reloader: Option<(ListingLoader, PathBuf)>,
+
+    /// Whether a reproduction from a bug report has been loaded, in which case the settings are the reporter's and
+    /// aren't saved
+    #[cfg(not(target_arch = "wasm32"))]
+    repro_loaded: bool,
 
     // Views
     current_view: ViewType,
     beatmap_listing: BeatmapListingView,
+    overview: OverviewView,
+    #[cfg(not(target_arch = "wasm32"))]
+    health: HealthView,
+    #[cfg(not(target_arch = "wasm32"))]
+    disk_usage: DiskUsageView,
     collection_listing: CollectionListingView,
+    score_listing: ScoreListingView,
     replays: ReplaysView,
 
+    /// The loaded files, which background jobs take snapshots of
+    library: SharedLibrary,
+    library_watch: LibraryWatch,
+
+    /// Version of the library that the views were last refreshed for
+    library_snapshot: Arc<LibrarySnapshot>,
+
     // MD5 Lookups
     beatmaps: HashMap<String, BeatmapEntry>,
     scores: HashMap<String, Vec<ScoreReplay>>,
@@ -37,10 +222,34 @@ pub struct MainApp {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum ViewType {
     BeatmapListing,
+    Overview,
+    #[cfg(not(target_arch = "wasm32"))]
+    Health,
+    #[cfg(not(target_arch = "wasm32"))]
+    DiskUsage,
     CollectionListing,
+    ScoreListing,
     Replays,
 }
 
+impl ViewType {
+    /// Gets the view that an action switches to, if it's one of the actions for the tabs.
+    fn from_action(id: ActionId) -> Option<ViewType> {
+        match id {
+            ActionId::ShowBeatmapListing => Some(ViewType::BeatmapListing),
+            ActionId::ShowOverview => Some(ViewType::Overview),
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::ShowHealth => Some(ViewType::Health),
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::ShowDiskUsage => Some(ViewType::DiskUsage),
+            ActionId::ShowCollections => Some(ViewType::CollectionListing),
+            ActionId::ShowScores => Some(ViewType::ScoreListing),
+            ActionId::ShowReplays => Some(ViewType::Replays),
+            _ => None,
+        }
+    }
+}
+
 /// Represents a file operation requested by the user.
 #[derive(Clone, Copy, Debug)]
 enum FileOperation {
@@ -48,19 +257,113 @@ enum FileOperation {
     GetCollectionListing,
     GetScoreListing,
     GetReplay,
+
+    /// Get an `osu.db` to compare the loaded one with
+    GetComparisonListing,
+
+    /// Get an `osu.db` to add to the workspace alongside the loaded one
+    GetWorkspaceListing,
+}
+
+impl FileOperation {
+    /// Gets a description of the file being opened, for error messages.
+    fn file_description(&self) -> &'static str {
+        use FileOperation::*;
+
+        match self {
+            GetBeatmapListing => "osu.db",
+            GetCollectionListing => "collection.db",
+            GetScoreListing => "scores.db",
+            GetReplay => ".osr replay",
+            GetComparisonListing => "osu.db to compare with",
+            GetWorkspaceListing => "osu.db to add",
+        }
+    }
+
+    /// Gets the kind of file being opened.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn file_kind(&self) -> FileKind {
+        use FileOperation::*;
+
+        match self {
+            GetBeatmapListing | GetComparisonListing | GetWorkspaceListing => FileKind::Beatmaps,
+            GetCollectionListing => FileKind::Collections,
+            GetScoreListing => FileKind::Scores,
+            GetReplay => FileKind::Replay,
+        }
+    }
+
+    /// Gets the operation used to load a kind of file.
+    fn from_kind(kind: FileKind) -> FileOperation {
+        match kind {
+            FileKind::Beatmaps => FileOperation::GetBeatmapListing,
+            FileKind::Collections => FileOperation::GetCollectionListing,
+            FileKind::Scores => FileOperation::GetScoreListing,
+            FileKind::Replay => FileOperation::GetReplay,
+        }
+    }
 }
 
 impl Default for MainApp {
     fn default() -> Self {
+        let library = SharedLibrary::default();
+
         Self {
             file_dialog: FileDialog::default(),
             pending_file_operation: None,
+            loader: None,
+            load_error: None,
+            parse_time: None,
+            comparison: None,
+            workspace: None,
+
+            settings: Settings::default(),
+            settings_window: SettingsWindow::default(),
+            status_bar: StatusBar::default(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            setup_wizard: SetupWizard::default(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            restore_backup: RestoreBackupWindow::default(),
+            performance: PerformanceOverlay::default(),
+            palette: CommandPalette::default(),
+
+            notes: NoteStore::default(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            fonts: FontFallback::default(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            loading_path: None,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            recent_files: Vec::new(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            watcher: FileWatcher::default(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            reloader: None,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            repro_loaded: false,
 
             current_view: ViewType::BeatmapListing,
             beatmap_listing: BeatmapListingView::default(),
+            overview: OverviewView::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            health: HealthView::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            disk_usage: DiskUsageView::default(),
             collection_listing: CollectionListingView::default(),
+            score_listing: ScoreListingView::default(),
             replays: ReplaysView::default(),
 
+            library_watch: library.subscribe(),
+            library_snapshot: library.snapshot(),
+            library,
+
             beatmaps: HashMap::new(),
             scores: HashMap::new(),
         }
@@ -68,138 +371,1316 @@ impl Default for MainApp {
 }
 
 impl eframe::App for MainApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(
+            storage,
+            TABLE_LAYOUT_KEY,
+            self.beatmap_listing.table_layout(),
+        );
+
+        eframe::set_value(storage, RECENT_ACTIONS_KEY, &self.palette.recent());
+
+        #[cfg(not(target_arch = "wasm32"))]
+        eframe::set_value(storage, RECENT_FILES_KEY, &self.recent_files);
+
+        #[cfg(target_arch = "wasm32")]
+        eframe::set_value(storage, SETTINGS_KEY, &self.settings);
+
+        #[cfg(target_arch = "wasm32")]
+        eframe::set_value(storage, NOTES_KEY, self.notes.notes().as_ref());
+
+        self.notes.save();
+    }
+
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        self.check_for_files();
+        self.performance.handle_key(ctx);
+        self.performance.record(
+            frame.info().cpu_usage,
+            self.beatmap_listing.take_text_cache_stats(),
+        );
+
+        self.check_for_files(ctx);
+        self.check_dropped_files(ctx);
+
+        if accessibility::handle_zoom_keys(ctx, &mut self.settings.ui_scale) {
+            self.apply_settings(ctx);
+            self.save_settings();
+        }
+
+        self.check_loader(ctx);
         self.menu_bar(ctx, frame);
 
+        // The palette is shown before the views, so that its keys aren't taken by the beatmap table
+        self.palette.handle_key(ctx);
+        if self.palette.visible {
+            let context = self.action_context();
+            if let Some(id) = self.palette.view(ctx, &context) {
+                self.run_action(ctx, id);
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.check_for_changes(ctx);
+
+        self.check_library();
+
+        let listing = self.beatmap_listing.listing().map(Arc::as_ref);
+        self.status_bar
+            .view(ctx, listing, self.beatmap_listing.shown_count());
+
+        if self.settings_window.view(ctx, &mut self.settings) {
+            self.apply_settings(ctx);
+            self.save_settings();
+        }
+
+        // Either way, the settings are saved so that the wizard isn't shown again
+        #[cfg(not(target_arch = "wasm32"))]
+        match self.setup_wizard.view(ctx) {
+            Some(WizardResponse::Use(installation)) => {
+                self.settings.osu_folder = Some(installation.root.clone());
+                self.settings.songs_folder = installation.configured_songs;
+                self.save_settings();
+                self.open_path(ctx, &installation.root);
+            }
+            Some(WizardResponse::Skip) => self.save_settings(),
+            None => {}
+        }
+
+        if let Some(comparison) = &mut self.comparison {
+            comparison.view(ctx);
+
+            if !comparison.visible {
+                self.comparison = None;
+            }
+        }
+
+        if let Some(workspace) = &mut self.workspace {
+            workspace.view(ctx);
+        }
+
+        // The restored file is opened again, rather than waiting for it to be noticed as changed
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = self.restore_backup.view(ctx, &self.settings.value_format()) {
+            self.open_path(ctx, &path);
+        }
+
         // Determine which view to show
         match self.current_view {
-            ViewType::BeatmapListing => self.beatmap_listing.view(ctx, &self.scores),
+            ViewType::BeatmapListing => {
+                #[cfg(not(target_arch = "wasm32"))]
+                self.collection_listing.confirm_save_window(ctx);
+
+                let request = self.beatmap_listing.view(
+                    ctx,
+                    &self.scores,
+                    self.collection_listing.membership(),
+                    &mut self.notes,
+                    &mut self.settings.filter_presets,
+                );
+
+                match request {
+                    Some(CollectionRequest::Add(request)) => {
+                        let created = self.collection_listing.add_beatmaps(request);
+
+                        // Switch to the new collection so that it can be renamed
+                        if created {
+                            self.current_view = ViewType::CollectionListing;
+                        }
+                    }
+                    Some(CollectionRequest::Remove(request)) => {
+                        self.collection_listing.remove_beatmaps(request)
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Some(CollectionRequest::Save) => self.collection_listing.request_save(),
+                    _ => {}
+                }
+
+                if self.beatmap_listing.take_presets_changed() {
+                    self.save_settings();
+                }
+            }
+            ViewType::Overview => {
+                // Show the beatmaps in the chart category that was clicked
+                if let Some(filter) = self.overview.view(ctx) {
+                    let (query, filter_bar) = filter.into_search();
+                    self.beatmap_listing.set_filter(query, filter_bar);
+                    self.current_view = ViewType::BeatmapListing;
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            ViewType::Health => {
+                // Show the beatmap that a finding is about
+                if let Some(index) =
+                    self.health
+                        .view(ctx, self.beatmap_listing.listing(), self.notes.notes())
+                {
+                    self.beatmap_listing.show_beatmap(index);
+                    self.current_view = ViewType::BeatmapListing;
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            ViewType::DiskUsage => {
+                // Show the difficulties of the beatmapset that was clicked
+                if let Some(query) = self.disk_usage.view(ctx, self.beatmap_listing.listing()) {
+                    self.beatmap_listing.set_filter(query, FilterBar::default());
+                    self.current_view = ViewType::BeatmapListing;
+                }
+            }
             ViewType::CollectionListing => {
                 self.collection_listing
-                    .view(ctx, &self.beatmaps, &self.scores)
+                    .view(ctx, self.beatmap_listing.listing(), &self.scores)
             }
+            ViewType::ScoreListing => self.score_listing.view(ctx, &self.beatmaps),
             ViewType::Replays => self.replays.view(ctx),
         }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = self.collection_listing.take_saved() {
+            self.watcher.saved(path);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = self.beatmap_listing.take_saved() {
+            self.watcher.saved(path);
+        }
+
+        self.performance.view(ctx);
     }
 }
 
 impl MainApp {
+    /// Creates the application, restoring any settings saved in a previous session.
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = Self::default();
+
+        if let Some(layout) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, TABLE_LAYOUT_KEY))
+        {
+            app.beatmap_listing.set_table_layout(layout);
+        }
+
+        if let Some(recent_actions) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, RECENT_ACTIONS_KEY))
+        {
+            app.palette.set_recent(recent_actions);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(recent_files) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, RECENT_FILES_KEY))
+        {
+            app.recent_files = recent_files;
+        }
+
+        // Without any saved settings, this is the first run, so help the user find their osu! installation
+        #[cfg(not(target_arch = "wasm32"))]
+        if !Settings::exist() {
+            app.setup_wizard.open();
+        }
+
+        // Corrupt settings are replaced with the defaults, rather than stopping the application from starting
+        #[cfg(not(target_arch = "wasm32"))]
+        match Settings::load() {
+            Ok(settings) => app.settings = settings,
+            Err(e) => {
+                log::warn!("Unable to load settings: {}", e);
+                app.settings_window.set_notice(e.to_string());
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        if let Some(settings) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, SETTINGS_KEY))
+        {
+            app.settings = settings;
+        }
+
+        // Notes that can't be read are left alone, so that they aren't replaced by the next save
+        #[cfg(not(target_arch = "wasm32"))]
+        match NoteStore::load() {
+            Ok(notes) => app.notes = notes,
+            Err(e) => log::warn!("Unable to load notes: {}", e),
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        if let Some(notes) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, NOTES_KEY))
+        {
+            app.notes = NoteStore::new(notes);
+        }
+
+        app.apply_settings(&cc.egui_ctx);
+        app
+    }
+
+    /// Gets the default osu! folder from the settings, if it still exists.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn default_osu_folder(&self) -> Option<PathBuf> {
+        self.settings
+            .osu_folder
+            .clone()
+            .filter(|folder| folder.is_dir())
+    }
+
+    /// Saves the settings to the config folder. On the web, they're saved along with the rest of the application's
+    /// state instead.
+    fn save_settings(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.repro_loaded {
+            return;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(e) = self.settings.save() {
+            log::error!("Unable to save settings: {}", e);
+        }
+    }
+
+    /// Applies the settings to every view.
+    fn apply_settings(&mut self, ctx: &egui::Context) {
+        ctx.set_theme(self.settings.theme.preference());
+        accessibility::apply(ctx, self.settings.ui_scale, self.settings.high_contrast);
+        i18n::set_language(self.settings.language);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.fonts.apply(ctx, self.settings.cjk_font.as_deref());
+            self.settings_window.loaded_font = self.fonts.loaded.clone();
+            self.health.set_language(self.settings.metadata_language());
+            self.disk_usage
+                .set_language(self.settings.metadata_language());
+        }
+
+        self.beatmap_listing.apply_settings(&self.settings);
+        self.collection_listing.apply_settings(&self.settings);
+        self.score_listing
+            .set_language(self.settings.metadata_language());
+
+        if let Some(workspace) = &mut self.workspace {
+            workspace.set_language(self.settings.metadata_language());
+        }
+    }
+
+    /// Opens a file or osu! installation folder, working out what kind of file it is from its name or contents.
+    ///
+    /// This is used for files passed on the command line, dropped onto the window or picked from the recent files.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_path(&mut self, ctx: &egui::Context, path: &Path) {
+        self.add_recent_file(path);
+
+        if path.is_dir() {
+            self.open_osu_folder(ctx, path);
+            return;
+        }
+
+        let name = path.file_name().map(|name| name.to_string_lossy());
+
+        // Databases that osu! might be writing can only be retried once their kind is known from the name
+        let data = match name.as_deref().and_then(FileKind::from_file_name) {
+            Some(kind) => shared::read_database(path, kind).map_err(LoadError::Parser),
+            None => shared::read(path).map_err(LoadError::IO),
+        };
+
+        match data {
+            Ok(data) => match FileKind::detect(name.as_deref(), &data) {
+                Some(kind) => self.load_file(ctx, FileOperation::from_kind(kind), data, Some(path)),
+                None => {
+                    self.show_load_error(&name.unwrap_or_default(), LoadError::Unrecognised, None)
+                }
+            },
+            Err(e) => self.show_load_error(&name.unwrap_or_default(), e, None),
+        }
+    }
+
+    /// Opens the database files in an osu! installation folder, and uses it to locate beatmap folders and replays.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_osu_folder(&mut self, ctx: &egui::Context, path: &Path) {
+        let Some(folder) = OsuFolder::discover(path) else {
+            self.show_load_error(&path.display().to_string(), LoadError::NotOsuFolder, None);
+            return;
+        };
+
+        self.set_osu_directory(&folder.root);
+
+        if let Some(scores) = folder.scores.as_ref().filter(|_| self.settings.load_scores) {
+            self.open_file(ctx, FileOperation::GetScoreListing, scores);
+        }
+
+        // collection.db is loaded along with osu.db once it has been parsed
+        match &folder.beatmaps {
+            Some(beatmaps) => self.open_file(ctx, FileOperation::GetBeatmapListing, beatmaps),
+            None => self.load_collections_alongside(&folder.root),
+        }
+    }
+
+    /// Reads a file of a known kind and loads it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_file(&mut self, ctx: &egui::Context, file_operation: FileOperation, path: &Path) {
+        match shared::read_database(path, file_operation.file_kind()) {
+            Ok(data) => self.load_file(ctx, file_operation, data, Some(path)),
+            Err(e) => self.show_load_error(
+                file_operation.file_description(),
+                LoadError::Parser(e),
+                None,
+            ),
+        }
+    }
+
+    /// Moves a path to the top of the recent files, removing the oldest one if the list is full.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn add_recent_file(&mut self, path: &Path) {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        self.recent_files.retain(|recent| *recent != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Sets the osu! installation folder for every view, which is used to locate beatmap folders and replays.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_osu_directory(&mut self, osu_directory: &Path) {
+        self.beatmap_listing
+            .set_osu_directory(Some(osu_directory.to_path_buf()));
+        self.collection_listing
+            .set_osu_directory(Some(osu_directory.to_path_buf()));
+        self.health
+            .set_osu_directory(Some(osu_directory.to_path_buf()));
+        self.disk_usage
+            .set_osu_directory(Some(osu_directory.to_path_buf()));
+        self.score_listing
+            .set_osu_directory(Some(osu_directory.to_path_buf()));
+    }
+
+    /// Opens any files that have been dropped onto the window, and shows a hint while files are being dragged over it.
+    fn check_dropped_files(&mut self, ctx: &egui::Context) {
+        let (hovering, dropped_files) =
+            ctx.input(|i| (!i.raw.hovered_files.is_empty(), i.raw.dropped_files.clone()));
+
+        if hovering {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                Id::new("file_drop_overlay"),
+            ));
+
+            let screen_rect = ctx.screen_rect();
+            painter.rect_filled(screen_rect, 0.0, egui::Color32::from_black_alpha(192));
+            painter.text(
+                screen_rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop osu!.db, collection.db, scores.db, a replay or the osu! folder to open it",
+                egui::TextStyle::Heading.resolve(&ctx.style()),
+                egui::Color32::WHITE,
+            );
+        }
+
+        for file in dropped_files {
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(path) = &file.path {
+                self.open_path(ctx, path);
+                continue;
+            }
+
+            // On the web, dropped files are only available as bytes
+            if let Some(bytes) = &file.bytes {
+                match FileKind::detect(Some(&file.name), bytes) {
+                    Some(kind) => self.load_file(
+                        ctx,
+                        FileOperation::from_kind(kind),
+                        bytes.to_vec(),
+                        #[cfg(not(target_arch = "wasm32"))]
+                        None,
+                    ),
+                    None => self.show_load_error(&file.name, LoadError::Unrecognised, None),
+                }
+            }
+        }
+    }
+
     /// Checks if we are waiting for a file and attempts to parse it if it has been loaded.
-    fn check_for_files(&mut self) {
+    fn check_for_files(&mut self, ctx: &egui::Context) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(file_operation) = self.pending_file_operation {
+            if let Some(e) = self.file_dialog.take_error() {
+                self.show_load_error(file_operation.file_description(), LoadError::IO(e), None);
+                self.pending_file_operation = None;
+            }
+        }
+
         if let Some(file_operation) = self.pending_file_operation {
             if let Some(data) = self.file_dialog.get() {
-                match file_operation {
-                    FileOperation::GetBeatmapListing => match BeatmapListing::from_bytes(&data) {
-                        Ok(beatmap_listing) => {
-                            // Setup the MD5 mapping for the loaded beatmaps
-                            self.beatmaps = beatmap_listing
-                                .beatmaps
-                                .iter()
-                                .filter_map(|b| b.md5.as_ref().map(|md5| (md5.clone(), b.clone())))
-                                .collect();
-
-                            // Update any window titles for the replays view
-                            self.replays.update_replay_titles(&self.beatmaps);
-
-                            // Load the beatmap listing and change views
-                            self.beatmap_listing.load_beatmap_listing(beatmap_listing);
-                            self.current_view = ViewType::BeatmapListing;
-                        }
-                        Err(e) => log::warn!("Unable to open beatmap listing: {}", e),
-                    },
-                    FileOperation::GetCollectionListing => {
-                        match CollectionListing::from_bytes(&data) {
-                            Ok(collection_listing) => {
-                                self.collection_listing
-                                    .load_collection_listing(collection_listing);
-                                self.current_view = ViewType::CollectionListing;
+                #[cfg(not(target_arch = "wasm32"))]
+                let path = self.file_dialog.path().map(Path::to_path_buf);
+
+                // Files opened for comparison or added to the workspace aren't opened directly, so they aren't remembered
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(path) = path.as_ref().filter(|_| {
+                    !matches!(
+                        file_operation,
+                        FileOperation::GetComparisonListing | FileOperation::GetWorkspaceListing
+                    )
+                }) {
+                    self.add_recent_file(path);
+                }
+
+                self.load_file(
+                    ctx,
+                    file_operation,
+                    data,
+                    #[cfg(not(target_arch = "wasm32"))]
+                    path.as_deref(),
+                );
+
+                self.pending_file_operation = None;
+            }
+        }
+    }
+
+    /// Parses the contents of a file and loads it into the relevant view.
+    ///
+    /// `path` is where the file was read from, if known, which is used to locate the osu! installation folder.
+    fn load_file(
+        &mut self,
+        ctx: &egui::Context,
+        file_operation: FileOperation,
+        data: Vec<u8>,
+        #[cfg(not(target_arch = "wasm32"))] path: Option<&Path>,
+    ) {
+        match file_operation {
+            FileOperation::GetBeatmapListing => {
+                // Large databases can take a while to parse, so this is done in the background
+                if let Some(loader) = self.loader.replace(ListingLoader::start(ctx, data)) {
+                    loader.cancel();
+                }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    self.loading_path = path.map(|path| path.to_path_buf());
+
+                    // Otherwise the previous file would replace this one once it has been reloaded
+                    if let Some((reloader, _)) = self.reloader.take() {
+                        reloader.cancel();
+                    }
+                }
+            }
+            FileOperation::GetCollectionListing => {
+                match timed(|| CollectionListing::from_bytes(&data)) {
+                    (Ok(collection_listing), parse_time) => {
+                        self.status_bar.set_collections(
+                            &collection_listing,
+                            parse_time,
+                            #[cfg(not(target_arch = "wasm32"))]
+                            path.map(|path| path.to_path_buf()),
+                        );
+
+                        self.library
+                            .set_collections(Some(Arc::new(collection_listing.clone())));
+                        self.collection_listing
+                            .load_collection_listing(collection_listing);
+
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            if let Some(path) = path {
+                                self.watcher.watch(FileKind::Collections, path);
                             }
-                            Err(e) => log::warn!("Unable to open collection listing: {}", e),
+
+                            self.collection_listing
+                                .set_path(path.map(|path| path.to_path_buf()));
                         }
+
+                        self.current_view = ViewType::CollectionListing;
                     }
-                    FileOperation::GetScoreListing => match ScoreListing::from_bytes(&data) {
-                        Ok(score_listing) => {
-                            log::info!(
-                                "Successfully loaded scores.db (version: {})",
-                                score_listing.version
-                            );
-
-                            // Setup the MD5 mapping for the loaded scores
-                            self.scores = score_listing
-                                .beatmap_scores
-                                .into_iter()
-                                .filter_map(|s| s.md5.map(|md5| (md5, s.scores)))
-                                .collect();
-
-                            // Order each beatmap's scores by descending score, then ascending date
-                            for beatmap_scores in self.scores.values_mut() {
-                                beatmap_scores.sort_unstable_by(|a, b| {
-                                    b.score
-                                        .cmp(&a.score)
-                                        .then_with(|| a.timestamp.cmp(&b.timestamp))
-                                });
-                            }
+                    (Err(e), _) => self.show_load_error(
+                        file_operation.file_description(),
+                        LoadError::Parser(e),
+                        None,
+                    ),
+                }
+            }
+            FileOperation::GetScoreListing => match timed(|| ScoreListing::from_bytes(&data)) {
+                (Ok(score_listing), parse_time) => {
+                    self.load_score_listing(
+                        score_listing,
+                        parse_time,
+                        #[cfg(not(target_arch = "wasm32"))]
+                        path,
+                    );
+                    self.current_view = ViewType::ScoreListing;
+                }
+                (Err(e), _) => self.show_load_error(
+                    file_operation.file_description(),
+                    LoadError::Parser(e),
+                    None,
+                ),
+            },
+            FileOperation::GetComparisonListing => {
+                // The comparison runs in the background, so it keeps its own snapshot in case the listing is reloaded
+                let Some(listing) = self.library.snapshot().listing.clone() else {
+                    return;
+                };
+
+                #[cfg(not(target_arch = "wasm32"))]
+                let other_name = path
+                    .and_then(|path| path.file_name())
+                    .map(|name| name.to_string_lossy().into_owned());
+
+                #[cfg(target_arch = "wasm32")]
+                let other_name = None;
+
+                // Parsing errors are shown in the comparison window, so that the loaded listing isn't affected
+                self.comparison = Some(ComparisonWindow::start(
+                    ctx,
+                    listing,
+                    data,
+                    other_name.unwrap_or_else(|| "the other osu.db".to_string()),
+                    self.settings.metadata_language(),
+                ));
+            }
+            FileOperation::GetWorkspaceListing => {
+                let Some(listing) = self.library.snapshot().listing.clone() else {
+                    return;
+                };
+
+                // osu!.db is always called the same thing, so the folder it's in (e.g. a backup) names it better
+                #[cfg(not(target_arch = "wasm32"))]
+                let name = path
+                    .and_then(|path| path.parent()?.file_name())
+                    .map(|name| name.to_string_lossy().into_owned());
+
+                #[cfg(target_arch = "wasm32")]
+                let name = None;
+
+                // Parsing errors are shown in the workspace window, so that the loaded listing isn't affected
+                self.workspace
+                    .get_or_insert_with(|| {
+                        WorkspaceWindow::new(listing, self.settings.metadata_language())
+                    })
+                    .add(ctx, name.unwrap_or_else(|| "osu.db".to_string()), data);
+            }
+            FileOperation::GetReplay => match ScoreReplay::from_bytes(&data) {
+                Ok(replay) => {
+                    log::info!(
+                        "Successfully loaded .osr replay (version: {})",
+                        replay.version
+                    );
+
+                    self.replays.load_replay(replay, &self.beatmaps);
+                    self.current_view = ViewType::Replays;
+                }
+                Err(e) => self.show_load_error(
+                    file_operation.file_description(),
+                    LoadError::Parser(e),
+                    None,
+                ),
+            },
+        }
+    }
+
+    /// Checks on the progress of loading an `osu.db` file, and shows any errors that occurred.
+    fn check_loader(&mut self, ctx: &egui::Context) {
+        if let Some(loader) = &mut self.loader {
+            match loader.poll() {
+                None => loader.view(ctx),
+                Some(result) => {
+                    self.parse_time = loader.parse_time();
+                    self.loader = None;
+
+                    match result {
+                        Ok(PartialListing {
+                            listing,
+                            skipped,
+                            error: None,
+                            ..
+                        }) => self.load_beatmap_listing(listing, skipped, None),
+                        // Damaged files are loaded without asking, if the user has chosen to do so. Files from newer
+                        // versions of osu! are too, since they probably aren't damaged.
+                        Ok(PartialListing {
+                            listing,
+                            skipped,
+                            error: Some(e),
+                            ..
+                        }) if self.settings.lenient_parsing
+                            || listing.format_confidence() == FormatConfidence::BestEffort =>
+                        {
+                            log::warn!("Loading the valid beatmaps from a damaged osu.db: {}", e);
+                            self.load_beatmap_listing(listing, skipped, Some(&e));
                         }
-                        Err(e) => log::warn!("Unable to open score listing: {}", e),
-                    },
-                    FileOperation::GetReplay => match ScoreReplay::from_bytes(&data) {
-                        Ok(replay) => {
-                            log::info!(
-                                "Successfully loaded .osr replay (version: {})",
-                                replay.version
-                            );
-
-                            self.replays.load_replay(replay, &self.beatmaps);
-                            self.current_view = ViewType::Replays;
+                        Ok(PartialListing {
+                            listing,
+                            skipped,
+                            error: Some(e),
+                            spans,
+                        }) => self.show_load_error(
+                            FileOperation::GetBeatmapListing.file_description(),
+                            LoadError::Parser(e),
+                            Some(PartialListing {
+                                listing,
+                                skipped,
+                                error: None,
+                                spans,
+                            }),
+                        ),
+                        Err(Error::Cancelled) => log::info!("Loading osu.db was cancelled"),
+                        Err(e) => self.show_load_error(
+                            FileOperation::GetBeatmapListing.file_description(),
+                            LoadError::Parser(e),
+                            None,
+                        ),
+                    }
+                }
+            }
+        }
+
+        if let Some(dialog) = &mut self.load_error {
+            match dialog.view(ctx) {
+                Some(LoadErrorResponse::Close) => self.load_error = None,
+                Some(LoadErrorResponse::LoadPartial(partial)) => {
+                    // Keep the error which stopped the remaining beatmaps from being parsed, for the diagnostics
+                    let error = match self.load_error.take() {
+                        Some(LoadErrorDialog {
+                            error: LoadError::Parser(e),
+                            ..
+                        }) => Some(e),
+                        _ => None,
+                    };
+
+                    self.load_beatmap_listing(partial.listing, partial.skipped, error.as_ref());
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Loads a parsed `osu.db` file, switching to the beatmap listing view. `skipped` is the number of beatmaps that
+    /// couldn't be parsed because of `error`.
+    fn load_beatmap_listing(
+        &mut self,
+        beatmap_listing: BeatmapListing,
+        skipped: usize,
+        error: Option<&Error>,
+    ) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let path = self.loading_path.take();
+
+        self.status_bar.set_beatmaps(
+            &beatmap_listing,
+            self.parse_time.take(),
+            #[cfg(not(target_arch = "wasm32"))]
+            path.clone(),
+        );
+
+        // Load the beatmap listing and change views
+        self.overview.load_beatmap_listing(&beatmap_listing);
+        self.beatmap_listing.load_beatmap_listing(
+            beatmap_listing,
+            skipped,
+            error,
+            self.notes.notes(),
+        );
+        self.library
+            .set_listing(self.beatmap_listing.listing().cloned());
+
+        // osu.db is stored in the osu! installation folder, next to the Songs folder and collection.db
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(osu_directory) = path.as_deref().and_then(Path::parent) {
+                self.load_collections_alongside(osu_directory);
+                self.set_osu_directory(osu_directory);
+            }
+
+            if let Some(path) = &path {
+                self.watcher.watch(FileKind::Beatmaps, path);
+            }
+
+            self.beatmap_listing.set_source_path(path.clone());
+            self.health.set_source_path(path);
+        }
+
+        self.current_view = ViewType::BeatmapListing;
+    }
+
+    /// Refreshes the views that look up beatmaps by MD5 hash once a new version of the library has been published, e.g.
+    /// after an `osu.db` has been loaded or reloaded.
+    fn check_library(&mut self) {
+        let Some(snapshot) = self.library_watch.changed() else {
+            return;
+        };
+
+        let previous = std::mem::replace(&mut self.library_snapshot, snapshot);
+        let listing_changed = match (&previous.listing, &self.library_snapshot.listing) {
+            (Some(previous), Some(current)) => !Arc::ptr_eq(previous, current),
+            (previous, current) => previous.is_some() != current.is_some(),
+        };
+
+        if !listing_changed {
+            return;
+        }
+
+        if let (Some(workspace), Some(listing)) =
+            (&mut self.workspace, &self.library_snapshot.listing)
+        {
+            workspace.set_loaded_listing(listing.clone());
+        }
+
+        // Setup the MD5 mapping for the loaded beatmaps
+        self.beatmaps = self
+            .library_snapshot
+            .beatmaps_by_md5()
+            .map(|(md5, beatmap)| (md5.to_string(), beatmap.clone()))
+            .collect();
+
+        // Update any window titles for the replays view, and scores which may no longer be orphaned
+        self.replays.update_replay_titles(&self.beatmaps);
+        self.score_listing.refresh();
+    }
+
+    /// Loads a parsed `scores.db` file into the scores view. `path` is where the file was read from, if known.
+    fn load_score_listing(
+        &mut self,
+        score_listing: ScoreListing,
+        parse_time: Option<Duration>,
+        #[cfg(not(target_arch = "wasm32"))] path: Option<&Path>,
+    ) {
+        log::info!(
+            "Successfully loaded scores.db (version: {})",
+            score_listing.version
+        );
+
+        self.status_bar.set_scores(
+            &score_listing,
+            parse_time,
+            #[cfg(not(target_arch = "wasm32"))]
+            path.map(|path| path.to_path_buf()),
+        );
+
+        // Setup the MD5 mapping for the loaded scores
+        self.scores = score_listing
+            .beatmap_scores
+            .into_iter()
+            .filter_map(|s| s.md5.map(|md5| (md5, s.scores)))
+            .collect();
+
+        // Order each beatmap's scores by descending score, then ascending date
+        for beatmap_scores in self.scores.values_mut() {
+            beatmap_scores.sort_unstable_by(|a, b| {
+                b.score
+                    .cmp(&a.score)
+                    .then_with(|| a.timestamp.cmp(&b.timestamp))
+            });
+        }
+
+        self.library.set_scores(Some(Arc::new(self.scores.clone())));
+
+        self.score_listing.load_score_listing(&self.scores);
+        self.overview.load_scores(&self.scores);
+        self.beatmap_listing.set_scores(&self.scores);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = path {
+            self.watcher.watch(FileKind::Scores, path);
+
+            // scores.db is stored in the osu! installation folder, next to the Data/r replays folder
+            if let Some(osu_directory) = path.parent() {
+                self.set_osu_directory(osu_directory);
+            }
+        }
+    }
+
+    /// Loads the `collection.db` file in the osu! installation folder, unless there are unsaved changes to the
+    /// collections that are already loaded.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_collections_alongside(&mut self, osu_directory: &Path) {
+        let path = osu_directory.join("collection.db");
+
+        if !self.settings.load_collections
+            || !path.is_file()
+            || self.collection_listing.is_modified()
+        {
+            return;
+        }
+
+        if let Err(e) = self.open_collections(&path) {
+            log::warn!("Unable to open '{}': {}", path.display(), e);
+        }
+    }
+
+    /// Reads and loads a `collection.db` file, replacing the loaded collections.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_collections(&mut self, path: &Path) -> Result<(), Error> {
+        let (collection_listing, parse_time) = timed(|| CollectionListing::from_file(path));
+        let collection_listing = collection_listing?;
+
+        self.status_bar
+            .set_collections(&collection_listing, parse_time, Some(path.to_path_buf()));
+        self.library
+            .set_collections(Some(Arc::new(collection_listing.clone())));
+        self.collection_listing
+            .load_collection_listing(collection_listing);
+        self.collection_listing.set_path(Some(path.to_path_buf()));
+        self.watcher.watch(FileKind::Collections, path);
+
+        Ok(())
+    }
+
+    /// Checks whether the loaded files have changed on disk, showing a banner offering to reload them, and loads any
+    /// `osu.db` that has finished being reloaded.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn check_for_changes(&mut self, ctx: &egui::Context) {
+        if let Some((reloader, _)) = &mut self.reloader {
+            if let Some(result) = reloader.poll() {
+                let parse_time = reloader.parse_time();
+                let path = self.reloader.take().map(|(_, path)| path);
+
+                match result {
+                    Ok(PartialListing {
+                        listing,
+                        skipped,
+                        error: None,
+                        ..
+                    }) => {
+                        // Keep the user's place, rather than switching to the beatmap listing
+                        let view = self.current_view;
+                        let selected = self.beatmap_listing.selected_md5s();
+
+                        self.parse_time = parse_time;
+                        self.loading_path = path;
+                        self.load_beatmap_listing(listing, skipped, None);
+
+                        self.current_view = view;
+                        self.beatmap_listing.select_md5s(&selected);
+                    }
+                    // osu! may still be writing the file, so a damaged file is retried rather than partially loaded
+                    Ok(PartialListing { error: Some(e), .. }) => {
+                        self.reload_failed(FileKind::Beatmaps, LoadError::Parser(e))
+                    }
+                    Err(Error::Cancelled) => {}
+                    Err(e) => self.reload_failed(FileKind::Beatmaps, LoadError::Parser(e)),
+                }
+            }
+        }
+
+        let mut reload = self.watcher.poll(ctx);
+        reload.extend(
+            self.watcher
+                .banner(ctx, self.collection_listing.is_modified()),
+        );
+
+        for (kind, path) in reload {
+            self.reload_file(ctx, kind, &path);
+        }
+    }
+
+    /// Reloads a file that has changed on disk, keeping the current search, sorting and selection.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_file(&mut self, ctx: &egui::Context, kind: FileKind, path: &Path) {
+        log::info!("Reloading '{}'", path.display());
+
+        match kind {
+            FileKind::Beatmaps => {
+                // The listing is replaced anyway once the file being opened has loaded
+                if self.loader.is_some() {
+                    return;
+                }
+
+                // Reloading would discard the edits, which are saved over the changed file instead
+                if self.beatmap_listing.is_modified() {
+                    log::warn!(
+                        "Not reloading '{}', since there are unsaved beatmap changes",
+                        path.display()
+                    );
+                    return;
+                }
+
+                match shared::read_database(path, kind) {
+                    Ok(data) => {
+                        let reloader = ListingLoader::start(ctx, data);
+
+                        if let Some((previous, _)) =
+                            self.reloader.replace((reloader, path.to_path_buf()))
+                        {
+                            previous.cancel();
                         }
-                        Err(e) => log::warn!("Unable to open replay file: {}", e),
-                    },
+                    }
+                    Err(e) => self.reload_failed(kind, LoadError::Parser(e)),
+                }
+            }
+            FileKind::Collections => {
+                if let Err(e) = self.open_collections(path) {
+                    self.reload_failed(kind, LoadError::Parser(e));
                 }
+            }
+            FileKind::Scores => match timed(|| ScoreListing::from_file(path)) {
+                (Ok(score_listing), parse_time) => {
+                    self.load_score_listing(score_listing, parse_time, Some(path))
+                }
+                (Err(e), _) => self.reload_failed(kind, LoadError::Parser(e)),
+            },
+            // Replays aren't watched
+            FileKind::Replay => {}
+        }
+    }
 
-                self.pending_file_operation = None;
+    /// Retries reloading a file that couldn't be read or parsed, e.g. because osu! was still writing to it, or shows
+    /// the error once it has been retried too many times.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_failed(&mut self, kind: FileKind, error: LoadError) {
+        if !self.watcher.retry(kind) {
+            let description = FileOperation::from_kind(kind).file_description();
+            self.show_load_error(description, error, None);
+        }
+    }
+
+    /// Shows a dialog explaining why a file couldn't be opened. `description` is the name or kind of file.
+    fn show_load_error(
+        &mut self,
+        description: &str,
+        error: LoadError,
+        partial: Option<PartialListing>,
+    ) {
+        match &error {
+            LoadError::IO(e) => log::warn!("Unable to read {}: {}", description, e),
+            LoadError::Parser(e) => log::warn!("Unable to parse {}: {}", description, e),
+            LoadError::Unrecognised => log::warn!("Unable to identify {}", description),
+            LoadError::NotOsuFolder => log::warn!("{} is not an osu! folder", description),
+        }
+
+        self.load_error = Some(LoadErrorDialog {
+            title: format!("Unable to open {}", description),
+            error,
+            partial,
+        });
+    }
+
+    /// Asks for a file of notes exported from elsewhere, and adds them to the user's notes.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn import_notes(&mut self, ctx: &egui::Context) {
+        let Some(path) = FileDialog::pick_file(None, "Notes", &["json"]) else {
+            return;
+        };
+
+        match self.notes.import(&path) {
+            Ok(changed) => {
+                log::info!("Imported {} notes from '{}'", changed, path.display());
+                self.beatmap_listing.notes_changed(ctx.input(|i| i.time));
             }
+            Err(e) => self.show_load_error("notes", LoadError::IO(e), None),
+        }
+    }
+
+    /// Asks where to save a copy of the user's notes, e.g. to move them to a different machine, then saves them.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_notes(&self) {
+        let Some(path) = FileDialog::save_file(None, notes::EXPORT_FILE_NAME) else {
+            return;
+        };
+
+        if let Err(e) = self.notes.export(&path) {
+            log::error!("Unable to export notes to '{}': {}", path.display(), e);
+        }
+    }
+
+    /// Asks where to save a reproduction of the loaded `osu.db`, search and settings for a bug report, then saves it.
+    /// If `anonymise` is set, the beatmaps' text and the player's name are replaced with pseudonyms first.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_repro(&self, anonymise: bool) {
+        use std::hash::{BuildHasher, Hasher};
+
+        let Some(listing) = self.beatmap_listing.listing() else {
+            return;
+        };
+
+        let Some(path) = FileDialog::save_file(None, debug::FILE_NAME) else {
+            return;
+        };
+
+        let mut repro = debug::Repro::new(
+            listing.as_ref().clone(),
+            self.beatmap_listing.search_query(),
+            self.beatmap_listing.table_layout().clone(),
+            &self.settings,
+            self.beatmap_listing.skipped_entries().cloned(),
+        );
+
+        // The seed is random and isn't saved, so that the pseudonyms can't be reversed
+        if anonymise {
+            let seed = std::collections::hash_map::RandomState::new()
+                .build_hasher()
+                .finish();
+            repro.anonymise(seed);
+        }
+
+        match debug::export_repro(&path, &repro) {
+            Ok(()) => log::info!("Exported a reproduction to '{}'", path.display()),
+            Err(e) => log::error!(
+                "Unable to export a reproduction to '{}': {}",
+                path.display(),
+                e
+            ),
         }
     }
 
+    /// Asks for a reproduction from a bug report, and restores the `osu.db`, search and settings in it.
+    ///
+    /// The osu! folders and font from this machine's settings are kept, and the settings aren't saved while the
+    /// reproduction is loaded.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_repro(&mut self, ctx: &egui::Context) {
+        let Some(path) = FileDialog::pick_file(None, "Reproduction", &["gz"]) else {
+            return;
+        };
+
+        let repro = match debug::load_repro(&path) {
+            Ok(repro) => repro,
+            Err(e) => {
+                self.show_load_error("reproduction", LoadError::IO(e), None);
+                return;
+            }
+        };
+
+        log::info!(
+            "Loaded a reproduction from viewer version {} (anonymised: {})",
+            repro.viewer_version,
+            repro.anonymised
+        );
+
+        self.repro_loaded = true;
+        self.settings = Settings {
+            cjk_font: self.settings.cjk_font.take(),
+            osu_folder: self.settings.osu_folder.take(),
+            songs_folder: self.settings.songs_folder.take(),
+            ..repro.settings
+        };
+        self.apply_settings(ctx);
+
+        let skipped = repro.skipped.as_ref().map_or(0, |skipped| skipped.count);
+        self.beatmap_listing.set_table_layout(repro.table_layout);
+        self.load_beatmap_listing(repro.listing, skipped, None);
+        self.beatmap_listing.set_skipped_entries(repro.skipped);
+        self.beatmap_listing
+            .set_filter(repro.query, repro.filter_bar);
+    }
+
+    /// Loads a made up library (see [`synthetic`]), e.g. to take screenshots without showing anyone's real `osu.db`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_demo_library(&mut self) {
+        let listing = synthetic::listing(&SyntheticOptions {
+            beatmaps: DEMO_BEATMAPS,
+            ..Default::default()
+        });
+
+        log::info!(
+            "Loaded a demo library with {} beatmaps",
+            listing.beatmaps.len()
+        );
+        self.load_beatmap_listing(listing, 0, None);
+    }
+
     /// Renders the top panel showing the menu bar.
+    /// Gets what's loaded and selected, which decides which actions can be used.
+    fn action_context(&self) -> ActionContext {
+        ActionContext {
+            workspace: self.workspace.is_some(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            databases: !self.open_databases().is_empty(),
+
+            performance_overlay: self.performance.visible,
+            ..self.beatmap_listing.action_context()
+        }
+    }
+
+    /// Gets the paths of the open `osu.db` and `collection.db` files, which backups can be restored for.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_databases(&self) -> Vec<PathBuf> {
+        [
+            self.beatmap_listing.source_path(),
+            self.collection_listing.path(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(Path::to_path_buf)
+        .collect()
+    }
+
+    /// Runs an action chosen from the menu bar or command palette.
+    fn run_action(&mut self, ctx: &egui::Context, id: ActionId) {
+        use FileOperation::*;
+
+        self.palette.record_use(id);
+
+        let file_operation = match id {
+            ActionId::OpenBeatmapListing => Some(GetBeatmapListing),
+            ActionId::OpenCollectionListing => Some(GetCollectionListing),
+            ActionId::OpenScoreListing => Some(GetScoreListing),
+            ActionId::OpenReplay => Some(GetReplay),
+            ActionId::CompareWith => Some(GetComparisonListing),
+            ActionId::AddDatabase => Some(GetWorkspaceListing),
+            _ => None,
+        };
+
+        if let Some(operation) = file_operation {
+            self.pending_file_operation = Some(operation);
+            self.file_dialog.open();
+            return;
+        }
+
+        match id {
+            ActionId::ShowDatabases => {
+                if let Some(workspace) = &mut self.workspace {
+                    workspace.visible = true;
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::ImportNotes => self.import_notes(ctx),
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::ExportNotes => self.export_notes(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::RestoreBackup => self.restore_backup.open(self.open_databases()),
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::ExportRepro => self.export_repro(false),
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::ExportAnonymisedRepro => self.export_repro(true),
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::LoadRepro => self.load_repro(ctx),
+            ActionId::TogglePerformanceOverlay => {
+                self.performance.visible = !self.performance.visible
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::GenerateReport => {
+                self.beatmap_listing.open_report();
+                self.current_view = ViewType::BeatmapListing;
+            }
+            ActionId::Settings => self.settings_window.visible = true,
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::OpenOsuFolder => {
+                // Start in the default folder from the settings, or the usual installation folder
+                let start = self
+                    .default_osu_folder()
+                    .or_else(|| OsuFolder::find_installed().map(|folder| folder.root));
+
+                if let Some(path) = FileDialog::pick_folder(start.as_deref()) {
+                    self.open_path(ctx, &path);
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::Close => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            _ => {}
+        }
+
+        if let Some(view) = ViewType::from_action(id) {
+            self.current_view = view;
+        }
+
+        // Beatmap actions are handled by the listing, the same as from its shortcuts and context menu
+        if id.action().menu == Menu::Beatmaps {
+            self.current_view = ViewType::BeatmapListing;
+            self.beatmap_listing.queue_action(id);
+        }
+    }
+
     fn menu_bar(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let mut action = None;
+
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    use FileOperation::*;
+                ui.menu_button(tr("File"), |ui| {
+                    let context = self.action_context();
+                    let group = |ui: &mut egui::Ui, group| {
+                        actions::menu_group(ui, Menu::File, group, &context)
+                    };
 
-                    if ui.button("Open osu.db...").clicked() {
-                        self.pending_file_operation = Some(GetBeatmapListing);
-                        self.file_dialog.open();
-                        ui.close_menu();
-                    }
+                    action = group(ui, 0).or(action);
+                    ui.separator();
+                    action = group(ui, 1).or(action);
 
-                    if ui.button("Open collection.db...").clicked() {
-                        self.pending_file_operation = Some(GetCollectionListing);
-                        self.file_dialog.open();
-                        ui.close_menu();
-                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        ui.separator();
+                        action = group(ui, 2).or(action);
 
-                    if ui.button("Open scores.db...").clicked() {
-                        self.pending_file_operation = Some(GetScoreListing);
-                        self.file_dialog.open();
-                        ui.close_menu();
-                    }
+                        ui.menu_button(tr("Bug Report"), |ui| {
+                            action = actions::menu(ui, Menu::BugReport, &context, false).or(action);
 
-                    if ui.button("Open .osr replay...").clicked() {
-                        self.pending_file_operation = Some(GetReplay);
-                        self.file_dialog.open();
-                        ui.close_menu();
+                            // Only shown while holding Shift, since it's for screenshots and demos
+                            if ui.input(|i| i.modifiers.shift)
+                                && ui.button(tr("Load Demo Library")).clicked()
+                            {
+                                ui.close_menu();
+                                self.load_demo_library();
+                            }
+                        });
+
+                        ui.separator();
+                        action = group(ui, 3).or(action);
                     }
 
+                    ui.separator();
+                    action = group(ui, 4).or(action);
+
                     #[cfg(not(target_arch = "wasm32"))]
                     {
                         ui.separator();
+                        action = group(ui, 5).or(action);
 
-                        if ui.button("Close").clicked() {
-                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                        }
+                        ui.add_enabled_ui(!self.recent_files.is_empty(), |ui| {
+                            ui.menu_button(tr("Open recent"), |ui| {
+                                let mut opened = None;
+
+                                for path in &self.recent_files {
+                                    if ui
+                                        .add_enabled(
+                                            path.exists(),
+                                            egui::Button::new(path.display().to_string()),
+                                        )
+                                        .on_disabled_hover_text(tr("This file no longer exists"))
+                                        .clicked()
+                                    {
+                                        opened = Some(path.clone());
+                                        ui.close_menu();
+                                    }
+                                }
+
+                                ui.separator();
+
+                                if ui.button(tr("Clear recent")).clicked() {
+                                    self.recent_files.clear();
+                                    ui.close_menu();
+                                }
+
+                                if let Some(path) = opened {
+                                    self.open_path(ctx, &path);
+                                }
+                            });
+                        });
+
+                        ui.separator();
+                        action = group(ui, 6).or(action);
+                    }
+                });
+
+                ui.menu_button(tr("Beatmaps"), |ui| {
+                    let context = self.action_context();
+                    action = actions::menu(ui, Menu::Beatmaps, &context, false);
+                });
+
+                ui.menu_button(tr("View"), |ui| {
+                    action = actions::menu(ui, Menu::View, &self.action_context(), false);
+
+                    ui.separator();
+
+                    if ui
+                        .add(
+                            egui::Button::new(tr("Command Palette..."))
+                                .shortcut_text(ctx.format_shortcut(&command_palette::SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        self.palette.open();
+                        ui.close_menu();
                     }
                 });
 
@@ -208,62 +1689,136 @@ impl MainApp {
                 ui.selectable_value(
                     &mut self.current_view,
                     ViewType::BeatmapListing,
-                    "Beatmap Listing",
+                    tr("Beatmap Listing"),
+                );
+                ui.selectable_value(&mut self.current_view, ViewType::Overview, tr("Overview"));
+
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.selectable_value(&mut self.current_view, ViewType::Health, tr("Health"));
+
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.selectable_value(
+                    &mut self.current_view,
+                    ViewType::DiskUsage,
+                    tr("Disk Usage"),
                 );
 
+                // Mark the tab when there are unsaved changes
+                let collections_label = if self.collection_listing.is_modified() {
+                    format!("{}*", tr("Collections"))
+                } else {
+                    tr("Collections").to_string()
+                };
+
                 ui.selectable_value(
                     &mut self.current_view,
                     ViewType::CollectionListing,
-                    "Collection Listing",
+                    collections_label,
                 );
 
-                ui.selectable_value(&mut self.current_view, ViewType::Replays, "Replays");
+                ui.selectable_value(&mut self.current_view, ViewType::ScoreListing, tr("Scores"));
+                ui.selectable_value(&mut self.current_view, ViewType::Replays, tr("Replays"));
             });
         });
+
+        if let Some(id) = action {
+            self.run_action(ctx, id);
+        }
     }
 }
 
 /// Opens a beatmap link in the browser.
 fn open_beatmap_in_browser(beatmap: &BeatmapEntry) {
-    // Fields to populate are:
-    // - Beatmapset ID
-    // - Gameplay Mode - #osu, #taiko, #fruits, #mania
-    // - Difficulty ID
-    let url = format!(
-        "https://osu.ppy.sh/beatmapsets/{}{}/{}",
-        beatmap.beatmap_id,
-        match beatmap.gameplay_mode {
-            GameplayMode::Standard => "#osu",
-            GameplayMode::Taiko => "#taiko",
-            GameplayMode::Catch => "#fruits",
-            GameplayMode::Mania => "#mania",
-        },
-        beatmap.difficulty_id
-    );
-
-    if let Err(e) = webbrowser::open(&url) {
-        log::error!("Unable to open beatmap link '{}': {}", &url, e);
+    if let Some(url) = links::beatmap_url(beatmap) {
+        open_url(&url);
     }
 }
 
 /// Opens a score details link in the browser.
 fn open_score_in_browser(score: &ScoreReplay) {
-    // Fields to populate are:
-    // - Gameplay Mode - osu, taiko, fruits, mania
-    // - Online Score ID
-    let url = format!("https://osu.ppy.sh/scores/{}/{}",
-        match score.gameplay_mode {
-            GameplayMode::Standard => "osu",
-            GameplayMode::Taiko => "taiko",
-            GameplayMode::Catch => "fruits",
-            GameplayMode::Mania => "mania",
-        },
-        score.online_score_id
+    if let Some(url) = links::score_url(score) {
+        open_url(&url);
+    }
+}
+
+/// Opens a link in the browser.
+fn open_url(url: &str) {
+    if let Err(e) = webbrowser::open(url) {
+        log::error!("Unable to open link '{}': {}", url, e);
+    }
+}
+
+/// Shows a file in the system's file manager, selecting it where the platform supports doing so.
+#[cfg(not(target_arch = "wasm32"))]
+fn reveal_in_file_manager(path: &std::path::Path) {
+    #[cfg(target_os = "windows")]
+    let result = std::process::Command::new("explorer")
+        .arg(format!("/select,{}", path.display()))
+        .spawn()
+        .map(|_| ());
+
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("open")
+        .arg("-R")
+        .arg(path)
+        .spawn()
+        .map(|_| ());
+
+    // Other platforms don't have a standard way to select a file, so just open its folder
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let result = open::that_detached(path.parent().unwrap_or(path));
+
+    if let Err(e) = result {
+        log::error!("Unable to show '{}': {}", path.display(), e);
+    }
+}
+
+/// Gets the colour used for a grade in-game.
+fn grade_colour(grade: Grade) -> egui::Color32 {
+    match grade {
+        Grade::SilverSS | Grade::SilverS => egui::Color32::from_rgb(200, 210, 220),
+        Grade::SS | Grade::S => egui::Color32::from_rgb(240, 190, 50),
+        Grade::A => egui::Color32::from_rgb(100, 200, 90),
+        Grade::B => egui::Color32::from_rgb(70, 140, 230),
+        Grade::C => egui::Color32::from_rgb(180, 100, 220),
+        Grade::D => egui::Color32::from_rgb(230, 70, 70),
+        Grade::Unplayed => egui::Color32::GRAY,
+    }
+}
+
+/// Renders a grade in the colour used for it in-game.
+fn grade_text(grade: Grade) -> egui::RichText {
+    egui::RichText::new(grade.to_string())
+        .strong()
+        .color(grade_colour(grade))
+}
+
+/// Renders a grade as a letter on a badge in the colour used for it in-game. The letter is always shown, so the grade
+/// can be told without the colour, and is read out by screen readers.
+fn grade_badge(ui: &mut egui::Ui, grade: Grade) -> egui::Response {
+    let galley = ui.painter().layout_no_wrap(
+        grade.to_string(),
+        egui::TextStyle::Button.resolve(ui.style()),
+        egui::Color32::from_gray(20),
     );
 
-    if let Err(e) = webbrowser::open(&url) {
-        log::error!("Unable to open score link '{}': {}", &url, e);
+    // Give every badge the same width, so that they line up in tables
+    let size = egui::vec2(28.0f32.max(galley.size().x + 6.0), galley.size().y + 2.0);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+
+    if ui.is_rect_visible(rect) {
+        ui.painter().rect_filled(rect, 4.0, grade_colour(grade));
+        ui.painter().galley(
+            rect.center() - galley.size() / 2.0,
+            galley,
+            egui::Color32::from_gray(20),
+        );
     }
+
+    response.widget_info(|| {
+        egui::WidgetInfo::labeled(egui::WidgetType::Label, true, grade.to_string())
+    });
+    response
 }
 
 /// Renders a flagset as a more readable string.
@@ -284,6 +1839,48 @@ fn mods_string(mods: flagset::FlagSet<Mods>) -> String {
     }
 }
 
+/// Formats a count with thousands separators (e.g. `87,567`).
+fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            formatted.push(',');
+        }
+
+        formatted.push(digit);
+    }
+
+    formatted
+}
+
+/// Formats a number of bytes using the largest unit that keeps the value above 1, e.g. `1.5 GB`. Units are powers of
+/// 1024, as in Windows Explorer.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Checks whether a text box has keyboard focus, in which case keyboard shortcuts shouldn't be handled.
+fn text_has_focus(ctx: &egui::Context) -> bool {
+    ctx.memory(|m| m.focused())
+        .is_some_and(|id| egui::TextEdit::load_state(ctx, id).is_some())
+}
+
 /// Renders an unsigned u32 value that acts as -1 when it is the maximum value.
 fn maybe_signed_u32(val: u32) -> egui::WidgetText {
     if val == 0xFFFFFFFF {
@@ -305,7 +1902,7 @@ fn maybe_signed_u64(val: u64) -> egui::WidgetText {
 /// Renders an optional string.
 fn optional_string<T: std::fmt::Display>(value: &Option<T>) -> egui::WidgetText {
     if let Some(v) = value {
-        v.to_string().into()
+        v.to_string().sanitized().into_owned().into()
     } else {
         egui::RichText::new("N/A").italics().into()
     }
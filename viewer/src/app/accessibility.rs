@@ -0,0 +1,185 @@
+use egui::{Color32, Stroke, Theme, Visuals};
+
+/// Smallest and largest interface scales that can be chosen.
+pub const MIN_SCALE: f32 = 0.5;
+pub const MAX_SCALE: f32 = 3.0;
+
+/// Amount the interface scale changes by with each press of Ctrl+Plus or Ctrl+Minus.
+const SCALE_STEP: f32 = 0.1;
+
+/// Changes the interface scale by a number of steps, staying on multiples of a step so that pressing Ctrl+Plus and
+/// Ctrl+Minus gets back to the same scale.
+pub fn step_scale(scale: f32, steps: i32) -> f32 {
+    let stepped = (scale / SCALE_STEP).round() + steps as f32;
+
+    // Round away the error from multiplying by the step, so that scales are shown as e.g. 110% rather than 110.00001%
+    ((stepped * SCALE_STEP * 100.0).round() / 100.0).clamp(MIN_SCALE, MAX_SCALE)
+}
+
+/// Handles Ctrl+Plus, Ctrl+Minus and Ctrl+0, which change the interface scale the same way as in a web browser.
+/// Returns `true` if the scale was changed.
+///
+/// egui's own handling of these shortcuts is turned off by [`apply`], since it doesn't know to save the scale.
+pub fn handle_zoom_keys(ctx: &egui::Context, scale: &mut f32) -> bool {
+    use egui::gui_zoom::kb_shortcuts::*;
+
+    let (reset, steps) = ctx.input_mut(|i| {
+        let reset = i.consume_shortcut(&ZOOM_RESET);
+        let zoom_in = i.consume_shortcut(&ZOOM_IN) || i.consume_shortcut(&ZOOM_IN_SECONDARY);
+        let zoom_out = i.consume_shortcut(&ZOOM_OUT);
+
+        (reset, zoom_in as i32 - zoom_out as i32)
+    });
+
+    let new_scale = if reset {
+        1.0
+    } else {
+        step_scale(*scale, steps)
+    };
+
+    if new_scale == *scale {
+        return false;
+    }
+
+    *scale = new_scale;
+    true
+}
+
+/// Applies the interface scale and the colours for the chosen contrast to both the light and dark themes.
+pub fn apply(ctx: &egui::Context, scale: f32, high_contrast: bool) {
+    ctx.options_mut(|options| options.zoom_with_keyboard = false);
+    ctx.set_zoom_factor(scale.clamp(MIN_SCALE, MAX_SCALE));
+
+    for theme in [Theme::Dark, Theme::Light] {
+        let mut visuals = theme.default_visuals();
+
+        if high_contrast {
+            make_high_contrast(&mut visuals);
+        }
+
+        ctx.set_visuals_of(theme, visuals);
+    }
+}
+
+/// Changes a theme's colours to make text and the edges of widgets stand out more, with plainer backgrounds, stronger
+/// row striping and a thick outline around the widget with keyboard focus.
+fn make_high_contrast(visuals: &mut Visuals) {
+    let (text, background, stripe, edge, accent) = if visuals.dark_mode {
+        (
+            Color32::WHITE,
+            Color32::BLACK,
+            Color32::from_gray(48),
+            Color32::from_gray(180),
+            Color32::from_rgb(255, 210, 0),
+        )
+    } else {
+        (
+            Color32::BLACK,
+            Color32::WHITE,
+            Color32::from_gray(215),
+            Color32::from_gray(60),
+            Color32::from_rgb(0, 70, 200),
+        )
+    };
+
+    visuals.override_text_color = Some(text);
+    visuals.panel_fill = background;
+    visuals.window_fill = background;
+    visuals.extreme_bg_color = background;
+    visuals.faint_bg_color = stripe;
+    visuals.striped = true;
+
+    visuals.window_stroke = Stroke::new(1.5, edge);
+    visuals.selection.stroke = Stroke::new(2.0, accent);
+    visuals.hyperlink_color = accent;
+
+    let widgets = &mut visuals.widgets;
+    widgets.noninteractive.bg_stroke = Stroke::new(1.0, edge);
+    widgets.noninteractive.fg_stroke.color = text;
+    widgets.inactive.bg_stroke = Stroke::new(1.0, edge);
+    widgets.inactive.fg_stroke.color = text;
+    widgets.hovered.bg_stroke = Stroke::new(1.5, text);
+    widgets.hovered.fg_stroke.color = text;
+
+    // egui draws the focused widget using the active style, so this is the focus outline
+    widgets.active.bg_stroke = Stroke::new(2.5, accent);
+    widgets.active.fg_stroke.color = text;
+}
+
+/// Gets the height of a row in a table, leaving room for a button in each row.
+///
+/// The height is rounded up so that each row, along with the spacing between rows, covers a whole number of physical
+/// pixels. Tables only lay out the rows in view, placing them at multiples of this height, so at scales such as 125%
+/// an unrounded height puts rows at fractions of a pixel. Their text is then snapped to the nearest pixel in
+/// different directions, so the rows look uneven and shift by a pixel while scrolling.
+pub fn row_height(ui: &egui::Ui) -> f32 {
+    let height = ui
+        .text_style_height(&egui::TextStyle::Body)
+        .max(ui.spacing().interact_size.y);
+
+    pixel_aligned_height(
+        height,
+        ui.spacing().item_spacing.y,
+        ui.ctx().pixels_per_point(),
+    )
+}
+
+/// Rounds up a row height so that it covers a whole number of pixels along with the spacing after it.
+fn pixel_aligned_height(height: f32, spacing: f32, pixels_per_point: f32) -> f32 {
+    // Small errors in the multiplication mustn't add a whole pixel
+    let pixels = ((height + spacing) * pixels_per_point - 1e-3).ceil();
+    pixels / pixels_per_point - spacing
+}
+
+/// Draws an outline around an area that has keyboard focus, such as a table, in the same colour as a focused widget.
+pub fn focus_outline(ui: &egui::Ui, rect: egui::Rect) {
+    let stroke = ui.visuals().widgets.active.bg_stroke;
+    let stroke = Stroke::new(stroke.width.max(1.5), ui.visuals().selection.stroke.color);
+
+    ui.painter()
+        .rect_stroke(rect.shrink(stroke.width / 2.0), 2.0, stroke);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_steps_stay_on_multiples_of_a_step() {
+        assert_eq!(step_scale(1.0, 1), 1.1);
+        assert_eq!(step_scale(step_scale(1.0, 1), -1), 1.0);
+        assert_eq!(step_scale(1.25, 1), 1.4);
+        assert_eq!(step_scale(2.95, 3), MAX_SCALE);
+        assert_eq!(step_scale(0.6, -5), MIN_SCALE);
+    }
+
+    #[test]
+    fn row_heights_cover_whole_pixels() {
+        for pixels_per_point in [1.0, 1.25, 1.5, 1.75, 2.0, 2.25, 3.0, 1.1, 0.9] {
+            for height in [18.0, 18.5, 20.0, 21.3] {
+                let aligned = pixel_aligned_height(height, 3.0, pixels_per_point);
+                let pixels = (aligned + 3.0) * pixels_per_point;
+
+                assert!(aligned >= height, "{} at {}", height, pixels_per_point);
+                assert!(
+                    (pixels - pixels.round()).abs() < 1e-3,
+                    "{} at {}",
+                    height,
+                    pixels_per_point
+                );
+                assert!(
+                    aligned - height < 1.0 / pixels_per_point + 1e-4,
+                    "{} at {}",
+                    height,
+                    pixels_per_point
+                );
+            }
+        }
+
+        // Heights that already cover whole pixels aren't changed
+        assert_eq!(pixel_aligned_height(18.0, 3.0, 2.0), 18.0);
+
+        // At 125%, 21 points is 26.25 pixels, so rows are made 27 pixels apart
+        assert!((pixel_aligned_height(18.0, 3.0, 1.25) - 18.6).abs() < 1e-4);
+    }
+}
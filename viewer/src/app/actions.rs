@@ -0,0 +1,740 @@
+use egui::{Key, KeyboardShortcut, Modifiers};
+use osu_db_parser::fuzzy;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+
+/// Represents the menus that actions are listed in. The command palette shows each action with its menu's name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Menu {
+    File,
+    BugReport,
+    Beatmaps,
+    View,
+}
+
+/// Identifies something the user can do from the menus, the beatmap table's context menu or the command palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ActionId {
+    OpenBeatmapListing,
+    OpenCollectionListing,
+    OpenScoreListing,
+    OpenReplay,
+    CompareWith,
+    AddDatabase,
+    ShowDatabases,
+    #[cfg(not(target_arch = "wasm32"))]
+    ImportNotes,
+    #[cfg(not(target_arch = "wasm32"))]
+    ExportNotes,
+    #[cfg(not(target_arch = "wasm32"))]
+    RestoreBackup,
+    #[cfg(not(target_arch = "wasm32"))]
+    ExportRepro,
+    #[cfg(not(target_arch = "wasm32"))]
+    ExportAnonymisedRepro,
+    #[cfg(not(target_arch = "wasm32"))]
+    LoadRepro,
+    TogglePerformanceOverlay,
+    #[cfg(not(target_arch = "wasm32"))]
+    GenerateReport,
+    Settings,
+    #[cfg(not(target_arch = "wasm32"))]
+    OpenOsuFolder,
+    #[cfg(not(target_arch = "wasm32"))]
+    Close,
+
+    Find,
+    JumpToBeatmap,
+    RandomBeatmap,
+    OpenPages,
+    OpenInOsuDirect,
+    #[cfg(not(target_arch = "wasm32"))]
+    OpenFolders,
+    #[cfg(not(target_arch = "wasm32"))]
+    EditSettings,
+    CopyMd5,
+    CopyBeatmapId,
+    CopyMarkdownRow,
+
+    ShowBeatmapListing,
+    ShowOverview,
+    #[cfg(not(target_arch = "wasm32"))]
+    ShowHealth,
+    #[cfg(not(target_arch = "wasm32"))]
+    ShowDiskUsage,
+    ShowCollections,
+    ShowScores,
+    ShowReplays,
+}
+
+/// Represents something an action needs before it can be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Needs {
+    /// An `osu.db` has been loaded
+    Listing,
+
+    /// At least one beatmap is selected
+    Selection,
+
+    /// One of the selected beatmaps has been submitted, so it has a page on the osu! website
+    Submitted,
+
+    /// One of the selected beatmaps has an MD5 hash
+    Md5,
+
+    /// One of the selected beatmaps has a difficulty ID
+    DifficultyId,
+
+    /// The osu! folder is known, so that beatmap folders can be found
+    OsuFolder,
+
+    /// One of the selected beatmaps' folders exists
+    Folder,
+
+    /// The selected beatmaps' settings can be changed from where the action is shown. Otherwise, the action is hidden.
+    Editable,
+
+    /// Several `osu.db` files have been combined. Otherwise, the action is hidden.
+    Workspace,
+
+    /// An `osu.db` or `collection.db` has been opened from a file
+    Databases,
+}
+
+/// Represents whether an action can be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Availability {
+    Enabled,
+
+    /// The action is shown, but can't be used for this reason
+    Disabled(&'static str),
+
+    /// The action doesn't apply here, so it isn't shown
+    Hidden,
+}
+
+/// What's loaded and selected when actions are shown, which decides which of them can be used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ActionContext {
+    pub listing: bool,
+
+    /// Number of selected beatmaps
+    pub selected: usize,
+
+    pub submitted: bool,
+    pub md5: bool,
+    pub difficulty_id: bool,
+    pub osu_folder: bool,
+    pub folder: bool,
+    pub editable: bool,
+    pub workspace: bool,
+    pub databases: bool,
+
+    /// Whether the performance overlay is shown, for the action that toggles it
+    pub performance_overlay: bool,
+}
+
+/// Represents an action, along with where and how it's shown.
+#[derive(Clone, Copy, Debug)]
+pub struct Action {
+    pub id: ActionId,
+    pub name: &'static str,
+    pub menu: Menu,
+
+    /// Actions in the same menu are separated by group
+    pub group: u8,
+
+    /// Shown when hovering over the action
+    pub hint: Option<&'static str>,
+
+    /// Shown when the action can't be used, in place of the reason given by what it needs
+    pub unavailable: Option<&'static str>,
+
+    /// Shortcut for the action, which is handled where the action is
+    pub shortcut: Option<KeyboardShortcut>,
+
+    pub needs: &'static [Needs],
+
+    /// Whether the action is also shown in the beatmap table's context menu
+    pub context_menu: bool,
+
+    /// Whether the action is turned on and off, in which case it's shown with a checkbox
+    pub toggle: bool,
+}
+
+/// Every action, in the order they're shown in the menus.
+pub const ACTIONS: &[Action] = &[
+    Action::new(ActionId::OpenBeatmapListing, "Open osu.db...", Menu::File, 0),
+    Action::new(
+        ActionId::OpenCollectionListing,
+        "Open collection.db...",
+        Menu::File,
+        0,
+    ),
+    Action::new(ActionId::OpenScoreListing, "Open scores.db...", Menu::File, 0),
+    Action::new(ActionId::OpenReplay, "Open .osr replay...", Menu::File, 0),
+    Action::new(ActionId::CompareWith, "Compare with...", Menu::File, 1)
+        .needs(&[Needs::Listing])
+        .unavailable("Open an osu.db to compare it with another one"),
+    Action::new(ActionId::AddDatabase, "Add Database...", Menu::File, 1)
+        .needs(&[Needs::Listing])
+        .unavailable("Open an osu.db to combine it with others")
+        .hint("Show which beatmaps are in each of several osu.db files, e.g. from another computer"),
+    Action::new(ActionId::ShowDatabases, "Databases", Menu::File, 1).needs(&[Needs::Workspace]),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::ImportNotes, "Import Notes...", Menu::File, 2),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::ExportNotes, "Export Notes...", Menu::File, 2),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::RestoreBackup, "Restore Backup...", Menu::File, 2)
+        .needs(&[Needs::Databases])
+        .unavailable("Open an osu.db or collection.db to restore a backup of it"),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(
+        ActionId::ExportRepro,
+        "Export Reproduction...",
+        Menu::BugReport,
+        0,
+    )
+    .needs(&[Needs::Listing])
+    .hint("Save the loaded osu.db, search and settings to attach to a bug report"),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(
+        ActionId::ExportAnonymisedRepro,
+        "Export Anonymised Reproduction...",
+        Menu::BugReport,
+        0,
+    )
+    .needs(&[Needs::Listing])
+    .hint("Replace the beatmaps' text and your name with made up words of the same length first"),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::LoadRepro, "Load Reproduction...", Menu::BugReport, 0),
+    Action::new(
+        ActionId::TogglePerformanceOverlay,
+        "Performance Overlay",
+        Menu::BugReport,
+        0,
+    )
+    .hint("Show how long frames take and how often the beatmap table's text is already laid out (F12)")
+    .shortcut(Modifiers::NONE, Key::F12)
+    .toggle(),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::GenerateReport, "Generate report...", Menu::File, 3)
+        .needs(&[Needs::Listing])
+        .unavailable("Open an osu.db to generate a report of it"),
+    Action::new(ActionId::Settings, "Settings...", Menu::File, 4),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::OpenOsuFolder, "Open osu! folder...", Menu::File, 5),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::Close, "Close", Menu::File, 6),
+    Action::new(ActionId::Find, "Find", Menu::Beatmaps, 0)
+        .needs(&[Needs::Listing])
+        .shortcut(Modifiers::COMMAND, Key::F),
+    Action::new(ActionId::JumpToBeatmap, "Jump to Beatmap...", Menu::Beatmaps, 0)
+        .needs(&[Needs::Listing])
+        .hint("Go to a beatmap by its MD5 hash or ID")
+        .shortcut(Modifiers::COMMAND, Key::G),
+    Action::new(ActionId::RandomBeatmap, "Random Beatmap", Menu::Beatmaps, 0)
+        .needs(&[Needs::Listing])
+        .hint("Pick a random beatmap from the search results")
+        .shortcut(Modifiers::NONE, Key::F2),
+    Action::new(ActionId::OpenPages, "Open Beatmap Page", Menu::Beatmaps, 1)
+        .needs(&[Needs::Listing, Needs::Selection, Needs::Submitted])
+        .unavailable("Unsubmitted beatmaps don't have a page on the osu! website")
+        .context_menu(),
+    Action::new(ActionId::OpenInOsuDirect, "Open in osu!direct", Menu::Beatmaps, 1)
+        .needs(&[Needs::Listing, Needs::Selection, Needs::Submitted])
+        .unavailable("Unsubmitted beatmaps can't be opened in osu!direct")
+        .context_menu(),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::OpenFolders, "Open Song Folder", Menu::Beatmaps, 1)
+        .needs(&[
+            Needs::Listing,
+            Needs::Selection,
+            Needs::OsuFolder,
+            Needs::Folder,
+        ])
+        .context_menu(),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::EditSettings, "Edit Settings...", Menu::Beatmaps, 1)
+        .needs(&[Needs::Editable, Needs::Listing, Needs::Selection])
+        .hint("Change the local offset, or whether the hitsounds, skin, storyboard and video are used")
+        .context_menu(),
+    Action::new(ActionId::CopyMd5, "Copy MD5", Menu::Beatmaps, 2)
+        .needs(&[Needs::Listing, Needs::Selection, Needs::Md5])
+        .context_menu(),
+    Action::new(ActionId::CopyBeatmapId, "Copy Beatmap ID", Menu::Beatmaps, 2)
+        .needs(&[Needs::Listing, Needs::Selection, Needs::DifficultyId])
+        .context_menu(),
+    Action::new(
+        ActionId::CopyMarkdownRow,
+        "Copy as Markdown Row",
+        Menu::Beatmaps,
+        2,
+    )
+    .needs(&[Needs::Listing, Needs::Selection])
+    .context_menu(),
+    Action::new(ActionId::ShowBeatmapListing, "Beatmap Listing", Menu::View, 0),
+    Action::new(ActionId::ShowOverview, "Overview", Menu::View, 0),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::ShowHealth, "Health", Menu::View, 0),
+    #[cfg(not(target_arch = "wasm32"))]
+    Action::new(ActionId::ShowDiskUsage, "Disk Usage", Menu::View, 0),
+    Action::new(ActionId::ShowCollections, "Collections", Menu::View, 0),
+    Action::new(ActionId::ShowScores, "Scores", Menu::View, 0),
+    Action::new(ActionId::ShowReplays, "Replays", Menu::View, 0),
+];
+
+/// Number of recently used actions that are ranked first in the command palette.
+pub const MAX_RECENT: usize = 8;
+
+impl Menu {
+    pub fn name(self) -> &'static str {
+        match self {
+            Menu::File => "File",
+            Menu::BugReport => "Bug Report",
+            Menu::Beatmaps => "Beatmaps",
+            Menu::View => "View",
+        }
+    }
+}
+
+impl ActionId {
+    /// Gets the action's details from the registry.
+    pub fn action(self) -> &'static Action {
+        ACTIONS
+            .iter()
+            .find(|action| action.id == self)
+            .expect("every action is registered")
+    }
+}
+
+impl Needs {
+    /// Checks whether this is met, giving the reason it isn't.
+    fn check(self, context: &ActionContext) -> Availability {
+        let (met, reason) = match self {
+            Needs::Listing => (context.listing, "Open an osu.db first"),
+            Needs::Selection => (context.selected > 0, "Select a beatmap first"),
+            Needs::Submitted => (
+                context.submitted,
+                "Unsubmitted beatmaps don't have a page on the osu! website",
+            ),
+            Needs::Md5 => (context.md5, "The beatmap doesn't have an MD5 hash"),
+            Needs::DifficultyId => (
+                context.difficulty_id,
+                "Unsubmitted beatmaps don't have an ID",
+            ),
+            Needs::OsuFolder => (
+                context.osu_folder,
+                "The osu! folder is unknown. Open osu.db from the osu! folder to locate beatmap folders.",
+            ),
+            Needs::Folder => (context.folder, "The beatmap's folder could not be found"),
+            Needs::Editable => return hidden_unless(context.editable),
+            Needs::Workspace => return hidden_unless(context.workspace),
+            Needs::Databases => (context.databases, "Open an osu.db or collection.db first"),
+        };
+
+        match met {
+            true => Availability::Enabled,
+            false => Availability::Disabled(reason),
+        }
+    }
+}
+
+fn hidden_unless(met: bool) -> Availability {
+    match met {
+        true => Availability::Enabled,
+        false => Availability::Hidden,
+    }
+}
+
+impl Action {
+    const fn new(id: ActionId, name: &'static str, menu: Menu, group: u8) -> Self {
+        Self {
+            id,
+            name,
+            menu,
+            group,
+            hint: None,
+            unavailable: None,
+            shortcut: None,
+            needs: &[],
+            context_menu: false,
+            toggle: false,
+        }
+    }
+
+    const fn needs(mut self, needs: &'static [Needs]) -> Self {
+        self.needs = needs;
+        self
+    }
+
+    const fn hint(mut self, hint: &'static str) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
+    const fn unavailable(mut self, reason: &'static str) -> Self {
+        self.unavailable = Some(reason);
+        self
+    }
+
+    const fn shortcut(mut self, modifiers: Modifiers, key: Key) -> Self {
+        self.shortcut = Some(KeyboardShortcut::new(modifiers, key));
+        self
+    }
+
+    const fn context_menu(mut self) -> Self {
+        self.context_menu = true;
+        self
+    }
+
+    const fn toggle(mut self) -> Self {
+        self.toggle = true;
+        self
+    }
+
+    /// Checks whether the action can be used. Actions that don't apply are hidden, even if something else they need
+    /// is missing too.
+    pub fn availability(&self, context: &ActionContext) -> Availability {
+        let mut checks = self.needs.iter().map(|needs| needs.check(context));
+
+        if checks.clone().any(|check| check == Availability::Hidden) {
+            return Availability::Hidden;
+        }
+
+        match checks.find(|check| *check != Availability::Enabled) {
+            Some(Availability::Disabled(reason)) => {
+                Availability::Disabled(self.unavailable.unwrap_or(reason))
+            }
+            _ => Availability::Enabled,
+        }
+    }
+
+    /// Gets the text shown for the action, with the number of beatmaps it applies to when there's more than one.
+    pub fn label(&self, context: &ActionContext) -> String {
+        let name = tr(self.name);
+
+        if self.needs.contains(&Needs::Selection) && context.selected > 1 {
+            format!("{} ({})", name, context.selected)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Gets the text that the command palette searches, which includes the menu the action is in.
+    pub fn palette_text(&self) -> String {
+        format!("{}: {}", tr(self.menu.name()), tr(self.name))
+    }
+
+    /// Checks whether a toggled action is currently on.
+    pub fn is_checked(&self, context: &ActionContext) -> bool {
+        match self.id {
+            ActionId::TogglePerformanceOverlay => context.performance_overlay,
+            _ => false,
+        }
+    }
+}
+
+/// Renders an action as a menu item, returning whether it was clicked. Nothing is shown if the action is hidden.
+pub fn menu_item(ui: &mut egui::Ui, action: &Action, context: &ActionContext) -> bool {
+    let availability = action.availability(context);
+    let enabled = availability == Availability::Enabled;
+
+    if availability == Availability::Hidden {
+        return false;
+    }
+
+    let mut response = if action.toggle {
+        let mut checked = action.is_checked(context);
+        ui.add_enabled(
+            enabled,
+            egui::Checkbox::new(&mut checked, action.label(context)),
+        )
+    } else {
+        let mut button = egui::Button::new(action.label(context));
+
+        if let Some(shortcut) = &action.shortcut {
+            button = button.shortcut_text(ui.ctx().format_shortcut(shortcut));
+        }
+
+        ui.add_enabled(enabled, button)
+    };
+
+    if let Availability::Disabled(reason) = availability {
+        response = response.on_disabled_hover_text(tr(reason));
+    }
+
+    if let Some(hint) = action.hint {
+        response = response.on_hover_text(tr(hint));
+    }
+
+    // Checkboxes leave the menu open, so that the change can be seen
+    let clicked = response.clicked();
+    if clicked && !action.toggle {
+        ui.close_menu();
+    }
+
+    clicked
+}
+
+/// Renders the actions in one group of a menu, returning the one that was clicked.
+pub fn menu_group(
+    ui: &mut egui::Ui,
+    menu: Menu,
+    group: u8,
+    context: &ActionContext,
+) -> Option<ActionId> {
+    let mut clicked = None;
+
+    for action in ACTIONS
+        .iter()
+        .filter(|action| action.menu == menu && action.group == group)
+    {
+        if menu_item(ui, action, context) {
+            clicked = Some(action.id);
+        }
+    }
+
+    clicked
+}
+
+/// Renders every action in a menu, with separators between the groups, returning the one that was clicked. Only the
+/// actions shown in context menus are rendered if `context_menu` is set.
+pub fn menu(
+    ui: &mut egui::Ui,
+    menu: Menu,
+    context: &ActionContext,
+    context_menu: bool,
+) -> Option<ActionId> {
+    let mut clicked = None;
+    let mut group = None;
+
+    for action in ACTIONS.iter().filter(|action| {
+        action.menu == menu
+            && (action.context_menu || !context_menu)
+            && action.availability(context) != Availability::Hidden
+    }) {
+        if group.is_some_and(|group| group != action.group) {
+            ui.separator();
+        }
+
+        group = Some(action.group);
+
+        if menu_item(ui, action, context) {
+            clicked = Some(action.id);
+        }
+    }
+
+    clicked
+}
+
+/// Finds the actions matching a command palette query, best match first, leaving out any that are hidden.
+///
+/// Every character of the query has to appear in order in the action's menu and name, e.g. `opdb` matches
+/// `File: Open osu.db...`. Matches at the start of words and runs of consecutive characters score higher, and recently
+/// used actions (most recent first in `recent`) are ranked above others that match as well. With an empty query, the
+/// recent actions come first, then the rest in menu order.
+pub fn search(query: &str, context: &ActionContext, recent: &[ActionId]) -> Vec<&'static Action> {
+    let query = fuzzy::fold(query)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+
+    let mut matches = ACTIONS
+        .iter()
+        .filter(|action| action.availability(context) != Availability::Hidden)
+        .filter_map(|action| {
+            let score = match_score(&query, &fuzzy::fold(&action.palette_text()))?;
+            let recency = recent
+                .iter()
+                .position(|id| *id == action.id)
+                .map_or(0, |i| MAX_RECENT - i.min(MAX_RECENT - 1));
+
+            Some((action, score, recency))
+        })
+        .collect::<Vec<_>>();
+
+    // Sorting is stable, so actions that score the same stay in menu order
+    matches.sort_by(|a, b| (b.1 + b.2).cmp(&(a.1 + a.2)).then(b.2.cmp(&a.2)));
+    matches.into_iter().map(|(action, ..)| action).collect()
+}
+
+/// Scores how well the query's characters match the text in order, or returns `None` if they don't all appear.
+fn match_score(query: &[char], text: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut remaining = query.iter().peekable();
+    let mut previous = None;
+    let mut consecutive = false;
+
+    for c in text.chars() {
+        let Some(&&wanted) = remaining.peek() else {
+            break;
+        };
+
+        if c == wanted {
+            let word_start = !previous.is_some_and(|p: char| p.is_alphanumeric());
+            score += 1 + 2 * word_start as usize + consecutive as usize;
+            consecutive = true;
+            remaining.next();
+        } else {
+            consecutive = false;
+        }
+
+        previous = Some(c);
+    }
+
+    remaining.peek().is_none().then_some(score)
+}
+
+/// Moves an action to the front of the recently used actions, keeping at most [`MAX_RECENT`] of them.
+pub fn record_use(recent: &mut Vec<ActionId>, id: ActionId) {
+    recent.retain(|recent| *recent != id);
+    recent.insert(0, id);
+    recent.truncate(MAX_RECENT);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(actions: Vec<&Action>) -> Vec<ActionId> {
+        actions.into_iter().map(|action| action.id).collect()
+    }
+
+    fn loaded() -> ActionContext {
+        ActionContext {
+            listing: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn every_action_is_registered_once() {
+        for action in ACTIONS {
+            assert_eq!(action.id.action().name, action.name);
+            assert_eq!(
+                ACTIONS.iter().filter(|other| other.id == action.id).count(),
+                1,
+                "{:?}",
+                action.id
+            );
+        }
+    }
+
+    #[test]
+    fn queries_match_characters_in_order() {
+        let context = loaded();
+
+        assert_eq!(
+            search("opdb", &context, &[]).first().map(|a| a.id),
+            Some(ActionId::OpenBeatmapListing)
+        );
+        assert_eq!(
+            search("copy md5", &context, &[]).first().map(|a| a.id),
+            Some(ActionId::CopyMd5)
+        );
+        assert_eq!(
+            search("VIEW: scores", &context, &[]).first().map(|a| a.id),
+            Some(ActionId::ShowScores)
+        );
+        assert!(search("zzz", &context, &[]).is_empty());
+        assert!(search("md5 copy", &context, &[]).is_empty());
+
+        // An empty query lists everything that isn't hidden
+        let shown = ACTIONS
+            .iter()
+            .filter(|action| action.availability(&context) != Availability::Hidden);
+        assert_eq!(search("", &context, &[]).len(), shown.count());
+    }
+
+    #[test]
+    fn recent_actions_are_ranked_first() {
+        let context = loaded();
+        let mut recent = Vec::new();
+
+        record_use(&mut recent, ActionId::Settings);
+        record_use(&mut recent, ActionId::CopyMd5);
+        record_use(&mut recent, ActionId::Settings);
+        assert_eq!(recent, [ActionId::Settings, ActionId::CopyMd5]);
+
+        assert_eq!(
+            ids(search("", &context, &recent))[..3],
+            [
+                ActionId::Settings,
+                ActionId::CopyMd5,
+                ActionId::OpenBeatmapListing
+            ]
+        );
+
+        // "open" matches several actions equally well at the start of a word, so the recent one wins
+        record_use(&mut recent, ActionId::OpenReplay);
+        assert_eq!(
+            search("open", &context, &recent).first().map(|a| a.id),
+            Some(ActionId::OpenReplay)
+        );
+
+        for action in &ACTIONS[..20] {
+            record_use(&mut recent, action.id);
+        }
+        assert_eq!(recent.len(), MAX_RECENT);
+    }
+
+    #[test]
+    fn selection_actions_need_a_selection() {
+        let copy = ActionId::CopyMd5.action();
+        let pages = ActionId::OpenPages.action();
+
+        assert_eq!(
+            copy.availability(&ActionContext::default()),
+            Availability::Disabled("Open an osu.db first")
+        );
+        assert_eq!(
+            copy.availability(&loaded()),
+            Availability::Disabled("Select a beatmap first")
+        );
+
+        let selected = ActionContext {
+            selected: 2,
+            md5: true,
+            ..loaded()
+        };
+        assert_eq!(copy.availability(&selected), Availability::Enabled);
+        assert_eq!(copy.label(&selected), "Copy MD5 (2)");
+
+        // Actions with their own explanation use it, whatever they're missing
+        assert_eq!(
+            pages.availability(&selected),
+            Availability::Disabled("Unsubmitted beatmaps don't have a page on the osu! website")
+        );
+    }
+
+    #[test]
+    fn actions_that_do_not_apply_are_hidden() {
+        let databases = ActionId::ShowDatabases.action();
+        assert_eq!(databases.availability(&loaded()), Availability::Hidden);
+        assert_eq!(
+            databases.availability(&ActionContext {
+                workspace: true,
+                ..loaded()
+            }),
+            Availability::Enabled
+        );
+
+        let results = ids(search("databases", &loaded(), &[]));
+        assert!(!results.contains(&ActionId::ShowDatabases), "{:?}", results);
+
+        // Disabled actions are still found, so that the palette can say why they can't be used
+        let results = ids(search("compare", &ActionContext::default(), &[]));
+        assert_eq!(results, [ActionId::CompareWith]);
+    }
+
+    #[test]
+    fn context_menus_only_have_selection_actions() {
+        for action in ACTIONS.iter().filter(|action| action.context_menu) {
+            assert_eq!(action.menu, Menu::Beatmaps);
+            assert!(action.needs.contains(&Needs::Selection), "{:?}", action.id);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::{fs::File, io::BufReader, path::Path, time::Duration};
+
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+
+/// How often to repaint while audio is playing, so that the seek bar keeps moving.
+const REPAINT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Plays a beatmap's audio file from its preview point, like hovering over a beatmap in song select.
+pub struct AudioPreview {
+    /// Audio output, which is opened the first time something is played and kept open afterwards
+    output: Option<(OutputStream, OutputStreamHandle)>,
+
+    /// Audio that is currently playing
+    playing: Option<Playing>,
+
+    /// Volume between 0 and 1, which is kept between previews
+    volume: f32,
+
+    /// Reason the most recent preview couldn't be played, along with the beatmap it was for
+    error: Option<(String, AudioError)>,
+}
+
+/// Represents audio that is currently playing.
+struct Playing {
+    /// MD5 hash of the beatmap the audio belongs to
+    md5: String,
+    sink: Sink,
+
+    /// Length of the audio, which is used for the seek bar
+    length: Duration,
+}
+
+/// Represents the reason audio couldn't be played.
+enum AudioError {
+    /// No audio output device could be opened
+    Output(String),
+
+    /// The audio file couldn't be opened
+    Open(std::io::Error),
+
+    /// The audio file isn't in a supported format, or is corrupt
+    Decode(rodio::decoder::DecoderError),
+}
+
+impl std::fmt::Display for AudioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioError::Output(e) => write!(f, "Unable to open audio output: {}", e),
+            AudioError::Open(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                write!(f, "The audio file could not be found")
+            }
+            AudioError::Open(e) => write!(f, "Unable to open the audio file: {}", e),
+            AudioError::Decode(e) => write!(f, "Unable to play the audio file: {}", e),
+        }
+    }
+}
+
+impl Default for AudioPreview {
+    fn default() -> Self {
+        Self {
+            output: None,
+            playing: None,
+            volume: 0.5,
+            error: None,
+        }
+    }
+}
+
+impl AudioPreview {
+    /// Stops the audio if it doesn't belong to the selected beatmap, e.g. because a different beatmap was selected.
+    pub fn sync_selection(&mut self, md5: Option<&str>) {
+        if self
+            .playing
+            .as_ref()
+            .is_some_and(|playing| Some(playing.md5.as_str()) != md5)
+        {
+            self.stop();
+        }
+    }
+
+    /// Stops any audio that is playing.
+    pub fn stop(&mut self) {
+        if let Some(playing) = self.playing.take() {
+            playing.sink.stop();
+        }
+    }
+
+    /// Starts playing an audio file from `start`. `length` is used for the seek bar if the length of the audio can't be
+    /// found from the file itself.
+    pub fn play(&mut self, md5: &str, path: &Path, start: Duration, length: Duration) {
+        self.stop();
+        self.error = None;
+
+        if let Err(e) = self.try_play(md5, path, start, length) {
+            log::error!("Unable to play audio '{}': {}", path.display(), e);
+            self.error = Some((md5.to_string(), e));
+        }
+    }
+
+    /// Opens the audio output if needed, and starts playing an audio file from `start`.
+    fn try_play(
+        &mut self,
+        md5: &str,
+        path: &Path,
+        start: Duration,
+        length: Duration,
+    ) -> Result<(), AudioError> {
+        let source = Decoder::new(BufReader::new(File::open(path).map_err(AudioError::Open)?))
+            .map_err(AudioError::Decode)?;
+        let length = source.total_duration().unwrap_or(length);
+
+        if self.output.is_none() {
+            self.output =
+                Some(OutputStream::try_default().map_err(|e| AudioError::Output(e.to_string()))?);
+        }
+
+        let (_, handle) = self.output.as_ref().expect("audio output was just opened");
+        let sink = Sink::try_new(handle).map_err(|e| AudioError::Output(e.to_string()))?;
+
+        sink.set_volume(self.volume);
+        sink.append(source);
+
+        // Some formats can't be seeked, in which case the preview starts from the beginning instead
+        if let Err(e) = sink.try_seek(start) {
+            log::warn!("Unable to seek audio '{}': {}", path.display(), e);
+        }
+
+        self.playing = Some(Playing {
+            md5: md5.to_string(),
+            sink,
+            length,
+        });
+
+        Ok(())
+    }
+
+    /// Renders the controls for previewing a beatmap's audio file.
+    ///
+    /// `path` is the beatmap's audio file if it could be located, or the reason it couldn't be.
+    pub fn controls(
+        &mut self,
+        ui: &mut egui::Ui,
+        md5: &str,
+        path: Result<&Path, &str>,
+        start: Duration,
+        length: Duration,
+    ) {
+        // Forget about audio that has finished playing
+        if self
+            .playing
+            .as_ref()
+            .is_some_and(|playing| playing.sink.empty())
+        {
+            self.playing = None;
+        }
+
+        let is_playing = self
+            .playing
+            .as_ref()
+            .is_some_and(|playing| playing.md5 == md5);
+
+        ui.horizontal_wrapped(|ui| {
+            if is_playing {
+                if ui.button("⏹ Stop").clicked() {
+                    self.stop();
+                }
+            } else {
+                let response = ui.add_enabled(path.is_ok(), egui::Button::new("▶ Preview"));
+                let response = match path {
+                    Ok(_) => {
+                        response.on_hover_text("Play the beatmap's audio from its preview point")
+                    }
+                    Err(reason) => response.on_disabled_hover_text(reason),
+                };
+
+                if response.clicked() {
+                    if let Ok(path) = path {
+                        self.play(md5, path, start, length);
+                    }
+                }
+            }
+
+            ui.label("🔊");
+            if ui
+                .add(egui::Slider::new(&mut self.volume, 0.0..=1.0).show_value(false))
+                .changed()
+            {
+                if let Some(playing) = &self.playing {
+                    playing.sink.set_volume(self.volume);
+                }
+            }
+        });
+
+        if let Some(playing) = self.playing.as_ref().filter(|playing| playing.md5 == md5) {
+            let length = playing.length.max(playing.sink.get_pos());
+            let mut position = playing.sink.get_pos().as_secs_f64();
+
+            ui.horizontal(|ui| {
+                let response = ui.add(
+                    egui::Slider::new(&mut position, 0.0..=length.as_secs_f64()).show_value(false),
+                );
+
+                if response.changed() {
+                    if let Err(e) = playing.sink.try_seek(Duration::from_secs_f64(position)) {
+                        log::warn!("Unable to seek audio: {}", e);
+                    }
+                }
+
+                let remaining = length.saturating_sub(Duration::from_secs_f64(position));
+                ui.label(format!(
+                    "-{}:{:02}",
+                    remaining.as_secs() / 60,
+                    remaining.as_secs() % 60
+                ));
+            });
+
+            ui.ctx().request_repaint_after(REPAINT_INTERVAL);
+        }
+
+        if let Some((_, error)) = self
+            .error
+            .as_ref()
+            .filter(|(error_md5, _)| error_md5 == md5)
+        {
+            ui.colored_label(ui.visuals().warn_fg_color, error.to_string());
+        }
+    }
+}
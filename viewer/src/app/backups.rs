@@ -0,0 +1,235 @@
+use std::path::{Path, PathBuf};
+
+use osu_db_parser::{
+    backups::{Backup, BackupMethod, Backups},
+    prelude::TimestampKind,
+};
+
+use super::{collection_listing::osu_appears_to_be_running, format_size, settings::ValueFormat};
+
+/// Backs up a database before it's saved, keeping the newest `keep` backups of it, or does nothing if `keep` is 0.
+/// The error is a message to show in place of the save status, since the file shouldn't be saved without a backup.
+pub fn back_up(path: &Path, operation: &str, changed: usize, keep: usize) -> Result<(), String> {
+    if keep == 0 {
+        return Ok(());
+    }
+
+    match Backups::beside(path)
+        .keep(keep)
+        .back_up(path, operation, changed)
+    {
+        Ok(Some(backup)) => {
+            log::info!(
+                "Backed up '{}' to '{}'",
+                path.display(),
+                backup.path.display()
+            );
+            Ok(())
+        }
+        Ok(None) => Ok(()),
+        Err(e) => {
+            log::error!("Unable to back up '{}': {}", path.display(), e);
+            Err(format!("Unable to back up the file before saving: {}", e))
+        }
+    }
+}
+
+/// A window listing the backups made of the open databases before they were saved, any of which can be put back.
+#[derive(Default)]
+pub struct RestoreBackupWindow {
+    pub visible: bool,
+
+    /// Databases whose backups can be restored
+    databases: Vec<PathBuf>,
+
+    /// Index of the database whose backups are shown
+    selected: usize,
+
+    /// Backups of the selected database, newest first
+    backups: Vec<Backup>,
+
+    /// ID of the backup waiting to be confirmed before it's restored
+    confirm: Option<String>,
+
+    /// Whether osu! was running when the restore was asked for, in which case it might write over the restored file
+    osu_running: bool,
+
+    /// Result of the most recent restore, or why the backups couldn't be listed or restored
+    status: Option<Result<String, String>>,
+}
+
+impl RestoreBackupWindow {
+    /// Opens the window for the backups of some databases, e.g. the open `osu!.db` and `collection.db`.
+    pub fn open(&mut self, databases: Vec<PathBuf>) {
+        self.databases = databases;
+        self.selected = 0;
+        self.confirm = None;
+        self.status = None;
+        self.visible = true;
+        self.refresh();
+    }
+
+    /// Lists the backups of the selected database again.
+    fn refresh(&mut self) {
+        self.backups.clear();
+
+        let Some(path) = self.databases.get(self.selected) else {
+            return;
+        };
+
+        match Backups::beside(path).list_for(path) {
+            Ok(backups) => self.backups = backups,
+            Err(e) => self.status = Some(Err(format!("Unable to list the backups: {}", e))),
+        }
+    }
+
+    /// Renders the window, returning the database that was restored so that it can be opened again.
+    pub fn view(&mut self, ctx: &egui::Context, format: &ValueFormat) -> Option<PathBuf> {
+        let mut visible = self.visible;
+        let mut restored = None;
+
+        egui::Window::new("Restore Backup")
+            .open(&mut visible)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                if self.databases.len() > 1 {
+                    let mut selected = self.selected;
+
+                    egui::ComboBox::from_id_salt("restore_backup_database")
+                        .selected_text(self.databases[selected].display().to_string())
+                        .show_ui(ui, |ui| {
+                            for (i, path) in self.databases.iter().enumerate() {
+                                ui.selectable_value(&mut selected, i, path.display().to_string());
+                            }
+                        });
+
+                    if selected != self.selected {
+                        self.selected = selected;
+                        self.confirm = None;
+                        self.refresh();
+                    }
+                }
+
+                if self.backups.is_empty() {
+                    ui.weak("There are no backups of this database yet. One is made each time it's saved.");
+                } else {
+                    self.backup_grid(ui, format);
+                }
+
+                if let Some(id) = self.confirm.clone() {
+                    ui.separator();
+                    restored = self.confirm_restore(ui, &id);
+                }
+
+                match &self.status {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, message);
+                    }
+                    None => {}
+                }
+            });
+
+        self.visible = visible;
+        restored
+    }
+
+    /// Renders the backups of the selected database, with a button to restore each one.
+    fn backup_grid(&mut self, ui: &mut egui::Ui, format: &ValueFormat) {
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                egui::Grid::new("restore_backup_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Made");
+                        ui.strong("Before");
+                        ui.strong("Changed");
+                        ui.strong("Size");
+                        ui.end_row();
+
+                        for backup in &self.backups {
+                            ui.label(format.datetime(backup.created, TimestampKind::Utc))
+                                .on_hover_text(&backup.id);
+                            ui.label(&backup.operation);
+                            ui.label(backup.changed.to_string());
+                            ui.label(format_size(backup.size))
+                                .on_hover_text(match backup.method {
+                                    BackupMethod::HardLink => {
+                                        "Stored as a hard link, so it takes up no extra space"
+                                    }
+                                    BackupMethod::Copy => "Stored as a copy",
+                                });
+
+                            if ui.button("Restore...").clicked() {
+                                self.confirm = Some(backup.id.clone());
+                                self.osu_running = osu_appears_to_be_running();
+                                self.status = None;
+                            }
+
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    /// Asks for confirmation before restoring a backup, returning the database if it was restored.
+    fn confirm_restore(&mut self, ui: &mut egui::Ui, id: &str) -> Option<PathBuf> {
+        let path = self.databases.get(self.selected)?.clone();
+
+        ui.label(format!(
+            "Replace {} with backup {}? The current file is backed up first, so this can be undone.",
+            path.display(),
+            id
+        ));
+
+        if self.osu_running {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "osu! appears to be running, and may replace the file again when it closes. Close osu! first.",
+            );
+        }
+
+        let mut restored = None;
+
+        ui.horizontal(|ui| {
+            if ui.button("Restore").clicked() {
+                self.confirm = None;
+
+                // At least as many backups are kept as there are now, so that restoring doesn't remove any
+                let backups = Backups::beside(&path).keep(self.backups.len() + 1);
+
+                self.status = Some(match backups.restore(id) {
+                    Ok(backup) => {
+                        restored = Some(path.clone());
+                        Ok(format!(
+                            "Restored {} to how it was before {}",
+                            path.display(),
+                            backup.operation
+                        ))
+                    }
+                    Err(e) => {
+                        log::error!(
+                            "Unable to restore backup {} of '{}': {}",
+                            id,
+                            path.display(),
+                            e
+                        );
+                        Err(format!("Unable to restore the backup: {}", e))
+                    }
+                });
+
+                self.refresh();
+            }
+
+            if ui.button("Cancel").clicked() {
+                self.confirm = None;
+            }
+        });
+
+        restored
+    }
+}
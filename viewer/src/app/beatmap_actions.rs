@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+
+use osu_db_parser::{links, prelude::*};
+
+use super::{format_count, open_url};
+
+/// Number of pages or folders that can be opened at once without asking first.
+const CONFIRM_THRESHOLD: usize = 10;
+
+/// Opens pages and folders for beatmaps, asking for confirmation before opening a large number at once.
+#[derive(Default)]
+pub struct BulkOpener {
+    /// Items waiting for the user to confirm that they should be opened
+    pending: Vec<Target>,
+}
+
+/// Represents something that can be opened for a beatmap.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Target {
+    Link(String),
+
+    #[cfg(not(target_arch = "wasm32"))]
+    Folder(PathBuf),
+}
+
+impl Target {
+    /// Opens the link in the browser, or the folder in the file manager.
+    fn open(&self) {
+        match self {
+            Target::Link(url) => open_url(url),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            Target::Folder(path) => {
+                if let Err(e) = open::that_detached(path) {
+                    log::error!("Unable to open folder '{}': {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+impl BulkOpener {
+    /// Opens the beatmap pages on the osu! website, skipping unsubmitted beatmaps.
+    pub fn open_pages<'a>(&mut self, beatmaps: impl Iterator<Item = &'a BeatmapEntry>) {
+        self.open(beatmaps.filter_map(links::beatmap_url).map(Target::Link));
+    }
+
+    /// Opens the beatmaps in osu!direct, skipping unsubmitted beatmaps.
+    pub fn open_in_osu_direct<'a>(&mut self, beatmaps: impl Iterator<Item = &'a BeatmapEntry>) {
+        self.open(beatmaps.filter_map(links::osu_direct_url).map(Target::Link));
+    }
+
+    /// Opens the beatmaps' folders in the file manager, skipping any that don't exist.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_folders<'a>(
+        &mut self,
+        osu_directory: &Path,
+        beatmaps: impl Iterator<Item = &'a BeatmapEntry>,
+    ) {
+        self.open(
+            beatmaps
+                .filter_map(|beatmap| osu_db_parser::paths::beatmap_folder(osu_directory, beatmap))
+                .filter(|path| path.is_dir())
+                .map(Target::Folder),
+        );
+    }
+
+    /// Opens the targets straight away, or asks for confirmation if there are too many.
+    fn open(&mut self, targets: impl Iterator<Item = Target>) {
+        // Difficulties from the same beatmapset share a folder, and may share a link if the difficulty is unknown
+        let mut seen = HashSet::new();
+        let targets = targets
+            .filter(|target| seen.insert(target.clone()))
+            .collect::<Vec<_>>();
+
+        if targets.len() > CONFIRM_THRESHOLD {
+            self.pending = targets;
+        } else {
+            targets.iter().for_each(Target::open);
+        }
+    }
+
+    /// Renders the confirmation window, if there are items waiting to be opened.
+    pub fn view(&mut self, ctx: &egui::Context) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let mut confirmed = None;
+
+        egui::Window::new("Open Multiple Items")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "This will open {} items at once. Are you sure?",
+                    format_count(self.pending.len())
+                ));
+
+                ui.horizontal(|ui| {
+                    if ui.button("Open All").clicked() {
+                        confirmed = Some(true);
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        confirmed = Some(false);
+                    }
+                });
+            });
+
+        match confirmed {
+            Some(true) => std::mem::take(&mut self.pending)
+                .iter()
+                .for_each(Target::open),
+            Some(false) => self.pending.clear(),
+            None => {}
+        }
+    }
+}
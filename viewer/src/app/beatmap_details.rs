@@ -1,5 +1,6 @@
 use egui::{Id, RichText};
-use osu_db_parser::prelude::*;
+use osu_db_parser::{format, prelude::*};
+use time::UtcOffset;
 
 use super::{maybe_signed_u32, mods_string, open_beatmap_in_browser, optional_string};
 
@@ -12,6 +13,16 @@ pub struct BeatmapDetailsWindow {
 }
 
 impl BeatmapDetailsWindow {
+    /// Creates a visible window for a beatmap, titled using its artist, title and difficulty.
+    pub fn new(id: Id, beatmap: &BeatmapEntry, language: MetadataLanguage) -> Self {
+        Self {
+            id,
+            title: beatmap.display_name(language),
+            visible: true,
+            data: beatmap.clone(),
+        }
+    }
+
     pub fn view(&mut self, ctx: &egui::Context) {
         egui::Window::new(&self.title)
             .id(self.id)
@@ -242,7 +253,7 @@ impl BeatmapDetailsWindow {
                             ui.end_row();
 
                             ui.label("Last Played");
-                            ui.label(self.data.last_played.to_string());
+                            ui.label(format::date_time(self.data.last_played, UtcOffset::UTC));
                             ui.end_row();
 
                             ui.label("Is osz2?");
@@ -1,32 +1,636 @@
-use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+use std::{collections::HashMap, sync::Arc};
 
 use egui::Id;
-use osu_db_parser::prelude::*;
+use osu_db_parser::{analysis, format, prelude::*};
 
 use super::{
-    beatmap_details::BeatmapDetailsWindow, flagset_string, open_beatmap_in_browser,
+    actions::{ActionContext, ActionId},
+    beatmap_actions::BulkOpener,
+    beatmap_details::BeatmapDetailsWindow,
+    beatmap_panel::{BeatmapPanel, BeatmapSettings, PanelAction},
+    beatmap_table::{BeatmapTable, RowAction, RowMenu, Selection, TableLayout},
+    collection_listing::{AddToCollection, CollectionMembership, CollectionRequest},
+    diagnostics::Diagnostics,
+    export::ExportDialog,
+    filter_bar::FilterBar,
+    flagset_string, format_count,
+    import_list::{ImportAction, ImportListDialog},
+    jump_to::{BeatmapIndex, JumpToWindow},
+    mode_tabs::{ModeTabs, TabState, TABS},
+    notes::NoteStore,
+    pinboard::{PinAction, PinboardStrip},
+    presets::FilterPreset,
+    random_pick::{RandomAction, RandomPicker},
+    score_columns::LocalScores,
     score_details::ScoreDetailsWindow,
+    score_listing::{show_score_details, ScoreAction},
+    search::SearchBox,
+    settings::Settings,
+    text_cache::CacheStats,
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use super::{
+    backups, bulk_edit::BulkEditDialog, collection_listing::osu_appears_to_be_running,
+    diagnostics::SkippedEntries, report::ReportDialog, score_listing::ReplayDialog,
+};
+
+/// Represents an action requested from the controls for the unsaved edits.
+#[cfg(not(target_arch = "wasm32"))]
+enum EditAction {
+    Undo,
+    Redo,
+    Save,
+}
+
+/// Number of similar beatmaps shown for the selected beatmap.
+const SIMILAR_MAPS: usize = 10;
+
 /// A view for displaying beatmap listing details.
 #[derive(Default)]
 pub struct BeatmapListingView {
-    data: Option<BeatmapListing>,
-    selected_beatmap_md5: Option<String>,
+    data: Option<Arc<BeatmapListing>>,
+
+    /// Number of beatmaps that couldn't be parsed when the listing was loaded
+    skipped: usize,
+
+    /// Problems found when the listing was loaded, and whether they're being shown
+    diagnostics: Diagnostics,
+    show_diagnostics: bool,
+
+    selection: Selection,
+    search: SearchBox,
+    table: BeatmapTable,
+    tabs: ModeTabs,
+    panel: BeatmapPanel,
+    opener: BulkOpener,
+    export: ExportDialog,
+    import_list: ImportListDialog,
+    jump_to: JumpToWindow,
+    random: RandomPicker,
+    pinboard: PinboardStrip,
+
+    /// Lookup of the loaded beatmaps by MD5 hash and ID, for jumping to a beatmap
+    index: BeatmapIndex,
+
+    /// Summary of the beatmaps matching the search, and the star ratings it uses
+    aggregates: FilterAggregates,
+    star_policy: StarPolicy,
+
+    /// Index of the beatmap that similar beatmaps were last found for, and the indices and scores of those beatmaps
+    similar: Option<(usize, Vec<(usize, f32)>)>,
+
+    /// Whether the filter presets have been changed since they were last saved
+    presets_changed: bool,
+
+    /// Action from the menu bar or command palette, which is handled the next time the view is shown
+    queued_action: Option<ActionId>,
 
     beatmap_windows: HashMap<String, BeatmapDetailsWindow>,
     score_windows: HashMap<String, ScoreDetailsWindow>,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    replay_dialog: Option<ReplayDialog>,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    report: ReportDialog,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    bulk_edit: BulkEditDialog,
+
+    /// Changes to the beatmaps' settings made from the panel, which can be undone. These are made to a copy of the
+    /// listing, which replaces `data` once it has been saved to `osu!.db`.
+    #[cfg(not(target_arch = "wasm32"))]
+    edits: Option<EditSession>,
+
+    /// Path of the `osu!.db` file the listing was loaded from, which edits are saved to
+    #[cfg(not(target_arch = "wasm32"))]
+    source_path: Option<PathBuf>,
+
+    /// Set when osu! appears to be running, so that the user can confirm whether to save anyway
+    #[cfg(not(target_arch = "wasm32"))]
+    confirm_save: bool,
+
+    /// Set when the edits have been saved, so that the change to the file isn't mistaken for one made by osu!
+    #[cfg(not(target_arch = "wasm32"))]
+    saved: bool,
+
+    /// Result of the most recent save
+    #[cfg(not(target_arch = "wasm32"))]
+    save_status: Option<String>,
+
+    /// Number of backups of `osu!.db` kept when saving, or 0 to not back it up
+    #[cfg(not(target_arch = "wasm32"))]
+    keep_backups: usize,
 }
 
 impl BeatmapListingView {
-    /// Loads a beatmap listing into this view.
-    pub fn load_beatmap_listing(&mut self, beatmap_listing: BeatmapListing) {
-        self.data = Some(beatmap_listing);
-        self.selected_beatmap_md5 = None;
+    /// Gets the layout of the beatmap table, so that it can be saved.
+    pub fn table_layout(&self) -> &TableLayout {
+        self.table.layout()
+    }
+
+    /// Restores a saved layout for the beatmap table.
+    pub fn set_table_layout(&mut self, layout: TableLayout) {
+        self.table.set_layout(layout);
+    }
+
+    /// Applies the settings that affect how beatmaps are shown.
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        let beatmaps = self
+            .data
+            .as_deref()
+            .map(|listing| listing.beatmaps.as_slice())
+            .unwrap_or_default();
+
+        self.table.set_display(
+            beatmaps,
+            settings.metadata_language(),
+            settings.star_policy,
+            settings.value_format(),
+        );
+        self.table
+            .set_custom_columns(beatmaps, settings.custom_columns());
+        self.panel.format = settings.value_format();
+        self.panel.language = settings.metadata_language();
+        self.pinboard.set_display(
+            settings.metadata_language(),
+            settings.star_policy,
+            settings.value_format(),
+        );
+        // Both settings affect which beatmaps match, so the other tabs need to be filtered again if they change
+        let fuzzy_changed = self.search.set_fuzzy_fallback(settings.fuzzy_fallback);
+        let stars_changed = self.search.set_star_policy(settings.star_policy);
+
+        if fuzzy_changed || stars_changed {
+            self.tabs.invalidate();
+        }
+
+        if self.star_policy != settings.star_policy {
+            self.star_policy = settings.star_policy;
+            self.refresh_aggregates();
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.keep_backups = settings.keep_backups;
+        }
+    }
+
+    /// Summarises the beatmaps matching the search, after the rows have changed.
+    fn refresh_aggregates(&mut self) {
+        let beatmaps = self
+            .data
+            .as_deref()
+            .map(|listing| listing.beatmaps.as_slice())
+            .unwrap_or_default();
+
+        self.aggregates = FilterAggregates::from_beatmaps(
+            self.table.rows().iter().filter_map(|&i| beatmaps.get(i)),
+            self.star_policy,
+        );
+    }
+
+    /// Loads a beatmap listing into this view. `skipped` is the number of beatmaps that couldn't be parsed because of
+    /// `error`, and `notes` are the user's notes, which the search can use.
+    pub fn load_beatmap_listing(
+        &mut self,
+        beatmap_listing: BeatmapListing,
+        skipped: usize,
+        error: Option<&Error>,
+        notes: &Notes,
+    ) {
+        self.search.cancel();
+        self.search.set_listing(&beatmap_listing);
+        let rows = self.search.matching_rows(&beatmap_listing.beatmaps, notes);
+
+        self.table.set_beatmapsets(beatmap_listing.beatmapsets());
+        self.table.update_custom_values(&beatmap_listing.beatmaps);
+        self.table.set_rows(&beatmap_listing.beatmaps, rows);
+        self.table.clear_text_cache();
+        self.index = BeatmapIndex::new(&beatmap_listing);
+        self.diagnostics = Diagnostics::new(&beatmap_listing, skipped, error);
+        self.data = Some(Arc::new(beatmap_listing));
+        self.selection.clear();
+        self.tabs.reset_for_new_listing();
+        self.random.clear();
+        self.similar = None;
+        self.skipped = skipped;
+        self.refresh_aggregates();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.edits = None;
+            self.save_status = None;
+            self.table.set_edited(BTreeSet::new());
+        }
+    }
+
+    /// Gets the MD5 hashes of the selected beatmaps, with the most recently clicked one first, so that the selection can
+    /// be restored after reloading the listing.
+    pub fn selected_md5s(&self) -> Vec<String> {
+        let Some(beatmap_listing) = &self.data else {
+            return Vec::new();
+        };
+
+        let beatmaps = &beatmap_listing.beatmaps;
+        let primary = self
+            .selection
+            .primary
+            .and_then(|i| beatmaps.get(i))
+            .and_then(|b| b.md5.clone());
+
+        let mut md5s = self.selection.md5s(beatmaps);
+        md5s.retain(|md5| Some(md5) != primary.as_ref());
+        primary.into_iter().chain(md5s).collect()
+    }
+
+    /// Selects the beatmaps with the given MD5 hashes, e.g. after reloading the listing. The first hash is treated as the
+    /// most recently clicked beatmap. Beatmaps that are no longer in the listing or hidden by the search are skipped.
+    pub fn select_md5s(&mut self, md5s: &[String]) {
+        let indices = md5s
+            .iter()
+            .filter_map(|md5| self.index.by_md5(md5))
+            .collect::<Vec<_>>();
+
+        self.selection.clear();
+        self.selection.beatmaps.extend(&indices);
+        self.selection.primary = indices.first().copied();
+        self.selection.anchor = self.selection.primary;
+        self.selection.retain_shown(self.table.rows());
+    }
+
+    /// Sets the local scores shown in the table's score columns, e.g. once a `scores.db` has been loaded.
+    pub fn set_scores(&mut self, scores: &HashMap<String, Vec<ScoreReplay>>) {
+        let beatmaps = self
+            .data
+            .as_ref()
+            .map(|listing| listing.beatmaps.as_slice())
+            .unwrap_or_default();
+
+        self.table.set_scores(beatmaps, LocalScores::new(scores));
+    }
+
+    /// Sets the osu! installation folder, which is used to locate beatmap folders.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_osu_directory(&mut self, osu_directory: Option<PathBuf>) {
+        self.panel.osu_directory = osu_directory;
+    }
+
+    /// Sets the path of the `osu.db` file the listing was loaded from, so that the diagnostics can show its raw entries
+    /// and edits to the beatmaps' settings can be saved to it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_source_path(&mut self, source_path: Option<PathBuf>) {
+        self.source_path = source_path.clone();
+        self.diagnostics.set_source_path(source_path);
+    }
+
+    /// Gets the path of the `osu.db` file the listing was loaded from.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn source_path(&self) -> Option<&Path> {
+        self.source_path.as_deref()
+    }
+
+    /// Checks whether any beatmap's settings have been edited without being saved.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_modified(&self) -> bool {
+        self.edits.as_ref().is_some_and(EditSession::is_modified)
+    }
+
+    /// Gets the path that the edits were saved to, if they've been saved since this was last called.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn take_saved(&mut self) -> Option<&Path> {
+        if std::mem::take(&mut self.saved) {
+            self.source_path.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Gets the settings of the selected beatmap, including any unsaved edits, if they can be edited. Only listings
+    /// loaded from a file can be edited, since the edits are saved back to it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn selected_settings(&self) -> Option<BeatmapSettings> {
+        let (beatmap_listing, i) = self.data.as_deref().zip(self.selection.primary)?;
+
+        if self.source_path.is_none()
+            || beatmap_listing.format_confidence() == FormatConfidence::BestEffort
+        {
+            return None;
+        }
+
+        let beatmap_listing = self
+            .edits
+            .as_ref()
+            .map_or(beatmap_listing, EditSession::listing);
+
+        beatmap_listing.beatmaps.get(i).map(BeatmapSettings::of)
+    }
+
+    /// Changes a beatmap's settings, starting an edit session if there isn't one already.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn edit_beatmap(&mut self, index: usize, edit: BulkEdit) {
+        let Some(beatmap_listing) = &self.data else {
+            return;
+        };
+
+        let session = self
+            .edits
+            .get_or_insert_with(|| EditSession::new(BeatmapListing::clone(beatmap_listing)));
+
+        if session.edit_beatmap(index, edit) {
+            self.save_status = None;
+            self.table.set_edited(session.edited_beatmaps());
+        }
+    }
+
+    /// Undoes or redoes the most recent edit to the beatmaps' settings.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn step_edits(&mut self, undo: bool) {
+        let Some(session) = &mut self.edits else {
+            return;
+        };
+
+        let changed = if undo { session.undo() } else { session.redo() };
+
+        if changed {
+            self.save_status = None;
+            self.table.set_edited(session.edited_beatmaps());
+        }
+    }
+
+    /// Saves the edited beatmaps, first asking the user to confirm if osu! appears to be running.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn request_save(&mut self) {
+        if osu_appears_to_be_running() {
+            self.confirm_save = true;
+        } else {
+            self.save();
+        }
+    }
+
+    /// Renders a window asking the user to confirm saving while osu! is running.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn confirm_save_window(&mut self, ctx: &egui::Context) {
+        if !self.confirm_save {
+            return;
+        }
+
+        egui::Window::new("osu! is running")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    "⚠ osu! appears to be running, and will overwrite osu!.db when it exits.",
+                );
+                ui.label(
+                    "Your changes will be lost, and osu! may not see them in the meantime. Close osu! before saving.",
+                );
+
+                ui.horizontal(|ui| {
+                    if ui.button("Save Anyway").clicked() {
+                        self.confirm_save = false;
+                        self.save();
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_save = false;
+                    }
+                });
+            });
+    }
+
+    /// Writes the edited listing back to the `osu!.db` file it was loaded from, which replaces the file once it has
+    /// been written in full. The file is backed up first.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&mut self) {
+        let (Some(session), Some(path)) = (&mut self.edits, &self.source_path) else {
+            return;
+        };
+
+        let changed = session.edited_beatmaps().len();
+
+        if let Err(message) = backups::back_up(path, "edit", changed, self.keep_backups) {
+            self.save_status = Some(message);
+            return;
+        }
+
+        match session.listing().to_file(path) {
+            Ok(()) => {
+                session.mark_saved();
+                self.data = Some(Arc::new(session.listing().clone()));
+                self.saved = true;
+                self.save_status = Some(format!("Saved to {}", path.display()));
+                self.table.set_edited(BTreeSet::new());
+
+                // Filters may depend on the edited settings
+                self.tabs.invalidate();
+            }
+            Err(e) => {
+                log::error!("Unable to save '{}': {}", path.display(), e);
+                self.save_status = Some(format!("Unable to save: {}", e));
+            }
+        }
+    }
+
+    /// Replaces the search query and filter bar, which are applied the next time the view is shown. The tab showing
+    /// every mode is switched to first, since the filter bar may be for a different mode.
+    pub fn set_filter(&mut self, query: String, filter_bar: FilterBar) {
+        self.switch_tab(0);
+        self.search.set_filter(query, filter_bar);
+    }
+
+    /// Switches to another tab, saving the search, sort, selection and scroll position of the current one. If the
+    /// beatmaps matching the other tab are still known, they're shown straight away, otherwise it's filtered again.
+    fn switch_tab(&mut self, tab: usize) {
+        if tab == self.tabs.current() {
+            return;
+        }
+
+        let beatmaps = self
+            .data
+            .as_deref()
+            .map(|listing| listing.beatmaps.as_slice())
+            .unwrap_or_default();
+
+        // The rows are only kept if they match the search, rather than being left over from before it was edited
+        let rows = self.search.is_settled().then(|| self.table.rows().to_vec());
+        let mut next = self.tabs.take(tab);
+        let (query, filter_bar) = self.search.switch_tab(
+            TABS[tab],
+            std::mem::take(&mut next.query),
+            std::mem::take(&mut next.filter_bar),
+            next.rows.is_none(),
+        );
+
+        let current = TabState {
+            query,
+            filter_bar,
+            sort: Some(self.table.sort()),
+            selection: std::mem::replace(&mut self.selection, next.selection),
+            scroll: self.table.scroll_position(),
+            rows,
+        };
+        self.tabs.switch(tab, current);
+
+        if let Some(sort) = next.sort {
+            self.table.set_sort(sort);
+        }
+
+        self.table.set_grade_mode(self.search.single_mode());
+
+        match next.rows {
+            Some(rows) => {
+                self.table.set_rows(beatmaps, rows);
+                self.table.scroll_to_position(next.scroll);
+            }
+            None => self.table.set_rows(beatmaps, Vec::new()),
+        }
+
+        self.refresh_aggregates();
+    }
+
+    /// Gets the current search query and quick filters.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn search_query(&self) -> (String, FilterBar) {
+        let (query, filter_bar) = self.search.query();
+        (query.to_string(), filter_bar.clone())
+    }
+
+    /// Gets the beatmaps that couldn't be parsed when the listing was loaded.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn skipped_entries(&self) -> Option<&SkippedEntries> {
+        self.diagnostics.skipped()
+    }
+
+    /// Replaces the beatmaps that couldn't be parsed shown in the diagnostics, e.g. with those from a bug report.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_skipped_entries(&mut self, skipped: Option<SkippedEntries>) {
+        self.diagnostics.set_skipped(skipped);
+    }
+
+    /// Selects a beatmap and scrolls to it, e.g. to show a beatmap found by the health checks. If the beatmap is hidden
+    /// by the current search, the search is cleared.
+    pub fn show_beatmap(&mut self, index: usize) {
+        if !self.table.is_shown(index) {
+            self.set_filter(String::new(), FilterBar::default());
+        }
+
+        self.selection.select_only(index);
+        self.table.scroll_to(index);
+    }
+
+    /// Filters the beatmaps again if the search depends on the user's notes, e.g. after they've been edited or imported.
+    /// `time` is when the notes were changed.
+    pub fn notes_changed(&mut self, time: f64) {
+        self.search.notes_changed(time);
+        self.tabs.invalidate();
+    }
+
+    /// Selects a random beatmap from the ones shown, and opens its details. `seed` is mixed into the random number.
+    fn pick_random(&mut self, seed: f64) {
+        let Some(beatmap_listing) = self.data.clone() else {
+            return;
+        };
+
+        let Some(index) = self
+            .random
+            .pick(self.table.rows(), &beatmap_listing.beatmaps, seed)
+        else {
+            return;
+        };
+
+        self.show_beatmap(index);
+        self.panel.expanded = true;
+
+        #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+        if self.random.play_audio {
+            self.panel.play_preview(&beatmap_listing.beatmaps[index]);
+        }
+    }
+
+    /// Checks whether the filter presets have been changed since this was last called, i.e. they need to be saved.
+    pub fn take_presets_changed(&mut self) -> bool {
+        std::mem::take(&mut self.presets_changed)
+    }
+
+    /// Shows the dialog for generating a report of the loaded beatmap listing.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_report(&mut self) {
+        self.report.visible = true;
+    }
+
+    /// Queues an action from the menu bar or command palette to be handled the next time the view is shown, the same
+    /// as if it was chosen from the table's context menu or its shortcut was pressed.
+    pub fn queue_action(&mut self, id: ActionId) {
+        self.queued_action = Some(id);
+    }
+
+    /// Gets what's loaded and selected, which decides which of the beatmap actions can be used.
+    pub fn action_context(&self) -> ActionContext {
+        let Some(beatmap_listing) = &self.data else {
+            return ActionContext::default();
+        };
+
+        let menu = RowMenu {
+            #[cfg(not(target_arch = "wasm32"))]
+            osu_directory: self.panel.osu_directory.as_deref(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            editable: true,
+
+            ..Default::default()
+        };
+
+        self.selection
+            .action_context(&beatmap_listing.beatmaps, &menu)
+    }
+
+    /// Gets the loaded beatmap listing, if any.
+    pub fn listing(&self) -> Option<&Arc<BeatmapListing>> {
+        self.data.as_ref()
+    }
+
+    /// Gets the number of beatmaps that match the current search.
+    pub fn shown_count(&self) -> usize {
+        self.table.row_count()
+    }
+
+    /// Takes how often the table's text was found already laid out since this was last called, for the performance
+    /// overlay.
+    pub fn take_text_cache_stats(&mut self) -> CacheStats {
+        self.table.take_text_cache_stats()
     }
 
     /// Renders the beatmap listing view.
-    pub fn view(&mut self, ctx: &egui::Context, scores: &HashMap<String, Vec<ScoreReplay>>) {
+    ///
+    /// If `collections` is given, beatmaps can be added to or removed from collections, and the request to do so is
+    /// returned. `notes` are the user's notes, which can be edited from the table and the details panel. `presets` are
+    /// the saved filter presets, which can be changed from the search box.
+    pub fn view(
+        &mut self,
+        ctx: &egui::Context,
+        scores: &HashMap<String, Vec<ScoreReplay>>,
+        collections: Option<&CollectionMembership>,
+        notes: &mut NoteStore,
+        presets: &mut Vec<FilterPreset>,
+    ) -> Option<CollectionRequest> {
+        let mut request = None;
+        let mut random_action = None;
+        let mut notes_changed = false;
+        let mut import_action = None;
+        let queued_action = self.queued_action.take();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut edit_action = None;
+
         // Unload any closed windows
         self.beatmap_windows.retain(|_, w| w.visible);
         self.score_windows.retain(|_, w| w.visible);
@@ -40,31 +644,371 @@ impl BeatmapListingView {
             score_window.view(ctx);
         }
 
-        // Render the left panel showing scores for the selected beatmap
-        egui::SidePanel::left("b_beatmap_scores").show_animated(
+        self.opener.view(ctx);
+
+        // Keyboard shortcuts for searching and jumping to a beatmap
+        if self.data.is_some() {
+            let (find, jump, random) = ctx.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::COMMAND, egui::Key::F)
+                        || queued_action == Some(ActionId::Find),
+                    i.consume_key(egui::Modifiers::COMMAND, egui::Key::G)
+                        || queued_action == Some(ActionId::JumpToBeatmap),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::F2)
+                        || queued_action == Some(ActionId::RandomBeatmap),
+                )
+            });
+
+            // F2 picks a random beatmap, the same as in osu!
+            if random {
+                random_action = Some(RandomAction::Pick);
+            }
+
+            if find {
+                self.search.focus();
+            }
+
+            if jump {
+                self.jump_to.open();
+            }
+
+            if let Some(index) = self.jump_to.view(ctx, &self.index) {
+                self.show_beatmap(index);
+            }
+        }
+
+        if let Some(beatmap_listing) = &self.data {
+            if self.export.visible {
+                request = self
+                    .export
+                    .view(
+                        ctx,
+                        beatmap_listing,
+                        &self.table,
+                        &self.selection.beatmaps,
+                        collections,
+                    )
+                    .map(CollectionRequest::Add);
+            }
+
+            if self.import_list.visible {
+                import_action = self
+                    .import_list
+                    .view(ctx, beatmap_listing, collections.is_some());
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if self.report.visible {
+                self.report.view(
+                    ctx,
+                    beatmap_listing,
+                    &self.table,
+                    &self.search.description(),
+                );
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if self.bulk_edit.visible {
+                self.bulk_edit
+                    .view(ctx, beatmap_listing, self.panel.osu_directory.as_deref());
+            }
+        }
+
+        match import_action {
+            Some(ImportAction::Select(md5s)) => self.select_md5s(&md5s),
+            Some(ImportAction::AddToCollection(add)) => request = Some(CollectionRequest::Add(add)),
+            None => {}
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.confirm_save_window(ctx);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(dialog) = &mut self.replay_dialog {
+            if !dialog.view(ctx) {
+                self.replay_dialog = None;
+            }
+        }
+
+        // Collect the results of any search that has finished
+        if let Some(beatmap_listing) = &self.data {
+            if let Some(rows) = self.search.poll() {
+                self.table.set_grade_mode(self.search.single_mode());
+                self.table.set_rows(&beatmap_listing.beatmaps, rows);
+
+                // Keep the selection in sync with the rows, and the selected beatmap in view
+                self.selection.retain_shown(self.table.rows());
+
+                if let Some(primary) = self.selection.primary {
+                    self.table.scroll_to(primary);
+                }
+
+                self.refresh_aggregates();
+            }
+        }
+
+        // Render the bottom panel showing the number of beatmaps shown
+        egui::TopBottomPanel::bottom("b_status_bar").show_animated(
             ctx,
-            self.selected_beatmap_md5.is_some(),
+            self.data.is_some(),
             |ui| {
-                ui.heading("Local Scores");
-
-                if let Some(scores) = &self
-                    .selected_beatmap_md5
-                    .as_ref()
-                    .and_then(|md5| scores.get(md5))
-                    .filter(|beatmap_scores| !beatmap_scores.is_empty())
-                {
-                    super::leaderboard(ui, scores, &mut self.score_windows)
-                } else {
-                    ui.label("No local scores found");
+                if let Some(beatmap_listing) = &self.data {
+                    ui.horizontal(|ui| {
+                        let mut status = format!(
+                            "{} of {} maps",
+                            format_count(self.table.row_count()),
+                            format_count(beatmap_listing.beatmaps.len())
+                        );
+
+                        if let Some(sets) = self.table.set_count() {
+                            status.push_str(&format!(" in {} sets", format_count(sets)));
+                        }
+
+                        ui.label(status);
+                        aggregates_summary(ui, &self.aggregates);
+
+                        // Collections can be changed from the table, so they can be saved from here too
+                        if collections.is_some_and(|membership| membership.modified) {
+                            ui.separator();
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                "Unsaved collection changes",
+                            );
+
+                            #[cfg(not(target_arch = "wasm32"))]
+                            if ui
+                                .add_enabled(
+                                    collections.is_some_and(|membership| membership.saveable),
+                                    egui::Button::new("Save Collections"),
+                                )
+                                .clicked()
+                            {
+                                request = Some(CollectionRequest::Save);
+                            }
+                        }
+
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if let Some(session) = &self.edits {
+                            edit_action = edit_controls(ui, session, self.save_status.as_deref());
+                        }
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.menu_button("Columns", |ui| {
+                                self.table.column_menu(ui);
+                            });
+
+                            let mut grouped = self.table.is_grouped();
+
+                            if ui
+                                .toggle_value(&mut grouped, "Group by Set")
+                                .on_hover_text(
+                                    "Show one row for each beatmapset, which can be expanded to show its difficulties",
+                                )
+                                .changed()
+                            {
+                                self.table.set_grouped(&beatmap_listing.beatmaps, grouped);
+
+                                if let Some(primary) = self.selection.primary {
+                                    self.table.scroll_to(primary);
+                                }
+                            }
+
+                            if let Some(action) =
+                                self.random.show(ui, &beatmap_listing.beatmaps, self.table.language())
+                            {
+                                random_action = Some(action);
+                            }
+
+                            if ui
+                                .button("Jump to...")
+                                .on_hover_text("Find a beatmap by its MD5 hash or ID (Ctrl+G)")
+                                .clicked()
+                            {
+                                self.jump_to.open();
+                            }
+
+                            if ui.button("Export...").clicked() {
+                                self.export.visible = true;
+                            }
+
+                            if ui
+                                .button("Import List...")
+                                .on_hover_text(
+                                    "Find the beatmaps in a list of MD5 hashes, beatmap IDs or links, e.g. a mappool",
+                                )
+                                .clicked()
+                            {
+                                self.import_list.visible = true;
+                            }
+
+                            ui.toggle_value(
+                                &mut self.show_diagnostics,
+                                format!("Diagnostics ({})", format_count(self.diagnostics.count())),
+                            )
+                            .on_hover_text(
+                                "Show skipped entries, validation warnings and the values of unknown fields",
+                            );
+                        });
+                    });
                 }
             },
         );
 
+        #[cfg(not(target_arch = "wasm32"))]
+        match edit_action {
+            Some(EditAction::Undo) => self.step_edits(true),
+            Some(EditAction::Redo) => self.step_edits(false),
+            Some(EditAction::Save) => self.request_save(),
+            None => {}
+        }
+
+        match random_action {
+            Some(RandomAction::Pick) => self.pick_random(ctx.input(|i| i.time)),
+            Some(RandomAction::Show(index)) => self.show_beatmap(index),
+            None => {}
+        }
+
+        // Render the bottom panel showing the diagnostics, above the status bar
+        let mut diagnostic_beatmap = None;
+
+        egui::TopBottomPanel::bottom("b_diagnostics")
+            .resizable(true)
+            .default_height(240.0)
+            .show_animated(ctx, self.show_diagnostics && self.data.is_some(), |ui| {
+                if let Some(beatmap_listing) = &self.data {
+                    diagnostic_beatmap =
+                        self.diagnostics
+                            .show(ui, beatmap_listing, self.table.language());
+                }
+            });
+
+        if let Some(index) = diagnostic_beatmap {
+            self.show_beatmap(index);
+        }
+
+        // Render the bottom panel comparing the pinned beatmaps, above the diagnostics
+        let mut pin_action = None;
+
+        egui::TopBottomPanel::bottom("b_pinboard")
+            .resizable(true)
+            .show_animated(
+                ctx,
+                !self.pinboard.pins().is_empty() && self.data.is_some(),
+                |ui| {
+                    if let Some(beatmap_listing) = &self.data {
+                        pin_action = self.pinboard.show(ui, beatmap_listing, &self.index);
+                    }
+                },
+            );
+
+        if let Some(PinAction::ShowBeatmap(index)) = pin_action {
+            self.show_beatmap(index);
+        }
+
+        let selected_beatmap = self
+            .data
+            .as_ref()
+            .zip(self.selection.primary)
+            .and_then(|(beatmap_listing, i)| beatmap_listing.beatmaps.get(i));
+
+        let beatmap_scores = selected_beatmap
+            .and_then(|beatmap| beatmap.md5.as_ref())
+            .and_then(|md5| scores.get(md5))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        // Notes are edited on a copy, which replaces the stored note if it was changed
+        let mut note = selected_beatmap
+            .filter(|beatmap| beatmap.md5.is_some())
+            .map(|beatmap| notes.get(beatmap));
+
+        let similar = match (&self.data, self.selection.primary) {
+            (Some(beatmap_listing), Some(i)) => similar_maps(&mut self.similar, beatmap_listing, i)
+                .iter()
+                .map(|&(j, score)| (j, &beatmap_listing.beatmaps[j], score))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // Settings are also edited on a copy, which is recorded as an edit if it was changed
+        #[cfg(not(target_arch = "wasm32"))]
+        let settings = self.selected_settings();
+        #[cfg(target_arch = "wasm32")]
+        let settings: Option<BeatmapSettings> = None;
+        let mut edited_settings = settings;
+
+        // Render the right panel showing details, local scores and similar beatmaps for the selected beatmap
+        let panel_action = self.panel.view(
+            ctx,
+            selected_beatmap,
+            beatmap_scores,
+            note.as_mut(),
+            edited_settings.as_mut(),
+            &similar,
+        );
+
+        if let Some((beatmap, note)) = selected_beatmap.zip(note) {
+            notes_changed |= notes.set(beatmap, note);
+        }
+
+        match panel_action {
+            Some(PanelAction::Score(ScoreAction::ShowDetails(i))) => {
+                show_score_details(&mut self.score_windows, &beatmap_scores[i], i)
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(PanelAction::Score(ScoreAction::RevealReplay(i))) => {
+                self.replay_dialog = Some(ReplayDialog::new(
+                    self.panel.osu_directory.as_deref(),
+                    &beatmap_scores[i],
+                ))
+            }
+
+            Some(PanelAction::ShowBeatmap(i)) => self.show_beatmap(i),
+
+            _ => {}
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (Some(i), Some(edited)) = (self.selection.primary, edited_settings) {
+            if edited_settings != settings {
+                self.edit_beatmap(i, edited.edit());
+            }
+        }
+
         // Render the central panel showing listing details + beatmaps
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Beatmap Listing");
 
-            if let Some(beatmap_listing) = &self.data {
+            // Cloned so that switching tabs can update the view while the listing is shown
+            if let Some(beatmap_listing) = &self.data.clone() {
+                if beatmap_listing.format_confidence() == FormatConfidence::BestEffort {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "⚠ This osu.db is from a newer version of osu! ({}) than is supported, so it was read on a \
+                             best-effort basis. Some values may be wrong, and it can't be saved.",
+                            beatmap_listing.version
+                        ),
+                    );
+                }
+
+                if self.skipped > 0 {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            format!(
+                                "⚠ {} beatmaps could not be read and were skipped",
+                                format_count(self.skipped)
+                            ),
+                        );
+
+                        if ui.link("Show Diagnostics").clicked() {
+                            self.show_diagnostics = true;
+                        }
+                    });
+                }
+
                 // Base Details
                 egui::Grid::new("base_details").show(ui, |ui| {
                     ui.label("Version");
@@ -84,7 +1028,7 @@ impl BeatmapListingView {
                     ui.end_row();
 
                     ui.label("Player Name");
-                    ui.label(beatmap_listing.player_name.clone().unwrap_or_default());
+                    ui.label(beatmap_listing.player_name_str().unwrap_or("(no player name)"));
                     ui.end_row();
 
                     ui.label("User Permissions");
@@ -92,69 +1036,225 @@ impl BeatmapListingView {
                     ui.end_row();
                 });
 
+                ui.separator();
+
                 // Beatmaps
-                ui.collapsing("Beatmaps", |ui| {
-                    let row_height = ui.text_style_height(&egui::TextStyle::Body);
-
-                    egui::ScrollArea::both()
-                        .auto_shrink([false, true])
-                        .scroll_bar_visibility(
-                            egui::scroll_area::ScrollBarVisibility::AlwaysVisible,
-                        )
-                        .show_rows(
-                            ui,
-                            row_height,
-                            beatmap_listing.beatmaps.len(),
-                            |ui, row_range| {
-                                for i in row_range {
-                                    let beatmap = &beatmap_listing.beatmaps[i];
-                                    let md5 = beatmap.md5.clone().unwrap_or_default();
-
-                                    // Beatmaps without an MD5 are invalid - most likely a corrupt DB
-                                    if !md5.is_empty() {
-                                        let header = format!(
-                                            "{} - {} [{}]",
-                                            &beatmap.artist_name.clone().unwrap_or_default(),
-                                            &beatmap.song_title.clone().unwrap_or_default(),
-                                            &beatmap.difficulty.clone().unwrap_or_default()
-                                        );
-
-                                        ui.selectable_value(
-                                            &mut self.selected_beatmap_md5,
-                                            Some(beatmap.md5.clone().unwrap_or_default()),
-                                            &header,
-                                        )
-                                        .context_menu(
-                                            |ui| {
-                                                if ui.button("Details").clicked() {
-                                                    self.beatmap_windows.insert(
-                                                        md5,
-                                                        BeatmapDetailsWindow {
-                                                            id: Id::new("b_beatmap_details")
-                                                                .with(i),
-                                                            title: header,
-                                                            visible: true,
-                                                            data: beatmap.clone(),
-                                                        },
-                                                    );
-
-                                                    ui.close_menu();
-                                                }
-
-                                                if ui.button("View Beatmap Online").clicked() {
-                                                    open_beatmap_in_browser(&beatmap);
-                                                    ui.close_menu();
-                                                }
-                                            },
-                                        );
-                                    }
-                                }
-                            },
+                if let Some(tab) = self.tabs.show(ui) {
+                    self.switch_tab(tab);
+                }
+
+                self.presets_changed |=
+                    self.search
+                        .show(ui, beatmap_listing, notes.notes(), presets);
+
+                let menu = RowMenu {
+                    collections,
+                    removable: false,
+                    notes: Some(notes.notes()),
+                    pins: Some(self.pinboard.pins()),
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    osu_directory: self.panel.osu_directory.as_deref(),
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    editable: true,
+                };
+
+                // Actions queued from the menu bar or command palette are handled like those from the context menu
+                if let Some(text) = queued_action.and_then(|id| {
+                    self.table
+                        .copy_text(id, &beatmap_listing.beatmaps, &self.selection)
+                }) {
+                    ui.ctx().copy_text(text);
+                }
+
+                let row_action = self
+                    .table
+                    .show(ui, &beatmap_listing.beatmaps, &mut self.selection, menu)
+                    .or(queued_action.and_then(RowAction::from_action));
+
+                match row_action {
+                    Some(RowAction::ShowDetails(i)) => {
+                        let beatmap = &beatmap_listing.beatmaps[i];
+
+                        self.beatmap_windows.insert(
+                            beatmap.md5.clone().unwrap_or_default(),
+                            BeatmapDetailsWindow::new(
+                                Id::new("b_beatmap_details").with(i),
+                                beatmap,
+                                self.table.language(),
+                            ),
                         );
-                });
+                    }
+                    Some(RowAction::Activate(_)) => self.panel.expanded = true,
+                    Some(RowAction::OpenPages) => self
+                        .opener
+                        .open_pages(self.selection.selected(&beatmap_listing.beatmaps)),
+                    Some(RowAction::OpenInOsuDirect) => self
+                        .opener
+                        .open_in_osu_direct(self.selection.selected(&beatmap_listing.beatmaps)),
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Some(RowAction::OpenFolders) => {
+                        if let Some(osu_directory) = &self.panel.osu_directory {
+                            self.opener.open_folders(
+                                osu_directory,
+                                self.selection.selected(&beatmap_listing.beatmaps),
+                            );
+                        }
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Some(RowAction::EditSettings) => {
+                        self.bulk_edit.open(self.selection.beatmaps.iter().copied())
+                    }
+                    Some(RowAction::AddToCollection(collection)) => {
+                        request = Some(CollectionRequest::Add(AddToCollection {
+                            collection,
+                            name: None,
+                            beatmap_md5s: self.selection.md5s(&beatmap_listing.beatmaps),
+                        }));
+                    }
+                    Some(RowAction::ToggleCollection { index, collection }) => {
+                        request = collections.and_then(|membership| {
+                            membership.toggle(&beatmap_listing.beatmaps[index], collection)
+                        });
+                    }
+                    Some(RowAction::ToggleStar(index)) => {
+                        notes_changed |= notes.toggle_star(&beatmap_listing.beatmaps[index]);
+                    }
+                    Some(RowAction::TogglePin(index)) => {
+                        self.pinboard.toggle(&beatmap_listing.beatmaps[index]);
+                    }
+                    Some(RowAction::RemoveFromCollection) | None => {}
+                }
             } else {
                 ui.label("No beatmap listing loaded...");
             }
         });
+
+        if notes_changed {
+            self.notes_changed(ctx.input(|i| i.time));
+        }
+
+        request
+    }
+}
+
+/// Renders the status of the edits to the beatmaps' settings in the status bar, with buttons for undoing them or saving
+/// them to `osu!.db`. `status` is the result of the most recent save.
+#[cfg(not(target_arch = "wasm32"))]
+fn edit_controls(
+    ui: &mut egui::Ui,
+    session: &EditSession,
+    status: Option<&str>,
+) -> Option<EditAction> {
+    let mut action = None;
+    let modified = session.is_modified();
+
+    if modified || status.is_some() {
+        ui.separator();
+    }
+
+    if modified {
+        ui.colored_label(ui.visuals().warn_fg_color, "Unsaved beatmap changes");
+    }
+
+    if let Some(status) = status {
+        ui.label(status);
+    }
+
+    if !modified && !session.can_undo() && !session.can_redo() {
+        return None;
+    }
+
+    if ui
+        .add_enabled(session.can_undo(), egui::Button::new("⟲"))
+        .on_hover_text("Undo the last change to a beatmap's settings")
+        .clicked()
+    {
+        action = Some(EditAction::Undo);
+    }
+
+    if ui
+        .add_enabled(session.can_redo(), egui::Button::new("⟳"))
+        .on_hover_text("Redo the last undone change")
+        .clicked()
+    {
+        action = Some(EditAction::Redo);
+    }
+
+    if ui
+        .add_enabled(modified, egui::Button::new("Save osu!.db"))
+        .on_hover_text("Write the changes to osu!.db, replacing it")
+        .clicked()
+    {
+        action = Some(EditAction::Save);
+    }
+
+    action
+}
+
+/// Renders a summary of the beatmaps matching the search in the status bar, with the number of each ranked status
+/// on hover.
+fn aggregates_summary(ui: &mut egui::Ui, aggregates: &FilterAggregates) {
+    if aggregates.count == 0 {
+        return;
+    }
+
+    let mut summary = Vec::new();
+
+    if let Some(stars) = aggregates.mean_stars() {
+        summary.push(format!("{:.2}★ average", stars));
+    }
+
+    summary.push(format!(
+        "{} drain",
+        format::duration_hmmss(time::Duration::seconds(aggregates.drain_time as i64))
+    ));
+
+    if let Some((min, max)) = aggregates.bpm_range {
+        summary.push(format!("{:.0}-{:.0} BPM", min, max));
     }
+
+    let statuses = aggregates
+        .statuses
+        .iter()
+        .map(|(status, count)| format!("{}: {}", status, format_count(*count)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    ui.separator();
+    ui.label(summary.join(" · ")).on_hover_text(format!(
+        "For the beatmaps matching the search\n\n{}",
+        statuses
+    ));
+}
+
+/// Gets the beatmaps similar to the beatmap at `index`, finding them again only if a different beatmap was selected
+/// since `cache` was last updated.
+fn similar_maps<'a>(
+    cache: &'a mut Option<(usize, Vec<(usize, f32)>)>,
+    beatmap_listing: &BeatmapListing,
+    index: usize,
+) -> &'a [(usize, f32)] {
+    if cache.as_ref().map(|(cached, _)| *cached) != Some(index) {
+        let similar = beatmap_listing
+            .beatmaps
+            .get(index)
+            .map(|beatmap| {
+                analysis::similar_map_indices(
+                    beatmap_listing,
+                    beatmap,
+                    SIMILAR_MAPS,
+                    &Default::default(),
+                )
+            })
+            .unwrap_or_default();
+
+        *cache = Some((index, similar));
+    }
+
+    cache
+        .as_ref()
+        .map(|(_, similar)| similar.as_slice())
+        .unwrap_or_default()
 }
@@ -0,0 +1,847 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+use std::time::Duration;
+
+use egui::RichText;
+use osu_db_parser::{
+    columns::ColumnValue,
+    difficulty::{self, DifficultySettings},
+    flagset::FlagSet,
+    links,
+    prelude::*,
+};
+
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+use super::audio::AudioPreview;
+#[cfg(not(target_arch = "wasm32"))]
+use super::thumbnails::{Thumbnail, ThumbnailCache};
+use super::{
+    format_count, grade_text, open_beatmap_in_browser, open_url,
+    score_listing::{score_menu, ScoreAction},
+    settings::ValueFormat,
+    timing_points::timing_points,
+};
+
+/// Represents an action requested from the panel.
+pub enum PanelAction {
+    /// An action for one of the beatmap's local scores
+    Score(ScoreAction),
+
+    /// Select the beatmap at this index, e.g. one of the similar beatmaps
+    ShowBeatmap(usize),
+}
+
+const GAMEPLAY_MODES: [GameplayMode; 4] = [
+    GameplayMode::Standard,
+    GameplayMode::Taiko,
+    GameplayMode::Catch,
+    GameplayMode::Mania,
+];
+
+/// Modifiers that can be previewed, in the order they're shown.
+const PREVIEW_MODS: [Mods; 6] = [
+    Mods::HardRock,
+    Mods::Easy,
+    Mods::DoubleTime,
+    Mods::HalfTime,
+    Mods::Hidden,
+    Mods::Flashlight,
+];
+
+/// Per-beatmap settings which can be changed from the panel. These are stored in `osu!.db`, and are normally only
+/// changed from song select in osu!.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BeatmapSettings {
+    /// Local offset, in milliseconds
+    pub local_offset: i16,
+
+    pub ignore_beatmap_hitsounds: bool,
+    pub ignore_beatmap_skin: bool,
+    pub disable_storyboard: bool,
+    pub disable_video: bool,
+    pub mania_scroll_speed: u8,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl BeatmapSettings {
+    /// Gets the current settings of a beatmap.
+    pub fn of(beatmap: &BeatmapEntry) -> Self {
+        Self {
+            local_offset: beatmap.local_offset as i16,
+            ignore_beatmap_hitsounds: beatmap.ignore_beatmap_hitsounds,
+            ignore_beatmap_skin: beatmap.ignore_beatmap_skin,
+            disable_storyboard: beatmap.disable_storyboard,
+            disable_video: beatmap.disable_video,
+            mania_scroll_speed: beatmap.mania_scroll_speed,
+        }
+    }
+
+    /// Gets the edit which changes a beatmap's settings to these.
+    pub fn edit(&self) -> BulkEdit {
+        BulkEdit {
+            local_offset: Some(OffsetEdit::Set(self.local_offset)),
+            ignore_beatmap_hitsounds: Some(self.ignore_beatmap_hitsounds),
+            ignore_beatmap_skin: Some(self.ignore_beatmap_skin),
+            disable_storyboard: Some(self.disable_storyboard),
+            disable_video: Some(self.disable_video),
+            visual_override: None,
+            mania_scroll_speed: Some(self.mania_scroll_speed),
+        }
+    }
+}
+
+/// A collapsible side panel showing the full details of the selected beatmap.
+pub struct BeatmapPanel {
+    pub expanded: bool,
+
+    /// osu! installation folder, used to locate the beatmap's folder
+    #[cfg(not(target_arch = "wasm32"))]
+    pub osu_directory: Option<PathBuf>,
+
+    /// How dates and star ratings are shown
+    pub format: ValueFormat,
+
+    /// Which version of the artist and title the similar beatmaps are named with
+    pub language: MetadataLanguage,
+
+    /// Modifiers to preview the difficulty settings and star rating with, which are kept when selecting a different
+    /// beatmap
+    mods: FlagSet<Mods>,
+
+    /// Background image thumbnails for recently selected beatmaps
+    #[cfg(not(target_arch = "wasm32"))]
+    thumbnails: ThumbnailCache,
+
+    /// Preview of the selected beatmap's audio
+    #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+    audio: AudioPreview,
+}
+
+impl Default for BeatmapPanel {
+    fn default() -> Self {
+        Self {
+            expanded: true,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            osu_directory: None,
+
+            format: ValueFormat::default(),
+            language: MetadataLanguage::default(),
+            mods: Mods::none(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            thumbnails: ThumbnailCache::default(),
+
+            #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+            audio: AudioPreview::default(),
+        }
+    }
+}
+
+impl BeatmapPanel {
+    /// Renders the panel for a beatmap and its local scores, returning any action requested from it. Nothing is shown
+    /// if no beatmap is selected.
+    ///
+    /// `note` is the user's note for the beatmap, and `settings` are its per-beatmap settings, which can be edited from
+    /// the panel if given. `similar` are the indices, beatmaps and scores of the beatmaps that are most similar to it.
+    pub fn view(
+        &mut self,
+        ctx: &egui::Context,
+        beatmap: Option<&BeatmapEntry>,
+        scores: &[ScoreReplay],
+        note: Option<&mut Note>,
+        settings: Option<&mut BeatmapSettings>,
+        similar: &[(usize, &BeatmapEntry, f32)],
+    ) -> Option<PanelAction> {
+        // Stop previewing the audio once a different beatmap is selected
+        #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+        self.audio
+            .sync_selection(beatmap.and_then(|beatmap| beatmap.md5.as_deref()));
+
+        let beatmap = beatmap?;
+        let mut action = None;
+
+        egui::SidePanel::right("b_beatmap_panel_collapsed")
+            .resizable(false)
+            .show_animated(ctx, !self.expanded, |ui| {
+                if ui
+                    .button("⏴")
+                    .on_hover_text("Show beatmap details")
+                    .clicked()
+                {
+                    self.expanded = true;
+                }
+            });
+
+        egui::SidePanel::right("b_beatmap_panel")
+            .default_width(320.0)
+            .show_animated(ctx, self.expanded, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("⏵")
+                        .on_hover_text("Hide beatmap details")
+                        .clicked()
+                    {
+                        self.expanded = false;
+                    }
+
+                    ui.heading("Beatmap Details");
+                });
+
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        self.details(ui, beatmap, note, settings);
+                        action = local_scores(ui, scores, self.format).map(PanelAction::Score);
+
+                        if let Some(index) = similar_maps(ui, similar, self.language) {
+                            action = Some(PanelAction::ShowBeatmap(index));
+                        }
+                    });
+            });
+
+        action
+    }
+
+    /// Renders buttons for opening the beatmap, which are disabled with an explanation when the data they need is
+    /// missing.
+    fn actions(&self, ui: &mut egui::Ui, beatmap: &BeatmapEntry) {
+        ui.horizontal_wrapped(|ui| {
+            let submitted = links::beatmap_url(beatmap).is_some();
+
+            if ui
+                .add_enabled(submitted, egui::Button::new("Beatmap Page"))
+                .on_disabled_hover_text("Unsubmitted beatmaps don't have a page on the osu! website")
+                .clicked()
+            {
+                open_beatmap_in_browser(beatmap);
+            }
+
+            if ui
+                .add_enabled(submitted, egui::Button::new("osu!direct"))
+                .on_disabled_hover_text("Unsubmitted beatmaps can't be opened in osu!direct")
+                .clicked()
+            {
+                if let Some(url) = links::osu_direct_url(beatmap) {
+                    open_url(&url);
+                }
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let folder = self
+                    .osu_directory
+                    .as_ref()
+                    .and_then(|osu_directory| {
+                        osu_db_parser::paths::beatmap_folder(osu_directory, beatmap)
+                    })
+                    .filter(|path| path.is_dir());
+
+                let reason = if self.osu_directory.is_none() {
+                    "The osu! folder is unknown. Open osu.db from the osu! folder to locate beatmap folders."
+                } else {
+                    "The beatmap's folder could not be found"
+                };
+
+                if ui
+                    .add_enabled(folder.is_some(), egui::Button::new("Open Folder"))
+                    .on_disabled_hover_text(reason)
+                    .clicked()
+                {
+                    if let Some(path) = folder {
+                        if let Err(e) = open::that_detached(&path) {
+                            log::error!("Unable to open folder '{}': {}", path.display(), e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Renders a thumbnail of the beatmap's background image, or a placeholder explaining why it isn't shown.
+    ///
+    /// Nothing is shown if the osu! folder is unknown.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn thumbnail(&mut self, ui: &mut egui::Ui, beatmap: &BeatmapEntry) {
+        let Some(osu_directory) = &self.osu_directory else {
+            return;
+        };
+
+        let width = ui.available_width();
+        let placeholder_size = egui::vec2(width, width * 9.0 / 16.0);
+
+        let placeholder = |ui: &mut egui::Ui, add_contents: &dyn Fn(&mut egui::Ui)| {
+            let (rect, _) = ui.allocate_exact_size(placeholder_size, egui::Sense::hover());
+            ui.painter()
+                .rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+            ui.allocate_new_ui(egui::UiBuilder::new().max_rect(rect), |ui| {
+                ui.centered_and_justified(add_contents);
+            });
+        };
+
+        match osu_db_parser::paths::beatmap_file(osu_directory, beatmap) {
+            Some(osu_file) => match self.thumbnails.get(ui.ctx(), &osu_file) {
+                Thumbnail::Loading => placeholder(ui, &|ui| {
+                    ui.spinner();
+                }),
+                Thumbnail::Loaded(texture) => {
+                    ui.add(
+                        egui::Image::from_texture(texture)
+                            .max_width(width)
+                            .rounding(4.0),
+                    );
+                }
+                Thumbnail::Unavailable(reason) => placeholder(ui, &|ui| {
+                    ui.weak(reason.as_str());
+                }),
+            },
+            None => placeholder(ui, &|ui| {
+                ui.weak("The beatmap's .osu file is unknown");
+            }),
+        }
+    }
+
+    /// Starts previewing a beatmap's audio, e.g. when it was picked at random. Nothing happens if the audio file can't
+    /// be found.
+    #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+    pub fn play_preview(&mut self, beatmap: &BeatmapEntry) {
+        let (path, start, length) = self.audio_source(beatmap);
+
+        if let Ok(path) = path {
+            self.audio.play(
+                beatmap.md5.as_deref().unwrap_or_default(),
+                &path,
+                start,
+                length,
+            );
+        }
+    }
+
+    /// Renders controls for previewing the beatmap's audio file, which are disabled with an explanation if it can't be
+    /// found.
+    #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+    fn audio_controls(&mut self, ui: &mut egui::Ui, beatmap: &BeatmapEntry) {
+        let (path, start, length) = self.audio_source(beatmap);
+
+        self.audio.controls(
+            ui,
+            beatmap.md5.as_deref().unwrap_or_default(),
+            path.as_deref().map_err(|reason| *reason),
+            start,
+            length,
+        );
+    }
+
+    /// Locates a beatmap's audio file, or gets the reason it couldn't be found, along with where the preview starts and
+    /// the length of the song.
+    #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+    fn audio_source(
+        &self,
+        beatmap: &BeatmapEntry,
+    ) -> (Result<PathBuf, &'static str>, Duration, Duration) {
+        let path = match &self.osu_directory {
+            Some(osu_directory) => osu_db_parser::paths::audio_file(osu_directory, beatmap)
+                .filter(|path| path.is_file())
+                .ok_or("The beatmap's audio file could not be found"),
+            None => Err("The osu! folder is unknown. Open osu.db from the osu! folder to locate audio files."),
+        };
+
+        // A preview time of -1 means osu! starts the preview 40% of the way through the song
+        let length = Duration::from_millis(beatmap.total_time as u64);
+        let start = if beatmap.audio_preview_time == u32::MAX {
+            length.mul_f64(0.4)
+        } else {
+            Duration::from_millis(beatmap.audio_preview_time as u64)
+        };
+
+        (path, start, length)
+    }
+
+    /// Renders the details of a beatmap, skipping any fields which aren't set.
+    fn details(
+        &mut self,
+        ui: &mut egui::Ui,
+        beatmap: &BeatmapEntry,
+        note: Option<&mut Note>,
+        settings: Option<&mut BeatmapSettings>,
+    ) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.thumbnail(ui, beatmap);
+
+        // Titles, with the romanised version shown underneath if it's different
+        let title = beatmap.display_title(true);
+        let artist = beatmap.display_artist(true);
+        let romanised = (beatmap.display_title(false), beatmap.display_artist(false));
+
+        ui.label(
+            RichText::new(
+                format!(
+                    "{} - {}",
+                    artist.unwrap_or_default(),
+                    title.unwrap_or_default()
+                )
+                .sanitized(),
+            )
+            .strong(),
+        );
+
+        if (title, artist) != romanised {
+            ui.weak(
+                format!(
+                    "{} - {}",
+                    romanised.1.unwrap_or_default(),
+                    romanised.0.unwrap_or_default()
+                )
+                .sanitized(),
+            );
+        }
+
+        if let Some(difficulty) = &beatmap.difficulty {
+            ui.label(format!(
+                "[{}] ({}, {})",
+                difficulty.sanitized(),
+                beatmap.gameplay_mode,
+                beatmap.ranked_status
+            ));
+        }
+
+        self.actions(ui, beatmap);
+
+        #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+        self.audio_controls(ui, beatmap);
+
+        if let Some(note) = note {
+            ui.separator();
+            note_editor(ui, note);
+        }
+
+        if let Some(settings) = settings {
+            ui.separator();
+            settings_editor(ui, settings);
+        }
+
+        ui.separator();
+
+        egui::Grid::new("b_beatmap_panel_grid")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                value_row(ui, "Creator", Column::Creator.value(beatmap));
+                wrapped_row(ui, "Source", Column::Source.value(beatmap));
+                wrapped_row(ui, "Tags", Column::Tags.value(beatmap));
+
+                copyable_row(ui, "MD5", Column::Md5.value(beatmap));
+
+                if beatmap.beatmapset_id().is_some() {
+                    copyable_row(ui, "Beatmapset ID", Column::BeatmapsetId.value(beatmap));
+                }
+
+                if beatmap.difficulty_id().is_some() {
+                    copyable_row(ui, "Difficulty ID", Column::DifficultyId.value(beatmap));
+                }
+
+                for mode in GAMEPLAY_MODES {
+                    let grade = beatmap.grade(mode);
+
+                    if grade != Grade::Unplayed {
+                        ui.label(format!("Grade ({})", mode));
+                        ui.label(grade.to_string());
+                        ui.end_row();
+                    }
+                }
+
+                if beatmap.drain_time > 0 {
+                    value_row(ui, "Drain Time", Column::Drain.value(beatmap));
+                }
+
+                if beatmap.total_time > 0 {
+                    value_row(ui, "Total Time", Column::Length.value(beatmap));
+                }
+
+                if let Some(main_bpm) = beatmap.main_bpm() {
+                    ui.label("BPM");
+
+                    match beatmap.bpm_range() {
+                        Some((min, max)) if (max - min).abs() > 0.001 => {
+                            ui.label(format!("{:.0} ({:.0}-{:.0})", main_bpm, min, max))
+                        }
+                        _ => ui.label(format!("{:.0}", main_bpm)),
+                    };
+
+                    ui.end_row();
+                }
+
+                if !beatmap.timing_points.is_empty() {
+                    ui.label("Timing Points");
+                    ui.label(beatmap.timing_points.len().to_string());
+                    ui.end_row();
+                }
+
+                date_row(ui, "Last Played", Column::LastPlayed, beatmap, self.format);
+                date_row(
+                    ui,
+                    "Last Modified",
+                    Column::LastModified,
+                    beatmap,
+                    self.format,
+                );
+            });
+
+        ui.separator();
+        self.mod_preview(ui, beatmap);
+
+        // Star ratings for each mod combination
+        for mode in GAMEPLAY_MODES {
+            let Some(ratings) = beatmap.star_ratings(mode).filter(|r| !r.is_empty()) else {
+                continue;
+            };
+
+            egui::CollapsingHeader::new(format!("Star Ratings ({})", mode))
+                .id_salt(("b_beatmap_panel_stars", mode as u8))
+                .default_open(mode == beatmap.gameplay_mode)
+                .show(ui, |ui| {
+                    egui::Grid::new(("b_beatmap_panel_stars_grid", mode as u8))
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for star_rating in ratings {
+                                ui.label(Mods::acronyms(star_rating.mods));
+                                ui.label(format!("{}★", self.format.stars(star_rating.rating)));
+                                ui.end_row();
+                            }
+                        });
+                });
+        }
+
+        egui::CollapsingHeader::new(format!("Timing Points ({})", beatmap.timing_points.len()))
+            .id_salt("b_beatmap_panel_timing")
+            .show(ui, |ui| {
+                timing_points(ui, egui::Id::new("b_beatmap_panel_timing"), beatmap);
+            });
+
+        // Beatmap folder
+        if let Some(folder_name) = beatmap.folder_name.as_deref().filter(|f| !f.is_empty()) {
+            ui.separator();
+            ui.label("Folder");
+            ui.add(egui::Label::new(RichText::new(folder_name).monospace()).wrap());
+        }
+    }
+
+    /// Renders toggles for previewing modifiers, along with the difficulty settings, star rating, BPM and length
+    /// adjusted for the chosen modifiers.
+    fn mod_preview(&mut self, ui: &mut egui::Ui, beatmap: &BeatmapEntry) {
+        ui.horizontal_wrapped(|ui| {
+            for m in PREVIEW_MODS {
+                // Conflicting modifiers can't be chosen together, e.g. Hard Rock and Easy
+                let conflict = PREVIEW_MODS
+                    .into_iter()
+                    .find(|&other| self.mods.contains(other) && difficulty::conflicts(m, other));
+
+                let mut enabled = self.mods.contains(m);
+                let response = ui.add_enabled_ui(conflict.is_none(), |ui| {
+                    ui.toggle_value(&mut enabled, m.acronym())
+                });
+
+                if let Some(other) = conflict {
+                    response
+                        .response
+                        .on_disabled_hover_text(format!("Can't be used with {}", other.acronym()));
+                }
+
+                if response.inner.changed() {
+                    if enabled {
+                        self.mods |= m;
+                    } else {
+                        self.mods -= m;
+                    }
+                }
+            }
+        });
+
+        // Difficulty settings, shown on the same 0-10 scale as in-game
+        let original = DifficultySettings::of(beatmap);
+        let adjusted = original.with_mods(self.mods);
+
+        let settings = [
+            ("AR", original.approach_rate, adjusted.approach_rate),
+            ("CS", original.circle_size, adjusted.circle_size),
+            (
+                "OD",
+                original.overall_difficulty,
+                adjusted.overall_difficulty,
+            ),
+            ("HP", original.hp_drain, adjusted.hp_drain),
+        ];
+
+        let rate = difficulty::clock_rate(self.mods);
+
+        egui::Grid::new("b_beatmap_panel_difficulty")
+            .num_columns(2)
+            .show(ui, |ui| {
+                if settings.iter().any(|(_, value, _)| *value > 0.0) {
+                    for (label, original, adjusted) in settings {
+                        let text = if (adjusted - original).abs() > 0.01 {
+                            format!("{:.1} ({:.1})", adjusted, original)
+                        } else {
+                            format!("{:.1}", adjusted)
+                        };
+
+                        ui.label(label);
+                        ui.add(
+                            egui::ProgressBar::new((adjusted / 10.0).clamp(0.0, 1.0)).text(text),
+                        );
+                        ui.end_row();
+                    }
+                }
+
+                // Star ratings are only stored for the modifiers that affect them
+                let star_mods = difficulty::star_rating_mods(self.mods);
+
+                ui.label("Stars");
+                match beatmap.star_rating(beatmap.gameplay_mode, star_mods) {
+                    Some(rating) => ui.label(format!("{}★", self.format.stars(rating))),
+                    None => ui.weak("Not calculated").on_hover_text(format!(
+                        "osu! hasn't stored a star rating for {} on this beatmap",
+                        Mods::acronyms(star_mods)
+                    )),
+                };
+                ui.end_row();
+
+                // Rate-changing modifiers speed up or slow down the whole song
+                if rate != 1.0 {
+                    if let Some(main_bpm) = beatmap.main_bpm() {
+                        ui.label("BPM");
+                        ui.label(format!("{:.0} ({:.0})", main_bpm * rate, main_bpm));
+                        ui.end_row();
+                    }
+
+                    if beatmap.total_time > 0 {
+                        let length = (beatmap.total_time as f64 / rate / 1000.0) as u32;
+
+                        ui.label("Length");
+                        ui.label(format!(
+                            "{} ({})",
+                            ColumnValue::Duration(length),
+                            Column::Length.value(beatmap)
+                        ));
+                        ui.end_row();
+                    }
+                }
+            });
+    }
+}
+
+/// Renders a toggle for starring the beatmap and a text box for the user's note about it. Notes are kept in a file of
+/// their own, rather than in osu!'s files.
+fn note_editor(ui: &mut egui::Ui, note: &mut Note) {
+    ui.horizontal(|ui| {
+        ui.strong("Notes");
+
+        let star = if note.starred {
+            "★ Starred"
+        } else {
+            "☆ Star"
+        };
+        ui.toggle_value(&mut note.starred, star)
+            .on_hover_text("Starred beatmaps can be found with starred=yes");
+    });
+
+    ui.add(
+        egui::TextEdit::multiline(&mut note.text)
+            .hint_text("Add a note (searchable with note=...)")
+            .desired_rows(2)
+            .desired_width(f32::INFINITY),
+    );
+}
+
+/// Renders controls for changing a beatmap's settings, which are the same as the ones in osu!'s song select.
+fn settings_editor(ui: &mut egui::Ui, settings: &mut BeatmapSettings) {
+    egui::CollapsingHeader::new("Settings")
+        .id_salt("b_beatmap_panel_settings")
+        .show(ui, |ui| {
+            egui::Grid::new("b_beatmap_panel_settings_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Local Offset");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.local_offset)
+                            .speed(1)
+                            .suffix(" ms"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Scroll Speed")
+                        .on_hover_text("osu!mania scroll speed for this beatmap");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.mania_scroll_speed)
+                            .range(1..=40)
+                            .clamp_existing_to_range(false),
+                    );
+                    ui.end_row();
+                });
+
+            ui.checkbox(
+                &mut settings.ignore_beatmap_hitsounds,
+                "Ignore beatmap hitsounds",
+            );
+            ui.checkbox(&mut settings.ignore_beatmap_skin, "Ignore beatmap skin");
+            ui.checkbox(&mut settings.disable_storyboard, "Disable storyboard");
+            ui.checkbox(&mut settings.disable_video, "Disable video");
+        });
+}
+
+/// Renders a beatmap's local scores, returning any action requested for one of them.
+fn local_scores(
+    ui: &mut egui::Ui,
+    scores: &[ScoreReplay],
+    format: ValueFormat,
+) -> Option<ScoreAction> {
+    let mut action = None;
+
+    ui.separator();
+
+    egui::CollapsingHeader::new(format!("Local Scores ({})", scores.len()))
+        .id_salt("b_beatmap_panel_scores")
+        .default_open(true)
+        .show(ui, |ui| {
+            if scores.is_empty() {
+                ui.weak("No local scores found");
+                return;
+            }
+
+            egui::Grid::new("b_beatmap_panel_scores_grid")
+                .num_columns(5)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (i, score) in scores.iter().enumerate() {
+                        ui.label(grade_text(score.grade()));
+
+                        let response = ui
+                            .selectable_label(false, format_count(score.score as usize))
+                            .on_hover_text(format!(
+                                "{}\nDouble-click to show the replay file",
+                                score.player_name.as_deref().unwrap_or_default()
+                            ));
+
+                        if response.double_clicked() {
+                            action = Some(ScoreAction::RevealReplay(i));
+                        }
+
+                        response.context_menu(|ui| {
+                            if let Some(menu_action) = score_menu(ui, i) {
+                                action = Some(menu_action);
+                            }
+                        });
+
+                        ui.label(Mods::acronyms(score.mods));
+                        ui.label(format!("{:.2}%", score.accuracy()));
+                        ui.label(format.datetime(score.timestamp, ScoreReplay::TIMESTAMP_KIND));
+                        ui.end_row();
+                    }
+                });
+        });
+
+    action
+}
+
+/// Renders the beatmaps most similar to the selected one, returning the index of the beatmap to select if one was
+/// clicked.
+fn similar_maps(
+    ui: &mut egui::Ui,
+    similar: &[(usize, &BeatmapEntry, f32)],
+    language: MetadataLanguage,
+) -> Option<usize> {
+    let mut clicked = None;
+
+    ui.separator();
+
+    egui::CollapsingHeader::new("Similar Beatmaps")
+        .id_salt("b_beatmap_panel_similar")
+        .default_open(true)
+        .show(ui, |ui| {
+            if similar.is_empty() {
+                ui.weak("No similar beatmaps found");
+                return;
+            }
+
+            ui.weak("Based on the mapper, tags, star rating, BPM and length");
+
+            egui::Grid::new("b_beatmap_panel_similar_grid")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for &(index, beatmap, score) in similar {
+                        ui.label(format!("{:.0}%", score * 100.0));
+
+                        if ui
+                            .link(beatmap.display_name(language))
+                            .on_hover_text("Select this beatmap")
+                            .clicked()
+                        {
+                            clicked = Some(index);
+                        }
+
+                        ui.end_row();
+                    }
+                });
+        });
+
+    clicked
+}
+
+/// Renders a row with a label and value, unless the value is missing.
+fn value_row(ui: &mut egui::Ui, label: &str, value: ColumnValue) {
+    if value != ColumnValue::Empty {
+        ui.label(label);
+        ui.label(value.to_string());
+        ui.end_row();
+    }
+}
+
+/// Renders a row with a beatmap's date in the chosen format and time zone, unless the date is missing.
+fn date_row(
+    ui: &mut egui::Ui,
+    label: &str,
+    column: Column,
+    beatmap: &BeatmapEntry,
+    format: ValueFormat,
+) {
+    if let (ColumnValue::DateTime(datetime), Some(kind)) =
+        (column.value(beatmap), column.timestamp_kind())
+    {
+        ui.label(label);
+        ui.label(format.datetime(datetime, kind));
+        ui.end_row();
+    }
+}
+
+/// Renders a row with a long value that wraps onto multiple lines, unless the value is missing or empty.
+fn wrapped_row(ui: &mut egui::Ui, label: &str, value: ColumnValue) {
+    let text = value.to_string();
+
+    if !text.is_empty() {
+        ui.label(label);
+        ui.add(egui::Label::new(text).wrap());
+        ui.end_row();
+    }
+}
+
+/// Renders a row with a value that can be copied to the clipboard, unless the value is missing.
+fn copyable_row(ui: &mut egui::Ui, label: &str, value: ColumnValue) {
+    if value != ColumnValue::Empty {
+        let text = value.to_string();
+
+        ui.label(label);
+        ui.horizontal(|ui| {
+            ui.monospace(&text);
+
+            if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                ui.ctx().copy_text(text.clone());
+            }
+        });
+        ui.end_row();
+    }
+}
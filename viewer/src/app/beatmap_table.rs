@@ -0,0 +1,2016 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    hash::{Hash, Hasher},
+};
+
+use egui_extras::{Column as TableColumn, TableBuilder};
+use osu_db_parser::{
+    columns::ColumnValue,
+    export::{self, Format},
+    links,
+    prelude::*,
+    sort,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    accessibility,
+    actions::{self, ActionContext, ActionId, Menu},
+    collection_listing::CollectionMembership,
+    grade_badge,
+    score_columns::{LocalScores, ScoreColumn, ScoreSortKey},
+    settings::ValueFormat,
+    text_cache::{self, CacheStats, TextCache},
+    text_has_focus,
+};
+
+/// Number of collection names shown in a row before the rest are abbreviated to a count.
+const SHOWN_COLLECTIONS: usize = 2;
+
+/// A sortable table of beatmaps, which only lays out the rows that are currently visible.
+#[derive(Default)]
+pub struct BeatmapTable {
+    layout: TableLayout,
+
+    /// Indices of the beatmaps to show, in display order
+    rows: Vec<usize>,
+
+    /// Beatmap to scroll to once it is shown in the table, and where to align it. With no alignment, the table is
+    /// only scrolled far enough to show the beatmap.
+    scroll_to: Option<(usize, Option<egui::Align>)>,
+
+    /// Display row to scroll to the top of the table, e.g. to restore where a tab was scrolled to
+    scroll_to_display: Option<usize>,
+
+    /// Number of rows that fit in the table when it was last shown, used for moving a page at a time
+    page_rows: usize,
+
+    /// First display row that was laid out when the table was last shown
+    first_shown: usize,
+
+    /// How dates and star ratings are shown
+    format: ValueFormat,
+
+    /// Beatmapsets in the listing, used when grouping the rows by set
+    sets: Vec<Beatmapset>,
+
+    /// Index into `sets` for each beatmap in the listing
+    set_of: Vec<usize>,
+
+    /// Beatmapsets containing the shown beatmaps, in display order, when grouping by set
+    groups: Vec<SetGroup>,
+
+    /// Rows that are laid out in the table. This is the same as `rows` unless the rows are grouped by set, in which
+    /// case each set gets a row of its own, and its difficulties are only included while it's expanded.
+    display: Vec<DisplayRow>,
+
+    /// Folder names of the expanded beatmapsets
+    expanded: HashSet<String>,
+
+    /// Summaries of the local scores on each beatmap, while a `scores.db` is loaded
+    scores: Option<LocalScores>,
+
+    /// Custom columns from the settings, which can be shown after the beatmap's own columns
+    custom: Vec<CustomColumn>,
+
+    /// Value of each custom column for every beatmap in the listing. These are calculated once for each listing, so
+    /// that sorting by a custom column doesn't need to evaluate its expression.
+    custom_values: Vec<Vec<f64>>,
+
+    /// Indices of the beatmaps with settings that have been edited but not saved yet, which are marked in the first
+    /// column
+    edited: BTreeSet<usize>,
+
+    /// Laid out text of the cells that have been shown recently
+    text_cache: TextCache,
+}
+
+/// Represents the user's choice of columns and sorting, which is saved between sessions.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableLayout {
+    /// Visible columns, in display order
+    columns: Vec<Column>,
+
+    /// Current sort key, if the user has clicked on a column header
+    sort: Option<SortKey>,
+
+    /// Current sort key, if the user has clicked on a score column's header instead
+    score_sort: Option<ScoreSortKey>,
+
+    /// Which version of the artist and title to show. This isn't saved, since it comes from the settings.
+    #[serde(skip)]
+    language: MetadataLanguage,
+
+    /// Which star rating to show in the Stars column. This isn't saved, since it comes from the settings.
+    #[serde(skip)]
+    stars: StarPolicy,
+
+    /// Gameplay mode to show grades for, when the beatmaps are filtered to a single mode. This isn't saved, since it
+    /// comes from the filter bar.
+    #[serde(skip)]
+    grade_mode: Option<GameplayMode>,
+
+    /// Whether to show one row for each beatmapset, which can be expanded to show its difficulties
+    group_by_set: bool,
+
+    /// Whether to show which collections contain each beatmap, after the other columns. This is only possible while a
+    /// collection listing is loaded.
+    show_collections: bool,
+
+    /// Whether to show the user's notes and bookmarks for each beatmap, after the other columns
+    show_notes: bool,
+
+    /// Visible columns of information from the local scores, in display order, which are shown after the beatmap's
+    /// own columns while a `scores.db` is loaded
+    score_columns: Vec<ScoreColumn>,
+
+    /// Names of the visible custom columns, in display order, which are shown between the beatmap's own columns and
+    /// the score columns
+    custom_columns: Vec<String>,
+
+    /// Current sort key, if the user has clicked on a custom column's header instead
+    custom_sort: Option<CustomSortKey>,
+}
+
+/// Represents how the rows of a table are sorted, so that each tab of the beatmap listing can be sorted differently.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableSort {
+    sort: Option<SortKey>,
+    score_sort: Option<ScoreSortKey>,
+    custom_sort: Option<CustomSortKey>,
+}
+
+/// Represents a custom column to sort beatmaps by, along with the direction to sort in.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct CustomSortKey {
+    name: String,
+    order: SortOrder,
+}
+
+/// Represents a beatmapset shown in the table when grouping by set.
+#[derive(Clone, Copy, Debug)]
+struct SetGroup {
+    /// Index into the table's beatmapsets
+    set: usize,
+
+    /// Position in the table's rows of the set's first shown difficulty. The set's shown difficulties follow it.
+    start: usize,
+
+    /// Number of difficulties shown
+    len: usize,
+}
+
+/// Represents a row that is laid out in the table.
+#[derive(Clone, Copy, Debug)]
+enum DisplayRow {
+    /// The beatmapset at this index into the table's groups
+    Set(usize),
+
+    /// The beatmap at this position in the table's rows
+    Beatmap(usize),
+
+    /// The beatmap at this position in the table's rows, shown under its expanded beatmapset
+    Difficulty(usize),
+}
+
+/// Represents the value shown for a column in a beatmapset's row.
+enum SetValue<'a> {
+    Value(ColumnValue<'a>),
+
+    /// The lowest and highest values of the set's difficulties
+    Range(ColumnValue<'a>, ColumnValue<'a>),
+}
+
+/// Represents the beatmaps selected in a table, as indices into the listing.
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    /// Most recently clicked beatmap
+    pub primary: Option<usize>,
+
+    /// Every selected beatmap, including the primary one
+    pub beatmaps: BTreeSet<usize>,
+
+    /// Beatmap that Shift selections extend from
+    pub anchor: Option<usize>,
+}
+
+/// Represents extra options to show in a row's context menu.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RowMenu<'a> {
+    /// Collections that the selected beatmaps can be added to, along with which collections contain each beatmap, if
+    /// a collection listing is loaded
+    pub collections: Option<&'a CollectionMembership>,
+
+    /// Whether the selected beatmaps can be removed from the collection being shown
+    pub removable: bool,
+
+    /// The user's notes, if they can be shown and changed from the table
+    pub notes: Option<&'a Notes>,
+
+    /// Beatmaps pinned for comparison, if beatmaps can be pinned from the table
+    pub pins: Option<&'a Pinboard>,
+
+    /// osu! installation folder, used to locate beatmap folders
+    #[cfg(not(target_arch = "wasm32"))]
+    pub osu_directory: Option<&'a Path>,
+
+    /// Whether the selected beatmaps' settings (e.g. local offset) can be edited
+    #[cfg(not(target_arch = "wasm32"))]
+    pub editable: bool,
+}
+
+/// Represents an action requested from a row's context menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowAction {
+    /// Show the details window for the beatmap at this index
+    ShowDetails(usize),
+
+    /// Enter was pressed with the beatmap at this index selected, which focuses on its details
+    Activate(usize),
+
+    /// Open the selected beatmaps' pages on the osu! website
+    OpenPages,
+
+    /// Open the selected beatmaps in osu!direct
+    OpenInOsuDirect,
+
+    /// Open the selected beatmaps' folders in the file manager
+    #[cfg(not(target_arch = "wasm32"))]
+    OpenFolders,
+
+    /// Change the selected beatmaps' settings, such as the local offset
+    #[cfg(not(target_arch = "wasm32"))]
+    EditSettings,
+
+    /// Add the selected beatmaps to the collection at this index, or to a new collection if there's no index
+    AddToCollection(Option<usize>),
+
+    /// Remove the selected beatmaps from the collection being shown
+    RemoveFromCollection,
+
+    /// Add the beatmap at `index` to a collection, or remove it if the collection already contains it
+    ToggleCollection { index: usize, collection: usize },
+
+    /// Star the beatmap at this index, or unstar it if it's already starred
+    ToggleStar(usize),
+
+    /// Pin the beatmap at this index for comparison, or unpin it if it's already pinned
+    TogglePin(usize),
+}
+
+impl Selection {
+    /// Selects a single beatmap.
+    pub fn select_only(&mut self, index: usize) {
+        self.primary = Some(index);
+        self.anchor = Some(index);
+        self.beatmaps.clear();
+        self.beatmaps.insert(index);
+    }
+
+    /// Adds or removes a single beatmap, e.g. when Ctrl clicking.
+    pub fn toggle(&mut self, index: usize) {
+        if !self.beatmaps.remove(&index) {
+            self.beatmaps.insert(index);
+        }
+
+        self.primary = Some(index);
+        self.anchor = Some(index);
+    }
+
+    /// Selects every beatmap in `rows` between the anchor and `index`, e.g. when Shift clicking. The previous
+    /// selection is replaced, unless `extend` is set. If the anchor isn't in `rows`, only `index` is selected.
+    pub fn select_range(&mut self, rows: &[usize], index: usize, extend: bool) {
+        let anchor = self
+            .anchor
+            .and_then(|anchor| rows.iter().position(|&r| r == anchor));
+        let target = rows.iter().position(|&r| r == index);
+
+        let (Some(anchor), Some(target)) = (anchor, target) else {
+            self.select_only(index);
+            return;
+        };
+
+        if !extend {
+            self.beatmaps.clear();
+        }
+
+        self.beatmaps
+            .extend(&rows[anchor.min(target)..=anchor.max(target)]);
+        self.primary = Some(index);
+    }
+
+    /// Deselects any beatmaps that aren't in `rows`, e.g. once the rows have been filtered.
+    pub fn retain_shown(&mut self, rows: &[usize]) {
+        let shown = rows.iter().copied().collect::<HashSet<_>>();
+        self.beatmaps.retain(|index| shown.contains(index));
+
+        if self
+            .primary
+            .is_some_and(|primary| !self.beatmaps.contains(&primary))
+        {
+            self.primary = self.beatmaps.first().copied();
+        }
+
+        if self.anchor.is_some_and(|anchor| !shown.contains(&anchor)) {
+            self.anchor = self.primary;
+        }
+    }
+
+    /// Clears the selection.
+    pub fn clear(&mut self) {
+        self.primary = None;
+        self.anchor = None;
+        self.beatmaps.clear();
+    }
+
+    /// Gets the selected beatmaps from the listing.
+    pub fn selected<'a>(
+        &'a self,
+        beatmaps: &'a [BeatmapEntry],
+    ) -> impl Iterator<Item = &'a BeatmapEntry> + 'a {
+        self.beatmaps.iter().filter_map(|&i| beatmaps.get(i))
+    }
+
+    /// Gets the MD5 hashes of the selected beatmaps, skipping any without one.
+    pub fn md5s(&self, beatmaps: &[BeatmapEntry]) -> Vec<String> {
+        self.beatmaps
+            .iter()
+            .filter_map(|&i| beatmaps.get(i).and_then(|b| b.md5.clone()))
+            .collect()
+    }
+
+    /// Gets what the selected beatmaps have, which decides which of the beatmap actions can be used on them.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut, unused_variables))]
+    pub fn action_context(&self, beatmaps: &[BeatmapEntry], menu: &RowMenu) -> ActionContext {
+        let selected = self.selected(beatmaps).collect::<Vec<_>>();
+
+        let mut context = ActionContext {
+            listing: true,
+            selected: selected.len(),
+            submitted: selected
+                .iter()
+                .any(|beatmap| links::beatmap_url(beatmap).is_some()),
+            md5: selected.iter().any(|beatmap| beatmap.md5.is_some()),
+            difficulty_id: selected
+                .iter()
+                .any(|beatmap| beatmap.difficulty_id().is_some()),
+            ..Default::default()
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            context.osu_folder = menu.osu_directory.is_some();
+            context.editable = menu.editable;
+            context.folder = menu.osu_directory.is_some_and(|osu_directory| {
+                selected.iter().any(|beatmap| {
+                    osu_db_parser::paths::beatmap_folder(osu_directory, beatmap)
+                        .is_some_and(|path| path.is_dir())
+                })
+            });
+        }
+
+        context
+    }
+}
+
+impl RowAction {
+    /// Gets the row action that an action from the registry is handled as, if it's handled by the view.
+    pub fn from_action(id: ActionId) -> Option<RowAction> {
+        match id {
+            ActionId::OpenPages => Some(RowAction::OpenPages),
+            ActionId::OpenInOsuDirect => Some(RowAction::OpenInOsuDirect),
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::OpenFolders => Some(RowAction::OpenFolders),
+            #[cfg(not(target_arch = "wasm32"))]
+            ActionId::EditSettings => Some(RowAction::EditSettings),
+            _ => None,
+        }
+    }
+}
+
+impl Default for TableLayout {
+    fn default() -> Self {
+        Self {
+            columns: vec![
+                Column::Artist,
+                Column::Title,
+                Column::Difficulty,
+                Column::Creator,
+                Column::Mode,
+                Column::Status,
+                Column::Stars,
+                Column::Length,
+                Column::Grade,
+            ],
+            sort: None,
+            score_sort: None,
+            language: MetadataLanguage::default(),
+            stars: StarPolicy::default(),
+            grade_mode: None,
+            group_by_set: false,
+            show_collections: false,
+            show_notes: false,
+            score_columns: Vec::new(),
+            custom_columns: Vec::new(),
+            custom_sort: None,
+        }
+    }
+}
+
+impl TableLayout {
+    /// Gets the column whose values are actually shown for a visible column.
+    fn displayed_column(&self, column: Column) -> Column {
+        match column {
+            Column::Grade => match self.grade_mode {
+                Some(GameplayMode::Standard) => Column::GradeStd,
+                Some(GameplayMode::Taiko) => Column::GradeTaiko,
+                Some(GameplayMode::Catch) => Column::GradeCatch,
+                Some(GameplayMode::Mania) => Column::GradeMania,
+                None => Column::Grade,
+            },
+            column => column,
+        }
+    }
+
+    /// Gets the value to show in a cell.
+    fn cell_value<'a>(&self, column: Column, beatmap: &'a BeatmapEntry) -> ColumnValue<'a> {
+        self.displayed_column(column)
+            .value_with(beatmap, self.language, self.stars)
+    }
+
+    /// Renders a beatmap as a Markdown table row, using the visible columns.
+    fn markdown_row(&self, beatmap: &BeatmapEntry) -> String {
+        let cells = self
+            .columns
+            .iter()
+            .map(|&column| {
+                self.cell_value(column, beatmap)
+                    .to_string()
+                    .replace('|', "\\|")
+            })
+            .collect::<Vec<_>>();
+
+        format!("| {} |", cells.join(" | "))
+    }
+
+    /// Gets the columns whose values are shown, in display order.
+    fn displayed_columns(&self) -> Vec<Column> {
+        self.columns
+            .iter()
+            .map(|&column| self.displayed_column(column))
+            .collect()
+    }
+
+    /// Gets the sort key to use for the rows.
+    fn sort_key(&self) -> Option<SortKey> {
+        self.sort.map(|sort| SortKey {
+            column: self.displayed_column(sort.column),
+            order: sort.order,
+            language: self.language,
+            stars: self.stars,
+        })
+    }
+
+    /// Gets the value to show in a beatmapset's row, summarising the values of its shown difficulties.
+    ///
+    /// Numbers are shown as a range, durations and dates use the longest or most recent value, grades use the best
+    /// grade, and text is only shown if every difficulty has the same text.
+    fn set_value<'a>(
+        &self,
+        column: Column,
+        difficulties: &[usize],
+        beatmaps: &'a [BeatmapEntry],
+    ) -> SetValue<'a> {
+        let values = difficulties
+            .iter()
+            .map(|&i| self.cell_value(column, &beatmaps[i]))
+            .collect::<Vec<_>>();
+
+        // Missing values are always sorted last, so they're only picked if every value is missing
+        let ascending = SortKey::new(column);
+        let descending = SortKey {
+            order: SortOrder::Descending,
+            ..ascending
+        };
+
+        let lowest = values
+            .iter()
+            .min_by(|a, b| ascending.compare_values(a, b))
+            .cloned()
+            .unwrap_or(ColumnValue::Empty);
+
+        let highest = values
+            .iter()
+            .min_by(|a, b| descending.compare_values(a, b))
+            .cloned()
+            .unwrap_or(ColumnValue::Empty);
+
+        match lowest {
+            ColumnValue::Integer(_) | ColumnValue::Float(_) if lowest != highest => {
+                SetValue::Range(lowest, highest)
+            }
+            ColumnValue::Duration(_) | ColumnValue::DateTime(_) => SetValue::Value(highest),
+            ColumnValue::Text(_) if values.iter().any(|value| *value != lowest) => {
+                SetValue::Value(ColumnValue::Empty)
+            }
+            value => SetValue::Value(value),
+        }
+    }
+}
+
+impl BeatmapTable {
+    /// Gets the text that a copy action puts on the clipboard for the selected beatmaps, or `None` if it isn't a copy
+    /// action.
+    pub fn copy_text(
+        &self,
+        id: ActionId,
+        beatmaps: &[BeatmapEntry],
+        selection: &Selection,
+    ) -> Option<String> {
+        let selected = selection.selected(beatmaps).collect::<Vec<_>>();
+        copy_text(&self.layout, id, &selected)
+    }
+
+    /// Gets the current layout, so that it can be saved.
+    pub fn layout(&self) -> &TableLayout {
+        &self.layout
+    }
+
+    /// Restores a saved layout.
+    pub fn set_layout(&mut self, layout: TableLayout) {
+        let TableLayout {
+            language,
+            stars,
+            grade_mode,
+            ..
+        } = self.layout;
+
+        self.layout = layout;
+
+        // Keep the settings that aren't part of the saved layout
+        self.layout.language = language;
+        self.layout.stars = stars;
+        self.layout.grade_mode = grade_mode;
+
+        // Make sure there's always something to show
+        if self.layout.columns.is_empty() {
+            self.layout.columns = TableLayout::default().columns;
+        }
+    }
+
+    /// Gets how the rows are sorted, e.g. to restore it when switching back to a tab.
+    pub fn sort(&self) -> TableSort {
+        TableSort {
+            sort: self.layout.sort,
+            score_sort: self.layout.score_sort,
+            custom_sort: self.layout.custom_sort.clone(),
+        }
+    }
+
+    /// Changes how the rows are sorted. The new sort is used the next time the rows are set.
+    pub fn set_sort(&mut self, sort: TableSort) {
+        self.layout.sort = sort.sort;
+        self.layout.score_sort = sort.score_sort;
+        self.layout.custom_sort = sort.custom_sort;
+    }
+
+    /// Gets the position of the first row shown at the top of the table, which can be scrolled back to with
+    /// [`BeatmapTable::scroll_to_position`].
+    pub fn scroll_position(&self) -> usize {
+        self.first_shown
+    }
+
+    /// Scrolls the table so that the row at a position from [`BeatmapTable::scroll_position`] is at the top, the next
+    /// time the table is shown.
+    pub fn scroll_to_position(&mut self, position: usize) {
+        self.scroll_to = None;
+        self.scroll_to_display = Some(position);
+    }
+
+    /// Sets the beatmaps to show, using indices into `beatmaps`. The rows are sorted using the current sort key, and
+    /// grouped by beatmapset if needed.
+    pub fn set_rows(&mut self, beatmaps: &[BeatmapEntry], rows: Vec<usize>) {
+        self.rows = rows;
+
+        if let Some(sort) = self.layout.score_sort {
+            self.sort_by_scores(beatmaps, sort);
+        } else if let Some((column, order)) = self.custom_sort_column(beatmaps) {
+            sort::sort_indices_by_values(&self.custom_values[column], order, &mut self.rows);
+        } else if let Some(sort) = self.layout.sort_key() {
+            sort.sort_indices(beatmaps, &mut self.rows);
+        }
+
+        self.group_rows();
+    }
+
+    /// Sets the local scores shown in the score columns, e.g. once a different `scores.db` is loaded, sorting the rows
+    /// again in case they're sorted by a score column.
+    pub fn set_scores(&mut self, beatmaps: &[BeatmapEntry], scores: LocalScores) {
+        self.scores = Some(scores);
+
+        let rows = std::mem::take(&mut self.rows);
+        self.set_rows(beatmaps, rows);
+    }
+
+    /// Sets the custom columns that can be shown, evaluating them for every beatmap if they've changed, and sorting
+    /// the rows again in case they're sorted by a custom column.
+    pub fn set_custom_columns(&mut self, beatmaps: &[BeatmapEntry], custom: Vec<CustomColumn>) {
+        if self.custom == custom {
+            return;
+        }
+
+        self.custom = custom;
+        self.update_custom_values(beatmaps);
+
+        let rows = std::mem::take(&mut self.rows);
+        self.set_rows(beatmaps, rows);
+    }
+
+    /// Evaluates the custom columns for every beatmap in the listing. This should be called whenever a different
+    /// listing is loaded, before its rows are set.
+    pub fn update_custom_values(&mut self, beatmaps: &[BeatmapEntry]) {
+        self.custom_values = self
+            .custom
+            .iter()
+            .map(|column| column.values(beatmaps))
+            .collect();
+    }
+
+    /// Gets the custom columns whose values are shown, in display order, e.g. to export the rows with the same
+    /// columns.
+    pub fn custom_columns(&self) -> Vec<CustomColumn> {
+        self.shown_custom_columns()
+            .into_iter()
+            .map(|i| self.custom[i].clone())
+            .collect()
+    }
+
+    /// Sets which beatmaps have unsaved edits to their settings, so that they can be marked.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_edited(&mut self, edited: BTreeSet<usize>) {
+        self.edited = edited;
+    }
+
+    /// Sets the beatmapsets in the listing, from [`BeatmapListing::beatmapsets`], so that the rows can be grouped by
+    /// set. This should be called before the listing's rows are set.
+    pub fn set_beatmapsets(&mut self, sets: Vec<Beatmapset>) {
+        let count = sets.iter().map(|set| set.beatmaps.len()).sum();
+        self.set_of = vec![0; count];
+
+        for (i, set) in sets.iter().enumerate() {
+            for &beatmap in &set.beatmaps {
+                self.set_of[beatmap] = i;
+            }
+        }
+
+        self.sets = sets;
+    }
+
+    /// Checks whether the rows are grouped by beatmapset.
+    pub fn is_grouped(&self) -> bool {
+        self.layout.group_by_set
+    }
+
+    /// Switches between showing one row for each beatmap and one row for each beatmapset.
+    pub fn set_grouped(&mut self, beatmaps: &[BeatmapEntry], grouped: bool) {
+        self.layout.group_by_set = grouped;
+
+        // Grouping moves each set's difficulties next to each other, so the original order needs to be restored when
+        // there's no sort key to do it
+        let mut rows = std::mem::take(&mut self.rows);
+
+        if !grouped
+            && self.layout.sort.is_none()
+            && self.layout.score_sort.is_none()
+            && self.layout.custom_sort.is_none()
+        {
+            rows.sort_unstable();
+        }
+
+        self.set_rows(beatmaps, rows);
+    }
+
+    /// Gets the number of beatmapsets shown, if the rows are grouped by set.
+    pub fn set_count(&self) -> Option<usize> {
+        self.grouping().then_some(self.groups.len())
+    }
+
+    /// Gets the beatmaps currently shown, as indices into the listing in display order.
+    pub fn rows(&self) -> &[usize] {
+        &self.rows
+    }
+
+    /// Gets the columns whose values are shown, in display order, e.g. to export the rows with the same columns.
+    pub fn displayed_columns(&self) -> Vec<Column> {
+        self.layout.displayed_columns()
+    }
+
+    /// Gets which version of the artist and title is shown, e.g. to export the rows with the same values.
+    pub fn language(&self) -> MetadataLanguage {
+        self.layout.language
+    }
+
+    /// Checks whether a beatmap is one of the rows currently shown.
+    pub fn is_shown(&self, index: usize) -> bool {
+        self.rows.contains(&index)
+    }
+
+    /// Scrolls to a beatmap the next time the table is shown, or once it has been added to the rows.
+    pub fn scroll_to(&mut self, index: usize) {
+        self.scroll_to = Some((index, Some(egui::Align::Center)));
+    }
+
+    /// Sets the gameplay mode to show grades for in the grade column, or `None` to use each beatmap's own mode. The
+    /// new mode is used the next time the rows are set.
+    pub fn set_grade_mode(&mut self, mode: Option<GameplayMode>) {
+        self.layout.grade_mode = mode;
+    }
+
+    /// Sets how the artist, title, dates and star ratings are shown, sorting the rows again in case the artist, title
+    /// or star ratings changed.
+    pub fn set_display(
+        &mut self,
+        beatmaps: &[BeatmapEntry],
+        language: MetadataLanguage,
+        stars: StarPolicy,
+        format: ValueFormat,
+    ) {
+        self.layout.language = language;
+        self.layout.stars = stars;
+        self.format = format;
+
+        let rows = std::mem::take(&mut self.rows);
+        self.set_rows(beatmaps, rows);
+    }
+
+    /// Drops the laid out text of the cells, e.g. when another listing is loaded.
+    pub fn clear_text_cache(&mut self) {
+        self.text_cache.clear();
+    }
+
+    /// Takes the number of times the cells' text was found already laid out since this was last called.
+    pub fn take_text_cache_stats(&mut self) -> CacheStats {
+        self.text_cache.take_stats()
+    }
+
+    /// Gets the number of rows currently shown.
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Sorts the rows by a score column. Like [`SortKey::sort_indices`], the sort is stable and each beatmap's scores
+    /// are only looked up once.
+    fn sort_by_scores(&mut self, beatmaps: &[BeatmapEntry], sort: ScoreSortKey) {
+        let Some(scores) = &self.scores else {
+            return;
+        };
+
+        let mut keyed = self
+            .rows
+            .iter()
+            .map(|&i| (scores.get(&beatmaps[i], self.layout.grade_mode), i))
+            .collect::<Vec<_>>();
+
+        keyed.sort_by(|(a, _), (b, _)| sort.compare(*a, *b));
+
+        self.rows = keyed.into_iter().map(|(_, i)| i).collect();
+    }
+
+    /// Gets the custom column to sort by, as an index into the table's custom columns, along with the sort order. This
+    /// is `None` unless the rows are sorted by a custom column that has been evaluated for `beatmaps`.
+    fn custom_sort_column(&self, beatmaps: &[BeatmapEntry]) -> Option<(usize, SortOrder)> {
+        let sort = self.layout.custom_sort.as_ref()?;
+        let column = self
+            .custom
+            .iter()
+            .position(|column| column.name == sort.name)?;
+
+        self.custom_values
+            .get(column)
+            .is_some_and(|values| values.len() == beatmaps.len())
+            .then_some((column, sort.order))
+    }
+
+    /// Gets the custom columns to show, as indices into the table's custom columns. Columns that have been removed from
+    /// the settings are skipped.
+    fn shown_custom_columns(&self) -> Vec<usize> {
+        self.layout
+            .custom_columns
+            .iter()
+            .filter_map(|name| self.custom.iter().position(|column| column.name == *name))
+            .collect()
+    }
+
+    /// Gets the value of a custom column for the beatmap at `index`, or `None` if it isn't a finite number (e.g. from
+    /// dividing by zero).
+    fn custom_value(&self, column: usize, index: usize) -> Option<f64> {
+        self.custom_values
+            .get(column)
+            .and_then(|values| values.get(index))
+            .copied()
+            .filter(|value| value.is_finite())
+    }
+
+    /// Gets the score columns to show, which are only shown while a `scores.db` is loaded.
+    fn shown_score_columns(&self) -> &[ScoreColumn] {
+        match self.scores {
+            Some(_) => &self.layout.score_columns,
+            None => &[],
+        }
+    }
+
+    /// Checks whether the rows are actually being grouped, which needs the listing's beatmapsets.
+    fn grouping(&self) -> bool {
+        self.layout.group_by_set && !self.sets.is_empty()
+    }
+
+    /// Moves each beatmapset's difficulties next to each other when grouping by set, then updates the displayed rows.
+    ///
+    /// Sets are placed in the order that their first difficulty appears in the sorted rows, which sorts them at the
+    /// set level, e.g. by their hardest difficulty when sorting by star rating in descending order.
+    fn group_rows(&mut self) {
+        self.groups.clear();
+
+        if self.grouping() {
+            let mut group_of = HashMap::new();
+            let mut grouped: Vec<(usize, Vec<usize>)> = Vec::new();
+
+            for &index in &self.rows {
+                let set = self.set_of[index];
+                let group = *group_of.entry(set).or_insert_with(|| {
+                    grouped.push((set, Vec::new()));
+                    grouped.len() - 1
+                });
+
+                grouped[group].1.push(index);
+            }
+
+            self.rows.clear();
+
+            for (set, difficulties) in grouped {
+                self.groups.push(SetGroup {
+                    set,
+                    start: self.rows.len(),
+                    len: difficulties.len(),
+                });
+
+                self.rows.extend(difficulties);
+            }
+        }
+
+        self.update_display();
+    }
+
+    /// Rebuilds the rows that are laid out in the table, e.g. after a beatmapset is expanded or collapsed.
+    fn update_display(&mut self) {
+        self.display.clear();
+
+        if !self.grouping() {
+            self.display
+                .extend((0..self.rows.len()).map(DisplayRow::Beatmap));
+            return;
+        }
+
+        for (i, group) in self.groups.iter().enumerate() {
+            let rows = group.start..group.start + group.len;
+
+            // Beatmaps without a folder name are in sets of their own, so there's nothing to group
+            match &self.sets[group.set].folder_name {
+                Some(folder) => {
+                    self.display.push(DisplayRow::Set(i));
+
+                    if self.expanded.contains(folder) {
+                        self.display.extend(rows.map(DisplayRow::Difficulty));
+                    }
+                }
+                None => self.display.extend(rows.map(DisplayRow::Beatmap)),
+            }
+        }
+    }
+
+    /// Gets the displayed row to scroll to and where to align it, expanding the beatmapset containing the beatmap if
+    /// needed. Returns `None` if there's nothing to scroll to, or the beatmap isn't shown yet.
+    fn take_scroll_row(&mut self) -> Option<(usize, Option<egui::Align>)> {
+        if let Some(position) = self.scroll_to_display.take() {
+            return (!self.display.is_empty())
+                .then(|| (position.min(self.display.len() - 1), Some(egui::Align::Min)));
+        }
+
+        let (index, align) = self.scroll_to?;
+        let position = self.rows.iter().position(|&r| r == index)?;
+
+        self.scroll_to = None;
+
+        if self.grouping() {
+            let group = self.groups[self
+                .groups
+                .partition_point(|group| group.start + group.len <= position)];
+
+            if let Some(folder) = &self.sets[group.set].folder_name {
+                if self.expanded.insert(folder.clone()) {
+                    self.update_display();
+                }
+            }
+        }
+
+        self.display
+            .iter()
+            .position(|row| match *row {
+                DisplayRow::Beatmap(row) | DisplayRow::Difficulty(row) => row == position,
+                DisplayRow::Set(_) => false,
+            })
+            .map(|row| (row, align))
+    }
+
+    /// Moves the selection with the arrow keys, Page Up/Down and Home/End, selecting a range instead when Shift is
+    /// held. Ctrl+C copies the selected rows as tab-separated values, and Enter activates the primary beatmap.
+    ///
+    /// Keys are ignored while typing in a text box, so that they can be used to edit the text.
+    fn handle_keys(
+        &mut self,
+        ctx: &egui::Context,
+        beatmaps: &[BeatmapEntry],
+        selection: &mut Selection,
+    ) -> Option<RowAction> {
+        if self.rows.is_empty() || text_has_focus(ctx) {
+            return None;
+        }
+
+        let last = self.rows.len() - 1;
+        let page = self.page_rows.max(1) as isize;
+        let current = selection
+            .primary
+            .and_then(|primary| self.rows.iter().position(|&r| r == primary));
+
+        let (target, extend, activate, copy) = ctx.input_mut(|i| {
+            // Extra Shift modifiers are ignored when matching keys, so this also catches Shift+arrows
+            let mut count = |key| i.count_and_consume_key(egui::Modifiers::NONE, key) as isize;
+
+            let steps = count(egui::Key::ArrowDown) - count(egui::Key::ArrowUp)
+                + page * (count(egui::Key::PageDown) - count(egui::Key::PageUp));
+
+            let target = if count(egui::Key::Home) > 0 {
+                Some(0)
+            } else if count(egui::Key::End) > 0 {
+                Some(last)
+            } else if steps != 0 {
+                // Start from the top when nothing is selected
+                Some(match current {
+                    Some(current) => current.saturating_add_signed(steps).min(last),
+                    None => 0,
+                })
+            } else {
+                None
+            };
+
+            let activate = count(egui::Key::Enter) > 0;
+            let copy = i
+                .events
+                .iter()
+                .any(|event| matches!(event, egui::Event::Copy));
+
+            (target, i.modifiers.shift, activate, copy)
+        });
+
+        if let Some(target) = target {
+            let index = self.rows[target];
+
+            if extend {
+                selection.select_range(&self.rows, index, false);
+            } else {
+                selection.select_only(index);
+            }
+
+            self.scroll_to = Some((index, None));
+        }
+
+        if copy && !selection.beatmaps.is_empty() {
+            ctx.copy_text(self.selected_tsv(beatmaps, selection));
+        }
+
+        selection
+            .primary
+            .filter(|_| activate)
+            .map(RowAction::Activate)
+    }
+
+    /// Formats the selected rows as tab-separated values with a header row, using the visible columns.
+    fn selected_tsv(&self, beatmaps: &[BeatmapEntry], selection: &Selection) -> String {
+        let selected = self
+            .rows
+            .iter()
+            .filter(|index| selection.beatmaps.contains(index))
+            .map(|&index| &beatmaps[index])
+            .collect::<Vec<_>>();
+
+        let mut output = Vec::new();
+
+        // Writing to memory can't fail, and there's no way to cancel
+        let _ = export::write_with_custom(
+            &mut output,
+            Format::Tsv,
+            &self.layout.displayed_columns(),
+            &self.custom_columns(),
+            self.layout.language,
+            &selected,
+            |_, _| true,
+        );
+
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    /// Renders the table, updating the selection when a row is clicked.
+    ///
+    /// Holding Ctrl (or Cmd on macOS) while clicking toggles a single row, and holding Shift selects a range of rows.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        beatmaps: &[BeatmapEntry],
+        selection: &mut Selection,
+        menu: RowMenu,
+    ) -> Option<RowAction> {
+        let row_height = accessibility::row_height(ui);
+
+        // Column widths are remembered by egui for each set of columns, so give each layout its own ID
+        let show_collections = self.layout.show_collections && menu.collections.is_some();
+        let show_notes = self.layout.show_notes && menu.notes.is_some();
+        let score_columns = self.shown_score_columns().to_vec();
+        let custom_columns = self.shown_custom_columns();
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.layout.columns.hash(&mut hasher);
+        for &column in &custom_columns {
+            self.custom[column].name.hash(&mut hasher);
+        }
+        score_columns.hash(&mut hasher);
+        show_collections.hash(&mut hasher);
+        show_notes.hash(&mut hasher);
+        let table_id = hasher.finish();
+
+        let mut clicked_column = None;
+        let mut clicked_score_column = None;
+        let mut clicked_custom_column = None;
+        let mut new_layout = None;
+        let mut resort = false;
+        let mut toggled_set = None;
+
+        // Rows that fit below the header, for Page Up/Down. Rows are placed this far apart, including the spacing
+        // between them.
+        let row_stride = row_height + ui.spacing().item_spacing.y;
+        self.page_rows = ((ui.available_height() / row_stride) as usize).saturating_sub(1);
+
+        // The table can be reached with Tab like any other widget, so that the arrow keys can be used after leaving a
+        // text box. It doesn't take clicks, which go to the rows instead.
+        let focus_rect = ui.available_rect_before_wrap();
+        let focus = ui.interact(
+            focus_rect,
+            ui.id().with("beatmap_table_focus"),
+            egui::Sense::focusable_noninteractive(),
+        );
+        focus.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Other, true, "Beatmaps"));
+
+        if focus.has_focus() {
+            // Otherwise, egui would move the focus to the next widget when an arrow key is pressed
+            ui.memory_mut(|memory| {
+                memory.set_focus_lock_filter(
+                    focus.id,
+                    egui::EventFilter {
+                        vertical_arrows: true,
+                        horizontal_arrows: true,
+                        ..Default::default()
+                    },
+                )
+            });
+        }
+
+        let mut action = self.handle_keys(ui.ctx(), beatmaps, selection);
+        let scroll_row = self.take_scroll_row();
+        let mut first_shown = usize::MAX;
+
+        // Taken out of the table while the rows are shown, since they borrow the rest of it
+        let mut text_cache = std::mem::take(&mut self.text_cache);
+        text_cache.set_style(text_cache::style_fingerprint(ui));
+
+        ui.push_id(table_id, |ui| {
+            let mut table = TableBuilder::new(ui)
+                .striped(true)
+                .resizable(true)
+                .sense(egui::Sense::click())
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .min_scrolled_height(0.0);
+
+            for column in &self.layout.columns {
+                table = table.column(
+                    TableColumn::initial(initial_width(*column))
+                        .at_least(30.0)
+                        .clip(true),
+                );
+            }
+
+            for _ in 0..custom_columns.len() + score_columns.len() {
+                table = table.column(TableColumn::initial(90.0).at_least(30.0).clip(true));
+            }
+
+            if show_collections {
+                table = table.column(TableColumn::initial(150.0).at_least(30.0).clip(true));
+            }
+
+            if show_notes {
+                table = table.column(TableColumn::initial(200.0).at_least(30.0).clip(true));
+            }
+
+            if let Some((row, align)) = scroll_row {
+                table = table.scroll_to_row(row, align);
+            }
+
+            table
+                .header(row_height, |mut header| {
+                    for column in self.layout.columns.clone() {
+                        let (_, response) = header.col(|ui| {
+                            let indicator = match self.layout.sort {
+                                Some(SortKey {
+                                    column: sort_column,
+                                    order,
+                                    ..
+                                }) if sort_column == column => match order {
+                                    SortOrder::Ascending => " ⏶",
+                                    SortOrder::Descending => " ⏷",
+                                },
+                                _ => "",
+                            };
+
+                            let label =
+                                egui::RichText::new(format!("{}{}", column.header(), indicator))
+                                    .strong();
+
+                            if ui.add(egui::Button::new(label).frame(false)).clicked() {
+                                clicked_column = Some(column);
+                            }
+                        });
+
+                        // Right clicking any header shows the column chooser. Changes are applied after the table
+                        // has been drawn, so that every row has the same columns.
+                        response.context_menu(|ui| {
+                            let mut layout = self.layout.clone();
+                            column_chooser(ui, &mut layout, &self.custom);
+
+                            if layout != self.layout {
+                                new_layout = Some(layout);
+                            }
+                        });
+                    }
+
+                    for &column in &custom_columns {
+                        let name = &self.custom[column].name;
+                        let (_, response) = header.col(|ui| {
+                            let indicator = match &self.layout.custom_sort {
+                                Some(sort) if sort.name == *name => match sort.order {
+                                    SortOrder::Ascending => " ⏶",
+                                    SortOrder::Descending => " ⏷",
+                                },
+                                _ => "",
+                            };
+
+                            let label =
+                                egui::RichText::new(format!("{}{}", name, indicator)).strong();
+
+                            if ui
+                                .add(egui::Button::new(label).frame(false))
+                                .on_hover_text(self.custom[column].expr.to_string())
+                                .clicked()
+                            {
+                                clicked_custom_column = Some(name.clone());
+                            }
+                        });
+
+                        response.context_menu(|ui| {
+                            let mut layout = self.layout.clone();
+                            column_chooser(ui, &mut layout, &self.custom);
+
+                            if layout != self.layout {
+                                new_layout = Some(layout);
+                            }
+                        });
+                    }
+
+                    for &column in &score_columns {
+                        let (_, response) = header.col(|ui| {
+                            let indicator = match self.layout.score_sort {
+                                Some(ScoreSortKey {
+                                    column: sort_column,
+                                    order,
+                                }) if sort_column == column => match order {
+                                    SortOrder::Ascending => " ⏶",
+                                    SortOrder::Descending => " ⏷",
+                                },
+                                _ => "",
+                            };
+
+                            let label =
+                                egui::RichText::new(format!("{}{}", column.header(), indicator))
+                                    .strong();
+
+                            if ui.add(egui::Button::new(label).frame(false)).clicked() {
+                                clicked_score_column = Some(column);
+                            }
+                        });
+
+                        response.context_menu(|ui| {
+                            let mut layout = self.layout.clone();
+                            column_chooser(ui, &mut layout, &self.custom);
+
+                            if layout != self.layout {
+                                new_layout = Some(layout);
+                            }
+                        });
+                    }
+
+                    // Membership and notes can't be sorted by, so these headers are just labels
+                    for (shown, title) in [(show_collections, "Collections"), (show_notes, "Notes")]
+                    {
+                        if !shown {
+                            continue;
+                        }
+
+                        let (_, response) = header.col(|ui| {
+                            ui.strong(title);
+                        });
+
+                        response.context_menu(|ui| {
+                            let mut layout = self.layout.clone();
+                            column_chooser(ui, &mut layout, &self.custom);
+
+                            if layout != self.layout {
+                                new_layout = Some(layout);
+                            }
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(row_height, self.display.len(), |mut row| {
+                        first_shown = first_shown.min(row.index());
+
+                        let (position, nested) = match self.display[row.index()] {
+                            DisplayRow::Set(group) => {
+                                if let Some(menu_action) = self.set_row(
+                                    &mut row,
+                                    self.groups[group],
+                                    beatmaps,
+                                    selection,
+                                    menu,
+                                    &mut toggled_set,
+                                ) {
+                                    action = Some(menu_action);
+                                }
+
+                                return;
+                            }
+                            DisplayRow::Beatmap(position) => (position, false),
+                            DisplayRow::Difficulty(position) => (position, true),
+                        };
+
+                        let index = self.rows[position];
+                        let beatmap = &beatmaps[index];
+
+                        row.set_selected(selection.beatmaps.contains(&index));
+
+                        for (i, &column) in self.layout.columns.iter().enumerate() {
+                            row.col(|ui| {
+                                // Difficulties are indented under their set's expand arrow
+                                if nested && i == 0 {
+                                    ui.add_space(ui.spacing().indent);
+                                }
+
+                                if i == 0 && self.edited.contains(&index) {
+                                    ui.label("✏")
+                                        .on_hover_text("Edited since osu!.db was last saved");
+                                }
+
+                                match self.layout.cell_value(column, beatmap) {
+                                    ColumnValue::Grade(grade) => {
+                                        grade_badge(ui, grade);
+                                    }
+
+                                    // Unplayed beatmaps are dimmed, so that they stand out from the rest
+                                    value => {
+                                        text_cache.label(
+                                            ui,
+                                            i,
+                                            &self.format.text(column, &value),
+                                            beatmap.is_unplayed,
+                                        );
+                                    }
+                                }
+                            });
+                        }
+
+                        for (i, &column) in custom_columns.iter().enumerate() {
+                            row.col(|ui| {
+                                let Some(value) = self.custom_value(column, index) else {
+                                    return;
+                                };
+
+                                text_cache.label(
+                                    ui,
+                                    self.layout.columns.len() + i,
+                                    &ColumnValue::Float(value).to_string(),
+                                    beatmap.is_unplayed,
+                                );
+                            });
+                        }
+
+                        let summary = self
+                            .scores
+                            .as_ref()
+                            .and_then(|scores| scores.get(beatmap, self.layout.grade_mode));
+
+                        for &column in &score_columns {
+                            row.col(|ui| self.score_cell(ui, column, summary));
+                        }
+
+                        if let Some(membership) = menu.collections.filter(|_| show_collections) {
+                            row.col(|ui| {
+                                if let Some(cell_action) =
+                                    collection_cell(ui, membership, beatmap, index)
+                                {
+                                    action = Some(cell_action);
+                                }
+                            });
+                        }
+
+                        if let Some(notes) = menu.notes.filter(|_| show_notes) {
+                            row.col(|ui| {
+                                if let Some(cell_action) = note_cell(ui, notes.get(beatmap), index)
+                                {
+                                    action = Some(cell_action);
+                                }
+                            });
+                        }
+
+                        let response = row.response();
+
+                        if response.clicked() {
+                            let modifiers = response.ctx.input(|i| i.modifiers);
+                            focus.request_focus();
+
+                            if modifiers.shift {
+                                // Select every row between the previously clicked one and this one, adding to the
+                                // selection if Ctrl is held too. Each set's difficulties are next to each other, so
+                                // this includes any collapsed sets between them.
+                                selection.select_range(&self.rows, index, modifiers.command);
+                            } else if modifiers.command {
+                                selection.toggle(index);
+                            } else {
+                                selection.select_only(index);
+                            }
+                        }
+
+                        // Right clicking outside the selection selects the clicked row instead
+                        if response.secondary_clicked() && !selection.beatmaps.contains(&index) {
+                            selection.select_only(index);
+                        }
+
+                        response.context_menu(|ui| {
+                            if let Some(menu_action) = row_context_menu(
+                                ui,
+                                &self.layout,
+                                beatmaps,
+                                selection,
+                                menu,
+                                Some(index),
+                            ) {
+                                action = Some(menu_action);
+                            }
+                        });
+                    });
+                });
+        });
+
+        self.text_cache = text_cache;
+
+        if focus.has_focus() {
+            accessibility::focus_outline(ui, focus_rect.intersect(ui.min_rect()));
+        }
+
+        // Expanding or collapsing a set changes the number of rows, so this is done once the table has been drawn
+        if let Some(folder) = toggled_set {
+            if !self.expanded.remove(&folder) {
+                self.expanded.insert(folder);
+            }
+
+            self.update_display();
+        }
+
+        // Nothing is laid out when there are no rows
+        self.first_shown = if first_shown == usize::MAX {
+            0
+        } else {
+            first_shown
+        };
+
+        // Clicking the current sort column toggles the sort order
+        if let Some(column) = clicked_column {
+            self.layout.sort = Some(match self.layout.sort {
+                Some(sort) if sort.column == column => SortKey {
+                    order: sort.order.reversed(),
+                    ..sort
+                },
+                _ => SortKey::new(column),
+            });
+            self.layout.score_sort = None;
+            self.layout.custom_sort = None;
+
+            resort = true;
+        }
+
+        if let Some(name) = clicked_custom_column {
+            self.layout.custom_sort = Some(match self.layout.custom_sort.take() {
+                Some(sort) if sort.name == name => CustomSortKey {
+                    order: sort.order.reversed(),
+                    ..sort
+                },
+                _ => CustomSortKey {
+                    name,
+                    order: SortOrder::Ascending,
+                },
+            });
+            self.layout.sort = None;
+            self.layout.score_sort = None;
+
+            resort = true;
+        }
+
+        if let Some(column) = clicked_score_column {
+            self.layout.score_sort = Some(match self.layout.score_sort {
+                Some(sort) if sort.column == column => ScoreSortKey {
+                    column,
+                    order: sort.order.reversed(),
+                },
+                _ => ScoreSortKey::new(column),
+            });
+            self.layout.sort = None;
+            self.layout.custom_sort = None;
+
+            resort = true;
+        }
+
+        if let Some(layout) = new_layout {
+            self.layout = layout;
+        }
+
+        // Keep the selected beatmap in view once its position changes
+        if resort {
+            let rows = std::mem::take(&mut self.rows);
+            self.set_rows(beatmaps, rows);
+            self.scroll_to = selection
+                .primary
+                .map(|index| (index, Some(egui::Align::Center)));
+        }
+
+        action
+    }
+
+    /// Renders a beatmap's value for a score column. Beatmaps without any scores in the current mode are left blank.
+    fn score_cell(&self, ui: &mut egui::Ui, column: ScoreColumn, summary: Option<&ScoreSummary>) {
+        match column.value(summary) {
+            ColumnValue::Empty => {}
+            ColumnValue::Grade(grade) => {
+                grade_badge(ui, grade);
+            }
+            ColumnValue::Float(accuracy) => {
+                ui.label(format!("{:.2}%", accuracy));
+            }
+            value => {
+                ui.label(self.format.text(Column::LastPlayed, &value));
+            }
+        }
+    }
+
+    /// Renders the column chooser, e.g. inside a menu.
+    pub fn column_menu(&mut self, ui: &mut egui::Ui) {
+        column_chooser(ui, &mut self.layout, &self.custom);
+    }
+
+    /// Renders a beatmapset's row, which summarises its shown difficulties. Clicking the row expands or collapses the
+    /// set, by setting `toggled` to its folder name, and right clicking it selects the set's difficulties.
+    fn set_row(
+        &self,
+        row: &mut egui_extras::TableRow,
+        group: SetGroup,
+        beatmaps: &[BeatmapEntry],
+        selection: &mut Selection,
+        menu: RowMenu,
+        toggled: &mut Option<String>,
+    ) -> Option<RowAction> {
+        let set = &self.sets[group.set];
+        let folder = set.folder_name.as_deref().unwrap_or_default();
+        let difficulties = &self.rows[group.start..group.start + group.len];
+        let expanded = self.expanded.contains(folder);
+        let mut action = None;
+
+        row.set_selected(
+            difficulties
+                .iter()
+                .all(|index| selection.beatmaps.contains(index)),
+        );
+
+        for (i, &column) in self.layout.columns.iter().enumerate() {
+            row.col(|ui| {
+                if i == 0 {
+                    ui.label(if expanded { "⏷" } else { "⏵" });
+                }
+
+                if column == Column::Difficulty {
+                    difficulty_count(ui, difficulties.len(), set.beatmaps.len());
+                    return;
+                }
+
+                match self.layout.set_value(column, difficulties, beatmaps) {
+                    SetValue::Value(ColumnValue::Grade(grade)) => {
+                        grade_badge(ui, grade);
+                    }
+                    SetValue::Value(value) => {
+                        ui.strong(self.format.text(column, &value));
+                    }
+                    SetValue::Range(lowest, highest) => {
+                        ui.strong(format!(
+                            "{} – {}",
+                            self.format.text(column, &lowest),
+                            self.format.text(column, &highest)
+                        ));
+                    }
+                }
+            });
+        }
+
+        for column in self.shown_custom_columns() {
+            row.col(|ui| {
+                let values = difficulties
+                    .iter()
+                    .filter_map(|&index| self.custom_value(column, index));
+
+                let Some((lowest, highest)) = values.fold(None, |range, value| match range {
+                    Some((lowest, highest)) => {
+                        Some((f64::min(lowest, value), f64::max(highest, value)))
+                    }
+                    None => Some((value, value)),
+                }) else {
+                    return;
+                };
+
+                if lowest == highest {
+                    ui.strong(ColumnValue::Float(lowest).to_string());
+                } else {
+                    ui.strong(format!(
+                        "{} – {}",
+                        ColumnValue::Float(lowest),
+                        ColumnValue::Float(highest)
+                    ));
+                }
+            });
+        }
+
+        // Difficulties can have different scores, be in different collections and have different notes, so these are
+        // only shown on their own rows
+        for _ in self.shown_score_columns() {
+            row.col(|_| {});
+        }
+
+        if self.layout.show_collections && menu.collections.is_some() {
+            row.col(|_| {});
+        }
+
+        if self.layout.show_notes && menu.notes.is_some() {
+            row.col(|_| {});
+        }
+
+        let response = row.response();
+
+        if response.clicked() {
+            *toggled = Some(folder.to_string());
+        }
+
+        // Right clicking a set acts on all of its shown difficulties
+        if response.secondary_clicked()
+            && !difficulties
+                .iter()
+                .all(|index| selection.beatmaps.contains(index))
+        {
+            selection.primary = difficulties.first().copied();
+            selection.anchor = selection.primary;
+            selection.beatmaps = difficulties.iter().copied().collect();
+        }
+
+        response.context_menu(|ui| {
+            action = row_context_menu(ui, &self.layout, beatmaps, selection, menu, None);
+        });
+
+        action
+    }
+}
+
+/// Renders a row's context menu, returning any action requested. `details` is the beatmap to show details for, if
+/// the row is for a single beatmap.
+fn row_context_menu(
+    ui: &mut egui::Ui,
+    layout: &TableLayout,
+    beatmaps: &[BeatmapEntry],
+    selection: &Selection,
+    menu: RowMenu,
+    details: Option<usize>,
+) -> Option<RowAction> {
+    let mut action = None;
+
+    if let Some(index) = details {
+        if ui.button("Details").clicked() {
+            action = Some(RowAction::ShowDetails(index));
+            ui.close_menu();
+        }
+
+        if let Some(pins) = menu.pins {
+            let pinned = pins.is_pinned(&beatmaps[index]);
+            let label = if pinned {
+                "Unpin"
+            } else {
+                "Pin for Comparison"
+            };
+
+            if ui
+                .add_enabled(pinned || !pins.is_full(), egui::Button::new(label))
+                .on_disabled_hover_text("Unpin a beatmap to make room for another")
+                .clicked()
+            {
+                action = Some(RowAction::TogglePin(index));
+                ui.close_menu();
+            }
+        }
+
+        ui.separator();
+    }
+
+    if let Some(menu_action) = beatmap_actions(ui, layout, beatmaps, selection, menu) {
+        action = Some(menu_action);
+    }
+
+    if let Some(collections) = menu.collections {
+        ui.separator();
+
+        ui.menu_button("Add to Collection", |ui| {
+            if ui.button("New Collection...").clicked() {
+                action = Some(RowAction::AddToCollection(None));
+                ui.close_menu();
+            }
+
+            if !collections.names.is_empty() {
+                ui.separator();
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (i, name) in collections.names.iter().enumerate() {
+                    if ui.button(name).clicked() {
+                        action = Some(RowAction::AddToCollection(Some(i)));
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+    }
+
+    if menu.removable {
+        ui.separator();
+
+        if ui.button("Remove from Collection").clicked() {
+            action = Some(RowAction::RemoveFromCollection);
+            ui.close_menu();
+        }
+    }
+
+    action
+}
+
+/// Renders the collections containing a beatmap, abbreviated to fit the column with the full list in a tooltip.
+/// Clicking the cell shows a popup for adding the beatmap to or removing it from each collection.
+fn collection_cell(
+    ui: &mut egui::Ui,
+    membership: &CollectionMembership,
+    beatmap: &BeatmapEntry,
+    index: usize,
+) -> Option<RowAction> {
+    let contained = membership.of(beatmap);
+    let names = contained
+        .iter()
+        .map(|&i| membership.names[i].as_str())
+        .collect::<Vec<_>>();
+
+    let label = if names.is_empty() {
+        egui::RichText::new("–").weak()
+    } else {
+        let mut text = names[..names.len().min(SHOWN_COLLECTIONS)].join(", ");
+
+        if names.len() > SHOWN_COLLECTIONS {
+            text.push_str(&format!(" +{}", names.len() - SHOWN_COLLECTIONS));
+        }
+
+        egui::RichText::new(text)
+    };
+
+    let mut response = ui.add(egui::Button::new(label).frame(false));
+
+    if !names.is_empty() {
+        response = response.on_hover_text(names.join("\n"));
+    }
+
+    let popup_id = ui.make_persistent_id(("collections_popup", index));
+
+    if response.clicked() {
+        ui.memory_mut(|memory| memory.toggle_popup(popup_id));
+    }
+
+    let mut action = None;
+
+    egui::popup_below_widget(
+        ui,
+        popup_id,
+        &response,
+        egui::PopupCloseBehavior::CloseOnClickOutside,
+        |ui| {
+            ui.set_min_width(150.0);
+
+            if membership.names.is_empty() {
+                ui.weak("No collections");
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for (i, name) in membership.names.iter().enumerate() {
+                        let mut checked = contained.contains(&i);
+
+                        if ui.checkbox(&mut checked, name).changed() {
+                            action = Some(RowAction::ToggleCollection {
+                                index,
+                                collection: i,
+                            });
+                        }
+                    }
+                });
+        },
+    );
+
+    action
+}
+
+/// Renders a beatmap's star and the first line of its note, with the full note in a tooltip. Clicking the star toggles
+/// it.
+fn note_cell(ui: &mut egui::Ui, note: Option<&Note>, index: usize) -> Option<RowAction> {
+    let starred = note.is_some_and(|note| note.starred);
+    let mut action = None;
+
+    let star = if starred {
+        egui::RichText::new("★").color(ui.visuals().warn_fg_color)
+    } else {
+        egui::RichText::new("☆").weak()
+    };
+
+    if ui
+        .add(egui::Button::new(star).frame(false))
+        .on_hover_text(if starred { "Unstar" } else { "Star" })
+        .clicked()
+    {
+        action = Some(RowAction::ToggleStar(index));
+    }
+
+    if let Some(text) = note
+        .map(|note| note.text.trim())
+        .filter(|text| !text.is_empty())
+    {
+        ui.label(text.lines().next().unwrap_or_default())
+            .on_hover_text(text);
+    }
+
+    action
+}
+
+/// Renders the number of difficulties in a beatmapset's row. If the search only matches some of them, a badge shows
+/// how many match instead.
+fn difficulty_count(ui: &mut egui::Ui, shown: usize, total: usize) {
+    if shown == total {
+        ui.strong(match total {
+            1 => "1 difficulty".to_string(),
+            total => format!("{} difficulties", total),
+        });
+    } else {
+        egui::Frame::none()
+            .fill(ui.visuals().selection.bg_fill)
+            .rounding(4.0)
+            .inner_margin(egui::Margin::symmetric(4.0, 0.0))
+            .show(ui, |ui| {
+                ui.label(
+                    egui::RichText::new(format!("{} of {} match", shown, total))
+                        .color(ui.visuals().selection.stroke.color),
+                );
+            });
+    }
+}
+
+/// Renders the context menu items for opening and copying the selected beatmaps, returning any action that needs to
+/// be handled by the view. Items are disabled with an explanation when none of the beatmaps have the data they need.
+fn beatmap_actions(
+    ui: &mut egui::Ui,
+    layout: &TableLayout,
+    beatmaps: &[BeatmapEntry],
+    selection: &Selection,
+    menu: RowMenu,
+) -> Option<RowAction> {
+    let context = selection.action_context(beatmaps, &menu);
+    let id = actions::menu(ui, Menu::Beatmaps, &context, true)?;
+
+    let selected = selection.selected(beatmaps).collect::<Vec<_>>();
+    if let Some(text) = copy_text(layout, id, &selected) {
+        ui.ctx().copy_text(text);
+    }
+
+    RowAction::from_action(id)
+}
+
+/// Gets the text that a copy action puts on the clipboard, with a line for each beatmap that has what's copied.
+fn copy_text(layout: &TableLayout, id: ActionId, selected: &[&BeatmapEntry]) -> Option<String> {
+    let lines = match id {
+        ActionId::CopyMd5 => selected
+            .iter()
+            .filter_map(|beatmap| beatmap.md5.clone())
+            .collect::<Vec<_>>(),
+        ActionId::CopyBeatmapId => selected
+            .iter()
+            .filter_map(|beatmap| beatmap.difficulty_id())
+            .map(|id| id.to_string())
+            .collect(),
+        ActionId::CopyMarkdownRow => selected
+            .iter()
+            .map(|beatmap| layout.markdown_row(beatmap))
+            .collect(),
+        _ => return None,
+    };
+
+    Some(lines.join("\n"))
+}
+
+/// Renders checkboxes for toggling each column, with the visible columns first so that they can be dragged to
+/// reorder them.
+fn column_chooser(ui: &mut egui::Ui, layout: &mut TableLayout, custom: &[CustomColumn]) {
+    ui.weak("Drag ☰ to reorder columns");
+
+    let mut moved = None;
+    let mut hidden = None;
+
+    for (i, &column) in layout.columns.iter().enumerate() {
+        let response = ui
+            .horizontal(|ui| {
+                ui.dnd_drag_source(ui.id().with(("b_column", column)), i, |ui| {
+                    ui.label("☰");
+                });
+
+                let mut visible = true;
+
+                // Always keep at least one column visible
+                ui.add_enabled_ui(layout.columns.len() > 1, |ui| {
+                    if ui.checkbox(&mut visible, column.header()).changed() {
+                        hidden = Some(i);
+                    }
+                });
+            })
+            .response;
+
+        if let Some(from) = response.dnd_release_payload::<usize>() {
+            moved = Some((*from, i));
+        }
+    }
+
+    ui.separator();
+
+    let mut shown = None;
+
+    for column in Column::ALL {
+        if !layout.columns.contains(&column) {
+            let mut visible = false;
+
+            if ui.checkbox(&mut visible, column.header()).changed() {
+                shown = Some(column);
+            }
+        }
+    }
+
+    ui.separator();
+
+    let mut toggled_custom_column = None;
+
+    for column in custom {
+        let mut visible = layout.custom_columns.contains(&column.name);
+
+        if ui
+            .checkbox(&mut visible, &column.name)
+            .on_hover_text(column.expr.to_string())
+            .changed()
+        {
+            toggled_custom_column = Some(column.name.clone());
+        }
+    }
+
+    if custom.is_empty() {
+        ui.weak("Add custom columns in the settings");
+    }
+
+    if let Some(name) = toggled_custom_column {
+        if layout.custom_columns.contains(&name) {
+            layout.custom_columns.retain(|n| *n != name);
+
+            if layout
+                .custom_sort
+                .as_ref()
+                .is_some_and(|sort| sort.name == name)
+            {
+                layout.custom_sort = None;
+            }
+        } else {
+            layout.custom_columns.push(name);
+        }
+    }
+
+    ui.separator();
+
+    ui.checkbox(&mut layout.show_collections, "Collections")
+        .on_hover_text(
+            "Show which collections contain each beatmap, while a collection.db is loaded",
+        );
+
+    ui.checkbox(&mut layout.show_notes, "Notes")
+        .on_hover_text("Show your notes and stars for each beatmap");
+
+    ui.separator();
+
+    let mut toggled_score_column = None;
+
+    for column in ScoreColumn::ALL {
+        let mut visible = layout.score_columns.contains(&column);
+
+        if ui
+            .checkbox(&mut visible, column.header())
+            .on_hover_text(
+                "Shown while a scores.db is loaded, using the mode chosen in the filter bar or each beatmap's own mode",
+            )
+            .changed()
+        {
+            toggled_score_column = Some(column);
+        }
+    }
+
+    if let Some(column) = toggled_score_column {
+        if layout.score_columns.contains(&column) {
+            layout.score_columns.retain(|&c| c != column);
+
+            if layout.score_sort.is_some_and(|sort| sort.column == column) {
+                layout.score_sort = None;
+            }
+        } else {
+            layout.score_columns.push(column);
+        }
+    }
+
+    if let Some((from, to)) = moved {
+        let column = layout.columns.remove(from);
+        layout.columns.insert(to, column);
+    }
+
+    if let Some(i) = hidden {
+        let column = layout.columns.remove(i);
+
+        // Stop sorting by columns that can't be seen
+        if layout.sort.is_some_and(|sort| sort.column == column) {
+            layout.sort = None;
+        }
+    }
+
+    if let Some(column) = shown {
+        layout.columns.push(column);
+    }
+}
+
+/// Gets a reasonable initial width for a column.
+fn initial_width(column: Column) -> f32 {
+    match column {
+        Column::Artist
+        | Column::ArtistUnicode
+        | Column::Title
+        | Column::TitleUnicode
+        | Column::Source
+        | Column::Tags
+        | Column::Folder
+        | Column::Url => 200.0,
+        Column::Difficulty | Column::Creator | Column::Md5 => 120.0,
+        Column::LastPlayed | Column::LastModified => 140.0,
+        _ => 70.0,
+    }
+}
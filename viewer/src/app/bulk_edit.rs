@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use osu_db_parser::prelude::*;
+
+use super::format_count;
+use crate::widgets::file_dialog::FileDialog;
+
+/// Name suggested for the edited copy of `osu!.db`.
+const EDITED_FILE_NAME: &str = "osu!.edited.db";
+
+/// A dialog for changing the per-beatmap settings of the selected beatmaps (e.g. the local offset, or whether the video
+/// is shown), then saving a copy of the beatmap listing with the changes.
+///
+/// A copy is saved rather than replacing `osu!.db`, since osu! overwrites it with its own copy when it closes.
+#[derive(Default)]
+pub struct BulkEditDialog {
+    pub visible: bool,
+
+    /// Indices of the beatmaps being edited
+    beatmaps: Vec<usize>,
+
+    offset: OffsetChoice,
+
+    /// Offset to shift by or set, in milliseconds
+    offset_value: i16,
+
+    /// New value for each setting, or `None` to leave it unchanged
+    ignore_hitsounds: Option<bool>,
+    ignore_skin: Option<bool>,
+    disable_storyboard: Option<bool>,
+    disable_video: Option<bool>,
+    visual_override: Option<bool>,
+
+    /// Result of the most recent save, or why it failed
+    status: Option<Result<String, String>>,
+}
+
+/// Represents how the local offset should be changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum OffsetChoice {
+    #[default]
+    Unchanged,
+    Shift,
+    Set,
+}
+
+impl BulkEditDialog {
+    /// Opens the dialog for editing the beatmaps at the given indices.
+    pub fn open(&mut self, beatmaps: impl Iterator<Item = usize>) {
+        self.beatmaps = beatmaps.collect();
+        self.status = None;
+        self.visible = true;
+    }
+
+    /// Gets the changes chosen in the dialog.
+    fn edit(&self) -> BulkEdit {
+        let local_offset = match self.offset {
+            OffsetChoice::Unchanged => None,
+            OffsetChoice::Shift => Some(OffsetEdit::Shift(self.offset_value)),
+            OffsetChoice::Set => Some(OffsetEdit::Set(self.offset_value)),
+        };
+
+        BulkEdit {
+            local_offset,
+            ignore_beatmap_hitsounds: self.ignore_hitsounds,
+            ignore_beatmap_skin: self.ignore_skin,
+            disable_storyboard: self.disable_storyboard,
+            disable_video: self.disable_video,
+            visual_override: self.visual_override,
+            mania_scroll_speed: None,
+        }
+    }
+
+    /// Renders the dialog, saving the edited copy of `listing` when requested. `osu_directory` is where the copy is
+    /// saved by default, and must not contain the `osu!.db` file that gets replaced.
+    pub fn view(
+        &mut self,
+        ctx: &egui::Context,
+        listing: &BeatmapListing,
+        osu_directory: Option<&Path>,
+    ) {
+        let mut visible = self.visible;
+
+        egui::Window::new("Edit Beatmap Settings")
+            .open(&mut visible)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Changing {} selected beatmaps",
+                    format_count(self.beatmaps.len())
+                ));
+
+                egui::Grid::new("bulk_edit_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 8.0])
+                    .show(ui, |ui| {
+                        ui.label("Local offset");
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut self.offset, OffsetChoice::Unchanged, "Unchanged");
+                            ui.selectable_value(&mut self.offset, OffsetChoice::Shift, "Shift by");
+                            ui.selectable_value(&mut self.offset, OffsetChoice::Set, "Set to");
+
+                            if self.offset != OffsetChoice::Unchanged {
+                                ui.add(egui::DragValue::new(&mut self.offset_value).suffix(" ms"));
+                            }
+                        });
+                        ui.end_row();
+
+                        flag_row(ui, "Ignore hitsounds", &mut self.ignore_hitsounds);
+                        flag_row(ui, "Ignore skin", &mut self.ignore_skin);
+                        flag_row(ui, "Disable storyboard", &mut self.disable_storyboard);
+                        flag_row(ui, "Disable video", &mut self.disable_video);
+                        flag_row(ui, "Visual override", &mut self.visual_override);
+                    });
+
+                ui.weak("A copy of osu!.db is saved with the changes. Replace osu!.db with it while osu! is closed.");
+                ui.separator();
+
+                let edit = self.edit();
+
+                if ui
+                    .add_enabled(!edit.is_empty(), egui::Button::new("Save Edited Copy..."))
+                    .on_disabled_hover_text("Choose a setting to change first")
+                    .clicked()
+                {
+                    self.save(listing, edit, osu_directory);
+                }
+
+                match &self.status {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, message);
+                    }
+                    None => {}
+                }
+            });
+
+        self.visible = visible;
+    }
+
+    /// Asks where to save the edited copy of the beatmap listing, then saves it.
+    fn save(&mut self, listing: &BeatmapListing, edit: BulkEdit, osu_directory: Option<&Path>) {
+        let Some(path) = FileDialog::save_file(osu_directory, EDITED_FILE_NAME) else {
+            return;
+        };
+
+        let original = osu_directory.and_then(|d| std::fs::canonicalize(d.join("osu!.db")).ok());
+        if original.is_some() && std::fs::canonicalize(&path).ok() == original {
+            self.status = Some(Err(
+                "Choose a different file name, so that osu!.db isn't replaced while osu! might be running"
+                    .to_string(),
+            ));
+            return;
+        }
+
+        let mut edited = listing.clone();
+        let changed = edited.bulk_edit_indices(self.beatmaps.iter().copied(), edit);
+
+        self.status = Some(match edited.to_file(&path) {
+            Ok(()) => Ok(format!(
+                "Changed {} of {} beatmaps, and saved them to {}",
+                format_count(changed),
+                format_count(self.beatmaps.len()),
+                path.display()
+            )),
+            Err(e) => {
+                log::error!("Unable to save '{}': {}", path.display(), e);
+                Err(format!("Unable to save: {}", e))
+            }
+        });
+    }
+}
+
+/// Renders a row for choosing whether to turn a setting on or off, or leave it unchanged.
+fn flag_row(ui: &mut egui::Ui, label: &str, value: &mut Option<bool>) {
+    ui.label(label);
+    ui.horizontal(|ui| {
+        ui.selectable_value(value, None, "Unchanged");
+        ui.selectable_value(value, Some(true), "On");
+        ui.selectable_value(value, Some(false), "Off");
+    });
+    ui.end_row();
+}
@@ -1,39 +1,378 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
 
 use egui::Id;
-use osu_db_parser::prelude::*;
+use osu_db_parser::{analysis, prelude::*};
 
+#[cfg(not(target_arch = "wasm32"))]
+use super::backups;
 use super::{
-    beatmap_details::BeatmapDetailsWindow, open_beatmap_in_browser,
+    beatmap_actions::BulkOpener,
+    beatmap_details::BeatmapDetailsWindow,
+    beatmap_table::{BeatmapTable, RowAction, RowMenu, Selection},
+    format_count,
     score_details::ScoreDetailsWindow,
+    settings::Settings,
 };
+#[cfg(not(target_arch = "wasm32"))]
+use crate::widgets::file_dialog::FileDialog;
+
+/// Name given to new collections, before the user renames them.
+const NEW_COLLECTION_NAME: &str = "New Collection";
 
-/// A view for displaying collection listing details.
+/// A view for displaying and editing collections.
 #[derive(Default)]
 pub struct CollectionListingView {
     data: Option<CollectionListing>,
+
+    /// Whether there are changes that haven't been saved yet
+    modified: bool,
+
+    /// Path of the loaded `collection.db` file, which changes are saved to
+    #[cfg(not(target_arch = "wasm32"))]
+    path: Option<PathBuf>,
+
+    /// osu! installation folder, used to locate beatmap folders
+    #[cfg(not(target_arch = "wasm32"))]
+    osu_directory: Option<PathBuf>,
+
+    /// Set when osu! appears to be running, so that the user can confirm whether to save anyway
+    #[cfg(not(target_arch = "wasm32"))]
+    confirm_save: bool,
+
+    /// Set when the collections have been saved, so that the change to the file isn't mistaken for one made by osu!
+    #[cfg(not(target_arch = "wasm32"))]
+    saved: bool,
+
+    /// Result of the most recent save
+    status: Option<String>,
+
+    /// Number of backups of `collection.db` kept when saving, or 0 to not back it up
+    #[cfg(not(target_arch = "wasm32"))]
+    keep_backups: usize,
+
+    /// Beatmap listing used to resolve the beatmaps in each collection
+    listing: Option<Arc<BeatmapListing>>,
+
+    /// Index of each beatmap in `listing`, by MD5 hash
+    beatmap_indices: HashMap<String, usize>,
+
+    /// Number of beatmaps in each collection that were found in `listing`, and the number that weren't
+    counts: Vec<(usize, usize)>,
+
+    /// Collections containing each beatmap, built when first needed and cleared whenever the collections change
+    membership: Option<CollectionMembership>,
+
+    /// Groups of beatmaps from more than one beatmapset that share a song source or tag (e.g. beatmap packs), which
+    /// can be made into collections, along with how many beatmapsets are in each
+    groups: Vec<(Collection, usize)>,
+
+    /// Tag prefixes to group beatmaps by, separated by spaces
+    group_tag_prefixes: String,
+
     selected_collection: Option<usize>,
-    selected_beatmap_md5: Option<String>,
+
+    /// Collection being renamed, along with the new name
+    renaming: Option<(usize, String)>,
+
+    table: BeatmapTable,
+    selection: Selection,
+    opener: BulkOpener,
 
     beatmap_windows: HashMap<String, BeatmapDetailsWindow>,
     score_windows: HashMap<String, ScoreDetailsWindow>,
 }
 
+/// Represents a request from another view to add beatmaps to a collection.
+pub struct AddToCollection {
+    /// Index of the collection to add to, or `None` to create a new collection
+    pub collection: Option<usize>,
+
+    /// Name for a new collection. If there's no name, a placeholder is used and the collection is renamed straight
+    /// away.
+    pub name: Option<String>,
+
+    /// MD5 hashes of the beatmaps to add
+    pub beatmap_md5s: Vec<String>,
+}
+
+/// Represents a request from another view to remove beatmaps from a collection.
+pub struct RemoveFromCollection {
+    /// Index of the collection to remove from
+    pub collection: usize,
+
+    /// MD5 hashes of the beatmaps to remove
+    pub beatmap_md5s: Vec<String>,
+}
+
+/// Represents a change to the collections requested from another view.
+pub enum CollectionRequest {
+    Add(AddToCollection),
+    Remove(RemoveFromCollection),
+
+    /// Save the collections to the `collection.db` file they were loaded from
+    #[cfg(not(target_arch = "wasm32"))]
+    Save,
+}
+
+/// Collections containing each beatmap, for showing and editing membership outside of the collection listing view.
+#[derive(Debug)]
+pub struct CollectionMembership {
+    /// Name of each collection, in order
+    pub names: Vec<String>,
+
+    /// Indices of the collections containing each beatmap, by MD5 hash
+    collections: HashMap<String, Vec<usize>>,
+
+    /// Whether there are changes that haven't been saved yet
+    pub modified: bool,
+
+    /// Whether changes can be saved, i.e. the collections were loaded from a file
+    pub saveable: bool,
+
+    /// Copy of the collections, which can be shared with a worker thread, e.g. to export them
+    pub listing: Arc<CollectionListing>,
+}
+
+impl CollectionMembership {
+    /// Gets the indices of the collections containing a beatmap.
+    pub fn of(&self, beatmap: &BeatmapEntry) -> &[usize] {
+        beatmap
+            .md5
+            .as_ref()
+            .and_then(|md5| self.collections.get(md5))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Gets the request that adds a beatmap to a collection, or removes it if the collection already contains it.
+    pub fn toggle(&self, beatmap: &BeatmapEntry, collection: usize) -> Option<CollectionRequest> {
+        let md5 = beatmap.md5.clone()?;
+
+        Some(if self.of(beatmap).contains(&collection) {
+            CollectionRequest::Remove(RemoveFromCollection {
+                collection,
+                beatmap_md5s: vec![md5],
+            })
+        } else {
+            CollectionRequest::Add(AddToCollection {
+                collection: Some(collection),
+                name: None,
+                beatmap_md5s: vec![md5],
+            })
+        })
+    }
+}
+
 impl CollectionListingView {
-    /// Loads a collection listing into this view.
+    /// Loads a collection listing into this view, discarding any unsaved changes.
     pub fn load_collection_listing(&mut self, collection_listing: CollectionListing) {
         self.data = Some(collection_listing);
+        self.modified = false;
+        self.status = None;
         self.selected_collection = None;
-        self.selected_beatmap_md5 = None;
+        self.renaming = None;
+        self.selection.clear();
+        self.refresh();
+    }
+
+    /// Applies the settings that affect how beatmaps are shown.
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        let beatmaps = self
+            .listing
+            .as_deref()
+            .map(|listing| listing.beatmaps.as_slice())
+            .unwrap_or_default();
+
+        self.table.set_display(
+            beatmaps,
+            settings.metadata_language(),
+            settings.star_policy,
+            settings.value_format(),
+        );
+        self.table
+            .set_custom_columns(beatmaps, settings.custom_columns());
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.keep_backups = settings.keep_backups;
+        }
+    }
+
+    /// Sets the path that changes are saved to.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_path(&mut self, path: Option<PathBuf>) {
+        self.path = path;
+    }
+
+    /// Gets the path of the loaded `collection.db` file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Sets the osu! installation folder, which is used to locate beatmap folders.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_osu_directory(&mut self, osu_directory: Option<PathBuf>) {
+        self.osu_directory = osu_directory;
+    }
+
+    /// Checks whether there are changes that haven't been saved yet.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Gets the path that the collections were saved to, if they've been saved since this was last called.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn take_saved(&mut self) -> Option<&Path> {
+        if std::mem::take(&mut self.saved) {
+            self.path.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Gets the collections containing each beatmap, if a collection listing is loaded.
+    pub fn membership(&mut self) -> Option<&CollectionMembership> {
+        let collection_listing = self.data.as_ref()?;
+
+        let membership = self.membership.get_or_insert_with(|| {
+            let mut collections = HashMap::<String, Vec<usize>>::new();
+
+            for (i, collection) in collection_listing.collections.iter().enumerate() {
+                for md5 in collection.beatmap_md5s.iter().flatten() {
+                    let indices = collections.entry(md5.clone()).or_default();
+
+                    // Collections can list the same beatmap more than once
+                    if indices.last() != Some(&i) {
+                        indices.push(i);
+                    }
+                }
+            }
+
+            CollectionMembership {
+                names: collection_listing
+                    .collections
+                    .iter()
+                    .map(|c| c.name.clone().unwrap_or_default())
+                    .collect(),
+                collections,
+                modified: false,
+                saveable: false,
+                listing: Arc::new(collection_listing.clone()),
+            }
+        });
+
+        membership.modified = self.modified;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            membership.saveable = self.path.is_some();
+        }
+
+        Some(membership)
+    }
+
+    /// Adds beatmaps to a collection, skipping any that are already in it.
+    ///
+    /// Returns `true` if a new collection was created, in which case it is selected so that it can be renamed.
+    pub fn add_beatmaps(&mut self, request: AddToCollection) -> bool {
+        let Some(collection_listing) = &mut self.data else {
+            return false;
+        };
+
+        let index = match request.collection {
+            Some(index) if index < collection_listing.collections.len() => index,
+            Some(_) => return false,
+            None => {
+                let name = unique_name(
+                    collection_listing,
+                    request.name.as_deref().unwrap_or(NEW_COLLECTION_NAME),
+                );
+
+                collection_listing.collections.push(Collection {
+                    name: Some(name.clone()),
+                    beatmap_md5s: Vec::new(),
+                });
+
+                let index = collection_listing.collections.len() - 1;
+                self.selected_collection = Some(index);
+
+                if request.name.is_none() {
+                    self.renaming = Some((index, name));
+                }
+
+                index
+            }
+        };
+
+        let collection = &mut collection_listing.collections[index];
+
+        for md5 in request.beatmap_md5s {
+            if !collection
+                .beatmap_md5s
+                .iter()
+                .any(|m| m.as_ref() == Some(&md5))
+            {
+                collection.beatmap_md5s.push(Some(md5));
+            }
+        }
+
+        self.modified = true;
+        self.refresh();
+
+        request.collection.is_none()
     }
 
-    /// Renders the collection listing view using the specified beatmap listing details.
+    /// Removes beatmaps from a collection, including any duplicate entries for them.
+    pub fn remove_beatmaps(&mut self, request: RemoveFromCollection) {
+        let Some(collection) = self
+            .data
+            .as_mut()
+            .and_then(|c| c.collections.get_mut(request.collection))
+        else {
+            return;
+        };
+
+        let before = collection.beatmap_md5s.len();
+
+        collection.beatmap_md5s.retain(|md5| {
+            !md5.as_ref()
+                .is_some_and(|md5| request.beatmap_md5s.contains(md5))
+        });
+
+        if collection.beatmap_md5s.len() != before {
+            self.modified = true;
+            self.selection.clear();
+            self.refresh();
+        }
+    }
+
+    /// Saves the collections, first asking the user to confirm if osu! appears to be running.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn request_save(&mut self) {
+        if osu_appears_to_be_running() {
+            self.confirm_save = true;
+        } else {
+            self.save();
+        }
+    }
+
+    /// Renders the collection listing view, resolving beatmaps using the specified beatmap listing.
     pub fn view(
         &mut self,
         ctx: &egui::Context,
-        beatmaps: &HashMap<String, BeatmapEntry>,
+        listing: Option<&Arc<BeatmapListing>>,
         scores: &HashMap<String, Vec<ScoreReplay>>,
     ) {
+        self.set_beatmap_listing(listing);
+
         // Unload any closed windows
         self.beatmap_windows.retain(|_, w| w.visible);
         self.score_windows.retain(|_, w| w.visible);
@@ -47,16 +386,26 @@ impl CollectionListingView {
             score_window.view(ctx);
         }
 
+        self.opener.view(ctx);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.confirm_save_window(ctx);
+
+        let selected_md5 = self
+            .listing
+            .as_ref()
+            .zip(self.selection.primary)
+            .and_then(|(listing, i)| listing.beatmaps.get(i))
+            .and_then(|beatmap| beatmap.md5.as_ref());
+
         // Render the left panel showing scores for the selected beatmap
-        egui::SidePanel::left("b_beatmap_scores").show_animated(
+        egui::SidePanel::left("c_beatmap_scores").show_animated(
             ctx,
-            self.selected_beatmap_md5.is_some(),
+            selected_md5.is_some(),
             |ui| {
                 ui.heading("Local Scores");
 
-                if let Some(scores) = &self
-                    .selected_beatmap_md5
-                    .as_ref()
+                if let Some(scores) = &selected_md5
                     .and_then(|md5| scores.get(md5))
                     .filter(|beatmap_scores| !beatmap_scores.is_empty())
                 {
@@ -69,105 +418,667 @@ impl CollectionListingView {
 
         // Render the central panel showing collections + beatmaps
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Collection Listing");
+            ui.heading("Collections");
+
+            if self.data.is_none() {
+                ui.label("No collection listing loaded...");
+                return;
+            }
+
+            self.toolbar(ui);
+            ui.separator();
+
+            egui::SidePanel::left("c_collection_list")
+                .resizable(true)
+                .default_width(250.0)
+                .show_inside(ui, |ui| {
+                    egui::TopBottomPanel::bottom("c_groups")
+                        .resizable(true)
+                        .show_inside(ui, |ui| self.group_list(ui));
+
+                    self.collection_list(ui);
+                });
+
+            egui::CentralPanel::default().show_inside(ui, |ui| self.collection_contents(ui));
+        });
+    }
+
+    /// Updates the beatmap listing used to resolve beatmaps, if it has changed.
+    fn set_beatmap_listing(&mut self, listing: Option<&Arc<BeatmapListing>>) {
+        let unchanged = match (&self.listing, listing) {
+            (Some(current), Some(new)) => Arc::ptr_eq(current, new),
+            (None, None) => true,
+            _ => false,
+        };
+
+        if !unchanged {
+            self.listing = listing.cloned();
+            self.beatmap_indices = self
+                .listing
+                .iter()
+                .flat_map(|listing| listing.beatmaps.iter().enumerate())
+                .filter_map(|(i, b)| b.md5.clone().map(|md5| (md5, i)))
+                .collect();
+
+            let beatmaps = self
+                .listing
+                .as_deref()
+                .map(|listing| listing.beatmaps.as_slice())
+                .unwrap_or_default();
+            self.table.update_custom_values(beatmaps);
+
+            self.selection.clear();
+            self.refresh();
+            self.refresh_groups();
+        }
+    }
 
+    /// Finds the groups of beatmaps that share a song source or one of the tag prefixes.
+    fn refresh_groups(&mut self) {
+        let Some(listing) = &self.listing else {
+            self.groups.clear();
+            return;
+        };
+
+        let prefixes = self
+            .group_tag_prefixes
+            .split_whitespace()
+            .collect::<Vec<_>>();
+        let tag_groups = if prefixes.is_empty() {
+            Vec::new()
+        } else {
+            analysis::group_by_tag_prefix(listing, &prefixes)
+        };
+
+        // Every beatmapset would otherwise be a group of its own
+        self.groups = analysis::group_by_source(listing)
+            .into_iter()
+            .chain(tag_groups)
+            .map(|group| (group.to_collection(), group.beatmapset_count()))
+            .filter(|(_, beatmapsets)| *beatmapsets > 1)
+            .collect();
+    }
+
+    /// Recalculates the beatmap counts for each collection, and the rows shown for the selected collection.
+    fn refresh(&mut self) {
+        self.membership = None;
+
+        let Some(collection_listing) = &self.data else {
+            self.counts.clear();
+            return;
+        };
+
+        self.counts = collection_listing
+            .collections
+            .iter()
+            .map(|collection| {
+                let found = collection
+                    .beatmap_md5s
+                    .iter()
+                    .filter(|md5| self.resolve(md5).is_some())
+                    .count();
+
+                (found, collection.beatmap_md5s.len() - found)
+            })
+            .collect();
+
+        let rows = self
+            .selected_collection
+            .and_then(|i| collection_listing.collections.get(i))
+            .map(|collection| {
+                collection
+                    .beatmap_md5s
+                    .iter()
+                    .filter_map(|md5| self.resolve(md5))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if let Some(listing) = &self.listing {
+            self.table.set_rows(&listing.beatmaps, rows);
+        }
+    }
+
+    /// Finds the index of a beatmap in the beatmap listing.
+    fn resolve(&self, md5: &OsuString) -> Option<usize> {
+        md5.as_ref()
+            .and_then(|md5| self.beatmap_indices.get(md5))
+            .copied()
+    }
+
+    /// Renders the buttons for creating collections and saving changes.
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
             if let Some(collection_listing) = &self.data {
-                // Version Details
-                ui.horizontal(|ui| {
-                    ui.label("Version");
-                    ui.label(collection_listing.version.to_string());
+                ui.label(format!("Version {}", collection_listing.version));
+                ui.separator();
+            }
+
+            if ui.button("New Collection").clicked() {
+                self.add_beatmaps(AddToCollection {
+                    collection: None,
+                    name: None,
+                    beatmap_md5s: Vec::new(),
                 });
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui
+                .button("Import Text...")
+                .on_hover_text("Add a collection from a text file with one MD5 hash on each line")
+                .clicked()
+            {
+                self.import_text();
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui
+                .add_enabled(
+                    self.modified && self.path.is_some(),
+                    egui::Button::new("Save"),
+                )
+                .clicked()
+            {
+                self.request_save();
+            }
+
+            if self.modified {
+                ui.colored_label(ui.visuals().warn_fg_color, "Unsaved changes");
+            } else if let Some(status) = &self.status {
+                ui.label(status);
+            }
+        });
+    }
+
+    /// Renders the list of collections, along with how many of their beatmaps could be found.
+    fn collection_list(&mut self, ui: &mut egui::Ui) {
+        let Some(collection_listing) = &mut self.data else {
+            return;
+        };
+
+        let mut selected = None;
+        let mut renamed = None;
+        let mut deleted = None;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut exported = None;
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (i, collection) in collection_listing.collections.iter().enumerate() {
+                    // Show a text box in place of the collection being renamed
+                    if let Some((renaming, name)) = self.renaming.as_mut().filter(|(r, _)| *r == i)
+                    {
+                        let response = ui.text_edit_singleline(name);
+
+                        if response.lost_focus() {
+                            renamed = Some((*renaming, name.clone()));
+                        } else {
+                            response.request_focus();
+                        }
+
+                        continue;
+                    }
+
+                    let (found, missing) = self.counts.get(i).copied().unwrap_or_default();
+                    let mut label = format!(
+                        "{} ({})",
+                        collection.name.as_deref().unwrap_or_default(),
+                        format_count(found)
+                    );
+
+                    if missing > 0 {
+                        label.push_str(&format!(", {} missing", format_count(missing)));
+                    }
+
+                    let response = ui.selectable_label(self.selected_collection == Some(i), label);
+
+                    if response.clicked() {
+                        selected = Some(i);
+                    }
+
+                    response.context_menu(|ui| {
+                        if ui.button("Rename").clicked() {
+                            self.renaming = Some((i, collection.name.clone().unwrap_or_default()));
+                            ui.close_menu();
+                        }
+
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if ui.button("Export as Text...").clicked() {
+                            exported = Some(i);
+                            ui.close_menu();
+                        }
+
+                        if ui.button("Delete").clicked() {
+                            deleted = Some(i);
+                            ui.close_menu();
+                        }
+                    });
+                }
+            });
+
+        if let Some((i, name)) = renamed {
+            let name = name.trim();
+
+            if !name.is_empty() && collection_listing.collections[i].name.as_deref() != Some(name) {
+                collection_listing.collections[i].name = Some(name.to_string());
+                self.modified = true;
+                self.membership = None;
+            }
+
+            self.renaming = None;
+        }
+
+        if let Some(i) = deleted {
+            collection_listing.collections.remove(i);
+            self.modified = true;
+            self.renaming = None;
+
+            self.selected_collection = match self.selected_collection {
+                Some(selected) if selected == i => None,
+                Some(selected) if selected > i => Some(selected - 1),
+                selected => selected,
+            };
+        }
+
+        if let Some(i) = selected {
+            self.selected_collection = Some(i);
+            self.selection.clear();
+        }
+
+        if selected.is_some() || deleted.is_some() {
+            self.refresh();
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(i) = exported {
+            self.export_text(i);
+        }
+    }
+
+    /// Asks for a text file with one MD5 hash on each line, and adds it as a new collection named after the file.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn import_text(&mut self) {
+        let Some(path) = FileDialog::pick_file(None, "Text files", &["txt"]) else {
+            return;
+        };
+
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let collection = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Collection::from_text(name, &text).map_err(|e| e.to_string()));
+
+        match collection {
+            Ok(collection) => {
+                let count = collection.beatmap_md5s.len();
+
+                self.add_beatmaps(AddToCollection {
+                    collection: None,
+                    name: collection.name,
+                    beatmap_md5s: collection.beatmap_md5s.into_iter().flatten().collect(),
+                });
+                self.status = Some(format!("Imported {} beatmaps", format_count(count)));
+            }
+            Err(e) => {
+                log::warn!("Unable to import '{}': {}", path.display(), e);
+                self.status = Some(format!("Unable to import: {}", e));
+            }
+        }
+    }
+
+    /// Asks where to save a collection as text, with one MD5 hash on each line followed by the beatmap's name.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_text(&mut self, index: usize) {
+        let Some(collection) = self
+            .data
+            .as_ref()
+            .and_then(|collection_listing| collection_listing.collections.get(index))
+        else {
+            return;
+        };
+
+        // Collection names can contain characters that aren't allowed in file names
+        let file_name = format!(
+            "{}.txt",
+            collection
+                .name
+                .as_deref()
+                .unwrap_or_default()
+                .replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_")
+        );
+
+        let Some(path) = FileDialog::save_file(None, &file_name) else {
+            return;
+        };
+
+        let result = File::create(&path)
+            .and_then(|file| collection.write_text(self.listing.as_deref(), BufWriter::new(file)));
+
+        self.status = Some(match result {
+            Ok(()) => format!("Exported to {}", path.display()),
+            Err(e) => {
+                log::error!("Unable to export '{}': {}", path.display(), e);
+                format!("Unable to export: {}", e)
+            }
+        });
+    }
+
+    /// Renders the groups of beatmaps sharing a song source or tag, each of which can be made into a collection.
+    fn group_list(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Groups");
+
+        ui.horizontal(|ui| {
+            ui.label("Tag prefixes");
+
+            let response = ui
+                .text_edit_singleline(&mut self.group_tag_prefixes)
+                .on_hover_text("Also group beatmaps by tags starting with these, e.g. \"owc mwc\"");
+
+            if response.lost_focus() {
+                self.refresh_groups();
+            }
+        });
+
+        if self.groups.is_empty() {
+            ui.label("No beatmaps from different beatmapsets share a song source or tag");
+            return;
+        }
+
+        let mut made = None;
 
-                // Available Collections
-                egui::ComboBox::from_id_salt("available_collections")
-                    .width(ui.available_width())
-                    .selected_text(
-                        self.selected_collection
-                            .and_then(|i| collection_listing.collections.get(i))
-                            .and_then(|collection| collection.name.as_ref().map(|n| n.as_str()))
-                            .unwrap_or_else(|| "Select collection..."),
-                    )
-                    .show_ui(ui, |ui| {
-                        for (i, collection) in collection_listing.collections.iter().enumerate() {
-                            ui.selectable_value(
-                                &mut self.selected_collection,
-                                Some(i),
-                                collection.name.clone().unwrap_or_default(),
-                            );
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (i, (collection, beatmapsets)) in self.groups.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .small_button("➕")
+                            .on_hover_text("Make a collection from these beatmaps")
+                            .clicked()
+                        {
+                            made = Some(i);
                         }
+
+                        ui.label(format!(
+                            "{} ({} beatmapsets)",
+                            collection.name.as_deref().unwrap_or_default(),
+                            format_count(*beatmapsets)
+                        ));
                     });
+                }
+            });
+
+        if let Some((collection, _)) = made.map(|i| self.groups[i].clone()) {
+            self.add_beatmaps(AddToCollection {
+                collection: None,
+                name: collection.name,
+                beatmap_md5s: collection.beatmap_md5s.into_iter().flatten().collect(),
+            });
+        }
+    }
+
+    /// Renders the beatmaps in the selected collection.
+    fn collection_contents(&mut self, ui: &mut egui::Ui) {
+        let Some(index) = self.selected_collection else {
+            ui.label("Select a collection to see its beatmaps");
+            return;
+        };
+
+        let Some(listing) = self.listing.clone() else {
+            ui.label("Open osu.db to see the beatmaps in this collection");
+            return;
+        };
+
+        let (_, missing) = self.counts.get(index).copied().unwrap_or_default();
+        let mut remove_missing = false;
+
+        if missing > 0 {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!(
+                        "⚠ {} beatmaps in this collection aren't in osu.db",
+                        format_count(missing)
+                    ),
+                );
+
+                remove_missing = ui.button("Remove Missing Beatmaps").clicked();
+            });
+        }
+
+        self.membership();
+
+        let menu = RowMenu {
+            collections: self.membership.as_ref(),
+            removable: true,
+            notes: None,
+            pins: None,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            osu_directory: self.osu_directory.as_deref(),
+
+            // Settings are edited from the beatmap listing, which has the whole listing to save
+            #[cfg(not(target_arch = "wasm32"))]
+            editable: false,
+        };
 
-                // Beatmaps in Current Collection
-                if let Some(collection) = self
-                    .selected_collection
-                    .and_then(|i| collection_listing.collections.get(i))
+        let action = self
+            .table
+            .show(ui, &listing.beatmaps, &mut self.selection, menu);
+
+        let removed_md5s = match action {
+            // There's no details panel here, so Enter shows the details window instead
+            Some(RowAction::ShowDetails(i) | RowAction::Activate(i)) => {
+                let beatmap = &listing.beatmaps[i];
+
+                self.beatmap_windows.insert(
+                    beatmap.md5.clone().unwrap_or_default(),
+                    BeatmapDetailsWindow::new(
+                        Id::new("c_beatmap_details").with(i),
+                        beatmap,
+                        self.table.language(),
+                    ),
+                );
+
+                None
+            }
+            Some(RowAction::OpenPages) => {
+                self.opener
+                    .open_pages(self.selection.selected(&listing.beatmaps));
+                None
+            }
+            Some(RowAction::OpenInOsuDirect) => {
+                self.opener
+                    .open_in_osu_direct(self.selection.selected(&listing.beatmaps));
+                None
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(RowAction::OpenFolders) => {
+                if let Some(osu_directory) = &self.osu_directory {
+                    self.opener
+                        .open_folders(osu_directory, self.selection.selected(&listing.beatmaps));
+                }
+
+                None
+            }
+            Some(RowAction::AddToCollection(collection)) => {
+                self.add_beatmaps(AddToCollection {
+                    collection,
+                    name: None,
+                    beatmap_md5s: self.selection.md5s(&listing.beatmaps),
+                });
+
+                None
+            }
+            Some(RowAction::RemoveFromCollection) => Some(self.selection.md5s(&listing.beatmaps)),
+            Some(RowAction::ToggleCollection {
+                index: i,
+                collection,
+            }) => {
+                match self
+                    .membership
+                    .as_ref()
+                    .and_then(|membership| membership.toggle(&listing.beatmaps[i], collection))
                 {
-                    let row_height = ui.text_style_height(&egui::TextStyle::Body);
-
-                    egui::ScrollArea::both()
-                        .auto_shrink([false, false])
-                        .show_rows(
-                            ui,
-                            row_height,
-                            collection.beatmap_md5s.len(),
-                            |ui, row_range| {
-                                // Beatmaps references without an MD5 are invalid - most likely a corrupt DB
-                                for i in row_range {
-                                    if let Some(md5) = collection.beatmap_md5s[i]
-                                        .as_ref()
-                                        .filter(|md5| !md5.is_empty())
-                                    {
-                                        if let Some(beatmap) = beatmaps.get(md5) {
-                                            let name = format!(
-                                                "{} - {} [{}]",
-                                                &beatmap.artist_name.clone().unwrap_or_default(),
-                                                &beatmap.song_title.clone().unwrap_or_default(),
-                                                &beatmap.difficulty.clone().unwrap_or_default()
-                                            );
-
-                                            ui.selectable_value(
-                                                &mut self.selected_beatmap_md5,
-                                                Some(md5.clone()),
-                                                &name,
-                                            )
-                                            .context_menu(|ui| {
-                                                if ui.button("Details").clicked() {
-                                                    self.beatmap_windows.insert(
-                                                        md5.clone(),
-                                                        BeatmapDetailsWindow {
-                                                            id: Id::new("c_beatmap_details")
-                                                                .with(i),
-                                                            title: name,
-                                                            visible: true,
-                                                            data: beatmap.clone(),
-                                                        },
-                                                    );
-
-                                                    ui.close_menu();
-                                                }
-
-                                                if ui.button("View Beatmap Online").clicked() {
-                                                    open_beatmap_in_browser(&beatmap);
-                                                    ui.close_menu();
-                                                }
-                                            });
-                                        } else {
-                                            ui.add_enabled(
-                                                false,
-                                                egui::SelectableLabel::new(
-                                                    false,
-                                                    format!("Unknown (MD5: {})", md5),
-                                                ),
-                                            );
-                                        }
-                                    }
-                                }
-                            },
-                        );
+                    Some(CollectionRequest::Add(request)) => {
+                        self.add_beatmaps(request);
+                    }
+                    Some(CollectionRequest::Remove(request)) => self.remove_beatmaps(request),
+                    _ => {}
                 }
-            } else {
-                ui.label("No collection listing loaded...");
+
+                None
             }
-        });
+            // Notes and pins aren't shown here, so they can't be changed
+            Some(RowAction::ToggleStar(_) | RowAction::TogglePin(_)) | None => None,
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(RowAction::EditSettings) => None,
+        };
+
+        if remove_missing || removed_md5s.is_some() {
+            let removed_md5s = removed_md5s.unwrap_or_default();
+            let beatmap_indices = &self.beatmap_indices;
+
+            if let Some(collection) = self
+                .data
+                .as_mut()
+                .and_then(|c| c.collections.get_mut(index))
+            {
+                collection.beatmap_md5s.retain(|md5| match md5 {
+                    Some(md5) if removed_md5s.contains(md5) => false,
+                    md5 => {
+                        !remove_missing
+                            || md5
+                                .as_ref()
+                                .is_some_and(|md5| beatmap_indices.contains_key(md5))
+                    }
+                });
+            }
+
+            self.modified = true;
+            self.selection.clear();
+            self.refresh();
+        }
+    }
+
+    /// Renders a window asking the user to confirm saving while osu! is running.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn confirm_save_window(&mut self, ctx: &egui::Context) {
+        if !self.confirm_save {
+            return;
+        }
+
+        egui::Window::new("osu! is running")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(
+                    "osu! appears to be running, and will overwrite collection.db when it exits.",
+                );
+                ui.label("Close osu! before saving to keep your changes.");
+
+                ui.horizontal(|ui| {
+                    if ui.button("Save Anyway").clicked() {
+                        self.confirm_save = false;
+                        self.save();
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_save = false;
+                    }
+                });
+            });
+    }
+
+    /// Writes the collections back to the `collection.db` file they were loaded from, backing it up first.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&mut self) {
+        let (Some(collection_listing), Some(path)) = (&self.data, &self.path) else {
+            return;
+        };
+
+        // Compared with the file rather than what was loaded, in case osu! has changed it since
+        let changed = CollectionListing::from_file(path)
+            .map(|saved| changed_collections(&saved, collection_listing))
+            .unwrap_or(collection_listing.collections.len());
+
+        if let Err(message) = backups::back_up(path, "collections", changed, self.keep_backups) {
+            self.status = Some(message);
+            return;
+        }
+
+        match collection_listing.to_file(path) {
+            Ok(()) => {
+                self.modified = false;
+                self.saved = true;
+                self.status = Some(format!("Saved to {}", path.display()));
+            }
+            Err(e) => {
+                log::error!("Unable to save '{}': {}", path.display(), e);
+                self.status = Some(format!("Unable to save: {}", e));
+            }
+        }
+    }
+}
+
+/// Counts the collections that are new, removed or different in `edited` compared with `saved`.
+#[cfg(not(target_arch = "wasm32"))]
+fn changed_collections(saved: &CollectionListing, edited: &CollectionListing) -> usize {
+    let missing_from = |a: &CollectionListing, b: &CollectionListing| {
+        a.collections
+            .iter()
+            .filter(|collection| !b.collections.contains(collection))
+            .count()
+    };
+
+    // A changed collection is missing from both, but is only counted once
+    missing_from(edited, saved).max(missing_from(saved, edited))
+}
+
+/// Gets a collection name based on `name` which isn't already used, e.g. `New Collection (2)`.
+fn unique_name(collection_listing: &CollectionListing, name: &str) -> String {
+    let is_used = |candidate: &str| {
+        collection_listing
+            .collections
+            .iter()
+            .any(|c| c.name.as_deref() == Some(candidate))
+    };
+
+    (1..)
+        .map(|n| match n {
+            1 => name.to_string(),
+            n => format!("{} ({})", name, n),
+        })
+        .find(|candidate| !is_used(candidate))
+        .unwrap_or_default()
+}
+
+/// Checks whether osu! appears to be running, by looking for its process.
+///
+/// osu! doesn't lock its database files, so this is the best indication of whether it may overwrite them.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn osu_appears_to_be_running() -> bool {
+    use std::process::Command;
+
+    #[cfg(windows)]
+    {
+        Command::new("tasklist")
+            .args(["/FI", "IMAGENAME eq osu!.exe", "/NH"])
+            .output()
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("osu!.exe"))
+    }
+
+    // On other platforms, osu! is usually run using Wine
+    #[cfg(not(windows))]
+    {
+        Command::new("pgrep")
+            .args(["-f", "osu!.exe"])
+            .output()
+            .is_ok_and(|output| output.status.success())
     }
 }
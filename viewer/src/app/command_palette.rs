@@ -0,0 +1,182 @@
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+use super::actions::{self, ActionContext, ActionId, Availability};
+use crate::i18n::tr;
+
+/// Shortcut that opens and closes the palette.
+pub const SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::P);
+
+/// Most results shown at once. The rest can be found by typing more of the action's name.
+const MAX_RESULTS: usize = 12;
+
+/// A search box for running any action by name with the keyboard, opened with Ctrl+Shift+P.
+///
+/// Actions are found by typing some of the letters in their menu and name, and run with Enter. Actions which need
+/// something that isn't loaded or selected are shown greyed out with the reason, and those that don't apply aren't
+/// shown at all. Recently run actions are listed first.
+#[derive(Default)]
+pub struct CommandPalette {
+    pub visible: bool,
+    query: String,
+
+    /// Index of the highlighted result, which Enter runs
+    highlighted: usize,
+
+    /// Whether the search box should take focus, after the palette was opened
+    focus: bool,
+
+    /// Actions run recently from anywhere, most recent first
+    recent: Vec<ActionId>,
+}
+
+impl CommandPalette {
+    /// Opens or closes the palette when Ctrl+Shift+P is pressed.
+    pub fn handle_key(&mut self, ctx: &egui::Context) {
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT)) {
+            if self.visible {
+                self.visible = false;
+            } else {
+                self.open();
+            }
+        }
+    }
+
+    /// Opens the palette with an empty search.
+    pub fn open(&mut self) {
+        self.visible = true;
+        self.focus = true;
+        self.query.clear();
+        self.highlighted = 0;
+    }
+
+    /// Gets the recently run actions, most recent first, so that they can be saved.
+    pub fn recent(&self) -> &[ActionId] {
+        &self.recent
+    }
+
+    pub fn set_recent(&mut self, recent: Vec<ActionId>) {
+        self.recent = recent;
+        self.recent.truncate(actions::MAX_RECENT);
+    }
+
+    /// Records that an action was run, whether from the palette or a menu, so that it's ranked first next time.
+    pub fn record_use(&mut self, id: ActionId) {
+        actions::record_use(&mut self.recent, id);
+    }
+
+    /// Renders the palette, if it's open, returning the action to run.
+    pub fn view(&mut self, ctx: &egui::Context, context: &ActionContext) -> Option<ActionId> {
+        if !self.visible {
+            return None;
+        }
+
+        let results = actions::search(&self.query, context, &self.recent);
+        let shown = results.len().min(MAX_RESULTS);
+        let mut chosen = None;
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+
+        if down && shown > 0 {
+            self.highlighted = (self.highlighted + 1) % shown;
+        }
+
+        if up && shown > 0 {
+            self.highlighted = (self.highlighted + shown - 1) % shown;
+        }
+
+        self.highlighted = self.highlighted.min(shown.saturating_sub(1));
+
+        egui::Window::new(tr("Command Palette"))
+            .id(egui::Id::new("command_palette"))
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+            .fixed_size(egui::vec2(420.0, 0.0))
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(tr("Type the name of an action"))
+                        .desired_width(f32::INFINITY),
+                );
+
+                if std::mem::take(&mut self.focus) {
+                    response.request_focus();
+                }
+
+                if response.changed() {
+                    self.highlighted = 0;
+                }
+
+                ui.separator();
+
+                if results.is_empty() {
+                    ui.weak(tr("No matching actions"));
+                }
+
+                for (i, action) in results.iter().take(shown).enumerate() {
+                    let availability = action.availability(context);
+                    let enabled = availability == Availability::Enabled;
+
+                    let mut response = ui
+                        .add_enabled_ui(enabled, |ui| {
+                            ui.horizontal(|ui| {
+                                let response = ui
+                                    .selectable_label(i == self.highlighted, action.palette_text());
+
+                                if let Some(shortcut) = &action.shortcut {
+                                    ui.with_layout(
+                                        egui::Layout::right_to_left(egui::Align::Center),
+                                        |ui| ui.weak(ctx.format_shortcut(shortcut)),
+                                    );
+                                }
+
+                                response
+                            })
+                            .inner
+                        })
+                        .inner;
+
+                    if let Availability::Disabled(reason) = availability {
+                        response = response.on_disabled_hover_text(tr(reason));
+                    }
+
+                    if i == self.highlighted && (up || down) {
+                        response.scroll_to_me(None);
+                    }
+
+                    if response.clicked() {
+                        chosen = Some(action.id);
+                    }
+                }
+
+                if results.len() > shown {
+                    ui.weak(tr(
+                        "More actions match. Type more of the name to find them.",
+                    ));
+                }
+            });
+
+        // Disabled actions are highlighted like the others, but Enter does nothing until they can be used
+        if enter {
+            chosen = results
+                .get(self.highlighted)
+                .filter(|action| action.availability(context) == Availability::Enabled)
+                .map(|action| action.id);
+        }
+
+        if escape || chosen.is_some() {
+            self.visible = false;
+        }
+
+        chosen
+    }
+}
@@ -0,0 +1,343 @@
+use std::sync::Arc;
+
+use egui_extras::{Column as TableColumn, TableBuilder};
+use osu_db_parser::{
+    analysis,
+    diff::{FieldChange, ListingDiff},
+    prelude::*,
+};
+
+use super::format_count;
+
+/// Maximum height of the lists of added and removed beatmaps.
+const LIST_HEIGHT: f32 = 300.0;
+
+/// A window comparing the loaded beatmap listing with another `osu.db` file.
+///
+/// The other file is parsed and compared on a worker thread, so that large databases don't block the window. On the
+/// web, there are no worker threads, so this is done immediately instead.
+pub struct ComparisonWindow {
+    title: String,
+    pub visible: bool,
+
+    /// Name of the other file, used to label the differences
+    other_name: String,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pending: Option<std::sync::mpsc::Receiver<Result<Comparison, Error>>>,
+
+    /// Differences between the two listings, or why the other file couldn't be parsed
+    result: Option<Result<Comparison, Error>>,
+}
+
+/// Represents the differences between two beatmap listings, with the beatmap names resolved so that the listings
+/// don't need to be kept around.
+struct Comparison {
+    /// Beatmaps only in the other listing
+    added: Vec<String>,
+
+    /// Beatmaps only in the loaded listing
+    removed: Vec<String>,
+
+    changed: Vec<ChangedBeatmap>,
+
+    /// Beatmaps whose NoMod star rating for their own gameplay mode changed, with the largest changes first
+    star_changes: Vec<StarRatingChange>,
+}
+
+/// Represents a beatmap which is in both listings with different details.
+struct ChangedBeatmap {
+    name: String,
+    fields: Vec<FieldChange>,
+}
+
+/// Represents a beatmap whose star rating changed, e.g. because osu! recalculated star ratings.
+struct StarRatingChange {
+    name: String,
+    old: f64,
+    new: f64,
+    delta: f64,
+}
+
+impl Comparison {
+    /// Parses the other `osu.db` file and compares it with the loaded beatmap listing.
+    fn new(
+        listing: &BeatmapListing,
+        data: &[u8],
+        language: MetadataLanguage,
+    ) -> Result<Comparison, Error> {
+        let other = BeatmapListing::from_bytes(data)?;
+        let diff = ListingDiff::new(listing, &other);
+
+        let mut star_changes = [
+            GameplayMode::Standard,
+            GameplayMode::Taiko,
+            GameplayMode::Catch,
+            GameplayMode::Mania,
+        ]
+        .into_iter()
+        .flat_map(|mode| {
+            analysis::star_rating_changes(listing, &other, mode, Mods::none())
+                .into_iter()
+                .filter(move |change| change.new.gameplay_mode == mode)
+        })
+        .map(|change| StarRatingChange {
+            name: change.new.display_name(language),
+            old: change.old_rating,
+            new: change.new_rating,
+            delta: change.delta,
+        })
+        .collect::<Vec<_>>();
+
+        star_changes.sort_by(|a, b| b.delta.abs().total_cmp(&a.delta.abs()));
+
+        Ok(Comparison {
+            added: diff
+                .added
+                .into_iter()
+                .map(|beatmap| beatmap.display_name(language))
+                .collect(),
+            removed: diff
+                .removed
+                .into_iter()
+                .map(|beatmap| beatmap.display_name(language))
+                .collect(),
+            changed: diff
+                .changed
+                .into_iter()
+                .map(|changes| ChangedBeatmap {
+                    name: changes.new.display_name(language),
+                    fields: changes.fields,
+                })
+                .collect(),
+            star_changes,
+        })
+    }
+}
+
+impl ComparisonWindow {
+    /// Starts comparing the loaded beatmap listing with the contents of another `osu.db` file.
+    pub fn start(
+        ctx: &egui::Context,
+        listing: Arc<BeatmapListing>,
+        data: Vec<u8>,
+        other_name: String,
+        language: MetadataLanguage,
+    ) -> Self {
+        let title = format!("Comparison with {}", other_name);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let ctx = ctx.clone();
+
+            std::thread::spawn(move || {
+                if tx.send(Comparison::new(&listing, &data, language)).is_ok() {
+                    ctx.request_repaint();
+                }
+            });
+
+            Self {
+                title,
+                visible: true,
+                other_name,
+                pending: Some(rx),
+                result: None,
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            ctx.request_repaint();
+
+            Self {
+                title,
+                visible: true,
+                other_name,
+                result: Some(Comparison::new(&listing, &data, language)),
+            }
+        }
+    }
+
+    /// Collects the result of the comparison, if it has finished.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll(&mut self) {
+        let Some(pending) = &self.pending else {
+            return;
+        };
+
+        match pending.try_recv() {
+            Ok(result) => self.result = Some(result),
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                self.result = Some(Err(Error::Cancelled))
+            }
+        }
+
+        self.pending = None;
+    }
+
+    pub fn view(&mut self, ctx: &egui::Context) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.poll();
+
+        egui::Window::new(&self.title)
+            .open(&mut self.visible)
+            .default_width(600.0)
+            .show(ctx, |ui| match &self.result {
+                None => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Comparing with {}...", self.other_name));
+                    });
+                }
+                Some(Err(e)) => {
+                    ui.label(format!(
+                        "{} could not be compared, since it appears to be corrupt or from an unsupported version of osu!.",
+                        self.other_name
+                    ));
+                    ui.weak(e.to_string());
+                }
+                Some(Ok(comparison)) => {
+                    comparison_details(ui, comparison, &self.other_name);
+                }
+            });
+    }
+}
+
+/// Renders the added, removed and changed beatmaps in collapsible sections.
+fn comparison_details(ui: &mut egui::Ui, comparison: &Comparison, other_name: &str) {
+    if comparison.added.is_empty() && comparison.removed.is_empty() && comparison.changed.is_empty()
+    {
+        ui.label("The beatmaps in both files are the same.");
+        return;
+    }
+
+    ui.weak(format!(
+        "Before is the loaded osu.db, after is {}. Beatmaps are matched using their MD5 hashes.",
+        other_name
+    ));
+
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            beatmap_list(
+                ui,
+                format!("Added ({})", format_count(comparison.added.len())),
+                format!("Only in {}", other_name),
+                &comparison.added,
+            );
+
+            beatmap_list(
+                ui,
+                format!("Removed ({})", format_count(comparison.removed.len())),
+                "Only in the loaded osu.db".to_string(),
+                &comparison.removed,
+            );
+
+            egui::CollapsingHeader::new(format!(
+                "Changed ({})",
+                format_count(comparison.changed.len())
+            ))
+            .id_salt("comparison_changed")
+            .show(ui, |ui| {
+                for (i, changed) in comparison.changed.iter().enumerate() {
+                    egui::CollapsingHeader::new(format!(
+                        "{} ({} fields)",
+                        changed.name,
+                        changed.fields.len()
+                    ))
+                    .id_salt(("comparison_changed", i))
+                    .show(ui, |ui| {
+                        egui::Grid::new(("comparison_fields", i))
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("Field");
+                                ui.strong("Before");
+                                ui.strong("After");
+                                ui.end_row();
+
+                                for field in &changed.fields {
+                                    ui.label(field.name);
+                                    ui.label(&field.old);
+                                    ui.label(&field.new);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                }
+            });
+
+            if !comparison.star_changes.is_empty() {
+                star_changes(ui, &comparison.star_changes);
+            }
+        });
+}
+
+/// Renders a collapsible table of the beatmaps whose star rating changed the most.
+fn star_changes(ui: &mut egui::Ui, changes: &[StarRatingChange]) {
+    egui::CollapsingHeader::new(format!("Star Ratings ({})", format_count(changes.len())))
+        .id_salt("comparison_star_changes")
+        .show(ui, |ui| {
+            let row_height = ui.text_style_height(&egui::TextStyle::Body);
+            let (up, down) = (ui.visuals().warn_fg_color, ui.visuals().hyperlink_color);
+
+            TableBuilder::new(ui)
+                .striped(true)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .max_scroll_height(LIST_HEIGHT)
+                .column(TableColumn::exact(60.0))
+                .column(TableColumn::exact(60.0))
+                .column(TableColumn::exact(60.0))
+                .column(TableColumn::remainder().clip(true))
+                .header(row_height, |mut header| {
+                    for title in ["Change", "Before", "After", "Beatmap"] {
+                        header.col(|ui| {
+                            ui.strong(title);
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(row_height, changes.len(), |mut row| {
+                        let change = &changes[row.index()];
+                        let colour = if change.delta > 0.0 { up } else { down };
+
+                        row.col(|ui| {
+                            ui.colored_label(colour, format!("{:+.2}", change.delta));
+                        });
+                        row.col(|ui| {
+                            ui.label(format!("{:.2}", change.old));
+                        });
+                        row.col(|ui| {
+                            ui.label(format!("{:.2}", change.new));
+                        });
+                        row.col(|ui| {
+                            ui.label(&change.name);
+                        });
+                    });
+                });
+        })
+        .header_response
+        .on_hover_text("Beatmaps whose NoMod star rating changed, with the largest changes first");
+}
+
+/// Renders a collapsible list of beatmap names, which only lays out the names that are currently visible.
+fn beatmap_list(ui: &mut egui::Ui, heading: String, hover_text: String, names: &[String]) {
+    egui::CollapsingHeader::new(heading)
+        .id_salt(("comparison_list", &hover_text))
+        .show(ui, |ui| {
+            let row_height = ui.text_style_height(&egui::TextStyle::Body);
+
+            egui::ScrollArea::vertical()
+                .id_salt(("comparison_list_scroll", &hover_text))
+                .max_height(LIST_HEIGHT)
+                .auto_shrink([false, true])
+                .show_rows(ui, row_height, names.len(), |ui, row_range| {
+                    for name in &names[row_range] {
+                        ui.label(name);
+                    }
+                });
+        })
+        .header_response
+        .on_hover_text(hover_text);
+}
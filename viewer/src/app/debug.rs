@@ -0,0 +1,209 @@
+//! Reproductions for bug reports, which capture the loaded `osu.db` along with the search, table layout, settings and
+//! diagnostics, so that a problem can be seen exactly as the user saw it.
+//!
+//! Reproductions are saved as gzipped JSON. The listing can be anonymised first, in which case the beatmaps' text and
+//! the player's name are replaced with pseudonyms of the same shape (see [`Anonymiser`]).
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use osu_db_parser::{anonymise::Anonymiser, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    beatmap_table::TableLayout, diagnostics::SkippedEntries, filter_bar::FilterBar,
+    settings::Settings,
+};
+
+/// Suggested name for an exported reproduction.
+pub const FILE_NAME: &str = "osu-db-viewer-repro.json.gz";
+
+/// Version of the reproduction format; this must be incremented whenever older versions of the viewer can't load it.
+const REPRO_VERSION: u32 = 1;
+
+/// Represents the state of the viewer captured for a bug report.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Repro {
+    version: u32,
+
+    /// Version of the viewer that captured the reproduction
+    pub viewer_version: String,
+
+    /// Whether the listing, search and presets have been anonymised
+    pub anonymised: bool,
+
+    pub listing: BeatmapListing,
+
+    /// Search query and quick filters
+    pub query: String,
+    pub filter_bar: FilterBar,
+
+    /// Columns and sorting of the beatmap table
+    pub table_layout: TableLayout,
+
+    /// Settings, without any paths on the user's machine
+    pub settings: Settings,
+
+    /// Beatmaps that couldn't be parsed when the listing was loaded. The other diagnostics are found from the listing
+    /// again when it's loaded.
+    pub skipped: Option<SkippedEntries>,
+}
+
+impl Repro {
+    /// Captures the state of the viewer.
+    pub fn new(
+        listing: BeatmapListing,
+        (query, filter_bar): (String, FilterBar),
+        table_layout: TableLayout,
+        settings: &Settings,
+        skipped: Option<SkippedEntries>,
+    ) -> Self {
+        Self {
+            version: REPRO_VERSION,
+            viewer_version: env!("CARGO_PKG_VERSION").to_string(),
+            anonymised: false,
+            listing,
+            query,
+            filter_bar,
+            table_layout,
+            settings: Settings {
+                cjk_font: None,
+                osu_folder: None,
+                songs_folder: None,
+                ..settings.clone()
+            },
+            skipped,
+        }
+    }
+
+    /// Anonymises the listing, along with the search query and filter presets so that they still find the same
+    /// beatmaps. `seed` should be random, so that the pseudonyms can't be reversed.
+    pub fn anonymise(&mut self, seed: u64) {
+        let mut anonymiser = Anonymiser::new(seed);
+
+        anonymiser.listing(&mut self.listing);
+        self.query = anonymiser.query(&self.query);
+
+        for preset in &mut self.settings.filter_presets {
+            preset.name = anonymiser.text(&preset.name);
+            preset.query = anonymiser.query(&preset.query);
+            anonymiser.filter(&mut preset.filter);
+        }
+
+        self.anonymised = true;
+    }
+}
+
+/// Saves a reproduction to a file.
+pub fn export_repro(path: &Path, repro: &Repro) -> io::Result<()> {
+    let mut writer = GzEncoder::new(BufWriter::new(File::create(path)?), Compression::default());
+    serde_json::to_writer(&mut writer, repro)?;
+    writer.finish()?.flush()
+}
+
+/// Loads a reproduction from a file, checking that it was saved using a format this version of the viewer can read.
+pub fn load_repro(path: &Path) -> io::Result<Repro> {
+    let reader = GzDecoder::new(BufReader::new(File::open(path)?));
+    let repro: Repro = serde_json::from_reader(reader)?;
+
+    if repro.version != REPRO_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "the reproduction was saved by viewer version {} in an unsupported format",
+                repro.viewer_version
+            ),
+        ));
+    }
+
+    Ok(repro)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use osu_db_parser::common::WINDOWS_EPOCH;
+
+    use super::*;
+
+    fn repro() -> Repro {
+        let listing = BeatmapListing {
+            version: 20240101,
+            folder_count: 1,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: Some("Player".to_string()),
+            beatmaps: vec![BeatmapEntry {
+                artist_name: Some("xi".to_string()),
+                song_title: Some("FREEDOM DiVE".to_string()),
+                difficulty: Some("FOUR DIMENSIONS".to_string()),
+                md5: Some("da8aae79c8f3306b5d65ec951874a7fb".to_string()),
+                ..Default::default()
+            }],
+            user_permissions: Default::default(),
+        };
+
+        let settings = Settings {
+            osu_folder: Some("C:\\Users\\Player\\AppData\\Local\\osu!".into()),
+            ..Default::default()
+        };
+
+        Repro::new(
+            listing,
+            ("artist=xi mode=std".to_string(), FilterBar::default()),
+            TableLayout::default(),
+            &settings,
+            None,
+        )
+    }
+
+    /// Saves and loads a reproduction, also returning its uncompressed contents.
+    fn round_trip(repro: &Repro, name: &str) -> (Repro, String) {
+        let path =
+            std::env::temp_dir().join(format!("osu-db-repro-{}-{}", name, std::process::id()));
+        export_repro(&path, repro).unwrap();
+
+        let loaded = load_repro(&path);
+        let mut contents = String::new();
+        GzDecoder::new(File::open(&path).unwrap())
+            .read_to_string(&mut contents)
+            .unwrap();
+        std::fs::remove_file(&path).ok();
+
+        (loaded.unwrap(), contents)
+    }
+
+    #[test]
+    fn reproductions_round_trip() {
+        let repro = repro();
+        let (loaded, contents) = round_trip(&repro, "plain");
+
+        assert_eq!(loaded, repro);
+        assert!(contents.contains("FREEDOM DiVE"));
+        assert!(!contents.contains("AppData"));
+    }
+
+    #[test]
+    fn anonymised_reproductions_hide_metadata() {
+        let mut repro = repro();
+        repro.anonymise(42);
+        let (loaded, contents) = round_trip(&repro, "anonymised");
+
+        assert_eq!(loaded, repro);
+        assert!(loaded.anonymised);
+
+        for original in ["FREEDOM", "DiVE", "FOUR DIMENSIONS", "Player", "da8aae79"] {
+            assert!(!contents.contains(original), "{} survived", original);
+        }
+
+        // The query still finds the beatmap
+        let filter = BeatmapFilter::parse(&loaded.query).unwrap();
+        assert!(loaded.query.ends_with(" mode=std"));
+        assert!(filter.matches(&loaded.listing.beatmaps[0]));
+    }
+}
@@ -0,0 +1,590 @@
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use osu_db_parser::{health, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::format_count;
+#[cfg(not(target_arch = "wasm32"))]
+use super::raw_entry::RawEntryWindow;
+
+/// Number of the most common values shown for each unknown field.
+const COMMON_VALUES: usize = 10;
+
+/// Largest height of the list of beatmaps in each group of warnings, so that one large group doesn't push the others
+/// out of view.
+const MAX_GROUP_HEIGHT: f32 = 200.0;
+
+/// Problems found when a beatmap listing was loaded, along with the values of the fields whose meaning isn't known.
+///
+/// This is mostly of interest for reporting bugs, or for working out what the unknown fields are used for across
+/// different versions of osu!.
+#[derive(Default)]
+pub struct Diagnostics {
+    skipped: Option<SkippedEntries>,
+
+    /// Validation warnings for the listing's header
+    header_warnings: Vec<health::Warning>,
+
+    /// Validation warnings, grouped by the kind of problem in the order each kind is first found
+    warnings: Vec<WarningGroup>,
+
+    unknown_fields: Vec<UnknownField>,
+
+    /// Path of the `osu.db` file the listing was loaded from, which raw entries are read from
+    #[cfg(not(target_arch = "wasm32"))]
+    source_path: Option<PathBuf>,
+
+    /// Number of the entry to show the raw bytes of, starting from 1
+    #[cfg(not(target_arch = "wasm32"))]
+    raw_number: usize,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    raw_entry: Option<RawEntryWindow>,
+
+    /// Why the raw bytes couldn't be read
+    #[cfg(not(target_arch = "wasm32"))]
+    raw_error: Option<String>,
+}
+
+/// Represents the beatmaps that couldn't be parsed, and why.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SkippedEntries {
+    pub count: usize,
+    reason: String,
+
+    /// Index of the first beatmap that couldn't be parsed, if known
+    index: Option<usize>,
+
+    /// Byte offset of the start of the first beatmap that couldn't be parsed, and where parsing failed
+    offsets: Option<(usize, usize)>,
+}
+
+/// Represents the beatmaps with one kind of validation warning.
+struct WarningGroup {
+    title: &'static str,
+
+    /// Index of each affected beatmap, along with the details of its warning
+    entries: Vec<(usize, String)>,
+}
+
+/// Represents the values stored in a field whose meaning isn't known.
+struct UnknownField {
+    name: &'static str,
+    description: &'static str,
+
+    /// Number of beatmaps which have the field, since some fields are only present in older versions
+    present: usize,
+    distinct: usize,
+
+    /// Most common values, most common first
+    values: Vec<CommonValue>,
+}
+
+/// Represents one of the values stored in an unknown field.
+struct CommonValue {
+    value: u32,
+    count: usize,
+
+    /// First beatmap with this value, as an example
+    first: usize,
+}
+
+impl Diagnostics {
+    /// Collects the diagnostics for a listing. `skipped` is the number of beatmaps that couldn't be parsed because of
+    /// `error`.
+    pub fn new(listing: &BeatmapListing, skipped: usize, error: Option<&Error>) -> Self {
+        let skipped = (skipped > 0).then(|| SkippedEntries::new(skipped, error));
+
+        let mut warnings: Vec<WarningGroup> = Vec::new();
+
+        for (index, beatmap) in listing.beatmaps.iter().enumerate() {
+            for warning in health::validate(beatmap) {
+                let title = warning.title();
+                let entry = (index, warning.to_string());
+
+                match warnings.iter_mut().find(|group| group.title == title) {
+                    Some(group) => group.entries.push(entry),
+                    None => warnings.push(WarningGroup {
+                        title,
+                        entries: vec![entry],
+                    }),
+                }
+            }
+        }
+
+        let header_warnings = health::validate_listing(listing);
+
+        let unknown_fields = vec![
+            UnknownField::new(
+                "Unknown Short",
+                "Only present in versions before 20140609",
+                listing
+                    .beatmaps
+                    .iter()
+                    .map(|beatmap| beatmap.unknown_u16.map(u32::from)),
+            ),
+            UnknownField::new(
+                "Unknown Int",
+                "Possibly another modification time",
+                listing
+                    .beatmaps
+                    .iter()
+                    .map(|beatmap| Some(beatmap.unknown_u32)),
+            ),
+        ];
+
+        Self {
+            skipped,
+            header_warnings,
+            warnings,
+            unknown_fields,
+            #[cfg(not(target_arch = "wasm32"))]
+            source_path: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            raw_number: 1,
+            #[cfg(not(target_arch = "wasm32"))]
+            raw_entry: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            raw_error: None,
+        }
+    }
+
+    /// Sets the path of the `osu.db` file the listing was loaded from, so that the raw bytes of its entries can be shown.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_source_path(&mut self, source_path: Option<PathBuf>) {
+        self.source_path = source_path;
+    }
+
+    /// Gets the beatmaps that couldn't be parsed, e.g. to include them in a bug report.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn skipped(&self) -> Option<&SkippedEntries> {
+        self.skipped.as_ref()
+    }
+
+    /// Replaces the beatmaps that couldn't be parsed, e.g. with those from a bug report, since the error that caused
+    /// them isn't kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_skipped(&mut self, skipped: Option<SkippedEntries>) {
+        self.skipped = skipped;
+    }
+
+    /// Gets the number of problems found, i.e. skipped beatmaps and validation warnings.
+    pub fn count(&self) -> usize {
+        let skipped = self.skipped.as_ref().map_or(0, |skipped| skipped.count);
+        let warnings = self
+            .warnings
+            .iter()
+            .map(|group| group.entries.len())
+            .sum::<usize>();
+
+        skipped + self.header_warnings.len() + warnings
+    }
+
+    /// Renders the diagnostics, returning the index of the beatmap that should be shown when one is clicked.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        listing: &BeatmapListing,
+        language: MetadataLanguage,
+    ) -> Option<usize> {
+        let mut shown = None;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut show_skipped_bytes = false;
+
+        ui.horizontal(|ui| {
+            ui.strong("Diagnostics");
+
+            if ui
+                .button("Copy All")
+                .on_hover_text("Copy every diagnostic as text, e.g. to paste into a bug report")
+                .clicked()
+            {
+                ui.ctx().copy_text(self.text(listing, language));
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            self.raw_entry_controls(ui, listing);
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(error) = &self.raw_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        egui::ScrollArea::vertical()
+            .id_salt("d_scroll")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                self.skipped_section(
+                    ui,
+                    &mut shown,
+                    #[cfg(not(target_arch = "wasm32"))]
+                    &mut show_skipped_bytes,
+                );
+                self.warnings_section(ui, listing, language, &mut shown);
+                self.unknown_fields_section(ui, &mut shown);
+            });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if show_skipped_bytes {
+                self.open_skipped_bytes(listing.version);
+            }
+
+            if let Some(window) = &mut self.raw_entry {
+                window.view(ui.ctx());
+            }
+        }
+
+        shown
+    }
+
+    /// Renders the controls for showing the raw bytes of an entry, which are read from the `osu.db` file again.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn raw_entry_controls(&mut self, ui: &mut egui::Ui, listing: &BeatmapListing) {
+        let count = listing.beatmaps.len();
+
+        ui.separator();
+        ui.label("Entry");
+        ui.add(egui::DragValue::new(&mut self.raw_number).range(1..=count.max(1)));
+
+        let Some(path) = self.source_path.clone() else {
+            ui.add_enabled(false, egui::Button::new("Show Raw Entry"))
+                .on_disabled_hover_text("The osu!.db file this was loaded from isn't known");
+            return;
+        };
+
+        if ui
+            .add_enabled(count > 0, egui::Button::new("Show Raw Entry"))
+            .on_hover_text(
+                "Show the bytes of the entry as they're stored in osu!.db, with each field marked",
+            )
+            .clicked()
+        {
+            let index = self.raw_number.clamp(1, count) - 1;
+            self.set_raw_entry(RawEntryWindow::beatmap(&path, listing, index));
+        }
+    }
+
+    /// Shows the raw bytes from the start of the first entry that couldn't be parsed.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_skipped_bytes(&mut self, version: u32) {
+        let offsets = self.skipped.as_ref().and_then(|skipped| skipped.offsets);
+
+        if let Some(((entry_offset, offset), path)) = offsets.zip(self.source_path.clone()) {
+            self.set_raw_entry(RawEntryWindow::skipped(
+                &path,
+                version,
+                entry_offset,
+                offset,
+            ));
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_raw_entry(&mut self, window: Result<RawEntryWindow, String>) {
+        match window {
+            Ok(window) => {
+                self.raw_entry = Some(window);
+                self.raw_error = None;
+            }
+            Err(e) => self.raw_error = Some(e),
+        }
+    }
+
+    /// Renders the details of the beatmaps that couldn't be parsed.
+    fn skipped_section(
+        &self,
+        ui: &mut egui::Ui,
+        shown: &mut Option<usize>,
+        #[cfg(not(target_arch = "wasm32"))] show_bytes: &mut bool,
+    ) {
+        let Some(skipped) = &self.skipped else {
+            ui.label("✔ Every beatmap was read");
+            return;
+        };
+
+        egui::CollapsingHeader::new(format!(
+            "Skipped Entries ({})",
+            format_count(skipped.count)
+        ))
+        .id_salt("d_skipped")
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new("d_skipped_grid").show(ui, |ui| {
+                ui.label("Reason");
+                ui.label(&skipped.reason);
+                ui.end_row();
+
+                if let Some(index) = skipped.index {
+                    ui.label("First Skipped Entry");
+                    ui.label(format!("#{}", format_count(index + 1)));
+                    ui.end_row();
+                }
+
+                if let Some((entry_offset, offset)) = skipped.offsets {
+                    ui.label("Entry Starts At");
+                    ui.monospace(format!("byte {} (0x{:X})", entry_offset, entry_offset));
+                    ui.end_row();
+
+                    ui.label("Failed At");
+                    ui.monospace(format!("byte {} (0x{:X})", offset, offset));
+                    ui.end_row();
+                }
+            });
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if skipped.offsets.is_some()
+                && ui
+                    .add_enabled(self.source_path.is_some(), egui::Button::new("Show Raw Bytes"))
+                    .on_hover_text("Show the bytes from the start of the entry, with each field that could be read marked")
+                    .on_disabled_hover_text("The osu!.db file this was loaded from isn't known")
+                    .clicked()
+            {
+                *show_bytes = true;
+            }
+
+            // Entries don't record their length, so a problem with the previous entry can show up as an invalid entry
+            if let Some(previous) = skipped.index.and_then(|index| index.checked_sub(1)) {
+                if ui
+                    .link("Show the entry before it")
+                    .on_hover_text(
+                        "If the previous entry was read with the wrong length, it may be the actual cause",
+                    )
+                    .clicked()
+                {
+                    *shown = Some(previous);
+                }
+            }
+        });
+    }
+
+    /// Renders the validation warnings, grouped by the kind of problem.
+    fn warnings_section(
+        &self,
+        ui: &mut egui::Ui,
+        listing: &BeatmapListing,
+        language: MetadataLanguage,
+        shown: &mut Option<usize>,
+    ) {
+        if self.warnings.is_empty() && self.header_warnings.is_empty() {
+            ui.label("✔ No validation warnings");
+            return;
+        }
+
+        let count = self
+            .warnings
+            .iter()
+            .map(|group| group.entries.len())
+            .sum::<usize>()
+            + self.header_warnings.len();
+
+        egui::CollapsingHeader::new(format!("Validation Warnings ({})", format_count(count)))
+            .id_salt("d_warnings")
+            .show(ui, |ui| {
+                for warning in &self.header_warnings {
+                    ui.horizontal(|ui| {
+                        ui.label(warning.title());
+                        ui.weak(warning.to_string());
+                    });
+                }
+
+                for group in &self.warnings {
+                    egui::CollapsingHeader::new(format!(
+                        "{} ({})",
+                        group.title,
+                        format_count(group.entries.len())
+                    ))
+                    .id_salt(("d_warning_group", group.title))
+                    .show(ui, |ui| {
+                        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+
+                        egui::ScrollArea::vertical()
+                            .id_salt(("d_warning_rows", group.title))
+                            .max_height(MAX_GROUP_HEIGHT)
+                            .show_rows(ui, row_height, group.entries.len(), |ui, range| {
+                                for (index, details) in &group.entries[range] {
+                                    ui.horizontal(|ui| {
+                                        let name = listing.beatmaps[*index].display_name(language);
+
+                                        if ui.link(name).clicked() {
+                                            *shown = Some(*index);
+                                        }
+
+                                        ui.weak(details);
+                                    });
+                                }
+                            });
+                    });
+                }
+            });
+    }
+
+    /// Renders the most common values of each unknown field.
+    fn unknown_fields_section(&self, ui: &mut egui::Ui, shown: &mut Option<usize>) {
+        egui::CollapsingHeader::new("Unknown Fields")
+            .id_salt("d_unknown_fields")
+            .show(ui, |ui| {
+                ui.weak(
+                    "Fields which are read but whose meaning isn't known. \
+                     Comparing them between versions of osu! may help work out what they're for.",
+                );
+
+                for field in &self.unknown_fields {
+                    ui.add_space(4.0);
+                    ui.strong(field.name).on_hover_text(field.description);
+
+                    if field.present == 0 {
+                        ui.weak("Not present in this version");
+                        continue;
+                    }
+
+                    ui.label(format!(
+                        "{} distinct values in {} beatmaps",
+                        format_count(field.distinct),
+                        format_count(field.present)
+                    ));
+
+                    egui::Grid::new(("d_unknown_field", field.name))
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for value in &field.values {
+                                ui.monospace(value.value.to_string());
+                                ui.monospace(format!("0x{:08X}", value.value));
+                                ui.label(format!("× {}", format_count(value.count)));
+
+                                if ui.link("Example").clicked() {
+                                    *shown = Some(value.first);
+                                }
+
+                                ui.end_row();
+                            }
+                        });
+                }
+            });
+    }
+
+    /// Formats every diagnostic as plain text.
+    fn text(&self, listing: &BeatmapListing, language: MetadataLanguage) -> String {
+        let mut text = format!(
+            "osu!.db version {}, {} beatmaps\n",
+            listing.version,
+            format_count(listing.beatmaps.len())
+        );
+
+        if let Some(skipped) = &self.skipped {
+            text.push_str(&format!(
+                "\nSkipped entries: {}\n  {}\n",
+                format_count(skipped.count),
+                skipped.reason
+            ));
+        }
+
+        for warning in &self.header_warnings {
+            text.push_str(&format!("\n{}: {}\n", warning.title(), warning));
+        }
+
+        for group in &self.warnings {
+            text.push_str(&format!(
+                "\n{}: {}\n",
+                group.title,
+                format_count(group.entries.len())
+            ));
+
+            for (index, details) in &group.entries {
+                text.push_str(&format!(
+                    "  #{} {}: {}\n",
+                    index + 1,
+                    listing.beatmaps[*index].display_name(language),
+                    details
+                ));
+            }
+        }
+
+        for field in &self.unknown_fields {
+            text.push_str(&format!(
+                "\n{}: {} distinct values in {} beatmaps\n",
+                field.name,
+                format_count(field.distinct),
+                format_count(field.present)
+            ));
+
+            for value in &field.values {
+                text.push_str(&format!(
+                    "  {} (0x{:08X}) x {}\n",
+                    value.value,
+                    value.value,
+                    format_count(value.count)
+                ));
+            }
+        }
+
+        text
+    }
+}
+
+impl SkippedEntries {
+    fn new(count: usize, error: Option<&Error>) -> Self {
+        let (index, offsets) = match error {
+            Some(Error::Beatmap {
+                index,
+                entry_offset,
+                offset,
+                ..
+            }) => (Some(*index), Some((*entry_offset, *offset))),
+            _ => (None, None),
+        };
+
+        Self {
+            count,
+            reason: error.map_or_else(|| "Unknown".to_string(), ToString::to_string),
+            index,
+            offsets,
+        }
+    }
+}
+
+impl UnknownField {
+    /// Counts the values of a field, where `values` has the value for each beatmap, or `None` if the beatmap doesn't
+    /// have the field.
+    fn new(
+        name: &'static str,
+        description: &'static str,
+        values: impl Iterator<Item = Option<u32>>,
+    ) -> Self {
+        let mut counts = HashMap::<u32, CommonValue>::new();
+        let mut present = 0;
+
+        for (index, value) in values.enumerate() {
+            let Some(value) = value else {
+                continue;
+            };
+
+            present += 1;
+            counts
+                .entry(value)
+                .or_insert(CommonValue {
+                    value,
+                    count: 0,
+                    first: index,
+                })
+                .count += 1;
+        }
+
+        let distinct = counts.len();
+        let mut values = counts.into_values().collect::<Vec<_>>();
+        values.sort_by(|a, b| b.count.cmp(&a.count).then(a.value.cmp(&b.value)));
+        values.truncate(COMMON_VALUES);
+
+        Self {
+            name,
+            description,
+            present,
+            distinct,
+            values,
+        }
+    }
+}
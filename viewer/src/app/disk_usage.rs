@@ -0,0 +1,541 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+};
+
+use egui_extras::{Column as TableColumn, TableBuilder};
+use osu_db_parser::{
+    disk_usage::{self, FileKind, Usage},
+    paths,
+    prelude::*,
+};
+
+use super::{format_count, format_size};
+
+/// Largest number of worker threads to measure folders with. Scanning is mostly limited by the disk, so more threads
+/// than this don't help.
+const MAX_THREADS: usize = 8;
+
+/// Width of the breakdown bar in each row.
+const BAR_WIDTH: f32 = 200.0;
+
+/// A view which measures the disk space used by each beatmapset in the Songs folder, to find the largest ones.
+#[derive(Default)]
+pub struct DiskUsageView {
+    /// Beatmap listing whose beatmapsets are measured
+    listing: Option<Arc<BeatmapListing>>,
+
+    /// osu! installation folder, which contains the Songs folder
+    osu_directory: Option<PathBuf>,
+
+    /// Which version of the artist and title the beatmapsets are named with
+    language: MetadataLanguage,
+
+    /// Scan that is currently running, if any
+    scan: Option<Scan>,
+
+    /// Results of the most recent scan, which are kept until the listing or osu! folder changes
+    report: Option<Report>,
+
+    /// Whether to only show the folders which are missing on disk
+    missing_only: bool,
+}
+
+/// Represents the results of a scan.
+struct Report {
+    /// Every beatmapset, largest first, followed by the ones that are missing on disk
+    sets: Vec<SetUsage>,
+
+    total: Usage,
+    missing: usize,
+}
+
+/// Represents the disk space used by one beatmapset.
+struct SetUsage {
+    /// Name of the beatmapset, e.g. `Artist - Title (Creator)`
+    name: String,
+
+    folder_name: String,
+    path: PathBuf,
+    difficulties: usize,
+
+    /// Disk space used by the folder, or `None` if it's referenced by `osu!.db` but doesn't exist
+    usage: Option<Usage>,
+}
+
+/// Measures the beatmapset folders on worker threads, so that the window stays responsive while scanning.
+struct Scan {
+    progress: Arc<Progress>,
+    result: mpsc::Receiver<Option<Report>>,
+}
+
+/// Represents the progress of a scan, shared with the worker threads.
+#[derive(Default)]
+struct Progress {
+    measured: AtomicUsize,
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Scan {
+    /// Starts measuring the beatmapsets in a listing. The folders are split between several worker threads.
+    fn start(
+        ctx: &egui::Context,
+        listing: Arc<BeatmapListing>,
+        osu_directory: PathBuf,
+        language: MetadataLanguage,
+    ) -> Self {
+        let progress = Arc::new(Progress::default());
+        let (tx, rx) = mpsc::channel();
+        let worker_progress = Arc::clone(&progress);
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            let report = measure(&listing, &osu_directory, language, &worker_progress);
+
+            if tx.send(report).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+
+        Self {
+            progress,
+            result: rx,
+        }
+    }
+
+    /// Asks the worker threads to stop scanning.
+    fn cancel(&self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Takes the result of the scan if it has finished, which is `None` if it was cancelled.
+    fn poll(&self) -> Option<Option<Report>> {
+        match self.result.try_recv() {
+            Ok(report) => Some(report),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(None),
+        }
+    }
+
+    /// Gets a description of the progress so far, e.g. `1,000 / 2,000 folders`.
+    fn progress_text(&self) -> String {
+        let measured = self.progress.measured.load(Ordering::Relaxed);
+        let total = self.progress.total.load(Ordering::Relaxed);
+
+        format!(
+            "{} / {} folders",
+            format_count(measured),
+            format_count(total)
+        )
+    }
+}
+
+impl DiskUsageView {
+    /// Sets the osu! installation folder, whose Songs folder is scanned.
+    pub fn set_osu_directory(&mut self, osu_directory: Option<PathBuf>) {
+        if self.osu_directory != osu_directory {
+            self.reset();
+            self.osu_directory = osu_directory;
+        }
+    }
+
+    /// Sets which version of the artist and title the beatmapsets in scans run from now on are named with.
+    pub fn set_language(&mut self, language: MetadataLanguage) {
+        self.language = language;
+    }
+
+    /// Renders the disk usage view for the specified beatmap listing, returning a search query for the beatmapset that
+    /// should be shown in the beatmap listing.
+    pub fn view(
+        &mut self,
+        ctx: &egui::Context,
+        listing: Option<&Arc<BeatmapListing>>,
+    ) -> Option<String> {
+        let mut query = None;
+
+        self.set_beatmap_listing(listing);
+        self.poll_scan();
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Disk Usage");
+
+            if self.listing.is_none() {
+                ui.label("No beatmap listing loaded...");
+                return;
+            }
+
+            self.toolbar(ui);
+            ui.separator();
+
+            match &self.report {
+                Some(report) => query = self.report_table(ui, report),
+                None if self.scan.is_none() => {
+                    ui.label(
+                        "Scan the Songs folder to see how much space each beatmapset uses. \
+                         This reads every file in the folder, so it may take a while.",
+                    );
+                }
+                None => {}
+            }
+        });
+
+        query
+    }
+
+    /// Updates the beatmap listing being measured, discarding the results if it has changed.
+    fn set_beatmap_listing(&mut self, listing: Option<&Arc<BeatmapListing>>) {
+        let unchanged = match (&self.listing, listing) {
+            (Some(current), Some(new)) => Arc::ptr_eq(current, new),
+            (None, None) => true,
+            _ => false,
+        };
+
+        if !unchanged {
+            self.reset();
+            self.listing = listing.cloned();
+        }
+    }
+
+    /// Cancels any running scan and discards the results.
+    fn reset(&mut self) {
+        if let Some(scan) = self.scan.take() {
+            scan.cancel();
+        }
+
+        self.report = None;
+    }
+
+    /// Collects the results of the scan, if it has finished.
+    fn poll_scan(&mut self) {
+        let Some(report) = self.scan.as_ref().and_then(Scan::poll) else {
+            return;
+        };
+
+        self.scan = None;
+
+        match report {
+            Some(report) => self.report = Some(report),
+            None => log::info!("Disk usage scan was cancelled"),
+        }
+    }
+
+    /// Renders the button for starting or cancelling a scan, along with the totals of the last scan.
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if let Some(scan) = &self.scan {
+                ui.spinner();
+                ui.label(scan.progress_text());
+
+                if ui.button("Cancel").clicked() {
+                    scan.cancel();
+                }
+            } else {
+                let text = if self.report.is_some() {
+                    "Scan Again"
+                } else {
+                    "Scan"
+                };
+
+                let button = ui
+                    .add_enabled(self.osu_directory.is_some(), egui::Button::new(text))
+                    .on_disabled_hover_text("Open the osu! folder to scan its Songs folder");
+
+                if button.clicked() {
+                    if let (Some(listing), Some(osu_directory)) =
+                        (&self.listing, &self.osu_directory)
+                    {
+                        self.scan = Some(Scan::start(
+                            ui.ctx(),
+                            Arc::clone(listing),
+                            osu_directory.clone(),
+                            self.language,
+                        ));
+                    }
+                }
+            }
+
+            if let Some(report) = &self.report {
+                ui.separator();
+                ui.strong(format_size(report.total.total()));
+                ui.label(format!(
+                    "in {} beatmapsets",
+                    format_count(report.sets.len() - report.missing)
+                ));
+
+                for kind in FileKind::ALL {
+                    ui.separator();
+                    legend(ui, kind);
+                    ui.label(format_size(report.total.get(kind)));
+                }
+
+                if report.missing > 0 {
+                    ui.separator();
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("{} folders missing", format_count(report.missing)),
+                    );
+                    ui.checkbox(&mut self.missing_only, "Only Missing");
+                }
+            }
+        });
+    }
+
+    /// Renders the beatmapsets in a table, largest first, returning a search query for the beatmapset that should be
+    /// shown in the beatmap listing.
+    fn report_table(&self, ui: &mut egui::Ui, report: &Report) -> Option<String> {
+        let mut query = None;
+
+        let rows = report
+            .sets
+            .iter()
+            .filter(|set| !self.missing_only || set.usage.is_none())
+            .collect::<Vec<_>>();
+
+        let largest = report
+            .sets
+            .first()
+            .and_then(|set| set.usage)
+            .map(|usage| usage.total())
+            .unwrap_or_default();
+
+        let row_height = ui.spacing().interact_size.y;
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(TableColumn::remainder().at_least(200.0).clip(true))
+            .column(TableColumn::auto())
+            .column(TableColumn::auto().at_least(80.0))
+            .column(TableColumn::exact(BAR_WIDTH))
+            .column(TableColumn::auto())
+            .header(row_height, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Beatmapset");
+                });
+                header.col(|ui| {
+                    ui.strong("Difficulties");
+                });
+                header.col(|ui| {
+                    ui.strong("Size");
+                });
+                header.col(|ui| {
+                    ui.strong("Breakdown");
+                });
+                header.col(|_| {});
+            })
+            .body(|body| {
+                body.rows(row_height, rows.len(), |mut row| {
+                    let set = rows[row.index()];
+
+                    row.col(|ui| {
+                        ui.label(&set.name).on_hover_text(&set.folder_name);
+                    });
+                    row.col(|ui| {
+                        ui.label(format_count(set.difficulties));
+                    });
+                    row.col(|ui| match set.usage {
+                        Some(usage) => {
+                            ui.label(format_size(usage.total()));
+                        }
+                        None => {
+                            ui.colored_label(ui.visuals().warn_fg_color, "Missing")
+                                .on_hover_text(format!(
+                                    "Referenced by osu!.db, but {} doesn't exist",
+                                    set.path.display()
+                                ));
+                        }
+                    });
+                    row.col(|ui| {
+                        if let Some(usage) = set.usage {
+                            breakdown_bar(ui, usage, largest);
+                        }
+                    });
+                    row.col(|ui| {
+                        if ui
+                            .add_enabled(set.usage.is_some(), egui::Button::new("Open Folder"))
+                            .clicked()
+                        {
+                            open_folder(&set.path);
+                        }
+
+                        if ui.button("Show Beatmaps").clicked() {
+                            query = Some(format!("folder=\"{}\"", set.folder_name));
+                        }
+                    });
+                });
+            });
+
+        query
+    }
+}
+
+/// Measures every beatmapset folder in a listing, returning `None` if the scan was cancelled.
+///
+/// The progress isn't repainted from here, since the spinner shown while scanning already repaints continuously.
+fn measure(
+    listing: &BeatmapListing,
+    osu_directory: &Path,
+    language: MetadataLanguage,
+    progress: &Progress,
+) -> Option<Report> {
+    // Beatmaps without a folder name can't be measured, and are reported by the health checks instead
+    let sets = listing
+        .beatmapsets()
+        .into_iter()
+        .filter_map(|set| {
+            let beatmap = &listing.beatmaps[set.beatmaps[0]];
+            let path = paths::beatmap_folder(osu_directory, beatmap)?;
+
+            Some(SetUsage {
+                name: format!(
+                    "{} - {} ({})",
+                    beatmap
+                        .display_artist(language.prefers_unicode())
+                        .unwrap_or_default(),
+                    beatmap
+                        .display_title(language.prefers_unicode())
+                        .unwrap_or_default(),
+                    beatmap.creator_name.as_deref().unwrap_or_default()
+                )
+                .sanitized()
+                .into_owned(),
+                folder_name: set.folder_name.unwrap_or_default(),
+                path,
+                difficulties: set.beatmaps.len(),
+                usage: None,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    progress.total.store(sets.len(), Ordering::Relaxed);
+
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .clamp(1, MAX_THREADS);
+
+    // Each worker takes the next folder that hasn't been measured yet, so that a few very large folders don't leave the
+    // other workers idle
+    let next = AtomicUsize::new(0);
+
+    let usages = std::thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut usages = Vec::new();
+
+                    while !progress.cancelled.load(Ordering::Relaxed) {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(set) = sets.get(i) else {
+                            break;
+                        };
+
+                        usages.push((i, disk_usage::folder_usage(&set.path).ok()));
+
+                        progress.measured.fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    usages
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect::<Vec<_>>()
+    });
+
+    if progress.cancelled.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let mut sets = sets;
+    let mut total = Usage::default();
+
+    for (i, usage) in usages {
+        if let Some(usage) = usage {
+            total += usage;
+        }
+
+        sets[i].usage = usage;
+    }
+
+    // Largest first, with missing folders at the end
+    sets.sort_by_key(|set| std::cmp::Reverse(set.usage.map(|usage| usage.total())));
+
+    let missing = sets.iter().filter(|set| set.usage.is_none()).count();
+
+    Some(Report {
+        sets,
+        total,
+        missing,
+    })
+}
+
+/// Paints a bar split into the disk space used by each kind of file. The bar's length is relative to the largest
+/// beatmapset, so that the sizes can be compared at a glance.
+fn breakdown_bar(ui: &mut egui::Ui, usage: Usage, largest: u64) {
+    let size = egui::vec2(BAR_WIDTH, ui.spacing().interact_size.y * 0.6);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+
+    if ui.is_rect_visible(rect) && largest > 0 {
+        let painter = ui.painter();
+        let mut left = rect.left();
+
+        for kind in FileKind::ALL {
+            let width = rect.width() * usage.get(kind) as f32 / largest as f32;
+            let segment = egui::Rect::from_min_max(
+                egui::pos2(left, rect.top()),
+                egui::pos2(left + width, rect.bottom()),
+            );
+
+            painter.rect_filled(segment, 0.0, kind_colour(kind));
+            left += width;
+        }
+    }
+
+    response.on_hover_ui(|ui| {
+        for kind in FileKind::ALL {
+            ui.horizontal(|ui| {
+                legend(ui, kind);
+                ui.label(format_size(usage.get(kind)));
+            });
+        }
+    });
+}
+
+/// Renders a coloured square and the name of a kind of file, as a legend for the breakdown bars.
+fn legend(ui: &mut egui::Ui, kind: FileKind) {
+    let size = egui::Vec2::splat(ui.text_style_height(&egui::TextStyle::Body) * 0.7);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    ui.painter().rect_filled(rect, 2.0, kind_colour(kind));
+
+    ui.label(match kind {
+        FileKind::Audio => "Audio",
+        FileKind::Video => "Video",
+        FileKind::Image => "Images",
+        FileKind::Other => "Other",
+    });
+}
+
+/// Gets the colour used for a kind of file in the breakdown bars.
+fn kind_colour(kind: FileKind) -> egui::Color32 {
+    match kind {
+        FileKind::Audio => egui::Color32::from_rgb(70, 140, 230),
+        FileKind::Video => egui::Color32::from_rgb(230, 70, 70),
+        FileKind::Image => egui::Color32::from_rgb(100, 200, 90),
+        FileKind::Other => egui::Color32::GRAY,
+    }
+}
+
+/// Opens a beatmapset's folder in the system's file manager.
+fn open_folder(path: &Path) {
+    if let Err(e) = open::that_detached(path) {
+        log::error!("Unable to open '{}': {}", path.display(), e);
+    }
+}
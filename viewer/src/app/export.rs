@@ -0,0 +1,601 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Write;
+use std::{collections::BTreeSet, sync::Arc};
+
+#[cfg(not(target_arch = "wasm32"))]
+use osu_db_parser::{export::BundleEntry, report::ReportOptions};
+use osu_db_parser::{
+    export::{self, Format},
+    links::Mirror,
+    prelude::*,
+};
+
+use super::{
+    beatmap_table::BeatmapTable,
+    collection_listing::{AddToCollection, CollectionMembership},
+    format_count,
+};
+
+/// Formats that the beatmaps can be exported as, in the order they're shown.
+const FORMATS: [ExportFormat; 6] = [
+    ExportFormat::Csv,
+    ExportFormat::Json,
+    ExportFormat::Markdown,
+    ExportFormat::Links,
+    ExportFormat::Collection,
+    ExportFormat::Bundle,
+];
+
+/// Sites that download links can be exported for, along with their names.
+const MIRRORS: [(Mirror, &str); 5] = [
+    (Mirror::Official, "osu! website"),
+    (Mirror::Beatconnect, "Beatconnect"),
+    (Mirror::Nerinyan, "Nerinyan"),
+    (Mirror::Catboy, "Catboy"),
+    (Mirror::Sayobot, "Sayobot"),
+];
+
+/// A dialog for exporting the beatmaps shown in the listing, or only the selected ones.
+///
+/// Files are written on a worker thread, so that large exports don't block the window.
+pub struct ExportDialog {
+    pub visible: bool,
+    scope: Scope,
+    format: ExportFormat,
+    mirror: Mirror,
+
+    /// Name of the collection to add the beatmaps to
+    collection_name: String,
+
+    /// Export that is currently being written
+    #[cfg(not(target_arch = "wasm32"))]
+    task: Option<ExportTask>,
+
+    /// Result of the most recent export, or why it failed
+    status: Option<Result<String, String>>,
+}
+
+/// Represents the columns that are exported, which match the ones shown in the beatmap table.
+#[derive(Clone, Debug)]
+struct ExportColumns {
+    columns: Vec<Column>,
+
+    /// Custom columns, which come after the other columns
+    custom: Vec<CustomColumn>,
+
+    /// Which version of the artist and title to export
+    language: MetadataLanguage,
+}
+
+/// Represents which beatmaps to export.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scope {
+    /// Every beatmap matching the current search
+    View,
+
+    /// Only the selected beatmaps
+    Selection,
+}
+
+/// Represents the different ways that beatmaps can be exported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ExportFormat {
+    Csv,
+
+    /// An array of every beatmap's details
+    Json,
+    Markdown,
+
+    /// Download links for each beatmapset
+    Links,
+
+    /// A new collection, added to the loaded `collection.db`
+    Collection,
+
+    /// A zip archive with statistics, a CSV table, download links, the collections and a report
+    Bundle,
+}
+
+/// Represents an export being written on a worker thread.
+#[cfg(not(target_arch = "wasm32"))]
+struct ExportTask {
+    path: std::path::PathBuf,
+    progress: Arc<Progress>,
+    result: std::sync::mpsc::Receiver<Result<(), Error>>,
+}
+
+/// Represents the progress of an export, shared with the worker thread.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct Progress {
+    written: std::sync::atomic::AtomicUsize,
+    total: std::sync::atomic::AtomicUsize,
+    cancelled: std::sync::atomic::AtomicBool,
+}
+
+impl Default for ExportDialog {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            scope: Scope::View,
+            format: ExportFormat::Csv,
+            mirror: Mirror::Official,
+            collection_name: String::new(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            task: None,
+
+            status: None,
+        }
+    }
+}
+
+impl ExportFormat {
+    /// Gets the name shown for the format.
+    fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Markdown => "Markdown table",
+            ExportFormat::Links => "Download links",
+            ExportFormat::Collection => "New collection",
+            ExportFormat::Bundle => "Bundle (zip)",
+        }
+    }
+
+    /// Gets the suggested file name for exports in this format.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn file_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "beatmaps.csv",
+            ExportFormat::Json => "beatmaps.json",
+            ExportFormat::Markdown => "beatmaps.md",
+            ExportFormat::Links | ExportFormat::Collection => "beatmaps.txt",
+            ExportFormat::Bundle => "osu-library.zip",
+        }
+    }
+}
+
+impl ExportDialog {
+    /// Renders the dialog, exporting the rows of the beatmap table when requested.
+    ///
+    /// The rows shown in `table` are exported in display order, using the columns and version of the artist and title
+    /// that it shows for CSV and Markdown exports. If `collections` is given, a collection listing is loaded, so the
+    /// beatmaps can be added to a new collection (returning the request to do so) and bundles include the collections.
+    pub fn view(
+        &mut self,
+        ctx: &egui::Context,
+        listing: &Arc<BeatmapListing>,
+        table: &BeatmapTable,
+        selected: &BTreeSet<usize>,
+        collections: Option<&CollectionMembership>,
+    ) -> Option<AddToCollection> {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.poll();
+
+        let rows = table.rows();
+        let columns = ExportColumns {
+            columns: table.displayed_columns(),
+            custom: table.custom_columns(),
+            language: table.language(),
+        };
+
+        let can_add_collection = collections.is_some();
+        #[cfg(not(target_arch = "wasm32"))]
+        let collection_listing = collections.map(|collections| Arc::clone(&collections.listing));
+
+        let mut request = None;
+        let mut visible = self.visible;
+
+        egui::Window::new("Export Beatmaps")
+            .open(&mut visible)
+            .resizable(false)
+            .show(ctx, |ui| {
+                // Exported in the same order as the table
+                let rows = match self.scope {
+                    Scope::View => rows.to_vec(),
+                    Scope::Selection => rows
+                        .iter()
+                        .copied()
+                        .filter(|i| selected.contains(i))
+                        .collect(),
+                };
+
+                egui::Grid::new("export_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 8.0])
+                    .show(ui, |ui| {
+                        ui.label("Beatmaps");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.scope, Scope::View, "Current view");
+                            ui.radio_value(&mut self.scope, Scope::Selection, "Selected rows");
+                        });
+                        ui.end_row();
+
+                        ui.label("Format");
+                        egui::ComboBox::from_id_salt("export_format")
+                            .selected_text(self.format.label())
+                            .show_ui(ui, |ui| {
+                                for format in FORMATS {
+                                    ui.selectable_value(&mut self.format, format, format.label());
+                                }
+                            });
+                        ui.end_row();
+
+                        match self.format {
+                            ExportFormat::Links | ExportFormat::Bundle => {
+                                ui.label("Download from");
+                                egui::ComboBox::from_id_salt("export_mirror")
+                                    .selected_text(mirror_name(self.mirror))
+                                    .show_ui(ui, |ui| {
+                                        for (mirror, name) in MIRRORS {
+                                            ui.selectable_value(&mut self.mirror, mirror, name);
+                                        }
+                                    });
+                                ui.end_row();
+                            }
+                            ExportFormat::Collection => {
+                                ui.label("Collection name");
+                                ui.text_edit_singleline(&mut self.collection_name);
+                                ui.end_row();
+                            }
+                            _ => {}
+                        }
+                    });
+
+                ui.label(format!("{} beatmaps will be exported.", format_count(rows.len())));
+
+                if matches!(self.format, ExportFormat::Csv | ExportFormat::Markdown) {
+                    ui.weak("The columns shown in the table are exported, in the same order.");
+                }
+
+                if self.format == ExportFormat::Bundle {
+                    ui.weak(
+                        "The statistics and report cover the whole library. The table has the columns shown, \
+                         without custom columns.",
+                    );
+
+                    if !can_add_collection {
+                        ui.weak("Open a collection.db to include the collections.");
+                    }
+                }
+
+                ui.separator();
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(task) = &self.task {
+                    use std::sync::atomic::Ordering;
+
+                    let written = task.progress.written.load(Ordering::Relaxed);
+                    let total = task.progress.total.load(Ordering::Relaxed).max(1);
+
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::ProgressBar::new(written as f32 / total as f32)
+                                .desired_width(250.0)
+                                .text(format!("{} / {}", format_count(written), format_count(total))),
+                        );
+
+                        if ui.button("Cancel").clicked() {
+                            task.progress.cancelled.store(true, Ordering::Relaxed);
+                        }
+                    });
+
+                    ui.ctx().request_repaint_after(std::time::Duration::from_millis(100));
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    if self.format == ExportFormat::Collection {
+                        let name = self.collection_name.trim();
+
+                        if ui
+                            .add_enabled(
+                                can_add_collection && !name.is_empty() && !rows.is_empty(),
+                                egui::Button::new("Add Collection"),
+                            )
+                            .on_disabled_hover_text(
+                                "Open a collection.db, enter a name and make sure there are beatmaps to add",
+                            )
+                            .clicked()
+                        {
+                            let beatmap_md5s = rows
+                                .iter()
+                                .filter_map(|&i| listing.beatmaps.get(i))
+                                .filter_map(|b| b.md5.clone())
+                                .collect::<Vec<_>>();
+
+                            self.status = Some(Ok(format!(
+                                "Added {} beatmaps to '{}'. Save the collections to write it to collection.db.",
+                                format_count(beatmap_md5s.len()),
+                                name
+                            )));
+
+                            request = Some(AddToCollection {
+                                collection: None,
+                                name: Some(name.to_string()),
+                                beatmap_md5s,
+                            });
+                        }
+                    } else {
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if ui
+                            .add_enabled(!rows.is_empty(), egui::Button::new("Save As..."))
+                            .clicked()
+                        {
+                            if let Some(path) = crate::widgets::file_dialog::FileDialog::save_file(
+                                None,
+                                self.format.file_name(),
+                            ) {
+                                self.start(
+                                    ctx,
+                                    listing,
+                                    collection_listing.clone(),
+                                    rows.clone(),
+                                    columns.clone(),
+                                    path,
+                                );
+                            }
+                        }
+
+                        #[cfg(target_arch = "wasm32")]
+                        ui.add_enabled(false, egui::Button::new("Save As..."))
+                            .on_disabled_hover_text("Files can't be saved on the web");
+                    }
+
+                    if ui
+                        .add_enabled(!rows.is_empty(), egui::Button::new("Copy to Clipboard as TSV"))
+                        .on_hover_text("Copy the table's columns, so that they can be pasted into a spreadsheet")
+                        .clicked()
+                    {
+                        let beatmaps = beatmaps(listing, &rows);
+                        let mut tsv = Vec::new();
+
+                        match export::write_with_custom(
+                            &mut tsv,
+                            Format::Tsv,
+                            &columns.columns,
+                            &columns.custom,
+                            columns.language,
+                            &beatmaps,
+                            |_, _| true,
+                        ) {
+                            Ok(()) => {
+                                ui.ctx().copy_text(String::from_utf8_lossy(&tsv).into_owned());
+                                self.status = Some(Ok(format!(
+                                    "Copied {} beatmaps to the clipboard.",
+                                    format_count(beatmaps.len())
+                                )));
+                            }
+                            Err(e) => self.status = Some(Err(e.to_string())),
+                        }
+                    }
+                });
+
+                match &self.status {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, message);
+                    }
+                    None => {}
+                }
+            });
+
+        self.visible = visible;
+        request
+    }
+
+    /// Starts writing the beatmaps to a file on a worker thread.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start(
+        &mut self,
+        ctx: &egui::Context,
+        listing: &Arc<BeatmapListing>,
+        collections: Option<Arc<CollectionListing>>,
+        rows: Vec<usize>,
+        columns: ExportColumns,
+        path: std::path::PathBuf,
+    ) {
+        use std::sync::atomic::Ordering;
+
+        let progress = Arc::new(Progress::default());
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let worker_progress = Arc::clone(&progress);
+        let worker_path = path.clone();
+        let listing = Arc::clone(listing);
+        let format = self.format;
+        let mirror = self.mirror;
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            let beatmaps = beatmaps(&listing, &rows);
+
+            let progress = |written, total| {
+                worker_progress.written.store(written, Ordering::Relaxed);
+                worker_progress.total.store(total, Ordering::Relaxed);
+                !worker_progress.cancelled.load(Ordering::Relaxed)
+            };
+
+            let result = std::fs::File::create(&worker_path)
+                .map_err(Error::from)
+                .and_then(|file| {
+                    let writer = std::io::BufWriter::new(file);
+
+                    match format {
+                        ExportFormat::Bundle => write_bundle(
+                            writer,
+                            &listing,
+                            collections.as_deref(),
+                            &beatmaps,
+                            mirror,
+                            &columns,
+                            progress,
+                        ),
+                        _ => write_beatmaps(writer, format, mirror, &columns, &beatmaps, progress),
+                    }
+                });
+
+            // Don't leave behind a partially written file
+            if result.is_err() {
+                std::fs::remove_file(&worker_path).ok();
+            }
+
+            if tx.send(result).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+
+        self.status = None;
+        self.task = Some(ExportTask {
+            path,
+            progress,
+            result: rx,
+        });
+    }
+
+    /// Collects the result of the export, if it has finished.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll(&mut self) {
+        let Some(task) = &self.task else {
+            return;
+        };
+
+        self.status = match task.result.try_recv() {
+            Ok(Ok(())) => Some(Ok(format!("Exported to {}", task.path.display()))),
+            Ok(Err(Error::Cancelled)) => Some(Ok("The export was cancelled.".to_string())),
+            Ok(Err(e)) => Some(Err(format!(
+                "Unable to write {}: {}",
+                task.path.display(),
+                e
+            ))),
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                Some(Err("The export stopped unexpectedly.".to_string()))
+            }
+        };
+
+        self.task = None;
+    }
+}
+
+/// Gets the beatmaps for a set of rows.
+fn beatmaps<'a>(listing: &'a BeatmapListing, rows: &[usize]) -> Vec<&'a BeatmapEntry> {
+    rows.iter()
+        .filter_map(|&i| listing.beatmaps.get(i))
+        .collect()
+}
+
+/// Writes beatmaps in one of the file formats. `progress` works the same way as for [`export::write`].
+#[cfg(not(target_arch = "wasm32"))]
+fn write_beatmaps<W: Write>(
+    writer: W,
+    format: ExportFormat,
+    mirror: Mirror,
+    columns: &ExportColumns,
+    beatmaps: &[&BeatmapEntry],
+    progress: impl FnMut(usize, usize) -> bool,
+) -> Result<(), Error> {
+    let format = match format {
+        ExportFormat::Csv => Format::Csv,
+        ExportFormat::Markdown => Format::Markdown,
+        ExportFormat::Links => Format::Links(mirror),
+        ExportFormat::Json => return write_json(writer, beatmaps, progress),
+
+        // Collections are added to the collection listing instead of being written to a file, and bundles are written
+        // by `write_bundle`
+        ExportFormat::Collection | ExportFormat::Bundle => return Ok(()),
+    };
+
+    export::write_with_custom(
+        writer,
+        format,
+        &columns.columns,
+        &columns.custom,
+        columns.language,
+        beatmaps,
+        progress,
+    )
+}
+
+/// Writes a zip archive with statistics and a report for the whole library, the beatmaps as CSV and download links,
+/// and each collection as text. `progress` is called with the number of files written so far.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_bundle<W: Write + std::io::Seek>(
+    writer: W,
+    listing: &BeatmapListing,
+    collections: Option<&CollectionListing>,
+    beatmaps: &[&BeatmapEntry],
+    mirror: Mirror,
+    columns: &ExportColumns,
+    progress: impl FnMut(usize, usize) -> bool,
+) -> Result<(), Error> {
+    let mut entries = vec![
+        BundleEntry::Stats,
+        BundleEntry::Table {
+            columns: columns.columns.clone(),
+            beatmaps: beatmaps.to_vec(),
+        },
+        BundleEntry::Links {
+            mirror,
+            beatmaps: beatmaps.to_vec(),
+        },
+    ];
+
+    entries.extend(
+        collections
+            .iter()
+            .flat_map(|collections| &collections.collections)
+            .map(BundleEntry::Collection),
+    );
+
+    entries.push(BundleEntry::Report(ReportOptions {
+        title: "My osu! Library".to_string(),
+        player_name: listing.player_name_str().map(str::to_string),
+        top_count: 10,
+        table: None,
+        language: columns.language,
+        generated: time::OffsetDateTime::now_utc(),
+    }));
+
+    export::bundle(writer, listing, &entries, columns.language, progress)?.flush()?;
+    Ok(())
+}
+
+/// Writes beatmaps as a JSON array, with one beatmap on each line.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_json<W: Write>(
+    mut writer: W,
+    beatmaps: &[&BeatmapEntry],
+    mut progress: impl FnMut(usize, usize) -> bool,
+) -> Result<(), Error> {
+    writer.write_all(b"[")?;
+
+    for (i, beatmap) in beatmaps.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+
+        writer.write_all(b"\n  ")?;
+        serde_json::to_writer(&mut writer, beatmap).map_err(std::io::Error::from)?;
+
+        if !progress(i + 1, beatmaps.len()) {
+            return Err(Error::Cancelled);
+        }
+    }
+
+    writer.write_all(b"\n]\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Gets the name shown for a mirror.
+fn mirror_name(mirror: Mirror) -> &'static str {
+    MIRRORS
+        .iter()
+        .find(|(m, _)| *m == mirror)
+        .map(|(_, name)| *name)
+        .unwrap_or_default()
+}
@@ -0,0 +1,365 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use osu_db_parser::detect::FileKind;
+
+/// How long a file must go without changing before it's treated as fully written.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Number of times to retry reloading a file that couldn't be parsed, e.g. because osu! was still writing to it.
+const RELOAD_RETRIES: u32 = 5;
+
+/// Delay before retrying a failed reload, which doubles after each attempt.
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Watches the loaded database files for changes made while the viewer is open, e.g. by osu!, and shows a banner
+/// offering to reload them.
+///
+/// osu! may replace a file rather than writing to it, so the folders containing the files are watched instead of the
+/// files themselves.
+#[derive(Default)]
+pub struct FileWatcher {
+    watcher: Option<RecommendedWatcher>,
+    events: Option<mpsc::Receiver<notify::Result<Event>>>,
+
+    /// Loaded files, at most one of each kind
+    files: Vec<WatchedFile>,
+
+    /// Folders that are being watched
+    directories: HashSet<PathBuf>,
+
+    /// Whether the loaded files have changed since the folders being watched were last updated
+    files_changed: bool,
+}
+
+/// Represents a loaded file that is being watched.
+struct WatchedFile {
+    kind: FileKind,
+    path: PathBuf,
+
+    /// Size and modification time of the version the viewer has, i.e. when it was last loaded or saved
+    known: Option<FileStamp>,
+
+    state: FileState,
+
+    /// Number of times reloading the file has failed since it was last loaded
+    attempts: u32,
+}
+
+/// Represents whether a watched file has changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileState {
+    Unchanged,
+
+    /// The file has changed, and may still be being written. `since` is when it last changed, and `reload` is whether
+    /// to reload it straight away once it settles, since a reload was already in progress.
+    Settling {
+        since: Instant,
+        stamp: Option<FileStamp>,
+        reload: bool,
+    },
+
+    /// The file has changed, and can be reloaded
+    Changed,
+
+    /// Reloading the file failed, and will be retried at this time
+    Retrying(Instant),
+}
+
+/// Identifies a version of a file by its size and modification time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileWatcher {
+    /// Starts watching a file that has been loaded, replacing any file of the same kind.
+    pub fn watch(&mut self, kind: FileKind, path: &Path) {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        self.files.retain(|file| file.kind != kind);
+        self.files.push(WatchedFile {
+            kind,
+            known: FileStamp::of(&path),
+            path,
+            state: FileState::Unchanged,
+            attempts: 0,
+        });
+
+        self.files_changed = true;
+    }
+
+    /// Records that the viewer has written to a file itself, so that the change isn't offered for reloading.
+    pub fn saved(&mut self, path: &Path) {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        if let Some(file) = self.files.iter_mut().find(|file| file.path == path) {
+            file.known = FileStamp::of(&path);
+            file.state = FileState::Unchanged;
+        }
+    }
+
+    /// Schedules another attempt at reloading a file that couldn't be parsed, returning `false` if it has already been
+    /// retried too many times.
+    pub fn retry(&mut self, kind: FileKind) -> bool {
+        let Some(file) = self.files.iter_mut().find(|file| file.kind == kind) else {
+            return false;
+        };
+
+        if file.attempts >= RELOAD_RETRIES {
+            file.attempts = 0;
+            return false;
+        }
+
+        file.state = FileState::Retrying(Instant::now() + RETRY_DELAY * 2u32.pow(file.attempts));
+        file.attempts += 1;
+        true
+    }
+
+    /// Processes the changes reported since this was last called, returning the files whose reloads are due to be
+    /// retried.
+    pub fn poll(&mut self, ctx: &egui::Context) -> Vec<(FileKind, PathBuf)> {
+        if std::mem::take(&mut self.files_changed) {
+            self.update_directories(ctx);
+        }
+
+        let now = Instant::now();
+
+        if let Some(events) = &self.events {
+            for event in events.try_iter() {
+                let event = match event {
+                    Ok(event) if !matches!(event.kind, EventKind::Access(_)) => event,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        log::warn!("Unable to watch for file changes: {}", e);
+                        continue;
+                    }
+                };
+
+                for file in &mut self.files {
+                    if event.paths.contains(&file.path) {
+                        let reload = matches!(
+                            file.state,
+                            FileState::Retrying(_) | FileState::Settling { reload: true, .. }
+                        );
+
+                        file.state = FileState::Settling {
+                            since: now,
+                            stamp: FileStamp::of(&file.path),
+                            reload,
+                        };
+                    }
+                }
+            }
+        }
+
+        let mut due = Vec::new();
+
+        for file in &mut self.files {
+            match file.state {
+                FileState::Settling {
+                    since,
+                    stamp,
+                    reload,
+                } => {
+                    let elapsed = now - since;
+
+                    if elapsed < SETTLE_TIME {
+                        ctx.request_repaint_after(SETTLE_TIME - elapsed);
+                        continue;
+                    }
+
+                    // Keep waiting while the file is still being written
+                    let current = FileStamp::of(&file.path);
+
+                    file.state = if current != stamp {
+                        ctx.request_repaint_after(SETTLE_TIME);
+                        FileState::Settling {
+                            since: now,
+                            stamp: current,
+                            reload,
+                        }
+                    } else if current == file.known {
+                        // e.g. the viewer's own save, or the file was only touched
+                        FileState::Unchanged
+                    } else if reload {
+                        due.push((file.kind, file.path.clone()));
+                        FileState::Unchanged
+                    } else {
+                        FileState::Changed
+                    };
+                }
+                FileState::Retrying(at) if at <= now => {
+                    due.push((file.kind, file.path.clone()));
+                    file.state = FileState::Unchanged;
+                }
+                FileState::Retrying(at) => ctx.request_repaint_after(at - now),
+                FileState::Unchanged | FileState::Changed => {}
+            }
+        }
+
+        due
+    }
+
+    /// Renders a banner listing the files that have changed on disk, returning the files to reload if the user asks to
+    /// reload them. `collections_modified` is whether the loaded collections have unsaved changes, in which case
+    /// `collection.db` is only reloaded if the user confirms that the changes should be discarded.
+    pub fn banner(
+        &mut self,
+        ctx: &egui::Context,
+        collections_modified: bool,
+    ) -> Vec<(FileKind, PathBuf)> {
+        let mut reload = Vec::new();
+
+        let changed = self
+            .files
+            .iter()
+            .filter(|file| file.state == FileState::Changed)
+            .collect::<Vec<_>>();
+
+        if changed.is_empty() {
+            return reload;
+        }
+
+        let names = changed
+            .iter()
+            .map(|file| file_name(&file.path))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let discards_changes = collections_modified
+            && changed
+                .iter()
+                .any(|file| file.kind == FileKind::Collections);
+
+        let mut dismissed = false;
+
+        egui::TopBottomPanel::top("reload_banner").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("⟳ {} changed on disk", names),
+                );
+
+                let reloadable = changed
+                    .iter()
+                    .filter(|file| !discards_changes || file.kind != FileKind::Collections)
+                    .map(|file| (file.kind, file.path.clone()))
+                    .collect::<Vec<_>>();
+
+                if !reloadable.is_empty()
+                    && ui
+                        .button("Reload")
+                        .on_hover_text("Keeps the current search, sorting and selection")
+                        .clicked()
+                {
+                    reload = reloadable;
+                }
+
+                if discards_changes {
+                    ui.separator();
+                    ui.label("The collections have unsaved changes, which reloading would discard");
+
+                    if ui.button("Reload Collections Anyway").clicked() {
+                        reload.extend(
+                            changed
+                                .iter()
+                                .filter(|file| file.kind == FileKind::Collections)
+                                .map(|file| (file.kind, file.path.clone())),
+                        );
+                    }
+                }
+
+                if ui.button("Dismiss").clicked() {
+                    dismissed = true;
+                }
+            });
+        });
+
+        for file in &mut self.files {
+            if dismissed && file.state == FileState::Changed {
+                // Treat the new version as known, so that the banner only comes back if it changes again
+                file.known = FileStamp::of(&file.path);
+                file.state = FileState::Unchanged;
+            } else if reload.iter().any(|(kind, _)| *kind == file.kind) {
+                file.state = FileState::Unchanged;
+            }
+        }
+
+        reload
+    }
+
+    /// Starts or stops watching folders so that the folder of every loaded file is watched.
+    fn update_directories(&mut self, ctx: &egui::Context) {
+        let directories = self
+            .files
+            .iter()
+            .filter_map(|file| file.path.parent().map(Path::to_path_buf))
+            .collect::<HashSet<_>>();
+
+        if self.watcher.is_none() {
+            let (tx, rx) = mpsc::channel();
+            let ctx = ctx.clone();
+
+            let watcher = notify::recommended_watcher(move |event| {
+                if tx.send(event).is_ok() {
+                    ctx.request_repaint();
+                }
+            });
+
+            match watcher {
+                Ok(watcher) => {
+                    self.watcher = Some(watcher);
+                    self.events = Some(rx);
+                }
+                Err(e) => {
+                    log::warn!("Unable to watch for file changes: {}", e);
+                    return;
+                }
+            }
+        }
+
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+
+        for directory in self.directories.difference(&directories) {
+            if let Err(e) = watcher.unwatch(directory) {
+                log::warn!("Unable to stop watching '{}': {}", directory.display(), e);
+            }
+        }
+
+        for directory in directories.difference(&self.directories) {
+            if let Err(e) = watcher.watch(directory, RecursiveMode::NonRecursive) {
+                log::warn!("Unable to watch '{}': {}", directory.display(), e);
+            }
+        }
+
+        self.directories = directories;
+    }
+}
+
+impl FileStamp {
+    /// Gets the current stamp of a file, or `None` if it can't be read, e.g. because it's being replaced.
+    fn of(path: &Path) -> Option<FileStamp> {
+        let metadata = std::fs::metadata(path).ok()?;
+
+        Some(FileStamp {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// Gets the name of a file for showing in the banner.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
@@ -0,0 +1,284 @@
+use osu_db_parser::{
+    format,
+    prelude::*,
+    query::{Condition, NumericKey, Operator},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::widgets::range_slider::RangeSlider;
+
+/// Upper end of the star rating slider. Beatmaps with a higher rating are included when the slider is at this value.
+const MAX_STARS: f64 = 10.0;
+
+/// Upper end of the length slider in seconds. Longer beatmaps are included when the slider is at this value.
+const MAX_LENGTH: f64 = 600.0;
+
+/// Gameplay modes shown as chips, in the order they appear.
+const MODES: [GameplayMode; 4] = [
+    GameplayMode::Standard,
+    GameplayMode::Taiko,
+    GameplayMode::Catch,
+    GameplayMode::Mania,
+];
+
+/// Ranked statuses shown as chips, in the order they appear.
+const STATUSES: [RankedStatus; 6] = [
+    RankedStatus::Ranked,
+    RankedStatus::Approved,
+    RankedStatus::Qualified,
+    RankedStatus::Loved,
+    RankedStatus::Pending,
+    RankedStatus::Unsubmitted,
+];
+
+/// A bar of quick filters for the beatmap listing, which are combined with the search query.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterBar {
+    /// Selected gameplay modes. If empty, beatmaps for any mode are shown.
+    modes: Vec<GameplayMode>,
+
+    /// Whether osu!standard beatmaps should be shown when filtering on other modes
+    converts: bool,
+
+    /// Selected ranked statuses. If empty, beatmaps with any status are shown.
+    statuses: Vec<RankedStatus>,
+
+    /// Whether to only show beatmaps that haven't been played
+    unplayed: bool,
+
+    /// Star rating range, applied to the NoMod rating for the selected mode
+    stars: (f64, f64),
+
+    /// Length range, in seconds
+    length: (f64, f64),
+}
+
+impl Default for FilterBar {
+    fn default() -> Self {
+        Self {
+            modes: Vec::new(),
+            converts: false,
+            statuses: Vec::new(),
+            unplayed: false,
+            stars: (0.0, MAX_STARS),
+            length: (0.0, MAX_LENGTH),
+        }
+    }
+}
+
+impl FilterBar {
+    /// Creates a filter bar showing beatmaps within a range of star ratings. The range is unbounded if `high` is past
+    /// the end of the slider.
+    pub fn with_stars(low: f64, high: f64) -> Self {
+        Self {
+            stars: (low.min(MAX_STARS), high.min(MAX_STARS)),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a filter bar showing beatmaps for a gameplay mode.
+    pub fn with_mode(mode: GameplayMode) -> Self {
+        Self {
+            modes: vec![mode],
+            ..Default::default()
+        }
+    }
+
+    /// Creates a filter bar showing beatmaps with a ranked status.
+    pub fn with_status(status: RankedStatus) -> Self {
+        Self {
+            statuses: vec![status],
+            ..Default::default()
+        }
+    }
+
+    /// Gets the gameplay mode being filtered on, if only one is selected. In a tab for one mode (`tab`), that mode is
+    /// always used.
+    pub fn single_mode(&self, tab: Option<GameplayMode>) -> Option<GameplayMode> {
+        match (tab, self.modes.as_slice()) {
+            (Some(mode), _) | (None, &[mode]) => Some(mode),
+            _ => None,
+        }
+    }
+
+    /// Gets the gameplay modes being filtered on, which is only the tab's mode in a tab for one mode.
+    fn shown_modes(&self, tab: Option<GameplayMode>) -> Vec<GameplayMode> {
+        match tab {
+            Some(mode) => vec![mode],
+            None => self.modes.clone(),
+        }
+    }
+
+    /// Gets the condition on the gameplay mode, if any modes are being filtered on.
+    pub fn mode_condition(&self, tab: Option<GameplayMode>) -> Option<Condition> {
+        let modes = self.shown_modes(tab);
+
+        (!modes.is_empty()).then_some(Condition::AnyMode {
+            modes,
+            converts: self.converts,
+        })
+    }
+
+    /// Checks whether any of the filters have been changed from their defaults.
+    pub fn is_active(&self) -> bool {
+        *self != FilterBar::default()
+    }
+
+    /// Gets the conditions for the current filters, to be added to the search query's filter. In a tab for one mode
+    /// (`tab`), the selected modes are replaced by the tab's mode.
+    pub fn conditions(&self, tab: Option<GameplayMode>) -> Vec<Condition> {
+        let mut conditions = Vec::from_iter(self.mode_condition(tab));
+
+        if !self.statuses.is_empty() {
+            conditions.push(Condition::AnyStatus(self.statuses.clone()));
+        }
+
+        if self.unplayed {
+            conditions.push(Condition::Unplayed(true));
+        }
+
+        // If a single mode is selected, use its star rating so that converts are rated for that mode. Otherwise, the
+        // star rating chosen in the settings is used.
+        let stars = match self.single_mode(tab) {
+            Some(mode) => NumericKey::ModeStars(mode),
+            None => NumericKey::Stars,
+        };
+
+        conditions.extend(range_conditions(stars, self.stars, MAX_STARS));
+        conditions.extend(range_conditions(
+            NumericKey::Length,
+            self.length,
+            MAX_LENGTH,
+        ));
+
+        conditions
+    }
+
+    /// Renders the filter bar, returning whether any of the filters were changed. In a tab for one mode (`tab`), the
+    /// mode can't be chosen, but converts can still be included.
+    pub fn show(&mut self, ui: &mut egui::Ui, tab: Option<GameplayMode>) -> bool {
+        let before = self.clone();
+
+        ui.horizontal_wrapped(|ui| {
+            if tab.is_none() {
+                for mode in MODES {
+                    chip(ui, &mut self.modes, mode);
+                }
+            }
+
+            ui.add_enabled(
+                self.shown_modes(tab)
+                    .iter()
+                    .any(|m| *m != GameplayMode::Standard),
+                egui::Checkbox::new(&mut self.converts, "Converts"),
+            )
+            .on_hover_text("Include osu!standard beatmaps, which can be converted to other modes");
+
+            ui.separator();
+
+            for status in STATUSES {
+                chip(ui, &mut self.statuses, status);
+            }
+
+            ui.separator();
+
+            ui.toggle_value(&mut self.unplayed, "Unplayed")
+                .on_hover_text("Only show beatmaps that haven't been played");
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Stars");
+            ui.add(
+                RangeSlider::new(&mut self.stars.0, &mut self.stars.1, 0.0..=MAX_STARS)
+                    .step_by(0.1),
+            );
+            ui.label(format!(
+                "{:.1} – {}",
+                self.stars.0,
+                range_end(self.stars.1, MAX_STARS, |stars| format!("{:.1}", stars))
+            ));
+
+            ui.separator();
+
+            ui.label("Length");
+            ui.add(
+                RangeSlider::new(&mut self.length.0, &mut self.length.1, 0.0..=MAX_LENGTH)
+                    .step_by(15.0),
+            );
+            ui.label(format!(
+                "{} – {}",
+                format_length(self.length.0),
+                range_end(self.length.1, MAX_LENGTH, format_length)
+            ));
+
+            ui.separator();
+
+            if ui
+                .add_enabled(self.is_active(), egui::Button::new("Clear Filters"))
+                .clicked()
+            {
+                *self = FilterBar::default();
+            }
+        });
+
+        *self != before
+    }
+}
+
+/// Renders a chip which adds or removes a value from a set of selected values when clicked.
+fn chip<T: Copy + PartialEq + std::fmt::Display>(
+    ui: &mut egui::Ui,
+    selected: &mut Vec<T>,
+    value: T,
+) {
+    let is_selected = selected.contains(&value);
+
+    if ui
+        .selectable_label(is_selected, value.to_string())
+        .clicked()
+    {
+        if is_selected {
+            selected.retain(|v| *v != value);
+        } else {
+            selected.push(value);
+        }
+    }
+}
+
+/// Gets the conditions for a slider range. The ends of the slider are left unbounded.
+fn range_conditions(key: NumericKey, (low, high): (f64, f64), max: f64) -> Vec<Condition> {
+    let mut conditions = Vec::new();
+
+    if low > 0.0 {
+        conditions.push(Condition::Numeric {
+            key,
+            op: Operator::GreaterOrEqual,
+            value: low,
+        });
+    }
+
+    if high < max {
+        conditions.push(Condition::Numeric {
+            key,
+            op: Operator::LessOrEqual,
+            value: high,
+        });
+    }
+
+    conditions
+}
+
+/// Formats the upper end of a slider range, showing that it is unbounded if it is at the end of the slider.
+fn range_end(value: f64, max: f64, format: impl Fn(f64) -> String) -> String {
+    if value < max {
+        format(value)
+    } else {
+        format!("{}+", format(value))
+    }
+}
+
+/// Formats a length in seconds as `m:ss`.
+fn format_length(seconds: f64) -> String {
+    format::duration_mmss(time::Duration::seconds(seconds.round() as i64))
+}
@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+
+/// Name of the fallback font in egui's font definitions.
+const CJK_FONT_NAME: &str = "cjk_fallback";
+
+/// Fonts covering Chinese, Japanese and Korean characters which are usually installed, checked in order when no font
+/// has been chosen in the settings.
+const SYSTEM_FONTS: &[&str] = &[
+    // Windows
+    r"C:\Windows\Fonts\YuGothM.ttc",
+    r"C:\Windows\Fonts\msgothic.ttc",
+    r"C:\Windows\Fonts\meiryo.ttc",
+    r"C:\Windows\Fonts\msyh.ttc",
+    r"C:\Windows\Fonts\malgun.ttf",
+    // macOS
+    "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/System/Library/Fonts/AppleSDGothicNeo.ttc",
+    "/Library/Fonts/Arial Unicode.ttf",
+    // Linux
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+];
+
+/// Loads a font covering Chinese, Japanese and Korean characters, which are common in Unicode artists and titles but
+/// aren't covered by egui's default fonts.
+///
+/// The font is added after the default fonts, so it's only used for characters they don't have. This means that text
+/// mixing scripts keeps the default font's spacing for Latin characters.
+#[derive(Default)]
+pub struct FontFallback {
+    /// Font chosen in the settings when the fonts were last set, or `None` if they haven't been set yet
+    requested: Option<Option<PathBuf>>,
+
+    /// Font that was loaded, if any
+    pub loaded: Option<PathBuf>,
+}
+
+impl FontFallback {
+    /// Loads the chosen font, or the first system font found if `path` is `None`. Nothing is done if the same font was
+    /// requested last time, since rebuilding the font atlas is slow.
+    pub fn apply(&mut self, ctx: &egui::Context, path: Option<&Path>) {
+        if self.requested.as_ref().map(Option::as_deref) == Some(path) {
+            return;
+        }
+
+        self.requested = Some(path.map(Path::to_path_buf));
+
+        let candidates = match path {
+            Some(path) => vec![path.to_path_buf()],
+            None => SYSTEM_FONTS.iter().map(PathBuf::from).collect(),
+        };
+
+        let mut fonts = egui::FontDefinitions::default();
+        self.loaded = None;
+
+        for candidate in candidates.into_iter().filter(|path| path.is_file()) {
+            match std::fs::read(&candidate) {
+                // egui panics if a font can't be parsed, so make sure the file is at least a font
+                Ok(data) if !is_font(&data) => {
+                    log::warn!(
+                        "'{}' is not a TrueType or OpenType font",
+                        candidate.display()
+                    )
+                }
+                Ok(data) => {
+                    fonts
+                        .font_data
+                        .insert(CJK_FONT_NAME.to_string(), egui::FontData::from_owned(data));
+
+                    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+                        fonts
+                            .families
+                            .entry(family)
+                            .or_default()
+                            .push(CJK_FONT_NAME.to_string());
+                    }
+
+                    log::info!("Using '{}' for CJK text", candidate.display());
+                    self.loaded = Some(candidate);
+                    break;
+                }
+                Err(e) => log::warn!("Unable to read font '{}': {}", candidate.display(), e),
+            }
+        }
+
+        // Resetting the fonts also removes a font that was loaded before
+        ctx.set_fonts(fonts);
+    }
+}
+
+/// Checks whether a file starts with the signature of a TrueType or OpenType font, or a collection of them.
+fn is_font(data: &[u8]) -> bool {
+    matches!(
+        data.get(..4),
+        Some([0x00, 0x01, 0x00, 0x00] | b"OTTO" | b"ttcf" | b"true")
+    )
+}
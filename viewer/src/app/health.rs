@@ -0,0 +1,924 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+};
+
+use egui_extras::{Column as TableColumn, TableBuilder};
+use osu_db_parser::{
+    beatmaps::DEFAULT_OUTDATED_TOLERANCE, format, hashes, health, paths, prelude::*,
+};
+use time::UtcOffset;
+
+use crate::widgets::file_dialog::FileDialog;
+
+use super::{accessibility, format_count, reveal_in_file_manager};
+
+/// How often to repaint while a check is running, in beatmaps or folders checked.
+const REPAINT_INTERVAL: usize = 500;
+
+/// Name suggested for the cleaned up copy of `osu!.db`.
+const CLEANED_FILE_NAME: &str = "osu!.cleaned.db";
+
+/// Name of the file in the data folder that remembers the hashes of `.osu` files.
+const HASH_CACHE_FILE: &str = "osu-file-hashes.txt";
+
+/// A view which runs health checks on the loaded beatmap listing, and saves a copy of it without the beatmaps that the
+/// user selects.
+#[derive(Default)]
+pub struct HealthView {
+    /// Beatmap listing being checked
+    listing: Option<Arc<BeatmapListing>>,
+
+    /// File the beatmap listing was loaded from, which the cleaned copy must not replace
+    source_path: Option<PathBuf>,
+
+    /// osu! installation folder, used to check for missing files and orphan folders
+    osu_directory: Option<PathBuf>,
+
+    /// Which version of the artist and title the beatmaps found by checks are named with
+    language: MetadataLanguage,
+
+    /// The user's notes, used to check for notes about beatmaps that are no longer in the listing
+    notes: Arc<Notes>,
+
+    /// State of each check, in the same order as [`Check::ALL`]
+    sections: [Section; 8],
+    current: Check,
+
+    /// Beatmaps to leave out of the cleaned copy, as indices into the listing
+    selected: BTreeSet<usize>,
+
+    /// Result of the most recent save
+    status: Option<String>,
+}
+
+/// Represents the checks that can be run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Check {
+    #[default]
+    Duplicates,
+    Validation,
+    MissingFiles,
+    ModifiedFiles,
+    StaleMetadata,
+    PossiblyOutdated,
+    OrphanFolders,
+    OrphanedNotes,
+}
+
+/// Holds the state for one of the checks.
+#[derive(Default)]
+struct Section {
+    /// Check that is currently running, if any
+    scan: Option<Scan>,
+
+    /// Findings from the most recent run, or why it failed
+    findings: Option<Result<Vec<Finding>, String>>,
+
+    /// Extra details about the most recent run, e.g. how many files had to be read
+    summary: Option<String>,
+
+    sort: Option<(FindingColumn, SortOrder)>,
+}
+
+/// Represents a problem found by one of the checks.
+struct Finding {
+    /// Index of the affected beatmap, or `None` if the finding isn't about a beatmap in the listing
+    index: Option<usize>,
+
+    /// Name of the affected beatmap or folder
+    name: String,
+    details: String,
+
+    /// Whether the beatmap should be left out of the cleaned copy when selecting every finding
+    suggested: bool,
+
+    /// Folder that can be shown in the file manager
+    folder: Option<PathBuf>,
+}
+
+/// Represents the sortable columns in the findings table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FindingColumn {
+    Name,
+    Details,
+}
+
+/// Runs a check on a worker thread, so that the window stays responsive while checking large listings.
+struct Scan {
+    progress: Arc<Progress>,
+    result: mpsc::Receiver<ScanResult>,
+}
+
+/// Represents the result of a check.
+struct ScanResult {
+    findings: Result<Vec<Finding>, Error>,
+
+    /// Extra details about the run, e.g. how many files had to be read
+    summary: Option<String>,
+}
+
+/// Represents the progress of a check, shared with the worker thread.
+#[derive(Default)]
+struct Progress {
+    checked: AtomicUsize,
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Check {
+    const ALL: [Check; 8] = [
+        Check::Duplicates,
+        Check::Validation,
+        Check::MissingFiles,
+        Check::ModifiedFiles,
+        Check::StaleMetadata,
+        Check::PossiblyOutdated,
+        Check::OrphanFolders,
+        Check::OrphanedNotes,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Check::Duplicates => "Duplicates",
+            Check::Validation => "Invalid Values",
+            Check::MissingFiles => "Missing Files",
+            Check::ModifiedFiles => "Modified Files",
+            Check::StaleMetadata => "Stale Metadata",
+            Check::PossiblyOutdated => "Possibly Outdated",
+            Check::OrphanFolders => "Orphan Folders",
+            Check::OrphanedNotes => "Orphaned Notes",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Check::Duplicates => "Beatmaps which are listed more than once with the same MD5 hash. The first copy of each beatmap is treated as the original.",
+            Check::Validation => "Beatmaps with values that look invalid, e.g. because their entry is corrupt.",
+            Check::MissingFiles => "Beatmaps whose .osu file no longer exists in the Songs folder.",
+            Check::ModifiedFiles => "Beatmaps whose .osu file has changed since osu! last read it, so its MD5 hash no longer matches osu!.db. Hashes are remembered, so running this again only reads files which have changed.",
+            Check::StaleMetadata => "Beatmaps whose title, artist, creator, difficulty name, difficulty settings or IDs in osu!.db are different from their .osu file, e.g. because it was edited outside of osu!. Beatmaps whose .osu file is missing are skipped.",
+            Check::PossiblyOutdated => "Beatmaps whose .osu file was modified more than 30 days before or after osu! last checked it online, so there may be an update that hasn't been downloaded. Unsubmitted beatmaps are skipped. The same beatmaps are found by searching for outdated=true.",
+            Check::OrphanFolders => "Folders in the Songs folder which aren't used by any beatmap. These aren't in osu!.db, so they can't be removed from the cleaned copy.",
+            Check::OrphanedNotes => "Your notes and stars for beatmaps which aren't in osu!.db, e.g. because they were deleted or updated. These are kept in case the beatmap is added again.",
+        }
+    }
+
+    /// Checks whether the check looks at files in the osu! installation folder.
+    fn needs_osu_directory(self) -> bool {
+        matches!(
+            self,
+            Check::MissingFiles
+                | Check::ModifiedFiles
+                | Check::StaleMetadata
+                | Check::OrphanFolders
+        )
+    }
+
+    /// Starts running the check on a worker thread.
+    fn start(
+        self,
+        ctx: &egui::Context,
+        listing: Arc<BeatmapListing>,
+        osu_directory: Option<PathBuf>,
+        notes: Arc<Notes>,
+        language: MetadataLanguage,
+    ) -> Scan {
+        Scan::start(ctx, move |progress, summary| {
+            let beatmaps = &listing.beatmaps;
+            let osu_directory = osu_directory.unwrap_or_default();
+
+            let findings = match self {
+                Check::Duplicates => health::duplicates(beatmaps)
+                    .into_iter()
+                    .flat_map(|duplicate| {
+                        let md5 = duplicate.md5;
+
+                        duplicate
+                            .indices
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, index)| {
+                                let copy = if i == 0 { "original" } else { "copy" };
+                                Finding::beatmap(
+                                    beatmaps,
+                                    language,
+                                    index,
+                                    format!("{} ({})", md5, copy),
+                                    i > 0,
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect(),
+                Check::Validation => {
+                    let mut findings = Vec::new();
+
+                    for (index, beatmap) in beatmaps.iter().enumerate() {
+                        for warning in health::validate(beatmap) {
+                            findings.push(Finding::beatmap(
+                                beatmaps,
+                                language,
+                                index,
+                                warning.to_string(),
+                                true,
+                            ));
+                        }
+
+                        if !progress(index + 1, beatmaps.len()) {
+                            return Err(Error::Cancelled);
+                        }
+                    }
+
+                    findings
+                }
+                Check::MissingFiles => health::missing_files(&osu_directory, beatmaps, progress)?
+                    .into_iter()
+                    .map(|missing| {
+                        let details = format!("Expected at {}", missing.path.display());
+                        Finding::beatmap(beatmaps, language, missing.index, details, true)
+                    })
+                    .collect(),
+                Check::ModifiedFiles => {
+                    let cache_path = hash_cache_path();
+                    let mut cache = cache_path
+                        .as_deref()
+                        .and_then(|path| HashCache::load(path).ok())
+                        .unwrap_or_default();
+
+                    let songs_dir = paths::songs_dir_guess(&osu_directory, None);
+                    let hashes =
+                        hashes::hash_osu_files(&songs_dir, beatmaps, &mut cache, true, progress);
+
+                    // Files hashed before cancelling are still worth remembering
+                    if let Some(path) = &cache_path {
+                        if let Err(e) = cache.save(path) {
+                            log::warn!("Unable to save file hashes to '{}': {}", path.display(), e);
+                        }
+                    }
+
+                    let stats = cache.stats();
+                    *summary = Some(format!(
+                        "Hashed {} changed files, {} cached",
+                        format_count(stats.misses),
+                        format_count(stats.hits)
+                    ));
+
+                    hashes?
+                        .into_iter()
+                        .filter(|(index, md5)| {
+                            beatmaps[*index]
+                                .md5
+                                .as_deref()
+                                .is_some_and(|expected| !md5.matches(expected))
+                        })
+                        .map(|(index, md5)| {
+                            let details = format!("File hash is {}", md5);
+                            Finding::beatmap(beatmaps, language, index, details, false)
+                        })
+                        .collect()
+                }
+                Check::StaleMetadata => {
+                    let songs_dir = paths::songs_dir_guess(&osu_directory, None);
+
+                    health::stale_metadata(&songs_dir, beatmaps, progress)?
+                        .into_iter()
+                        .flat_map(|stale| {
+                            stale.mismatches.into_iter().map(move |mismatch| {
+                                Finding::beatmap(
+                                    beatmaps,
+                                    language,
+                                    stale.index,
+                                    mismatch.to_string(),
+                                    false,
+                                )
+                            })
+                        })
+                        .collect()
+                }
+                Check::PossiblyOutdated => beatmaps
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, beatmap)| beatmap.is_possibly_outdated(DEFAULT_OUTDATED_TOLERANCE))
+                    .map(|(index, beatmap)| {
+                        let details = format!(
+                            "Modified {}, last checked online {}",
+                            format::short_date(beatmap.last_modification_time, UtcOffset::UTC),
+                            format::short_date(beatmap.last_checked_online, UtcOffset::UTC)
+                        );
+                        Finding::beatmap(beatmaps, language, index, details, false)
+                    })
+                    .collect(),
+                Check::OrphanFolders => health::orphan_folders(&osu_directory, beatmaps, progress)?
+                    .into_iter()
+                    .map(|folder| Finding {
+                        index: None,
+                        name: folder
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default(),
+                        details: folder.display().to_string(),
+                        suggested: false,
+                        folder: Some(folder),
+                    })
+                    .collect(),
+                Check::OrphanedNotes => notes
+                    .orphaned(beatmaps)
+                    .into_iter()
+                    .map(|orphaned| {
+                        let star = if orphaned.note.starred { "★ " } else { "" };
+
+                        Finding {
+                            index: None,
+                            name: orphaned.md5.to_string(),
+                            details: format!("{}{}", star, orphaned.note.text.trim()),
+                            suggested: false,
+                            folder: None,
+                        }
+                    })
+                    .collect(),
+            };
+
+            Ok(findings)
+        })
+    }
+}
+
+impl Finding {
+    /// Creates a finding about a beatmap in the listing.
+    fn beatmap(
+        beatmaps: &[BeatmapEntry],
+        language: MetadataLanguage,
+        index: usize,
+        details: String,
+        suggested: bool,
+    ) -> Self {
+        Self {
+            index: Some(index),
+            name: beatmaps[index].display_name(language),
+            details,
+            suggested,
+            folder: None,
+        }
+    }
+}
+
+impl Scan {
+    /// Starts running `check` on a worker thread. `check` is given a callback for reporting its progress, which returns
+    /// `false` once the check has been cancelled, and somewhere to put a summary of the run.
+    fn start<F>(ctx: &egui::Context, check: F) -> Self
+    where
+        F: FnOnce(
+                &mut dyn FnMut(usize, usize) -> bool,
+                &mut Option<String>,
+            ) -> Result<Vec<Finding>, Error>
+            + Send
+            + 'static,
+    {
+        let progress = Arc::new(Progress::default());
+        let (tx, rx) = mpsc::channel();
+        let worker_progress = Arc::clone(&progress);
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            let mut summary = None;
+            let result = check(
+                &mut |checked, total| {
+                    worker_progress.checked.store(checked, Ordering::Relaxed);
+                    worker_progress.total.store(total, Ordering::Relaxed);
+
+                    if checked % REPAINT_INTERVAL == 0 {
+                        ctx.request_repaint();
+                    }
+
+                    !worker_progress.cancelled.load(Ordering::Relaxed)
+                },
+                &mut summary,
+            );
+
+            if tx
+                .send(ScanResult {
+                    findings: result,
+                    summary,
+                })
+                .is_ok()
+            {
+                ctx.request_repaint();
+            }
+        });
+
+        Self {
+            progress,
+            result: rx,
+        }
+    }
+
+    /// Asks the worker thread to stop checking.
+    fn cancel(&self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Takes the result of the check and its summary, if it has finished.
+    fn poll(&self) -> Option<ScanResult> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(ScanResult {
+                findings: Err(Error::Cancelled),
+                summary: None,
+            }),
+        }
+    }
+
+    /// Gets a description of the progress so far, e.g. `1,000 / 2,000`.
+    fn progress_text(&self) -> String {
+        let checked = self.progress.checked.load(Ordering::Relaxed);
+        let total = self.progress.total.load(Ordering::Relaxed);
+
+        if total > 0 {
+            format!("{} / {}", format_count(checked), format_count(total))
+        } else {
+            "Checking...".to_string()
+        }
+    }
+}
+
+impl HealthView {
+    /// Sets the file that the beatmap listing was loaded from, so that the cleaned copy can't replace it.
+    pub fn set_source_path(&mut self, source_path: Option<PathBuf>) {
+        self.source_path = source_path;
+    }
+
+    /// Sets the osu! installation folder, which is used to check for missing files and orphan folders.
+    pub fn set_osu_directory(&mut self, osu_directory: Option<PathBuf>) {
+        self.osu_directory = osu_directory;
+    }
+
+    /// Sets which version of the artist and title the beatmaps found by checks run from now on are named with.
+    pub fn set_language(&mut self, language: MetadataLanguage) {
+        self.language = language;
+    }
+
+    /// Renders the health view for the specified beatmap listing and the user's notes, returning the index of the
+    /// beatmap that should be shown in the beatmap listing.
+    pub fn view(
+        &mut self,
+        ctx: &egui::Context,
+        listing: Option<&Arc<BeatmapListing>>,
+        notes: &Arc<Notes>,
+    ) -> Option<usize> {
+        let mut shown = None;
+
+        self.set_beatmap_listing(listing);
+        self.notes = Arc::clone(notes);
+        self.poll_scans();
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Health");
+
+            if self.listing.is_none() {
+                ui.label("No beatmap listing loaded...");
+                return;
+            }
+
+            self.toolbar(ui);
+            ui.separator();
+
+            egui::SidePanel::left("h_check_list")
+                .resizable(true)
+                .default_width(250.0)
+                .show_inside(ui, |ui| self.check_list(ui));
+
+            egui::CentralPanel::default().show_inside(ui, |ui| shown = self.findings(ui));
+        });
+
+        shown
+    }
+
+    /// Updates the beatmap listing being checked, discarding any findings if it has changed.
+    fn set_beatmap_listing(&mut self, listing: Option<&Arc<BeatmapListing>>) {
+        let unchanged = match (&self.listing, listing) {
+            (Some(current), Some(new)) => Arc::ptr_eq(current, new),
+            (None, None) => true,
+            _ => false,
+        };
+
+        if !unchanged {
+            for section in &self.sections {
+                if let Some(scan) = &section.scan {
+                    scan.cancel();
+                }
+            }
+
+            self.listing = listing.cloned();
+            self.sections = Default::default();
+            self.selected.clear();
+            self.status = None;
+        }
+    }
+
+    /// Collects the findings of any checks that have finished.
+    fn poll_scans(&mut self) {
+        for (check, section) in Check::ALL.into_iter().zip(&mut self.sections) {
+            let Some(result) = section.scan.as_ref().and_then(Scan::poll) else {
+                continue;
+            };
+
+            section.scan = None;
+
+            match result.findings {
+                Ok(mut findings) => {
+                    sort_findings(&mut findings, section.sort);
+                    section.findings = Some(Ok(findings));
+                    section.summary = result.summary;
+                }
+                Err(Error::Cancelled) => log::info!("{} check was cancelled", check.title()),
+                Err(e) => {
+                    log::warn!("{} check failed: {}", check.title(), e);
+                    section.findings = Some(Err(e.to_string()));
+                }
+            }
+        }
+    }
+
+    /// Starts running a check, unless it is already running.
+    fn run(&mut self, ctx: &egui::Context, check: Check) {
+        let Some(listing) = &self.listing else {
+            return;
+        };
+
+        let section = &mut self.sections[check as usize];
+
+        if section.scan.is_none() {
+            section.scan = Some(check.start(
+                ctx,
+                Arc::clone(listing),
+                self.osu_directory.clone(),
+                Arc::clone(&self.notes),
+                self.language,
+            ));
+        }
+    }
+
+    /// Renders the buttons for saving the cleaned copy, along with a notice that the original files aren't changed.
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Run All Checks").clicked() {
+                for check in Check::ALL {
+                    if !check.needs_osu_directory() || self.osu_directory.is_some() {
+                        self.run(ui.ctx(), check);
+                    }
+                }
+            }
+
+            ui.separator();
+
+            ui.label(format!(
+                "{} beatmaps selected for removal",
+                format_count(self.selected.len())
+            ));
+
+            if ui
+                .add_enabled(
+                    !self.selected.is_empty(),
+                    egui::Button::new("Save Cleaned Copy..."),
+                )
+                .clicked()
+            {
+                self.save_cleaned_copy();
+            }
+
+            if ui
+                .add_enabled(
+                    !self.selected.is_empty(),
+                    egui::Button::new("Clear Selection"),
+                )
+                .clicked()
+            {
+                self.selected.clear();
+            }
+
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+        });
+
+        ui.weak(
+            "The cleaned copy is saved as a new osu!.db file without the selected beatmaps. \
+             This does not modify your original files.",
+        );
+    }
+
+    /// Renders the list of checks, along with buttons to run or cancel each one.
+    fn check_list(&mut self, ui: &mut egui::Ui) {
+        let mut started = None;
+
+        for check in Check::ALL {
+            let section = &self.sections[check as usize];
+
+            let label = match &section.findings {
+                Some(Ok(findings)) => {
+                    format!("{} ({})", check.title(), format_count(findings.len()))
+                }
+                Some(Err(_)) => format!("{} (failed)", check.title()),
+                None => check.title().to_string(),
+            };
+
+            if ui.selectable_label(self.current == check, label).clicked() {
+                self.current = check;
+            }
+
+            ui.horizontal(|ui| {
+                if let Some(scan) = &section.scan {
+                    ui.spinner();
+                    ui.label(scan.progress_text());
+
+                    if ui.button("Cancel").clicked() {
+                        scan.cancel();
+                    }
+                } else {
+                    let text = if section.findings.is_some() {
+                        "Run Again"
+                    } else {
+                        "Run"
+                    };
+
+                    let enabled = !check.needs_osu_directory() || self.osu_directory.is_some();
+
+                    if ui
+                        .add_enabled(enabled, egui::Button::new(text))
+                        .on_disabled_hover_text(
+                            "Open the osu! folder to check the files in its Songs folder",
+                        )
+                        .clicked()
+                    {
+                        started = Some(check);
+                    }
+                }
+            });
+
+            ui.add_space(4.0);
+        }
+
+        if let Some(check) = started {
+            self.current = check;
+            self.run(ui.ctx(), check);
+        }
+    }
+
+    /// Renders the findings of the current check, returning the index of the beatmap that should be shown in the
+    /// beatmap listing.
+    fn findings(&mut self, ui: &mut egui::Ui) -> Option<usize> {
+        let check = self.current;
+        let section = &mut self.sections[check as usize];
+
+        ui.strong(check.title());
+        ui.label(check.description());
+
+        if let Some(summary) = &section.summary {
+            ui.weak(summary);
+        }
+
+        ui.add_space(4.0);
+
+        let findings = match &mut section.findings {
+            Some(Ok(findings)) => findings,
+            Some(Err(e)) => {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("Unable to run check: {}", e),
+                );
+                return None;
+            }
+            None if section.scan.is_some() => {
+                ui.spinner();
+                return None;
+            }
+            None => {
+                ui.label("This check hasn't been run yet.");
+                return None;
+            }
+        };
+
+        if findings.is_empty() {
+            ui.label("No problems found.");
+            return None;
+        }
+
+        if findings.iter().any(|f| f.index.is_some()) {
+            ui.horizontal(|ui| {
+                if ui.button("Select Suggested").clicked() {
+                    self.selected.extend(
+                        findings
+                            .iter()
+                            .filter(|f| f.suggested)
+                            .filter_map(|f| f.index),
+                    );
+                }
+
+                if ui.button("Deselect All").clicked() {
+                    for index in findings.iter().filter_map(|f| f.index) {
+                        self.selected.remove(&index);
+                    }
+                }
+            });
+        }
+
+        let row_height = accessibility::row_height(ui);
+
+        let mut clicked_column = None;
+        let mut shown = None;
+
+        ui.push_id(check as usize, |ui| {
+            TableBuilder::new(ui)
+                .striped(true)
+                .resizable(true)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .min_scrolled_height(0.0)
+                .column(TableColumn::exact(24.0))
+                .column(TableColumn::initial(350.0).at_least(30.0).clip(true))
+                .column(TableColumn::initial(350.0).at_least(30.0).clip(true))
+                .column(TableColumn::remainder().at_least(60.0))
+                .header(row_height, |mut header| {
+                    header.col(|_| {});
+
+                    for (column, title) in [
+                        (FindingColumn::Name, "Beatmap"),
+                        (FindingColumn::Details, "Details"),
+                    ] {
+                        header.col(|ui| {
+                            let indicator = match section.sort {
+                                Some((sort_column, SortOrder::Ascending))
+                                    if sort_column == column =>
+                                {
+                                    " ⏶"
+                                }
+                                Some((sort_column, SortOrder::Descending))
+                                    if sort_column == column =>
+                                {
+                                    " ⏷"
+                                }
+                                _ => "",
+                            };
+
+                            let label =
+                                egui::RichText::new(format!("{}{}", title, indicator)).strong();
+
+                            if ui.add(egui::Button::new(label).frame(false)).clicked() {
+                                clicked_column = Some(column);
+                            }
+                        });
+                    }
+
+                    header.col(|_| {});
+                })
+                .body(|body| {
+                    body.rows(row_height, findings.len(), |mut row| {
+                        let finding = &findings[row.index()];
+
+                        row.col(|ui| {
+                            if let Some(index) = finding.index {
+                                let mut selected = self.selected.contains(&index);
+
+                                if ui
+                                    .checkbox(&mut selected, "")
+                                    .on_hover_text("Leave this beatmap out of the cleaned copy")
+                                    .changed()
+                                {
+                                    if selected {
+                                        self.selected.insert(index);
+                                    } else {
+                                        self.selected.remove(&index);
+                                    }
+                                }
+                            }
+                        });
+
+                        row.col(|ui| {
+                            ui.label(&finding.name);
+                        });
+
+                        row.col(|ui| {
+                            ui.label(&finding.details);
+                        });
+
+                        row.col(|ui| {
+                            if let Some(index) = finding.index {
+                                if ui
+                                    .small_button("Show")
+                                    .on_hover_text("Show this beatmap in the beatmap listing")
+                                    .clicked()
+                                {
+                                    shown = Some(index);
+                                }
+                            }
+
+                            if let Some(folder) = &finding.folder {
+                                if ui.small_button("Open Folder").clicked() {
+                                    reveal_in_file_manager(folder);
+                                }
+                            }
+                        });
+                    });
+                });
+        });
+
+        // Clicking the current sort column toggles the sort order
+        if let Some(column) = clicked_column {
+            section.sort = Some(match section.sort {
+                Some((sort_column, order)) if sort_column == column => (column, order.reversed()),
+                _ => (column, SortOrder::Ascending),
+            });
+
+            sort_findings(findings, section.sort);
+        }
+
+        shown
+    }
+
+    /// Asks where to save the cleaned copy of the beatmap listing, then saves it without the selected beatmaps.
+    fn save_cleaned_copy(&mut self) {
+        let Some(listing) = &self.listing else {
+            return;
+        };
+
+        let directory = self
+            .source_path
+            .as_deref()
+            .and_then(Path::parent)
+            .or(self.osu_directory.as_deref());
+
+        let Some(path) = FileDialog::save_file(directory, CLEANED_FILE_NAME) else {
+            return;
+        };
+
+        if self.replaces_original(&path) {
+            self.status = Some(
+                "Choose a different file name, so that the original osu!.db isn't replaced"
+                    .to_string(),
+            );
+            return;
+        }
+
+        let cleaned = listing.subset(|i, _| !self.selected.contains(&i));
+
+        match cleaned.to_file(&path) {
+            Ok(()) => {
+                self.status = Some(format!(
+                    "Saved {} beatmaps to {}",
+                    format_count(cleaned.beatmaps.len()),
+                    path.display()
+                ));
+            }
+            Err(e) => {
+                log::error!("Unable to save '{}': {}", path.display(), e);
+                self.status = Some(format!("Unable to save: {}", e));
+            }
+        }
+    }
+
+    /// Checks whether saving to `path` would replace the file the listing was loaded from, or the `osu!.db` file that
+    /// osu! uses.
+    fn replaces_original(&self, path: &Path) -> bool {
+        let Ok(path) = std::fs::canonicalize(path) else {
+            // The file doesn't exist yet
+            return false;
+        };
+
+        self.source_path
+            .iter()
+            .cloned()
+            .chain(self.osu_directory.as_ref().map(|d| d.join("osu!.db")))
+            .filter_map(|original| std::fs::canonicalize(original).ok())
+            .any(|original| original == path)
+    }
+}
+
+/// Sorts findings by a column, keeping them in the order they were found if there's no sort column.
+fn sort_findings(findings: &mut [Finding], sort: Option<(FindingColumn, SortOrder)>) {
+    let Some((column, order)) = sort else {
+        return;
+    };
+
+    findings.sort_by(|a, b| {
+        let ordering = match column {
+            FindingColumn::Name => a.name.cmp(&b.name),
+            FindingColumn::Details => a.details.cmp(&b.details),
+        };
+
+        match order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    });
+}
+
+/// Gets the path of the file that remembers the hashes of `.osu` files, if the platform has a data folder.
+fn hash_cache_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "osu-db-viewer")
+        .map(|dirs| dirs.data_dir().join(HASH_CACHE_FILE))
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use osu_db_parser::{
+    import::{self, ResolvedList},
+    prelude::*,
+};
+
+use super::{collection_listing::AddToCollection, format_count};
+
+/// A dialog for importing a plain text list of beatmaps (e.g. a tournament mappool), which finds them in the listing so
+/// that they can be selected or made into a collection.
+#[derive(Default)]
+pub struct ImportListDialog {
+    pub visible: bool,
+
+    /// The list, with one MD5 hash, beatmap ID or link per line
+    text: String,
+
+    /// Name of the collection to make from the beatmaps that were found
+    collection_name: String,
+
+    /// The list as it was last looked up in the listing
+    resolved: Option<ResolvedList>,
+
+    /// Why the list couldn't be opened
+    #[cfg(not(target_arch = "wasm32"))]
+    error: Option<String>,
+}
+
+/// Represents what to do with the beatmaps found from a list.
+pub enum ImportAction {
+    /// Select the beatmaps with these MD5 hashes
+    Select(Vec<String>),
+
+    /// Add the beatmaps to a new collection
+    AddToCollection(AddToCollection),
+}
+
+impl ImportListDialog {
+    /// Renders the dialog, returning what to do with the beatmaps found in `listing` once the user has chosen. If
+    /// `can_add_collection` is set, a collection listing is loaded, so the beatmaps can be made into a collection.
+    pub fn view(
+        &mut self,
+        ctx: &egui::Context,
+        listing: &Arc<BeatmapListing>,
+        can_add_collection: bool,
+    ) -> Option<ImportAction> {
+        let mut action = None;
+        let mut visible = self.visible;
+
+        egui::Window::new("Import List")
+            .open(&mut visible)
+            .default_width(400.0)
+            .show(ctx, |ui| {
+                ui.label("Paste a list with one MD5 hash, beatmap ID or osu! website link per line.");
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Open File...").clicked() {
+                    self.open_file();
+                }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(error) = &self.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                egui::ScrollArea::vertical()
+                    .id_salt("import_list_text")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        let response = ui.add(
+                            egui::TextEdit::multiline(&mut self.text)
+                                .code_editor()
+                                .desired_rows(8)
+                                .desired_width(f32::INFINITY),
+                        );
+
+                        if response.changed() {
+                            self.resolved = None;
+                        }
+                    });
+
+                if ui
+                    .add_enabled(!self.text.trim().is_empty(), egui::Button::new("Find Beatmaps"))
+                    .clicked()
+                {
+                    self.resolved = Some(import::resolve_list(&self.text, listing));
+                }
+
+                let Some(resolved) = &self.resolved else {
+                    return;
+                };
+
+                ui.separator();
+                ui.label(format!(
+                    "{} beatmaps found, {} missing, {} lines skipped",
+                    format_count(resolved.found.len()),
+                    format_count(resolved.missing.len()),
+                    format_count(resolved.unparseable.len())
+                ));
+
+                if !resolved.missing.is_empty() || !resolved.unparseable.is_empty() {
+                    egui::CollapsingHeader::new("Problems").show(ui, |ui| {
+                        egui::ScrollArea::vertical()
+                            .id_salt("import_list_problems")
+                            .max_height(150.0)
+                            .show(ui, |ui| {
+                                for (line, entry) in &resolved.missing {
+                                    ui.label(format!("Line {}: {} isn't in osu!.db", line, entry));
+                                }
+
+                                for (line, text) in &resolved.unparseable {
+                                    ui.label(format!("Line {}: '{}' isn't a beatmap", line, text));
+                                }
+                            });
+                    });
+                }
+
+                let md5s = resolved
+                    .indices()
+                    .filter_map(|i| listing.beatmaps[i].md5.clone())
+                    .collect::<Vec<_>>();
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!md5s.is_empty(), egui::Button::new("Select Found"))
+                        .on_hover_text("Only beatmaps shown by the current search are selected")
+                        .clicked()
+                    {
+                        action = Some(ImportAction::Select(md5s.clone()));
+                    }
+
+                    ui.separator();
+                    ui.label("Collection name");
+                    ui.text_edit_singleline(&mut self.collection_name);
+
+                    let name = self.collection_name.trim();
+
+                    if ui
+                        .add_enabled(
+                            can_add_collection && !name.is_empty() && !md5s.is_empty(),
+                            egui::Button::new("Add Collection"),
+                        )
+                        .on_disabled_hover_text(
+                            "Open a collection.db, enter a name and make sure some beatmaps were found",
+                        )
+                        .clicked()
+                    {
+                        action = Some(ImportAction::AddToCollection(AddToCollection {
+                            collection: None,
+                            name: Some(name.to_string()),
+                            beatmap_md5s: md5s.clone(),
+                        }));
+                    }
+                });
+            });
+
+        self.visible = visible && action.is_none();
+        action
+    }
+
+    /// Asks the user to pick a list, and reads it into the text box.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_file(&mut self) {
+        let Some(path) =
+            crate::widgets::file_dialog::FileDialog::pick_file(None, "Text files", &["txt"])
+        else {
+            return;
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                self.text = text;
+                self.resolved = None;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("Unable to open '{}': {}", path.display(), e)),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use osu_db_parser::prelude::*;
+
+/// A lookup of beatmaps by MD5 hash and ID, built when a listing is loaded so that jumping to a beatmap doesn't need
+/// to search the whole listing.
+#[derive(Default)]
+pub struct BeatmapIndex {
+    by_md5: HashMap<String, usize>,
+    by_difficulty_id: HashMap<DifficultyId, usize>,
+
+    /// First difficulty of each beatmapset
+    by_beatmapset_id: HashMap<BeatmapSetId, usize>,
+}
+
+/// A small window for jumping to a beatmap by its MD5 hash or ID.
+#[derive(Default)]
+pub struct JumpToWindow {
+    visible: bool,
+    text: String,
+
+    /// Whether the text box should take focus, i.e. the window has just been opened
+    focus: bool,
+
+    /// Whether the last search didn't match anything
+    not_found: bool,
+}
+
+impl BeatmapIndex {
+    /// Builds the index for a listing.
+    pub fn new(listing: &BeatmapListing) -> Self {
+        let mut index = BeatmapIndex::default();
+
+        for (i, beatmap) in listing.beatmaps.iter().enumerate() {
+            if let Some(md5) = beatmap.md5.as_deref().filter(|md5| !md5.is_empty()) {
+                index.by_md5.entry(md5.to_ascii_lowercase()).or_insert(i);
+            }
+
+            if let Some(difficulty_id) = beatmap.difficulty_id() {
+                index.by_difficulty_id.entry(difficulty_id).or_insert(i);
+            }
+
+            if let Some(beatmapset_id) = beatmap.beatmapset_id() {
+                index.by_beatmapset_id.entry(beatmapset_id).or_insert(i);
+            }
+        }
+
+        index
+    }
+
+    /// Finds a beatmap by its MD5 hash, beatmap ID, or beatmapset ID. IDs are matched against beatmap IDs first, and
+    /// matching a beatmapset finds its first difficulty.
+    pub fn find(&self, text: &str) -> Option<usize> {
+        let text = text.trim();
+
+        if let Some(&index) = self.by_md5.get(&text.to_ascii_lowercase()) {
+            return Some(index);
+        }
+
+        let id = text.parse::<u32>().ok()?;
+
+        DifficultyId::new(id)
+            .and_then(|id| self.by_difficulty_id.get(&id))
+            .or_else(|| BeatmapSetId::new(id).and_then(|id| self.by_beatmapset_id.get(&id)))
+            .copied()
+    }
+
+    /// Finds a beatmap by its MD5 hash.
+    pub fn by_md5(&self, md5: &str) -> Option<usize> {
+        self.by_md5.get(&md5.to_ascii_lowercase()).copied()
+    }
+}
+
+impl JumpToWindow {
+    /// Opens the window, with the text box focused.
+    pub fn open(&mut self) {
+        self.visible = true;
+        self.focus = true;
+        self.not_found = false;
+    }
+
+    /// Renders the window, returning the index of the beatmap to jump to once one has been found. The window is closed
+    /// when a beatmap is found.
+    pub fn view(&mut self, ctx: &egui::Context, index: &BeatmapIndex) -> Option<usize> {
+        let mut found = None;
+
+        egui::Window::new("Jump to Beatmap")
+            .open(&mut self.visible)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.text)
+                            .hint_text("MD5 hash or beatmap ID")
+                            .desired_width(280.0),
+                    );
+
+                    if std::mem::take(&mut self.focus) {
+                        response.request_focus();
+                    }
+
+                    if response.changed() {
+                        self.not_found = false;
+                    }
+
+                    let submitted =
+                        response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                    if (ui.button("Go").clicked() || submitted) && !self.text.trim().is_empty() {
+                        found = index.find(&self.text);
+                        self.not_found = found.is_none();
+                    }
+                });
+
+                if self.not_found {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        "No beatmap has this MD5 hash or ID",
+                    );
+                }
+            });
+
+        if found.is_some() {
+            self.visible = false;
+        }
+
+        found
+    }
+}
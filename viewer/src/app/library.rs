@@ -0,0 +1,310 @@
+//! The loaded library, which is shared between the interface and any background jobs that read it.
+//!
+//! The library is published as immutable, versioned [`LibrarySnapshot`]s. A job takes a snapshot when it starts and
+//! keeps a consistent view of the library until it finishes, even if a reload or an edit replaces the library in the
+//! meantime. Anything that needs to know when the library changes can [`subscribe`](SharedLibrary::subscribe) to it.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use osu_db_parser::prelude::*;
+
+/// Represents the library at a particular point in time.
+#[derive(Clone, Debug, Default)]
+pub struct LibrarySnapshot {
+    /// Number of times the library had been changed when this snapshot was published, which starts at 0 for an empty
+    /// library
+    pub version: u64,
+
+    pub listing: Option<Arc<BeatmapListing>>,
+
+    /// Index into the listing's beatmaps for each MD5 hash
+    pub md5_index: Arc<HashMap<String, usize>>,
+
+    /// Local scores on each beatmap, by MD5 hash, while a `scores.db` is loaded
+    pub scores: Option<Arc<HashMap<String, Vec<ScoreReplay>>>>,
+
+    /// Collections, as they were when they were loaded
+    pub collections: Option<Arc<CollectionListing>>,
+}
+
+/// A handle to the loaded library, which can be cloned and sent to other threads.
+#[derive(Clone, Debug, Default)]
+pub struct SharedLibrary {
+    shared: Arc<Shared>,
+}
+
+/// Receives the latest version of the library each time it changes, skipping any versions published in between.
+#[derive(Debug)]
+pub struct LibraryWatch {
+    shared: Arc<Shared>,
+
+    /// Version of the most recent snapshot that was received
+    seen: u64,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    current: Mutex<Arc<LibrarySnapshot>>,
+}
+
+impl LibrarySnapshot {
+    /// Gets the beatmaps in the listing, by MD5 hash.
+    pub fn beatmaps_by_md5(&self) -> impl Iterator<Item = (&str, &BeatmapEntry)> {
+        let beatmaps = self
+            .listing
+            .as_deref()
+            .map(|listing| listing.beatmaps.as_slice())
+            .unwrap_or_default();
+
+        self.md5_index
+            .iter()
+            .filter_map(|(md5, &i)| beatmaps.get(i).map(|beatmap| (md5.as_str(), beatmap)))
+    }
+}
+
+impl SharedLibrary {
+    /// Gets the current version of the library, which stays the same however long it's held.
+    pub fn snapshot(&self) -> Arc<LibrarySnapshot> {
+        Arc::clone(&self.shared.current())
+    }
+
+    /// Gets the version of the current snapshot.
+    pub fn version(&self) -> u64 {
+        self.shared.current().version
+    }
+
+    /// Replaces the beatmap listing, indexing its beatmaps by MD5 hash.
+    pub fn set_listing(&self, listing: Option<Arc<BeatmapListing>>) {
+        let md5_index = listing
+            .iter()
+            .flat_map(|listing| listing.beatmaps.iter().enumerate())
+            .filter_map(|(i, beatmap)| beatmap.md5.clone().map(|md5| (md5, i)))
+            .collect();
+
+        self.update(|snapshot| {
+            snapshot.listing = listing;
+            snapshot.md5_index = Arc::new(md5_index);
+        });
+    }
+
+    /// Replaces the local scores.
+    pub fn set_scores(&self, scores: Option<Arc<HashMap<String, Vec<ScoreReplay>>>>) {
+        self.update(|snapshot| snapshot.scores = scores);
+    }
+
+    /// Replaces the collections.
+    pub fn set_collections(&self, collections: Option<Arc<CollectionListing>>) {
+        self.update(|snapshot| snapshot.collections = collections);
+    }
+
+    /// Publishes a new snapshot, made by changing a copy of the current one.
+    ///
+    /// The parts of the library are reference counted, so copying the snapshot is cheap. Updates are applied one at a
+    /// time, so concurrent updates can't undo each other's changes.
+    pub fn update(&self, change: impl FnOnce(&mut LibrarySnapshot)) {
+        let mut current = self.shared.current();
+
+        let mut snapshot = LibrarySnapshot::clone(&current);
+        change(&mut snapshot);
+        snapshot.version = current.version + 1;
+
+        *current = Arc::new(snapshot);
+    }
+
+    /// Subscribes to changes to the library, starting from the current version.
+    pub fn subscribe(&self) -> LibraryWatch {
+        LibraryWatch {
+            shared: Arc::clone(&self.shared),
+            seen: self.version(),
+        }
+    }
+}
+
+impl LibraryWatch {
+    /// Gets the latest snapshot if the library has changed since it was last received, without waiting, so that it
+    /// can be checked once per frame by the interface.
+    pub fn changed(&mut self) -> Option<Arc<LibrarySnapshot>> {
+        let current = self.shared.current();
+
+        if current.version == self.seen {
+            return None;
+        }
+
+        self.seen = current.version;
+        Some(Arc::clone(&current))
+    }
+}
+
+impl Shared {
+    /// Locks the current snapshot. A panic while it was locked can't leave it half-updated, since snapshots are only
+    /// ever replaced whole, so the lock is used even if it has been poisoned.
+    fn current(&self) -> MutexGuard<'_, Arc<LibrarySnapshot>> {
+        self.current.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+    };
+
+    use osu_db_parser::common::WINDOWS_EPOCH;
+
+    use super::*;
+
+    /// Creates a listing whose beatmaps' MD5 hashes depend on `generation`, so that a snapshot mixing one update's
+    /// listing with another's index would be noticed.
+    fn listing(generation: usize) -> Arc<BeatmapListing> {
+        Arc::new(BeatmapListing {
+            version: 20240101,
+            folder_count: generation as u32,
+            account_unlocked: true,
+            account_unlock_date: WINDOWS_EPOCH,
+            player_name: Some(format!("Player {}", generation)),
+            beatmaps: (0..generation % 7 + 1)
+                .map(|i| BeatmapEntry {
+                    md5: Some(format!("{}-{}", generation, i)),
+                    ..Default::default()
+                })
+                .collect(),
+            user_permissions: Default::default(),
+        })
+    }
+
+    /// Checks that a snapshot's parts all belong to the same update.
+    fn assert_consistent(snapshot: &LibrarySnapshot) {
+        let Some(listing) = &snapshot.listing else {
+            assert!(snapshot.md5_index.is_empty());
+            return;
+        };
+
+        assert_eq!(snapshot.md5_index.len(), listing.beatmaps.len());
+        for (md5, beatmap) in snapshot.beatmaps_by_md5() {
+            assert_eq!(beatmap.md5.as_deref(), Some(md5));
+        }
+    }
+
+    #[test]
+    fn snapshots_are_unaffected_by_later_updates() {
+        let library = SharedLibrary::default();
+        let empty = library.snapshot();
+
+        library.set_listing(Some(listing(1)));
+        let loaded = library.snapshot();
+
+        library.set_scores(Some(Arc::new(HashMap::new())));
+        library.set_listing(Some(listing(2)));
+
+        assert_eq!(empty.version, 0);
+        assert!(empty.listing.is_none());
+        assert_eq!(loaded.version, 1);
+        assert_eq!(loaded.md5_index.get("1-0"), Some(&0));
+        assert!(loaded.scores.is_none());
+
+        let latest = library.snapshot();
+        assert_eq!(latest.version, 3);
+        assert!(!latest.md5_index.contains_key("1-0"));
+        assert_eq!(latest.beatmaps_by_md5().count(), 3);
+        assert!(latest.scores.is_some());
+    }
+
+    #[test]
+    fn watches_skip_to_the_latest_version() {
+        let library = SharedLibrary::default();
+        let mut watch = library.subscribe();
+        assert!(watch.changed().is_none());
+
+        library.set_listing(Some(listing(1)));
+        library.set_listing(Some(listing(2)));
+
+        assert_eq!(watch.changed().map(|snapshot| snapshot.version), Some(2));
+        assert!(watch.changed().is_none());
+    }
+
+    #[test]
+    fn readers_see_consistent_snapshots_while_writers_update() {
+        const WRITERS: usize = 4;
+        const UPDATES: usize = 250;
+
+        let library = SharedLibrary::default();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let readers = (0..4)
+            .map(|_| {
+                let library = library.clone();
+                let done = Arc::clone(&done);
+
+                thread::spawn(move || {
+                    let mut last_version = 0;
+
+                    while !done.load(Ordering::Relaxed) {
+                        let snapshot = library.snapshot();
+                        assert_consistent(&snapshot);
+
+                        // Versions never go backwards
+                        assert!(snapshot.version >= last_version);
+                        last_version = snapshot.version;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // A subscriber on another thread, which should end up on the final version
+        let watcher = {
+            let mut watch = library.subscribe();
+
+            thread::spawn(move || {
+                let mut last_version = 0;
+
+                while last_version < (WRITERS * UPDATES) as u64 {
+                    match watch.changed() {
+                        Some(snapshot) => {
+                            assert_consistent(&snapshot);
+                            assert!(snapshot.version > last_version);
+                            last_version = snapshot.version;
+                        }
+                        None => thread::yield_now(),
+                    }
+                }
+
+                last_version
+            })
+        };
+
+        let writers = (0..WRITERS)
+            .map(|writer| {
+                let library = library.clone();
+
+                thread::spawn(move || {
+                    for update in 0..UPDATES {
+                        match update % 3 {
+                            0 => library.set_scores(Some(Arc::new(HashMap::new()))),
+                            _ => library.set_listing(Some(listing(writer * UPDATES + update))),
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let final_version = watcher.join().unwrap();
+        done.store(true, Ordering::Relaxed);
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        // Every update was applied, and none were lost to a concurrent one
+        assert_eq!(library.version(), (WRITERS * UPDATES) as u64);
+        assert_eq!(final_version, library.version());
+        assert_consistent(&library.snapshot());
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use osu_db_parser::prelude::*;
+
+use super::{format_count, status_bar::timed};
+
+/// How often to repaint while parsing, in beatmaps.
+#[cfg(not(target_arch = "wasm32"))]
+const REPAINT_INTERVAL: usize = 500;
+
+/// Parses an `osu.db` file on a worker thread, so that the window stays responsive while loading large databases.
+///
+/// On the web, there are no worker threads, so the file is parsed immediately instead.
+pub struct ListingLoader {
+    progress: Arc<Progress>,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    result: std::sync::mpsc::Receiver<(Result<PartialListing, Error>, Option<Duration>)>,
+
+    #[cfg(target_arch = "wasm32")]
+    result: Option<(Result<PartialListing, Error>, Option<Duration>)>,
+
+    /// How long parsing took, once it has finished and if it could be measured
+    parse_time: Option<Duration>,
+}
+
+/// Represents the progress of a loader, shared with the worker thread.
+#[derive(Default)]
+struct Progress {
+    parsed: AtomicUsize,
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+/// A dialog showing why a file couldn't be loaded.
+pub struct LoadErrorDialog {
+    pub title: String,
+    pub error: LoadError,
+
+    /// Beatmaps that were parsed before the error, which the user can choose to load anyway
+    pub partial: Option<PartialListing>,
+}
+
+/// Represents the reason a file couldn't be loaded.
+pub enum LoadError {
+    /// The file couldn't be read
+    IO(std::io::Error),
+
+    /// The file was read, but couldn't be parsed
+    Parser(Error),
+
+    /// The file isn't any of the kinds that can be opened
+    Unrecognised,
+
+    /// The folder doesn't contain any of osu!'s database files
+    NotOsuFolder,
+}
+
+/// Represents the user's response to a [`LoadErrorDialog`].
+pub enum LoadErrorResponse {
+    Close,
+
+    /// Load the valid beatmaps, skipping the rest
+    LoadPartial(PartialListing),
+}
+
+impl ListingLoader {
+    /// Starts parsing the contents of an `osu.db` file.
+    pub fn start(ctx: &egui::Context, data: Vec<u8>) -> Self {
+        let progress = Arc::new(Progress::default());
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let worker_progress = Arc::clone(&progress);
+            let ctx = ctx.clone();
+
+            std::thread::spawn(move || {
+                let result = timed(|| {
+                    BeatmapListing::from_bytes_lenient(&data, |parsed, total| {
+                        worker_progress.parsed.store(parsed, Ordering::Relaxed);
+                        worker_progress.total.store(total, Ordering::Relaxed);
+
+                        if parsed % REPAINT_INTERVAL == 0 {
+                            ctx.request_repaint();
+                        }
+
+                        !worker_progress.cancelled.load(Ordering::Relaxed)
+                    })
+                });
+
+                if tx.send(result).is_ok() {
+                    ctx.request_repaint();
+                }
+            });
+
+            Self {
+                progress,
+                result: rx,
+                parse_time: None,
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            ctx.request_repaint();
+
+            Self {
+                progress,
+                result: Some(timed(|| {
+                    BeatmapListing::from_bytes_lenient(&data, |_, _| true)
+                })),
+                parse_time: None,
+            }
+        }
+    }
+
+    /// Asks the worker thread to stop parsing.
+    pub fn cancel(&self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Takes the result of parsing, if it has finished.
+    pub fn poll(&mut self) -> Option<Result<PartialListing, Error>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let finished = match self.result.try_recv() {
+            Ok(finished) => Some(finished),
+            Err(std::sync::mpsc::TryRecvError::Empty) => None,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => Some((Err(Error::Cancelled), None)),
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        let finished = self.result.take();
+
+        finished.map(|(result, parse_time)| {
+            self.parse_time = parse_time;
+            result
+        })
+    }
+
+    /// Gets how long parsing took, once it has finished and if it could be measured.
+    pub fn parse_time(&self) -> Option<Duration> {
+        self.parse_time
+    }
+
+    /// Renders a window showing the progress of parsing, with a button to cancel it.
+    pub fn view(&self, ctx: &egui::Context) {
+        let parsed = self.progress.parsed.load(Ordering::Relaxed);
+        let total = self.progress.total.load(Ordering::Relaxed);
+        let cancelled = self.progress.cancelled.load(Ordering::Relaxed);
+
+        egui::Window::new("Loading osu.db")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                let fraction = if total > 0 {
+                    parsed as f32 / total as f32
+                } else {
+                    0.0
+                };
+
+                ui.add(
+                    egui::ProgressBar::new(fraction)
+                        .desired_width(300.0)
+                        .text(format!(
+                            "{} / {} beatmaps",
+                            format_count(parsed),
+                            format_count(total)
+                        )),
+                );
+
+                ui.vertical_centered(|ui| {
+                    if ui
+                        .add_enabled(!cancelled, egui::Button::new("Cancel"))
+                        .clicked()
+                    {
+                        self.cancel();
+                    }
+                });
+            });
+    }
+}
+
+impl LoadErrorDialog {
+    /// Renders the dialog, returning the user's response once they have chosen one.
+    pub fn view(&mut self, ctx: &egui::Context) -> Option<LoadErrorResponse> {
+        let mut response = None;
+
+        egui::Window::new(&self.title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                match &self.error {
+                    LoadError::IO(e) => {
+                        ui.label(io_error_message(e));
+                        ui.weak(e.to_string());
+                    }
+                    LoadError::Parser(Error::Beatmap {
+                        index,
+                        total,
+                        entry_offset,
+                        offset,
+                        kind,
+                    }) => {
+                        ui.label("The file appears to be corrupt or from an unsupported version of osu!.");
+
+                        egui::Grid::new("load_error_details").show(ui, |ui| {
+                            ui.label("Beatmap");
+                            ui.label(format!(
+                                "{} of {}",
+                                format_count(index + 1),
+                                format_count(*total)
+                            ));
+                            ui.end_row();
+
+                            ui.label("Beatmap Start");
+                            ui.label(format!("Byte {}", entry_offset));
+                            ui.end_row();
+
+                            ui.label("Error Location");
+                            ui.label(format!(
+                                "Byte {} ({} bytes into the beatmap)",
+                                offset,
+                                offset - entry_offset
+                            ));
+                            ui.end_row();
+
+                            ui.label("Reason");
+                            ui.label(kind.description());
+                            ui.end_row();
+                        });
+                    }
+                    LoadError::Parser(e) => {
+                        ui.label("The file appears to be corrupt or from an unsupported version of osu!.");
+                        ui.weak(e.to_string());
+                    }
+                    LoadError::Unrecognised => {
+                        ui.label("The file doesn't appear to be an osu!.db, collection.db, scores.db or .osr replay.");
+                    }
+                    LoadError::NotOsuFolder => {
+                        ui.label("No osu!.db, collection.db or scores.db was found in the folder.");
+                    }
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let valid_count = self
+                        .partial
+                        .as_ref()
+                        .map(|p| p.listing.beatmaps.len())
+                        .filter(|count| *count > 0);
+
+                    if let Some(valid_count) = valid_count {
+                        let label = format!("Load {} Valid Beatmaps", format_count(valid_count));
+
+                        if ui.button(label).clicked() {
+                            response = self.partial.take().map(LoadErrorResponse::LoadPartial);
+                        }
+                    }
+
+                    if ui.button("Close").clicked() {
+                        response = Some(LoadErrorResponse::Close);
+                    }
+                });
+            });
+
+        response
+    }
+}
+
+/// Gets a friendly description of an I/O error.
+fn io_error_message(error: &std::io::Error) -> &'static str {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => "The file could not be found.",
+        std::io::ErrorKind::PermissionDenied => {
+            "Permission was denied when reading the file. It may be in use by another program."
+        }
+        _ => "The file could not be read.",
+    }
+}
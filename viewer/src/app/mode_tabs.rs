@@ -0,0 +1,100 @@
+use osu_db_parser::prelude::*;
+
+use super::{
+    beatmap_table::{Selection, TableSort},
+    filter_bar::FilterBar,
+};
+
+/// Gameplay modes shown by each tab, where `None` shows every mode.
+pub const TABS: [Option<GameplayMode>; 5] = [
+    None,
+    Some(GameplayMode::Standard),
+    Some(GameplayMode::Taiko),
+    Some(GameplayMode::Catch),
+    Some(GameplayMode::Mania),
+];
+
+/// Represents what a tab was showing when the user switched away from it.
+#[derive(Default)]
+pub struct TabState {
+    pub query: String,
+    pub filter_bar: FilterBar,
+
+    /// How the rows were sorted, or `None` to keep the current sort if the tab hasn't been shown yet
+    pub sort: Option<TableSort>,
+
+    pub selection: Selection,
+
+    /// Position of the row at the top of the table
+    pub scroll: usize,
+
+    /// Beatmaps matching the tab's filter, which are shown straight away when switching back to the tab. This is
+    /// cleared whenever they might have changed, e.g. when a different listing is loaded.
+    pub rows: Option<Vec<usize>>,
+}
+
+/// Tabs showing the beatmaps for each gameplay mode, each with their own search, sort, selection and scroll position.
+///
+/// Only the tabs that aren't being shown are stored here, since the beatmap listing holds the state of the current
+/// tab.
+#[derive(Default)]
+pub struct ModeTabs {
+    current: usize,
+    saved: [TabState; TABS.len()],
+}
+
+impl ModeTabs {
+    /// Gets the index of the tab being shown.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Renders the tabs, returning the index of the tab that was clicked if it isn't the current one.
+    pub fn show(&self, ui: &mut egui::Ui) -> Option<usize> {
+        let mut clicked = None;
+
+        ui.horizontal(|ui| {
+            for (i, mode) in TABS.iter().enumerate() {
+                let label = match mode {
+                    Some(mode) => mode.to_string(),
+                    None => "All".to_string(),
+                };
+
+                if ui.selectable_label(i == self.current, label).clicked() && i != self.current {
+                    clicked = Some(i);
+                }
+            }
+        });
+
+        clicked
+    }
+
+    /// Takes the state of a tab, to restore it when switching to that tab.
+    pub fn take(&mut self, tab: usize) -> TabState {
+        std::mem::take(&mut self.saved[tab])
+    }
+
+    /// Switches to another tab, storing the state of the current one.
+    pub fn switch(&mut self, tab: usize, current: TabState) {
+        self.saved[self.current] = current;
+        self.current = tab;
+    }
+
+    /// Forgets the beatmaps matching the tabs that aren't being shown, so that they're filtered again when switched
+    /// to, e.g. because a setting that affects filtering was changed.
+    pub fn invalidate(&mut self) {
+        for state in &mut self.saved {
+            state.rows = None;
+        }
+    }
+
+    /// Forgets everything about the tabs that aren't being shown that depends on the beatmaps, since a different
+    /// listing has been loaded. Their searches and sorts are kept.
+    pub fn reset_for_new_listing(&mut self) {
+        for state in &mut self.saved {
+            state.rows = None;
+            state.selection.clear();
+            state.scroll = 0;
+        }
+    }
+}
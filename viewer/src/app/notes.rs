@@ -0,0 +1,150 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use osu_db_parser::prelude::*;
+
+/// Name of the file that the notes are saved to, inside the platform's data folder.
+#[cfg(not(target_arch = "wasm32"))]
+const NOTES_FILE: &str = "notes.json";
+
+/// Name suggested when exporting the notes.
+#[cfg(not(target_arch = "wasm32"))]
+pub const EXPORT_FILE_NAME: &str = "osu-db-viewer-notes.json";
+
+/// The user's notes and bookmarks for beatmaps, which are saved in a file of their own so that they're kept when
+/// `osu!.db` is rebuilt.
+///
+/// The notes are shared with background searches and checks, so changing them copies the notes if a search is still
+/// using the previous ones.
+#[derive(Default)]
+pub struct NoteStore {
+    notes: Arc<Notes>,
+
+    /// Whether the notes have been changed since they were last saved
+    modified: bool,
+}
+
+impl NoteStore {
+    /// Loads the notes saved in the data folder, starting with no notes if they haven't been saved yet.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> std::io::Result<NoteStore> {
+        let Some(path) = notes_path() else {
+            return Ok(NoteStore::default());
+        };
+
+        match std::fs::read(path) {
+            Ok(data) => Ok(NoteStore {
+                notes: Arc::new(serde_json::from_slice(&data)?),
+                modified: false,
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(NoteStore::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Creates a store holding notes restored from the application's state, e.g. on the web.
+    #[cfg(target_arch = "wasm32")]
+    pub fn new(notes: Notes) -> NoteStore {
+        NoteStore {
+            notes: Arc::new(notes),
+            modified: false,
+        }
+    }
+
+    /// Saves the notes to the data folder if they've been changed. On the web, they're saved along with the rest of the
+    /// application's state instead.
+    pub fn save(&mut self) {
+        if !self.modified {
+            return;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let result = match notes_path() {
+                Some(path) => write_notes(&path, &self.notes),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "unable to locate the data folder",
+                )),
+            };
+
+            if let Err(e) = result {
+                log::error!("Unable to save notes: {}", e);
+                return;
+            }
+        }
+
+        self.modified = false;
+    }
+
+    /// Gets the notes, e.g. to pass to a background search.
+    pub fn notes(&self) -> &Arc<Notes> {
+        &self.notes
+    }
+
+    /// Gets a copy of the note for a beatmap, which is empty if the beatmap doesn't have one.
+    pub fn get(&self, beatmap: &BeatmapEntry) -> Note {
+        self.notes.get(beatmap).cloned().unwrap_or_default()
+    }
+
+    /// Replaces the note for a beatmap. Returns `true` if the note was changed.
+    pub fn set(&mut self, beatmap: &BeatmapEntry, note: Note) -> bool {
+        let Some(md5) = beatmap.md5.as_deref() else {
+            return false;
+        };
+
+        if self.get(beatmap) == note {
+            return false;
+        }
+
+        Arc::make_mut(&mut self.notes).set(md5, note);
+        self.modified = true;
+        true
+    }
+
+    /// Stars a beatmap, or unstars it if it's already starred. Returns `true` if the note was changed.
+    pub fn toggle_star(&mut self, beatmap: &BeatmapEntry) -> bool {
+        let mut note = self.get(beatmap);
+        note.starred = !note.starred;
+        self.set(beatmap, note)
+    }
+
+    /// Exports every note to a file, e.g. to move them to a different machine.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export(&self, path: &Path) -> std::io::Result<()> {
+        write_notes(path, &self.notes)
+    }
+
+    /// Imports the notes from an exported file, replacing any existing notes for the same beatmaps. Returns the number
+    /// of notes that were added or changed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn import(&mut self, path: &Path) -> std::io::Result<usize> {
+        let imported = serde_json::from_slice::<Notes>(&std::fs::read(path)?)?;
+        let changed = Arc::make_mut(&mut self.notes).merge(imported);
+
+        if changed > 0 {
+            self.modified = true;
+            self.save();
+        }
+
+        Ok(changed)
+    }
+}
+
+/// Gets the path of the notes file, if the platform has a data folder.
+#[cfg(not(target_arch = "wasm32"))]
+fn notes_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "osu-db-viewer")
+        .map(|dirs| dirs.data_dir().join(NOTES_FILE))
+}
+
+/// Writes notes to a file as JSON, creating its folder if needed.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_notes(path: &Path, notes: &Notes) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(path, serde_json::to_vec_pretty(notes)?)
+}
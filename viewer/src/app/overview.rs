@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+
+use osu_db_parser::{
+    analysis,
+    prelude::*,
+    stats::{LibraryStats, STAR_BUCKETS},
+};
+use time::{Date, OffsetDateTime, UtcOffset};
+
+use super::{filter_bar::FilterBar, format_count};
+
+/// Number of mappers shown in the top mappers chart.
+const TOP_MAPPERS: usize = 15;
+
+/// Height of each bar in the charts.
+const BAR_HEIGHT: f32 = 16.0;
+
+/// Size of each day in the activity calendar, including the gap between days.
+const DAY_SIZE: f32 = 14.0;
+
+/// A view showing charts summarising the loaded beatmap listing.
+#[derive(Default)]
+pub struct OverviewView {
+    stats: Option<LibraryStats>,
+    activity: ActivityCalendar,
+}
+
+/// Represents where the dates in the activity calendar come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ActivitySource {
+    /// When each beatmap was last played
+    #[default]
+    LastPlayed,
+
+    /// When each score was set
+    Scores,
+}
+
+/// A calendar showing how much was played on each day of a year.
+#[derive(Default)]
+struct ActivityCalendar {
+    last_played: Vec<OffsetDateTime>,
+    scores: Vec<OffsetDateTime>,
+    source: ActivitySource,
+
+    /// Offset from UTC used to split days, in hours
+    utc_offset: f32,
+
+    /// Year being shown, or the latest year with any activity if not set
+    year: Option<i32>,
+
+    /// Number of plays on each day, for the current source and offset
+    heatmap: HashMap<Date, u32>,
+}
+
+/// Represents a chart category that was clicked, which should be shown on the beatmap listing tab.
+pub enum OverviewFilter {
+    /// Star rating histogram bucket
+    Stars(usize),
+    Status(RankedStatus),
+    Mode(GameplayMode),
+    Mapper(String),
+}
+
+impl OverviewFilter {
+    /// Gets the search query and filter bar that show the beatmaps in this category.
+    pub fn into_search(self) -> (String, FilterBar) {
+        match self {
+            OverviewFilter::Stars(bucket) => (
+                String::new(),
+                FilterBar::with_stars(bucket as f64, bucket as f64 + 1.0),
+            ),
+            OverviewFilter::Status(status) => (String::new(), FilterBar::with_status(status)),
+            OverviewFilter::Mode(mode) => (String::new(), FilterBar::with_mode(mode)),
+            OverviewFilter::Mapper(creator) => (
+                format!("creator=\"{}\"", creator.replace('"', "")),
+                FilterBar::default(),
+            ),
+        }
+    }
+}
+
+impl OverviewView {
+    /// Recalculates the statistics for a newly loaded beatmap listing.
+    pub fn load_beatmap_listing(&mut self, beatmap_listing: &BeatmapListing) {
+        self.stats = Some(LibraryStats::from_beatmaps(
+            &beatmap_listing.beatmaps,
+            TOP_MAPPERS,
+        ));
+
+        self.activity.last_played = beatmap_listing
+            .beatmaps
+            .iter()
+            .filter(|beatmap| !beatmap.is_unplayed)
+            .map(|beatmap| beatmap.last_played)
+            .collect();
+        self.activity.refresh();
+    }
+
+    /// Updates the activity calendar with newly loaded scores, keyed by beatmap MD5 hash.
+    pub fn load_scores(&mut self, scores: &HashMap<String, Vec<ScoreReplay>>) {
+        self.activity.scores = scores
+            .values()
+            .flatten()
+            .map(|score| score.timestamp)
+            .collect();
+        self.activity.refresh();
+    }
+
+    /// Renders the overview, returning the category that was clicked in any of the charts.
+    pub fn view(&mut self, ctx: &egui::Context) -> Option<OverviewFilter> {
+        let mut filter = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Overview");
+
+            let Some(stats) = &self.stats else {
+                ui.label("No beatmap listing loaded...");
+                return;
+            };
+
+            ui.label(format!(
+                "{} beatmaps, {} unplayed ({:.1}%)",
+                format_count(stats.total),
+                format_count(stats.unplayed),
+                stats.unplayed_percentage()
+            ));
+            ui.weak("Click on a bar to show its beatmaps in the beatmap listing.");
+
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    self.activity.view(ui);
+                    ui.add_space(8.0);
+
+                    ui.columns(2, |columns| {
+                        let ui = &mut columns[0];
+
+                        ui.strong("Star Rating (NoMod)");
+                        let buckets = (0..STAR_BUCKETS)
+                            .map(|bucket| {
+                                if bucket + 1 < STAR_BUCKETS {
+                                    format!("{}-{}★", bucket, bucket + 1)
+                                } else {
+                                    format!("{}+★", bucket)
+                                }
+                            })
+                            .zip(stats.star_histogram)
+                            .collect::<Vec<_>>();
+
+                        if let Some(i) = bar_chart(ui, "overview_stars", &buckets, true) {
+                            filter = Some(OverviewFilter::Stars(i));
+                        }
+
+                        ui.add_space(8.0);
+                        ui.strong("Ranked Status");
+                        if let Some(i) =
+                            bar_chart(ui, "overview_statuses", &labelled(&stats.statuses), true)
+                        {
+                            filter = Some(OverviewFilter::Status(stats.statuses[i].0));
+                        }
+
+                        ui.add_space(8.0);
+                        ui.strong("Gameplay Mode");
+                        if let Some(i) =
+                            bar_chart(ui, "overview_modes", &labelled(&stats.modes), true)
+                        {
+                            filter = Some(OverviewFilter::Mode(stats.modes[i].0));
+                        }
+
+                        let ui = &mut columns[1];
+
+                        ui.strong("Beatmaps Added per Year");
+                        let years = stats
+                            .per_year
+                            .iter()
+                            .map(|(year, count)| (year.to_string(), *count))
+                            .collect::<Vec<_>>();
+
+                        bar_chart(ui, "overview_years", &years, false);
+
+                        ui.add_space(8.0);
+                        ui.strong("Top Mappers");
+                        if let Some(i) = bar_chart(ui, "overview_mappers", &stats.top_mappers, true)
+                        {
+                            filter = Some(OverviewFilter::Mapper(stats.top_mappers[i].0.clone()));
+                        }
+                    });
+                });
+        });
+
+        filter
+    }
+}
+
+impl ActivityCalendar {
+    /// Recalculates the heatmap after the dates or settings change.
+    fn refresh(&mut self) {
+        let dates = match self.source {
+            ActivitySource::LastPlayed => &self.last_played,
+            ActivitySource::Scores => &self.scores,
+        };
+
+        let offset = UtcOffset::from_whole_seconds((self.utc_offset * 3600.0) as i32)
+            .unwrap_or(UtcOffset::UTC);
+        self.heatmap = analysis::activity_heatmap(dates.iter().copied(), offset);
+    }
+
+    /// Renders the calendar for the selected year, along with its settings.
+    fn view(&mut self, ui: &mut egui::Ui) {
+        ui.strong("Activity");
+
+        let mut years = self
+            .heatmap
+            .keys()
+            .map(|date| date.year())
+            .collect::<Vec<_>>();
+        years.sort_unstable();
+        years.dedup();
+
+        let year = self
+            .year
+            .filter(|year| years.contains(year))
+            .or(years.last().copied());
+
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            let source = self.source;
+
+            egui::ComboBox::from_id_salt("overview_activity_source")
+                .selected_text(match self.source {
+                    ActivitySource::LastPlayed => "Last played",
+                    ActivitySource::Scores => "Scores set",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.source,
+                        ActivitySource::LastPlayed,
+                        "Last played",
+                    );
+                    ui.add_enabled_ui(!self.scores.is_empty(), |ui| {
+                        ui.selectable_value(&mut self.source, ActivitySource::Scores, "Scores set")
+                            .on_disabled_hover_text("Load scores.db to show when scores were set");
+                    });
+                });
+
+            changed |= self.source != source;
+
+            if let Some(year) = year {
+                egui::ComboBox::from_id_salt("overview_activity_year")
+                    .selected_text(year.to_string())
+                    .show_ui(ui, |ui| {
+                        for &option in years.iter().rev() {
+                            if ui
+                                .selectable_label(option == year, option.to_string())
+                                .clicked()
+                            {
+                                self.year = Some(option);
+                            }
+                        }
+                    });
+            }
+
+            ui.label("UTC offset:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.utc_offset)
+                        .range(-12.0..=14.0)
+                        .speed(0.25)
+                        .fixed_decimals(2)
+                        .suffix(" h"),
+                )
+                .on_hover_text("Timezone used to decide which day each date falls on")
+                .changed();
+        });
+
+        if changed {
+            self.refresh();
+        }
+
+        match year {
+            Some(year) => self.calendar(ui, year),
+            None => {
+                ui.weak("No data");
+            }
+        }
+    }
+
+    /// Renders a grid with a column for each week of `year`, and a row for each day of the week starting on Monday.
+    fn calendar(&self, ui: &mut egui::Ui, year: i32) {
+        let Ok(first) = Date::from_ordinal_date(year, 1) else {
+            return;
+        };
+
+        let days = time::util::days_in_year(year);
+        let start = first.weekday().number_days_from_monday() as usize;
+        let weeks = (start + days as usize).div_ceil(7);
+
+        let max = self.heatmap.values().copied().max().unwrap_or_default();
+        let total = self
+            .heatmap
+            .iter()
+            .filter(|(date, _)| date.year() == year)
+            .map(|(_, count)| *count)
+            .sum::<u32>();
+
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(weeks as f32 * DAY_SIZE, 7.0 * DAY_SIZE),
+            egui::Sense::hover(),
+        );
+
+        let empty = ui.visuals().extreme_bg_color;
+        let full = ui.visuals().selection.bg_fill;
+        let hovered = response.hover_pos();
+        let mut hovered_day = None;
+
+        for ordinal in 1..=days {
+            let Ok(date) = Date::from_ordinal_date(year, ordinal) else {
+                continue;
+            };
+
+            let index = start + ordinal as usize - 1;
+            let min = rect.min + egui::vec2((index / 7) as f32, (index % 7) as f32) * DAY_SIZE;
+            let day = egui::Rect::from_min_size(min, egui::Vec2::splat(DAY_SIZE - 2.0));
+
+            let count = self.heatmap.get(&date).copied().unwrap_or_default();
+            let colour = if count == 0 || max == 0 {
+                empty
+            } else {
+                // Days with any plays are always visibly filled
+                empty.lerp_to_gamma(full, 0.25 + 0.75 * count as f32 / max as f32)
+            };
+
+            ui.painter().rect_filled(day, 2.0, colour);
+
+            if hovered.is_some_and(|pos| day.contains(pos)) {
+                ui.painter()
+                    .rect_stroke(day, 2.0, ui.visuals().selection.stroke);
+                hovered_day = Some((date, count));
+            }
+        }
+
+        if let Some((date, count)) = hovered_day {
+            response.on_hover_ui_at_pointer(|ui| {
+                ui.label(format!("{}: {}", date, format_count(count as usize)));
+            });
+        }
+
+        ui.weak(format!("{} in {}", format_count(total as usize), year));
+    }
+}
+
+/// Converts counts for each category into labelled rows for a bar chart.
+fn labelled<T: std::fmt::Display>(counts: &[(T, usize)]) -> Vec<(String, usize)> {
+    counts
+        .iter()
+        .map(|(category, count)| (category.to_string(), *count))
+        .collect()
+}
+
+/// Renders a horizontal bar chart, with a row for each category. If `clickable` is set, the index of the row that was
+/// clicked is returned.
+fn bar_chart(
+    ui: &mut egui::Ui,
+    id: &str,
+    rows: &[(String, usize)],
+    clickable: bool,
+) -> Option<usize> {
+    let mut clicked = None;
+
+    if rows.is_empty() {
+        ui.weak("No data");
+        return None;
+    }
+
+    let max = rows
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or_default();
+
+    egui::Grid::new(id).num_columns(3).show(ui, |ui| {
+        for (i, (label, count)) in rows.iter().enumerate() {
+            ui.label(label);
+
+            let sense = if clickable && *count > 0 {
+                egui::Sense::click()
+            } else {
+                egui::Sense::hover()
+            };
+
+            let width = (ui.available_width() - 60.0).max(100.0);
+            let (rect, response) = ui.allocate_exact_size(egui::vec2(width, BAR_HEIGHT), sense);
+
+            let fraction = if max == 0 {
+                0.0
+            } else {
+                *count as f32 / max as f32
+            };
+
+            let mut bar = rect;
+            bar.set_width(rect.width() * fraction);
+
+            let colour = if response.hovered() && sense.click {
+                ui.visuals().selection.stroke.color
+            } else {
+                ui.visuals().selection.bg_fill
+            };
+
+            ui.painter().rect_filled(bar, 2.0, colour);
+
+            if response
+                .on_hover_cursor(if sense.click {
+                    egui::CursorIcon::PointingHand
+                } else {
+                    egui::CursorIcon::Default
+                })
+                .clicked()
+            {
+                clicked = Some(i);
+            }
+
+            ui.label(format_count(*count));
+            ui.end_row();
+        }
+    });
+
+    clicked
+}
@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+
+use super::text_cache::CacheStats;
+
+/// Number of recent frames that the overlay's figures cover.
+const SAMPLES: usize = 120;
+
+/// Time a frame can take before it's shown as slow, in seconds. Scrolling the beatmap table should stay well under this,
+/// even with a large library on integrated graphics.
+const FRAME_BUDGET: f32 = 0.004;
+
+/// Time spent on a frame, and the text cache lookups made during it.
+#[derive(Clone, Copy, Debug)]
+struct FrameSample {
+    seconds: f32,
+    cache: CacheStats,
+}
+
+/// An overlay in the corner of the window showing how long recent frames took and how often the beatmap table's text
+/// was found already laid out, so that slowdowns while scrolling can be seen as they happen. It's toggled with F12.
+#[derive(Default)]
+pub struct PerformanceOverlay {
+    pub visible: bool,
+
+    /// Most recent frames, oldest first
+    frames: VecDeque<FrameSample>,
+}
+
+impl PerformanceOverlay {
+    /// Toggles the overlay when F12 is pressed.
+    pub fn handle_key(&mut self, ctx: &egui::Context) {
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::F12)) {
+            self.visible = !self.visible;
+        }
+    }
+
+    /// Records a frame. `seconds` is the time spent on the previous frame, which eframe gives at the start of each one,
+    /// and `cache` is the lookups made during it.
+    pub fn record(&mut self, seconds: Option<f32>, cache: CacheStats) {
+        let Some(seconds) = seconds else {
+            return;
+        };
+
+        if self.frames.len() == SAMPLES {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(FrameSample { seconds, cache });
+    }
+
+    /// Renders the overlay, if it's visible.
+    pub fn view(&self, ctx: &egui::Context) {
+        if !self.visible {
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("performance_overlay"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -32.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    let Some(last) = self.frames.back() else {
+                        ui.label("Waiting for frames...");
+                        return;
+                    };
+
+                    let slowest = self.frames.iter().map(|f| f.seconds).fold(0.0, f32::max);
+                    let average = self.frames.iter().map(|f| f.seconds).sum::<f32>()
+                        / self.frames.len() as f32;
+
+                    let text = format!(
+                        "Frame: {} (average {}, slowest {})",
+                        format_millis(last.seconds),
+                        format_millis(average),
+                        format_millis(slowest)
+                    );
+
+                    if slowest > FRAME_BUDGET {
+                        ui.colored_label(ui.visuals().warn_fg_color, text)
+                    } else {
+                        ui.label(text)
+                    };
+
+                    let hits = self.frames.iter().map(|f| f.cache.hits as u64).sum::<u64>();
+                    let misses = self
+                        .frames
+                        .iter()
+                        .map(|f| f.cache.misses as u64)
+                        .sum::<u64>();
+
+                    ui.label(match hit_rate(hits, misses) {
+                        Some(rate) => format!(
+                            "Text cache: {:.1}% hits, {} laid out, {} kept",
+                            rate * 100.0,
+                            misses,
+                            last.cache.entries
+                        ),
+                        None => format!("Text cache: unused, {} kept", last.cache.entries),
+                    });
+
+                    ui.weak(format!("Over the last {} frames", self.frames.len()));
+                });
+            });
+    }
+}
+
+/// Formats a time in seconds as milliseconds, e.g. `1.25 ms`.
+fn format_millis(seconds: f32) -> String {
+    format!("{:.2} ms", seconds * 1000.0)
+}
+
+/// Gets the fraction of lookups that found the text already laid out, or `None` if there weren't any.
+fn hit_rate(hits: u64, misses: u64) -> Option<f64> {
+    let total = hits + misses;
+    (total > 0).then(|| hits as f64 / total as f64)
+}
@@ -0,0 +1,180 @@
+use egui::RichText;
+use osu_db_parser::{pinboard::MAX_PINS, prelude::*};
+
+use super::{jump_to::BeatmapIndex, settings::ValueFormat};
+
+/// A strip comparing the stats of the beatmaps pinned from the table, which is kept while the search changes.
+#[derive(Default)]
+pub struct PinboardStrip {
+    pins: Pinboard,
+
+    /// Which version of the artist and title to show and export
+    language: MetadataLanguage,
+
+    /// Which star rating to compare
+    stars: StarPolicy,
+
+    /// How star ratings are shown
+    format: ValueFormat,
+
+    /// Whether the comparison has been copied since the pins last changed
+    copied: bool,
+}
+
+/// Represents an action requested from the compare strip.
+pub enum PinAction {
+    /// Select the pinned beatmap at this index
+    ShowBeatmap(usize),
+}
+
+impl PinboardStrip {
+    /// Gets the pinned beatmaps.
+    pub fn pins(&self) -> &Pinboard {
+        &self.pins
+    }
+
+    /// Sets how the pinned beatmaps are shown, from the settings.
+    pub fn set_display(
+        &mut self,
+        language: MetadataLanguage,
+        stars: StarPolicy,
+        format: ValueFormat,
+    ) {
+        self.language = language;
+        self.stars = stars;
+        self.format = format;
+    }
+
+    /// Pins a beatmap, or unpins it if it's already pinned.
+    pub fn toggle(&mut self, beatmap: &BeatmapEntry) {
+        if self.pins.toggle(beatmap) {
+            self.copied = false;
+        }
+    }
+
+    /// Renders the pinned beatmaps' stats in aligned columns, with the best value of each row highlighted. Rows that
+    /// can't be compared because the beatmaps are for different modes are greyed out.
+    ///
+    /// Pinned beatmaps which aren't in `listing` (e.g. after reloading it) are left out.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        listing: &BeatmapListing,
+        index: &BeatmapIndex,
+    ) -> Option<PinAction> {
+        let mut action = None;
+        let indices = self
+            .pins
+            .md5s()
+            .iter()
+            .filter_map(|md5| index.by_md5(md5))
+            .collect::<Vec<_>>();
+        let comparison = Comparison::new(
+            indices.iter().map(|&i| &listing.beatmaps[i]).collect(),
+            self.stars,
+        );
+
+        let mut unpin = None;
+
+        ui.horizontal(|ui| {
+            ui.strong(format!("Pinned ({}/{})", self.pins.len(), MAX_PINS));
+
+            if comparison.beatmaps.len() < 2 {
+                ui.weak("Pin another beatmap from its context menu to compare them");
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Clear").clicked() {
+                    self.pins.clear();
+                    self.copied = false;
+                }
+
+                let copy = if self.copied {
+                    "Copied ✔"
+                } else {
+                    "Copy as Markdown"
+                };
+
+                if ui
+                    .button(copy)
+                    .on_hover_text(
+                        "Copy the comparison as a Markdown table, e.g. to paste into a chat",
+                    )
+                    .clicked()
+                {
+                    ui.ctx().copy_text(comparison.to_markdown(self.language));
+                    self.copied = true;
+                }
+            });
+        });
+
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::Grid::new("b_pinboard_grid")
+                .striped(true)
+                .min_col_width(80.0)
+                .show(ui, |ui| {
+                    ui.label("");
+
+                    for (&i, beatmap) in indices.iter().zip(&comparison.beatmaps) {
+                        ui.horizontal(|ui| {
+                            let name = beatmap.display_title(self.language.prefers_unicode());
+                            let difficulty = beatmap
+                                .difficulty
+                                .as_deref()
+                                .unwrap_or_default()
+                                .sanitized();
+
+                            if ui
+                                .link(format!("[{}]", difficulty))
+                                .on_hover_text(format!(
+                                    "{} ({})",
+                                    name.unwrap_or_default().sanitized(),
+                                    beatmap.gameplay_mode
+                                ))
+                                .clicked()
+                            {
+                                action = Some(PinAction::ShowBeatmap(i));
+                            }
+
+                            if ui.small_button("✕").on_hover_text("Unpin").clicked() {
+                                unpin = beatmap.md5.clone();
+                            }
+                        });
+                    }
+
+                    ui.end_row();
+
+                    for row in &comparison.rows {
+                        if row.comparable {
+                            ui.strong(row.column.header());
+                        } else {
+                            ui.weak(row.column.header()).on_hover_text(
+                                "The pinned beatmaps are for different modes, so this can't be compared",
+                            );
+                        }
+
+                        for (position, value) in row.values.iter().enumerate() {
+                            let text = RichText::new(self.format.text(row.column, value));
+
+                            if !row.comparable {
+                                ui.label(text.weak());
+                            } else if row.best.contains(&position) {
+                                ui.label(text.strong().color(ui.visuals().hyperlink_color));
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+
+                        ui.end_row();
+                    }
+                });
+        });
+
+        if let Some(md5) = unpin {
+            self.pins.unpin(&md5);
+            self.copied = false;
+        }
+
+        action
+    }
+}
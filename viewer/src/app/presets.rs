@@ -0,0 +1,280 @@
+use osu_db_parser::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::filter_bar::FilterBar;
+
+/// Represents a saved combination of search query and quick filters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FilterPreset {
+    pub name: String,
+
+    /// Search query, shown in the search box when the preset is applied
+    pub query: String,
+
+    /// Quick filters, shown in the filter bar when the preset is applied
+    pub filter_bar: FilterBar,
+
+    /// Filter for the query and quick filters combined, which is what's used when the preset is applied. This is kept
+    /// so that presets keep working even if the query syntax changes.
+    pub filter: BeatmapFilter,
+}
+
+/// Represents an action requested from the presets menu.
+pub enum PresetAction {
+    /// Apply the preset at this index
+    Apply(usize),
+
+    /// Save the current search as a preset with this name
+    SaveCurrent(String),
+}
+
+/// A menu for applying and saving filter presets, along with a window for managing them.
+#[derive(Default)]
+pub struct PresetMenu {
+    /// Name for the next preset to be saved
+    new_name: String,
+
+    manager: PresetManager,
+}
+
+/// A window for renaming, deleting, reordering, importing and exporting filter presets.
+#[derive(Default)]
+struct PresetManager {
+    visible: bool,
+
+    /// Preset being renamed, along with the new name
+    renaming: Option<(usize, String)>,
+
+    /// Presets pasted in to be imported, as JSON
+    import_text: String,
+
+    /// Result of the most recent import or export
+    status: Option<String>,
+}
+
+impl PresetMenu {
+    /// Renders the presets menu button. Returns any action requested, and whether the presets were changed in the
+    /// manager window.
+    ///
+    /// `can_save` is whether the current search can be saved, i.e. the query is valid.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        presets: &mut Vec<FilterPreset>,
+        can_save: bool,
+    ) -> (Option<PresetAction>, bool) {
+        let mut action = None;
+
+        ui.menu_button("Presets", |ui| {
+            if presets.is_empty() {
+                ui.weak("No saved presets");
+            }
+
+            for (i, preset) in presets.iter().enumerate() {
+                if ui
+                    .button(&preset.name)
+                    .on_hover_text(preset_summary(preset))
+                    .clicked()
+                {
+                    action = Some(PresetAction::Apply(i));
+                    ui.close_menu();
+                }
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_name)
+                        .hint_text("Preset name")
+                        .desired_width(150.0),
+                );
+
+                let name = self.new_name.trim();
+
+                if ui
+                    .add_enabled(can_save && !name.is_empty(), egui::Button::new("Save"))
+                    .on_disabled_hover_text("Enter a name, and make sure the search is valid")
+                    .clicked()
+                {
+                    action = Some(PresetAction::SaveCurrent(name.to_string()));
+                    self.new_name.clear();
+                    ui.close_menu();
+                }
+            });
+
+            if ui.button("Manage Presets...").clicked() {
+                self.manager.visible = true;
+                ui.close_menu();
+            }
+        });
+
+        let changed = self.manager.view(ui.ctx(), presets);
+        (action, changed)
+    }
+}
+
+impl PresetManager {
+    /// Renders the window, returning `true` if the presets were changed.
+    fn view(&mut self, ctx: &egui::Context, presets: &mut Vec<FilterPreset>) -> bool {
+        let mut changed = false;
+
+        egui::Window::new("Filter Presets")
+            .open(&mut self.visible)
+            .default_width(400.0)
+            .show(ctx, |ui| {
+                if presets.is_empty() {
+                    ui.weak("Presets saved from the search bar are shown here.");
+                }
+
+                let mut moved = None;
+                let mut deleted = None;
+
+                egui::Grid::new("preset_manager_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (i, preset) in presets.iter_mut().enumerate() {
+                            match &mut self.renaming {
+                                Some((renaming, name)) if *renaming == i => {
+                                    let response = ui.text_edit_singleline(name);
+
+                                    if response.lost_focus() {
+                                        let name = name.trim();
+
+                                        if !name.is_empty() && preset.name != name {
+                                            preset.name = name.to_string();
+                                            changed = true;
+                                        }
+
+                                        self.renaming = None;
+                                    } else {
+                                        response.request_focus();
+                                    }
+                                }
+                                _ => {
+                                    ui.label(&preset.name).on_hover_text(preset_summary(preset));
+                                }
+                            }
+
+                            ui.horizontal(|ui| {
+                                if ui.small_button("⏶").on_hover_text("Move up").clicked() && i > 0
+                                {
+                                    moved = Some((i, i - 1));
+                                }
+
+                                if ui.small_button("⏷").on_hover_text("Move down").clicked() {
+                                    moved = Some((i, i + 1));
+                                }
+
+                                if ui.small_button("Rename").clicked() {
+                                    self.renaming = Some((i, preset.name.clone()));
+                                }
+
+                                if ui.small_button("Delete").clicked() {
+                                    deleted = Some(i);
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+
+                if let Some((from, to)) = moved.filter(|&(_, to)| to < presets.len()) {
+                    presets.swap(from, to);
+                    self.renaming = None;
+                    changed = true;
+                }
+
+                if let Some(i) = deleted {
+                    presets.remove(i);
+                    self.renaming = None;
+                    changed = true;
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !presets.is_empty(),
+                            egui::Button::new("Copy All to Clipboard"),
+                        )
+                        .on_hover_text("Copy the presets as JSON, so that they can be shared")
+                        .clicked()
+                    {
+                        match serde_json::to_string_pretty(presets) {
+                            Ok(json) => {
+                                ui.ctx().copy_text(json);
+                                self.status = Some(format!("Copied {} presets", presets.len()));
+                            }
+                            Err(e) => {
+                                self.status = Some(format!("Unable to export presets: {}", e))
+                            }
+                        }
+                    }
+
+                    if ui
+                        .add_enabled(
+                            !self.import_text.trim().is_empty(),
+                            egui::Button::new("Import"),
+                        )
+                        .on_hover_text(
+                            "Add the presets pasted below, replacing any with the same name",
+                        )
+                        .clicked()
+                    {
+                        match serde_json::from_str::<Vec<FilterPreset>>(&self.import_text) {
+                            Ok(imported) => {
+                                self.status = Some(format!("Imported {} presets", imported.len()));
+                                import_presets(presets, imported);
+                                self.import_text.clear();
+                                changed = true;
+                            }
+                            Err(e) => {
+                                self.status = Some(format!("Unable to import presets: {}", e))
+                            }
+                        }
+                    }
+                });
+
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.import_text)
+                        .hint_text("Paste exported presets here to import them")
+                        .desired_rows(4)
+                        .desired_width(f32::INFINITY)
+                        .code_editor(),
+                );
+
+                if let Some(status) = &self.status {
+                    ui.weak(status);
+                }
+            });
+
+        changed
+    }
+}
+
+/// Adds imported presets to the end of the list, replacing any existing presets with the same name.
+fn import_presets(presets: &mut Vec<FilterPreset>, imported: Vec<FilterPreset>) {
+    for preset in imported {
+        match presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+    }
+}
+
+/// Describes what a preset filters on, for tooltips.
+fn preset_summary(preset: &FilterPreset) -> String {
+    let mut summary = if preset.query.is_empty() {
+        "No search query".to_string()
+    } else {
+        format!("Search: {}", preset.query)
+    };
+
+    if preset.filter_bar.is_active() {
+        summary.push_str("\nWith quick filters");
+    }
+
+    summary
+}
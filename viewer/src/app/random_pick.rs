@@ -0,0 +1,151 @@
+use std::{collections::hash_map::RandomState, hash::BuildHasher};
+
+use osu_db_parser::prelude::*;
+
+/// Number of random picks remembered, so that the user can go back to them.
+const HISTORY_LENGTH: usize = 20;
+
+/// Picks random beatmaps from the rows shown in the table, remembering the recent picks.
+#[derive(Default)]
+pub struct RandomPicker {
+    /// Recently picked beatmaps, as indices into the listing, oldest first
+    history: Vec<usize>,
+
+    /// Whether to only pick beatmaps that haven't been played
+    unplayed_only: bool,
+
+    /// Whether to start the audio preview of each picked beatmap
+    #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+    pub play_audio: bool,
+
+    /// Explanation of why the last attempt didn't pick anything
+    status: Option<&'static str>,
+}
+
+/// Represents an action requested from the random pick controls.
+pub enum RandomAction {
+    /// Pick a new random beatmap
+    Pick,
+
+    /// Go back to the beatmap at this index, which was picked earlier
+    Show(usize),
+}
+
+impl RandomPicker {
+    /// Forgets the previous picks, e.g. because a different listing was loaded.
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.status = None;
+    }
+
+    /// Picks a random beatmap from `rows`, which are the rows currently shown. `seed` is mixed into the random number,
+    /// e.g. the current time.
+    ///
+    /// The previous pick is avoided when there's anything else to choose from, so that the button always moves to a
+    /// different beatmap. `None` is returned if there's nothing to pick from.
+    pub fn pick(&mut self, rows: &[usize], beatmaps: &[BeatmapEntry], seed: f64) -> Option<usize> {
+        let last = self.history.last().copied();
+        let candidates = rows
+            .iter()
+            .copied()
+            .filter(|&i| !self.unplayed_only || beatmaps[i].is_unplayed)
+            .collect::<Vec<_>>();
+
+        let picked = match candidates.as_slice() {
+            [] => None,
+            [only] => Some(*only),
+            _ => {
+                let others = candidates
+                    .iter()
+                    .copied()
+                    .filter(|&i| Some(i) != last)
+                    .collect::<Vec<_>>();
+
+                Some(others[random_below(others.len(), seed)])
+            }
+        };
+
+        self.status = match picked {
+            None if self.unplayed_only && !rows.is_empty() => {
+                Some("None of the shown beatmaps are unplayed")
+            }
+            None => Some("No beatmaps are shown"),
+            Some(_) => None,
+        };
+
+        if let Some(index) = picked.filter(|&index| Some(index) != last) {
+            self.history.push(index);
+
+            if self.history.len() > HISTORY_LENGTH {
+                self.history.remove(0);
+            }
+        }
+
+        picked
+    }
+
+    /// Renders the button for picking a random beatmap, along with a menu of the previous picks and options.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        beatmaps: &[BeatmapEntry],
+        language: MetadataLanguage,
+    ) -> Option<RandomAction> {
+        let mut action = None;
+
+        ui.menu_button("⏷", |ui| {
+            ui.checkbox(&mut self.unplayed_only, "Unplayed only");
+
+            #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+            ui.checkbox(&mut self.play_audio, "Play audio preview");
+
+            ui.separator();
+            ui.strong("Previous Picks");
+
+            if self.history.is_empty() {
+                ui.weak("Nothing picked yet");
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for &index in self.history.iter().rev() {
+                        if let Some(beatmap) = beatmaps.get(index) {
+                            if ui.button(beatmap.display_name(language)).clicked() {
+                                action = Some(RandomAction::Show(index));
+                                ui.close_menu();
+                            }
+                        }
+                    }
+                });
+        })
+        .response
+        .on_hover_text("Random pick options and history");
+
+        if ui
+            .button("🎲 Random")
+            .on_hover_text("Select a random beatmap from the ones shown (F2)")
+            .clicked()
+        {
+            action = Some(RandomAction::Pick);
+        }
+
+        if let Some(status) = self.status {
+            ui.weak(status);
+        }
+
+        action
+    }
+}
+
+/// Picks a number below `len`, which must not be zero.
+///
+/// egui doesn't provide random numbers, so this uses the standard library's randomly keyed hasher, which is plenty
+/// for choosing a beatmap. Each `RandomState` has different keys, and `seed` makes up for platforms where the keys
+/// aren't random.
+fn random_below(len: usize, seed: f64) -> usize {
+    let hash = RandomState::new().hash_one(seed.to_bits());
+
+    // Scale the hash into range, which is unbiased enough for any realistic number of beatmaps
+    ((hash as u128 * len as u128) >> 64) as usize
+}
@@ -0,0 +1,289 @@
+use std::path::Path;
+
+use osu_db_parser::{
+    prelude::*,
+    raw::{self, EntryLayout, FieldKind, RawField},
+};
+
+/// Number of bytes shown on each row of the dump.
+const BYTES_PER_ROW: usize = 16;
+
+/// Number of bytes shown after the point where an entry couldn't be parsed, since the problem is often just before the
+/// next field that can be recognised.
+const TRAILING_BYTES: usize = 256;
+
+/// Background colours of alternating fields, so that the boundaries between them can be seen.
+const FIELD_COLOURS: [egui::Color32; 2] = [
+    egui::Color32::from_rgba_premultiplied(40, 70, 110, 90),
+    egui::Color32::from_rgba_premultiplied(40, 100, 60, 90),
+];
+
+/// A window showing the raw bytes of one beatmap entry as a hex dump, with each field that was found in them marked.
+///
+/// This is mostly useful for reporting bugs when the parser and an `osu.db` file disagree, e.g. after osu! changes the
+/// format.
+pub struct RawEntryWindow {
+    pub visible: bool,
+    title: String,
+
+    /// Position of the first byte in the file
+    offset: usize,
+
+    bytes: Vec<u8>,
+    layout: EntryLayout,
+}
+
+impl RawEntryWindow {
+    /// Reads the entry of the beatmap at `index` in a listing from the `osu.db` file it was loaded from.
+    pub fn beatmap(path: &Path, listing: &BeatmapListing, index: usize) -> Result<Self, String> {
+        let data = read(path)?;
+        let entry = listing
+            .raw_entry_bytes(index, &data)
+            .ok_or_else(|| "The file has changed since it was loaded".to_string())?;
+
+        Ok(Self {
+            visible: true,
+            title: format!("Raw Entry #{}", index + 1),
+            offset: entry.as_ptr() as usize - data.as_ptr() as usize,
+            layout: raw::entry_layout(listing.version, entry),
+            bytes: entry.to_vec(),
+        })
+    }
+
+    /// Reads the bytes of an entry which couldn't be parsed, from `entry_offset` until a little after `failed_offset`.
+    pub fn skipped(
+        path: &Path,
+        version: u32,
+        entry_offset: usize,
+        failed_offset: usize,
+    ) -> Result<Self, String> {
+        let data = read(path)?;
+        let end = failed_offset
+            .max(entry_offset)
+            .saturating_add(TRAILING_BYTES)
+            .min(data.len());
+        let bytes = data
+            .get(entry_offset..end)
+            .ok_or_else(|| "The file has changed since it was loaded".to_string())?;
+
+        Ok(Self {
+            visible: true,
+            title: format!("Raw Bytes at 0x{:X}", entry_offset),
+            offset: entry_offset,
+            layout: raw::entry_layout(version, bytes),
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    /// Renders the window.
+    pub fn view(&mut self, ctx: &egui::Context) {
+        egui::Window::new(&self.title)
+            .id(egui::Id::new("raw_entry"))
+            .open(&mut self.visible)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{} bytes from 0x{:X}, {} fields found",
+                        self.bytes.len(),
+                        self.offset,
+                        self.layout.fields.len()
+                    ));
+
+                    if ui
+                        .button("Copy")
+                        .on_hover_text(
+                            "Copy each field's bytes as text, e.g. to paste into a bug report",
+                        )
+                        .clicked()
+                    {
+                        ui.ctx()
+                            .copy_text(fields_text(&self.bytes, &self.layout, self.offset));
+                    }
+                });
+
+                if let Some(name) = self.layout.incomplete {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("The bytes end before {} could be read", name),
+                    );
+                }
+
+                ui.separator();
+                hex_dump(ui, &self.bytes, &self.layout, self.offset);
+
+                egui::CollapsingHeader::new("Fields")
+                    .id_salt("raw_entry_fields")
+                    .show(ui, |ui| {
+                        field_grid(ui, &self.bytes, &self.layout, self.offset)
+                    });
+            });
+    }
+}
+
+/// Reads the whole `osu.db` file, since entries can only be found by skipping over the ones before them.
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("Unable to read '{}': {}", path.display(), e))
+}
+
+/// Renders the bytes as rows of hex and ASCII, colouring the bytes of each field and naming it when hovered.
+fn hex_dump(ui: &mut egui::Ui, bytes: &[u8], layout: &EntryLayout, offset: usize) {
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let rows = bytes.len().div_ceil(BYTES_PER_ROW);
+
+    egui::ScrollArea::vertical()
+        .id_salt("raw_entry_dump")
+        .max_height(320.0)
+        .auto_shrink([false, true])
+        .show_rows(ui, row_height, rows, |ui, range| {
+            for row in range {
+                let start = row * BYTES_PER_ROW;
+                let end = (start + BYTES_PER_ROW).min(bytes.len());
+
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    ui.monospace(format!("{:08X}  ", offset + start));
+
+                    for (i, byte) in (start..).zip(&bytes[start..end]) {
+                        byte_label(ui, layout, i, format!("{:02X} ", byte));
+                    }
+
+                    // Keep the ASCII column lined up on the last row
+                    ui.monospace("   ".repeat(BYTES_PER_ROW - (end - start)));
+                    ui.monospace(" ");
+
+                    for (i, &byte) in (start..).zip(&bytes[start..end]) {
+                        let c = if byte.is_ascii_graphic() || byte == b' ' {
+                            byte as char
+                        } else {
+                            '.'
+                        };
+
+                        byte_label(ui, layout, i, c.to_string());
+                    }
+                });
+            }
+        });
+}
+
+/// Renders one byte of the dump, coloured by the field it belongs to. Bytes which aren't part of any field are shown in
+/// the error colour if the entry was incomplete, since that's where parsing went wrong.
+fn byte_label(ui: &mut egui::Ui, layout: &EntryLayout, i: usize, text: String) {
+    let field = layout
+        .fields
+        .iter()
+        .position(|field| field.range.contains(&i));
+
+    let text = egui::RichText::new(text).monospace();
+    let text = match field {
+        Some(field) => text.background_color(FIELD_COLOURS[field % FIELD_COLOURS.len()]),
+        None if layout.incomplete.is_some() => text.color(ui.visuals().error_fg_color),
+        None => text.weak(),
+    };
+
+    let response = ui.label(text);
+
+    match field.map(|field| &layout.fields[field]) {
+        Some(field) => response.on_hover_text(format!(
+            "{} ({}), bytes {}–{}",
+            field.name,
+            kind_name(field.kind),
+            field.range.start,
+            field.range.end
+        )),
+        None => response.on_hover_text(match layout.incomplete {
+            Some(name) => format!("Not read; {} was expected here", name),
+            None => "After the end of the entry".to_string(),
+        }),
+    };
+}
+
+/// Renders a table of the fields that were found, with their positions and bytes.
+fn field_grid(ui: &mut egui::Ui, bytes: &[u8], layout: &EntryLayout, offset: usize) {
+    egui::ScrollArea::vertical()
+        .id_salt("raw_entry_field_rows")
+        .max_height(240.0)
+        .show(ui, |ui| {
+            egui::Grid::new("raw_entry_field_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Offset");
+                    ui.strong("Field");
+                    ui.strong("Kind");
+                    ui.strong("Bytes");
+                    ui.end_row();
+
+                    for field in &layout.fields {
+                        ui.monospace(format!("0x{:X}", offset + field.range.start));
+                        ui.label(field.name);
+                        ui.label(kind_name(field.kind));
+                        ui.monospace(field_hex(bytes, field));
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+/// Describes how a field is stored.
+fn kind_name(kind: FieldKind) -> String {
+    match kind {
+        FieldKind::Fixed(1) => "1 byte".to_string(),
+        FieldKind::Fixed(size) => format!("{} bytes", size),
+        FieldKind::String => "string".to_string(),
+        FieldKind::List(item_size) => format!("list of {}-byte items", item_size),
+    }
+}
+
+/// Formats the bytes of a field as hex, shortening long fields so that the table stays readable.
+fn field_hex(bytes: &[u8], field: &RawField) -> String {
+    const MAX_BYTES: usize = 24;
+
+    let field_bytes = &bytes[field.range.clone()];
+    let hex = field_bytes
+        .iter()
+        .take(MAX_BYTES)
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if field_bytes.len() > MAX_BYTES {
+        format!("{} … ({} bytes)", hex, field_bytes.len())
+    } else {
+        hex
+    }
+}
+
+/// Formats each field's position, name and bytes as plain text, followed by any bytes that weren't part of a field.
+fn fields_text(bytes: &[u8], layout: &EntryLayout, offset: usize) -> String {
+    let hex = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let mut text = String::new();
+
+    for field in &layout.fields {
+        text.push_str(&format!(
+            "{:08X}  {:<24}  {}\n",
+            offset + field.range.start,
+            field.name,
+            hex(&bytes[field.range.clone()])
+        ));
+    }
+
+    let end = layout.fields.last().map_or(0, |field| field.range.end);
+
+    if let Some(name) = layout.incomplete {
+        text.push_str(&format!(
+            "{:08X}  {:<24}  {}\n",
+            offset + end,
+            format!("({}?)", name),
+            hex(&bytes[end..])
+        ));
+    }
+
+    text
+}
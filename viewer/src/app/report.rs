@@ -0,0 +1,321 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+};
+
+use osu_db_parser::{
+    prelude::*,
+    report::{self, ReportOptions, ReportTable, LARGE_REPORT_BYTES},
+};
+use time::OffsetDateTime;
+
+use super::{beatmap_table::BeatmapTable, format_count};
+use crate::widgets::file_dialog::FileDialog;
+
+/// A dialog for generating an HTML report of the loaded beatmap listing, which can be shared with others.
+///
+/// Reports are written on a worker thread, so that large tables don't block the window.
+pub struct ReportDialog {
+    pub visible: bool,
+    settings: ReportSettings,
+
+    /// File that the report will be written to once the user confirms that it's large, along with its estimated size
+    confirm: Option<(PathBuf, u64)>,
+
+    /// Report that is currently being written
+    task: Option<ReportTask>,
+
+    /// Result of the most recent report, or why it failed
+    status: Option<Result<String, String>>,
+}
+
+/// Represents the choices made in the dialog.
+#[derive(Clone)]
+struct ReportSettings {
+    title: String,
+
+    /// Number of beatmaps in each of the top beatmap lists
+    top_count: usize,
+
+    /// Whether to include a table of the beatmaps matching the current search
+    include_table: bool,
+
+    /// Which version of each beatmap's artist and title to show, which comes from the table being reported on
+    language: MetadataLanguage,
+}
+
+/// Represents a report being written on a worker thread.
+struct ReportTask {
+    path: PathBuf,
+    progress: Arc<Progress>,
+    result: mpsc::Receiver<Result<(), Error>>,
+}
+
+/// Represents the progress of a report, shared with the worker thread.
+#[derive(Default)]
+struct Progress {
+    written: AtomicUsize,
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Default for ReportDialog {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            settings: ReportSettings {
+                title: "My osu! Library".to_string(),
+                top_count: 10,
+                include_table: false,
+                language: MetadataLanguage::default(),
+            },
+            confirm: None,
+            task: None,
+            status: None,
+        }
+    }
+}
+
+impl ReportDialog {
+    /// Renders the dialog, generating a report when requested.
+    ///
+    /// The rows shown in `table` are the beatmaps matching the current search in display order, which are included in
+    /// the report's table along with the shown columns. `description` describes the current search.
+    pub fn view(
+        &mut self,
+        ctx: &egui::Context,
+        listing: &Arc<BeatmapListing>,
+        table: &BeatmapTable,
+        description: &str,
+    ) {
+        self.poll();
+
+        let rows = table.rows();
+        let columns = &table.displayed_columns();
+        self.settings.language = table.language();
+
+        let mut visible = self.visible;
+
+        egui::Window::new("Generate Report")
+            .open(&mut visible)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("report_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 8.0])
+                    .show(ui, |ui| {
+                        ui.label("Title");
+                        ui.text_edit_singleline(&mut self.settings.title);
+                        ui.end_row();
+
+                        ui.label("Top beatmaps");
+                        ui.add(egui::Slider::new(&mut self.settings.top_count, 1..=50))
+                            .on_hover_text(
+                                "Number of beatmaps in the hardest, longest and most recently added lists",
+                            );
+                        ui.end_row();
+
+                        ui.label("Table");
+                        ui.checkbox(
+                            &mut self.settings.include_table,
+                            format!("{} ({} beatmaps)", description, format_count(rows.len())),
+                        )
+                        .on_hover_text("Include a table of the beatmaps matching the current search, with the columns shown in the listing");
+                        ui.end_row();
+                    });
+
+                ui.weak("The report is a single HTML file, with its charts and styles embedded in it.");
+                ui.separator();
+
+                if let Some(task) = &self.task {
+                    let written = task.progress.written.load(Ordering::Relaxed);
+                    let total = task.progress.total.load(Ordering::Relaxed).max(1);
+
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::ProgressBar::new(written as f32 / total as f32)
+                                .desired_width(250.0)
+                                .text(format!("{} / {}", format_count(written), format_count(total))),
+                        );
+
+                        if ui.button("Cancel").clicked() {
+                            task.progress.cancelled.store(true, Ordering::Relaxed);
+                        }
+                    });
+
+                    ui.ctx().request_repaint_after(std::time::Duration::from_millis(100));
+                    return;
+                }
+
+                if let Some((_, size)) = &self.confirm {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "⚠ The report will be about {:.0} MB, which may be too large to share. Leaving out the table makes it much smaller.",
+                            *size as f64 / (1024.0 * 1024.0)
+                        ),
+                    );
+
+                    let (write, cancel) = ui
+                        .horizontal(|ui| {
+                            (
+                                ui.button("Write Anyway").clicked(),
+                                ui.button("Cancel").clicked(),
+                            )
+                        })
+                        .inner;
+
+                    if write {
+                        if let Some((path, _)) = self.confirm.take() {
+                            self.start(ctx, listing, rows, columns, description, path);
+                        }
+                    } else if cancel {
+                        self.confirm = None;
+                    }
+
+                    return;
+                }
+
+                if ui.button("Generate...").clicked() {
+                    if let Some(path) = FileDialog::save_file(None, "report.html") {
+                        let size = self
+                            .settings
+                            .options(listing, rows, columns, description)
+                            .estimated_size();
+
+                        if size > LARGE_REPORT_BYTES {
+                            self.confirm = Some((path, size));
+                        } else {
+                            self.start(ctx, listing, rows, columns, description, path);
+                        }
+                    }
+                }
+
+                match &self.status {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, message);
+                    }
+                    None => {}
+                }
+            });
+
+        self.visible = visible;
+    }
+
+    /// Starts writing the report to a file on a worker thread.
+    fn start(
+        &mut self,
+        ctx: &egui::Context,
+        listing: &Arc<BeatmapListing>,
+        rows: &[usize],
+        columns: &[Column],
+        description: &str,
+        path: PathBuf,
+    ) {
+        let progress = Arc::new(Progress::default());
+        let (tx, rx) = mpsc::channel();
+
+        // The options borrow the listing, so they're created on the worker thread
+        let settings = self.settings.clone();
+        let worker_progress = Arc::clone(&progress);
+        let worker_path = path.clone();
+        let listing = Arc::clone(listing);
+        let rows = rows.to_vec();
+        let columns = columns.to_vec();
+        let description = description.to_string();
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            let options = settings.options(&listing, &rows, &columns, &description);
+
+            let result = std::fs::File::create(&worker_path)
+                .map_err(Error::from)
+                .and_then(|file| {
+                    report::write(
+                        std::io::BufWriter::new(file),
+                        &listing.beatmaps,
+                        &options,
+                        |written, total| {
+                            worker_progress.written.store(written, Ordering::Relaxed);
+                            worker_progress.total.store(total, Ordering::Relaxed);
+                            !worker_progress.cancelled.load(Ordering::Relaxed)
+                        },
+                    )
+                });
+
+            // Don't leave behind a partially written file
+            if result.is_err() {
+                std::fs::remove_file(&worker_path).ok();
+            }
+
+            if tx.send(result).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+
+        self.status = None;
+        self.task = Some(ReportTask {
+            path,
+            progress,
+            result: rx,
+        });
+    }
+
+    /// Collects the result of the report, if it has finished.
+    fn poll(&mut self) {
+        let Some(task) = &self.task else {
+            return;
+        };
+
+        self.status = match task.result.try_recv() {
+            Ok(Ok(())) => Some(Ok(format!("Saved the report to {}", task.path.display()))),
+            Ok(Err(Error::Cancelled)) => Some(Ok("The report was cancelled.".to_string())),
+            Ok(Err(e)) => Some(Err(format!(
+                "Unable to write {}: {}",
+                task.path.display(),
+                e
+            ))),
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Some(Err("The report stopped unexpectedly.".to_string()))
+            }
+        };
+
+        self.task = None;
+    }
+}
+
+impl ReportSettings {
+    /// Gets the options for a report of the listing.
+    fn options<'a>(
+        &self,
+        listing: &'a BeatmapListing,
+        rows: &[usize],
+        columns: &[Column],
+        description: &str,
+    ) -> ReportOptions<'a> {
+        let table = self.include_table.then(|| ReportTable {
+            description: description.to_string(),
+            columns: columns.to_vec(),
+            beatmaps: rows
+                .iter()
+                .filter_map(|&i| listing.beatmaps.get(i))
+                .collect(),
+        });
+
+        ReportOptions {
+            title: self.title.trim().to_string(),
+            player_name: listing.player_name_str().map(str::to_string),
+            top_count: self.top_count,
+            table,
+            language: self.language,
+            generated: OffsetDateTime::now_utc(),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use osu_db_parser::{columns::ColumnValue, prelude::*};
+use serde::{Deserialize, Serialize};
+
+/// Gameplay modes, in the order that their summaries are stored.
+const MODES: [GameplayMode; 4] = [
+    GameplayMode::Standard,
+    GameplayMode::Taiko,
+    GameplayMode::Catch,
+    GameplayMode::Mania,
+];
+
+/// Represents a column of information about the local scores on a beatmap, which can be shown while a `scores.db` is
+/// loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScoreColumn {
+    /// Number of local scores, which is the closest thing to a play count that `scores.db` has
+    Count,
+    BestAccuracy,
+    BestGrade,
+
+    /// When the most recent score was set
+    LastScore,
+}
+
+/// Represents a score column to sort beatmaps by, along with the direction to sort in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreSortKey {
+    pub column: ScoreColumn,
+    pub order: SortOrder,
+}
+
+/// A lookup of the local scores on each beatmap, summarised for each gameplay mode. This is built once when a
+/// `scores.db` is loaded, so that the table doesn't need to go through every score when sorting.
+#[derive(Default)]
+pub struct LocalScores {
+    by_md5: HashMap<String, [Option<ScoreSummary>; 4]>,
+}
+
+impl ScoreColumn {
+    /// Every score column, in their default display order.
+    pub const ALL: [ScoreColumn; 4] = [
+        ScoreColumn::Count,
+        ScoreColumn::BestAccuracy,
+        ScoreColumn::BestGrade,
+        ScoreColumn::LastScore,
+    ];
+
+    /// Gets the header shown for this column.
+    pub fn header(&self) -> &'static str {
+        match self {
+            ScoreColumn::Count => "Local Scores",
+            ScoreColumn::BestAccuracy => "Best Accuracy",
+            ScoreColumn::BestGrade => "Best Grade",
+            ScoreColumn::LastScore => "Last Score",
+        }
+    }
+
+    /// Gets this column's value from a beatmap's scores, which is empty if there are no scores.
+    pub fn value(&self, summary: Option<&ScoreSummary>) -> ColumnValue<'static> {
+        let Some(summary) = summary else {
+            return ColumnValue::Empty;
+        };
+
+        match self {
+            ScoreColumn::Count => ColumnValue::Integer(summary.count as u64),
+            ScoreColumn::BestAccuracy => ColumnValue::Float(summary.best_accuracy),
+            ScoreColumn::BestGrade => ColumnValue::Grade(summary.best_grade),
+            ScoreColumn::LastScore => ColumnValue::DateTime(summary.last_played),
+        }
+    }
+
+    /// Compares two beatmaps' scores by this column, in ascending order.
+    fn compare(&self, a: &ScoreSummary, b: &ScoreSummary) -> Ordering {
+        match self {
+            ScoreColumn::Count => a.count.cmp(&b.count),
+            ScoreColumn::BestAccuracy => a.best_accuracy.total_cmp(&b.best_accuracy),
+            ScoreColumn::BestGrade => a.best_grade.cmp(&b.best_grade),
+            ScoreColumn::LastScore => a.last_played.cmp(&b.last_played),
+        }
+    }
+}
+
+impl ScoreSortKey {
+    /// Creates a sort key for a column, ordered so that the most played, best or most recently played beatmaps come
+    /// first.
+    pub fn new(column: ScoreColumn) -> ScoreSortKey {
+        ScoreSortKey {
+            column,
+            order: match column {
+                // Grades are ordered from best to worst
+                ScoreColumn::BestGrade => SortOrder::Ascending,
+                _ => SortOrder::Descending,
+            },
+        }
+    }
+
+    /// Compares two beatmaps' scores. Beatmaps without any scores are always placed last, regardless of the sort
+    /// order.
+    pub fn compare(&self, a: Option<&ScoreSummary>, b: Option<&ScoreSummary>) -> Ordering {
+        let ordering = match (a, b) {
+            (None, None) => return Ordering::Equal,
+            (None, _) => return Ordering::Greater,
+            (_, None) => return Ordering::Less,
+            (Some(a), Some(b)) => self.column.compare(a, b),
+        };
+
+        match self.order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    }
+}
+
+impl LocalScores {
+    /// Builds the lookup from the loaded scores, by beatmap MD5 hash.
+    pub fn new(scores: &HashMap<String, Vec<ScoreReplay>>) -> Self {
+        let by_md5 = scores
+            .iter()
+            .map(|(md5, scores)| {
+                (
+                    md5.clone(),
+                    MODES.map(|mode| ScoreSummary::of(scores, mode)),
+                )
+            })
+            .collect();
+
+        LocalScores { by_md5 }
+    }
+
+    /// Gets the summary of a beatmap's scores in a gameplay mode, or in the beatmap's own mode if `mode` is `None`.
+    pub fn get(&self, beatmap: &BeatmapEntry, mode: Option<GameplayMode>) -> Option<&ScoreSummary> {
+        let md5 = beatmap.md5.as_deref()?;
+        let mode = mode.unwrap_or(beatmap.gameplay_mode);
+
+        self.by_md5
+            .get(md5)
+            .and_then(|summaries| summaries[mode as usize].as_ref())
+    }
+}
@@ -0,0 +1,599 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use std::{cmp::Ordering, collections::HashMap};
+
+use egui::Id;
+use egui_extras::{Column as TableColumn, TableBuilder};
+use osu_db_parser::{columns::ColumnValue, prelude::*};
+
+use super::{format_count, grade_text, score_details::ScoreDetailsWindow};
+
+/// A view listing every local score, joined with the details of the beatmap it was set on.
+#[derive(Default)]
+pub struct ScoreListingView {
+    /// Every score from `scores.db`
+    scores: Vec<ScoreReplay>,
+
+    /// Indices of the scores to show, in display order
+    rows: Vec<usize>,
+
+    /// Number of scores set on beatmaps that aren't in the loaded `osu.db`
+    orphaned: usize,
+
+    /// Whether the rows need to be filtered and sorted again
+    stale: bool,
+
+    sort: Option<(ScoreColumn, SortOrder)>,
+    search: String,
+
+    /// Which version of the artist and title the beatmaps are named with
+    language: MetadataLanguage,
+
+    filter: OrphanFilter,
+    selected: Option<usize>,
+
+    /// osu! installation folder, used to locate replay files
+    #[cfg(not(target_arch = "wasm32"))]
+    osu_directory: Option<PathBuf>,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    replay_dialog: Option<ReplayDialog>,
+
+    score_windows: HashMap<String, ScoreDetailsWindow>,
+}
+
+/// Represents the columns shown in the score table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScoreColumn {
+    Date,
+    Beatmap,
+    Player,
+    Grade,
+    Mods,
+    Score,
+    Accuracy,
+    Combo,
+}
+
+/// Represents which scores to show, based on whether their beatmap is in the loaded `osu.db`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum OrphanFilter {
+    #[default]
+    All,
+
+    /// Only scores set on beatmaps in `osu.db`
+    Known,
+
+    /// Only scores set on beatmaps missing from `osu.db`
+    Orphaned,
+}
+
+/// Represents an action requested for a score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoreAction {
+    /// Show the details window for the score at this index
+    ShowDetails(usize),
+
+    /// Offer to show the replay file for the score at this index
+    RevealReplay(usize),
+}
+
+/// A dialog offering to show a score's replay file in the file manager.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ReplayDialog {
+    /// Expected location of the replay, if it could be determined
+    path: Option<PathBuf>,
+    open: bool,
+}
+
+impl ScoreColumn {
+    const ALL: [ScoreColumn; 8] = [
+        ScoreColumn::Date,
+        ScoreColumn::Beatmap,
+        ScoreColumn::Player,
+        ScoreColumn::Grade,
+        ScoreColumn::Mods,
+        ScoreColumn::Score,
+        ScoreColumn::Accuracy,
+        ScoreColumn::Combo,
+    ];
+
+    /// Gets the text shown in the column's header.
+    fn header(&self) -> &'static str {
+        match self {
+            ScoreColumn::Date => "Date",
+            ScoreColumn::Beatmap => "Beatmap",
+            ScoreColumn::Player => "Player",
+            ScoreColumn::Grade => "Grade",
+            ScoreColumn::Mods => "Mods",
+            ScoreColumn::Score => "Score",
+            ScoreColumn::Accuracy => "Accuracy",
+            ScoreColumn::Combo => "Combo",
+        }
+    }
+
+    /// Gets the initial width of the column.
+    fn initial_width(&self) -> f32 {
+        match self {
+            ScoreColumn::Date => 140.0,
+            ScoreColumn::Beatmap => 360.0,
+            ScoreColumn::Player => 120.0,
+            ScoreColumn::Grade | ScoreColumn::Mods | ScoreColumn::Score | ScoreColumn::Accuracy => {
+                80.0
+            }
+            ScoreColumn::Combo => 60.0,
+        }
+    }
+
+    /// Compares two scores by this column, in ascending order.
+    fn compare(
+        &self,
+        a: &ScoreReplay,
+        b: &ScoreReplay,
+        beatmaps: &HashMap<String, BeatmapEntry>,
+        language: MetadataLanguage,
+    ) -> Ordering {
+        match self {
+            ScoreColumn::Date => a.timestamp.cmp(&b.timestamp),
+            ScoreColumn::Beatmap => beatmap_name(a, beatmaps, language)
+                .to_lowercase()
+                .cmp(&beatmap_name(b, beatmaps, language).to_lowercase()),
+            ScoreColumn::Player => a
+                .player_name
+                .as_deref()
+                .unwrap_or_default()
+                .to_lowercase()
+                .cmp(&b.player_name.as_deref().unwrap_or_default().to_lowercase()),
+            ScoreColumn::Grade => (a.grade() as u8).cmp(&(b.grade() as u8)),
+            ScoreColumn::Mods => Mods::acronyms(a.mods).cmp(&Mods::acronyms(b.mods)),
+            ScoreColumn::Score => a.score.cmp(&b.score),
+            ScoreColumn::Accuracy => a.accuracy().total_cmp(&b.accuracy()),
+            ScoreColumn::Combo => a.max_combo.cmp(&b.max_combo),
+        }
+    }
+}
+
+impl OrphanFilter {
+    /// Gets the text shown for this option.
+    fn label(&self) -> &'static str {
+        match self {
+            OrphanFilter::All => "All Scores",
+            OrphanFilter::Known => "Known Beatmaps",
+            OrphanFilter::Orphaned => "Orphaned Scores",
+        }
+    }
+}
+
+impl ScoreListingView {
+    /// Loads every score from a score listing into this view.
+    pub fn load_score_listing(&mut self, scores: &HashMap<String, Vec<ScoreReplay>>) {
+        self.scores = scores.values().flatten().cloned().collect();
+        self.selected = None;
+        self.stale = true;
+
+        // Show the most recent scores first until the user picks a column
+        if self.sort.is_none() {
+            self.sort = Some((ScoreColumn::Date, SortOrder::Descending));
+        }
+    }
+
+    /// Sets the osu! installation folder, which is used to locate replay files.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_osu_directory(&mut self, osu_directory: Option<PathBuf>) {
+        self.osu_directory = osu_directory;
+    }
+
+    /// Sets which version of the artist and title the beatmaps are named with, sorting the rows again if it changed.
+    pub fn set_language(&mut self, language: MetadataLanguage) {
+        if language != self.language {
+            self.language = language;
+            self.stale = true;
+        }
+    }
+
+    /// Marks the rows as needing to be updated, e.g. after a different `osu.db` has been loaded.
+    pub fn refresh(&mut self) {
+        self.stale = true;
+    }
+
+    /// Renders the score listing view.
+    pub fn view(&mut self, ctx: &egui::Context, beatmaps: &HashMap<String, BeatmapEntry>) {
+        // Unload any closed windows
+        self.score_windows.retain(|_, w| w.visible);
+
+        for score_window in self.score_windows.values_mut() {
+            score_window.view(ctx);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(dialog) = &mut self.replay_dialog {
+            if !dialog.view(ctx) {
+                self.replay_dialog = None;
+            }
+        }
+
+        if self.stale {
+            self.update_rows(beatmaps);
+        }
+
+        egui::TopBottomPanel::bottom("s_status_bar").show_animated(
+            ctx,
+            !self.scores.is_empty(),
+            |ui| {
+                ui.label(format!(
+                    "{} of {} scores ({} orphaned)",
+                    format_count(self.rows.len()),
+                    format_count(self.scores.len()),
+                    format_count(self.orphaned)
+                ));
+            },
+        );
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Local Scores");
+
+            if self.scores.is_empty() {
+                ui.label("No scores.db loaded...");
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.search)
+                        .hint_text("Search by beatmap, player or MD5")
+                        .desired_width(300.0),
+                );
+
+                if response.changed() {
+                    self.stale = true;
+                }
+
+                egui::ComboBox::from_id_salt("s_orphan_filter")
+                    .selected_text(self.filter.label())
+                    .show_ui(ui, |ui| {
+                        for filter in [
+                            OrphanFilter::All,
+                            OrphanFilter::Known,
+                            OrphanFilter::Orphaned,
+                        ] {
+                            if ui
+                                .selectable_value(&mut self.filter, filter, filter.label())
+                                .changed()
+                            {
+                                self.stale = true;
+                            }
+                        }
+                    });
+            });
+
+            ui.separator();
+
+            if let Some(action) = self.table(ui, beatmaps) {
+                self.handle_action(action);
+            }
+        });
+
+        if self.stale {
+            self.update_rows(beatmaps);
+        }
+    }
+
+    /// Renders the table of scores, returning any action requested for a row.
+    fn table(
+        &mut self,
+        ui: &mut egui::Ui,
+        beatmaps: &HashMap<String, BeatmapEntry>,
+    ) -> Option<ScoreAction> {
+        let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+        let mut clicked_column = None;
+        let mut action = None;
+
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .sense(egui::Sense::click())
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .min_scrolled_height(0.0);
+
+        for column in ScoreColumn::ALL {
+            table = table.column(
+                TableColumn::initial(column.initial_width())
+                    .at_least(30.0)
+                    .clip(true),
+            );
+        }
+
+        table
+            .header(row_height, |mut header| {
+                for column in ScoreColumn::ALL {
+                    header.col(|ui| {
+                        let indicator = match self.sort {
+                            Some((sort_column, SortOrder::Ascending)) if sort_column == column => {
+                                " ⏶"
+                            }
+                            Some((sort_column, SortOrder::Descending)) if sort_column == column => {
+                                " ⏷"
+                            }
+                            _ => "",
+                        };
+
+                        let label =
+                            egui::RichText::new(format!("{}{}", column.header(), indicator))
+                                .strong();
+
+                        if ui.add(egui::Button::new(label).frame(false)).clicked() {
+                            clicked_column = Some(column);
+                        }
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(row_height, self.rows.len(), |mut row| {
+                    let index = self.rows[row.index()];
+                    let score = &self.scores[index];
+
+                    row.set_selected(self.selected == Some(index));
+
+                    for column in ScoreColumn::ALL {
+                        row.col(|ui| match column {
+                            ScoreColumn::Date => {
+                                ui.label(ColumnValue::DateTime(score.timestamp).to_string());
+                            }
+                            ScoreColumn::Beatmap if is_orphaned(score, beatmaps) => {
+                                ui.label(
+                                    egui::RichText::new(beatmap_name(
+                                        score,
+                                        beatmaps,
+                                        self.language,
+                                    ))
+                                    .monospace()
+                                    .weak(),
+                                )
+                                .on_hover_text("This beatmap isn't in the loaded osu.db");
+                            }
+                            ScoreColumn::Beatmap => {
+                                ui.label(beatmap_name(score, beatmaps, self.language));
+                            }
+                            ScoreColumn::Player => {
+                                ui.label(score.player_name.as_deref().unwrap_or_default());
+                            }
+                            ScoreColumn::Grade => {
+                                ui.label(grade_text(score.grade()));
+                            }
+                            ScoreColumn::Mods => {
+                                ui.label(Mods::acronyms(score.mods));
+                            }
+                            ScoreColumn::Score => {
+                                ui.label(format_count(score.score as usize));
+                            }
+                            ScoreColumn::Accuracy => {
+                                ui.label(format!("{:.2}%", score.accuracy()));
+                            }
+                            ScoreColumn::Combo => {
+                                ui.label(format!("{}x", score.max_combo));
+                            }
+                        });
+                    }
+
+                    let response = row.response();
+
+                    if response.clicked() {
+                        self.selected = Some(index);
+                    }
+
+                    if response.double_clicked() {
+                        action = Some(ScoreAction::RevealReplay(index));
+                    }
+
+                    response.context_menu(|ui| {
+                        self.selected = Some(index);
+
+                        if let Some(menu_action) = score_menu(ui, index) {
+                            action = Some(menu_action);
+                        }
+                    });
+                });
+            });
+
+        // Clicking the current sort column toggles the sort order
+        if let Some(column) = clicked_column {
+            self.sort = Some(match self.sort {
+                Some((sort_column, order)) if sort_column == column => (column, order.reversed()),
+                _ => (column, SortOrder::Ascending),
+            });
+
+            self.stale = true;
+        }
+
+        action
+    }
+
+    /// Filters and sorts the rows using the current search, filter and sort column.
+    fn update_rows(&mut self, beatmaps: &HashMap<String, BeatmapEntry>) {
+        let search = self.search.trim().to_lowercase();
+
+        self.orphaned = self
+            .scores
+            .iter()
+            .filter(|score| is_orphaned(score, beatmaps))
+            .count();
+
+        self.rows = (0..self.scores.len())
+            .filter(|&i| {
+                let score = &self.scores[i];
+
+                let shown = match self.filter {
+                    OrphanFilter::All => true,
+                    OrphanFilter::Known => !is_orphaned(score, beatmaps),
+                    OrphanFilter::Orphaned => is_orphaned(score, beatmaps),
+                };
+
+                shown
+                    && (search.is_empty()
+                        || beatmap_name(score, beatmaps, self.language)
+                            .to_lowercase()
+                            .contains(&search)
+                        || score
+                            .player_name
+                            .as_deref()
+                            .is_some_and(|p| p.to_lowercase().contains(&search))
+                        || score
+                            .beatmap_md5
+                            .as_deref()
+                            .is_some_and(|m| m.contains(&search)))
+            })
+            .collect();
+
+        if let Some((column, order)) = self.sort {
+            self.rows.sort_by(|&a, &b| {
+                let ordering =
+                    column.compare(&self.scores[a], &self.scores[b], beatmaps, self.language);
+
+                match order {
+                    SortOrder::Ascending => ordering,
+                    SortOrder::Descending => ordering.reverse(),
+                }
+            });
+        }
+
+        self.stale = false;
+    }
+
+    /// Handles an action requested for a score.
+    fn handle_action(&mut self, action: ScoreAction) {
+        match action {
+            ScoreAction::ShowDetails(i) => {
+                show_score_details(&mut self.score_windows, &self.scores[i], i)
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            ScoreAction::RevealReplay(i) => {
+                self.replay_dialog = Some(ReplayDialog::new(
+                    self.osu_directory.as_deref(),
+                    &self.scores[i],
+                ))
+            }
+
+            #[cfg(target_arch = "wasm32")]
+            ScoreAction::RevealReplay(_) => {}
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ReplayDialog {
+    /// Creates a dialog for a score's replay, which is expected to be in the `Data/r` folder.
+    pub fn new(osu_directory: Option<&Path>, score: &ScoreReplay) -> Self {
+        Self {
+            path: osu_directory.and_then(|dir| osu_db_parser::paths::replay_file(dir, score)),
+            open: true,
+        }
+    }
+
+    /// Renders the dialog, returning `false` once it has been closed.
+    pub fn view(&mut self, ctx: &egui::Context) -> bool {
+        let mut close = false;
+
+        egui::Window::new("Replay File")
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut self.open)
+            .show(ctx, |ui| {
+                match &self.path {
+                    Some(path) if path.is_file() => {
+                        ui.label("The replay for this score was found:");
+                        ui.monospace(path.display().to_string());
+
+                        ui.horizontal(|ui| {
+                            if ui.button("Show in File Manager").clicked() {
+                                super::reveal_in_file_manager(path);
+                                close = true;
+                            }
+
+                            if ui.button("Cancel").clicked() {
+                                close = true;
+                            }
+                        });
+                    }
+                    Some(path) => {
+                        ui.label("No replay file was found for this score. It may have been deleted.");
+                        ui.weak(format!("Expected location: {}", path.display()));
+                    }
+                    None => {
+                        ui.label(
+                            "The replay can't be located. Open osu.db or scores.db from the osu! folder so that its Data/r folder can be found.",
+                        );
+                    }
+                }
+            });
+
+        self.open && !close
+    }
+}
+
+/// Renders the context menu items for the score at an index, returning the chosen action.
+pub fn score_menu(ui: &mut egui::Ui, index: usize) -> Option<ScoreAction> {
+    let mut action = None;
+
+    if ui.button("Show Details").clicked() {
+        action = Some(ScoreAction::ShowDetails(index));
+        ui.close_menu();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if ui.button("Show Replay File").clicked() {
+        action = Some(ScoreAction::RevealReplay(index));
+        ui.close_menu();
+    }
+
+    action
+}
+
+/// Opens the details window for a score, replacing any window already open for it.
+pub fn show_score_details(
+    score_windows: &mut HashMap<String, ScoreDetailsWindow>,
+    score: &ScoreReplay,
+    index: usize,
+) {
+    let key = score
+        .replay_md5
+        .clone()
+        .unwrap_or_else(|| index.to_string());
+
+    score_windows.insert(
+        key.clone(),
+        ScoreDetailsWindow {
+            id: Id::new("score_details").with(key),
+            title: format!(
+                "{} - {} ({:.02}%)",
+                score.player_name.as_deref().unwrap_or_default(),
+                format_count(score.score as usize),
+                score.accuracy()
+            ),
+            visible: true,
+            data: score.clone(),
+        },
+    );
+}
+
+/// Checks whether a score was set on a beatmap that isn't in the loaded `osu.db`.
+fn is_orphaned(score: &ScoreReplay, beatmaps: &HashMap<String, BeatmapEntry>) -> bool {
+    score
+        .beatmap_md5
+        .as_ref()
+        .is_none_or(|md5| !beatmaps.contains_key(md5))
+}
+
+/// Gets the name of the beatmap a score was set on, or its raw MD5 hash if the beatmap isn't known.
+fn beatmap_name(
+    score: &ScoreReplay,
+    beatmaps: &HashMap<String, BeatmapEntry>,
+    language: MetadataLanguage,
+) -> String {
+    let md5 = score.beatmap_md5.as_deref().unwrap_or_default();
+
+    match beatmaps.get(md5) {
+        Some(beatmap) => beatmap.display_name(language),
+        None => md5.to_string(),
+    }
+}
@@ -0,0 +1,462 @@
+use std::sync::Arc;
+
+use osu_db_parser::{fuzzy::FuzzyIndex, prelude::*, query::QUERY_KEYS};
+
+use super::{
+    filter_bar::FilterBar,
+    presets::{FilterPreset, PresetAction, PresetMenu},
+};
+
+/// How long to wait after the user stops typing before filtering, in seconds.
+const DEBOUNCE_SECONDS: f64 = 0.15;
+
+/// Listings with more beatmaps than this are filtered on a background thread.
+#[cfg(not(target_arch = "wasm32"))]
+const BACKGROUND_FILTER_THRESHOLD: usize = 10_000;
+
+/// A search box which filters beatmaps using osu!'s song select query syntax (e.g. `stars>5 mode=mania`).
+///
+/// The query is combined with the quick filters in the filter bar shown below it.
+#[derive(Default)]
+pub struct SearchBox {
+    query: String,
+    filter_bar: FilterBar,
+
+    /// Time that the query was last edited, if it hasn't been applied yet
+    edited_at: Option<f64>,
+
+    /// Error for the most recent query, if it was invalid
+    error: Option<QueryError>,
+
+    presets: PresetMenu,
+
+    /// Filter from a preset that has just been applied, which is used instead of parsing the query
+    preset_filter: Option<BeatmapFilter>,
+
+    /// Receives the results of a filter running on a background thread
+    #[cfg(not(target_arch = "wasm32"))]
+    pending: Option<std::sync::mpsc::Receiver<Vec<usize>>>,
+
+    /// Results of the most recent filter, waiting to be collected
+    results: Option<Vec<usize>>,
+
+    /// Whether the search box should take keyboard focus the next time it's shown
+    focus_requested: bool,
+
+    /// Folded text of the loaded beatmaps, for matching the query approximately
+    index: Arc<FuzzyIndex>,
+
+    /// Whether a query that doesn't match anything exactly is matched approximately instead
+    fuzzy_fallback: bool,
+
+    /// Which star rating `stars` conditions use
+    star_policy: StarPolicy,
+
+    /// Gameplay mode of the tab this search is for, if it only shows one mode
+    tab: Option<GameplayMode>,
+}
+
+impl SearchBox {
+    /// Gets the current filter, including the filter bar and the tab's mode. The query is ignored if it is invalid.
+    pub fn filter(&self) -> BeatmapFilter {
+        self.with_filter_bar(self.query.parse().unwrap_or_default())
+    }
+
+    /// Gets the current query and quick filters, e.g. to include them in a bug report.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn query(&self) -> (&str, &FilterBar) {
+        (&self.query, &self.filter_bar)
+    }
+
+    /// Describes the current search, e.g. for the table of beatmaps in a report.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn description(&self) -> String {
+        match (self.query.trim(), self.filter_bar.is_active()) {
+            ("", false) => "Every beatmap".to_string(),
+            ("", true) => "Beatmaps matching the quick filters".to_string(),
+            (query, false) => format!("Beatmaps matching \"{}\"", query),
+            (query, true) => format!("Beatmaps matching \"{}\" and the quick filters", query),
+        }
+    }
+
+    /// Prepares to search a newly loaded listing, folding its text once so that fuzzy searches are quick.
+    pub fn set_listing(&mut self, listing: &BeatmapListing) {
+        self.index = Arc::new(FuzzyIndex::new(&listing.beatmaps));
+    }
+
+    /// Sets whether a query that doesn't match anything exactly is matched approximately instead, filtering again if
+    /// this changed. Returns whether it changed.
+    pub fn set_fuzzy_fallback(&mut self, fuzzy_fallback: bool) -> bool {
+        let changed = self.fuzzy_fallback != fuzzy_fallback;
+
+        if changed {
+            self.fuzzy_fallback = fuzzy_fallback;
+            self.edited_at = Some(f64::NEG_INFINITY);
+        }
+
+        changed
+    }
+
+    /// Sets which star rating `stars` conditions use, filtering again if this changed. Returns whether it changed.
+    pub fn set_star_policy(&mut self, star_policy: StarPolicy) -> bool {
+        let changed = self.star_policy != star_policy;
+
+        if changed {
+            self.star_policy = star_policy;
+            self.edited_at = Some(f64::NEG_INFINITY);
+        }
+
+        changed
+    }
+
+    /// Finds the beatmaps matching the current filter straight away, e.g. when a listing has just been loaded.
+    pub fn matching_rows(&self, beatmaps: &[BeatmapEntry], notes: &Notes) -> Vec<usize> {
+        matching_rows(
+            beatmaps,
+            notes,
+            &self.filter(),
+            &self.index,
+            self.fuzzy_fallback,
+        )
+    }
+
+    /// Gets the gameplay mode being shown, i.e. the tab's mode or the one selected in the filter bar if only one is
+    /// selected.
+    pub fn single_mode(&self) -> Option<GameplayMode> {
+        self.filter_bar.single_mode(self.tab)
+    }
+
+    /// Adds the conditions from the filter bar to a filter parsed from the query, using the chosen star rating.
+    fn with_filter_bar(&self, mut filter: BeatmapFilter) -> BeatmapFilter {
+        filter
+            .conditions
+            .extend(self.filter_bar.conditions(self.tab));
+        self.for_tab(filter)
+    }
+
+    /// Uses the chosen star rating in a filter, adapting it to the tab's mode if it only shows one mode.
+    fn for_tab(&self, mut filter: BeatmapFilter) -> BeatmapFilter {
+        filter.stars = self.star_policy;
+
+        match self.tab {
+            Some(mode) => filter.for_mode(mode),
+            None => filter,
+        }
+    }
+
+    /// Checks whether the results shown are up to date, i.e. no filter is waiting to be applied or still running.
+    pub fn is_settled(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.pending.is_some() {
+            return false;
+        }
+
+        self.edited_at.is_none() && self.preset_filter.is_none() && self.results.is_none()
+    }
+
+    /// Switches to searching a different tab, returning the query and filter bar of the previous tab. If `refilter` is
+    /// set, the new tab's filter is applied the next time the search box is shown, otherwise its previous results are
+    /// assumed to still be valid.
+    pub fn switch_tab(
+        &mut self,
+        tab: Option<GameplayMode>,
+        query: String,
+        filter_bar: FilterBar,
+        refilter: bool,
+    ) -> (String, FilterBar) {
+        self.cancel();
+        self.tab = tab;
+        self.error = None;
+        self.preset_filter = None;
+        self.edited_at = refilter.then_some(f64::NEG_INFINITY);
+
+        (
+            std::mem::replace(&mut self.query, query),
+            std::mem::replace(&mut self.filter_bar, filter_bar),
+        )
+    }
+
+    /// Replaces the query and the filter bar, e.g. to show the beatmaps in one of the overview's charts. The new
+    /// filter is applied the next time the search box is shown.
+    pub fn set_filter(&mut self, query: String, filter_bar: FilterBar) {
+        self.query = query;
+        self.filter_bar = filter_bar;
+        self.error = None;
+
+        // Skip the debounce, since the user isn't typing
+        self.edited_at = Some(f64::NEG_INFINITY);
+    }
+
+    /// Filters the listing again once the user stops editing the notes, if the filter depends on them. `time` is when
+    /// the notes were changed.
+    pub fn notes_changed(&mut self, time: f64) {
+        if self.filter().uses_notes() {
+            self.edited_at = Some(time);
+        }
+    }
+
+    /// Gives the search box keyboard focus the next time it's shown, e.g. when Ctrl+F is pressed.
+    pub fn focus(&mut self) {
+        self.focus_requested = true;
+    }
+
+    /// Applies a filter preset, replacing the query and the filter bar.
+    fn apply_preset(&mut self, preset: &FilterPreset) {
+        self.set_filter(preset.query.clone(), preset.filter_bar.clone());
+        self.preset_filter = Some(preset.filter.clone());
+    }
+
+    /// Saves the current query and filter bar as a preset, replacing any existing preset with the same name.
+    fn save_preset(&self, name: String, presets: &mut Vec<FilterPreset>) {
+        let preset = FilterPreset {
+            name,
+            query: self.query.clone(),
+            filter_bar: self.filter_bar.clone(),
+            filter: self.filter(),
+        };
+
+        match presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+    }
+
+    /// Renders the search box, filtering the listing once the user stops typing. Returns `true` if the filter presets
+    /// were changed, so that they can be saved.
+    ///
+    /// Invalid queries are shown as an error, and don't affect the previous results. `notes` are the user's notes, which
+    /// can be searched too.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        listing: &Arc<BeatmapListing>,
+        notes: &Arc<Notes>,
+        presets: &mut Vec<FilterPreset>,
+    ) -> bool {
+        let mut presets_changed = false;
+
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text("Search (e.g. stars>5 mode=mania camellia)")
+                    .desired_width(400.0),
+            );
+
+            if std::mem::take(&mut self.focus_requested) {
+                response.request_focus();
+            }
+
+            if response.changed() {
+                self.edited_at = Some(ui.input(|i| i.time));
+            }
+
+            help_button(ui);
+
+            let can_save = self.query.parse::<BeatmapFilter>().is_ok();
+            let (action, changed) = self.presets.show(ui, presets, can_save);
+            presets_changed = changed;
+
+            match action {
+                Some(PresetAction::Apply(i)) => self.apply_preset(&presets[i]),
+                Some(PresetAction::SaveCurrent(name)) => {
+                    self.save_preset(name, presets);
+                    presets_changed = true;
+                }
+                None => {}
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if self.pending.is_some() {
+                ui.spinner();
+            }
+        });
+
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+        }
+
+        // Changes to the filter bar are applied straight away, along with any pending changes to the query
+        if self.filter_bar.show(ui, self.tab) {
+            self.edited_at = None;
+            self.apply(ui.ctx(), listing, notes);
+        }
+
+        // Apply the query once the user has stopped typing for a moment
+        if let Some(edited_at) = self.edited_at {
+            let elapsed = ui.input(|i| i.time) - edited_at;
+
+            if elapsed >= DEBOUNCE_SECONDS {
+                self.edited_at = None;
+                self.apply(ui.ctx(), listing, notes);
+            } else {
+                ui.ctx()
+                    .request_repaint_after(std::time::Duration::from_secs_f64(
+                        DEBOUNCE_SECONDS - elapsed,
+                    ));
+            }
+        }
+
+        presets_changed
+    }
+
+    /// Parses the query and starts filtering the listing, or shows an error if the query is invalid.
+    fn apply(&mut self, ctx: &egui::Context, listing: &Arc<BeatmapListing>, notes: &Arc<Notes>) {
+        // Presets already include the filter bar, so they can be used as they are apart from the star rating, which
+        // follows the settings, and the tab's mode, since the preset may have been saved in a different tab
+        if let Some(mut filter) = self.preset_filter.take() {
+            if self.tab.is_some() {
+                filter
+                    .conditions
+                    .extend(self.filter_bar.mode_condition(self.tab));
+            }
+
+            let filter = self.for_tab(filter);
+
+            self.error = None;
+            self.start_filter(ctx, listing, notes, filter);
+            return;
+        }
+
+        match self.query.parse::<BeatmapFilter>() {
+            Ok(filter) => {
+                self.error = None;
+                let filter = self.with_filter_bar(filter);
+                self.start_filter(ctx, listing, notes, filter);
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Discards the results of any filter that is still running, e.g. because a different listing was loaded.
+    pub fn cancel(&mut self) {
+        self.results = None;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.pending = None;
+        }
+    }
+
+    /// Takes the results of the most recent filter, if it has finished.
+    pub fn poll(&mut self) -> Option<Vec<usize>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(pending) = &self.pending {
+            match pending.try_recv() {
+                Ok(rows) => {
+                    self.pending = None;
+                    return Some(rows);
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => {}
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+
+        self.results.take()
+    }
+
+    /// Starts filtering the listing, on a background thread if it's large enough.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    fn start_filter(
+        &mut self,
+        ctx: &egui::Context,
+        listing: &Arc<BeatmapListing>,
+        notes: &Arc<Notes>,
+        filter: BeatmapFilter,
+    ) {
+        self.results = None;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // Replacing the receiver means the results of any filter that is still running will be discarded
+            self.pending = None;
+
+            if listing.beatmaps.len() > BACKGROUND_FILTER_THRESHOLD {
+                let (tx, rx) = std::sync::mpsc::channel();
+                let listing = Arc::clone(listing);
+                let notes = Arc::clone(notes);
+                let index = Arc::clone(&self.index);
+                let fuzzy_fallback = self.fuzzy_fallback;
+                let ctx = ctx.clone();
+
+                std::thread::spawn(move || {
+                    let rows =
+                        matching_rows(&listing.beatmaps, &notes, &filter, &index, fuzzy_fallback);
+
+                    if tx.send(rows).is_ok() {
+                        ctx.request_repaint();
+                    }
+                });
+
+                self.pending = Some(rx);
+                return;
+            }
+        }
+
+        self.results = Some(matching_rows(
+            &listing.beatmaps,
+            notes,
+            &filter,
+            &self.index,
+            self.fuzzy_fallback,
+        ));
+    }
+}
+
+/// Finds the indices of the beatmaps which match a filter, using `notes` for any conditions on the user's notes (see
+/// [`BeatmapFilter::search`]). Approximate matches are ranked from closest to furthest.
+///
+/// Beatmaps without an MD5 hash are always skipped, since they are most likely from a corrupt database.
+fn matching_rows(
+    beatmaps: &[BeatmapEntry],
+    notes: &Notes,
+    filter: &BeatmapFilter,
+    index: &FuzzyIndex,
+    fuzzy_fallback: bool,
+) -> Vec<usize> {
+    filter
+        .search(beatmaps, notes, index, fuzzy_fallback)
+        .into_iter()
+        .filter(|&i| {
+            beatmaps[i]
+                .md5
+                .as_deref()
+                .is_some_and(|md5| !md5.is_empty())
+        })
+        .collect()
+}
+
+/// Renders a button which shows the supported query syntax when clicked.
+fn help_button(ui: &mut egui::Ui) {
+    let response = ui.button("?").on_hover_text("Search syntax");
+    let popup_id = ui.make_persistent_id("b_search_help");
+
+    if response.clicked() {
+        ui.memory_mut(|m| m.toggle_popup(popup_id));
+    }
+
+    egui::popup_below_widget(
+        ui,
+        popup_id,
+        &response,
+        egui::PopupCloseBehavior::CloseOnClickOutside,
+        |ui| {
+            ui.set_min_width(300.0);
+
+            ui.label("Words are matched against the artist, title, creator, difficulty, source and tags.");
+            ui.label("Filters have the form key<op>value, where <op> is one of = != < <= > >=.");
+            ui.label("Use double quotes for values containing spaces, e.g. artist=\"the quick brown fox\".");
+            ui.label("Start with ~ to match words approximately, e.g. ~fredom dive, with the closest matches first.");
+
+            ui.separator();
+            ui.strong("Supported keys");
+            ui.label(QUERY_KEYS.join(", "));
+
+            ui.separator();
+            ui.strong("Examples");
+            ui.monospace("stars>5.5 stars<6 mode=std status=ranked");
+            ui.monospace("length<2:00 unplayed=yes camellia");
+            ui.monospace("starred=yes note=practice");
+            ui.monospace("~fredom dive mode=std");
+        },
+    );
+}
@@ -0,0 +1,839 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+
+use std::collections::HashSet;
+
+use osu_db_parser::{
+    columns::ColumnValue,
+    expr::Field,
+    format::{self, DaylightSaving, TimeZone},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use time::{OffsetDateTime, UtcOffset};
+
+use super::{
+    accessibility::{MAX_SCALE, MIN_SCALE},
+    presets::FilterPreset,
+};
+use crate::i18n::{tr, Language};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::widgets::file_dialog::FileDialog;
+
+/// Gameplay modes that star ratings can be chosen from, in the order they're shown.
+const STAR_MODES: [GameplayMode; 4] = [
+    GameplayMode::Standard,
+    GameplayMode::Taiko,
+    GameplayMode::Catch,
+    GameplayMode::Mania,
+];
+
+/// Daylight saving rules that can be chosen for the time zone, in the order they're shown.
+const DAYLIGHT_SAVING: [DaylightSaving; 3] = [
+    DaylightSaving::None,
+    DaylightSaving::Europe,
+    DaylightSaving::NorthAmerica,
+];
+
+/// Name of the file that the settings are saved to, inside the platform's config folder.
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Represents the user's application settings, which are saved between sessions.
+///
+/// Settings that are missing from the saved file use their defaults, and unrecognised ones are ignored, so that
+/// settings files from older and newer versions can still be loaded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Language of the interface
+    pub language: Language,
+
+    /// Font to use for Chinese, Japanese and Korean text, or `None` to look for one installed on the system
+    #[cfg(not(target_arch = "wasm32"))]
+    pub cjk_font: Option<PathBuf>,
+
+    /// Whether to show the Unicode artist and title (when available) instead of the romanised ones
+    pub show_unicode: bool,
+
+    pub date_format: DateFormat,
+
+    /// Time zone that dates are shown in, as minutes ahead of UTC outside of daylight saving time
+    pub utc_offset_minutes: i16,
+
+    /// Daylight saving rules that the time zone follows
+    pub daylight_saving: DaylightSaving,
+
+    /// Whether to show dates in UTC instead of the time zone. Dates stored as local time are still converted using the
+    /// time zone.
+    pub show_utc: bool,
+
+    /// Number of decimal places to show for star ratings
+    pub star_decimals: usize,
+
+    /// Which star rating the Stars column and `stars` filters use
+    pub star_policy: StarPolicy,
+
+    pub theme: Theme,
+
+    /// Size of the interface, as a multiple of the size chosen by the operating system
+    pub ui_scale: f32,
+
+    /// Whether to use stronger colours and outlines, and stronger striping in tables
+    pub high_contrast: bool,
+
+    /// osu! installation folder to open on startup, and to start in when picking a folder
+    #[cfg(not(target_arch = "wasm32"))]
+    pub osu_folder: Option<PathBuf>,
+
+    /// Folder that osu! keeps beatmaps in, if it has been moved out of the installation's `Songs` folder using the
+    /// `BeatmapDirectory` setting
+    #[cfg(not(target_arch = "wasm32"))]
+    pub songs_folder: Option<PathBuf>,
+
+    /// Whether to load the `collection.db` file next to an `osu.db` file when opening it
+    pub load_collections: bool,
+
+    /// Whether to load the `scores.db` file when opening an osu! folder
+    pub load_scores: bool,
+
+    /// Whether to load the valid beatmaps from a damaged `osu.db` file without asking first
+    pub lenient_parsing: bool,
+
+    /// Whether a search that doesn't match anything exactly shows approximate matches instead
+    pub fuzzy_fallback: bool,
+
+    /// Number of backups kept of each database before it's saved, or 0 to not back databases up
+    #[cfg(not(target_arch = "wasm32"))]
+    pub keep_backups: usize,
+
+    /// Saved combinations of search query and quick filters, in the order they're shown
+    pub filter_presets: Vec<FilterPreset>,
+
+    /// Columns calculated from each beatmap's fields, which can be shown in the beatmap tables
+    pub custom_columns: Vec<CustomColumnSetting>,
+}
+
+/// Represents a column whose values are calculated from an expression over each beatmap's fields.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomColumnSetting {
+    /// Name shown in the column's header
+    pub name: String,
+
+    /// Expression to calculate, e.g. `objects / drain_time`
+    pub expression: String,
+}
+
+/// Represents the different ways that dates can be shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DateFormat {
+    /// e.g. `2024-03-25 18:30:00`
+    #[default]
+    Iso,
+
+    /// e.g. `25/03/2024 18:30`
+    DayMonthYear,
+
+    /// e.g. `03/25/2024 18:30`
+    MonthDayYear,
+
+    /// e.g. `3 days ago`
+    Relative,
+}
+
+/// Represents the colour scheme to use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    /// Follow the operating system's setting
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// Represents the settings that affect how beatmap values are shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueFormat {
+    pub date_format: DateFormat,
+    pub time_zone: TimeZone,
+    pub show_utc: bool,
+    pub star_decimals: usize,
+}
+
+/// A window for changing the settings, which are applied immediately.
+#[derive(Default)]
+pub struct SettingsWindow {
+    pub visible: bool,
+
+    /// CJK font that was loaded, which is shown when the font is chosen automatically
+    #[cfg(not(target_arch = "wasm32"))]
+    pub loaded_font: Option<PathBuf>,
+
+    /// Why the saved settings couldn't be loaded, if they were corrupt
+    notice: Option<String>,
+
+    /// Interface scale being chosen with the slider, which isn't applied until the slider is let go
+    dragged_scale: Option<f32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: Language::default(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            cjk_font: None,
+
+            show_unicode: false,
+            date_format: DateFormat::default(),
+            utc_offset_minutes: 0,
+            daylight_saving: DaylightSaving::None,
+            show_utc: false,
+            star_decimals: 2,
+            star_policy: StarPolicy::default(),
+            theme: Theme::default(),
+            ui_scale: 1.0,
+            high_contrast: false,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            osu_folder: None,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            songs_folder: None,
+
+            load_collections: true,
+            load_scores: true,
+            lenient_parsing: false,
+            fuzzy_fallback: true,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            keep_backups: osu_db_parser::backups::DEFAULT_KEEP,
+
+            filter_presets: Vec::new(),
+            custom_columns: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings saved in the config folder, using the defaults if they haven't been saved yet.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> std::io::Result<Settings> {
+        let Some(path) = settings_path() else {
+            return Ok(Settings::default());
+        };
+
+        match std::fs::read(path) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Checks whether the settings have been saved before, i.e. whether this isn't the first time the viewer has run.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn exist() -> bool {
+        settings_path().is_some_and(|path| path.exists())
+    }
+
+    /// Saves the settings to the config folder.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = settings_path() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "unable to locate the config folder",
+            ));
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Gets which version of each beatmap's artist and title to show.
+    pub fn metadata_language(&self) -> MetadataLanguage {
+        if self.show_unicode {
+            MetadataLanguage::Unicode
+        } else {
+            MetadataLanguage::Romanised
+        }
+    }
+
+    /// Gets the settings that affect how beatmap values are shown.
+    pub fn value_format(&self) -> ValueFormat {
+        ValueFormat {
+            date_format: self.date_format,
+            time_zone: TimeZone {
+                offset: utc_offset(self.utc_offset_minutes),
+                daylight_saving: self.daylight_saving,
+            },
+            show_utc: self.show_utc,
+            star_decimals: self.star_decimals,
+        }
+    }
+
+    /// Gets the custom columns that can be shown, skipping any that are invalid or have the same name as an earlier
+    /// column.
+    pub fn custom_columns(&self) -> Vec<CustomColumn> {
+        let mut names = HashSet::new();
+
+        self.custom_columns
+            .iter()
+            .filter_map(|column| column.compile().ok())
+            .filter(|column| names.insert(column.name.clone()))
+            .collect()
+    }
+}
+
+impl CustomColumnSetting {
+    /// Parses the column's expression, explaining why the column can't be shown if it's invalid.
+    pub fn compile(&self) -> Result<CustomColumn, String> {
+        let name = self.name.trim();
+
+        if name.is_empty() {
+            return Err(tr("Enter a name for the column").to_string());
+        }
+
+        CustomColumn::new(name, &self.expression).map_err(|e| e.to_string())
+    }
+}
+
+impl DateFormat {
+    const ALL: [DateFormat; 4] = [
+        DateFormat::Iso,
+        DateFormat::DayMonthYear,
+        DateFormat::MonthDayYear,
+        DateFormat::Relative,
+    ];
+
+    /// Formats a date and time, which has already been moved to the offset it's shown in. Missing dates are shown as
+    /// "never".
+    pub fn format(&self, datetime: Option<OffsetDateTime>) -> String {
+        let Some(datetime) = datetime else {
+            return format::NEVER.to_string();
+        };
+
+        let (year, month, day) = (datetime.year(), datetime.month() as u8, datetime.day());
+        let (hour, minute) = (datetime.hour(), datetime.minute());
+
+        match self {
+            DateFormat::Iso => format::date_time(datetime, datetime.offset()),
+            DateFormat::DayMonthYear => {
+                format!("{:02}/{:02}/{} {:02}:{:02}", day, month, year, hour, minute)
+            }
+            DateFormat::MonthDayYear => {
+                format!("{:02}/{:02}/{} {:02}:{:02}", month, day, year, hour, minute)
+            }
+            DateFormat::Relative => format::relative(datetime, OffsetDateTime::now_utc()),
+        }
+    }
+
+    /// Gets the name shown for the format in the settings.
+    fn label(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "Year-Month-Day",
+            DateFormat::DayMonthYear => "Day/Month/Year",
+            DateFormat::MonthDayYear => "Month/Day/Year",
+            DateFormat::Relative => "Relative (e.g. 3 days ago)",
+        }
+    }
+}
+
+impl Theme {
+    /// Gets the equivalent egui theme preference.
+    pub fn preference(&self) -> egui::ThemePreference {
+        match self {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Light => egui::ThemePreference::Light,
+            Theme::Dark => egui::ThemePreference::Dark,
+        }
+    }
+}
+
+impl Default for ValueFormat {
+    fn default() -> Self {
+        Settings::default().value_format()
+    }
+}
+
+impl ValueFormat {
+    /// Gets the text to show for a column's value, using the chosen date format and star rating precision.
+    pub fn text(&self, column: Column, value: &ColumnValue) -> String {
+        match value {
+            ColumnValue::DateTime(datetime) => self.datetime(
+                *datetime,
+                column.timestamp_kind().unwrap_or(TimestampKind::Utc),
+            ),
+            ColumnValue::Empty if column == Column::LastPlayed => format::NEVER.to_string(),
+            ColumnValue::Float(_) if is_stars_column(column) => {
+                format!("{:.*}", self.star_decimals, value)
+            }
+            value => value.to_string(),
+        }
+    }
+
+    /// Formats a stored date and time using the chosen format and time zone, or in UTC if it was chosen instead (see
+    /// [`TimeZone::zoned`]).
+    pub fn datetime(&self, datetime: OffsetDateTime, kind: TimestampKind) -> String {
+        let zoned =
+            format::known(datetime).and_then(|datetime| self.time_zone.zoned(datetime, kind));
+
+        self.date_format.format(match self.show_utc {
+            true => zoned.map(|datetime| datetime.to_offset(UtcOffset::UTC)),
+            false => zoned,
+        })
+    }
+
+    /// Formats a star rating.
+    pub fn stars(&self, rating: f64) -> String {
+        format!("{:.*}", self.star_decimals, rating)
+    }
+}
+
+impl SettingsWindow {
+    /// Shows a notice explaining that the saved settings couldn't be loaded, so the defaults are being used instead.
+    pub fn set_notice(&mut self, notice: String) {
+        self.notice = Some(notice);
+    }
+
+    /// Renders the settings window and any notice. Returns `true` if a setting was changed.
+    pub fn view(&mut self, ctx: &egui::Context, settings: &mut Settings) -> bool {
+        let mut notice_open = self.notice.is_some();
+
+        if let Some(notice) = &self.notice {
+            egui::Window::new("Unable to load settings")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut notice_open)
+                .show(ctx, |ui| {
+                    ui.label("Your saved settings appear to be corrupt, so the default settings are being used.");
+                    ui.label("They will be replaced the next time a setting is changed.");
+                    ui.weak(notice);
+                });
+        }
+
+        if !notice_open {
+            self.notice = None;
+        }
+
+        let previous = settings.clone();
+
+        egui::Window::new(tr("Settings"))
+            .id(egui::Id::new("settings_window"))
+            .open(&mut self.visible)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("settings_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 8.0])
+                    .show(ui, |ui| {
+                        ui.label(tr("Language"));
+                        egui::ComboBox::from_id_salt("settings_language")
+                            .selected_text(settings.language.name())
+                            .show_ui(ui, |ui| {
+                                for language in Language::ALL {
+                                    ui.selectable_value(
+                                        &mut settings.language,
+                                        language,
+                                        language.name(),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label(tr("Theme"));
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut settings.theme, Theme::System, tr("System"));
+                            ui.selectable_value(&mut settings.theme, Theme::Light, tr("Light"));
+                            ui.selectable_value(&mut settings.theme, Theme::Dark, tr("Dark"));
+                            ui.checkbox(&mut settings.high_contrast, tr("High contrast"));
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("Interface scale"))
+                            .on_hover_text(tr("Ctrl+Plus and Ctrl+Minus also change the scale, and Ctrl+0 resets it"));
+                        scale_slider(ui, &mut self.dragged_scale, &mut settings.ui_scale);
+                        ui.end_row();
+
+                        ui.label(tr("Artist and title"));
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut settings.show_unicode, false, tr("Romanised"));
+                            ui.selectable_value(&mut settings.show_unicode, true, tr("Unicode"))
+                                .on_hover_text(tr("Beatmaps without a Unicode artist or title show the romanised one instead"));
+                        });
+                        ui.end_row();
+
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            ui.label(tr("CJK font")).on_hover_text(tr(
+                                "Font used for Chinese, Japanese and Korean text that the default font doesn't cover",
+                            ));
+                            ui.horizontal(|ui| {
+                                match (&settings.cjk_font, &self.loaded_font) {
+                                    (Some(font), _) => ui.label(font.display().to_string()),
+                                    (None, Some(font)) => ui.weak(format!(
+                                        "{} ({})",
+                                        tr("Automatic"),
+                                        font.display()
+                                    )),
+                                    (None, None) => ui.weak(tr("No system font found")),
+                                };
+
+                                if ui.button(tr("Browse...")).clicked() {
+                                    let directory = settings
+                                        .cjk_font
+                                        .as_deref()
+                                        .or(self.loaded_font.as_deref())
+                                        .and_then(Path::parent);
+
+                                    if let Some(font) = FileDialog::pick_file(
+                                        directory,
+                                        "Fonts",
+                                        &["ttf", "otf", "ttc", "otc"],
+                                    ) {
+                                        settings.cjk_font = Some(font);
+                                    }
+                                }
+
+                                if settings.cjk_font.is_some() && ui.button(tr("Clear")).clicked() {
+                                    settings.cjk_font = None;
+                                }
+                            });
+                            ui.end_row();
+                        }
+
+                        ui.label(tr("Dates"));
+                        egui::ComboBox::from_id_salt("settings_date_format")
+                            .selected_text(settings.date_format.label())
+                            .show_ui(ui, |ui| {
+                                for date_format in DateFormat::ALL {
+                                    ui.selectable_value(
+                                        &mut settings.date_format,
+                                        date_format,
+                                        date_format.label(),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label(tr("Time zone"))
+                            .on_hover_text(tr("How far ahead of UTC the clocks are, outside of daylight saving time"));
+                        ui.add(
+                            egui::DragValue::new(&mut settings.utc_offset_minutes)
+                                .range(-12 * 60..=14 * 60)
+                                .speed(15)
+                                .custom_formatter(|minutes, _| format_utc_offset(minutes as i16)),
+                        );
+                        ui.end_row();
+
+                        ui.label(tr("Daylight saving"));
+                        egui::ComboBox::from_id_salt("settings_daylight_saving")
+                            .selected_text(tr(daylight_saving_label(settings.daylight_saving)))
+                            .show_ui(ui, |ui| {
+                                for daylight_saving in DAYLIGHT_SAVING {
+                                    ui.selectable_value(
+                                        &mut settings.daylight_saving,
+                                        daylight_saving,
+                                        tr(daylight_saving_label(daylight_saving)),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label(tr("UTC"));
+                        ui.checkbox(&mut settings.show_utc, tr("Show dates in UTC"))
+                            .on_hover_text(tr(
+                                "Last played and last checked dates are stored in osu!'s local time, which is converted using the time zone",
+                            ));
+                        ui.end_row();
+
+                        ui.label(tr("Star rating decimals"));
+                        ui.add(egui::Slider::new(&mut settings.star_decimals, 0..=4));
+                        ui.end_row();
+
+                        ui.label(tr("Star ratings")).on_hover_text(tr(
+                            "Which gameplay mode's star rating the Stars column and stars filters use",
+                        ));
+                        star_policy_settings(ui, &mut settings.star_policy);
+                        ui.end_row();
+
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            ui.label(tr("Default osu! folder")).on_hover_text(tr(
+                                "Opened on startup, and used as the starting folder when opening an osu! folder",
+                            ));
+                            ui.horizontal(|ui| {
+                                match &settings.osu_folder {
+                                    Some(folder) => ui.label(folder.display().to_string()),
+                                    None => ui.weak(tr("None")),
+                                };
+
+                                if ui.button(tr("Browse...")).clicked() {
+                                    if let Some(folder) =
+                                        FileDialog::pick_folder(settings.osu_folder.as_deref())
+                                    {
+                                        settings.osu_folder = Some(folder);
+                                    }
+                                }
+
+                                if settings.osu_folder.is_some() && ui.button(tr("Clear")).clicked() {
+                                    settings.osu_folder = None;
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Songs folder")).on_hover_text(tr(
+                                "Where osu! keeps beatmaps, if it has been set to keep them outside the osu! folder",
+                            ));
+                            ui.horizontal(|ui| {
+                                match &settings.songs_folder {
+                                    Some(folder) => ui.label(folder.display().to_string()),
+                                    None => ui.weak(tr("Songs in the osu! folder")),
+                                };
+
+                                if ui.button(tr("Browse...")).clicked() {
+                                    if let Some(folder) =
+                                        FileDialog::pick_folder(settings.songs_folder.as_deref())
+                                    {
+                                        settings.songs_folder = Some(folder);
+                                    }
+                                }
+
+                                if settings.songs_folder.is_some() && ui.button(tr("Clear")).clicked() {
+                                    settings.songs_folder = None;
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Backups")).on_hover_text(tr(
+                                "Kept in an osu-db-backups folder next to each database, and restored with File → Restore Backup...",
+                            ));
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut settings.keep_backups).range(0..=100));
+                                ui.label(tr("backups of each database before saving (0 turns them off)"));
+                            });
+                            ui.end_row();
+                        }
+
+                        ui.label(tr("When opening files"));
+                        ui.vertical(|ui| {
+                            // Other files can't be found on the web, since there's no access to the file system
+                            #[cfg(not(target_arch = "wasm32"))]
+                            {
+                                ui.checkbox(
+                                    &mut settings.load_collections,
+                                    tr("Also open collection.db from the same folder as osu.db"),
+                                );
+                                ui.checkbox(
+                                    &mut settings.load_scores,
+                                    tr("Also open scores.db when opening an osu! folder"),
+                                );
+                            }
+
+                            ui.checkbox(
+                                &mut settings.lenient_parsing,
+                                tr("Load the valid beatmaps from a damaged osu.db without asking"),
+                            );
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("Search"));
+                        ui.checkbox(
+                            &mut settings.fuzzy_fallback,
+                            tr("Show similar beatmaps when nothing matches exactly"),
+                        )
+                        .on_hover_text(tr("Start a search with ~ to always match approximately"));
+                        ui.end_row();
+
+                        ui.label(tr("Custom columns")).on_hover_text(tr(
+                            "Columns calculated from each beatmap's fields, which can be shown by right clicking the table's header",
+                        ));
+                        ui.vertical(|ui| custom_column_settings(ui, &mut settings.custom_columns));
+                        ui.end_row();
+                    });
+
+                ui.separator();
+
+                // Presets and custom columns aren't really settings, so they're kept
+                if ui.button(tr("Reset to Defaults")).clicked() {
+                    *settings = Settings {
+                        filter_presets: std::mem::take(&mut settings.filter_presets),
+                        custom_columns: std::mem::take(&mut settings.custom_columns),
+                        ..Settings::default()
+                    };
+                }
+            });
+
+        *settings != previous
+    }
+}
+
+/// Renders the slider for the interface scale. Scaling the interface while the slider is being dragged would move the
+/// slider out from under the pointer, so `ui_scale` is only changed once it's let go.
+fn scale_slider(ui: &mut egui::Ui, dragged_scale: &mut Option<f32>, ui_scale: &mut f32) {
+    let mut scale = dragged_scale.unwrap_or(*ui_scale);
+
+    let response = ui.add(
+        egui::Slider::new(&mut scale, MIN_SCALE..=MAX_SCALE)
+            .step_by(0.05)
+            .custom_formatter(|scale, _| format!("{:.0}%", scale * 100.0))
+            .custom_parser(|text| {
+                let percent = text.trim().trim_end_matches('%').trim_end();
+                percent.parse::<f64>().ok().map(|percent| percent / 100.0)
+            }),
+    );
+
+    if response.dragged() {
+        *dragged_scale = Some(scale);
+    } else {
+        *dragged_scale = None;
+        *ui_scale = scale;
+    }
+}
+
+/// Renders the list of custom columns, showing why any invalid column can't be shown.
+fn custom_column_settings(ui: &mut egui::Ui, columns: &mut Vec<CustomColumnSetting>) {
+    let mut names = HashSet::new();
+    let mut removed = None;
+
+    for (i, column) in columns.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut column.name)
+                    .hint_text(tr("Name"))
+                    .desired_width(100.0),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut column.expression)
+                    .hint_text("objects / drain_time")
+                    .code_editor()
+                    .desired_width(200.0),
+            );
+
+            if ui.button("🗑").on_hover_text(tr("Remove")).clicked() {
+                removed = Some(i);
+            }
+        });
+
+        let error = match column.compile() {
+            Ok(compiled) if !names.insert(compiled.name.clone()) => {
+                Some(tr("Another column already has this name").to_string())
+            }
+            Ok(_) => None,
+            Err(e) => Some(e),
+        };
+
+        if let Some(error) = error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    if let Some(i) = removed {
+        columns.remove(i);
+    }
+
+    let fields = Field::ALL.map(|field| field.name()).join(", ");
+
+    if ui
+        .button(tr("Add Column"))
+        .on_hover_text(format!(
+            "{}\n{}",
+            tr("Combine fields and numbers with + - * / and parentheses, using these fields:"),
+            fields
+        ))
+        .clicked()
+    {
+        columns.push(CustomColumnSetting::default());
+    }
+}
+
+/// Renders the settings for which star rating is used.
+fn star_policy_settings(ui: &mut egui::Ui, policy: &mut StarPolicy) {
+    let (mut mode, mut fallback) = match policy.source {
+        StarSource::NativeMode => (None, false),
+        StarSource::FixedMode(mode) => (Some(mode), false),
+        StarSource::PreferredWithFallback(mode) => (Some(mode), true),
+    };
+
+    ui.vertical(|ui| {
+        let own_mode = tr("Each beatmap's own mode");
+
+        egui::ComboBox::from_id_salt("settings_star_mode")
+            .selected_text(mode.map_or(own_mode.to_string(), |mode| mode.to_string()))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut mode, None, own_mode);
+
+                for star_mode in STAR_MODES {
+                    ui.selectable_value(&mut mode, Some(star_mode), star_mode.to_string());
+                }
+            });
+
+        ui.add_enabled(
+            mode.is_some(),
+            egui::Checkbox::new(
+                &mut fallback,
+                tr("Use the beatmap's own mode if there is no star rating for this mode"),
+            ),
+        )
+        .on_hover_text(tr(
+            "Only osu!standard beatmaps have star ratings for the other modes, which they can be converted to",
+        ));
+
+        ui.checkbox(
+            &mut policy.missing_as_zero,
+            tr("Treat beatmaps without a star rating as 0 stars"),
+        )
+        .on_hover_text(tr("Otherwise, they never match stars filters and are sorted last"));
+    });
+
+    policy.source = match (mode, fallback) {
+        (None, _) => StarSource::NativeMode,
+        (Some(mode), false) => StarSource::FixedMode(mode),
+        (Some(mode), true) => StarSource::PreferredWithFallback(mode),
+    };
+}
+
+/// Gets the path of the settings file, if the platform has a config folder.
+#[cfg(not(target_arch = "wasm32"))]
+fn settings_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "osu-db-viewer")
+        .map(|dirs| dirs.config_dir().join(SETTINGS_FILE))
+}
+
+/// Converts a time zone setting to an offset from UTC, falling back to UTC if it's out of range.
+fn utc_offset(minutes: i16) -> UtcOffset {
+    UtcOffset::from_whole_seconds(minutes as i32 * 60).unwrap_or(UtcOffset::UTC)
+}
+
+/// Gets the name shown for a time zone's daylight saving rules in the settings.
+fn daylight_saving_label(daylight_saving: DaylightSaving) -> &'static str {
+    match daylight_saving {
+        DaylightSaving::None => "None",
+        DaylightSaving::Europe => "Europe (March to October)",
+        DaylightSaving::NorthAmerica => "North America (March to November)",
+    }
+}
+
+/// Formats a time zone setting, e.g. `UTC+09:00` or `UTC-05:30`.
+fn format_utc_offset(minutes: i16) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = minutes.unsigned_abs();
+    format!("UTC{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+/// Checks whether a column shows star ratings.
+fn is_stars_column(column: Column) -> bool {
+    matches!(
+        column,
+        Column::Stars
+            | Column::StarsStd
+            | Column::StarsTaiko
+            | Column::StarsCatch
+            | Column::StarsMania
+    )
+}
@@ -0,0 +1,258 @@
+use std::path::{Path, PathBuf};
+
+use osu_db_parser::{
+    paths::{self, OsuFolder},
+    prelude::*,
+};
+
+use super::format_count;
+use crate::{i18n::tr, widgets::file_dialog::FileDialog};
+
+/// A window shown the first time the viewer runs, which looks for the osu! installation so that it can be opened
+/// straight away and on every startup after that.
+#[derive(Default)]
+pub struct SetupWizard {
+    visible: bool,
+
+    /// Folders that were searched for osu!, in the order they were searched
+    searched: Vec<PathBuf>,
+
+    /// Result of checking the folder that was found or chosen
+    outcome: Option<Result<Installation, Problem>>,
+
+    /// Folder entered by the user, when osu! couldn't be found or they want to use a different installation
+    manual_path: String,
+}
+
+/// Represents the user's choice in the wizard.
+pub enum WizardResponse {
+    /// Use this installation, saving it to the settings and opening it
+    Use(Installation),
+
+    /// Close the wizard without choosing an installation
+    Skip,
+}
+
+/// Represents an osu! installation whose `osu!.db` has been checked.
+pub struct Installation {
+    pub root: PathBuf,
+
+    /// Header of the installation's `osu!.db`, which was read to check that it's valid
+    pub header: ListingHeader,
+
+    /// Folder that beatmaps are kept in, if it exists
+    pub songs: Option<PathBuf>,
+
+    /// Folder set by `BeatmapDirectory` in the user's config file, if they've moved their beatmaps out of `Songs`
+    pub configured_songs: Option<PathBuf>,
+}
+
+/// Represents why a folder can't be used as the osu! installation.
+enum Problem {
+    /// None of the usual installation folders contain osu!
+    NotFound,
+
+    /// The folder doesn't contain any of osu!'s database files
+    NotOsuFolder(PathBuf),
+
+    /// The folder has other database files, but no `osu!.db`
+    MissingBeatmaps(PathBuf),
+
+    /// The folder's `osu!.db` couldn't be read
+    InvalidBeatmaps(PathBuf, Error),
+}
+
+impl SetupWizard {
+    /// Shows the wizard and searches the usual installation folders for osu!.
+    pub fn open(&mut self) {
+        self.visible = true;
+        self.searched = paths::default_locations();
+
+        // Stop at the first folder containing osu!, even if it has problems, since that's the one the user would expect
+        // to be used
+        self.outcome = Some(
+            self.searched
+                .iter()
+                .find(|location| OsuFolder::discover(location).is_some())
+                .map_or(Err(Problem::NotFound), |location| check(location)),
+        );
+    }
+
+    /// Renders the wizard, returning the user's choice once they've made one.
+    pub fn view(&mut self, ctx: &egui::Context) -> Option<WizardResponse> {
+        if !self.visible {
+            return None;
+        }
+
+        let mut response = None;
+
+        egui::Window::new(tr("Welcome"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(tr(
+                    "The viewer can open your osu! installation on startup. It has been looked for in the usual places.",
+                ));
+                ui.separator();
+
+                match &self.outcome {
+                    Some(Ok(installation)) => installation_summary(ui, installation),
+                    Some(Err(problem)) => self.problem_summary(ui, problem),
+                    None => {}
+                }
+
+                ui.separator();
+                ui.label(tr("Or choose the osu! folder yourself:"));
+
+                ui.horizontal(|ui| {
+                    let edit = ui.text_edit_singleline(&mut self.manual_path);
+                    let entered = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                    if ui.button(tr("Browse...")).clicked() {
+                        if let Some(folder) = FileDialog::pick_folder(None) {
+                            self.manual_path = folder.display().to_string();
+                            self.outcome = Some(check(&folder));
+                        }
+                    }
+
+                    if (ui.button(tr("Check")).clicked() || entered) && !self.manual_path.is_empty() {
+                        self.outcome = Some(check(Path::new(self.manual_path.trim())));
+                    }
+                });
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let usable = matches!(self.outcome, Some(Ok(_)));
+
+                    if ui
+                        .add_enabled(usable, egui::Button::new(tr("Open")))
+                        .on_hover_text(tr("Saves the folder to the settings, then opens it"))
+                        .clicked()
+                    {
+                        if let Some(Ok(installation)) = self.outcome.take() {
+                            response = Some(WizardResponse::Use(installation));
+                        }
+                    }
+
+                    if ui
+                        .button(tr("Skip"))
+                        .on_hover_text(tr("The folder can be chosen later in the settings"))
+                        .clicked()
+                    {
+                        response = Some(WizardResponse::Skip);
+                    }
+                });
+            });
+
+        if response.is_some() {
+            self.visible = false;
+        }
+
+        response
+    }
+
+    /// Explains why osu! couldn't be used, and what to do about it.
+    fn problem_summary(&self, ui: &mut egui::Ui, problem: &Problem) {
+        let error = ui.visuals().error_fg_color;
+
+        match problem {
+            Problem::NotFound => {
+                ui.colored_label(error, tr("✖ osu! wasn't found in any of these folders:"));
+
+                for location in &self.searched {
+                    ui.weak(location.display().to_string());
+                }
+            }
+            Problem::NotOsuFolder(root) => {
+                ui.colored_label(
+                    error,
+                    format!(
+                        "✖ {} doesn't contain osu!.db, collection.db or scores.db, so it doesn't look like an osu! folder.",
+                        root.display()
+                    ),
+                );
+            }
+            Problem::MissingBeatmaps(root) => {
+                ui.colored_label(
+                    error,
+                    format!("✖ {} doesn't contain osu!.db.", root.display()),
+                );
+                ui.label(tr(
+                    "osu! creates it when it first runs, so try starting osu! once and then checking again.",
+                ));
+            }
+            Problem::InvalidBeatmaps(root, e) => {
+                ui.colored_label(
+                    error,
+                    format!("✖ osu!.db in {} couldn't be read: {}", root.display(), e),
+                );
+                ui.label(tr(
+                    "It may be damaged, or from a version of osu! that isn't supported. Files can still be opened from the File menu.",
+                ));
+            }
+        }
+    }
+}
+
+/// Shows what was found in an installation, including any problems that don't stop it from being used.
+fn installation_summary(ui: &mut egui::Ui, installation: &Installation) {
+    let header = &installation.header;
+
+    ui.label(format!("✔ Found osu! in {}", installation.root.display()));
+    ui.label(format!(
+        "✔ osu!.db belongs to {} and lists {} beatmaps",
+        header.player_name_str().unwrap_or("an unknown player"),
+        format_count(header.beatmap_count as usize)
+    ));
+
+    match (&installation.songs, &installation.configured_songs) {
+        (Some(songs), Some(_)) => {
+            ui.label(format!(
+                "✔ Beatmaps are kept in {}, as set in osu!'s config file",
+                songs.display()
+            ));
+        }
+        (Some(songs), None) => {
+            ui.label(format!("✔ Beatmaps are kept in {}", songs.display()));
+        }
+        (None, configured) => {
+            let missing = configured
+                .clone()
+                .unwrap_or_else(|| installation.root.join("Songs"));
+
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("⚠ The beatmaps folder {} doesn't exist.", missing.display()),
+            );
+            ui.label(tr(
+                "The databases can still be opened, but beatmap files, audio and backgrounds won't be found until it does. It can be changed in the settings.",
+            ));
+        }
+    }
+}
+
+/// Checks whether a folder is an osu! installation with a readable `osu!.db`. Only the `osu!.db` header is read, so
+/// this is quick even for large databases.
+fn check(root: &Path) -> Result<Installation, Problem> {
+    let folder =
+        OsuFolder::discover(root).ok_or_else(|| Problem::NotOsuFolder(root.to_path_buf()))?;
+    let beatmaps = folder
+        .beatmaps
+        .ok_or_else(|| Problem::MissingBeatmaps(root.to_path_buf()))?;
+    let header = ListingHeader::from_file(beatmaps)
+        .map_err(|e| Problem::InvalidBeatmaps(root.to_path_buf(), e))?;
+
+    // The player's name picks out their config file if several Windows users share the installation
+    let player_name = header.player_name_str();
+    let songs = Some(paths::songs_dir_guess(root, player_name)).filter(|path| path.is_dir());
+    let configured_songs = paths::configured_songs_folder(root, player_name);
+
+    Ok(Installation {
+        root: folder.root,
+        header,
+        songs,
+        configured_songs,
+    })
+}
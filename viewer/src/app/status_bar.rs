@@ -0,0 +1,244 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::time::Duration;
+
+use osu_db_parser::{footprint::Footprint, format, prelude::*};
+use time::UtcOffset;
+
+use super::{format_count, format_size};
+
+/// A bar along the bottom of the window showing which files are loaded, how long they took to parse, and roughly how
+/// much memory they use.
+#[derive(Default)]
+pub struct StatusBar {
+    beatmaps: Option<LoadedFile>,
+    collections: Option<LoadedFile>,
+    scores: Option<LoadedFile>,
+}
+
+/// Represents a loaded database file.
+struct LoadedFile {
+    /// Name of the kind of file, e.g. `osu.db`
+    name: &'static str,
+
+    /// Where the file was read from, if known
+    #[cfg(not(target_arch = "wasm32"))]
+    path: Option<PathBuf>,
+
+    version: u32,
+
+    /// Number of entries in the file, e.g. beatmaps
+    entries: usize,
+
+    /// How long parsing took, if it could be measured
+    parse_time: Option<Duration>,
+
+    /// Estimated memory used by the parsed file, in bytes
+    footprint: usize,
+}
+
+impl StatusBar {
+    /// Records a loaded `osu.db` file.
+    pub fn set_beatmaps(
+        &mut self,
+        listing: &BeatmapListing,
+        parse_time: Option<Duration>,
+        #[cfg(not(target_arch = "wasm32"))] path: Option<PathBuf>,
+    ) {
+        self.beatmaps = Some(LoadedFile {
+            name: "osu.db",
+            #[cfg(not(target_arch = "wasm32"))]
+            path,
+            version: listing.version,
+            entries: listing.beatmaps.len(),
+            parse_time,
+            footprint: listing.footprint(),
+        });
+    }
+
+    /// Records a loaded `collection.db` file.
+    pub fn set_collections(
+        &mut self,
+        listing: &CollectionListing,
+        parse_time: Option<Duration>,
+        #[cfg(not(target_arch = "wasm32"))] path: Option<PathBuf>,
+    ) {
+        self.collections = Some(LoadedFile {
+            name: "collection.db",
+            #[cfg(not(target_arch = "wasm32"))]
+            path,
+            version: listing.version,
+            entries: listing.collections.len(),
+            parse_time,
+            footprint: listing.footprint(),
+        });
+    }
+
+    /// Records a loaded `scores.db` file.
+    pub fn set_scores(
+        &mut self,
+        listing: &ScoreListing,
+        parse_time: Option<Duration>,
+        #[cfg(not(target_arch = "wasm32"))] path: Option<PathBuf>,
+    ) {
+        self.scores = Some(LoadedFile {
+            name: "scores.db",
+            #[cfg(not(target_arch = "wasm32"))]
+            path,
+            version: listing.version,
+            entries: listing
+                .beatmap_scores
+                .iter()
+                .map(|beatmap| beatmap.scores.len())
+                .sum(),
+            parse_time,
+            footprint: listing.footprint(),
+        });
+    }
+
+    /// Renders the status bar. `listing` is the loaded beatmap listing, of which `shown` beatmaps match the current
+    /// search.
+    pub fn view(&self, ctx: &egui::Context, listing: Option<&BeatmapListing>, shown: usize) {
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let files = [&self.beatmaps, &self.collections, &self.scores];
+
+                if files.iter().all(|file| file.is_none()) {
+                    ui.weak("No files loaded");
+                    return;
+                }
+
+                if let Some(file) = &self.beatmaps {
+                    file.show(ui, "beatmaps");
+
+                    if let Some(listing) = listing {
+                        ui.label(format!(
+                            "({} shown)",
+                            format_count(shown.min(listing.beatmaps.len()))
+                        ));
+
+                        ui.label(format!(
+                            "· {}",
+                            listing.player_name_str().unwrap_or("(no player name)")
+                        ));
+
+                        account_status(ui, listing.account_status());
+                    }
+                }
+
+                if let Some(file) = &self.collections {
+                    ui.separator();
+                    file.show(ui, "collections");
+                }
+
+                if let Some(file) = &self.scores {
+                    ui.separator();
+                    file.show(ui, "scores");
+                }
+
+                let footprint = files
+                    .iter()
+                    .filter_map(|file| file.as_ref())
+                    .map(|file| file.footprint)
+                    .sum::<usize>();
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.weak(format!("≈ {} in memory", format_size(footprint as u64)))
+                        .on_hover_text(
+                            "Estimated from the sizes of the loaded entries and their text, \
+                             not including the interface or allocator overhead",
+                        );
+                });
+            });
+        });
+    }
+}
+
+impl LoadedFile {
+    /// Renders the file's name, version, number of entries and parse time. `entry_name` is what the entries are, e.g.
+    /// `beatmaps`.
+    fn show(&self, ui: &mut egui::Ui, entry_name: &str) {
+        #[cfg(not(target_arch = "wasm32"))]
+        match &self.path {
+            Some(path) => {
+                if ui
+                    .link(self.name)
+                    .on_hover_text(format!("{}\nClick to show in folder", path.display()))
+                    .clicked()
+                {
+                    super::reveal_in_file_manager(path);
+                }
+            }
+            None => {
+                ui.strong(self.name);
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        ui.strong(self.name);
+
+        let mut details = format!(
+            "v{} · {} {}",
+            self.version,
+            format_count(self.entries),
+            entry_name
+        );
+
+        if let Some(parse_time) = self.parse_time {
+            details.push_str(&format!(" · parsed in {}", format_duration(parse_time)));
+        }
+
+        ui.label(details).on_hover_text(format!(
+            "≈ {} in memory",
+            format_size(self.footprint as u64)
+        ));
+    }
+}
+
+/// Renders a warning if the account that the listing belongs to is locked, e.g. because it was restricted.
+fn account_status(ui: &mut egui::Ui, status: AccountStatus) {
+    let text = match status {
+        AccountStatus::Unlocked => return,
+        AccountStatus::LockedUntil(date) => format!(
+            "· Account locked until {}",
+            format::short_date(date, UtcOffset::UTC)
+        ),
+        AccountStatus::LockedIndefinitely => "· Account locked".to_string(),
+    };
+
+    let hover_text = match status {
+        AccountStatus::LockedUntil(date) => format!(
+            "osu! will unlock the account at {} UTC",
+            format::date_time(date, UtcOffset::UTC)
+        ),
+        _ => "The account is locked with no unlock date".to_string(),
+    };
+
+    ui.colored_label(ui.visuals().warn_fg_color, text)
+        .on_hover_text(hover_text);
+}
+
+/// Measures how long `parse` takes, where the platform has a clock to do so.
+pub fn timed<T>(parse: impl FnOnce() -> T) -> (T, Option<Duration>) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let start = std::time::Instant::now();
+        let result = parse();
+        (result, Some(start.elapsed()))
+    }
+
+    // std::time::Instant isn't available on the web
+    #[cfg(target_arch = "wasm32")]
+    {
+        (parse(), None)
+    }
+}
+
+/// Formats a short duration, e.g. `850 ms` or `1.25 s`.
+fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{} ms", duration.as_millis())
+    } else {
+        format!("{:.2} s", duration.as_secs_f64())
+    }
+}
@@ -0,0 +1,305 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use egui::{
+    text::{LayoutJob, TextWrapping},
+    Color32, Galley,
+};
+
+/// Most laid out texts kept at once. This covers several thousand cells, which is many screens of scrolling.
+const CAPACITY: usize = 8192;
+
+/// Identifies the laid out text of a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextKey {
+    /// Column the cell is in, so that the column's text can be dropped when its width changes
+    pub column: usize,
+
+    /// Hash of the text
+    pub text: u64,
+
+    pub color: Color32,
+
+    /// Width the text is cut short to, in hundredths of a point
+    pub width: u32,
+}
+
+/// Counts of the lookups made since the stats were last taken, and the number of texts kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u32,
+    pub misses: u32,
+    pub entries: usize,
+}
+
+/// Keeps the laid out text of the beatmap table's cells, so that scrolling doesn't shape the same artists and titles
+/// again every frame.
+///
+/// egui only keeps a galley until the end of the frame after it was last shown, so text scrolled out of view and back
+/// is shaped again, and each label still builds and hashes a layout job first. Here, a cell's text is found from its
+/// hash, colour and width alone.
+///
+/// Texts are kept in two generations, each holding up to half the capacity. When the newer one is full, the older one
+/// is dropped and the newer one takes its place, and texts used from the older one are moved to the newer one. This
+/// keeps the texts that were shown most recently without tracking when each one was used.
+pub struct TextCache<V = Arc<Galley>> {
+    current: HashMap<TextKey, V>,
+    previous: HashMap<TextKey, V>,
+    capacity: usize,
+
+    /// Width of each column when its text was last looked up
+    widths: Vec<f32>,
+
+    /// Fingerprint of the scale, fonts and colours that the texts were laid out with
+    style: u64,
+
+    hits: u32,
+    misses: u32,
+}
+
+impl<V: Clone> Default for TextCache<V> {
+    fn default() -> Self {
+        Self::with_capacity(CAPACITY)
+    }
+}
+
+impl<V: Clone> TextCache<V> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            current: HashMap::new(),
+            previous: HashMap::new(),
+            capacity,
+            widths: Vec::new(),
+            style: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Drops every text, e.g. when another library is loaded.
+    pub fn clear(&mut self) {
+        self.current = HashMap::new();
+        self.previous = HashMap::new();
+        self.widths.clear();
+    }
+
+    /// Drops every text if the style they were laid out with has changed, e.g. after the theme or scale changed.
+    pub fn set_style(&mut self, style: u64) {
+        if style != self.style {
+            self.style = style;
+            self.clear();
+        }
+    }
+
+    /// Drops a column's text if the column's width has changed, since it's cut short to the old width.
+    pub fn set_column_width(&mut self, column: usize, width: f32) {
+        if self.widths.len() <= column {
+            self.widths.resize(column + 1, f32::NAN);
+        }
+
+        if self.widths[column] == width {
+            return;
+        }
+
+        // Widths start as NaN, which is never equal, so there's only anything to drop after the first change
+        if !self.widths[column].is_nan() {
+            self.current.retain(|key, _| key.column != column);
+            self.previous.retain(|key, _| key.column != column);
+        }
+
+        self.widths[column] = width;
+    }
+
+    /// Gets a cell's text, laying it out if it isn't kept.
+    pub fn get_or_insert_with(&mut self, key: TextKey, layout: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.current.get(&key) {
+            self.hits += 1;
+            return value.clone();
+        }
+
+        let value = match self.previous.remove(&key) {
+            Some(value) => {
+                self.hits += 1;
+                value
+            }
+            None => {
+                self.misses += 1;
+                layout()
+            }
+        };
+
+        if self.current.len() >= self.capacity / 2 {
+            self.previous = std::mem::take(&mut self.current);
+        }
+
+        self.current.insert(key, value.clone());
+        value
+    }
+
+    /// Takes the number of lookups made since this was last called, along with the number of texts kept.
+    pub fn take_stats(&mut self) -> CacheStats {
+        let stats = CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.current.len() + self.previous.len(),
+        };
+
+        self.hits = 0;
+        self.misses = 0;
+        stats
+    }
+}
+
+impl TextCache {
+    /// Shows a cell's text, cut short with `…` if it doesn't fit in the cell. The full text is shown on hover if it's
+    /// cut short.
+    pub fn label(
+        &mut self,
+        ui: &mut egui::Ui,
+        column: usize,
+        text: &str,
+        weak: bool,
+    ) -> egui::Response {
+        self.set_column_width(column, ui.max_rect().width());
+
+        let color = if weak {
+            ui.visuals().weak_text_color()
+        } else {
+            ui.visuals().text_color()
+        };
+        let width = ui.available_width();
+
+        let key = TextKey {
+            column,
+            text: hash(text),
+            color,
+            width: (width * 100.0).round() as u32,
+        };
+
+        let galley = self.get_or_insert_with(key, || {
+            let font_id = egui::TextStyle::Body.resolve(ui.style());
+            let mut job = LayoutJob::simple_singleline(text.to_owned(), font_id, color);
+            job.wrap = TextWrapping::truncate_at_width(width);
+
+            ui.fonts(|fonts| fonts.layout_job(job))
+        });
+
+        ui.add(egui::Label::new(galley))
+    }
+}
+
+/// Gets a fingerprint of everything besides the text, colour and width that changes how a cell's text is laid out.
+pub fn style_fingerprint(ui: &egui::Ui) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    ui.ctx().pixels_per_point().to_bits().hash(&mut hasher);
+    egui::TextStyle::Body.resolve(ui.style()).hash(&mut hasher);
+    ui.visuals().dark_mode.hash(&mut hasher);
+
+    // Fonts are added when a fallback font for Chinese, Japanese and Korean text is loaded
+    ui.ctx().fonts(|fonts| {
+        let fonts = fonts.lock();
+        let definitions = fonts.fonts.definitions();
+        definitions
+            .font_data
+            .keys()
+            .for_each(|name| name.hash(&mut hasher));
+        definitions.families.hash(&mut hasher);
+    });
+
+    hasher.finish()
+}
+
+fn hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(column: usize, text: &str, width: u32) -> TextKey {
+        TextKey {
+            column,
+            text: hash(text),
+            color: Color32::WHITE,
+            width,
+        }
+    }
+
+    /// Looks up a text, returning whether it had to be laid out.
+    fn missed(cache: &mut TextCache<u32>, key: TextKey) -> bool {
+        let mut missed = false;
+        cache.get_or_insert_with(key, || {
+            missed = true;
+            1
+        });
+        missed
+    }
+
+    #[test]
+    fn texts_are_laid_out_once() {
+        let mut cache = TextCache::with_capacity(8);
+
+        assert!(missed(&mut cache, key(0, "Camellia", 100)));
+        assert!(!missed(&mut cache, key(0, "Camellia", 100)));
+        assert!(missed(&mut cache, key(0, "Camellia", 120)));
+        assert!(missed(&mut cache, key(1, "Camellia", 100)));
+
+        assert_eq!(
+            cache.take_stats(),
+            CacheStats {
+                hits: 1,
+                misses: 3,
+                entries: 3
+            }
+        );
+        assert_eq!(cache.take_stats().hits, 0);
+    }
+
+    #[test]
+    fn size_is_bounded_keeping_recent_texts() {
+        let mut cache = TextCache::with_capacity(8);
+
+        for i in 0..4 {
+            missed(&mut cache, key(0, &i.to_string(), 100));
+        }
+
+        // The first texts move to the older generation, and using one moves it back
+        missed(&mut cache, key(0, "4", 100));
+        assert!(!missed(&mut cache, key(0, "0", 100)));
+
+        for i in 5..20 {
+            missed(&mut cache, key(0, &i.to_string(), 100));
+            assert!(cache.take_stats().entries <= 8);
+        }
+
+        assert!(missed(&mut cache, key(0, "1", 100)));
+        assert!(!missed(&mut cache, key(0, "19", 100)));
+    }
+
+    #[test]
+    fn width_and_style_changes_drop_texts() {
+        let mut cache = TextCache::with_capacity(8);
+
+        cache.set_column_width(0, 100.0);
+        cache.set_column_width(1, 200.0);
+        missed(&mut cache, key(0, "Artist", 100));
+        missed(&mut cache, key(1, "Title", 200));
+
+        cache.set_column_width(0, 100.0);
+        assert_eq!(cache.take_stats().entries, 2);
+
+        cache.set_column_width(0, 150.0);
+        assert_eq!(cache.take_stats().entries, 1);
+        assert!(!missed(&mut cache, key(1, "Title", 200)));
+
+        cache.set_style(1);
+        assert_eq!(cache.take_stats().entries, 0);
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, Sender},
+};
+
+use osu_db_parser::{osu_file, paths};
+
+/// Maximum number of thumbnails kept in memory. The least recently shown thumbnails are unloaded first.
+const CACHE_SIZE: usize = 64;
+
+/// Size that background images are scaled down to fit within.
+const THUMBNAIL_SIZE: (u32, u32) = (480, 270);
+
+/// Represents the state of a beatmap's background thumbnail.
+pub enum Thumbnail {
+    Loading,
+    Loaded(egui::TextureHandle),
+
+    /// The beatmap doesn't have a background image, or it couldn't be loaded
+    Unavailable(String),
+}
+
+/// Result of loading a thumbnail on the worker thread, along with the `.osu` file it was loaded for.
+type LoadResult = (PathBuf, Result<egui::ColorImage, String>);
+
+/// Loads and caches thumbnails of beatmap background images, keyed by the path to the beatmap's `.osu` file.
+///
+/// Images are loaded and scaled down on a worker thread, which is started when the first thumbnail is requested.
+#[derive(Default)]
+pub struct ThumbnailCache {
+    thumbnails: HashMap<PathBuf, Thumbnail>,
+
+    /// Keys of the cached thumbnails, from least to most recently used
+    recent: VecDeque<PathBuf>,
+
+    /// Channels for sending paths to the worker thread, and receiving the loaded images
+    worker: Option<(Sender<PathBuf>, Receiver<LoadResult>)>,
+}
+
+impl ThumbnailCache {
+    /// Gets the thumbnail for a beatmap's `.osu` file, starting to load it if it isn't already cached.
+    pub fn get(&mut self, ctx: &egui::Context, osu_file: &Path) -> &Thumbnail {
+        self.receive(ctx);
+
+        if self.thumbnails.contains_key(osu_file) {
+            // Move to the back of the queue, since this is now the most recently used
+            if let Some(i) = self.recent.iter().position(|p| p == osu_file) {
+                let path = self.recent.remove(i).expect("index was just found");
+                self.recent.push_back(path);
+            }
+        } else {
+            self.request(ctx, osu_file);
+        }
+
+        &self.thumbnails[osu_file]
+    }
+
+    /// Starts loading a thumbnail on the worker thread, unloading the least recently used thumbnail if the cache is
+    /// full.
+    fn request(&mut self, ctx: &egui::Context, osu_file: &Path) {
+        while self.recent.len() >= CACHE_SIZE {
+            if let Some(oldest) = self.recent.pop_front() {
+                self.thumbnails.remove(&oldest);
+            }
+        }
+
+        let (requests, _) = self.worker.get_or_insert_with(|| start_worker(ctx));
+        let thumbnail = match requests.send(osu_file.to_path_buf()) {
+            Ok(()) => Thumbnail::Loading,
+            Err(_) => Thumbnail::Unavailable("The background image loader has stopped".to_string()),
+        };
+
+        self.thumbnails.insert(osu_file.to_path_buf(), thumbnail);
+        self.recent.push_back(osu_file.to_path_buf());
+    }
+
+    /// Collects any thumbnails that the worker thread has finished loading.
+    fn receive(&mut self, ctx: &egui::Context) {
+        let Some((_, results)) = &self.worker else {
+            return;
+        };
+
+        for (osu_file, result) in results.try_iter() {
+            // Thumbnails that were unloaded while they were still loading are no longer needed
+            let Some(thumbnail) = self.thumbnails.get_mut(&osu_file) else {
+                continue;
+            };
+
+            *thumbnail = match result {
+                Ok(image) => Thumbnail::Loaded(ctx.load_texture(
+                    osu_file.to_string_lossy(),
+                    image,
+                    egui::TextureOptions::LINEAR,
+                )),
+                Err(e) => Thumbnail::Unavailable(e),
+            };
+        }
+    }
+}
+
+/// Starts the worker thread, which loads the thumbnail for each `.osu` file it receives.
+fn start_worker(ctx: &egui::Context) -> (Sender<PathBuf>, Receiver<LoadResult>) {
+    let (request_tx, request_rx) = std::sync::mpsc::channel::<PathBuf>();
+    let (result_tx, result_rx) = std::sync::mpsc::channel();
+    let ctx = ctx.clone();
+
+    std::thread::spawn(move || {
+        for osu_file in request_rx {
+            // Decoders may panic on malformed images, which shouldn't stop any other thumbnails from loading
+            let result = std::panic::catch_unwind(|| load_thumbnail(&osu_file))
+                .unwrap_or_else(|_| Err("The background image could not be decoded".to_string()));
+
+            if result_tx.send((osu_file, result)).is_err() {
+                break;
+            }
+
+            ctx.request_repaint();
+        }
+    });
+
+    (request_tx, result_rx)
+}
+
+/// Finds a beatmap's background image from its `.osu` file, then loads and scales it down.
+fn load_thumbnail(osu_file: &Path) -> Result<egui::ColorImage, String> {
+    let contents = std::fs::read(osu_file).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => "The beatmap's .osu file could not be found".to_string(),
+        _ => format!("Unable to read the beatmap's .osu file: {}", e),
+    })?;
+
+    let background = osu_file::background_filename(&String::from_utf8_lossy(&contents))
+        .ok_or("No background image")?;
+
+    let path = osu_file
+        .parent()
+        .and_then(|folder| paths::beatmap_resource(folder, &background))
+        .ok_or("The background image is outside of the beatmap's folder")?;
+
+    let image = image::open(&path)
+        .map_err(|e| match e {
+            image::ImageError::IoError(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                "The background image could not be found".to_string()
+            }
+            e => format!("Unable to load the background image: {}", e),
+        })?
+        .thumbnail(THUMBNAIL_SIZE.0, THUMBNAIL_SIZE.1)
+        .to_rgba8();
+
+    Ok(egui::ColorImage::from_rgba_unmultiplied(
+        [image.width() as usize, image.height() as usize],
+        image.as_raw(),
+    ))
+}
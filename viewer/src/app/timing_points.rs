@@ -0,0 +1,243 @@
+use egui_extras::{Column as TableColumn, TableBuilder};
+use osu_db_parser::prelude::*;
+
+use super::accessibility;
+
+/// Height of the timeline, including space for the labels above and below it.
+const TIMELINE_HEIGHT: f32 = 56.0;
+
+/// Horizontal distance within which the pointer is considered to be over a timing point.
+const HOVER_DISTANCE: f32 = 4.0;
+
+/// Colour of uninherited timing points (red lines), matching the osu! editor.
+const UNINHERITED_COLOUR: egui::Color32 = egui::Color32::from_rgb(230, 70, 70);
+
+/// Colour of inherited timing points (green lines), matching the osu! editor.
+const INHERITED_COLOUR: egui::Color32 = egui::Color32::from_rgb(80, 200, 120);
+
+/// Renders a beatmap's timing points as a timeline, along with a list of the points which can be copied.
+///
+/// Uninherited points are shown as tall markers labelled with their BPM, and inherited points as shorter ticks
+/// labelled with their slider velocity multiplier. Labels are skipped where they would overlap, but every point's
+/// details are shown when hovering over it.
+pub fn timing_points(ui: &mut egui::Ui, id: egui::Id, beatmap: &BeatmapEntry) {
+    if beatmap.timing_points.is_empty() {
+        ui.weak("No timing points");
+        return;
+    }
+
+    timeline(ui, beatmap);
+
+    egui::CollapsingHeader::new("List")
+        .id_salt(id.with("list"))
+        .show(ui, |ui| {
+            if ui.button("Copy to Clipboard").clicked() {
+                ui.ctx()
+                    .copy_text(timing_points_text(&beatmap.timing_points));
+            }
+
+            timing_point_table(ui, id, &beatmap.timing_points);
+        });
+}
+
+/// Renders the timeline, scaled so that it covers the whole song.
+fn timeline(ui: &mut egui::Ui, beatmap: &BeatmapEntry) {
+    let points = &beatmap.timing_points;
+
+    // Points can be placed before the song starts or after it ends, so make sure they're all on the timeline
+    let (first, last) = points
+        .iter()
+        .fold((0.0f64, 0.0f64), |(first, last), point| {
+            (first.min(point.song_offset), last.max(point.song_offset))
+        });
+
+    let start = first;
+    let end = last.max(beatmap.total_time as f64);
+    let duration = (end - start).max(1.0);
+
+    let size = egui::vec2(ui.available_width(), TIMELINE_HEIGHT);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+
+    if !ui.is_rect_visible(rect) {
+        return;
+    }
+
+    let painter = ui.painter_at(rect);
+    let font = egui::TextStyle::Small.resolve(ui.style());
+    let text_colour = ui.visuals().text_color();
+
+    let track = egui::Rect::from_min_max(
+        egui::pos2(rect.left(), rect.top() + 16.0),
+        egui::pos2(rect.right(), rect.bottom() - 16.0),
+    );
+    let x = |offset: f64| track.left() + ((offset - start) / duration) as f32 * track.width();
+
+    painter.rect_filled(track, 2.0, ui.visuals().extreme_bg_color);
+
+    // Inherited points are drawn first, so that uninherited points at the same offset are drawn over them
+    let mut label_end = f32::NEG_INFINITY;
+
+    for point in points.iter().filter(|point| !point.is_uninherited()) {
+        let x = x(point.song_offset);
+        let tick_top = track.center().y;
+
+        painter.line_segment(
+            [egui::pos2(x, tick_top), egui::pos2(x, track.bottom())],
+            egui::Stroke::new(1.0, INHERITED_COLOUR),
+        );
+
+        if let Some(multiplier) = point.slider_velocity_multiplier() {
+            label_end = label(
+                &painter,
+                egui::pos2(x, track.bottom() + 1.0),
+                egui::Align2::CENTER_TOP,
+                format!("{:.2}×", multiplier),
+                font.clone(),
+                text_colour,
+                label_end,
+            );
+        }
+    }
+
+    label_end = f32::NEG_INFINITY;
+
+    for point in points.iter().filter(|point| point.is_uninherited()) {
+        let x = x(point.song_offset);
+
+        painter.line_segment(
+            [egui::pos2(x, track.top()), egui::pos2(x, track.bottom())],
+            egui::Stroke::new(2.0, UNINHERITED_COLOUR),
+        );
+
+        if let Some(bpm) = point.bpm() {
+            label_end = label(
+                &painter,
+                egui::pos2(x, track.top() - 1.0),
+                egui::Align2::CENTER_BOTTOM,
+                format!("{:.0}", bpm),
+                font.clone(),
+                text_colour,
+                label_end,
+            );
+        }
+    }
+
+    // Show the details of the closest point to the pointer
+    let hovered = response.hover_pos().and_then(|pointer| {
+        points
+            .iter()
+            .map(|point| (point, (x(point.song_offset) - pointer.x).abs()))
+            .filter(|(_, distance)| *distance <= HOVER_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(point, _)| point)
+    });
+
+    if let Some(point) = hovered {
+        response.on_hover_ui_at_pointer(|ui| {
+            ui.label(format_offset(point.song_offset));
+            ui.label(describe(point));
+        });
+    }
+}
+
+/// Draws a label centred on a timing point, unless it would overlap the previous label. Returns the right edge of the
+/// last label drawn.
+fn label(
+    painter: &egui::Painter,
+    pos: egui::Pos2,
+    anchor: egui::Align2,
+    text: String,
+    font: egui::FontId,
+    colour: egui::Color32,
+    previous_end: f32,
+) -> f32 {
+    let galley = painter.layout_no_wrap(text, font, colour);
+    let rect = anchor.anchor_size(pos, galley.size());
+
+    if rect.left() <= previous_end + 4.0 {
+        return previous_end;
+    }
+
+    painter.galley(rect.min, galley, colour);
+    rect.right()
+}
+
+/// Renders the timing points in a table, which only lays out the rows that are currently visible, since some
+/// beatmaps have hundreds of timing points.
+fn timing_point_table(ui: &mut egui::Ui, id: egui::Id, points: &[TimingPoint]) {
+    let row_height = accessibility::row_height(ui);
+
+    ui.push_id(id.with("table"), |ui| {
+        TableBuilder::new(ui)
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(TableColumn::initial(90.0).at_least(60.0))
+            .column(TableColumn::remainder())
+            .max_scroll_height(240.0)
+            .min_scrolled_height(0.0)
+            .header(row_height, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Offset");
+                });
+                header.col(|ui| {
+                    ui.strong("Value");
+                });
+            })
+            .body(|body| {
+                body.rows(row_height, points.len(), |mut row| {
+                    let point = &points[row.index()];
+
+                    row.col(|ui| {
+                        ui.monospace(format_offset(point.song_offset));
+                    });
+                    row.col(|ui| {
+                        let colour = if point.is_uninherited() {
+                            UNINHERITED_COLOUR
+                        } else {
+                            INHERITED_COLOUR
+                        };
+
+                        ui.colored_label(colour, describe(point));
+                    });
+                });
+            });
+    });
+}
+
+/// Describes what a timing point sets, i.e. its BPM or slider velocity multiplier.
+fn describe(point: &TimingPoint) -> String {
+    match (point.bpm(), point.slider_velocity_multiplier()) {
+        (Some(bpm), _) => format!("{:.2} BPM", bpm),
+        (_, Some(multiplier)) => format!("{:.2}× slider velocity", multiplier),
+        _ if point.is_uninherited() => "Invalid uninherited point".to_string(),
+        _ => "Invalid inherited point".to_string(),
+    }
+}
+
+/// Formats the timing points as tab-separated lines of offset and description, e.g. to paste into a spreadsheet.
+fn timing_points_text(points: &[TimingPoint]) -> String {
+    points
+        .iter()
+        .map(|point| {
+            format!(
+                "{}\t{}\n",
+                format_offset(point.song_offset),
+                describe(point)
+            )
+        })
+        .collect()
+}
+
+/// Formats an offset in milliseconds as `mm:ss.mmm`.
+fn format_offset(offset: f64) -> String {
+    let sign = if offset < 0.0 { "-" } else { "" };
+    let millis = offset.abs().round() as u64;
+
+    format!(
+        "{}{:02}:{:02}.{:03}",
+        sign,
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
@@ -0,0 +1,381 @@
+use std::sync::Arc;
+
+use egui_extras::{Column as TableColumn, TableBuilder};
+use osu_db_parser::{prelude::*, workspace::SourceId};
+
+use super::format_count;
+
+/// Name of the first database, which is the one loaded in the main view.
+const LOADED_NAME: &str = "Loaded osu.db";
+
+/// A window combining the loaded beatmap listing with other `osu.db` files, e.g. from another computer, and showing
+/// which of them each beatmap is in.
+///
+/// Other files are parsed on a worker thread, so that large databases don't block the window. On the web, there are
+/// no worker threads, so this is done immediately instead.
+pub struct WorkspaceWindow {
+    pub visible: bool,
+
+    /// The loaded listing, followed by the other files in the order they were added
+    workspace: Workspace,
+
+    /// Search entered for each database, which limits the beatmaps included from it
+    filters: Vec<String>,
+
+    /// Why each database's search couldn't be used, if it's invalid
+    filter_errors: Vec<Option<String>>,
+
+    view: SourceView,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pending: Vec<(
+        String,
+        std::sync::mpsc::Receiver<Result<BeatmapListing, Error>>,
+    )>,
+
+    /// Files which couldn't be added, and why
+    errors: Vec<(String, Error)>,
+
+    /// Which version of the artist and title the beatmaps are named with
+    language: MetadataLanguage,
+
+    /// Beatmaps included by the current view, which are found again whenever the view, a search or the databases
+    /// change
+    rows: Vec<WorkspaceRow>,
+}
+
+/// Represents which beatmaps are shown in the workspace window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SourceView {
+    /// Every beatmap in any database
+    All,
+
+    /// Beatmaps in every database
+    Common,
+
+    /// Beatmaps in one database but none of the others
+    Only(SourceId),
+}
+
+/// Represents a beatmap shown in the workspace window.
+struct WorkspaceRow {
+    name: String,
+
+    /// Names of the databases the beatmap is in
+    sources: String,
+}
+
+impl WorkspaceWindow {
+    /// Creates a workspace containing the loaded beatmap listing.
+    pub fn new(listing: Arc<BeatmapListing>, language: MetadataLanguage) -> Self {
+        let mut workspace = Workspace::new();
+        workspace.add(LOADED_NAME, listing);
+
+        let mut window = Self {
+            visible: true,
+            workspace,
+            filters: vec![String::new()],
+            filter_errors: vec![None],
+            view: SourceView::All,
+            #[cfg(not(target_arch = "wasm32"))]
+            pending: Vec::new(),
+            errors: Vec::new(),
+            language,
+            rows: Vec::new(),
+        };
+
+        window.refresh();
+        window
+    }
+
+    /// Starts adding the contents of another `osu.db` file to the workspace.
+    pub fn add(&mut self, ctx: &egui::Context, name: String, data: Vec<u8>) {
+        self.visible = true;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let ctx = ctx.clone();
+
+            std::thread::spawn(move || {
+                if tx.send(BeatmapListing::from_bytes(&data)).is_ok() {
+                    ctx.request_repaint();
+                }
+            });
+
+            self.pending.push((name, rx));
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            ctx.request_repaint();
+            self.finish_adding(name, BeatmapListing::from_bytes(&data));
+        }
+    }
+
+    /// Replaces the loaded listing once it has been reloaded, keeping its search.
+    pub fn set_loaded_listing(&mut self, listing: Arc<BeatmapListing>) {
+        if let Some(source) = self.workspace.source_mut(self.workspace.ids()[0]) {
+            source.listing = listing;
+        }
+
+        self.refresh();
+    }
+
+    /// Sets which version of the artist and title the beatmaps are named with.
+    pub fn set_language(&mut self, language: MetadataLanguage) {
+        if language != self.language {
+            self.language = language;
+            self.refresh();
+        }
+    }
+
+    /// Adds a parsed file to the workspace, or remembers why it couldn't be parsed.
+    fn finish_adding(&mut self, name: String, result: Result<BeatmapListing, Error>) {
+        match result {
+            Ok(listing) => {
+                // Databases often have the same file name, so later ones are numbered to tell them apart
+                let name = match self.workspace.find(&name) {
+                    Some(_) => format!("{} ({})", name, self.workspace.len() + 1),
+                    None => name,
+                };
+
+                self.workspace.add(name, listing);
+                self.filters.push(String::new());
+                self.filter_errors.push(None);
+                self.refresh();
+            }
+            Err(e) => self.errors.push((name, e)),
+        }
+    }
+
+    /// Collects any files that have finished parsing.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll(&mut self) {
+        let mut finished = Vec::new();
+
+        self.pending
+            .retain(|(name, pending)| match pending.try_recv() {
+                Ok(result) => {
+                    finished.push((name.clone(), result));
+                    false
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => true,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    finished.push((name.clone(), Err(Error::Cancelled)));
+                    false
+                }
+            });
+
+        for (name, result) in finished {
+            self.finish_adding(name, result);
+        }
+    }
+
+    /// Finds the beatmaps included by the current view again.
+    fn refresh(&mut self) {
+        let ids = self.workspace.ids();
+
+        let entries = match self.view {
+            SourceView::All => self.workspace.union(&ids),
+            SourceView::Common => self.workspace.intersection(&ids),
+            SourceView::Only(id) => {
+                let others = ids.iter().copied().filter(|&other| other != id);
+                self.workspace.difference(id, &others.collect::<Vec<_>>())
+            }
+        };
+
+        let presence = self.workspace.presence();
+
+        self.rows = entries
+            .into_iter()
+            .map(|(_, beatmap)| {
+                let sources = beatmap
+                    .md5
+                    .as_deref()
+                    .and_then(|md5| presence.get(md5))
+                    .map(|sources| {
+                        sources
+                            .iter()
+                            .map(|&id| self.workspace.name(id))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                    .unwrap_or_default();
+
+                WorkspaceRow {
+                    name: beatmap.display_name(self.language),
+                    sources,
+                }
+            })
+            .collect();
+    }
+
+    /// Applies the search entered for a database.
+    fn apply_filter(&mut self, id: SourceId) {
+        let i = id.index();
+
+        match BeatmapFilter::parse(&self.filters[i]) {
+            Ok(filter) => {
+                self.filter_errors[i] = None;
+                self.workspace.set_filter(id, Some(filter));
+            }
+            Err(e) => {
+                self.filter_errors[i] = Some(e.to_string());
+                self.workspace.set_filter(id, None);
+            }
+        }
+
+        self.refresh();
+    }
+
+    /// Gets the label of a view in the selector.
+    fn view_label(&self, view: SourceView) -> String {
+        match view {
+            SourceView::All => "In any database".to_string(),
+            SourceView::Common => "In every database".to_string(),
+            SourceView::Only(id) => format!("Only in {}", self.workspace.name(id)),
+        }
+    }
+
+    pub fn view(&mut self, ctx: &egui::Context) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.poll();
+
+        let mut visible = self.visible;
+
+        egui::Window::new("Databases")
+            .open(&mut visible)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                self.sources(ui);
+
+                #[cfg(not(target_arch = "wasm32"))]
+                for (name, _) in &self.pending {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Adding {}...", name));
+                    });
+                }
+
+                for (name, e) in &self.errors {
+                    ui.label(format!(
+                        "{} could not be added, since it appears to be corrupt or from an unsupported version of osu!.",
+                        name
+                    ));
+                    ui.weak(e.to_string());
+                }
+
+                ui.separator();
+
+                if self.workspace.len() > 1 {
+                    self.view_selector(ui);
+                    self.beatmap_table(ui);
+                } else {
+                    ui.weak("Use File → Add Database... to add an osu.db from another computer or a backup.");
+                }
+            });
+
+        self.visible = visible;
+    }
+
+    /// Renders each database with its number of beatmaps and its search.
+    fn sources(&mut self, ui: &mut egui::Ui) {
+        let mut changed = None;
+
+        egui::Grid::new("workspace_sources")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Database");
+                ui.strong("Beatmaps");
+                ui.strong("Search");
+                ui.end_row();
+
+                for (id, source) in self.workspace.sources() {
+                    let i = id.index();
+
+                    ui.label(&source.name);
+                    ui.label(format_count(source.beatmaps().count()));
+
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.filters[i])
+                            .hint_text("e.g. status=ranked")
+                            .desired_width(200.0),
+                    );
+
+                    if response.changed() {
+                        changed = Some(id);
+                    }
+
+                    if let Some(e) = &self.filter_errors[i] {
+                        response.on_hover_text(e);
+                    }
+
+                    ui.end_row();
+                }
+            });
+
+        if let Some(id) = changed {
+            self.apply_filter(id);
+        }
+    }
+
+    /// Renders the selector for which beatmaps to show.
+    fn view_selector(&mut self, ui: &mut egui::Ui) {
+        let views = [SourceView::All, SourceView::Common]
+            .into_iter()
+            .chain(self.workspace.ids().into_iter().map(SourceView::Only))
+            .collect::<Vec<_>>();
+        let mut selected = self.view;
+
+        ui.horizontal(|ui| {
+            ui.label("Show beatmaps");
+
+            egui::ComboBox::from_id_salt("workspace_view")
+                .selected_text(self.view_label(selected))
+                .show_ui(ui, |ui| {
+                    for view in views {
+                        ui.selectable_value(&mut selected, view, self.view_label(view));
+                    }
+                });
+
+            ui.weak(format!("{} beatmaps", format_count(self.rows.len())));
+        });
+
+        if selected != self.view {
+            self.view = selected;
+            self.refresh();
+        }
+    }
+
+    /// Renders the beatmaps included by the current view, along with the databases each one is in.
+    fn beatmap_table(&self, ui: &mut egui::Ui) {
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(TableColumn::remainder().at_least(200.0).clip(true))
+            .column(TableColumn::auto().at_least(150.0).clip(true))
+            .header(row_height, |mut header| {
+                for title in ["Beatmap", "Databases"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(row_height, self.rows.len(), |mut row| {
+                    let beatmap = &self.rows[row.index()];
+
+                    row.col(|ui| {
+                        ui.label(&beatmap.name);
+                    });
+                    row.col(|ui| {
+                        ui.label(&beatmap.sources);
+                    });
+                });
+            });
+    }
+}
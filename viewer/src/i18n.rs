@@ -0,0 +1,314 @@
+//! Translations of the interface text.
+//!
+//! Text is looked up by its English wording, so untranslated text falls back to English without any extra work. Only
+//! the interface itself is translated; beatmap metadata is always shown as stored in the database.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        OnceLock,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Language currently used for the interface, as a [`Language`] discriminant.
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+/// Represents the languages that the interface can be shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Japanese,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Japanese];
+
+    /// Gets the name of the language, in the language itself.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Japanese => "日本語",
+        }
+    }
+
+    /// Gets the translations for the language, keyed by their English text.
+    fn translations(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => &[],
+            Language::Japanese => JAPANESE,
+        }
+    }
+}
+
+/// Changes the language used for the interface.
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+/// Translates interface text into the current language, or returns it unchanged if there isn't a translation.
+pub fn tr(text: &'static str) -> &'static str {
+    static LOOKUPS: OnceLock<Vec<HashMap<&'static str, &'static str>>> = OnceLock::new();
+
+    let lookups = LOOKUPS.get_or_init(|| {
+        Language::ALL
+            .iter()
+            .map(|language| language.translations().iter().copied().collect())
+            .collect()
+    });
+
+    let language = LANGUAGE.load(Ordering::Relaxed) as usize;
+
+    lookups
+        .get(language)
+        .and_then(|lookup| lookup.get(text))
+        .copied()
+        .unwrap_or(text)
+}
+
+/// Japanese translations of the menu bar, command palette, tabs and settings window.
+const JAPANESE: &[(&str, &str)] = &[
+    // Menu bar
+    ("File", "ファイル"),
+    ("Open osu.db...", "osu.db を開く..."),
+    ("Open collection.db...", "collection.db を開く..."),
+    ("Open scores.db...", "scores.db を開く..."),
+    ("Open .osr replay...", ".osr リプレイを開く..."),
+    ("Compare with...", "比較..."),
+    (
+        "Open an osu.db to compare it with another one",
+        "比較するには osu.db を開いてください",
+    ),
+    ("Add Database...", "データベースを追加..."),
+    (
+        "Open an osu.db to combine it with others",
+        "他と組み合わせるには osu.db を開いてください",
+    ),
+    (
+        "Show which beatmaps are in each of several osu.db files, e.g. from another computer",
+        "複数の osu.db（別のパソコンのものなど）にどのビートマップがあるかを表示します",
+    ),
+    ("Databases", "データベース"),
+    ("Import Notes...", "メモをインポート..."),
+    ("Export Notes...", "メモをエクスポート..."),
+    ("Restore Backup...", "バックアップを復元..."),
+    (
+        "Open an osu.db or collection.db to restore a backup of it",
+        "バックアップを復元するには osu.db か collection.db を開いてください",
+    ),
+    ("Bug Report", "不具合報告"),
+    ("Export Reproduction...", "再現データをエクスポート..."),
+    (
+        "Save the loaded osu.db, search and settings to attach to a bug report",
+        "読み込んだ osu.db、検索、設定を保存して不具合報告に添付します",
+    ),
+    ("Export Anonymised Reproduction...", "匿名化した再現データをエクスポート..."),
+    (
+        "Replace the beatmaps' text and your name with made up words of the same length first",
+        "譜面の文字列と名前を同じ長さの架空の文字列に置き換えてから保存します",
+    ),
+    ("Load Reproduction...", "再現データを読み込む..."),
+    ("Performance Overlay", "パフォーマンス表示"),
+    (
+        "Show how long frames take and how often the beatmap table's text is already laid out (F12)",
+        "フレームにかかる時間と、ビートマップ表のテキストがキャッシュされている割合を表示します (F12)",
+    ),
+    ("Load Demo Library", "デモ用ライブラリを読み込む"),
+    ("Generate report...", "レポートを作成..."),
+    (
+        "Open an osu.db to generate a report of it",
+        "レポートを作成するには osu.db を開いてください",
+    ),
+    ("Settings...", "設定..."),
+    ("Open osu! folder...", "osu! フォルダを開く..."),
+    ("Open recent", "最近使ったファイル"),
+    ("This file no longer exists", "このファイルは存在しません"),
+    ("Clear recent", "履歴を消去"),
+    ("Close", "閉じる"),
+    ("Beatmaps", "ビートマップ"),
+    ("Find", "検索"),
+    ("Jump to Beatmap...", "ビートマップへ移動..."),
+    (
+        "Go to a beatmap by its MD5 hash or ID",
+        "MD5 ハッシュか ID でビートマップへ移動します",
+    ),
+    ("Random Beatmap", "ランダムなビートマップ"),
+    (
+        "Pick a random beatmap from the search results",
+        "検索結果からランダムにビートマップを選びます",
+    ),
+    ("Open Beatmap Page", "ビートマップのページを開く"),
+    ("Open in osu!direct", "osu!direct で開く"),
+    ("Open Song Folder", "曲のフォルダを開く"),
+    ("Edit Settings...", "設定を編集..."),
+    ("Copy MD5", "MD5 をコピー"),
+    ("Copy Beatmap ID", "ビートマップ ID をコピー"),
+    ("Copy as Markdown Row", "Markdown の行としてコピー"),
+    ("View", "表示"),
+    ("Command Palette...", "コマンドパレット..."),
+    // Command palette
+    ("Type the name of an action", "操作の名前を入力"),
+    ("No matching actions", "一致する操作はありません"),
+    (
+        "More actions match. Type more of the name to find them.",
+        "他にも一致する操作があります。名前をさらに入力してください。",
+    ),
+    ("Open an osu.db first", "先に osu.db を開いてください"),
+    ("Select a beatmap first", "先にビートマップを選択してください"),
+    ("Open an osu.db or collection.db first", "先に osu.db か collection.db を開いてください"),
+    // Tabs
+    ("Beatmap Listing", "ビートマップ一覧"),
+    ("Overview", "概要"),
+    ("Health", "ヘルスチェック"),
+    ("Disk Usage", "ディスク使用量"),
+    ("Collections", "コレクション"),
+    ("Scores", "スコア"),
+    ("Replays", "リプレイ"),
+    // Settings
+    ("Settings", "設定"),
+    ("Language", "言語"),
+    ("Theme", "テーマ"),
+    ("System", "システム"),
+    ("Light", "ライト"),
+    ("Dark", "ダーク"),
+    ("High contrast", "ハイコントラスト"),
+    ("Interface scale", "表示倍率"),
+    (
+        "Ctrl+Plus and Ctrl+Minus also change the scale, and Ctrl+0 resets it",
+        "Ctrl+プラスと Ctrl+マイナスでも倍率を変更でき、Ctrl+0 で元に戻ります",
+    ),
+    ("Artist and title", "アーティストとタイトル"),
+    ("Romanised", "ローマ字"),
+    ("Unicode", "Unicode"),
+    (
+        "Beatmaps without a Unicode artist or title show the romanised one instead",
+        "Unicode のアーティスト名やタイトルがないビートマップはローマ字で表示されます",
+    ),
+    ("Dates", "日付"),
+    ("Time zone", "タイムゾーン"),
+    (
+        "How far ahead of UTC the clocks are, outside of daylight saving time",
+        "夏時間でないときのUTCとの時差",
+    ),
+    ("Daylight saving", "夏時間"),
+    ("Europe (March to October)", "ヨーロッパ（3月〜10月）"),
+    ("North America (March to November)", "北アメリカ（3月〜11月）"),
+    ("Show dates in UTC", "日付をUTCで表示"),
+    (
+        "Last played and last checked dates are stored in osu!'s local time, which is converted using the time zone",
+        "最終プレイ日時と最終確認日時はosu!の現地時間で保存されているため、タイムゾーンを使って変換されます",
+    ),
+    ("Star rating decimals", "難易度の小数点以下の桁数"),
+    ("Star ratings", "難易度"),
+    (
+        "Which gameplay mode's star rating the Stars column and stars filters use",
+        "難易度の列と stars フィルターで使うゲームモードの難易度",
+    ),
+    ("Each beatmap's own mode", "各ビートマップのゲームモード"),
+    (
+        "Use the beatmap's own mode if there is no star rating for this mode",
+        "このモードの難易度がない場合はビートマップのゲームモードを使う",
+    ),
+    (
+        "Only osu!standard beatmaps have star ratings for the other modes, which they can be converted to",
+        "他のモードの難易度があるのは、コンバートできる osu!standard のビートマップだけです",
+    ),
+    (
+        "Treat beatmaps without a star rating as 0 stars",
+        "難易度がないビートマップを 0 として扱う",
+    ),
+    (
+        "Otherwise, they never match stars filters and are sorted last",
+        "そうでない場合、stars フィルターには一致せず、最後に並べられます",
+    ),
+    ("Default osu! folder", "デフォルトの osu! フォルダ"),
+    (
+        "Opened on startup, and used as the starting folder when opening an osu! folder",
+        "起動時に開かれ、osu! フォルダを開くときの初期フォルダとして使われます",
+    ),
+    ("Songs folder", "Songs フォルダ"),
+    (
+        "Where osu! keeps beatmaps, if it has been set to keep them outside the osu! folder",
+        "osu! フォルダの外にビートマップを保存するよう設定している場合の保存先",
+    ),
+    ("Songs in the osu! folder", "osu! フォルダ内の Songs"),
+    ("Backups", "バックアップ"),
+    (
+        "Kept in an osu-db-backups folder next to each database, and restored with File → Restore Backup...",
+        "各データベースの隣の osu-db-backups フォルダに保存され、ファイル → バックアップを復元... で復元できます",
+    ),
+    (
+        "backups of each database before saving (0 turns them off)",
+        "件のバックアップを保存前に各データベースごとに保持（0 で無効）",
+    ),
+    ("None", "なし"),
+    ("Browse...", "参照..."),
+    ("Clear", "クリア"),
+    ("CJK font", "CJK フォント"),
+    (
+        "Font used for Chinese, Japanese and Korean text that the default font doesn't cover",
+        "デフォルトのフォントに含まれない中国語・日本語・韓国語の文字に使われるフォント",
+    ),
+    ("Automatic", "自動"),
+    ("No system font found", "システムフォントが見つかりません"),
+    ("When opening files", "ファイルを開くとき"),
+    (
+        "Also open collection.db from the same folder as osu.db",
+        "osu.db と同じフォルダの collection.db も開く",
+    ),
+    (
+        "Also open scores.db when opening an osu! folder",
+        "osu! フォルダを開くときに scores.db も開く",
+    ),
+    (
+        "Load the valid beatmaps from a damaged osu.db without asking",
+        "破損した osu.db から有効なビートマップを確認せずに読み込む",
+    ),
+    ("Search", "検索"),
+    (
+        "Show similar beatmaps when nothing matches exactly",
+        "完全に一致するものがないときに似たビートマップを表示する",
+    ),
+    (
+        "Start a search with ~ to always match approximately",
+        "検索を ~ で始めると常にあいまい検索になります",
+    ),
+    ("Reset to Defaults", "初期設定に戻す"),
+    // Setup wizard
+    ("Welcome", "ようこそ"),
+    (
+        "The viewer can open your osu! installation on startup. It has been looked for in the usual places.",
+        "起動時に osu! のインストール先を開くことができます。よく使われる場所を検索しました。",
+    ),
+    ("Or choose the osu! folder yourself:", "または osu! フォルダを自分で選択:"),
+    ("Check", "確認"),
+    ("Open", "開く"),
+    (
+        "Saves the folder to the settings, then opens it",
+        "フォルダを設定に保存してから開きます",
+    ),
+    ("Skip", "スキップ"),
+    (
+        "The folder can be chosen later in the settings",
+        "フォルダは後で設定から選択できます",
+    ),
+    (
+        "✖ osu! wasn't found in any of these folders:",
+        "✖ 次のフォルダに osu! が見つかりませんでした:",
+    ),
+    (
+        "osu! creates it when it first runs, so try starting osu! once and then checking again.",
+        "osu! の初回起動時に作成されるので、一度 osu! を起動してから再度確認してください。",
+    ),
+    (
+        "It may be damaged, or from a version of osu! that isn't supported. Files can still be opened from the File menu.",
+        "破損しているか、対応していないバージョンの osu! のものかもしれません。ファイルメニューから個別に開くことはできます。",
+    ),
+    (
+        "The databases can still be opened, but beatmap files, audio and backgrounds won't be found until it does. It can be changed in the settings.",
+        "データベースは開けますが、フォルダが存在するまでビートマップのファイル・音声・背景は見つかりません。設定で変更できます。",
+    ),
+];
@@ -1,19 +1,56 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod app;
+mod i18n;
 mod widgets;
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
-    env_logger::init();
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    init_logging(args.iter().any(|arg| arg == "--verbose"));
+
+    // A file or osu! folder can be given to open on startup, e.g. `viewer "C:\Users\Me\AppData\Local\osu!"`
+    let startup_path = args
+        .into_iter()
+        .find(|arg| arg != "--verbose")
+        .map(std::path::PathBuf::from);
 
     eframe::run_native(
         "osu! Database Viewer",
         eframe::NativeOptions::default(),
-        Box::new(|_| Ok(Box::new(app::MainApp::default()))),
+        Box::new(|cc| {
+            let mut app = app::MainApp::new(cc);
+
+            // Otherwise, open the default osu! folder from the settings
+            if let Some(path) = startup_path.or_else(|| app.default_osu_folder()) {
+                app.open_path(&cc.egui_ctx, &path);
+            }
+
+            Ok(Box::new(app))
+        }),
     )
 }
 
+/// Logs to stderr. Only errors are logged unless `--verbose` is given, which logs what's being parsed and how long it
+/// takes, e.g. to attach to a bug report. `RUST_LOG` can be used instead for finer control.
+#[cfg(not(target_arch = "wasm32"))]
+fn init_logging(verbose: bool) {
+    use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+
+    let filter = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) => EnvFilter::new(directives),
+        Err(_) if verbose => EnvFilter::new("info,osu_db_parser=debug,osu_db_viewer=debug"),
+        Err(_) => EnvFilter::new("error"),
+    };
+
+    // Log records from the viewer and eframe are forwarded to the subscriber too
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
 #[cfg(target_arch = "wasm32")]
 fn main() {
     use eframe::wasm_bindgen::JsCast as _;
@@ -39,7 +76,7 @@ fn main() {
             .start(
                 canvas,
                 eframe::WebOptions::default(),
-                Box::new(|_| Ok(Box::new(app::MainApp::default()))),
+                Box::new(|cc| Ok(Box::new(app::MainApp::new(cc)))),
             )
             .await
             .expect("failed to start eframe");
@@ -4,3 +4,5 @@ pub mod file_dialog;
 #[cfg(target_arch = "wasm32")]
 #[path = "widgets/file_dialog_wasm.rs"]
 pub mod file_dialog;
+
+pub mod range_slider;
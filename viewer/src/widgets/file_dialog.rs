@@ -2,15 +2,23 @@
 //!
 //! Based on the implementation from [kirjavascript/trueLMAO](https://github.com/kirjavascript/trueLMAO/tree/master).
 
+use std::path::{Path, PathBuf};
+
 use rfd;
 
 pub struct FileDialog {
     file: Option<Vec<u8>>,
+    path: Option<PathBuf>,
+    error: Option<std::io::Error>,
 }
 
 impl Default for FileDialog {
     fn default() -> Self {
-        Self { file: None }
+        Self {
+            file: None,
+            path: None,
+            error: None,
+        }
     }
 }
 
@@ -18,8 +26,61 @@ impl FileDialog {
     pub fn open(&mut self) {
         let path = rfd::FileDialog::new().pick_file();
         if let Some(path) = path {
-            self.file = std::fs::read(path).ok();
+            match std::fs::read(&path) {
+                Ok(file) => self.file = Some(file),
+                Err(e) => self.error = Some(e),
+            }
+
+            self.path = Some(path);
+        }
+    }
+
+    /// Asks the user to pick a folder, starting in `directory` if given.
+    pub fn pick_folder(directory: Option<&Path>) -> Option<PathBuf> {
+        let mut dialog = rfd::FileDialog::new();
+
+        if let Some(directory) = directory {
+            dialog = dialog.set_directory(directory);
+        }
+
+        dialog.pick_folder()
+    }
+
+    /// Asks the user to pick a file with one of `extensions`, starting in `directory` if given. Unlike [`Self::open`],
+    /// the file isn't read.
+    pub fn pick_file(
+        directory: Option<&Path>,
+        filter_name: &str,
+        extensions: &[&str],
+    ) -> Option<PathBuf> {
+        let mut dialog = rfd::FileDialog::new().add_filter(filter_name, extensions);
+
+        if let Some(directory) = directory {
+            dialog = dialog.set_directory(directory);
+        }
+
+        dialog.pick_file()
+    }
+
+    /// Asks the user where to save a file, starting in `directory` with `file_name` as the suggested name.
+    pub fn save_file(directory: Option<&Path>, file_name: &str) -> Option<PathBuf> {
+        let mut dialog = rfd::FileDialog::new().set_file_name(file_name);
+
+        if let Some(directory) = directory {
+            dialog = dialog.set_directory(directory);
         }
+
+        dialog.save_file()
+    }
+
+    /// Takes the error that occurred when reading the most recently picked file, if any.
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        self.error.take()
+    }
+
+    /// Gets the path of the most recently picked file.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
     }
 
     pub fn get(&mut self) -> Option<Vec<u8>> {
@@ -0,0 +1,110 @@
+use std::ops::RangeInclusive;
+
+/// A slider with two handles, for picking the lower and upper ends of a range.
+pub struct RangeSlider<'a> {
+    low: &'a mut f64,
+    high: &'a mut f64,
+    bounds: RangeInclusive<f64>,
+
+    /// Values are rounded to a multiple of this, if non-zero
+    step: f64,
+}
+
+impl<'a> RangeSlider<'a> {
+    /// Creates a slider for editing `low..=high`, which can be anywhere within `bounds`.
+    pub fn new(low: &'a mut f64, high: &'a mut f64, bounds: RangeInclusive<f64>) -> Self {
+        Self {
+            low,
+            high,
+            bounds,
+            step: 0.0,
+        }
+    }
+
+    /// Rounds values to a multiple of `step`.
+    pub fn step_by(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+}
+
+impl egui::Widget for RangeSlider<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let size = egui::vec2(ui.spacing().slider_width, ui.spacing().interact_size.y);
+        let (rect, mut response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+
+        let (min, max) = (*self.bounds.start(), *self.bounds.end());
+        let radius = rect.height() / 2.5;
+        let track = rect.shrink2(egui::vec2(radius, 0.0));
+
+        let to_x = |value: f64| {
+            track.left() + ((value - min) / (max - min)).clamp(0.0, 1.0) as f32 * track.width()
+        };
+        let from_x = |x: f32| {
+            let value =
+                min + ((x - track.left()) / track.width()).clamp(0.0, 1.0) as f64 * (max - min);
+
+            if self.step > 0.0 {
+                (value / self.step).round() * self.step
+            } else {
+                value
+            }
+        };
+
+        if let Some(pos) = response.interact_pointer_pos() {
+            // The handle being dragged is picked when the pointer is first pressed, so that the handles can't swap
+            let handle_id = response.id.with("low_handle");
+            let low_handle = if ui.input(|i| i.pointer.any_pressed()) {
+                let to_low = (pos.x - to_x(*self.low)).abs();
+                let to_high = (pos.x - to_x(*self.high)).abs();
+                let low_handle = to_low < to_high || (to_low == to_high && pos.x < to_x(*self.low));
+
+                ui.data_mut(|d| d.insert_temp(handle_id, low_handle));
+                low_handle
+            } else {
+                ui.data(|d| d.get_temp(handle_id)).unwrap_or(true)
+            };
+
+            let value = from_x(pos.x);
+            let (handle, value) = if low_handle {
+                (&mut *self.low, value.min(*self.high))
+            } else {
+                (&mut *self.high, value.max(*self.low))
+            };
+
+            if *handle != value {
+                *handle = value;
+                response.mark_changed();
+            }
+        }
+
+        if ui.is_rect_visible(rect) {
+            let visuals = ui.style().interact(&response);
+            let y = rect.center().y;
+            let rail = (y - radius / 2.0)..=(y + radius / 2.0);
+
+            let painter = ui.painter();
+            painter.rect_filled(
+                egui::Rect::from_x_y_ranges(track.x_range(), rail.clone()),
+                radius / 2.0,
+                ui.visuals().widgets.inactive.bg_fill,
+            );
+            painter.rect_filled(
+                egui::Rect::from_x_y_ranges(to_x(*self.low)..=to_x(*self.high), rail),
+                radius / 2.0,
+                ui.visuals().selection.bg_fill,
+            );
+
+            for value in [*self.low, *self.high] {
+                painter.circle(
+                    egui::pos2(to_x(value), y),
+                    radius,
+                    visuals.bg_fill,
+                    visuals.fg_stroke,
+                );
+            }
+        }
+
+        response
+    }
+}